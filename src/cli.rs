@@ -1,7 +1,39 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 use crate::utils::version;
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
-pub struct Cli {}
+pub struct Cli {
+    /// Transfer without starting the user interface, for scripts
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Upload a local file, or a directory with --recursive, to s3://bucket/key
+    Upload {
+        local_path: String,
+        /// s3://bucket/key, a key ending with `/` gets the name of the file appended
+        destination: String,
+        /// Name of the credential file in the creds directory, the first one by default
+        #[arg(long)]
+        creds: Option<String>,
+        /// Upload all the files below the directory
+        #[arg(short, long)]
+        recursive: bool,
+    },
+    /// Download s3://bucket/key, or all the objects under the prefix with --recursive,
+    /// into a local directory
+    Download {
+        source: String,
+        directory: String,
+        /// Name of the credential file in the creds directory, the first one by default
+        #[arg(long)]
+        creds: Option<String>,
+        /// Download all the objects under the prefix
+        #[arg(short, long)]
+        recursive: bool,
+    },
+}
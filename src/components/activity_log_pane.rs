@@ -0,0 +1,177 @@
+//! This module provides the pane at the bottom of every page listing the recent events
+use crate::model::action::Action;
+use crate::model::activity_log::{ActivityLog, Severity};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// Rows taken by the pane, including its borders
+pub const ACTIVITY_LOG_HEIGHT: u16 = 12;
+const VISIBLE_ENTRIES: u64 = (ACTIVITY_LOG_HEIGHT - 2) as u64;
+
+/// Scroll position of the pane opened with '~', it follows the newest entries until it's
+/// scrolled up or paused with 'f'. Scrolling back to the newest entry follows them again
+#[derive(Debug, Default)]
+pub struct ActivityLogPane {
+    /// Sequence number of the top entry while not following
+    top: Option<u64>,
+}
+
+impl ActivityLogPane {
+    fn following_top(log: &ActivityLog) -> u64 {
+        log.end_sequence()
+            .saturating_sub(VISIBLE_ENTRIES)
+            .max(log.first_sequence())
+    }
+
+    /// Sequence number of the top entry shown, a paused pane keeps it until the entry is dropped
+    fn top(&self, log: &ActivityLog) -> u64 {
+        let following = Self::following_top(log);
+        match self.top {
+            Some(top) => top.clamp(log.first_sequence(), following),
+            None => following,
+        }
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.top.is_none()
+    }
+
+    fn scroll(&mut self, log: &ActivityLog, delta: i64) {
+        let top = (self.top(log) as i64 + delta).max(log.first_sequence() as i64) as u64;
+        self.top = if top >= Self::following_top(log) {
+            None
+        } else {
+            Some(top)
+        };
+    }
+
+    /// Handles the key while the pane is open, returns the action to be sent by the router
+    pub fn handle_key_event(&mut self, key: KeyEvent, log: &ActivityLog) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('~') | KeyCode::Esc => {
+                self.top = None;
+                return Some(Action::ToggleActivityLog);
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.scroll(log, 1),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll(log, -1),
+            KeyCode::PageDown => self.scroll(log, VISIBLE_ENTRIES as i64),
+            KeyCode::PageUp => self.scroll(log, -(VISIBLE_ENTRIES as i64)),
+            KeyCode::Char('f') => {
+                self.top = match self.top {
+                    Some(_) => None,
+                    None => Some(self.top(log)),
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn entry_style(severity: Severity) -> Style {
+        match severity {
+            Severity::Info => Style::default(),
+            Severity::Warning => Style::default().fg(Color::Yellow),
+            Severity::Error => Style::default().fg(Color::Red),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, log: &ActivityLog) {
+        let skip = (self.top(log) - log.first_sequence()) as usize;
+        let lines: Vec<Line> = if log.entries().is_empty() {
+            vec![Line::styled(
+                "No activity yet",
+                Style::default().fg(Color::DarkGray),
+            )]
+        } else {
+            log.entries()
+                .iter()
+                .skip(skip)
+                .take(VISIBLE_ENTRIES as usize)
+                .map(|entry| {
+                    Line::from(vec![
+                        Span::styled(entry.time(), Style::default().fg(Color::DarkGray)),
+                        Span::raw(" "),
+                        Span::styled(entry.message.clone(), Self::entry_style(entry.severity)),
+                    ])
+                })
+                .collect()
+        };
+        let title = if self.is_following() {
+            " Activity (UTC) | '~' close ".to_string()
+        } else {
+            " Activity (UTC) | paused, 'f' follows | '~' close ".to_string()
+        };
+        let pane = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(Style::default().fg(Color::Blue)),
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(pane, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::empty())
+    }
+
+    fn log_with(entries: usize) -> ActivityLog {
+        let mut log = ActivityLog::default();
+        for i in 0..entries {
+            log.push(Severity::Info, format!("event {}", i));
+        }
+        log
+    }
+
+    #[test]
+    fn paused_pane_keeps_its_position_while_entries_arrive() {
+        let mut log = log_with(30);
+        let mut pane = ActivityLogPane::default();
+        assert_eq!(pane.top(&log), 20);
+
+        pane.handle_key_event(key(KeyCode::Char('f')), &log);
+        log.push(Severity::Error, "failed".into());
+        assert_eq!(pane.top(&log), 20);
+
+        pane.handle_key_event(key(KeyCode::Char('f')), &log);
+        assert_eq!(pane.top(&log), 21);
+    }
+
+    #[test]
+    fn scrolling_back_to_the_newest_entries_follows_them() {
+        let log = log_with(30);
+        let mut pane = ActivityLogPane::default();
+        pane.handle_key_event(key(KeyCode::PageUp), &log);
+        assert_eq!(pane.top(&log), 10);
+        assert!(!pane.is_following());
+        pane.handle_key_event(key(KeyCode::PageUp), &log);
+        assert_eq!(pane.top(&log), 0);
+
+        pane.handle_key_event(key(KeyCode::PageDown), &log);
+        pane.handle_key_event(key(KeyCode::Char('j')), &log);
+        assert_eq!(pane.top(&log), 11);
+        pane.handle_key_event(key(KeyCode::PageDown), &log);
+        assert!(pane.is_following());
+    }
+
+    #[test]
+    fn tilde_and_esc_close_the_pane() {
+        let log = log_with(3);
+        let mut pane = ActivityLogPane::default();
+        assert_eq!(pane.handle_key_event(key(KeyCode::Char('k')), &log), None);
+        assert_eq!(
+            pane.handle_key_event(key(KeyCode::Char('~')), &log),
+            Some(Action::ToggleActivityLog)
+        );
+        assert_eq!(
+            pane.handle_key_event(key(KeyCode::Esc), &log),
+            Some(Action::ToggleActivityLog)
+        );
+    }
+}
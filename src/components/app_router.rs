@@ -1,23 +1,38 @@
+use crate::components::activity_log_pane::{ActivityLogPane, ACTIVITY_LOG_HEIGHT};
 use crate::components::component::{Component, ComponentRender};
 use crate::components::file_manager_page::FileManagerPage;
 use crate::components::help_page::HelpPage;
 use crate::components::s3_creds_page::S3CredsPage;
+use crate::components::stats_page::StatsPage;
 use crate::components::transfers_page::TransfersPage;
 use crate::model::action::Action;
+use crate::model::activity_log::ActivityLog;
 use crate::model::state::ActivePage;
 use crate::model::state::State;
-use crossterm::event::KeyEvent;
+use crate::settings::color_support::{adapt_buffer, ColorSupport};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent};
+use ratatui::layout::Rect;
 use ratatui::Frame;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::UnboundedSender;
 
+/// Time in which Ctrl+x has to be pressed again to stop all the network activity
+const STOP_NETWORK_WINDOW: Duration = Duration::from_secs(1);
+
 struct Props {
     active_page: ActivePage,
+    show_activity_log: bool,
+    activity_log: ActivityLog,
+    color_support: ColorSupport,
 }
 
 impl From<&State> for Props {
     fn from(state: &State) -> Self {
         Props {
             active_page: state.clone().active_page,
+            show_activity_log: state.show_activity_log,
+            activity_log: state.activity_log.clone(),
+            color_support: state.color_support,
         }
     }
 }
@@ -29,6 +44,12 @@ pub struct AppRouter {
     help_page: HelpPage,
     transfers_page: TransfersPage,
     s3_creds_page: S3CredsPage,
+    stats_page: StatsPage,
+    action_tx: UnboundedSender<Action>,
+    /// When Ctrl+x was pressed the first time
+    stop_network_pressed: Option<Instant>,
+    /// Shown over the bottom of every page, it takes the keys while open
+    activity_log_pane: ActivityLogPane,
 }
 
 impl AppRouter {
@@ -38,6 +59,7 @@ impl AppRouter {
             ActivePage::Help => &self.help_page,
             ActivePage::Transfers => &self.transfers_page,
             ActivePage::S3Creds => &self.s3_creds_page,
+            ActivePage::Stats => &self.stats_page,
         }
     }
 
@@ -47,8 +69,25 @@ impl AppRouter {
             ActivePage::Help => &mut self.help_page,
             ActivePage::Transfers => &mut self.transfers_page,
             ActivePage::S3Creds => &mut self.s3_creds_page,
+            ActivePage::Stats => &mut self.stats_page,
         }
     }
+
+    /// Handles the stop network key on every page, returns whether the key was consumed
+    fn handle_stop_network_key(&mut self, key: &KeyEvent, now: Instant) -> bool {
+        if key.code != KeyCode::Char('x') || !key.modifiers.contains(KeyModifiers::CONTROL) {
+            return false;
+        }
+        if key.kind == KeyEventKind::Press {
+            match self.stop_network_pressed.take() {
+                Some(pressed) if now.duration_since(pressed) <= STOP_NETWORK_WINDOW => {
+                    let _ = self.action_tx.send(Action::StopNetworkActivity);
+                }
+                _ => self.stop_network_pressed = Some(now),
+            }
+        }
+        true
+    }
 }
 
 impl Component for AppRouter {
@@ -63,6 +102,10 @@ impl Component for AppRouter {
             help_page: HelpPage::new(state, action_tx.clone()),
             transfers_page: TransfersPage::new(state, action_tx.clone()),
             s3_creds_page: S3CredsPage::new(state, action_tx.clone()),
+            stats_page: StatsPage::new(state, action_tx.clone()),
+            action_tx,
+            stop_network_pressed: None,
+            activity_log_pane: ActivityLogPane::default(),
         }
         .move_with_state(state)
     }
@@ -78,6 +121,8 @@ impl Component for AppRouter {
             help_page: self.help_page.move_with_state(state),
             transfers_page: self.transfers_page.move_with_state(state),
             s3_creds_page: self.s3_creds_page.move_with_state(state),
+            stats_page: self.stats_page.move_with_state(state),
+            ..self
         }
     }
 
@@ -87,8 +132,35 @@ impl Component for AppRouter {
     }
 
     fn handle_key_event(&mut self, key: KeyEvent) {
+        if self.handle_stop_network_key(&key, Instant::now()) {
+            return;
+        }
+        if self.props.show_activity_log {
+            if key.kind == KeyEventKind::Press {
+                if let Some(action) = self
+                    .activity_log_pane
+                    .handle_key_event(key, &self.props.activity_log)
+                {
+                    self.props.show_activity_log = false;
+                    let _ = self.action_tx.send(action);
+                }
+            }
+            return;
+        }
         self.get_active_page_component_mut().handle_key_event(key)
     }
+
+    fn handle_paste(&mut self, text: &str) {
+        self.get_active_page_component_mut().handle_paste(text)
+    }
+
+    /// The open activity log covers the page, the mouse is ignored until it's closed
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        if !self.props.show_activity_log {
+            self.get_active_page_component_mut()
+                .handle_mouse_event(mouse)
+        }
+    }
 }
 
 impl ComponentRender<()> for AppRouter {
@@ -98,6 +170,70 @@ impl ComponentRender<()> for AppRouter {
             ActivePage::Help => self.help_page.render(frame, props),
             ActivePage::Transfers => self.transfers_page.render(frame, props),
             ActivePage::S3Creds => self.s3_creds_page.render(frame, props),
+            ActivePage::Stats => self.stats_page.render(frame, props),
+        }
+        if self.props.show_activity_log {
+            let size = frame.size();
+            let height = ACTIVITY_LOG_HEIGHT.min(size.height);
+            let area = Rect::new(size.x, size.y + size.height - height, size.width, height);
+            self.activity_log_pane
+                .render(frame, area, &self.props.activity_log);
         }
+        adapt_buffer(frame.buffer_mut(), self.props.color_support);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEventState;
+    use tokio::sync::mpsc;
+
+    fn ctrl_x() -> KeyEvent {
+        KeyEvent {
+            code: KeyCode::Char('x'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }
+    }
+
+    #[test]
+    fn stop_network_needs_two_presses_within_a_second() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut router = AppRouter::new(&State::default(), tx);
+        let start = Instant::now();
+
+        assert!(router.handle_stop_network_key(&ctrl_x(), start));
+        assert!(router.handle_stop_network_key(&ctrl_x(), start + Duration::from_secs(2)));
+        assert!(rx.try_recv().is_err());
+
+        assert!(router.handle_stop_network_key(&ctrl_x(), start + Duration::from_millis(2500)));
+        assert_eq!(rx.try_recv(), Ok(Action::StopNetworkActivity));
+
+        let plain_x = KeyEvent {
+            modifiers: KeyModifiers::NONE,
+            ..ctrl_x()
+        };
+        assert!(!router.handle_stop_network_key(&plain_x, start));
+    }
+
+    #[test]
+    fn open_activity_log_takes_the_keys_until_closed() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let state = State {
+            show_activity_log: true,
+            ..State::default()
+        };
+        let mut router = AppRouter::new(&state, tx);
+
+        // 'q' would quit on the file manager
+        router.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+        router.handle_key_event(KeyEvent::new(KeyCode::Char('~'), KeyModifiers::NONE));
+        assert_eq!(rx.try_recv(), Ok(Action::ToggleActivityLog));
+
+        router.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(rx.try_recv(), Ok(Action::Exit));
     }
 }
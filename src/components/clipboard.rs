@@ -0,0 +1,76 @@
+//! This module provides copying texts to the clipboard through the terminal
+//! and the popup showing the texts it cannot copy
+use aws_smithy_types::base64;
+use ratatui::{prelude::*, widgets::*};
+use std::io::{self, IsTerminal, Write};
+
+/// Longest text copied, terminals and multiplexers drop longer OSC 52 sequences
+const MAX_COPIED_BYTES: usize = 74_994;
+
+/// Sequence asking the terminal to put the text into the system clipboard
+fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::encode(text))
+}
+
+/// Copies the text with the terminal. Returns `false` when it's switched off with
+/// `osc52_clipboard`, the output isn't a terminal or the text is too long,
+/// the caller shows the text instead
+pub fn copy_to_clipboard(text: &str, enabled: bool) -> bool {
+    if !enabled || text.len() > MAX_COPIED_BYTES || !io::stdout().is_terminal() {
+        return false;
+    }
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(osc52_sequence(text).as_bytes())
+        .and_then(|_| stdout.flush())
+        .is_ok()
+}
+
+/// Popup with a text that couldn't be copied, it can be selected with the mouse
+/// while holding Shift
+pub fn fallback_popup(text: &str) -> Paragraph {
+    Paragraph::new(text).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(
+                block::Title::from(Line::from(vec![
+                    Span::raw("|"),
+                    Span::styled("close", Style::default().fg(Color::Yellow)),
+                    Span::raw("("),
+                    Span::styled(
+                        "Esc",
+                        Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+                    ),
+                    Span::raw(")|"),
+                ]))
+                .alignment(Alignment::Right)
+                .position(block::Position::Bottom),
+            )
+            .title(
+                block::Title::from(Line::from(
+                    "| No clipboard, select the text with Shift and the mouse |",
+                ))
+                .alignment(Alignment::Left)
+                .position(block::Position::Top),
+            ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_sent_base64_encoded() {
+        assert_eq!(
+            osc52_sequence("aws s3api list-buckets"),
+            "\x1b]52;c;YXdzIHMzYXBpIGxpc3QtYnVja2V0cw==\x07"
+        );
+    }
+
+    #[test]
+    fn text_is_not_copied_when_switched_off() {
+        assert!(!copy_to_clipboard("aws s3api list-buckets", false));
+        assert!(!copy_to_clipboard(&"a".repeat(MAX_COPIED_BYTES + 1), true));
+    }
+}
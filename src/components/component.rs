@@ -1,6 +1,6 @@
 use crate::model::action::Action;
 use crate::model::state::State;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::Frame;
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -18,6 +18,14 @@ pub trait Component {
     fn name(&self) -> &str;
 
     fn handle_key_event(&mut self, key: KeyEvent);
+
+    /// Text pasted with bracketed paste, components without a focused input ignore it
+    /// so the text is never taken for key bindings
+    fn handle_paste(&mut self, _text: &str) {}
+
+    /// Clicks and wheel scrolls, matched against the areas of the last rendered frame.
+    /// Components without mouse support ignore them
+    fn handle_mouse_event(&mut self, _mouse: MouseEvent) {}
 }
 
 pub trait ComponentRender<Props> {
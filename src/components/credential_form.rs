@@ -0,0 +1,276 @@
+//! This module provides the form adding or editing a credential on the accounts page
+use crate::components::keymap::hint_spans;
+use crate::components::paste::paste_into;
+use crate::settings::file_credentials::{CredentialEntry, FileCredential};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+const LABELS: [&str; 5] = ["Name", "Access key", "Secret key", "Region", "Endpoint"];
+const SECRET_FIELD: usize = 2;
+
+/// What the key did to the form, the page closes it or sends the entry to be saved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormEvent {
+    Cancel,
+    Save(CredentialEntry),
+}
+
+/// Form opened with the add or edit key on the accounts page, the secret key is masked
+pub struct CredentialForm {
+    /// Edited credential, `None` for a new one
+    original: Option<FileCredential>,
+    inputs: [Input; 5],
+    focused: usize,
+    /// Result of the validation of the last Enter
+    error: Option<String>,
+}
+
+impl CredentialForm {
+    pub fn new_credential() -> Self {
+        CredentialForm {
+            original: None,
+            inputs: Default::default(),
+            focused: 0,
+            error: None,
+        }
+    }
+
+    pub fn edit(cred: &FileCredential) -> Self {
+        let values = [
+            &cred.name,
+            &cred.access_key,
+            &cred.secret_key,
+            &cred.default_region,
+            cred.endpoint_url.as_deref().unwrap_or_default(),
+        ];
+        CredentialForm {
+            original: Some(cred.clone()),
+            inputs: values.map(|value| Input::new(value.to_string())),
+            focused: 0,
+            error: None,
+        }
+    }
+
+    fn entry(&self) -> CredentialEntry {
+        let value = |i: usize| self.inputs[i].value().trim().to_string();
+        CredentialEntry {
+            name: value(0),
+            access_key: value(1),
+            secret_key: value(SECRET_FIELD),
+            default_region: value(3),
+            endpoint_url: value(4),
+        }
+    }
+
+    /// Handles the key, Tab and the arrows move between the fields and Enter saves the
+    /// entry when it's valid for the `existing` credentials
+    pub fn handle_key_event(
+        &mut self,
+        key: KeyEvent,
+        existing: &[FileCredential],
+    ) -> Option<FormEvent> {
+        match key.code {
+            KeyCode::Esc => return Some(FormEvent::Cancel),
+            KeyCode::Tab | KeyCode::Down => self.focused = (self.focused + 1) % LABELS.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focused = (self.focused + LABELS.len() - 1) % LABELS.len()
+            }
+            KeyCode::Enter => {
+                let entry = self.entry();
+                match entry.validate(self.original.as_ref(), existing) {
+                    Ok(()) => return Some(FormEvent::Save(entry)),
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            _ => {
+                let _ = self.inputs[self.focused].handle_event(&Event::Key(key));
+            }
+        }
+        None
+    }
+
+    /// Pasted text goes into the focused field, a submitting paste saves like Enter
+    pub fn handle_paste(
+        &mut self,
+        text: &str,
+        submit_single_line: bool,
+        existing: &[FileCredential],
+    ) -> Option<FormEvent> {
+        if paste_into(&mut self.inputs[self.focused], text, submit_single_line) {
+            let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+            return self.handle_key_event(enter, existing);
+        }
+        None
+    }
+
+    /// Original name of the edited credential
+    pub fn original_name(&self) -> Option<String> {
+        self.original.as_ref().map(|cred| cred.name.clone())
+    }
+
+    fn displayed_value(&self, index: usize) -> String {
+        let value = self.inputs[index].value();
+        match index {
+            SECRET_FIELD => "*".repeat(value.chars().count()),
+            _ => value.to_string(),
+        }
+    }
+
+    fn footer(&self) -> (String, Color) {
+        if let Some(error) = &self.error {
+            return (format!("* {}", error), Color::Red);
+        }
+        match self
+            .original
+            .as_ref()
+            .and_then(|cred| cred.profile.as_ref())
+        {
+            Some(profile) => (
+                format!(
+                    "The keys may stay empty, profile {} is used instead",
+                    profile
+                ),
+                Color::Yellow,
+            ),
+            None => (
+                "The other settings of the file are kept, without an endpoint the one of \
+                 AWS_ENDPOINT_URL_S3 or AWS_ENDPOINT_URL is used"
+                    .to_string(),
+                Color::DarkGray,
+            ),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let title = match &self.original {
+            Some(cred) => format!("| Edit account {} |", cred.name),
+            None => "| New account |".to_string(),
+        };
+        let mut hints = vec![Span::raw("|")];
+        hints.extend(hint_spans("Tab", "next field"));
+        hints.extend(hint_spans("Enter", "save"));
+        hints.extend(hint_spans("Esc", "cancel"));
+        let block = Block::default().borders(Borders::ALL).title(title).title(
+            block::Title::from(Line::from(hints))
+                .alignment(Alignment::Left)
+                .position(block::Position::Bottom),
+        );
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let label_width = 12;
+        let mut constraints = vec![Constraint::Length(1); LABELS.len()];
+        constraints.push(Constraint::Min(0));
+        let rows = Layout::vertical(constraints).split(inner);
+        for (i, label) in LABELS.iter().enumerate() {
+            let style = match i == self.focused {
+                true => Style::default().fg(Color::Green),
+                false => Style::default(),
+            };
+            let line = Line::from(vec![
+                Span::styled(format!("{:<1$}", label, label_width), style.bold()),
+                Span::styled(self.displayed_value(i), style),
+            ]);
+            frame.render_widget(Paragraph::new(line), rows[i]);
+        }
+        let (text, color) = self.footer();
+        frame.render_widget(
+            Paragraph::new(text)
+                .style(Style::default().fg(color))
+                .wrap(Wrap { trim: true }),
+            rows[LABELS.len()],
+        );
+        let cursor = label_width + self.inputs[self.focused].visual_cursor();
+        frame.set_cursor(rows[self.focused].x + cursor as u16, rows[self.focused].y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(form: &mut CredentialForm, code: KeyCode) -> Option<FormEvent> {
+        form.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE), &[])
+    }
+
+    fn type_text(form: &mut CredentialForm, text: &str) {
+        for c in text.chars() {
+            press(form, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn filled_form_is_saved_with_a_masked_secret() {
+        let mut form = CredentialForm::new_credential();
+        type_text(&mut form, "lab");
+        press(&mut form, KeyCode::Enter);
+        assert!(form.error.is_some());
+
+        for value in ["AKIALAB", "s3cr3t", " us-east-1 "] {
+            press(&mut form, KeyCode::Tab);
+            type_text(&mut form, value);
+        }
+        assert_eq!(form.displayed_value(SECRET_FIELD), "******");
+        assert_eq!(
+            press(&mut form, KeyCode::Enter),
+            Some(FormEvent::Save(CredentialEntry {
+                name: "lab".into(),
+                access_key: "AKIALAB".into(),
+                secret_key: "s3cr3t".into(),
+                default_region: "us-east-1".into(),
+                endpoint_url: String::new(),
+            }))
+        );
+
+        press(&mut form, KeyCode::Tab);
+        type_text(&mut form, "minio.local");
+        press(&mut form, KeyCode::Enter);
+        assert!(form
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("is not an endpoint"));
+        for _ in 0.."minio.local".len() {
+            press(&mut form, KeyCode::Backspace);
+        }
+        type_text(&mut form, "https://minio.local:9000");
+        let saved = press(&mut form, KeyCode::Enter);
+        assert!(matches!(
+            saved,
+            Some(FormEvent::Save(entry)) if entry.endpoint_url == "https://minio.local:9000"
+        ));
+        assert_eq!(press(&mut form, KeyCode::Esc), Some(FormEvent::Cancel));
+    }
+
+    #[test]
+    fn edited_credential_starts_with_its_values() {
+        let cred = FileCredential {
+            name: "work".into(),
+            access_key: "AKIAWORK".into(),
+            secret_key: "secret".into(),
+            default_region: "eu-west-1".into(),
+            endpoint_url: Some("http://localhost:9000".into()),
+            ..FileCredential::default()
+        };
+        let mut form = CredentialForm::edit(&cred);
+        assert_eq!(form.original_name(), Some("work".into()));
+        press(&mut form, KeyCode::BackTab);
+        press(&mut form, KeyCode::BackTab);
+        press(&mut form, KeyCode::Backspace);
+        assert_eq!(form.handle_paste("2", false, &[cred.clone()]), None);
+        // the edited credential may keep its name
+        assert_eq!(
+            press(&mut form, KeyCode::Enter),
+            Some(FormEvent::Save(CredentialEntry {
+                name: "work".into(),
+                access_key: "AKIAWORK".into(),
+                secret_key: "secret".into(),
+                default_region: "eu-west-2".into(),
+                endpoint_url: "http://localhost:9000".into(),
+            }))
+        );
+    }
+}
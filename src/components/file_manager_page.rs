@@ -1,64 +1,220 @@
+use crate::components::clipboard::{copy_to_clipboard, fallback_popup};
 use crate::components::component::{Component, ComponentRender};
+use crate::components::keymap::{
+    hint_at, hint_key, hint_line, hint_spans, rotation_for, HintContext, HintPanel,
+};
+use crate::components::metadata_popup::MetadataPopup;
+use crate::components::paste::paste_into;
+use crate::components::popup_sync::PopupSync;
 use crate::model::action::Action;
+use crate::model::aws_cli_command::CliAccount;
+use crate::model::bulk_deletion::{group_s3_deletions, DeleteProgress};
+use crate::model::file_comparison::{ComparisonVerdict, FileComparison};
+use crate::model::folder_name::folder_key;
+use crate::model::jump_target::{FileManagerFocus, JumpTarget};
+use crate::model::listing_filter::filter_rows;
 use crate::model::local_data_item::LocalDataItem;
 use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::navigation_history::NavigationStep;
 use crate::model::navigation_state::NavigationState;
+use crate::model::notification::Notification;
+use crate::model::object_lock::ObjectLockInfo;
+use crate::model::object_preview::{ObjectPreview, PreviewContent};
+use crate::model::rename::{relative_name, renamed_key, renamed_path};
 use crate::model::s3_data_item::S3DataItem;
+use crate::model::s3_path::{breadcrumb, parse_s3_path};
 use crate::model::s3_selected_item::S3SelectedItem;
 use crate::model::state::{ActivePage, State};
+use crate::services::bucket_location::{BucketLocation, S3Provider};
+use crate::services::transfer_persistence::{QueueSnapshot, ResumableUpload};
+use crate::settings::color_support::{adapt_color, FOCUS_COLOR};
+use crate::settings::custom_command::{CustomCommand, RowValues};
 use crate::settings::file_credentials::FileCredential;
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use crate::settings::key_bindings::{KeyAction, KeyBindings, KeyScope};
+use crate::utils::{display_width, format_bytes, format_duration, truncate_to_width};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::widgets::block::Title;
 use ratatui::{prelude::*, widgets::*};
+use std::cell::Cell;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use throbber_widgets_tui::Throbber;
 use tokio::sync::mpsc::UnboundedSender;
 use tui_input::backend::crossterm::EventHandler;
 use tui_input::Input;
 
 static INPUT_SIZE: usize = 60;
+/// Number of lines the preview scrolls with PageUp/PageDown
+const PREVIEW_PAGE_JUMP: u16 = 20;
+/// Number of lines the preview scrolls with a turn of the mouse wheel
+const PREVIEW_WHEEL_JUMP: i32 = 3;
+/// Time in which a second click on the same row opens it like Enter
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Areas of the last rendered frame the mouse events are matched against
+#[derive(Debug, Clone, Copy, Default)]
+struct MouseAreas {
+    /// Empty while the loading throbber replaces the table
+    s3_table: Rect,
+    local_table: Rect,
+    /// First rows displayed by the tables
+    s3_offset: usize,
+    local_offset: usize,
+    /// Text of the hint line, without the space left of it
+    hint_line: Rect,
+    hint_rotation: usize,
+}
+
+fn contains(area: Rect, column: u16, row: u16) -> bool {
+    (area.left()..area.right()).contains(&column) && (area.top()..area.bottom()).contains(&row)
+}
+
+/// Account of restored transfers missing from the credentials, with the accounts that can
+/// run them instead
+#[derive(Debug, Clone, PartialEq)]
+struct MissingAccount {
+    name: String,
+    transfers: usize,
+    accounts: Vec<String>,
+}
 
 #[derive(Clone)]
 struct Props {
     local_table_state: TableState,
+    /// Rows of the local panel, the ones matching its filter
     local_data: Vec<LocalDataItem>,
+    /// Whole local listing, without the hidden files unless they are shown
+    local_data_listed: Vec<LocalDataItem>,
+    /// Hidden files left out of the local listing
+    local_hidden_files: usize,
     s3_table_state: TableState,
+    /// Rows of the s3 panel, the ones matching its filter
     s3_data: Vec<S3DataItem>,
+    /// Whole s3 listing
+    s3_data_listed: Vec<S3DataItem>,
     s3_data_full_list: Vec<S3DataItem>,
-    s3_history: Vec<NavigationState>,
+    selection_limit_error: Option<String>,
+    long_download_keys: usize,
+    /// Location of the s3 panel in the history of the current account
+    s3_location: NavigationState,
+    /// When the displayed bucket list was listed, when it was kept from an earlier listing
+    cached_buckets_listed_at: Option<Instant>,
+    s3_row_restore: Option<(u64, usize)>,
     s3_loading: bool,
+    /// The rows of the running listing are shown as they arrive
+    s3_listing_streamed: bool,
     s3_list_recursive_loading: bool,
     s3_selected_items: Vec<S3SelectedItem>,
     local_selected_items: Vec<LocalSelectedItem>,
+    /// The listing failed or was stopped after its first pages
+    s3_listing_partial: bool,
     current_local_path: String,
+    /// Directory the downloads selected on the s3 panel are written to
+    download_dir: String,
     current_s3_bucket: Option<String>,
     current_s3_path: String,
     current_s3_creds: FileCredential,
     s3_delete_state: Option<String>,
+    s3_delete_progress: Option<DeleteProgress>,
     local_delete_state: Option<String>,
     create_bucket_state: Option<String>,
+    create_folder_state: Option<String>,
+    transfers_total: usize,
+    transfers_done: usize,
+    transfers_running: bool,
+    in_flight_requests: usize,
+    /// The certificates of the endpoint of the current account aren't verified
+    skips_tls_verify: bool,
+    notification: Option<Notification>,
+    focus_color: Color,
+    delete_lock_info: Option<(String, String, ObjectLockInfo)>,
+    object_comparison: Option<FileComparison>,
+    object_comparison_loading: bool,
+    object_comparison_error: Option<String>,
+    object_preview: Option<ObjectPreview>,
+    object_preview_loading: bool,
+    object_preview_error: Option<String>,
+    /// A pasted single line ended with a line break submits the input
+    paste_submits_single_line: bool,
+    osc52_clipboard: bool,
+    key_bindings: KeyBindings,
+    credentials_error: Option<String>,
+    /// Number of the transfers restored from the previous session waiting for the answer
+    restored_transfers: Option<usize>,
+    /// Asked before the restored transfers, one missing account at a time
+    missing_account: Option<MissingAccount>,
+    /// Interrupted uploads of the earlier sessions waiting for the answer
+    resumable_uploads: Vec<ResumableUpload>,
+    custom_commands: Vec<CustomCommand>,
 }
 
 impl From<&State> for Props {
     fn from(state: &State) -> Self {
         let st = state.clone();
+        let local_data_listed: Vec<LocalDataItem> = st
+            .local_data
+            .into_iter()
+            .filter(|item| state.show_hidden_files || !item.is_hidden)
+            .collect();
         Props {
             local_table_state: TableState::default(),
-            local_data: st.local_data,
+            local_data: local_data_listed.clone(),
+            local_hidden_files: state.local_data.len() - local_data_listed.len(),
+            local_data_listed,
             s3_table_state: TableState::default(),
-            s3_data: st.s3_data,
+            s3_data: st.s3_data.clone(),
+            s3_data_listed: st.s3_data,
             s3_data_full_list: st.s3_data_full_list,
-            s3_history: Vec::new(),
+            selection_limit_error: st.selection_limit_error,
+            long_download_keys: st.long_download_keys,
+            s3_location: state.s3_location(),
+            cached_buckets_listed_at: st.cached_buckets_listed_at,
+            s3_row_restore: st.s3_row_restore,
             s3_loading: st.s3_loading,
+            s3_listing_streamed: st.streamed_s3_listing == Some(st.s3_listing_request),
             s3_list_recursive_loading: st.s3_list_recursive_loading,
             s3_selected_items: st.s3_selected_items,
             local_selected_items: st.local_selected_items,
+            s3_listing_partial: st.s3_listing_partial,
+            download_dir: state.download_dir(),
             current_local_path: st.current_local_path,
             current_s3_bucket: st.current_s3_bucket,
             current_s3_path: st.current_s3_path.unwrap_or("/".to_string()),
             current_s3_creds: st.current_creds,
             s3_delete_state: st.s3_delete_state,
+            s3_delete_progress: st.s3_delete_progress,
             local_delete_state: st.local_delete_state,
             create_bucket_state: st.create_bucket_state,
+            create_folder_state: st.create_folder_state,
+            transfers_total: st.transfers_total,
+            transfers_done: st.transfers_done,
+            transfers_running: state.has_transfers_in_progress(),
+            in_flight_requests: st.in_flight_requests,
+            skips_tls_verify: state.skips_tls_verify(&state.current_creds),
+            notification: st.notification,
+            focus_color: adapt_color(FOCUS_COLOR, st.color_support),
+            delete_lock_info: st.delete_lock_info,
+            object_comparison: st.object_comparison,
+            object_comparison_loading: st.object_comparison_loading,
+            object_comparison_error: st.object_comparison_error,
+            object_preview: st.object_preview,
+            object_preview_loading: st.object_preview_loading,
+            object_preview_error: st.object_preview_error,
+            paste_submits_single_line: st.app_config.paste_submits_single_line,
+            osc52_clipboard: st.app_config.osc52_clipboard,
+            key_bindings: st.key_bindings,
+            credentials_error: st.credentials_error,
+            restored_transfers: st.restored_transfers.as_ref().map(QueueSnapshot::len),
+            missing_account: state.orphaned_transfers.as_ref().and_then(|restored| {
+                let name = restored.missing_accounts.first()?;
+                Some(MissingAccount {
+                    name: name.clone(),
+                    transfers: restored.orphaned_by(name),
+                    accounts: state.creds.iter().map(|c| c.name.clone()).collect(),
+                })
+            }),
+            resumable_uploads: st.resumable_uploads,
+            custom_commands: st.app_config.custom_commands,
         }
     }
 }
@@ -74,15 +230,180 @@ pub struct FileManagerPage {
     s3_panel_selected: bool,
     show_problem_popup: bool,
     show_bucket_input: bool,
+    /// The name input creates a folder in the current s3 location
+    show_folder_input: bool,
+    /// Validation error of the typed folder name
+    folder_name_error: Option<String>,
+    /// The name input takes the location opened with 'g'
+    show_go_to_input: bool,
+    /// Why the typed location can't be opened
+    go_to_error: Option<String>,
+    /// Object renamed with the name input, names are relative to the current s3 location
+    renaming: Option<S3SelectedItem>,
+    rename_error: Option<String>,
+    /// Rename waiting for the confirmation to replace the object already at the new key
+    rename_conflict: Option<(S3SelectedItem, String)>,
+    /// Local file or directory renamed with the name input, always within its directory
+    renaming_local: Option<LocalDataItem>,
+    /// Local rename waiting for the confirmation to replace the file already at the new name
+    local_rename_conflict: Option<(LocalDataItem, String)>,
     show_delete_confirmation: bool,
+    /// Confirmation of deleting all the selected items with 'D'
+    show_delete_selected_confirmation: bool,
     show_delete_multiple_confirmation: bool,
     show_download_confirmation: bool,
     show_delete_error: bool,
-    default_navigation_state: NavigationState,
     input: Input,
+    /// Moment the hint line started cycling through the contextual hints
+    hints_shown_at: Instant,
+    /// Id of the last jump from the transfers page shown by this page
+    applied_focus: u64,
+    /// Id of the last row restored after a back/forward step
+    applied_row_restore: u64,
+    /// Ids of the last bucket creation and deletion results shown by this page
+    applied_create_bucket_result: u64,
+    applied_create_folder_result: u64,
+    applied_go_to_path_result: u64,
+    applied_rename_result: u64,
+    applied_delete_result: u64,
+    applied_copied_object_result: u64,
+    metadata_popup: Option<MetadataPopup>,
+    /// Popups opened or closed with a key before the state store handled the action
+    metadata_sync: PopupSync,
+    preview_sync: PopupSync,
+    comparison_sync: PopupSync,
+    recursive_listing_sync: PopupSync,
+    /// Shown when '=' is pressed without an s3 object and a local file highlighted
+    show_compare_hint: bool,
+    /// First line of the preview displayed in the popup
+    preview_scroll: u16,
+    /// Object yanked with 'y' or 'Y' waiting to be pasted with 'P', `true` moves it
+    yanked: Option<(S3SelectedItem, bool)>,
+    /// Name filters of the panels typed after '/', cleared when the panel lists another location
+    s3_filter: String,
+    local_filter: String,
+    /// Input of the filter of the selected panel while it's typed
+    filter_input: Option<Input>,
+    /// Text the clipboard didn't take, shown in a popup until Esc
+    copy_fallback: Option<String>,
+    /// Account highlighted in the popup of the missing account
+    remap_choice: usize,
+    /// Upload highlighted in the popup of the interrupted uploads
+    resume_choice: usize,
+    mouse_areas: Cell<MouseAreas>,
+    /// Time, panel (`true` for s3) and row of the last click on a table row
+    last_click: Option<(Instant, bool, usize)>,
 }
 
 impl FileManagerPage {
+    fn submit_bucket_name(&mut self) {
+        let _ = self.action_tx.send(Action::CreateBucket {
+            name: self.input.value().to_string(),
+        });
+        self.show_bucket_input = false;
+    }
+
+    /// Creates the typed folder in the current s3 location, invalid names keep the input open
+    fn submit_folder_name(&mut self) {
+        let location = self.props.s3_location.clone();
+        let bucket = match location.current_bucket {
+            Some(bucket) => bucket,
+            None => return,
+        };
+        let name = self.input.value().to_string();
+        match folder_key(location.current_prefix.as_deref(), &name) {
+            Ok(_) => {
+                let _ = self.action_tx.send(Action::CreateFolder {
+                    bucket,
+                    prefix: location.current_prefix,
+                    name,
+                });
+                self.show_folder_input = false;
+                self.folder_name_error = None;
+            }
+            Err(error) => self.folder_name_error = Some(error),
+        }
+    }
+
+    /// Opens the typed location once the state store checked it exists, paths which can't be
+    /// parsed keep the input open
+    fn submit_go_to(&mut self) {
+        let current_bucket = self.props.s3_location.current_bucket.clone();
+        match parse_s3_path(self.input.value(), current_bucket.as_deref()) {
+            Ok(location) => {
+                let _ = self.action_tx.send(Action::GoToS3Path {
+                    location,
+                    row: self.props.s3_table_state.selected(),
+                });
+                self.go_to_error = None;
+            }
+            Err(error) => self.go_to_error = Some(error),
+        }
+    }
+
+    /// Opens the name input of the highlighted object filled with its name in the current
+    /// s3 location
+    fn start_rename_of_highlighted_s3_object(&mut self) {
+        if let Some(item) = self.highlighted_s3_selected_object() {
+            let key = item.path.clone().unwrap_or(item.name.clone());
+            let prefix = self.props.s3_location.current_prefix.clone();
+            self.input = Input::new(relative_name(prefix.as_deref(), &key).to_string());
+            self.rename_error = None;
+            self.renaming = Some(item);
+        }
+    }
+
+    /// Renames the object to the typed name, invalid names keep the input open
+    fn submit_rename(&mut self) {
+        let prefix = self.props.s3_location.current_prefix.clone();
+        match renamed_key(prefix.as_deref(), self.input.value()) {
+            Ok(new_key) => {
+                if let Some(item) = self.renaming.take() {
+                    let _ = self.action_tx.send(Action::RenameS3Item {
+                        item,
+                        new_key,
+                        overwrite: false,
+                    });
+                }
+                self.rename_error = None;
+            }
+            Err(error) => self.rename_error = Some(error),
+        }
+    }
+
+    /// Opens the name input of the highlighted local row filled with its name
+    fn start_rename_of_highlighted_local_item(&mut self) {
+        if let Some(item) = self
+            .props
+            .local_table_state
+            .selected()
+            .and_then(|index| self.props.local_data.get(index))
+        {
+            self.input = Input::new(item.name.clone());
+            self.rename_error = None;
+            self.renaming_local = Some(item.clone());
+        }
+    }
+
+    /// Renames the local item to the typed name, invalid names keep the input open
+    fn submit_local_rename(&mut self) {
+        let Some(item) = self.renaming_local.clone() else {
+            return;
+        };
+        match renamed_path(Path::new(&item.path), self.input.value()) {
+            Ok(_) => {
+                let _ = self.action_tx.send(Action::RenameLocalItem {
+                    item,
+                    new_name: self.input.value().to_string(),
+                    overwrite: false,
+                });
+                self.renaming_local = None;
+                self.rename_error = None;
+            }
+            Err(error) => self.rename_error = Some(error),
+        }
+    }
+
     fn make_transfer_error_popup(&self) -> Paragraph {
         // Define the text for the paragraph
         let text = "   To move data into s3 you need to select at least a bucket to which you want to transfer your files";
@@ -123,6 +444,7 @@ impl FileManagerPage {
     fn make_delete_alert(&self, text: String, text_color: Color) -> Paragraph {
         let input = Paragraph::new(text)
             .style(Style::default().fg(text_color))
+            .wrap(Wrap { trim: true })
             .block(
                 Block::default()
                     .borders(Borders::ALL)
@@ -195,6 +517,7 @@ impl FileManagerPage {
             .position(block::Position::Bottom);
         let input = Paragraph::new(text)
             .style(Style::default().fg(text_color))
+            .wrap(Wrap { trim: false })
             .block(
                 Block::default()
                     .borders(Borders::ALL)
@@ -212,7 +535,118 @@ impl FileManagerPage {
             );
         input
     }
-    fn make_bucket_name_input(&self) -> Paragraph {
+
+    fn make_restored_transfers_popup(&self, count: usize) -> Paragraph {
+        let mut hints = vec![Span::raw("|")];
+        hints.extend(hint_spans("r", "resume now"));
+        hints.extend(hint_spans("Enter", "keep selected"));
+        hints.extend(hint_spans("d", "discard"));
+        Paragraph::new(format!(
+            "You have {} unfinished transfer(s) from the last session. \
+             Resume them now, keep them selected for later or discard them?",
+            count
+        ))
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default().borders(Borders::ALL).title(
+                Title::from(Line::from(hints))
+                    .alignment(Alignment::Right)
+                    .position(block::Position::Bottom),
+            ),
+        )
+    }
+
+    fn make_resumable_uploads_popup(&self) -> Paragraph {
+        let mut hints = vec![Span::raw("|")];
+        hints.extend(hint_spans("r", "resume"));
+        hints.extend(hint_spans("a", "abort"));
+        hints.extend(hint_spans("Esc", "decide later"));
+        let mut lines = vec![
+            Line::from(format!(
+                "{} upload(s) of the last session were interrupted. Resume them from their \
+                 uploaded parts, abort them or decide at the next start?",
+                self.props.resumable_uploads.len()
+            )),
+            Line::from(""),
+        ];
+        lines.extend(
+            self.props
+                .resumable_uploads
+                .iter()
+                .enumerate()
+                .map(|(i, upload)| {
+                    let text = format!(
+                        "{}/{} ({} of {} parts)",
+                        upload.bucket,
+                        upload.key,
+                        upload.parts.len(),
+                        upload.part_count()
+                    );
+                    if i == self.resume_choice {
+                        Line::styled(
+                            format!("> {}", text),
+                            Style::default()
+                                .fg(self.props.focus_color)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        Line::from(format!("  {}", text))
+                    }
+                }),
+        );
+        Paragraph::new(lines)
+            .style(Style::default().fg(Color::Yellow))
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default().borders(Borders::ALL).title(
+                    Title::from(Line::from(hints))
+                        .alignment(Alignment::Right)
+                        .position(block::Position::Bottom),
+                ),
+            )
+    }
+
+    fn make_missing_account_popup(&self, missing: &MissingAccount) -> Paragraph {
+        let mut hints = vec![Span::raw("|")];
+        if !missing.accounts.is_empty() {
+            hints.extend(hint_spans("Enter", "use account"));
+        }
+        hints.extend(hint_spans("d", "drop"));
+        let mut lines = vec![
+            Line::from(format!(
+                "The account '{}' of {} unfinished transfer(s) from the last session is gone. \
+                 Run them with another account or drop them?",
+                missing.name, missing.transfers
+            )),
+            Line::from(""),
+        ];
+        lines.extend(missing.accounts.iter().enumerate().map(|(i, account)| {
+            if i == self.remap_choice {
+                Line::styled(
+                    format!("> {}", account),
+                    Style::default()
+                        .fg(self.props.focus_color)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Line::from(format!("  {}", account))
+            }
+        }));
+        Paragraph::new(lines)
+            .style(Style::default().fg(Color::Yellow))
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default().borders(Borders::ALL).title(
+                    Title::from(Line::from(hints))
+                        .alignment(Alignment::Right)
+                        .position(block::Position::Bottom),
+                ),
+            )
+    }
+
+    fn make_name_input(&self, title: impl Into<String>) -> Paragraph {
+        let title: String = title.into();
         let scroll = self.input.visual_scroll(INPUT_SIZE);
         let input = Paragraph::new(self.input.value())
             .style(Style::default().fg(Color::Green))
@@ -252,9 +686,7 @@ impl FileManagerPage {
                             .position(ratatui::widgets::block::Position::Bottom),
                     )
                     .title(
-                        ratatui::widgets::block::Title::from(Line::from(vec![Span::raw(
-                            "| Enter new bucket name |",
-                        )]))
+                        ratatui::widgets::block::Title::from(Line::from(vec![Span::raw(title)]))
                             .alignment(Alignment::Left)
                             .position(ratatui::widgets::block::Position::Top),
                     ),
@@ -262,6 +694,21 @@ impl FileManagerPage {
         input
     }
 
+    fn render_name_input(&self, frame: &mut Frame, block: Paragraph, error: Option<String>) {
+        let area = Self::centered_rect(40, 20, frame.size());
+
+        frame.render_widget(Clear, area); //this clears out the background
+        frame.render_widget(block, area);
+        if let Some(error) = error {
+            let error_paragraph =
+                Paragraph::new(format!("* {:?}", error)).style(Style::default().fg(Color::Red));
+            let error_rect = Rect::new(area.x + 1, area.y + 4, area.width, area.height);
+            frame.render_widget(Clear, error_rect);
+            frame.render_widget(error_paragraph, error_rect);
+        }
+        frame.set_cursor(area.x + self.input.visual_cursor() as u16 + 1, area.y + 1);
+    }
+
     fn get_loading_info(&self) -> Throbber {
         Throbber::default()
             .label("Loading s3 data...")
@@ -270,7 +717,7 @@ impl FileManagerPage {
     }
 
     fn get_local_table(&self, focus_color: Color) -> Table {
-        let header = Row::new(vec!["Name", "Size", "Type"])
+        let header = Row::new(vec!["Name", "Size", "Type", "Modified"])
             .fg(focus_color)
             .bold()
             .underlined()
@@ -282,9 +729,10 @@ impl FileManagerPage {
             .iter()
             .map(|item| FileManagerPage::get_local_row(self, item, focus_color));
         let widths = [
-            Constraint::Length(60),
-            Constraint::Length(20),
-            Constraint::Length(20),
+            Constraint::Length(50),
+            Constraint::Length(15),
+            Constraint::Length(10),
+            Constraint::Length(25),
         ];
         let block = self.get_home_local_block();
         let table = Table::new(rows, widths)
@@ -297,90 +745,152 @@ impl FileManagerPage {
                     .add_modifier(Modifier::REVERSED),
             )
             .widths([
-                Constraint::Percentage(60),
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
+                Constraint::Percentage(50),
+                Constraint::Percentage(15),
+                Constraint::Percentage(10),
+                Constraint::Percentage(25),
             ]);
         table
     }
 
-    fn flatten_s3_items(&self, s3_selected_items: Vec<S3SelectedItem>) -> Vec<S3SelectedItem> {
-        let nested: Vec<Vec<S3SelectedItem>> = s3_selected_items
-            .iter()
-            .map(|i| i.clone().children.unwrap_or_default())
-            .collect();
-        let mut children: Vec<S3SelectedItem> = nested.into_iter().flatten().collect();
-        let single_files: Vec<S3SelectedItem> = s3_selected_items
-            .into_iter()
-            .filter(|i| i.children.is_none())
-            .collect();
-        children.extend(single_files);
-        children
-    }
-
-    fn flatten_local_items(
-        &self,
-        local_selected_items: Vec<LocalSelectedItem>,
-    ) -> Vec<LocalSelectedItem> {
-        let nested: Vec<Vec<LocalSelectedItem>> = local_selected_items
-            .iter()
-            .map(|i| i.clone().children.unwrap_or_default())
-            .collect();
-        let mut children: Vec<LocalSelectedItem> = nested.into_iter().flatten().collect();
-        let single_files: Vec<LocalSelectedItem> = local_selected_items
-            .into_iter()
-            .filter(|i| i.children.is_none())
-            .collect();
-        children.extend(single_files);
-        children
-    }
-
-    fn get_status_line(&self) -> Paragraph {
-        let s3_items = self.flatten_s3_items(self.props.s3_selected_items.clone());
-        let local_items = self.flatten_local_items(self.props.local_selected_items.clone());
-        let to_transfer = s3_items.len() + local_items.len();
-        let transferred = s3_items.iter().filter(|i| i.transferred).count()
-            + local_items.iter().filter(|i| i.transferred).count();
-        if let Some(bucket) = &self.props.current_s3_bucket {
-            let bottom_text = Paragraph::new(format!(
-                " Account: {} • Bucket: {} • Transfers: {}/{}",
-                self.props.current_s3_creds.name, bucket, to_transfer, transferred
-            ))
+    fn get_status_line(&self, width: u16) -> Paragraph {
+        match self.props.notification.as_ref().filter(|n| !n.is_expired()) {
+            Some(notification) => Paragraph::new(format!(" {}", notification.message))
                 .style(Style::default().fg(Color::White))
-                .bg(Color::Blue);
-            bottom_text
-        } else {
-            let bottom_text = Paragraph::new(format!(
-                " Account: {} • Transfers: {}/{}",
-                self.props.current_s3_creds.name, to_transfer, transferred
-            ))
+                .bg(Color::Red),
+            None => {
+                let badge = Self::tls_badge(self.props.skips_tls_verify);
+                let text = Self::status_line_text(
+                    &self.props.current_s3_creds.name,
+                    self.props.current_s3_bucket.as_deref(),
+                    self.props.transfers_total,
+                    self.props.transfers_done,
+                    self.props.in_flight_requests,
+                    self.filtered_rows(),
+                    (width as usize).saturating_sub(display_width(badge)),
+                );
+                Paragraph::new(Line::from(vec![
+                    Span::raw(text),
+                    Span::styled(badge, Style::default().bg(Color::Red).bold()),
+                ]))
                 .style(Style::default().fg(Color::White))
-                .bg(Color::Blue);
-            bottom_text
+                .bg(Color::Blue)
+            }
         }
     }
 
-    fn get_help_line(&self) -> Paragraph {
-        if self.props.s3_selected_items.is_empty() && self.props.local_selected_items.is_empty() {
-            Paragraph::new(
-                "| 't' transfer select, 's' s3 account, 'l' transfers list, 'Esc/Enter' browsing",
-            )
-                .style(Style::default().fg(Color::White))
-                .bg(Color::Blue)
-                .alignment(Alignment::Right)
+    /// Kept at the end of the status line while the certificates of the endpoint of the
+    /// account aren't verified
+    fn tls_badge(skips_tls_verify: bool) -> &'static str {
+        match skips_tls_verify {
+            true => " INSECURE TLS ",
+            false => "",
+        }
+    }
+
+    /// Builds the status line fitting into `width` columns, the counters are always
+    /// displayed in full while the account and bucket names are shortened when necessary.
+    /// The running requests and the rows matching the filter of the selected panel are shown
+    /// only when there are some, without credentials the account part reads "No account"
+    fn status_line_text(
+        account: &str,
+        bucket: Option<&str>,
+        to_transfer: usize,
+        transferred: usize,
+        in_flight: usize,
+        filtered: Option<(usize, usize)>,
+        width: usize,
+    ) -> String {
+        let account_label = if account.is_empty() {
+            " No account"
         } else {
-            Paragraph::new("| Press 'l' to see the transfers list,'s' to select s3 account ")
-                .style(Style::default().fg(Color::White))
-                .bg(Color::Blue)
-                .alignment(Alignment::Right)
+            " Account: "
+        };
+        let bucket_label = " • Bucket: ";
+        let mut counters = format!(" • Transfers: {}/{}", to_transfer, transferred);
+        if in_flight > 0 {
+            counters.push_str(&format!(" • Requests: {}", in_flight));
+        }
+        if let Some((matching, listed)) = filtered {
+            counters.push_str(&format!(" • filtered: {}/{}", matching, listed));
+        }
+        let fixed_width = display_width(account_label)
+            + display_width(&counters)
+            + bucket.map_or(0, |_| display_width(bucket_label));
+        let budget = width.saturating_sub(fixed_width);
+        match bucket {
+            Some(bucket) => {
+                let account_budget = (budget / 2).max(budget.saturating_sub(display_width(bucket)));
+                let account = truncate_to_width(account, account_budget);
+                let bucket = truncate_to_width(bucket, budget - display_width(&account));
+                format!(
+                    "{}{}{}{}{}",
+                    account_label, account, bucket_label, bucket, counters
+                )
+            }
+            None => format!(
+                "{}{}{}",
+                account_label,
+                truncate_to_width(account, budget),
+                counters
+            ),
+        }
+    }
+
+    fn hint_context(&self) -> HintContext {
+        HintContext {
+            panel: if self.s3_panel_selected {
+                HintPanel::S3
+            } else {
+                HintPanel::Local
+            },
+            popup_open: self.copy_fallback.is_some()
+                || self.metadata_popup.is_some()
+                || self.comparison_popup_open()
+                || self.preview_popup_open()
+                || self.show_problem_popup
+                || self.show_bucket_input
+                || self.show_folder_input
+                || self.show_go_to_input
+                || self.renaming.is_some()
+                || self.rename_conflict.is_some()
+                || self.renaming_local.is_some()
+                || self.local_rename_conflict.is_some()
+                || self.filter_input.is_some()
+                || self.show_delete_confirmation
+                || self.show_delete_selected_confirmation
+                || self.show_delete_multiple_confirmation
+                || self.show_download_confirmation
+                || self.show_delete_error
+                || self.props.credentials_error.is_some()
+                || self.props.restored_transfers.is_some()
+                || self.props.missing_account.is_some()
+                || !self.props.resumable_uploads.is_empty(),
+            has_selection: !self.props.s3_selected_items.is_empty()
+                || !self.props.local_selected_items.is_empty(),
+            transfers_running: self.props.transfers_running,
+            inside_bucket: self.props.current_s3_bucket.is_some(),
         }
     }
 
+    /// Location of the s3 panel as `bucket / pre / fix`, shortened to fit the width
+    fn get_breadcrumb(&self, width: u16) -> Paragraph<'static> {
+        let path = breadcrumb(&self.props.s3_location, (width as usize).saturating_sub(1));
+        Paragraph::new(format!(" {}", path)).bold()
+    }
+
+    fn get_help_line(hints: String) -> Paragraph<'static> {
+        Paragraph::new(hints)
+            .style(Style::default().fg(Color::White))
+            .bg(Color::Blue)
+            .alignment(Alignment::Right)
+    }
+
     fn get_s3_row(&self, item: &S3DataItem, focus_color: Color) -> Row {
         if self.contains_s3_item(
             item,
             &self.props.s3_selected_items,
-            &self.props.current_s3_creds,
+            &self.props.current_s3_creds.name,
         ) {
             Row::new(item.to_columns().clone())
                 .fg(focus_color)
@@ -394,7 +904,7 @@ impl FileManagerPage {
         if self.contains_local_item(
             item,
             &self.props.local_selected_items,
-            &self.props.current_s3_creds,
+            &self.props.current_s3_creds.name,
         ) {
             Row::new(item.to_columns().clone())
                 .fg(focus_color)
@@ -408,12 +918,12 @@ impl FileManagerPage {
         &self,
         data_item: &S3DataItem,
         selected_items: &[S3SelectedItem],
-        s3_creds: &FileCredential,
+        account: &str,
     ) -> bool {
-        let destination_dir = self.props.current_local_path.clone();
+        let destination_dir = self.props.download_dir.clone();
         let search_item = S3SelectedItem::from_s3_data_item(
             data_item.clone(),
-            s3_creds.clone(),
+            account.to_string(),
             destination_dir.clone(),
         ); // Convert S3DataItem to S3SelectedItem
         selected_items.contains(&search_item) // Search for the item in the list
@@ -423,15 +933,20 @@ impl FileManagerPage {
         &self,
         data_item: &LocalDataItem,
         selected_items: &[LocalSelectedItem],
-        s3_creds: &FileCredential,
+        account: &str,
     ) -> bool {
         let search_item =
-            LocalSelectedItem::from_local_data_item(data_item.clone(), s3_creds.clone());
+            LocalSelectedItem::from_local_data_item(data_item.clone(), account.to_string());
         selected_items.contains(&search_item) // Search for the item in the list
     }
 
     fn get_s3_table(&self, focus_color: Color) -> Table {
-        let header = Row::new(vec!["Name", "Size", "Type"])
+        // the bucket list shows the creation and the region of the buckets instead
+        let (modified, class) = match self.props.s3_location.current_bucket {
+            Some(_) => ("Last modified", "Storage class"),
+            None => ("Created", "Region"),
+        };
+        let header = Row::new(vec!["Name", "Size", "Type", modified, class])
             .fg(focus_color)
             .bold()
             .underlined()
@@ -443,7 +958,9 @@ impl FileManagerPage {
             .iter()
             .map(|item| FileManagerPage::get_s3_row(self, item, focus_color));
         let widths = [
-            Constraint::Length(60),
+            Constraint::Length(40),
+            Constraint::Length(10),
+            Constraint::Length(10),
             Constraint::Length(20),
             Constraint::Length(20),
         ];
@@ -458,43 +975,388 @@ impl FileManagerPage {
                     .add_modifier(Modifier::REVERSED),
             )
             .widths([
-                Constraint::Percentage(60),
+                Constraint::Percentage(40),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
                 Constraint::Percentage(20),
                 Constraint::Percentage(20),
             ]);
         table
     }
 
+    /// Title of the s3 panel with the state of the listing, the yanked object and the running
+    /// recursive delete
+    fn s3_panel_title(&self) -> String {
+        let title = match self.props.cached_buckets_listed_at {
+            Some(listed_at) if self.props.s3_location.current_bucket.is_none() => format!(
+                "S3 List ({} objects) • cached {} ago - press F5 to refresh",
+                self.props.s3_data.len(),
+                format_duration(listed_at.elapsed())
+            ),
+            _ if self.props.s3_loading => format!(
+                "S3 List ({} objects so far) • listing - press Esc to stop",
+                self.props.s3_data.len()
+            ),
+            _ => format!("S3 List ({} objects)", self.props.s3_data.len()),
+        };
+        let title = Self::with_filter(title, &self.s3_filter);
+        let title = match &self.yanked {
+            Some((item, delete_source)) => format!(
+                "{} • {} {} - press P to paste",
+                title,
+                if *delete_source { "moving" } else { "copying" },
+                item.name
+            ),
+            None => title,
+        };
+        match &self.props.s3_delete_progress {
+            Some(progress) => format!(
+                "{} • deleting {}: {} of {} objects",
+                title, progress.location, progress.deleted, progress.total
+            ),
+            None => title,
+        }
+    }
+
+    /// Panel title with the partial listing marked in yellow
+    fn s3_panel_title_line(&self) -> Line {
+        let mut spans = vec![Span::raw(self.s3_panel_title())];
+        if self.props.s3_listing_partial && !self.props.s3_loading {
+            spans.push(Span::styled(
+                " (partial — press F5 to retry)",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        Line::from(spans)
+    }
+
+    /// Adds the warning about the partial listing of the s3 panel to the question of a bulk
+    /// operation, the question turns yellow
+    fn with_partial_listing_warning(&self, question: String, color: Color) -> (String, Color) {
+        match self.props.s3_listing_partial {
+            true => (
+                format!(
+                    "{}\n\nThe listing of this location is incomplete, rows may be missing \
+                     - press F5 to retry",
+                    question
+                ),
+                Color::Yellow,
+            ),
+            false => (question, color),
+        }
+    }
+
     fn get_home_s3_block(&self) -> Block {
+        let title = self.s3_panel_title_line();
         if self.s3_panel_selected {
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!("S3 List ({} objects)", self.props.s3_data.len()))
+                .title(title)
                 .fg(Color::Blue)
         } else {
-            Block::default()
-                .borders(Borders::ALL)
-                .title(format!("S3 List ({} objects)", self.props.s3_data.len()))
+            Block::default().borders(Borders::ALL).title(title)
         }
     }
 
     fn get_home_local_block(&self) -> Block {
+        let objects = match self.props.local_hidden_files {
+            0 => format!("{} objects", self.props.local_data.len()),
+            hidden => format!("{} objects, {} hidden", self.props.local_data.len(), hidden),
+        };
+        let title = Self::with_filter(format!("Local List ({})", objects), &self.local_filter);
         if !self.s3_panel_selected {
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!(
-                    "Local List ({} objects)",
-                    self.props.local_data.len()
-                ))
+                .title(title)
                 .fg(Color::Blue)
         } else {
-            Block::default().borders(Borders::ALL).title(format!(
-                "Local List ({} objects)",
-                self.props.local_data.len()
-            ))
+            Block::default().borders(Borders::ALL).title(title)
+        }
+    }
+
+    fn with_filter(title: String, filter: &str) -> String {
+        if filter.is_empty() {
+            title
+        } else {
+            format!("{} • filter: {}", title, filter)
+        }
+    }
+
+    /// Rows matching the filter of the selected panel and the listed rows, `None` without a filter
+    fn filtered_rows(&self) -> Option<(usize, usize)> {
+        match self.s3_panel_selected {
+            true if !self.s3_filter.is_empty() => {
+                Some((self.props.s3_data.len(), self.props.s3_data_listed.len()))
+            }
+            false if !self.local_filter.is_empty() => Some((
+                self.props.local_data.len(),
+                self.props.local_data_listed.len(),
+            )),
+            _ => None,
+        }
+    }
+
+    fn panel_filter(&self) -> &str {
+        match self.s3_panel_selected {
+            true => &self.s3_filter,
+            false => &self.local_filter,
+        }
+    }
+
+    /// Runs the action of a key pressed while no popup is open
+    fn handle_key_action(&mut self, action: KeyAction) {
+        match action {
+            KeyAction::Create => {
+                if self.s3_panel_selected {
+                    self.input.reset();
+                    if self.props.s3_location.current_bucket.is_some() {
+                        self.folder_name_error = None;
+                        self.show_folder_input = true;
+                    } else {
+                        self.show_bucket_input = true;
+                    }
+                }
+            }
+            KeyAction::Refresh => {
+                if self.s3_panel_selected && !self.props.s3_loading {
+                    let _ = self.action_tx.send(
+                        match self.props.s3_location.current_bucket.is_none() {
+                            true => Action::RefreshBuckets,
+                            false => Action::RefreshS3Location,
+                        },
+                    );
+                }
+            }
+            KeyAction::Filter => self.open_filter_input(),
+            KeyAction::HistoryBack => self.go_back(),
+            KeyAction::HistoryForward => {
+                if self.s3_panel_selected && !self.props.s3_loading {
+                    self.navigate_s3(NavigationStep::Forward)
+                }
+            }
+            KeyAction::Parent => {
+                if self.s3_panel_selected && !self.props.s3_loading {
+                    self.navigate_s3(NavigationStep::Parent)
+                }
+            }
+            KeyAction::GoTo => {
+                if self.s3_panel_selected && !self.props.s3_loading {
+                    self.input.reset();
+                    self.go_to_error = None;
+                    self.show_go_to_input = true;
+                }
+            }
+            KeyAction::Delete => {
+                if self.s3_panel_selected {
+                    if let Some((bucket, key)) = self.highlighted_s3_object() {
+                        let _ = self
+                            .action_tx
+                            .send(Action::FetchObjectLockInfo { bucket, key });
+                    }
+                }
+                self.show_delete_confirmation = true;
+            }
+            KeyAction::DeleteSelected => {
+                if !self.props.s3_selected_items.is_empty()
+                    || !self.props.local_selected_items.is_empty()
+                {
+                    self.show_delete_selected_confirmation = true;
+                }
+            }
+            KeyAction::Metadata => {
+                if self.s3_panel_selected {
+                    self.show_selected_s3_object_metadata()
+                }
+            }
+            KeyAction::Preview => {
+                if self.s3_panel_selected {
+                    self.preview_highlighted_object()
+                }
+            }
+            KeyAction::Compare => self.compare_highlighted_pair(),
+            KeyAction::AwsCli => {
+                if let Some(command) = self.panel_cli_command() {
+                    self.copy_text(command);
+                }
+            }
+            KeyAction::Yank | KeyAction::YankMove => {
+                if self.s3_panel_selected {
+                    self.yank_highlighted_s3_object(action == KeyAction::YankMove)
+                }
+            }
+            KeyAction::Paste => {
+                if self.s3_panel_selected && !self.props.s3_loading {
+                    self.paste_yanked_s3_object()
+                }
+            }
+            KeyAction::Rename => {
+                if self.s3_panel_selected {
+                    self.start_rename_of_highlighted_s3_object()
+                }
+            }
+            KeyAction::RenameLocal => {
+                if !self.s3_panel_selected {
+                    self.start_rename_of_highlighted_local_item()
+                }
+            }
+            KeyAction::ToggleHidden => {
+                if !self.s3_panel_selected {
+                    let _ = self.action_tx.send(Action::ToggleHiddenFiles);
+                }
+            }
+            KeyAction::CopyContents => {
+                if self.s3_panel_selected {
+                    self.copy_highlighted_object_contents()
+                }
+            }
+            KeyAction::ToggleSelect => {
+                if self.s3_panel_selected {
+                    self.transfer_from_s3_to_local()
+                } else {
+                    self.transfer_from_local_to_s3()
+                }
+            }
+            KeyAction::NavigateHelp => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::Help,
+                });
+            }
+            KeyAction::NavigateTransfers => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::Transfers,
+                });
+            }
+            KeyAction::NavigateAccounts => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::S3Creds,
+                });
+            }
+            KeyAction::NavigateStats => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::Stats,
+                });
+            }
+            KeyAction::ActivityLog => {
+                let _ = self.action_tx.send(Action::ToggleActivityLog);
+            }
+            KeyAction::Quit => {
+                let _ = self.action_tx.send(Action::Exit);
+            }
+            // actions of the transfers page
+            _ => {}
+        }
+    }
+
+    /// Previous location of the s3 panel or the parent directory of the local one
+    fn go_back(&mut self) {
+        match self.s3_panel_selected {
+            true => {
+                if self.props.s3_loading {
+                    let _ = self.action_tx.send(Action::CancelS3Listing);
+                } else {
+                    self.navigate_s3(NavigationStep::Back)
+                }
+            }
+            false => {
+                if self.show_problem_popup {
+                    self.show_problem_popup = false;
+                } else {
+                    self.handle_go_back_local()
+                }
+            }
+        }
+    }
+
+    /// Opens the input of the filter of the selected panel with the filter applied so far
+    fn open_filter_input(&mut self) {
+        self.filter_input = Some(Input::new(self.panel_filter().to_string()));
+    }
+
+    /// Filters the rows of the selected panel and highlights the first matching one
+    fn set_panel_filter(&mut self, filter: String) {
+        let (table_state, len) = match self.s3_panel_selected {
+            true => {
+                self.s3_filter = filter;
+                self.apply_s3_filter();
+                (&mut self.props.s3_table_state, self.props.s3_data.len())
+            }
+            false => {
+                self.local_filter = filter;
+                self.apply_local_filter();
+                (
+                    &mut self.props.local_table_state,
+                    self.props.local_data.len(),
+                )
+            }
+        };
+        table_state.select(if len == 0 { None } else { Some(0) });
+    }
+
+    /// The row indexes of the table always point into the filtered rows, so every action on
+    /// the highlighted row takes the item displayed there
+    fn apply_s3_filter(&mut self) {
+        if self.s3_filter.is_empty() {
+            self.props.s3_data = self.props.s3_data_listed.clone();
+            return;
+        }
+        self.props.s3_data = filter_rows(&self.props.s3_data_listed, &self.s3_filter, |i| {
+            i.name.as_str()
+        });
+        Self::clamp_selection(&mut self.props.s3_table_state, self.props.s3_data.len());
+    }
+
+    fn apply_local_filter(&mut self) {
+        if self.local_filter.is_empty() {
+            self.props.local_data = self.props.local_data_listed.clone();
+            return;
+        }
+        self.props.local_data =
+            filter_rows(&self.props.local_data_listed, &self.local_filter, |i| {
+                i.name.as_str()
+            });
+        Self::clamp_selection(
+            &mut self.props.local_table_state,
+            self.props.local_data.len(),
+        );
+    }
+
+    fn clamp_selection(table_state: &mut TableState, len: usize) {
+        match table_state.selected() {
+            Some(_) if len == 0 => table_state.select(None),
+            Some(i) if i >= len => table_state.select(Some(len - 1)),
+            _ => {}
+        }
+    }
+
+    /// Enter keeps the typed filter, Esc clears it, the rows follow every typed character
+    fn handle_filter_key_event(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.filter_input = None,
+            KeyCode::Esc => {
+                self.filter_input = None;
+                self.set_panel_filter(String::new());
+            }
+            _ => {
+                if let Some(input) = self.filter_input.as_mut() {
+                    let _ = input.handle_event(&crossterm::event::Event::Key(key));
+                    let filter = input.value().to_string();
+                    self.set_panel_filter(filter);
+                }
+            }
         }
     }
 
+    fn make_filter_input(&self, input: &Input, scroll: usize) -> Paragraph {
+        Paragraph::new(input.value())
+            .scroll((0, scroll as u16))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Filter by name (Enter keeps, Esc clears)")
+                    .fg(Color::Yellow),
+            )
+    }
+
     pub fn move_up_s3_table_selection(&mut self) {
         let i = match self.props.s3_table_state.selected() {
             Some(i) => {
@@ -563,14 +1425,89 @@ impl FileManagerPage {
         }
     }
 
-    pub fn handle_selected_local_row(&mut self) {
-        if let Some(selected_row) = self
-            .props
-            .local_table_state
-            .selected()
-            .and_then(|index| self.props.local_data.get(index))
-        {
-            if selected_row.is_directory {
+    /// Panel under the pointer, `true` for s3, with the index of the listed row under it
+    fn table_row_at(&self, column: u16, row: u16) -> Option<(bool, Option<usize>)> {
+        let areas = self.mouse_areas.get();
+        let (s3_panel, area, offset, rows) = if contains(areas.s3_table, column, row) {
+            (
+                true,
+                areas.s3_table,
+                areas.s3_offset,
+                self.props.s3_data.len(),
+            )
+        } else if contains(areas.local_table, column, row) {
+            (
+                false,
+                areas.local_table,
+                areas.local_offset,
+                self.props.local_data.len(),
+            )
+        } else {
+            return None;
+        };
+        // the rows start below the border and the header
+        let first_row = area.top() + 2;
+        let index = (row >= first_row && row + 1 < area.bottom())
+            .then(|| offset + (row - first_row) as usize)
+            .filter(|index| *index < rows);
+        Some((s3_panel, index))
+    }
+
+    /// Selects the clicked row and focuses its panel, a second click on the row opens it
+    fn click_table_row(&mut self, column: u16, row: u16, now: Instant) {
+        let Some((s3_panel, index)) = self.table_row_at(column, row) else {
+            return;
+        };
+        self.s3_panel_selected = s3_panel;
+        let Some(index) = index else {
+            self.last_click = None;
+            return;
+        };
+        match s3_panel {
+            true => self.props.s3_table_state.select(Some(index)),
+            false => self.props.local_table_state.select(Some(index)),
+        }
+        let double_click = self.last_click.is_some_and(|(at, panel, clicked)| {
+            panel == s3_panel && clicked == index && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+        });
+        if double_click {
+            self.last_click = None;
+            match s3_panel {
+                true => self.handle_selected_s3_row(),
+                false => self.handle_selected_local_row(),
+            }
+        } else {
+            self.last_click = Some((now, s3_panel, index));
+        }
+    }
+
+    /// Moves the selection of the table under the pointer by `rows`, without wrapping around
+    fn scroll_table(&mut self, column: u16, row: u16, rows: isize) {
+        let (table_state, len) = match self.table_row_at(column, row) {
+            Some((true, _)) => (&mut self.props.s3_table_state, self.props.s3_data.len()),
+            Some((false, _)) => (
+                &mut self.props.local_table_state,
+                self.props.local_data.len(),
+            ),
+            None => return,
+        };
+        if len > 0 {
+            let selected = table_state
+                .selected()
+                .unwrap_or(0)
+                .saturating_add_signed(rows);
+            table_state.select(Some(selected.min(len - 1)));
+        }
+    }
+
+    pub fn handle_selected_local_row(&mut self) {
+        if let Some(selected_row) = self
+            .props
+            .local_table_state
+            .selected()
+            .and_then(|index| self.props.local_data.get(index))
+        {
+            if selected_row.is_directory {
                 let _ = self.action_tx.send(Action::FetchLocalData {
                     path: selected_row.path.clone(),
                 });
@@ -586,61 +1523,612 @@ impl FileManagerPage {
             .selected()
             .and_then(|index| self.props.s3_data.get(index))
         {
-            if selected_row.is_bucket {
-                self.go_into(Some(selected_row.path.clone()), None);
-                let _ = self.action_tx.send(Action::FetchS3Data {
-                    bucket: self.current_state().current_bucket.clone(),
-                    prefix: self.current_state().current_prefix.clone(),
-                });
+            let location = if selected_row.is_bucket {
+                NavigationState::new(Some(selected_row.path.clone()), None)
             } else if selected_row.is_directory {
-                self.go_into(None, Some(selected_row.path.clone())); //get bucket and prefix from previous entries
-                let _ = self.action_tx.send(Action::FetchS3Data {
-                    bucket: self.current_state().current_bucket.clone(),
-                    prefix: self.current_state().current_prefix.clone(),
-                });
+                NavigationState::new(
+                    self.props.s3_location.current_bucket.clone(),
+                    Some(selected_row.path.clone()),
+                )
+            } else {
+                return;
+            };
+            self.navigate_s3(NavigationStep::Into(location));
+        }
+    }
+
+    /// Bucket and key of the highlighted s3 row when it's an object
+    fn highlighted_s3_object(&self) -> Option<(String, String)> {
+        let selected_row = self
+            .props
+            .s3_table_state
+            .selected()
+            .and_then(|index| self.props.s3_data.get(index))
+            .filter(|row| !row.is_bucket && !row.is_directory)?;
+        let bucket = selected_row
+            .bucket
+            .clone()
+            .or(self.props.current_s3_bucket.clone())?;
+        Some((bucket, selected_row.path.clone()))
+    }
+
+    /// Keeps the highlighted s3 object to be pasted with 'P', `delete_source` moves it
+    /// Highlighted row of the s3 panel when it's an object
+    fn highlighted_s3_selected_object(&self) -> Option<S3SelectedItem> {
+        let selected_row = self
+            .props
+            .s3_table_state
+            .selected()
+            .and_then(|index| self.props.s3_data.get(index))
+            .filter(|row| !row.is_bucket && !row.is_directory)?;
+        let mut item = S3SelectedItem::from_s3_data_item(
+            selected_row.clone(),
+            self.props.current_s3_creds.name.clone(),
+            self.props.current_local_path.clone(),
+        );
+        item.bucket = item.bucket.or(self.props.current_s3_bucket.clone());
+        Some(item)
+    }
+
+    fn yank_highlighted_s3_object(&mut self, delete_source: bool) {
+        if let Some(item) = self.highlighted_s3_selected_object() {
+            self.yanked = Some((item, delete_source));
+        }
+    }
+
+    /// Copies the yanked object into the location shown on the s3 panel, a moved object
+    /// is forgotten as it won't be at its source anymore
+    fn paste_yanked_s3_object(&mut self) {
+        let destination_bucket = match self.props.s3_location.current_bucket.clone() {
+            Some(bucket) => bucket,
+            None => {
+                self.show_problem_popup = self.yanked.is_some();
+                return;
+            }
+        };
+        if let Some((item, delete_source)) = self.yanked.clone() {
+            if delete_source {
+                self.yanked = None;
             }
+            let _ = self.action_tx.send(Action::CopyS3Item {
+                item,
+                destination_bucket,
+                destination_prefix: self.props.s3_location.current_prefix.clone(),
+                delete_source,
+            });
         }
     }
 
-    fn go_into(&mut self, bucket: Option<String>, prefix: Option<String>) {
-        if let Some(b) = bucket {
-            self.props.s3_history.clear();
-            self.props
-                .s3_history
-                .push(NavigationState::new(Some(b.clone()), None));
-        }
-        if let Some(p) = prefix {
-            // Navigate into a new directory within the current bucket
-            let current_state = self.current_state().clone();
-            self.props.s3_history.push(NavigationState::new(
-                current_state.current_bucket.clone(),
-                Some(p),
+    fn show_selected_s3_object_metadata(&mut self) {
+        if let Some((bucket, key)) = self.highlighted_s3_object() {
+            let _ = self
+                .action_tx
+                .send(Action::FetchObjectMetadata { bucket, key });
+            self.metadata_popup = Some(MetadataPopup::default());
+            self.metadata_sync = PopupSync::Opening;
+        }
+    }
+
+    /// Account of the s3 panel for the aws cli commands, in the region of the opened bucket
+    fn cli_account(&self) -> CliAccount {
+        let region = self
+            .props
+            .s3_data_listed
+            .first()
+            .filter(|_| self.props.s3_location.current_bucket.is_some())
+            .and_then(|row| row.region.clone());
+        let env = |name: &str| std::env::var(name).ok();
+        CliAccount::new(&self.props.current_s3_creds, env).in_region(region.as_deref())
+    }
+
+    /// Where the bucket typed in the name input is created for the provider of the endpoint
+    fn new_bucket_location(&self) -> BucketLocation {
+        let endpoint = self
+            .props
+            .current_s3_creds
+            .endpoint(|name| std::env::var(name).ok());
+        BucketLocation::new(
+            S3Provider::from_endpoint(endpoint.as_deref()),
+            &self.props.current_s3_creds.default_region,
+        )
+    }
+
+    /// aws cli command listing the location of the s3 panel, or uploading the highlighted local
+    /// file the way 't' does
+    fn panel_cli_command(&self) -> Option<String> {
+        let account = self.cli_account();
+        if self.s3_panel_selected {
+            let location = &self.props.s3_location;
+            return Some(account.list_objects(
+                location.current_bucket.as_deref(),
+                location.current_prefix.as_deref(),
             ));
         }
+        let bucket = self.props.current_s3_bucket.clone()?;
+        let row = self
+            .props
+            .local_table_state
+            .selected()
+            .and_then(|index| self.props.local_data.get(index))
+            .filter(|row| !row.is_directory)?;
+        let item = LocalSelectedItem::new(
+            row.name.clone(),
+            row.path.clone(),
+            false,
+            bucket.clone(),
+            "/".to_string(),
+            self.props.current_s3_creds.name.clone(),
+            None,
+        );
+        Some(account.put_object(&bucket, &item.destination_key(), &item.path))
+    }
+
+    /// aws cli command downloading the object of the metadata popup into the download directory
+    fn metadata_cli_command(&self) -> Option<String> {
+        let metadata = self.metadata_popup.as_ref()?.viewed_object()?;
+        let local_path = Path::new(&self.props.download_dir).join(&metadata.key);
+        Some(self.cli_account().get_object(
+            &metadata.bucket,
+            &metadata.key,
+            &local_path.to_string_lossy(),
+        ))
+    }
+
+    /// Copies the text to the clipboard, the popup shows it when it cannot be copied
+    fn copy_text(&mut self, text: String) {
+        if copy_to_clipboard(&text, self.props.osc52_clipboard) {
+            let _ = self.action_tx.send(Action::CopiedToClipboard { text });
+        } else {
+            self.copy_fallback = Some(text);
+        }
+    }
+
+    /// Copies the text read from the object, the message names the object instead of its text
+    fn copy_object_text(&mut self, location: String, text: String) {
+        if copy_to_clipboard(&text, self.props.osc52_clipboard) {
+            let _ = self
+                .action_tx
+                .send(Action::CopiedToClipboard { text: location });
+        } else {
+            self.copy_fallback = Some(text);
+        }
     }
 
-    fn go_up(&mut self) {
-        if !self.props.s3_history.is_empty() {
-            self.props.s3_history.pop();
+    fn copy_highlighted_object_contents(&mut self) {
+        if let Some((bucket, key)) = self.highlighted_s3_object() {
+            let _ = self
+                .action_tx
+                .send(Action::CopyS3ObjectText { bucket, key });
         }
     }
 
-    fn current_state(&self) -> &NavigationState {
+    /// Path of the highlighted local row when it's a file
+    fn highlighted_local_file(&self) -> Option<String> {
         self.props
-            .s3_history
-            .last()
-            .unwrap_or(&self.default_navigation_state)
+            .local_table_state
+            .selected()
+            .and_then(|index| self.props.local_data.get(index))
+            .filter(|row| !row.is_directory)
+            .map(|row| row.path.clone())
+    }
+
+    /// Values of the highlighted row of the focused panel for the placeholders of the custom
+    /// commands, the name of a highlighted bucket is its `{bucket}`
+    fn highlighted_row_values(&self) -> RowValues {
+        let account = self.props.current_s3_creds.name.clone();
+        if !self.s3_panel_selected {
+            let local_path = self
+                .props
+                .local_table_state
+                .selected()
+                .and_then(|index| self.props.local_data.get(index))
+                .map(|row| row.path.clone());
+            return RowValues {
+                local_path,
+                account,
+                ..RowValues::default()
+            };
+        }
+        let row = self
+            .props
+            .s3_table_state
+            .selected()
+            .and_then(|index| self.props.s3_data.get(index));
+        match row {
+            Some(row) if row.is_bucket => RowValues {
+                bucket: Some(row.name.clone()),
+                account,
+                ..RowValues::default()
+            },
+            Some(row) => RowValues {
+                bucket: row.bucket.clone().or(self.props.current_s3_bucket.clone()),
+                key: Some(row.path.clone()),
+                account,
+                ..RowValues::default()
+            },
+            None => RowValues {
+                account,
+                ..RowValues::default()
+            },
+        }
+    }
+
+    /// 'j' and 'k' pick the account to run the transfers of the missing account with, Enter
+    /// uses it and 'd' drops the transfers
+    fn handle_missing_account_key_event(&mut self, key: KeyEvent) {
+        let Some(missing) = self.props.missing_account.clone() else {
+            return;
+        };
+        let replacement = match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                let last = missing.accounts.len().saturating_sub(1);
+                self.remap_choice = (self.remap_choice + 1).min(last);
+                return;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.remap_choice = self.remap_choice.saturating_sub(1);
+                return;
+            }
+            KeyCode::Enter => match missing.accounts.get(self.remap_choice) {
+                Some(account) => Some(account.clone()),
+                None => return,
+            },
+            KeyCode::Char('d') => None,
+            _ => return,
+        };
+        self.remap_choice = 0;
+        self.props.missing_account = None;
+        let _ = self.action_tx.send(Action::RemapRestoredAccount {
+            account: missing.name,
+            replacement,
+        });
+    }
+
+    /// 'r' runs the restored transfers, Enter or Esc keeps them selected and 'd' discards them
+    fn handle_restored_transfers_key_event(&mut self, key: KeyEvent) {
+        let actions = match key.code {
+            KeyCode::Char('r') => vec![Action::ResumeRestoredTransfers, Action::RunTransfers],
+            KeyCode::Enter | KeyCode::Esc => vec![Action::ResumeRestoredTransfers],
+            KeyCode::Char('d') => vec![Action::DiscardRestoredTransfers],
+            _ => return,
+        };
+        self.props.restored_transfers = None;
+        for action in actions {
+            let _ = self.action_tx.send(action);
+        }
+    }
+
+    /// 'j' and 'k' pick the interrupted upload, 'r' resumes it, 'a' aborts it and Esc keeps
+    /// all of them for the next start
+    fn handle_resumable_uploads_key_event(&mut self, key: KeyEvent) {
+        let uploads = &self.props.resumable_uploads;
+        let action = match (key.code, uploads.get(self.resume_choice)) {
+            (KeyCode::Char('j') | KeyCode::Down, _) => {
+                self.resume_choice = (self.resume_choice + 1).min(uploads.len().saturating_sub(1));
+                return;
+            }
+            (KeyCode::Char('k') | KeyCode::Up, _) => {
+                self.resume_choice = self.resume_choice.saturating_sub(1);
+                return;
+            }
+            (KeyCode::Char('r'), Some(upload)) => Action::ResumeUpload(upload.clone()),
+            (KeyCode::Char('a'), Some(upload)) => Action::AbortUpload(upload.clone()),
+            (KeyCode::Esc, _) => Action::PostponeResumableUploads,
+            _ => return,
+        };
+        if matches!(action, Action::PostponeResumableUploads) {
+            self.props.resumable_uploads.clear();
+        } else {
+            self.props.resumable_uploads.remove(self.resume_choice);
+        }
+        self.resume_choice = self
+            .resume_choice
+            .min(self.props.resumable_uploads.len().saturating_sub(1));
+        let _ = self.action_tx.send(action);
+    }
+
+    fn compare_highlighted_pair(&mut self) {
+        match (self.highlighted_s3_object(), self.highlighted_local_file()) {
+            (Some((bucket, key)), Some(local_path)) => {
+                let _ = self.action_tx.send(Action::CompareObjectWithLocal {
+                    bucket,
+                    key,
+                    local_path,
+                    full_hash: false,
+                });
+                self.props.object_comparison_loading = true;
+                self.comparison_sync = PopupSync::Opening;
+            }
+            _ => self.show_compare_hint = true,
+        }
+    }
+
+    fn comparison_popup_open(&self) -> bool {
+        self.show_compare_hint || self.comparison_in_props()
+    }
+
+    fn comparison_in_props(&self) -> bool {
+        self.props.object_comparison_loading
+            || self.props.object_comparison.is_some()
+            || self.props.object_comparison_error.is_some()
+    }
+
+    fn close_comparison_popup(&mut self) {
+        if !self.show_compare_hint {
+            let _ = self.action_tx.send(Action::CloseObjectComparison);
+            self.comparison_sync = PopupSync::Closing;
+        }
+        self.show_compare_hint = false;
+        self.props.object_comparison = None;
+        self.props.object_comparison_loading = false;
+        self.props.object_comparison_error = None;
+    }
+
+    fn make_comparison_popup(&self) -> Paragraph {
+        let mut lines = Vec::new();
+        if self.show_compare_hint {
+            lines.push(Line::from(
+                "Highlight an object in the s3 panel and a file in the local panel to compare them",
+            ));
+        } else if self.props.object_comparison_loading {
+            lines.push(Line::from("Comparing..."));
+        } else if let Some(error) = &self.props.object_comparison_error {
+            lines.push(Line::styled(
+                format!("Comparison failed: {}", error),
+                Style::default().fg(Color::Red),
+            ));
+        } else if let Some(comparison) = &self.props.object_comparison {
+            let verdict_color = match comparison.verdict {
+                ComparisonVerdict::Identical => Color::Green,
+                ComparisonVerdict::UndeterminedMultipartEtag => Color::Yellow,
+                _ => Color::Red,
+            };
+            lines.push(Line::from(format!(
+                "s3://{}/{}",
+                comparison.bucket, comparison.key
+            )));
+            lines.push(Line::from(comparison.local_path.clone()));
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                comparison.verdict_description(),
+                Style::default().fg(verdict_color),
+            ));
+            lines.push(Line::from(format!(
+                "Checked in {:.1}s",
+                comparison.elapsed.as_secs_f64()
+            )));
+        }
+        let mut hints = vec![
+            Span::raw("|"),
+            Span::styled("close", Style::default().fg(Color::Yellow)),
+            Span::raw("("),
+            Span::styled(
+                "Esc",
+                Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+            ),
+            Span::raw(")|"),
+        ];
+        if self
+            .props
+            .object_comparison
+            .as_ref()
+            .is_some_and(|c| c.can_hash_fully())
+        {
+            hints.extend([
+                Span::styled("full hash", Style::default().fg(Color::Yellow)),
+                Span::raw("("),
+                Span::styled(
+                    "f",
+                    Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+                ),
+                Span::raw(")|"),
+            ]);
+        }
+        Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(
+                    Title::from(Line::from(hints))
+                        .alignment(Alignment::Right)
+                        .position(block::Position::Bottom),
+                )
+                .title(
+                    Title::from(Line::from("| Compare with local file |"))
+                        .alignment(Alignment::Left)
+                        .position(block::Position::Top),
+                ),
+        )
+    }
+
+    fn preview_highlighted_object(&mut self) {
+        if let Some((bucket, key)) = self.highlighted_s3_object() {
+            let _ = self.action_tx.send(Action::PreviewS3Item { bucket, key });
+            self.props.object_preview = None;
+            self.props.object_preview_loading = true;
+            self.preview_sync = PopupSync::Opening;
+            self.preview_scroll = 0;
+        }
+    }
+
+    fn preview_popup_open(&self) -> bool {
+        self.props.object_preview_loading
+            || self.props.object_preview.is_some()
+            || self.props.object_preview_error.is_some()
+    }
+
+    fn close_preview_popup(&mut self) {
+        let _ = self.action_tx.send(Action::ClosePreview);
+        self.preview_sync = PopupSync::Closing;
+        self.props.object_preview = None;
+        self.props.object_preview_loading = false;
+        self.props.object_preview_error = None;
+    }
+
+    /// Moves the preview by `lines`, keeping at least its last line in the popup
+    fn scroll_preview(&mut self, lines: i32) {
+        let last_line = self
+            .props
+            .object_preview
+            .as_ref()
+            .map_or(0, |preview| preview.line_count().saturating_sub(1));
+        let scroll = (self.preview_scroll as i32 + lines).clamp(0, last_line as i32);
+        self.preview_scroll = u16::try_from(scroll).unwrap_or(u16::MAX);
+    }
+
+    fn make_preview_popup(&self) -> Paragraph {
+        let mut title = "| Preview |".to_string();
+        let mut size_info = String::new();
+        let lines: Vec<Line> = if self.props.object_preview_loading {
+            vec![Line::from("Loading...")]
+        } else if let Some(error) = &self.props.object_preview_error {
+            vec![Line::styled(
+                format!("Preview failed: {}", error),
+                Style::default().fg(Color::Red),
+            )]
+        } else if let Some(preview) = &self.props.object_preview {
+            title = format!("| s3://{}/{} |", preview.bucket, preview.key);
+            if preview.truncated() {
+                size_info = format!(
+                    "|first {} of {}|",
+                    format_bytes(preview.read_bytes),
+                    format_bytes(preview.object_size)
+                );
+            }
+            match &preview.content {
+                PreviewContent::Text(text) => text.lines().map(Line::from).collect(),
+                PreviewContent::Binary => vec![Line::styled(
+                    "Binary file, preview not available",
+                    Style::default().fg(Color::Yellow),
+                )],
+            }
+        } else {
+            Vec::new()
+        };
+        let hints = vec![
+            Span::raw("|"),
+            Span::styled("scroll", Style::default().fg(Color::Yellow)),
+            Span::raw("("),
+            Span::styled(
+                "j/k",
+                Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+            ),
+            Span::raw(")|"),
+            Span::styled("close", Style::default().fg(Color::Yellow)),
+            Span::raw("("),
+            Span::styled(
+                "Esc",
+                Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+            ),
+            Span::raw(")|"),
+        ];
+        Paragraph::new(lines)
+            .scroll((self.preview_scroll, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(
+                        Title::from(Line::from(hints))
+                            .alignment(Alignment::Right)
+                            .position(block::Position::Bottom),
+                    )
+                    .title(
+                        Title::from(Line::from(size_info))
+                            .alignment(Alignment::Left)
+                            .position(block::Position::Bottom),
+                    )
+                    .title(
+                        Title::from(Line::from(title))
+                            .alignment(Alignment::Left)
+                            .position(block::Position::Top),
+                    ),
+            )
+    }
+
+    fn delete_lock_warning(&self) -> Option<String> {
+        let (bucket, key, info) = self.props.delete_lock_info.as_ref()?;
+        if !self.s3_panel_selected
+            || self.highlighted_s3_object() != Some((bucket.clone(), key.clone()))
+        {
+            return None;
+        }
+        info.delete_warning()
+    }
+
+    fn sync_metadata_popup(&mut self, state: &State) {
+        let in_state = state.object_metadata_loading
+            || state.object_metadata.is_some()
+            || state.object_metadata_error.is_some();
+        if !self.metadata_sync.resolve(in_state) {
+            self.metadata_popup = None;
+            return;
+        }
+        if !in_state {
+            return;
+        }
+        let popup = self
+            .metadata_popup
+            .get_or_insert_with(MetadataPopup::default);
+        popup.refresh(
+            state.object_metadata.as_ref(),
+            state.object_metadata_loading,
+            state.object_metadata_error.as_ref(),
+        );
+    }
+
+    /// Keeps the popups opened or closed with a key as they are until the state store handles
+    /// their actions, states broadcast before that would show them the other way
+    fn sync_requested_popups(&mut self) {
+        let preview_in_state = self.preview_popup_open();
+        if self.preview_sync.resolve(preview_in_state) != preview_in_state {
+            self.props.object_preview = None;
+            self.props.object_preview_error = None;
+            self.props.object_preview_loading = !preview_in_state;
+        }
+        let comparison_in_state = self.comparison_in_props();
+        if self.comparison_sync.resolve(comparison_in_state) != comparison_in_state {
+            self.props.object_comparison = None;
+            self.props.object_comparison_error = None;
+            self.props.object_comparison_loading = !comparison_in_state;
+        }
+        self.props.s3_list_recursive_loading = self
+            .recursive_listing_sync
+            .resolve(self.props.s3_list_recursive_loading);
+    }
+
+    /// Shows the location of a finished jump and highlights the jumped to item when it's listed,
+    /// the state records the jump in the s3 history so 'Esc' returns to the previous location
+    fn apply_focus(&mut self, focus: &FileManagerFocus) {
+        self.applied_focus = focus.id;
+        let item_name = focus.target.item_name();
+        match &focus.target {
+            JumpTarget::Local { .. } => {
+                self.s3_panel_selected = false;
+                let row = item_name
+                    .and_then(|name| self.props.local_data.iter().position(|i| i.name == name));
+                if !self.props.local_data.is_empty() {
+                    self.props.local_table_state.select(Some(row.unwrap_or(0)));
+                }
+            }
+            JumpTarget::S3 { .. } => {
+                self.s3_panel_selected = true;
+                let row = item_name
+                    .and_then(|name| self.props.s3_data.iter().position(|i| i.name == name));
+                if !self.props.s3_data.is_empty() {
+                    self.props.s3_table_state.select(Some(row.unwrap_or(0)));
+                }
+            }
+        }
     }
 
     pub fn handle_go_back_local(&mut self) {
         let _ = self.action_tx.send(Action::MoveBackLocal);
     }
 
-    pub fn handle_go_back_s3(&mut self) {
-        self.go_up();
-        let _ = self.action_tx.send(Action::FetchS3Data {
-            bucket: self.current_state().current_bucket.clone(),
-            prefix: self.current_state().current_prefix.clone(),
+    /// Moves the s3 panel through the history of the current account
+    fn navigate_s3(&mut self, step: NavigationStep) {
+        let _ = self.action_tx.send(Action::NavigateS3 {
+            step,
+            row: self.props.s3_table_state.selected(),
         });
     }
 
@@ -652,20 +2140,17 @@ impl FileManagerPage {
             .and_then(|index| self.props.s3_data.get(index))
         {
             let sr = selected_row.clone();
-            let cc = self.props.current_s3_creds.clone();
-            let creds = FileCredential {
-                default_region: sr.clone().region.unwrap_or(cc.default_region.clone()),
-                ..cc
-            };
+            let account = self.props.current_s3_creds.name.clone();
             let selected_item = S3SelectedItem::from_s3_data_item(
                 sr.clone(),
-                creds,
-                self.props.current_local_path.clone(),
+                account,
+                self.props.download_dir.clone(),
             );
             if !self.props.s3_selected_items.contains(&selected_item) {
                 if selected_item.is_bucket || selected_item.is_directory {
                     self.show_download_confirmation = true;
                     self.props.s3_list_recursive_loading = true;
+                    self.recursive_listing_sync = PopupSync::Opening;
                     let _ = self.action_tx.send(Action::ListS3DataRecursiveForItem {
                         item: selected_item,
                     });
@@ -690,12 +2175,8 @@ impl FileManagerPage {
             .and_then(|index| self.props.s3_data.get(index))
         {
             let sr = selected_row.clone();
-            let cc = self.props.current_s3_creds.clone();
-            let creds = FileCredential {
-                default_region: sr.clone().region.unwrap_or(cc.default_region.clone()),
-                ..cc
-            };
-            let destination_dir = self.props.current_local_path.clone();
+            let account = self.props.current_s3_creds.name.clone();
+            let destination_dir = self.props.download_dir.clone();
             let children = self
                 .props
                 .s3_data_full_list
@@ -703,15 +2184,15 @@ impl FileManagerPage {
                 .map(|i| {
                     S3SelectedItem::from_s3_data_item(
                         i.clone(),
-                        creds.clone(),
+                        account.clone(),
                         destination_dir.clone(),
                     )
                 })
                 .collect();
             let selected_item = S3SelectedItem::from_s3_data_item_with_children(
                 sr.clone(),
-                creds.clone(),
-                self.props.current_local_path.clone(),
+                account.clone(),
+                self.props.download_dir.clone(),
                 children,
             );
             if !self.props.s3_selected_items.contains(&selected_item) {
@@ -734,11 +2215,7 @@ impl FileManagerPage {
             .and_then(|index| self.props.s3_data.get(index))
         {
             let sr = selected_row.clone();
-            let cc = self.props.current_s3_creds.clone();
-            let creds = FileCredential {
-                default_region: sr.clone().region.unwrap_or(cc.default_region.clone()),
-                ..cc
-            };
+            let account = self.props.current_s3_creds.name.clone();
             let destination_dir = self.props.current_local_path.clone();
             let children = self
                 .props
@@ -747,14 +2224,14 @@ impl FileManagerPage {
                 .map(|i| {
                     S3SelectedItem::from_s3_data_item(
                         i.clone(),
-                        creds.clone(),
+                        account.clone(),
                         destination_dir.clone(),
                     )
                 })
                 .collect();
             let selected_item = S3SelectedItem::from_s3_data_item_with_children(
                 sr.clone(),
-                creds.clone(),
+                account.clone(),
                 self.props.current_local_path.clone(),
                 children,
             );
@@ -784,7 +2261,7 @@ impl FileManagerPage {
                     sr.is_directory,
                     selected_bucket,
                     destination_path,
-                    self.props.current_s3_creds.clone(),
+                    self.props.current_s3_creds.name.clone(),
                     None,
                 );
                 if !self.props.local_selected_items.contains(&selected_item) {
@@ -810,19 +2287,16 @@ impl FileManagerPage {
             .and_then(|index| self.props.s3_data.get(index))
         {
             let sr = selected_row.clone();
-            let cc = self.props.current_s3_creds.clone();
-            let creds = FileCredential {
-                default_region: sr.clone().region.unwrap_or(cc.default_region.clone()),
-                ..cc
-            };
+            let account = self.props.current_s3_creds.name.clone();
             let selected_item = S3SelectedItem::from_s3_data_item(
                 sr.clone(),
-                creds.clone(),
+                account.clone(),
                 self.props.current_local_path.clone(),
             );
-            if selected_item.is_directory {
+            if selected_item.is_directory || selected_item.is_bucket {
                 self.show_delete_multiple_confirmation = true;
                 self.props.s3_list_recursive_loading = true;
+                self.recursive_listing_sync = PopupSync::Opening;
                 let _ = self.action_tx.send(Action::ListS3DataRecursiveForItem {
                     item: selected_item,
                 });
@@ -848,7 +2322,7 @@ impl FileManagerPage {
                 sr.is_directory,
                 "".to_string(),
                 self.props.current_s3_path.clone(),
-                self.props.current_s3_creds.clone(),
+                self.props.current_s3_creds.name.clone(),
                 None,
             );
             let _ = self.action_tx.send(Action::DeleteLocalItem {
@@ -857,6 +2331,45 @@ impl FileManagerPage {
         }
     }
 
+    /// Question of the confirmation popup, directories in the s3 selection are counted
+    /// by the objects listed when they were selected
+    fn delete_selected_question(&self) -> String {
+        let objects: usize = group_s3_deletions(&self.props.s3_selected_items)
+            .iter()
+            .map(|deletion| deletion.keys.len())
+            .sum();
+        let local_items = self.props.local_selected_items.len();
+        format!(
+            "Are you sure you want to delete the {} selected items? \
+             ({} s3 objects, {} local files and directories)",
+            objects + local_items,
+            objects,
+            local_items
+        )
+    }
+
+    /// Question of the confirmation popup of a directory or a bucket, the objects are counted
+    /// by the recursive listing
+    fn recursive_delete_question(&self) -> String {
+        let objects = self.props.s3_data_full_list.len();
+        match self
+            .props
+            .s3_table_state
+            .selected()
+            .and_then(|index| self.props.s3_data.get(index))
+        {
+            Some(row) if row.is_bucket => format!(
+                "{} objects in the bucket {} will be deleted, then the bucket itself. Proceed?",
+                objects, row.name
+            ),
+            Some(row) => format!(
+                "{} objects under {} will be deleted. Proceed?",
+                objects, row.path
+            ),
+            None => format!("You have selected {} items to delete. Proceed?", objects),
+        }
+    }
+
     fn send_clear_delete_errors_message(&mut self) {
         let _ = self.action_tx.send(Action::ClearDeletionErrors);
         self.show_delete_error = false;
@@ -889,13 +2402,47 @@ impl Component for FileManagerPage {
             props: Props::from(state),
             show_problem_popup: false,
             show_bucket_input: false,
+            show_folder_input: false,
+            folder_name_error: None,
+            show_go_to_input: false,
+            go_to_error: None,
+            renaming: None,
+            rename_error: None,
+            rename_conflict: None,
+            renaming_local: None,
+            local_rename_conflict: None,
             show_delete_confirmation: false,
+            show_delete_selected_confirmation: false,
             show_delete_multiple_confirmation: false,
             show_download_confirmation: false,
             show_delete_error: false,
             s3_panel_selected: true,
-            default_navigation_state: NavigationState::new(None, None),
             input: Input::default().with_value(String::from("")),
+            hints_shown_at: Instant::now(),
+            applied_focus: 0,
+            applied_row_restore: 0,
+            applied_create_bucket_result: state.create_bucket_result,
+            applied_create_folder_result: state.create_folder_result,
+            applied_go_to_path_result: state.go_to_path_result,
+            applied_rename_result: state.rename_result,
+            applied_delete_result: state.delete_result,
+            applied_copied_object_result: state.copied_object_result,
+            metadata_popup: None,
+            metadata_sync: PopupSync::Synced,
+            preview_sync: PopupSync::Synced,
+            comparison_sync: PopupSync::Synced,
+            recursive_listing_sync: PopupSync::Synced,
+            show_compare_hint: false,
+            preview_scroll: 0,
+            yanked: None,
+            s3_filter: String::new(),
+            local_filter: String::new(),
+            filter_input: None,
+            copy_fallback: None,
+            remap_choice: 0,
+            resume_choice: 0,
+            mouse_areas: Cell::new(MouseAreas::default()),
+            last_click: None,
         }
             .move_with_state(state)
     }
@@ -905,36 +2452,172 @@ impl Component for FileManagerPage {
             Self: Sized,
     {
         let new_props = Props::from(state);
-        FileManagerPage {
-            show_delete_error: state.s3_delete_state.is_some()
-                || state.local_delete_state.is_some(),
-            show_bucket_input: state.create_bucket_state.is_some(),
+        // the filters belong to the listed location
+        let s3_moved = new_props.s3_location != self.props.s3_location
+            || new_props.current_s3_creds.name != self.props.current_s3_creds.name;
+        let local_moved = new_props.current_local_path != self.props.current_local_path;
+        let filter_moved = match self.s3_panel_selected {
+            true => s3_moved,
+            false => local_moved,
+        };
+        let mut page = FileManagerPage {
+            s3_filter: if s3_moved {
+                String::new()
+            } else {
+                self.s3_filter.clone()
+            },
+            local_filter: if local_moved {
+                String::new()
+            } else {
+                self.local_filter.clone()
+            },
+            filter_input: if filter_moved {
+                None
+            } else {
+                self.filter_input.clone()
+            },
             props: Props {
-                s3_history: self.props.s3_history.clone(),
                 s3_table_state: self.props.s3_table_state.clone(),
                 local_table_state: self.props.local_table_state.clone(),
                 ..new_props
             },
             ..self
+        };
+        page.apply_s3_filter();
+        page.apply_local_filter();
+        // only the results of the operations open these popups, every other state keeps them
+        if state.create_bucket_result > page.applied_create_bucket_result {
+            page.applied_create_bucket_result = state.create_bucket_result;
+            page.show_bucket_input |= state.create_bucket_state.is_some();
         }
-    }
-
-    fn name(&self) -> &str {
-        "File Manager"
-    }
-
-    fn handle_key_event(&mut self, key: KeyEvent) {
-        if key.kind != KeyEventKind::Press {
-            return;
+        if state.create_folder_result > page.applied_create_folder_result {
+            page.applied_create_folder_result = state.create_folder_result;
+            page.show_folder_input |= state.create_folder_state.is_some();
         }
-        if self.show_bucket_input {
-            match key.code {
-                KeyCode::Enter => {
-                    let _ = self.action_tx.send(Action::CreateBucket {
-                        name: self.input.value().to_string(),
-                    });
-                    self.show_bucket_input = false;
+        if state.go_to_path_result > page.applied_go_to_path_result {
+            page.applied_go_to_path_result = state.go_to_path_result;
+            page.go_to_error = state.go_to_path_error.clone();
+            page.show_go_to_input = page.go_to_error.is_some();
+        }
+        if state.rename_result > page.applied_rename_result {
+            page.applied_rename_result = state.rename_result;
+            page.rename_conflict = state.rename_conflict.clone();
+            page.local_rename_conflict = state.local_rename_conflict.clone();
+        }
+        if state.delete_result > page.applied_delete_result {
+            page.applied_delete_result = state.delete_result;
+            page.show_delete_error =
+                state.s3_delete_state.is_some() || state.local_delete_state.is_some();
+        }
+        if state.copied_object_result > page.applied_copied_object_result {
+            page.applied_copied_object_result = state.copied_object_result;
+            if let Some((location, text)) = state.copied_object_text.clone() {
+                page.copy_object_text(location, text);
+            }
+        }
+        page.sync_requested_popups();
+        if let Some(focus) = state
+            .file_manager_focus
+            .as_ref()
+            .filter(|f| f.id > page.applied_focus)
+        {
+            page.apply_focus(focus);
+        }
+        if let Some((id, row)) = state
+            .s3_row_restore
+            .filter(|(id, _)| *id > page.applied_row_restore)
+        {
+            page.applied_row_restore = id;
+            if !page.props.s3_data.is_empty() {
+                let row = row.min(page.props.s3_data.len() - 1);
+                page.props.s3_table_state.select(Some(row));
+            }
+        }
+        page.sync_metadata_popup(state);
+        page
+    }
+
+    fn name(&self) -> &str {
+        "File Manager"
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if self.copy_fallback.is_some() {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                self.copy_fallback = None;
+            }
+        } else if self.props.credentials_error.is_some() {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                let _ = self.action_tx.send(Action::ClearCredentialsError);
+                self.props.credentials_error = None;
+            }
+        } else if self.props.missing_account.is_some() {
+            self.handle_missing_account_key_event(key);
+        } else if self.props.restored_transfers.is_some() {
+            self.handle_restored_transfers_key_event(key);
+        } else if !self.props.resumable_uploads.is_empty() {
+            self.handle_resumable_uploads_key_event(key);
+        } else if let Some(command) = self.metadata_cli_command().filter(|_| {
+            self.props.key_bindings.action(&key, KeyScope::FileManager) == Some(KeyAction::AwsCli)
+        }) {
+            self.copy_text(command);
+        } else if let Some(popup) = self.metadata_popup.as_mut() {
+            if let Some(action) = popup.handle_key_event(key) {
+                if action == Action::CloseObjectMetadata {
+                    self.metadata_popup = None;
+                    self.metadata_sync = PopupSync::Closing;
                 }
+                let _ = self.action_tx.send(action);
+            }
+        } else if self.comparison_popup_open() {
+            match key.code {
+                KeyCode::Esc => self.close_comparison_popup(),
+                KeyCode::Char('f') => {
+                    if let Some(comparison) = self
+                        .props
+                        .object_comparison
+                        .clone()
+                        .filter(|c| c.can_hash_fully())
+                    {
+                        let _ = self.action_tx.send(Action::CompareObjectWithLocal {
+                            bucket: comparison.bucket,
+                            key: comparison.key,
+                            local_path: comparison.local_path,
+                            full_hash: true,
+                        });
+                        self.props.object_comparison = None;
+                        self.props.object_comparison_loading = true;
+                    }
+                }
+                _ => {}
+            }
+        } else if self.preview_popup_open() {
+            match key.code {
+                KeyCode::Esc => self.close_preview_popup(),
+                KeyCode::Char('j') | KeyCode::Down => self.scroll_preview(1),
+                KeyCode::Char('k') | KeyCode::Up => self.scroll_preview(-1),
+                KeyCode::PageDown => self.scroll_preview(PREVIEW_PAGE_JUMP as i32),
+                KeyCode::PageUp => self.scroll_preview(-(PREVIEW_PAGE_JUMP as i32)),
+                _ => {
+                    let action = self.props.key_bindings.action(&key, KeyScope::FileManager);
+                    if action == Some(KeyAction::CopyContents) {
+                        if let Some(preview) = &self.props.object_preview {
+                            let _ = self.action_tx.send(Action::CopyS3ObjectText {
+                                bucket: preview.bucket.clone(),
+                                key: preview.key.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        } else if self.filter_input.is_some() {
+            self.handle_filter_key_event(key);
+        } else if self.show_bucket_input {
+            match key.code {
+                KeyCode::Enter => self.submit_bucket_name(),
                 KeyCode::Esc => {
                     self.show_bucket_input = false;
                     self.send_clear_delete_errors_message();
@@ -943,6 +2626,83 @@ impl Component for FileManagerPage {
                     let _ = self.input.handle_event(&crossterm::event::Event::Key(key));
                 }
             }
+        } else if self.show_folder_input {
+            match key.code {
+                KeyCode::Enter => self.submit_folder_name(),
+                KeyCode::Esc => {
+                    self.show_folder_input = false;
+                    self.folder_name_error = None;
+                    self.send_clear_delete_errors_message();
+                }
+                _ => {
+                    let _ = self.input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            }
+        } else if self.show_go_to_input {
+            match key.code {
+                KeyCode::Enter => self.submit_go_to(),
+                KeyCode::Esc => {
+                    self.show_go_to_input = false;
+                    self.go_to_error = None;
+                }
+                _ => {
+                    let _ = self.input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            }
+        } else if self.renaming.is_some() {
+            match key.code {
+                KeyCode::Enter => self.submit_rename(),
+                KeyCode::Esc => {
+                    self.renaming = None;
+                    self.rename_error = None;
+                }
+                _ => {
+                    let _ = self.input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            }
+        } else if self.rename_conflict.is_some() {
+            match key.code {
+                KeyCode::Enter => {
+                    if let Some((item, new_key)) = self.rename_conflict.take() {
+                        let _ = self.action_tx.send(Action::RenameS3Item {
+                            item,
+                            new_key,
+                            overwrite: true,
+                        });
+                    }
+                }
+                KeyCode::Esc => {
+                    self.rename_conflict = None;
+                }
+                _ => {}
+            }
+        } else if self.renaming_local.is_some() {
+            match key.code {
+                KeyCode::Enter => self.submit_local_rename(),
+                KeyCode::Esc => {
+                    self.renaming_local = None;
+                    self.rename_error = None;
+                }
+                _ => {
+                    let _ = self.input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            }
+        } else if self.local_rename_conflict.is_some() {
+            match key.code {
+                KeyCode::Enter => {
+                    if let Some((item, new_name)) = self.local_rename_conflict.take() {
+                        let _ = self.action_tx.send(Action::RenameLocalItem {
+                            item,
+                            new_name,
+                            overwrite: true,
+                        });
+                    }
+                }
+                KeyCode::Esc => {
+                    self.local_rename_conflict = None;
+                }
+                _ => {}
+            }
         } else if self.show_delete_confirmation {
             match key.code {
                 KeyCode::Enter => {
@@ -962,6 +2722,17 @@ impl Component for FileManagerPage {
                 }
                 _ => {}
             }
+        } else if self.show_delete_selected_confirmation {
+            match key.code {
+                KeyCode::Enter => {
+                    let _ = self.action_tx.send(Action::DeleteSelectedItems);
+                    self.show_delete_selected_confirmation = false;
+                }
+                KeyCode::Esc => {
+                    self.show_delete_selected_confirmation = false;
+                }
+                _ => {}
+            }
         } else if self.show_delete_error {
             match key.code {
                 KeyCode::Enter => {
@@ -972,10 +2743,15 @@ impl Component for FileManagerPage {
                 }
                 _ => {}
             }
-        } else if self.show_download_confirmation && !self.props.s3_list_recursive_loading {
+        } else if self.show_download_confirmation {
             match key.code {
+                // the keys wait for the listing of the selection
+                _ if self.props.s3_list_recursive_loading => {}
                 KeyCode::Enter => {
-                    self.finish_recursive_transfer_from_s3_to_local();
+                    // an empty listing leaves nothing to select
+                    if !self.props.s3_data_full_list.is_empty() {
+                        self.finish_recursive_transfer_from_s3_to_local();
+                    }
                     self.show_download_confirmation = false;
                 }
                 KeyCode::Esc => {
@@ -983,10 +2759,15 @@ impl Component for FileManagerPage {
                 }
                 _ => {}
             }
-        } else if self.show_delete_multiple_confirmation && !self.props.s3_list_recursive_loading {
+        } else if self.show_delete_multiple_confirmation {
             match key.code {
+                // the keys wait for the listing of the selection
+                _ if self.props.s3_list_recursive_loading => {}
                 KeyCode::Enter => {
-                    self.finish_recursive_delete_from_s3_to_local();
+                    // a selection over the limit is not deleted
+                    if self.props.selection_limit_error.is_none() {
+                        self.finish_recursive_delete_from_s3_to_local();
+                    }
                     self.show_delete_multiple_confirmation = false;
                 }
                 KeyCode::Esc => {
@@ -994,6 +2775,18 @@ impl Component for FileManagerPage {
                 }
                 _ => {}
             }
+        } else if let Some(action) = self.props.key_bindings.action(&key, KeyScope::FileManager) {
+            self.handle_key_action(action);
+        } else if let Some(command) = self
+            .props
+            .custom_commands
+            .iter()
+            .find(|c| c.key.matches(&key) && c.rows.includes(self.s3_panel_selected))
+        {
+            let _ = self.action_tx.send(Action::RunCustomCommand {
+                command: command.clone(),
+                row: self.highlighted_row_values(),
+            });
         } else {
             match key.code {
                 KeyCode::Char('j') | KeyCode::Down => match self.s3_panel_selected {
@@ -1004,76 +2797,97 @@ impl Component for FileManagerPage {
                     true => self.move_up_s3_table_selection(),
                     false => self.move_up_local_table_selection(),
                 },
-                KeyCode::Char('c') => {
-                    if self.s3_panel_selected {
-                        self.input.reset();
-                        self.show_bucket_input = true;
-                    }
-                }
                 KeyCode::Enter => match self.s3_panel_selected {
                     true => self.handle_selected_s3_row(),
                     false => self.handle_selected_local_row(),
                 },
-                KeyCode::Esc => match self.s3_panel_selected {
-                    true => {
-                        if !self.props.s3_loading {
-                            self.handle_go_back_s3()
-                        }
-                    }
-                    false => {
-                        if self.show_problem_popup {
-                            self.show_problem_popup = false;
-                        } else {
-                            self.handle_go_back_local()
-                        }
-                    }
-                },
-                KeyCode::Delete | KeyCode::Backspace => {
-                    self.show_delete_confirmation = true;
-                }
-                KeyCode::Char('t') => {
-                    if self.s3_panel_selected {
-                        self.transfer_from_s3_to_local()
-                    } else {
-                        self.transfer_from_local_to_s3()
-                    }
+                KeyCode::Esc if !self.panel_filter().is_empty() => {
+                    self.set_panel_filter(String::new())
                 }
+                KeyCode::Esc => self.go_back(),
                 KeyCode::Left => {
                     self.s3_panel_selected = true;
                 }
                 KeyCode::Right => {
                     self.s3_panel_selected = false;
                 }
-                KeyCode::Char('?') => {
-                    let _ = self.action_tx.send(Action::Navigate {
-                        page: ActivePage::Help,
-                    });
-                }
-                KeyCode::Char('l') => {
-                    let _ = self.action_tx.send(Action::Navigate {
-                        page: ActivePage::Transfers,
-                    });
-                }
-                KeyCode::Char('s') => {
-                    let _ = self.action_tx.send(Action::Navigate {
-                        page: ActivePage::S3Creds,
-                    });
-                }
                 KeyCode::Tab => {
                     self.s3_panel_selected = !&self.s3_panel_selected;
                 }
-                KeyCode::Char('q') => {
-                    let _ = self.action_tx.send(Action::Exit);
-                }
                 _ => {}
             }
         }
     }
+
+    /// Only the bucket name, the filter and the metadata inputs take pastes
+    fn handle_paste(&mut self, text: &str) {
+        let submit = self.props.paste_submits_single_line;
+        if let Some(popup) = self.metadata_popup.as_mut() {
+            if let Some(action) = popup.handle_paste(text, submit) {
+                let _ = self.action_tx.send(action);
+            }
+        } else if let Some(input) = self.filter_input.as_mut() {
+            let submitted = paste_into(input, text, submit);
+            let filter = input.value().to_string();
+            self.set_panel_filter(filter);
+            if submitted {
+                self.filter_input = None;
+            }
+        } else if self.show_bucket_input && paste_into(&mut self.input, text, submit) {
+            self.submit_bucket_name();
+        } else if self.show_folder_input && paste_into(&mut self.input, text, submit) {
+            self.submit_folder_name();
+        } else if self.show_go_to_input && paste_into(&mut self.input, text, submit) {
+            self.submit_go_to();
+        } else if self.renaming.is_some() && paste_into(&mut self.input, text, submit) {
+            self.submit_rename();
+        } else if self.renaming_local.is_some() && paste_into(&mut self.input, text, submit) {
+            self.submit_local_rename();
+        }
+    }
+
+    /// Clicks select a row and focus its panel or run the clicked hint, the wheel moves the
+    /// selection of the table under the pointer. While a popup is open only the hints and
+    /// the scrolling of the preview take the mouse
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        let areas = self.mouse_areas.get();
+        let (column, row) = (mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if contains(areas.hint_line, column, row) => {
+                let key_bindings = &self.props.key_bindings;
+                let offset = (column - areas.hint_line.x) as usize;
+                let key = hint_at(
+                    &self.hint_context(),
+                    areas.hint_rotation,
+                    key_bindings,
+                    offset,
+                )
+                .and_then(|command| hint_key(command, key_bindings));
+                if let Some(key) = key {
+                    self.handle_key_event(key);
+                }
+            }
+            MouseEventKind::ScrollDown if self.preview_popup_open() => {
+                self.scroll_preview(PREVIEW_WHEEL_JUMP)
+            }
+            MouseEventKind::ScrollUp if self.preview_popup_open() => {
+                self.scroll_preview(-PREVIEW_WHEEL_JUMP)
+            }
+            _ if self.hint_context().popup_open => {}
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.click_table_row(column, row, Instant::now())
+            }
+            MouseEventKind::ScrollDown => self.scroll_table(column, row, 1),
+            MouseEventKind::ScrollUp => self.scroll_table(column, row, -1),
+            _ => {}
+        }
+    }
 }
 
 impl ComponentRender<()> for FileManagerPage {
     fn render(&self, frame: &mut Frame, _props: ()) {
-        let focus_color = Color::Rgb(98, 114, 164);
+        let focus_color = self.props.focus_color;
+        let mut mouse_areas = MouseAreas::default();
         // Split the frame into two main vertical sections
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -1083,13 +2897,24 @@ impl ComponentRender<()> for FileManagerPage {
             ])
             .split(frame.size());
 
+        // One line above the tables, the location of the s3 panel is shown over its half
+        let panels_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(vertical_chunks[0]);
+
         // Now split the top part horizontally into two side-by-side areas
         let horizontal_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(vertical_chunks[0]); // Apply this layout to the main area
+            .split(panels_layout[1]); // Apply this layout to the main area
+        let breadcrumb_area = Rect {
+            width: horizontal_chunks[0].width,
+            ..panels_layout[0]
+        };
+        frame.render_widget(self.get_breadcrumb(breadcrumb_area.width), breadcrumb_area);
 
-        if self.props.s3_loading {
+        if self.props.s3_loading && !self.props.s3_listing_streamed {
             let chunks_h = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
@@ -1121,54 +2946,121 @@ impl ComponentRender<()> for FileManagerPage {
             frame.render_widget(loading_info, loader_layout[1]);
         } else {
             let s3_table = self.get_s3_table(focus_color);
-            frame.render_stateful_widget(
-                &s3_table,
-                horizontal_chunks[0],
-                &mut self.props.clone().s3_table_state,
-            );
+            let mut s3_table_state = self.props.s3_table_state.clone();
+            frame.render_stateful_widget(&s3_table, horizontal_chunks[0], &mut s3_table_state);
+            mouse_areas.s3_table = horizontal_chunks[0];
+            mouse_areas.s3_offset = s3_table_state.offset();
         }
         let local_table = self.get_local_table(focus_color);
-        frame.render_stateful_widget(
-            &local_table,
-            horizontal_chunks[1],
-            &mut self.props.clone().local_table_state,
-        );
+        let mut local_table_state = self.props.local_table_state.clone();
+        frame.render_stateful_widget(&local_table, horizontal_chunks[1], &mut local_table_state);
+        mouse_areas.local_table = horizontal_chunks[1];
+        mouse_areas.local_offset = local_table_state.offset();
+        if let Some(input) = &self.filter_input {
+            // over the bottom rows of the filtered panel
+            let panel = horizontal_chunks[if self.s3_panel_selected { 0 } else { 1 }];
+            let height = panel.height.min(3);
+            let area = Rect::new(
+                panel.x,
+                panel.y + panel.height - height,
+                panel.width,
+                height,
+            );
+            let scroll = input.visual_scroll(area.width.saturating_sub(2) as usize);
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.make_filter_input(input, scroll), area);
+            frame.set_cursor(
+                area.x + (input.visual_cursor() - scroll) as u16 + 1,
+                area.y + 1,
+            );
+        }
 
-        let status_line = self.get_status_line();
-        let help_line = self.get_help_line();
         let status_line_layout = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
             .split(vertical_chunks[1]);
+        let status_line = self.get_status_line(status_line_layout[0].width);
+        let rotation = rotation_for(self.hints_shown_at.elapsed());
+        let hints = hint_line(&self.hint_context(), rotation, &self.props.key_bindings);
+        // the hints are aligned to the right, a line too long for the area keeps its start
+        let hints_area = status_line_layout[1];
+        let hints_width = (display_width(&hints) as u16).min(hints_area.width);
+        mouse_areas.hint_line = Rect::new(
+            hints_area.right() - hints_width,
+            hints_area.y,
+            hints_width,
+            hints_area.height,
+        );
+        mouse_areas.hint_rotation = rotation;
+        self.mouse_areas.set(mouse_areas);
         frame.render_widget(status_line, status_line_layout[0]);
-        frame.render_widget(help_line, status_line_layout[1]);
+        frame.render_widget(Self::get_help_line(hints), hints_area);
 
-        if self.show_problem_popup {
+        if let Some(popup) = &self.metadata_popup {
+            let area = Self::centered_rect(70, 60, frame.size());
+            popup.render(frame, area);
+        } else if self.comparison_popup_open() {
+            let area = Self::centered_rect(60, 25, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.make_comparison_popup(), area);
+        } else if self.preview_popup_open() {
+            let area = Self::centered_rect(80, 70, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.make_preview_popup(), area);
+        } else if self.show_problem_popup {
             let area = Self::centered_rect(60, 20, frame.size());
             frame.render_widget(Clear, area); //this clears out the background
             let block = self.make_transfer_error_popup();
             frame.render_widget(block, area);
         } else if self.show_bucket_input {
-            let block = self.make_bucket_name_input();
-            let area = Self::centered_rect(40, 20, frame.size());
-
-            frame.render_widget(Clear, area); //this clears out the background
-            frame.render_widget(block, area);
-            if let Some(error) = self.props.create_bucket_state.clone() {
-                let error_paragraph =
-                    Paragraph::new(format!("* {:?}", error)).style(Style::default().fg(Color::Red));
-                let error_rect = Rect::new(area.x + 1, area.y + 4, area.width, area.height);
-                frame.render_widget(Clear, error_rect);
-                frame.render_widget(error_paragraph, error_rect);
-            }
-            frame.set_cursor(area.x + self.input.visual_cursor() as u16 + 1, area.y + 1);
+            let location = self.new_bucket_location().describe();
+            let title = format!("| Enter new bucket name, created {} |", location);
+            let block = self.make_name_input(title);
+            self.render_name_input(frame, block, self.props.create_bucket_state.clone());
+        } else if self.show_folder_input {
+            let block = self.make_name_input("| Enter new folder name |");
+            let error = self
+                .folder_name_error
+                .clone()
+                .or(self.props.create_folder_state.clone());
+            self.render_name_input(frame, block, error);
+        } else if self.show_go_to_input {
+            let block =
+                self.make_name_input("| Go to s3://bucket/prefix or a prefix of the bucket |");
+            self.render_name_input(frame, block, self.go_to_error.clone());
+        } else if self.renaming.is_some() {
+            let block = self.make_name_input("| Enter new name, '/' starts a full key |");
+            self.render_name_input(frame, block, self.rename_error.clone());
+        } else if let Some((_, new_key)) = &self.rename_conflict {
+            let area = Self::centered_rect(60, 20, frame.size());
+            frame.render_widget(Clear, area);
+            let question = format!("{} already exists. Replace it?", new_key);
+            frame.render_widget(self.make_delete_alert(question, Color::Yellow), area);
+        } else if self.renaming_local.is_some() {
+            let block = self.make_name_input("| Enter new name |");
+            self.render_name_input(frame, block, self.rename_error.clone());
+        } else if let Some((_, new_name)) = &self.local_rename_conflict {
+            let area = Self::centered_rect(60, 20, frame.size());
+            frame.render_widget(Clear, area);
+            let question = format!("{} already exists. Replace it?", new_name);
+            frame.render_widget(self.make_delete_alert(question, Color::Yellow), area);
         } else if self.show_delete_confirmation {
             let area = Self::centered_rect(60, 20, frame.size());
             frame.render_widget(Clear, area); //this clears out the background
-            let block = self.make_delete_alert(
-                "Are you sure you want to delete this object?".to_string(),
-                Color::Green,
-            );
+            let question = "Are you sure you want to delete this object?".to_string();
+            let block = match self.delete_lock_warning() {
+                Some(warning) => {
+                    self.make_delete_alert(format!("{}\n\n{}", question, warning), Color::Yellow)
+                }
+                None => self.make_delete_alert(question, Color::Green),
+            };
+            frame.render_widget(block, area);
+        } else if self.show_delete_selected_confirmation {
+            let area = Self::centered_rect(60, 20, frame.size());
+            frame.render_widget(Clear, area);
+            let (question, color) =
+                self.with_partial_listing_warning(self.delete_selected_question(), Color::Yellow);
+            let block = self.make_delete_alert(question, color);
             frame.render_widget(block, area);
         } else if self.show_delete_multiple_confirmation {
             let area = Self::centered_rect(60, 20, frame.size());
@@ -1179,16 +3071,12 @@ impl ComponentRender<()> for FileManagerPage {
                     Color::Green,
                     false,
                 )
+            } else if let Some(error) = self.props.selection_limit_error.clone() {
+                self.make_confirm_download_alert(error, Color::Red, true)
             } else {
-                self.make_confirm_download_alert(
-                    format!(
-                        "You have selected {} items to delete. Proceed?",
-                        self.props.s3_data_full_list.len()
-                    )
-                        .to_string(),
-                    Color::Green,
-                    true,
-                )
+                let (question, color) = self
+                    .with_partial_listing_warning(self.recursive_delete_question(), Color::Green);
+                self.make_confirm_download_alert(question, color, true)
             };
             frame.render_widget(block, area);
         } else if self.show_download_confirmation {
@@ -1200,16 +3088,35 @@ impl ComponentRender<()> for FileManagerPage {
                     Color::Green,
                     false,
                 )
-            } else {
+            } else if let Some(error) = self.props.selection_limit_error.clone() {
+                self.make_confirm_download_alert(error, Color::Red, true)
+            } else if self.props.s3_data_full_list.is_empty() {
                 self.make_confirm_download_alert(
+                    "Nothing to download, the selected location has no objects".to_string(),
+                    Color::Yellow,
+                    true,
+                )
+            } else if self.props.long_download_keys > 0 {
+                let (question, color) = self.with_partial_listing_warning(
+                    format!(
+                        "You have selected {} items to download. Proceed?\n\n\
+                         {} of the keys are too long for the local file system, \
+                         their downloads will fail",
+                        self.props.s3_data_full_list.len(),
+                        self.props.long_download_keys
+                    ),
+                    Color::Yellow,
+                );
+                self.make_confirm_download_alert(question, color, true)
+            } else {
+                let (question, color) = self.with_partial_listing_warning(
                     format!(
                         "You have selected {} items to download. Proceed?",
                         self.props.s3_data_full_list.len()
-                    )
-                        .to_string(),
+                    ),
                     Color::Green,
-                    true,
-                )
+                );
+                self.make_confirm_download_alert(question, color, true)
             };
             frame.render_widget(block, area);
         } else if self.show_delete_error {
@@ -1222,18 +3129,50 @@ impl ComponentRender<()> for FileManagerPage {
                 _ => None,
             };
             if let Some(err) = possible_error {
-                let area = Self::centered_rect(60, 20, frame.size());
+                // the failures of a bulk deletion are listed one per line
+                let height = if err.lines().count() > 3 { 50 } else { 20 };
+                let area = Self::centered_rect(60, height, frame.size());
                 frame.render_widget(Clear, area); //this clears out the background
                 let block = self.make_delete_alert(err, Color::Red);
                 frame.render_widget(block, area);
             }
         }
+        if let Some(missing) = &self.props.missing_account {
+            let area = Self::centered_rect(60, 30, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.make_missing_account_popup(missing), area);
+        }
+        if !self.props.resumable_uploads.is_empty() {
+            let area = Self::centered_rect(60, 30, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.make_resumable_uploads_popup(), area);
+        }
+        if let Some(count) = self.props.restored_transfers {
+            let area = Self::centered_rect(60, 20, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.make_restored_transfers_popup(count), area);
+        }
+        if let Some(error) = &self.props.credentials_error {
+            let area = Self::centered_rect(60, 20, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.make_delete_alert(error.clone(), Color::Red), area);
+        }
+        if let Some(text) = &self.copy_fallback {
+            let area = Self::centered_rect(60, 25, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(fallback_popup(text), area);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::bulk_deletion::BulkDeletion;
+    use crate::model::object_copy::ObjectCopy;
+    use crate::model::object_metadata::ObjectMetadata;
+    use crate::services::transfer_persistence::RestoredQueue;
+    use crate::settings::key_bindings::parse_key_bindings;
     use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
     use tokio::sync::mpsc;
 
@@ -1261,4 +3200,1602 @@ mod tests {
             "Local panel should be selected after tab"
         );
     }
+
+    #[test]
+    fn status_line_fits_into_the_available_width() {
+        for bucket in [
+            "photos",
+            "写真のバックアップ用バケット",
+            "🎉-party-🎉-bucket",
+            "re\u{301}sume\u{301}s",
+        ] {
+            for width in [40, 60, 80] {
+                let line = FileManagerPage::status_line_text(
+                    "production-account",
+                    Some(bucket),
+                    12,
+                    3,
+                    0,
+                    None,
+                    width,
+                );
+                assert!(
+                    display_width(&line) <= width,
+                    "{:?} is wider than {}",
+                    line,
+                    width
+                );
+                assert!(line.ends_with(" • Transfers: 12/3"));
+            }
+        }
+    }
+
+    #[test]
+    fn status_line_keeps_short_names_intact() {
+        let line = FileManagerPage::status_line_text("aws", Some("photos"), 1, 0, 0, None, 80);
+        assert_eq!(line, " Account: aws • Bucket: photos • Transfers: 1/0");
+        let line = FileManagerPage::status_line_text("aws", None, 1, 0, 0, None, 80);
+        assert_eq!(line, " Account: aws • Transfers: 1/0");
+    }
+
+    #[test]
+    fn status_line_is_badged_while_tls_is_not_verified() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let creds = FileCredential {
+            name: "minio".into(),
+            selected: true,
+            insecure_skip_tls_verify: true,
+            ..FileCredential::default()
+        };
+        let mut state = State::new(vec![creds]);
+        state.notification = None;
+        let page = FileManagerPage::new(&state, tx.clone());
+        assert!(!page.props.skips_tls_verify);
+
+        state.confirm_skip_tls_verify("minio");
+        let page = FileManagerPage::new(&state, tx);
+        assert!(page.props.skips_tls_verify);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(60, 1)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(page.get_status_line(60), frame.size()))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.starts_with(" Account: minio • Transfers: 0/0 INSECURE TLS "));
+    }
+
+    #[test]
+    fn status_line_without_credentials_has_no_dangling_label() {
+        let line = FileManagerPage::status_line_text("", None, 0, 0, 0, None, 80);
+        assert_eq!(line, " No account • Transfers: 0/0");
+        let line = FileManagerPage::status_line_text("", Some("photos"), 0, 0, 0, None, 80);
+        assert_eq!(line, " No account • Bucket: photos • Transfers: 0/0");
+    }
+
+    #[test]
+    fn status_line_renders_with_default_state() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let page = FileManagerPage::new(&State::default(), tx);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(60, 1)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(page.get_status_line(60), frame.size()))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.starts_with(" No account • Transfers: 0/0"));
+        assert!(!text.contains("Account:"));
+    }
+
+    #[test]
+    fn status_line_shows_the_running_requests() {
+        let line = FileManagerPage::status_line_text("aws", Some("photos"), 1, 0, 3, None, 80);
+        assert_eq!(
+            line,
+            " Account: aws • Bucket: photos • Transfers: 1/0 • Requests: 3"
+        );
+        let line = FileManagerPage::status_line_text(
+            "production-account",
+            Some("photos"),
+            1,
+            0,
+            12,
+            None,
+            60,
+        );
+        assert!(display_width(&line) <= 60);
+        assert!(line.ends_with(" • Requests: 12"));
+    }
+
+    #[test]
+    fn jump_focus_highlights_item_once() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let page = FileManagerPage::new(&state, tx);
+        let item = |name: &str| S3DataItem {
+            bucket: Some("bucket".into()),
+            name: name.into(),
+            size: "1 KB".into(),
+            file_type: "txt".into(),
+            path: format!("a/b/{}", name),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        };
+        state.s3_data = vec![item("first.txt"), item("second.txt")];
+        state.file_manager_focus = Some(FileManagerFocus {
+            id: 1,
+            target: JumpTarget::S3 {
+                bucket: "bucket".into(),
+                prefix: Some("a/b/".into()),
+                item_name: Some("second.txt".into()),
+                account: String::new(),
+            },
+        });
+
+        let mut page = page.move_with_state(&state);
+        assert!(page.s3_panel_selected);
+        assert_eq!(page.props.s3_table_state.selected(), Some(1));
+
+        page.props.s3_table_state.select(Some(0));
+        let page = page.move_with_state(&state);
+        assert_eq!(page.props.s3_table_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn history_keys_send_the_highlighted_row() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let state = State::default();
+        let mut page = FileManagerPage::new(&state, tx);
+        page.s3_panel_selected = true;
+        page.props.s3_table_state.select(Some(2));
+
+        for (code, step) in [
+            (KeyCode::Esc, NavigationStep::Back),
+            (KeyCode::Char('<'), NavigationStep::Back),
+            (KeyCode::Char('>'), NavigationStep::Forward),
+            (KeyCode::Char('u'), NavigationStep::Parent),
+        ] {
+            page.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+            assert_eq!(
+                rx.try_recv().unwrap(),
+                Action::NavigateS3 { step, row: Some(2) }
+            );
+        }
+    }
+
+    #[test]
+    fn esc_stops_the_running_listing() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.set_s3_loading(true);
+        let mut page = FileManagerPage::new(&state, tx);
+        page.s3_panel_selected = true;
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(rx.try_recv().unwrap(), Action::CancelS3Listing);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn empty_listing_selects_nothing_to_download() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: None,
+            name: "empty-bucket".into(),
+            size: "".into(),
+            file_type: "".into(),
+            path: "".into(),
+            is_directory: false,
+            is_bucket: true,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.s3_panel_selected = true;
+        page.props.s3_table_state.select(Some(0));
+        page.show_download_confirmation = true;
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!page.show_download_confirmation);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn paste_goes_into_the_bucket_name_at_once() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let mut page = FileManagerPage::new(&state, tx);
+
+        // without an input the text isn't taken for key bindings, 'q' would quit
+        page.handle_paste("q");
+        assert!(rx.try_recv().is_err());
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE));
+        page.handle_paste("archive-2024\nq");
+        assert!(page.show_bucket_input);
+        assert_eq!(page.input.value(), "archive-2024q");
+        assert!(rx.try_recv().is_err());
+
+        state.app_config.paste_submits_single_line = true;
+        let mut page = page.move_with_state(&state);
+        page.input.reset();
+        page.show_bucket_input = true;
+        page.handle_paste("archive-2024\n");
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::CreateBucket {
+                name: "archive-2024".into()
+            }
+        );
+        assert!(!page.show_bucket_input);
+    }
+
+    #[test]
+    fn going_back_restores_the_row_once() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = (0..5)
+            .map(|i| S3DataItem {
+                bucket: Some("bucket".into()),
+                name: format!("file{}.txt", i),
+                size: "1 KB".into(),
+                file_type: "txt".into(),
+                path: format!("file{}.txt", i),
+                is_directory: false,
+                is_bucket: false,
+                region: None,
+                leading_slash: false,
+                last_modified: None,
+                storage_class: None,
+                creation_date: None,
+            })
+            .collect();
+        state.s3_row_restore = Some((1, 3));
+        let mut page = FileManagerPage::new(&state, tx);
+        assert_eq!(page.props.s3_table_state.selected(), Some(3));
+
+        page.props.s3_table_state.select(Some(0));
+        let page = page.move_with_state(&state);
+        assert_eq!(page.props.s3_table_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn yanked_object_is_pasted_into_the_opened_location() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("staging".into()),
+            name: "file.csv".into(),
+            size: "1 KB".into(),
+            file_type: "csv".into(),
+            path: "data/file.csv".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+
+        state.navigate_s3(
+            NavigationStep::Into(NavigationState::new(
+                Some("production".into()),
+                Some("archive/".into()),
+            )),
+            None,
+        );
+        let mut page = page.move_with_state(&state);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('P'), KeyModifiers::NONE));
+        match rx.try_recv().unwrap() {
+            Action::CopyS3Item {
+                item,
+                destination_bucket,
+                destination_prefix,
+                delete_source,
+            } => {
+                assert_eq!(item.path, Some("data/file.csv".into()));
+                assert_eq!(destination_bucket, "production");
+                assert_eq!(destination_prefix, Some("archive/".into()));
+                assert!(delete_source);
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+
+        // the moved object is gone from its source
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('P'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn renamed_object_replaces_an_existing_key_only_when_confirmed() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.navigate_s3(
+            NavigationStep::Into(NavigationState::new(
+                Some("staging".into()),
+                Some("data/".into()),
+            )),
+            None,
+        );
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("staging".into()),
+            name: "file.csv".into(),
+            size: "1 KB".into(),
+            file_type: "csv".into(),
+            path: "data/file.csv".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        press(&mut page, KeyCode::Char('n'));
+        assert_eq!(page.input.value(), "file.csv");
+
+        for _ in 0..3 {
+            press(&mut page, KeyCode::Backspace);
+        }
+        for c in "txt".chars() {
+            press(&mut page, KeyCode::Char(c));
+        }
+        press(&mut page, KeyCode::Enter);
+        let item = match rx.try_recv().unwrap() {
+            Action::RenameS3Item {
+                item,
+                new_key,
+                overwrite,
+            } => {
+                assert_eq!(new_key, "data/file.txt");
+                assert!(!overwrite);
+                item
+            }
+            other => panic!("unexpected action {:?}", other),
+        };
+        assert!(page.renaming.is_none());
+
+        // the object already at the new key is replaced after Enter
+        let rename = ObjectCopy::renamed(&item, "data/file.txt".into()).unwrap();
+        state.finish_s3_rename(item, &rename, Ok(false));
+        let mut page = page.move_with_state(&state);
+        assert!(page.rename_conflict.is_some());
+        press(&mut page, KeyCode::Enter);
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::RenameS3Item {
+                overwrite: true,
+                ..
+            }
+        ));
+        assert!(page.rename_conflict.is_none());
+    }
+
+    #[test]
+    fn hidden_local_files_are_listed_only_when_shown() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.local_data = vec![
+            LocalDataItem::init(
+                ".cache".into(),
+                "-".into(),
+                "Dir",
+                "/home/user/.cache",
+                true,
+            ),
+            LocalDataItem::init(
+                "notes.txt".into(),
+                "1 KB".into(),
+                "txt",
+                "/home/user/n",
+                false,
+            ),
+        ];
+        let mut page = FileManagerPage::new(&state, tx.clone());
+        page.s3_panel_selected = false;
+        assert_eq!(page.props.local_data.len(), 1);
+        assert_eq!(page.props.local_hidden_files, 1);
+        press(&mut page, KeyCode::Char('.'));
+        assert_eq!(rx.try_recv().unwrap(), Action::ToggleHiddenFiles);
+
+        state.show_hidden_files = true;
+        let page = FileManagerPage::new(&state, tx);
+        assert_eq!(page.props.local_data_listed.len(), 2);
+        assert_eq!(page.props.local_hidden_files, 0);
+    }
+
+    #[test]
+    fn local_rename_stays_in_the_directory_and_confirms_replacing() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.current_local_path = "/home/user/notes".into();
+        state.local_data = vec![LocalDataItem::init(
+            "draft.txt".into(),
+            "1 KB".into(),
+            "txt",
+            "/home/user/notes/draft.txt",
+            false,
+        )];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.s3_panel_selected = false;
+        page.props.local_table_state.select(Some(0));
+        press(&mut page, KeyCode::Char('r'));
+        assert_eq!(page.input.value(), "draft.txt");
+
+        // a path separator keeps the input open with the error
+        page.input = Input::new("old/draft.txt".into());
+        press(&mut page, KeyCode::Enter);
+        assert!(rx.try_recv().is_err());
+        assert!(page.rename_error.is_some());
+
+        page.input = Input::new("todo.txt".into());
+        press(&mut page, KeyCode::Enter);
+        let (item, new_name) = match rx.try_recv().unwrap() {
+            Action::RenameLocalItem {
+                item,
+                new_name,
+                overwrite,
+            } => {
+                assert!(!overwrite);
+                (item, new_name)
+            }
+            other => panic!("unexpected action {:?}", other),
+        };
+        assert_eq!(new_name, "todo.txt");
+        assert!(page.renaming_local.is_none());
+
+        // the file already at the new name is replaced after Enter
+        state.finish_local_rename(item, new_name, Ok(false));
+        let mut page = page.move_with_state(&state);
+        assert!(page.local_rename_conflict.is_some());
+        press(&mut page, KeyCode::Enter);
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::RenameLocalItem {
+                overwrite: true,
+                ..
+            }
+        ));
+        assert!(page.local_rename_conflict.is_none());
+    }
+
+    #[test]
+    fn metadata_popup_follows_the_selected_object_and_state() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("bucket".into()),
+            name: "file.csv".into(),
+            size: "1 KB".into(),
+            file_type: "csv".into(),
+            path: "data/file.csv".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE));
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::FetchObjectMetadata {
+                bucket: "bucket".into(),
+                key: "data/file.csv".into(),
+            }
+        );
+        assert!(page.hint_context().popup_open);
+
+        state.start_object_metadata_request();
+        let page = page.move_with_state(&state);
+        assert!(page.metadata_popup.is_some());
+
+        state.clear_object_metadata();
+        let page = page.move_with_state(&state);
+        assert!(page.metadata_popup.is_none());
+    }
+
+    fn press(page: &mut FileManagerPage, code: KeyCode) {
+        page.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn aws_cli_commands_are_shown_without_a_clipboard() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.app_config.osc52_clipboard = false;
+        state.current_creds.name = "staging".into();
+        state.navigate_s3(
+            NavigationStep::Into(NavigationState::new(
+                Some("data".into()),
+                Some("team a/".into()),
+            )),
+            None,
+        );
+        state.current_s3_bucket = Some("data".into());
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("data".into()),
+            name: "q1.csv".into(),
+            size: "1 KB".into(),
+            file_type: "csv".into(),
+            path: "team a/q1.csv".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: Some("eu-central-1".into()),
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        state.current_local_path = "/home/me".into();
+        state.local_data = vec![LocalDataItem::init(
+            "report.csv".into(),
+            "1 KB".into(),
+            "csv",
+            "/home/me/report.csv",
+            false,
+        )];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.props.local_table_state.select(Some(0));
+
+        press(&mut page, KeyCode::Char('a'));
+        let listing = page.copy_fallback.clone().unwrap();
+        assert!(listing.starts_with(
+            "aws s3api list-objects-v2 --bucket data --delimiter / --prefix 'team a/'"
+        ));
+        assert!(listing.ends_with("--region eu-central-1 --profile staging"));
+        assert!(page.hint_context().popup_open);
+        press(&mut page, KeyCode::Esc);
+        assert!(page.copy_fallback.is_none());
+
+        press(&mut page, KeyCode::Tab);
+        press(&mut page, KeyCode::Char('a'));
+        assert!(page.copy_fallback.clone().unwrap().starts_with(
+            "aws s3api put-object --bucket data --key report.csv --body /home/me/report.csv"
+        ));
+        press(&mut page, KeyCode::Esc);
+
+        // the metadata popup copies the download of its object
+        press(&mut page, KeyCode::Tab);
+        press(&mut page, KeyCode::Char('m'));
+        state.start_object_metadata_request();
+        state.set_object_metadata(Ok(ObjectMetadata {
+            bucket: "data".into(),
+            key: "team a/q1.csv".into(),
+            ..ObjectMetadata::default()
+        }));
+        page = page.move_with_state(&state);
+        press(&mut page, KeyCode::Char('a'));
+        assert!(page.copy_fallback.clone().unwrap().starts_with(
+            "aws s3api get-object --bucket data --key 'team a/q1.csv' '/home/me/team a/q1.csv'"
+        ));
+        assert!(page.metadata_popup.is_some());
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::FetchObjectMetadata { .. }
+        ));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn folder_is_created_in_the_current_location() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.navigate_s3(
+            NavigationStep::Into(NavigationState::new(
+                Some("media".into()),
+                Some("2024/".into()),
+            )),
+            None,
+        );
+        let mut page = FileManagerPage::new(&state, tx);
+        press(&mut page, KeyCode::Char('c'));
+        assert!(page.show_folder_input);
+        assert!(!page.show_bucket_input);
+
+        // invalid names stay in the input with the error
+        for c in "q1//".chars() {
+            press(&mut page, KeyCode::Char(c));
+        }
+        press(&mut page, KeyCode::Enter);
+        assert!(rx.try_recv().is_err());
+        assert!(page.show_folder_input);
+        assert!(page.folder_name_error.is_some());
+
+        press(&mut page, KeyCode::Backspace);
+        press(&mut page, KeyCode::Enter);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::CreateFolder {
+                bucket: "media".into(),
+                prefix: Some("2024/".into()),
+                name: "q1/".into(),
+            }
+        );
+        assert!(!page.show_folder_input);
+        assert_eq!(page.folder_name_error, None);
+
+        // a failed creation brings the input back
+        state.finish_folder_creation(
+            "media",
+            &Some("2024/".into()),
+            "q1/",
+            Some("AccessDenied".into()),
+        );
+        let page = page.move_with_state(&state);
+        assert!(page.show_folder_input);
+        assert_eq!(page.input.value(), "q1/");
+    }
+
+    #[test]
+    fn go_to_input_stays_open_with_the_errors() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let mut page = FileManagerPage::new(&state, tx);
+        press(&mut page, KeyCode::Char('g'));
+        assert!(page.show_go_to_input);
+        assert!(page.hint_context().popup_open);
+
+        // a plain prefix needs an opened bucket
+        for c in "2024/01".chars() {
+            press(&mut page, KeyCode::Char(c));
+        }
+        press(&mut page, KeyCode::Enter);
+        assert!(rx.try_recv().is_err());
+        assert!(page.go_to_error.is_some());
+
+        page.input = Input::new("s3://media/2024/01".into());
+        press(&mut page, KeyCode::Enter);
+        let location = NavigationState::new(Some("media".into()), Some("2024/01/".into()));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::GoToS3Path {
+                location: location.clone(),
+                row: None,
+            }
+        );
+        assert!(page.show_go_to_input);
+        assert_eq!(page.go_to_error, None);
+
+        // the popup shows why the location can't be opened and closes once it's opened
+        let missing = "Nothing found under s3://media/2024/01/".to_string();
+        state.finish_go_to_s3_path("", location.clone(), None, Err(missing.clone()));
+        page = page.move_with_state(&state);
+        assert!(page.show_go_to_input);
+        assert_eq!(page.go_to_error, Some(missing));
+        state.finish_go_to_s3_path("", location.clone(), None, Ok(()));
+        page = page.move_with_state(&state);
+        assert!(!page.show_go_to_input);
+        assert_eq!(page.props.s3_location, location);
+    }
+
+    #[test]
+    fn bucket_input_changes_only_with_the_creation_results() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let mut page = FileManagerPage::new(&state, tx);
+        press(&mut page, KeyCode::Char('c'));
+        for c in "logs".chars() {
+            press(&mut page, KeyCode::Char(c));
+        }
+
+        // progress of the transfers and listings keep the input with the typed name
+        for done in 1..=3 {
+            state.transfers_done = done;
+            state.set_s3_loading(done % 2 == 1);
+            page = page.move_with_state(&state);
+        }
+        assert!(page.show_bucket_input);
+        assert_eq!(page.input.value(), "logs");
+
+        // a failed creation brings the input back with the typed name
+        press(&mut page, KeyCode::Enter);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::CreateBucket {
+                name: "logs".into()
+            }
+        );
+        assert!(!page.show_bucket_input);
+        state.set_create_bucket_error(Some("BucketAlreadyExists".into()));
+        page = page.move_with_state(&state);
+        assert!(page.show_bucket_input);
+        assert_eq!(page.input.value(), "logs");
+
+        // the states still carrying the error don't reopen the closed input
+        press(&mut page, KeyCode::Esc);
+        assert_eq!(rx.try_recv().unwrap(), Action::ClearDeletionErrors);
+        state.transfers_done = 4;
+        page = page.move_with_state(&state);
+        assert!(!page.show_bucket_input);
+    }
+
+    #[test]
+    fn dismissed_delete_error_stays_closed() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let page = FileManagerPage::new(&state, tx);
+
+        state.set_s3_delete_error(Some("AccessDenied".into()));
+        let mut page = page.move_with_state(&state);
+        assert!(page.show_delete_error);
+
+        press(&mut page, KeyCode::Esc);
+        assert_eq!(rx.try_recv().unwrap(), Action::ClearDeletionErrors);
+        state.transfers_done = 1;
+        let page = page.move_with_state(&state);
+        assert!(!page.show_delete_error);
+    }
+
+    #[test]
+    fn download_confirmation_waits_for_the_recursive_listing() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("bucket".into()),
+            name: "logs/".into(),
+            size: "".into(),
+            file_type: "".into(),
+            path: "logs/".into(),
+            is_directory: true,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        // listing of an earlier selection still in the state
+        state.s3_data_full_list = state.s3_data.clone();
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        press(&mut page, KeyCode::Char('t'));
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::ListS3DataRecursiveForItem { .. }
+        ));
+
+        // states broadcast before the listing starts don't accept Enter on the old listing
+        state.transfers_done = 1;
+        page = page.move_with_state(&state);
+        assert!(page.show_download_confirmation);
+        press(&mut page, KeyCode::Enter);
+        assert!(page.show_download_confirmation);
+        assert!(rx.try_recv().is_err());
+
+        state.set_s3_list_recursive_loading(true);
+        page = page.move_with_state(&state);
+        state.set_s3_list_recursive_loading(false);
+        page = page.move_with_state(&state);
+        press(&mut page, KeyCode::Esc);
+        assert!(!page.show_download_confirmation);
+    }
+
+    #[test]
+    fn selection_over_the_limit_is_not_deleted() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("bucket".into()),
+            name: "logs/".into(),
+            size: "".into(),
+            file_type: "".into(),
+            path: "logs/".into(),
+            is_directory: true,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        state.selection_limit_error = Some("Selection exceeds 1,000,000 objects".into());
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.show_delete_multiple_confirmation = true;
+
+        press(&mut page, KeyCode::Enter);
+        assert!(!page.show_delete_multiple_confirmation);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn bucket_is_deleted_with_its_objects() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let row = |bucket: Option<&str>, name: &str, is_bucket: bool| S3DataItem {
+            bucket: bucket.map(String::from),
+            name: name.into(),
+            size: "".into(),
+            file_type: "".into(),
+            path: name.into(),
+            is_directory: false,
+            is_bucket,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        };
+        state.s3_data = vec![row(None, "logs", true)];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        press(&mut page, KeyCode::Delete);
+        press(&mut page, KeyCode::Enter);
+        match rx.try_recv().unwrap() {
+            Action::ListS3DataRecursiveForItem { item } => assert!(item.is_bucket),
+            action => panic!("unexpected action {:?}", action),
+        }
+        assert!(page.show_delete_multiple_confirmation);
+
+        state.set_s3_list_recursive_loading(true);
+        page = page.move_with_state(&state);
+        state.s3_data_full_list = vec![
+            row(Some("logs"), "2024/01/a.log", false),
+            row(Some("logs"), "2024/02/b.log", false),
+        ];
+        state.set_s3_list_recursive_loading(false);
+        page = page.move_with_state(&state);
+        assert_eq!(
+            page.recursive_delete_question(),
+            "2 objects in the bucket logs will be deleted, then the bucket itself. Proceed?"
+        );
+        press(&mut page, KeyCode::Enter);
+        match rx.try_recv().unwrap() {
+            Action::DeleteS3Item { item } => {
+                assert!(item.is_bucket);
+                assert_eq!(item.children.map(|c| c.len()), Some(2));
+            }
+            action => panic!("unexpected action {:?}", action),
+        }
+
+        state.set_s3_delete_progress(DeleteProgress {
+            location: "s3://logs/".into(),
+            deleted: 1000,
+            total: 50000,
+        });
+        page = page.move_with_state(&state);
+        assert!(page
+            .s3_panel_title()
+            .ends_with("• deleting s3://logs/: 1000 of 50000 objects"));
+    }
+
+    #[test]
+    fn rebound_keys_replace_the_defaults() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.key_bindings =
+            parse_key_bindings("toggle_select = \"space\"\nquit = \"ctrl+q\"").unwrap();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("data".into()),
+            name: "q1.csv".into(),
+            size: "1 KB".into(),
+            file_type: "csv".into(),
+            path: "q1.csv".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.s3_panel_selected = true;
+        page.props.s3_table_state.select(Some(0));
+
+        press(&mut page, KeyCode::Char('t'));
+        press(&mut page, KeyCode::Char('q'));
+        assert!(rx.try_recv().is_err());
+
+        press(&mut page, KeyCode::Char(' '));
+        match rx.try_recv().unwrap() {
+            Action::SelectS3Item { item } => assert_eq!(item.name, "q1.csv"),
+            other => panic!("unexpected action {:?}", other),
+        }
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        assert_eq!(rx.try_recv().unwrap(), Action::Exit);
+    }
+
+    #[test]
+    fn partial_listing_is_marked_and_refreshed_with_f5() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.navigate_s3(
+            NavigationStep::Into(NavigationState::new(Some("logs".into()), None)),
+            None,
+        );
+        state.current_s3_bucket = Some("logs".into());
+        state.s3_listing_partial = true;
+        let mut page = FileManagerPage::new(&state, tx);
+
+        assert!(page
+            .s3_panel_title_line()
+            .spans
+            .iter()
+            .any(|span| span.content == " (partial — press F5 to retry)"));
+        let (question, color) =
+            page.with_partial_listing_warning("Delete 2 objects?".into(), Color::Green);
+        assert!(question.contains("The listing of this location is incomplete"));
+        assert_eq!(color, Color::Yellow);
+
+        press(&mut page, KeyCode::F(5));
+        assert_eq!(rx.try_recv().unwrap(), Action::RefreshS3Location);
+
+        state.s3_listing_partial = false;
+        page = page.move_with_state(&state);
+        assert_eq!(page.s3_panel_title_line().spans.len(), 1);
+        let (question, color) =
+            page.with_partial_listing_warning("Delete 2 objects?".into(), Color::Green);
+        assert_eq!(question, "Delete 2 objects?");
+        assert_eq!(color, Color::Green);
+    }
+
+    #[test]
+    fn metadata_popup_waits_for_the_store() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("bucket".into()),
+            name: "file.csv".into(),
+            size: "1 KB".into(),
+            file_type: "csv".into(),
+            path: "file.csv".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        press(&mut page, KeyCode::Char('m'));
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::FetchObjectMetadata { .. }
+        ));
+
+        // a state broadcast before the request starts doesn't close the popup
+        state.transfers_done = 1;
+        page = page.move_with_state(&state);
+        assert!(page.metadata_popup.is_some());
+
+        state.start_object_metadata_request();
+        page = page.move_with_state(&state);
+        press(&mut page, KeyCode::Esc);
+        assert_eq!(rx.try_recv().unwrap(), Action::CloseObjectMetadata);
+
+        // nor does one broadcast before the store closes it open it again
+        page = page.move_with_state(&state);
+        assert!(page.metadata_popup.is_none());
+        state.clear_object_metadata();
+        page = page.move_with_state(&state);
+        assert!(page.metadata_popup.is_none());
+    }
+
+    #[test]
+    fn delete_confirmation_warns_about_locked_highlighted_object() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let item = |name: &str| S3DataItem {
+            bucket: Some("bucket".into()),
+            name: name.into(),
+            size: "1 KB".into(),
+            file_type: "txt".into(),
+            path: name.into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        };
+        state.s3_data = vec![item("locked.txt"), item("free.txt")];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.handle_key_event(KeyEvent::new(KeyCode::Delete, KeyModifiers::NONE));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::FetchObjectLockInfo {
+                bucket: "bucket".into(),
+                key: "locked.txt".into(),
+            }
+        );
+
+        state.delete_lock_info = Some((
+            "bucket".into(),
+            "locked.txt".into(),
+            ObjectLockInfo {
+                legal_hold: Some(true),
+                ..ObjectLockInfo::default()
+            },
+        ));
+        let mut page = page.move_with_state(&state);
+        assert!(page.delete_lock_warning().is_some());
+
+        page.props.s3_table_state.select(Some(1));
+        assert_eq!(page.delete_lock_warning(), None);
+    }
+
+    #[test]
+    fn compare_needs_an_object_and_a_local_file() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("bucket".into()),
+            name: "backup.tar".into(),
+            size: "4 GB".into(),
+            file_type: "tar".into(),
+            path: "backup.tar".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('='), KeyModifiers::NONE));
+        assert!(page.show_compare_hint);
+        assert!(rx.try_recv().is_err());
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(!page.comparison_popup_open());
+
+        page.props.local_data = vec![LocalDataItem::init(
+            "backup.tar".into(),
+            "4 GB".into(),
+            "tar",
+            "/home/user/backup.tar",
+            false,
+        )];
+        page.props.local_table_state.select(Some(0));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('='), KeyModifiers::NONE));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::CompareObjectWithLocal {
+                bucket: "bucket".into(),
+                key: "backup.tar".into(),
+                local_path: "/home/user/backup.tar".into(),
+                full_hash: false,
+            }
+        );
+        assert!(page.comparison_popup_open());
+    }
+
+    #[test]
+    fn preview_scrolls_within_the_content_and_closes() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("logs".into()),
+            name: "app.log".into(),
+            size: "2 KB".into(),
+            file_type: "log".into(),
+            path: "2024/app.log".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::NONE));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::PreviewS3Item {
+                bucket: "logs".into(),
+                key: "2024/app.log".into(),
+            }
+        );
+        assert!(page.preview_popup_open());
+
+        state.start_object_preview();
+        state.set_object_preview(Ok(ObjectPreview::from_bytes(
+            "logs",
+            "2024/app.log",
+            b"first\nsecond\nthird\n",
+            2048,
+        )));
+        let mut page = page.move_with_state(&state);
+        page.handle_key_event(KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE));
+        assert_eq!(page.preview_scroll, 2);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE));
+        assert_eq!(page.preview_scroll, 1);
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(rx.try_recv().unwrap(), Action::ClosePreview);
+        assert!(!page.preview_popup_open());
+    }
+
+    #[test]
+    fn object_contents_are_shown_when_the_clipboard_is_off() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.app_config.osc52_clipboard = false;
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("config".into()),
+            name: "app.json".into(),
+            size: "1 KB".into(),
+            file_type: "json".into(),
+            path: "prod/app.json".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::CopyS3ObjectText {
+                bucket: "config".into(),
+                key: "prod/app.json".into(),
+            }
+        );
+
+        state.finish_object_text_read("config", "prod/app.json", Ok("{}\n".into()));
+        let mut page = page.move_with_state(&state);
+        assert_eq!(page.copy_fallback, Some("{}\n".to_string()));
+        // the text is copied once, later states keep the closed popup closed
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        let page = page.move_with_state(&state);
+        assert!(page.copy_fallback.is_none());
+    }
+
+    #[test]
+    fn credentials_error_is_shown_until_dismissed() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.s3_listing_request = 1;
+        assert!(state.fail_credentials(1, "the SSO session has expired".into()));
+        let mut page = FileManagerPage::new(&state, tx);
+        assert_eq!(
+            page.props.credentials_error.as_deref(),
+            Some("Cannot use account : the SSO session has expired")
+        );
+        // keys other than Esc and Enter stay in the popup
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(rx.try_recv().unwrap(), Action::ClearCredentialsError);
+        assert!(page.props.credentials_error.is_none());
+    }
+
+    #[test]
+    fn restored_transfers_are_resumed_or_discarded() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.restored_transfers = Some(QueueSnapshot {
+            downloads: Vec::new(),
+            uploads: ["a.txt", "b.txt", "c.txt"]
+                .iter()
+                .map(|name| {
+                    LocalSelectedItem::new(
+                        name.to_string(),
+                        format!("/tmp/{}", name),
+                        false,
+                        "backup".into(),
+                        name.to_string(),
+                        String::new(),
+                        None,
+                    )
+                })
+                .collect(),
+        });
+        let mut page = FileManagerPage::new(&state, tx);
+        assert_eq!(page.props.restored_transfers, Some(3));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
+        assert_eq!(rx.try_recv().unwrap(), Action::ResumeRestoredTransfers);
+        assert_eq!(rx.try_recv().unwrap(), Action::RunTransfers);
+        assert!(page.props.restored_transfers.is_none());
+
+        let mut page = page.move_with_state(&state);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+        assert_eq!(rx.try_recv().unwrap(), Action::DiscardRestoredTransfers);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn interrupted_uploads_are_resumed_aborted_or_postponed() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let upload = |name: &str| ResumableUpload {
+            account: "work".into(),
+            bucket: "backup".into(),
+            key: name.into(),
+            path: format!("/tmp/{}", name),
+            size: 20,
+            modified: 0,
+            part_size: 8,
+            upload_id: name.into(),
+            parts: Default::default(),
+        };
+        let mut state = State::default();
+        state.resumable_uploads = vec![upload("a.img"), upload("b.img"), upload("c.img")];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::ResumeUpload(upload("b.img"))
+        );
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
+        assert_eq!(rx.try_recv().unwrap(), Action::AbortUpload(upload("c.img")));
+        assert_eq!(page.props.resumable_uploads, vec![upload("a.img")]);
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(rx.try_recv().unwrap(), Action::PostponeResumableUploads);
+        assert!(page.props.resumable_uploads.is_empty());
+    }
+
+    #[test]
+    fn missing_account_of_restored_transfers_is_remapped_or_dropped() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let account = |name: &str| FileCredential {
+            name: name.into(),
+            ..FileCredential::default()
+        };
+        let mut state = State::new(vec![account("work"), account("home")]);
+        let upload = LocalSelectedItem::new(
+            "a.txt".into(),
+            "/tmp/a.txt".into(),
+            false,
+            "backup".into(),
+            "a.txt".into(),
+            "old".into(),
+            None,
+        );
+        state.orphaned_transfers = Some(RestoredQueue {
+            orphaned: QueueSnapshot {
+                downloads: Vec::new(),
+                uploads: vec![upload],
+            },
+            missing_accounts: vec!["old".into(), "gone".into()],
+            ..RestoredQueue::default()
+        });
+        let mut page = FileManagerPage::new(&state, tx);
+        assert_eq!(
+            page.props.missing_account,
+            Some(MissingAccount {
+                name: "old".into(),
+                transfers: 1,
+                accounts: vec!["work".into(), "home".into()],
+            })
+        );
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert!(rx.try_recv().is_err());
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::RemapRestoredAccount {
+                account: "old".into(),
+                replacement: Some("home".into()),
+            }
+        );
+        assert!(page.props.missing_account.is_none());
+
+        let mut page = page.move_with_state(&state);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::RemapRestoredAccount {
+                account: "old".into(),
+                replacement: None,
+            }
+        );
+    }
+
+    #[test]
+    fn custom_commands_get_the_highlighted_row() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let command = |name: &str, template: &str, applies_to: &str| {
+            let entry = [
+                ("name", name),
+                ("key", "alt+e"),
+                ("command", template),
+                ("applies_to", applies_to),
+            ]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+            CustomCommand::from_entry(&entry).unwrap()
+        };
+        let etl = command("etl", "etl-submit {bucket} {key}", "s3");
+        let open = command("open", "xdg-open {local_path}", "local");
+        let mut state = State::default();
+        state.app_config.custom_commands = vec![etl.clone(), open.clone()];
+        state.current_creds.name = "prod".into();
+        state.s3_data = vec![S3DataItem {
+            bucket: Some("logs".into()),
+            name: "app.log".into(),
+            size: "2 KB".into(),
+            file_type: "log".into(),
+            path: "2024/app.log".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        }];
+        let mut page = FileManagerPage::new(&state, tx);
+        page.props.s3_table_state.select(Some(0));
+        let alt_e = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::ALT);
+        page.handle_key_event(alt_e);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::RunCustomCommand {
+                command: etl,
+                row: RowValues {
+                    bucket: Some("logs".into()),
+                    key: Some("2024/app.log".into()),
+                    local_path: None,
+                    account: "prod".into(),
+                },
+            }
+        );
+
+        // the same key runs the command of the local rows on the local panel
+        page.s3_panel_selected = false;
+        page.handle_key_event(alt_e);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::RunCustomCommand {
+                command: open,
+                row: RowValues {
+                    account: "prod".into(),
+                    ..RowValues::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn binary_preview_is_not_displayed() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.set_object_preview(Ok(ObjectPreview::from_bytes(
+            "photos",
+            "cat.png",
+            b"\x89PNG\0\0",
+            5_000_000,
+        )));
+        let page = FileManagerPage::new(&state, tx);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| page.render(frame, ())).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("Binary file, preview not available"));
+        assert!(text.contains("first 6 B of 4.8 MB"));
+    }
+
+    #[test]
+    fn filtered_rows_act_on_the_displayed_item() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.current_s3_bucket = Some("staging".into());
+        state.s3_data = ["a.txt", "report-q1.csv", "b.txt", "report-q2.csv"]
+            .iter()
+            .map(|name| S3DataItem {
+                bucket: Some("staging".into()),
+                name: name.to_string(),
+                size: "10".into(),
+                file_type: "".into(),
+                path: name.to_string(),
+                is_directory: false,
+                is_bucket: false,
+                region: None,
+                leading_slash: false,
+                last_modified: None,
+                storage_class: None,
+                creation_date: None,
+            })
+            .collect();
+        let mut page = FileManagerPage::new(&state, tx);
+        page.s3_panel_selected = true;
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('/'), KeyModifiers::NONE));
+        for c in "report".chars() {
+            page.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(page.filter_input.is_none());
+        assert_eq!(page.props.s3_table_state.selected(), Some(0));
+        assert_eq!(page.filtered_rows(), Some((2, 4)));
+
+        // the second filtered row is the fourth listed item
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE));
+        match rx.try_recv().unwrap() {
+            Action::SelectS3Item { item } => assert_eq!(item.name, "report-q2.csv"),
+            other => panic!("unexpected action {:?}", other),
+        }
+
+        // the filter stays with the listing updates and goes with Esc
+        let mut page = page.move_with_state(&state);
+        assert_eq!(page.props.s3_data.len(), 2);
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(page.props.s3_data.len(), 4);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn status_line_shows_the_filtered_rows() {
+        let line = FileManagerPage::status_line_text("aws", None, 1, 0, 0, Some((2, 40)), 80);
+        assert_eq!(line, " Account: aws • Transfers: 1/0 • filtered: 2/40");
+    }
+
+    #[test]
+    fn selected_items_are_deleted_after_confirmation() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        let mut page = FileManagerPage::new(&state, tx.clone());
+        // nothing is selected
+        press(&mut page, KeyCode::Char('D'));
+        assert!(!page.show_delete_selected_confirmation);
+
+        state.local_selected_items = vec![LocalSelectedItem::new(
+            "notes.txt".into(),
+            "/tmp/notes.txt".into(),
+            false,
+            "".into(),
+            "".into(),
+            String::new(),
+            None,
+        )];
+        let mut page = FileManagerPage::new(&state, tx);
+        press(&mut page, KeyCode::Char('D'));
+        assert!(page.show_delete_selected_confirmation);
+        assert!(page
+            .delete_selected_question()
+            .contains("delete the 1 selected items? (0 s3 objects, 1 local files"));
+        press(&mut page, KeyCode::Esc);
+        assert!(!page.show_delete_selected_confirmation);
+        assert!(rx.try_recv().is_err());
+
+        press(&mut page, KeyCode::Char('D'));
+        press(&mut page, KeyCode::Enter);
+        assert_eq!(rx.try_recv().unwrap(), Action::DeleteSelectedItems);
+        assert!(!page.show_delete_selected_confirmation);
+
+        // the failures are shown in a single popup
+        state.finish_bulk_deletion(BulkDeletion {
+            failed: vec![("/tmp/notes.txt".into(), "Permission denied".into())],
+            ..BulkDeletion::default()
+        });
+        let page = page.move_with_state(&state);
+        assert!(page.show_delete_error);
+    }
+
+    #[test]
+    fn mouse_selects_opens_and_scrolls_the_rows() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = State::default();
+        state.current_s3_bucket = Some("photos".into());
+        state.s3_data = ["2023/", "2024/", "cover.jpg"]
+            .iter()
+            .map(|name| S3DataItem {
+                bucket: Some("photos".into()),
+                name: name.to_string(),
+                size: "".into(),
+                file_type: "".into(),
+                path: name.to_string(),
+                is_directory: name.ends_with('/'),
+                is_bucket: false,
+                region: None,
+                leading_slash: false,
+                last_modified: None,
+                storage_class: None,
+                creation_date: None,
+            })
+            .collect();
+        let mut page = FileManagerPage::new(&state, tx);
+        page.s3_panel_selected = false;
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(160, 12)).unwrap();
+        terminal.draw(|frame| page.render(frame, ())).unwrap();
+        let top_line: String = terminal.backend().buffer().content[..160]
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(top_line.starts_with(" Buckets "));
+        let mouse = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        let click = |column, row| mouse(MouseEventKind::Down(MouseButton::Left), column, row);
+
+        // the rows of the s3 panel start below the breadcrumb, its border and header
+        page.handle_mouse_event(click(5, 4));
+        assert!(page.s3_panel_selected);
+        assert_eq!(page.props.s3_table_state.selected(), Some(1));
+        assert!(rx.try_recv().is_err());
+        page.handle_mouse_event(click(5, 4));
+        match rx.try_recv().unwrap() {
+            Action::NavigateS3 {
+                step: NavigationStep::Into(location),
+                row: Some(1),
+            } => assert_eq!(location.current_prefix, Some("2024/".into())),
+            other => panic!("unexpected action {:?}", other),
+        }
+
+        page.handle_mouse_event(mouse(MouseEventKind::ScrollDown, 5, 7));
+        page.handle_mouse_event(mouse(MouseEventKind::ScrollDown, 5, 7));
+        assert_eq!(page.props.s3_table_state.selected(), Some(2));
+        page.handle_mouse_event(mouse(MouseEventKind::ScrollUp, 5, 7));
+        assert_eq!(page.props.s3_table_state.selected(), Some(1));
+
+        // an empty local panel is only focused
+        page.handle_mouse_event(click(100, 3));
+        assert!(!page.s3_panel_selected);
+        assert_eq!(page.props.local_table_state.selected(), None);
+
+        // a clicked hint runs its key
+        terminal.draw(|frame| page.render(frame, ())).unwrap();
+        let bottom_line: String = terminal.backend().buffer().content[11 * 160..]
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        let hint = bottom_line
+            .find("'/' filter")
+            .expect("hint of the local panel");
+        let column = bottom_line[..hint].chars().count() as u16;
+        page.handle_mouse_event(click(column + 4, 11));
+        assert!(page.filter_input.is_some());
+    }
 }
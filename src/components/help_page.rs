@@ -1,51 +1,31 @@
 use crate::components::component::{Component, ComponentRender};
+use crate::components::keymap::{displayed_keys, KEY_BINDINGS};
 use crate::model::action::Action;
 use crate::model::state::{ActivePage, State};
+use crate::settings::key_bindings::{KeyAction, KeyBindings, KeyScope};
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{prelude::*, widgets::*};
 use tokio::sync::mpsc::UnboundedSender;
 
 struct Props {
     commands: Vec<Vec<String>>,
+    key_bindings: KeyBindings,
 }
 
 impl From<&State> for Props {
-    fn from(_state: &State) -> Self {
+    fn from(state: &State) -> Self {
         Props {
-            commands: vec![
-                vec![
-                    "Tab/↔".to_string(),
-                    "move between local and s3 panel".to_string(),
-                ],
-                vec![
-                    "s".to_string(),
-                    "select account currently in use".to_string(),
-                ],
-                vec![
-                    "Esc".to_string(),
-                    "move back to the file manager window".to_string(),
-                ],
-                vec![
-                    "↕ / j / k".to_string(),
-                    "move up/down on the lists".to_string(),
-                ],
-                vec![
-                    "t".to_string(),
-                    "select/deselect files to transfer".to_string(),
-                ],
-                vec!["c".to_string(), "create bucket".to_string()],
-                vec!["⌫ / Del".to_string(), "delete item".to_string()],
-                vec![
-                    "l".to_string(),
-                    "show currently selected files to transfer".to_string(),
-                ],
-                vec![
-                    "r".to_string(),
-                    "run currently selected transfers".to_string(),
-                ],
-                vec!["q".to_string(), "quit the application".to_string()],
-                vec!["?".to_string(), "this help page".to_string()],
-            ],
+            commands: KEY_BINDINGS
+                .iter()
+                .filter(|b| b.in_help)
+                .map(|b| {
+                    vec![
+                        displayed_keys(b.command, &state.key_bindings),
+                        b.description.to_string(),
+                    ]
+                })
+                .collect(),
+            key_bindings: state.key_bindings.clone(),
         }
     }
 }
@@ -88,11 +68,19 @@ impl Component for HelpPage {
             return;
         }
 
-        match key.code {
-            KeyCode::Char('q') => {
+        match self.props.key_bindings.action(&key, KeyScope::AllPages) {
+            Some(KeyAction::Quit) => {
                 let _ = self.action_tx.send(Action::Exit);
             }
-            KeyCode::Esc => {
+            Some(KeyAction::NavigateStats) => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::Stats,
+                });
+            }
+            Some(KeyAction::ActivityLog) => {
+                let _ = self.action_tx.send(Action::ToggleActivityLog);
+            }
+            _ if key.code == KeyCode::Esc => {
                 let _ = self.action_tx.send(Action::Navigate {
                     page: ActivePage::FileManager,
                 });
@@ -137,3 +125,30 @@ impl ComponentRender<()> for HelpPage {
         frame.render_widget(table, h_layout[1]);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    #[test]
+    fn renders_with_default_state_at_any_size() {
+        let (tx, _rx) = unbounded_channel::<Action>();
+        let page = HelpPage::new(&State::default(), tx);
+        for (width, height) in [(120, 40), (10, 3), (1, 1)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|frame| page.render(frame, ())).unwrap();
+        }
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|frame| page.render(frame, ())).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("Command Name"));
+    }
+}
@@ -0,0 +1,778 @@
+//! This module provides the list of key bindings shared by the help page and the hint lines
+use crate::settings::key_bindings::KeyBindings;
+use crate::utils::display_width;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Time after which the hint line switches to the next group of hints
+pub const HINT_ROTATION_INTERVAL: Duration = Duration::from_secs(4);
+/// Maximum number of hints displayed on the hint line at once
+pub const MAX_VISIBLE_HINTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    SwitchPanel,
+    SelectAccount,
+    AddCredential,
+    EditCredential,
+    Back,
+    MoveUpDown,
+    PageJump,
+    Browse,
+    Filter,
+    History,
+    Parent,
+    GoTo,
+    ToggleTransfer,
+    CreateBucket,
+    CreateFolder,
+    RefreshBuckets,
+    Delete,
+    DeleteSelected,
+    ObjectMetadata,
+    Preview,
+    CompareWithLocal,
+    AwsCli,
+    Yank,
+    Paste,
+    Rename,
+    RenameLocal,
+    ToggleHidden,
+    CopyContents,
+    TransfersList,
+    RunTransfers,
+    RunHighlighted,
+    MarkTransfer,
+    ToggleManifest,
+    ErrorFilter,
+    SortTransfers,
+    PauseTransfer,
+    CancelTransfer,
+    OpenLocation,
+    SelectionFile,
+    Stats,
+    ActivityLog,
+    StopNetwork,
+    Suspend,
+    Quit,
+    Help,
+    Confirm,
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBinding {
+    pub command: Command,
+    pub keys: &'static str,
+    /// Description displayed on the help page
+    pub description: &'static str,
+    /// Short description displayed on the hint line
+    pub hint: &'static str,
+    /// Popup-only bindings are not listed on the help page
+    pub in_help: bool,
+}
+
+/// Every key binding of the application, in the order displayed on the help page
+pub const KEY_BINDINGS: &[KeyBinding] = &[
+    KeyBinding {
+        command: Command::SwitchPanel,
+        keys: "Tab/↔",
+        description: "move between local and s3 panel",
+        hint: "switch panel",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::SelectAccount,
+        keys: "s",
+        description: "select account currently in use",
+        hint: "s3 account",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::AddCredential,
+        keys: "n",
+        description: "add an account on the account list",
+        hint: "add",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::EditCredential,
+        keys: "e",
+        description: "edit the highlighted account on the account list",
+        hint: "edit",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Back,
+        keys: "Esc",
+        description: "move back to the file manager window",
+        hint: "back",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::MoveUpDown,
+        keys: "↕ / j / k",
+        description: "move up/down on the lists",
+        hint: "move",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::PageJump,
+        keys: "PgUp / PgDn",
+        description: "jump pages on the transfers list",
+        hint: "jump pages",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Browse,
+        keys: "Enter",
+        description: "open the highlighted bucket or directory",
+        hint: "browse",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Filter,
+        keys: "/",
+        description: "filter the rows by name, Enter keeps the filter and Esc clears it",
+        hint: "filter",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::History,
+        keys: "< / >",
+        description: "previous/next location of the s3 panel, Esc also goes back",
+        hint: "history",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Parent,
+        keys: "u",
+        description: "open the parent of the current s3 location",
+        hint: "parent",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::GoTo,
+        keys: "g",
+        description: "go to s3://bucket/prefix, or to a prefix of the opened bucket",
+        hint: "go to",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::ToggleTransfer,
+        keys: "t",
+        description: "select/deselect files to transfer",
+        hint: "transfer select",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::CreateBucket,
+        keys: "c",
+        description: "create bucket",
+        hint: "create bucket",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::CreateFolder,
+        keys: "c",
+        description: "create folder in the current location of a bucket",
+        hint: "create folder",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::RefreshBuckets,
+        keys: "F5",
+        description: "list the buckets again instead of showing the ones kept for the account, inside a bucket list the opened location again",
+        hint: "refresh",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Delete,
+        keys: "⌫ / Del",
+        description: "delete item",
+        hint: "delete",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::DeleteSelected,
+        keys: "D",
+        description: "delete all the selected items",
+        hint: "delete selected",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::ObjectMetadata,
+        keys: "m",
+        description: "show/edit metadata of the highlighted s3 object",
+        hint: "metadata",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Preview,
+        keys: "v",
+        description: "preview the beginning of the highlighted s3 object",
+        hint: "preview",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::CompareWithLocal,
+        keys: "=",
+        description: "compare the highlighted s3 object with the highlighted local file",
+        hint: "compare",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::AwsCli,
+        keys: "a",
+        description: "copy the aws cli command doing the same as the highlighted item or popup",
+        hint: "aws cli",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Yank,
+        keys: "y / Y",
+        description: "yank the highlighted s3 object to copy/move it, 'P' pastes it",
+        hint: "yank",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Paste,
+        keys: "P",
+        description: "copy/move the yanked s3 object into the opened bucket or directory",
+        hint: "paste",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Rename,
+        keys: "n",
+        description: "rename the highlighted s3 object, a leading '/' gives its full key",
+        hint: "rename",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::RenameLocal,
+        keys: "r",
+        description: "rename the highlighted local file or directory in place",
+        hint: "rename",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::ToggleHidden,
+        keys: ".",
+        description: "show or hide dotfiles and hidden files on the local panel",
+        hint: "hidden files",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::CopyContents,
+        keys: "Ctrl+y",
+        description: "copy the text of the highlighted small s3 object to the clipboard",
+        hint: "copy contents",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::TransfersList,
+        keys: "l",
+        description: "show currently selected files to transfer",
+        hint: "transfers list",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::RunTransfers,
+        keys: "r",
+        description: "run currently selected transfers",
+        hint: "run transfers",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::RunHighlighted,
+        keys: "R",
+        description: "run only the highlighted transfer",
+        hint: "run highlighted",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::MarkTransfer,
+        keys: "space",
+        description: "mark transfers, 'r' then runs only the marked ones",
+        hint: "mark",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::ToggleManifest,
+        keys: "M",
+        description: "write manifest.csv of the downloads into their destination directory",
+        hint: "manifest",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::ErrorFilter,
+        keys: "e",
+        description: "list all the transfers again after picking a group of the failure summary",
+        hint: "all transfers",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::SortTransfers,
+        keys: "S / I",
+        description: "sort the transfers by name, progress, status or account / reverse the order",
+        hint: "sort",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::PauseTransfer,
+        keys: "p / u",
+        description: "pause / resume the highlighted transfer, a download continues where it was",
+        hint: "pause/resume",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::CancelTransfer,
+        keys: "X",
+        description: "cancel the highlighted transfer and clean up what it already transferred",
+        hint: "cancel transfer",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::OpenLocation,
+        keys: "o / O",
+        description: "show source/destination of the transfer in the file manager",
+        hint: "open location",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::SelectionFile,
+        keys: "x / L",
+        description: "export the selection to a JSON file / import one into the selection",
+        hint: "export/import",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Stats,
+        keys: "i",
+        description: "statistics of the current session",
+        hint: "statistics",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::ActivityLog,
+        keys: "~",
+        description: "show/hide the recent actions and errors, j/k scroll and f pauses following",
+        hint: "activity log",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::StopNetwork,
+        keys: "Ctrl+x Ctrl+x",
+        description: "stop all the s3 requests and transfers (press twice within a second)",
+        hint: "stop network",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Suspend,
+        keys: "Ctrl+z",
+        description: "suspend to the shell, transfers keep running (`fg` to return)",
+        hint: "suspend",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Quit,
+        keys: "q",
+        description: "quit the application",
+        hint: "quit",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Help,
+        keys: "?",
+        description: "this help page",
+        hint: "help",
+        in_help: true,
+    },
+    KeyBinding {
+        command: Command::Confirm,
+        keys: "Enter",
+        description: "confirm",
+        hint: "confirm",
+        in_help: false,
+    },
+    KeyBinding {
+        command: Command::Cancel,
+        keys: "Esc",
+        description: "cancel",
+        hint: "cancel",
+        in_help: false,
+    },
+];
+
+pub fn binding(command: Command) -> &'static KeyBinding {
+    KEY_BINDINGS
+        .iter()
+        .find(|b| b.command == command)
+        .expect("every command has a key binding")
+}
+
+/// Part of the UI the hints are generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintPanel {
+    Local,
+    S3,
+    Transfers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HintContext {
+    pub panel: HintPanel,
+    pub popup_open: bool,
+    pub has_selection: bool,
+    pub transfers_running: bool,
+    /// Whether the s3 panel shows the content of a bucket rather than the bucket list
+    pub inside_bucket: bool,
+}
+
+/// Commands relevant for the given context, the most important first
+pub fn contextual_commands(ctx: &HintContext) -> Vec<Command> {
+    if ctx.popup_open {
+        return vec![Command::Confirm, Command::Cancel];
+    }
+    let mut commands = Vec::new();
+    match ctx.panel {
+        HintPanel::Transfers => {
+            if ctx.has_selection && !ctx.transfers_running {
+                commands.extend([
+                    Command::RunTransfers,
+                    Command::RunHighlighted,
+                    Command::MarkTransfer,
+                    Command::ToggleManifest,
+                ]);
+            }
+            if ctx.transfers_running {
+                commands.extend([
+                    Command::Stats,
+                    Command::PauseTransfer,
+                    Command::CancelTransfer,
+                ]);
+            }
+            if ctx.has_selection {
+                commands.extend([
+                    Command::OpenLocation,
+                    Command::SortTransfers,
+                    Command::SelectionFile,
+                    Command::AwsCli,
+                    Command::Delete,
+                    Command::PageJump,
+                ]);
+            }
+            commands.extend([Command::Back, Command::SelectAccount]);
+        }
+        HintPanel::S3 | HintPanel::Local => {
+            if ctx.has_selection {
+                commands.push(Command::TransfersList);
+            }
+            if ctx.transfers_running {
+                commands.push(Command::Stats);
+            }
+            commands.extend([Command::ToggleTransfer, Command::Browse, Command::Filter]);
+            if ctx.panel == HintPanel::S3 {
+                if ctx.inside_bucket {
+                    commands.extend([
+                        Command::Back,
+                        Command::Parent,
+                        Command::History,
+                        Command::GoTo,
+                        Command::CreateFolder,
+                        Command::ObjectMetadata,
+                        Command::Preview,
+                        Command::CompareWithLocal,
+                        Command::AwsCli,
+                        Command::Yank,
+                        Command::Paste,
+                        Command::Rename,
+                        Command::CopyContents,
+                    ]);
+                } else {
+                    commands.extend([
+                        Command::GoTo,
+                        Command::CreateBucket,
+                        Command::RefreshBuckets,
+                    ]);
+                }
+            } else {
+                commands.extend([Command::RenameLocal, Command::ToggleHidden]);
+            }
+            if ctx.has_selection {
+                commands.push(Command::DeleteSelected);
+            }
+            commands.extend([
+                Command::Delete,
+                Command::SwitchPanel,
+                Command::SelectAccount,
+            ]);
+        }
+    }
+    commands.push(Command::Help);
+    commands
+}
+
+/// Picks up to `MAX_VISIBLE_HINTS` commands, cycling through all of them as `rotation` grows
+pub fn visible_commands(ctx: &HintContext, rotation: usize) -> Vec<Command> {
+    let commands = contextual_commands(ctx);
+    if commands.len() <= MAX_VISIBLE_HINTS {
+        return commands;
+    }
+    let pages = commands.len().div_ceil(MAX_VISIBLE_HINTS);
+    commands
+        .chunks(MAX_VISIBLE_HINTS)
+        .nth(rotation % pages)
+        .map(|c| c.to_vec())
+        .unwrap_or_default()
+}
+
+/// Keys of the command as displayed, the ones replaced in the key bindings file if any
+pub fn displayed_keys(command: Command, key_bindings: &KeyBindings) -> String {
+    key_bindings
+        .customized_keys(command)
+        .unwrap_or_else(|| binding(command).keys.to_string())
+}
+
+fn hint_text(command: Command, key_bindings: &KeyBindings) -> String {
+    format!(
+        "'{}' {}",
+        displayed_keys(command, key_bindings),
+        binding(command).hint
+    )
+}
+
+/// Spans of a key hint in the bottom border of a popup, `label(key)|`
+pub fn hint_spans<'a>(key: impl Into<Cow<'a, str>>, label: &'a str) -> Vec<Span<'a>> {
+    vec![
+        Span::styled(label, Style::default().fg(Color::Yellow)),
+        Span::raw("("),
+        Span::styled(
+            key,
+            Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+        ),
+        Span::raw(")|"),
+    ]
+}
+
+pub fn hint_line(ctx: &HintContext, rotation: usize, key_bindings: &KeyBindings) -> String {
+    let hints: Vec<String> = visible_commands(ctx, rotation)
+        .into_iter()
+        .map(|c| hint_text(c, key_bindings))
+        .collect();
+    format!("| {} ", hints.join(", "))
+}
+
+/// Command of the hint displayed at `column` of the hint line, counted from its first character
+pub fn hint_at(
+    ctx: &HintContext,
+    rotation: usize,
+    key_bindings: &KeyBindings,
+    column: usize,
+) -> Option<Command> {
+    let mut start = display_width("| ");
+    for command in visible_commands(ctx, rotation) {
+        let end = start + display_width(&hint_text(command, key_bindings));
+        if (start..end).contains(&column) {
+            return Some(command);
+        }
+        start = end + display_width(", ");
+    }
+    None
+}
+
+/// Key the page gets when the hint of the command is clicked, `None` for the keys handled
+/// before the pages
+pub fn hint_key(command: Command, key_bindings: &KeyBindings) -> Option<KeyEvent> {
+    let key = |code| Some(KeyEvent::new(code, KeyModifiers::NONE));
+    match command {
+        Command::Browse | Command::Confirm => key(KeyCode::Enter),
+        Command::Back | Command::Cancel => key(KeyCode::Esc),
+        Command::SwitchPanel => key(KeyCode::Tab),
+        Command::MoveUpDown => key(KeyCode::Down),
+        Command::PageJump => key(KeyCode::PageDown),
+        // the same key creates a bucket or a folder
+        Command::CreateFolder => key_bindings.command_key(Command::CreateBucket),
+        Command::StopNetwork | Command::Suspend => None,
+        _ => key_bindings.command_key(command),
+    }
+}
+
+/// Rotation index for hints displayed for `elapsed` time
+pub fn rotation_for(elapsed: Duration) -> usize {
+    (elapsed.as_secs() / HINT_ROTATION_INTERVAL.as_secs()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::key_bindings::parse_key_bindings;
+
+    fn ctx(panel: HintPanel) -> HintContext {
+        HintContext {
+            panel,
+            popup_open: false,
+            has_selection: false,
+            transfers_running: false,
+            inside_bucket: false,
+        }
+    }
+
+    #[test]
+    fn every_command_has_a_binding() {
+        for b in KEY_BINDINGS {
+            assert_eq!(binding(b.command).command, b.command);
+        }
+    }
+
+    #[test]
+    fn popup_shows_only_confirmation_hints() {
+        for panel in [HintPanel::Local, HintPanel::S3, HintPanel::Transfers] {
+            let c = HintContext {
+                popup_open: true,
+                has_selection: true,
+                ..ctx(panel)
+            };
+            assert_eq!(
+                contextual_commands(&c),
+                vec![Command::Confirm, Command::Cancel]
+            );
+        }
+    }
+
+    #[test]
+    fn s3_panel_hints_depend_on_location() {
+        let buckets = contextual_commands(&ctx(HintPanel::S3));
+        assert!(buckets.contains(&Command::CreateBucket));
+        assert!(buckets.contains(&Command::RefreshBuckets));
+        assert!(!buckets.contains(&Command::Back));
+
+        let inside = contextual_commands(&HintContext {
+            inside_bucket: true,
+            ..ctx(HintPanel::S3)
+        });
+        assert!(inside.contains(&Command::Back));
+        assert!(inside.contains(&Command::ObjectMetadata));
+        assert!(inside.contains(&Command::Preview));
+        assert!(inside.contains(&Command::Yank));
+        assert!(!inside.contains(&Command::CreateBucket));
+        assert!(inside.contains(&Command::CreateFolder));
+        assert!(inside.contains(&Command::Rename));
+        assert!(!buckets.contains(&Command::CreateFolder));
+        assert!(!inside.contains(&Command::RefreshBuckets));
+        assert!(buckets.contains(&Command::GoTo) && inside.contains(&Command::GoTo));
+
+        let local = contextual_commands(&ctx(HintPanel::Local));
+        assert!(!local.contains(&Command::CreateBucket));
+        assert!(local.contains(&Command::RenameLocal));
+        assert!(local.contains(&Command::ToggleHidden));
+        assert!(!inside.contains(&Command::RenameLocal));
+    }
+
+    #[test]
+    fn selection_brings_transfers_list_first() {
+        let c = HintContext {
+            has_selection: true,
+            ..ctx(HintPanel::Local)
+        };
+        assert_eq!(contextual_commands(&c)[0], Command::TransfersList);
+        assert!(contextual_commands(&c).contains(&Command::DeleteSelected));
+        assert_eq!(
+            contextual_commands(&ctx(HintPanel::Local))[0],
+            Command::ToggleTransfer
+        );
+        assert!(!contextual_commands(&ctx(HintPanel::Local)).contains(&Command::DeleteSelected));
+    }
+
+    #[test]
+    fn transfers_page_hints_follow_the_run_state() {
+        let empty = contextual_commands(&ctx(HintPanel::Transfers));
+        assert_eq!(
+            empty,
+            vec![Command::Back, Command::SelectAccount, Command::Help]
+        );
+
+        let queued = contextual_commands(&HintContext {
+            has_selection: true,
+            ..ctx(HintPanel::Transfers)
+        });
+        assert_eq!(queued[0], Command::RunTransfers);
+
+        let running = contextual_commands(&HintContext {
+            has_selection: true,
+            transfers_running: true,
+            ..ctx(HintPanel::Transfers)
+        });
+        assert_eq!(running[0], Command::Stats);
+        assert!(!running.contains(&Command::RunTransfers));
+    }
+
+    #[test]
+    fn hints_rotate_when_they_do_not_fit() {
+        let c = ctx(HintPanel::S3);
+        let all = contextual_commands(&c);
+        assert!(all.len() > MAX_VISIBLE_HINTS);
+
+        let pages = all.len().div_ceil(MAX_VISIBLE_HINTS);
+        let first = visible_commands(&c, 0);
+        let last = visible_commands(&c, pages - 1);
+        assert_eq!(first.len(), MAX_VISIBLE_HINTS);
+        assert_eq!(first, all[..MAX_VISIBLE_HINTS].to_vec());
+        assert_eq!(last, all[(pages - 1) * MAX_VISIBLE_HINTS..].to_vec());
+        assert_eq!(visible_commands(&c, pages), first);
+    }
+
+    #[test]
+    fn hint_line_uses_keys_from_the_registry() {
+        let line = hint_line(&ctx(HintPanel::Transfers), 0, &KeyBindings::default());
+        assert_eq!(line, "| 'Esc' back, 's' s3 account, '?' help ");
+        assert_eq!(rotation_for(Duration::from_secs(9)), 2);
+    }
+
+    #[test]
+    fn clicked_hint_gives_the_key_of_its_command() {
+        let key_bindings = parse_key_bindings("navigate_accounts = \"ctrl+a\"").unwrap();
+        let c = ctx(HintPanel::Transfers);
+        // | 'Esc' back, 'Ctrl+a' s3 account, '?' help
+        let hint = |column| hint_at(&c, 0, &key_bindings, column);
+        assert_eq!(hint(0), None);
+        assert_eq!(hint(2), Some(Command::Back));
+        assert_eq!(hint(11), Some(Command::Back));
+        assert_eq!(hint(12), None);
+        assert_eq!(hint(14), Some(Command::SelectAccount));
+        assert_eq!(hint(36), Some(Command::Help));
+        assert_eq!(hint(44), None);
+
+        assert_eq!(
+            hint_key(Command::SelectAccount, &key_bindings),
+            Some(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            hint_key(Command::Back, &key_bindings),
+            Some(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))
+        );
+        assert_eq!(
+            hint_key(Command::CreateFolder, &key_bindings),
+            Some(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE))
+        );
+        assert_eq!(hint_key(Command::StopNetwork, &key_bindings), None);
+    }
+
+    #[test]
+    fn hint_line_shows_the_rebound_keys() {
+        let key_bindings = parse_key_bindings("navigate_accounts = \"ctrl+a\"").unwrap();
+        let line = hint_line(&ctx(HintPanel::Transfers), 0, &key_bindings);
+        assert_eq!(line, "| 'Esc' back, 'Ctrl+a' s3 account, '?' help ");
+    }
+
+    #[test]
+    fn hint_spans_read_label_then_key() {
+        let spans = hint_spans("Enter", "use");
+        let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(text, "use(Enter)|");
+        assert_eq!(spans[2].style.fg, Some(Color::Red));
+    }
+}
@@ -0,0 +1,673 @@
+//! This module provides the popup displaying and editing the metadata of a single s3 object
+use crate::components::paste::paste_into;
+use crate::model::action::Action;
+use crate::model::object_metadata::{validate_user_metadata, ObjectMetadata};
+use crate::model::storage_class::{
+    check_transition, current_class, transition_warning, STORAGE_CLASSES,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    View,
+    Edit,
+    /// Typing the key of a new pair
+    EditKey,
+    /// Typing the value, `None` index means a new pair with the given key
+    EditValue {
+        index: Option<usize>,
+        key: String,
+    },
+    ConfirmSave,
+    /// Picking the storage class, the index points into `STORAGE_CLASSES`
+    PickStorageClass {
+        index: usize,
+    },
+    ConfirmStorageClass {
+        storage_class: &'static str,
+    },
+}
+
+fn class_index(storage_class: &str) -> usize {
+    STORAGE_CLASSES
+        .iter()
+        .position(|c| *c == storage_class)
+        .unwrap_or(0)
+}
+
+/// Popup opened with 'm' on an s3 object, the user metadata can be edited and saved
+/// and the storage class changed, both rewrite the object in place
+pub struct MetadataPopup {
+    metadata: Option<ObjectMetadata>,
+    /// Edited copy of the user metadata
+    pairs: Vec<(String, String)>,
+    selected: usize,
+    mode: Mode,
+    input: Input,
+    loading: bool,
+    /// Error of the last s3 request or of the validation
+    error: Option<String>,
+}
+
+impl Default for MetadataPopup {
+    /// A popup waiting for the metadata to be fetched
+    fn default() -> Self {
+        MetadataPopup {
+            metadata: None,
+            pairs: Vec::new(),
+            selected: 0,
+            mode: Mode::View,
+            input: Input::default(),
+            loading: true,
+            error: None,
+        }
+    }
+}
+
+impl MetadataPopup {
+    /// Syncs the popup with the state, a changed object (e.g. after a successful save)
+    /// replaces the edited pairs, a failed request keeps them so the save can be retried
+    pub fn refresh(
+        &mut self,
+        metadata: Option<&ObjectMetadata>,
+        loading: bool,
+        error: Option<&String>,
+    ) {
+        if metadata != self.metadata.as_ref() {
+            self.metadata = metadata.cloned();
+            self.pairs = metadata
+                .map(|m| m.user_metadata.clone())
+                .unwrap_or_default();
+            self.selected = 0;
+            self.mode = Mode::View;
+        }
+        if self.loading && !loading {
+            self.error = error.cloned();
+        }
+        self.loading = loading;
+    }
+
+    /// Object shown without any change in progress, 'a' copies the aws cli command downloading it
+    pub fn viewed_object(&self) -> Option<&ObjectMetadata> {
+        self.metadata
+            .as_ref()
+            .filter(|_| !self.loading && self.mode == Mode::View)
+    }
+
+    pub fn is_editing_text(&self) -> bool {
+        matches!(self.mode, Mode::EditKey | Mode::EditValue { .. })
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        if self.pairs.is_empty() {
+            return;
+        }
+        self.selected = if down {
+            (self.selected + 1) % self.pairs.len()
+        } else {
+            (self.selected + self.pairs.len() - 1) % self.pairs.len()
+        };
+    }
+
+    fn discard_changes(&mut self) {
+        self.pairs = self
+            .metadata
+            .as_ref()
+            .map(|m| m.user_metadata.clone())
+            .unwrap_or_default();
+        self.selected = 0;
+        self.error = None;
+        self.mode = Mode::View;
+    }
+
+    fn current_storage_class(&self) -> &str {
+        current_class(
+            self.metadata
+                .as_ref()
+                .and_then(|m| m.storage_class.as_deref()),
+        )
+    }
+
+    /// Starts picking with the current class highlighted
+    fn pick_storage_class(&mut self) {
+        let index = class_index(self.current_storage_class());
+        self.error = None;
+        self.mode = Mode::PickStorageClass { index };
+    }
+
+    fn edited_metadata(&self) -> Option<ObjectMetadata> {
+        self.metadata.as_ref().map(|m| ObjectMetadata {
+            user_metadata: self.pairs.clone(),
+            ..m.clone()
+        })
+    }
+
+    /// Handles the key, returns the action to be sent by the file manager
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> Option<Action> {
+        if self.loading {
+            return match key.code {
+                KeyCode::Esc => Some(Action::CloseObjectMetadata),
+                _ => None,
+            };
+        }
+        match self.mode.clone() {
+            Mode::View => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.move_selection(true),
+                KeyCode::Char('k') | KeyCode::Up => self.move_selection(false),
+                KeyCode::Char('e') if self.metadata.is_some() => {
+                    self.error = None;
+                    self.mode = Mode::Edit;
+                }
+                KeyCode::Char('c') if self.metadata.is_some() => self.pick_storage_class(),
+                KeyCode::Char('u') => {
+                    return self.metadata.as_ref().map(|m| Action::PresignObjectUrl {
+                        bucket: m.bucket.clone(),
+                        key: m.key.clone(),
+                    });
+                }
+                KeyCode::Esc => return Some(Action::CloseObjectMetadata),
+                _ => {}
+            },
+            Mode::PickStorageClass { index } => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.mode = Mode::PickStorageClass {
+                        index: (index + 1) % STORAGE_CLASSES.len(),
+                    }
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.mode = Mode::PickStorageClass {
+                        index: (index + STORAGE_CLASSES.len() - 1) % STORAGE_CLASSES.len(),
+                    }
+                }
+                KeyCode::Enter => {
+                    let storage_class = STORAGE_CLASSES[index];
+                    let current = self.current_storage_class().to_string();
+                    match check_transition(Some(&current), storage_class) {
+                        Ok(()) => {
+                            self.error = None;
+                            self.mode = Mode::ConfirmStorageClass { storage_class };
+                        }
+                        Err(e) => self.error = Some(e),
+                    }
+                }
+                KeyCode::Esc => {
+                    self.error = None;
+                    self.mode = Mode::View;
+                }
+                _ => {}
+            },
+            Mode::ConfirmStorageClass { storage_class } => match key.code {
+                KeyCode::Enter => {
+                    self.mode = Mode::View;
+                    return self.metadata.as_ref().map(|m| Action::ChangeStorageClass {
+                        bucket: m.bucket.clone(),
+                        key: m.key.clone(),
+                        storage_class: storage_class.to_string(),
+                    });
+                }
+                KeyCode::Esc => {
+                    self.mode = Mode::PickStorageClass {
+                        index: class_index(storage_class),
+                    }
+                }
+                _ => {}
+            },
+            Mode::Edit => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.move_selection(true),
+                KeyCode::Char('k') | KeyCode::Up => self.move_selection(false),
+                KeyCode::Char('a') => {
+                    self.input.reset();
+                    self.mode = Mode::EditKey;
+                }
+                KeyCode::Enter => {
+                    if let Some((key, value)) = self.pairs.get(self.selected) {
+                        self.input = Input::default().with_value(value.clone());
+                        self.mode = Mode::EditValue {
+                            index: Some(self.selected),
+                            key: key.clone(),
+                        };
+                    }
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if self.selected < self.pairs.len() {
+                        self.pairs.remove(self.selected);
+                        self.selected = self.selected.min(self.pairs.len().saturating_sub(1));
+                    }
+                }
+                KeyCode::Char('s') => match validate_user_metadata(&self.pairs) {
+                    Ok(()) => {
+                        self.error = None;
+                        self.mode = Mode::ConfirmSave;
+                    }
+                    Err(e) => self.error = Some(e),
+                },
+                KeyCode::Esc => self.discard_changes(),
+                _ => {}
+            },
+            Mode::EditKey => match key.code {
+                KeyCode::Enter => {
+                    let key = self.input.value().trim().to_string();
+                    self.input.reset();
+                    self.mode = Mode::EditValue { index: None, key };
+                }
+                KeyCode::Esc => self.mode = Mode::Edit,
+                _ => {
+                    let _ = self.input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            },
+            Mode::EditValue {
+                index,
+                key: pair_key,
+            } => match key.code {
+                KeyCode::Enter => {
+                    let value = self.input.value().to_string();
+                    match index {
+                        Some(i) => self.pairs[i].1 = value,
+                        None => {
+                            self.pairs.push((pair_key, value));
+                            self.selected = self.pairs.len() - 1;
+                        }
+                    }
+                    self.mode = Mode::Edit;
+                }
+                KeyCode::Esc => self.mode = Mode::Edit,
+                _ => {
+                    let _ = self.input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            },
+            Mode::ConfirmSave => match key.code {
+                KeyCode::Enter => {
+                    // stays in the edit mode, a successful save brings new metadata
+                    self.mode = Mode::Edit;
+                    return self
+                        .edited_metadata()
+                        .map(|metadata| Action::SaveObjectMetadata { metadata });
+                }
+                KeyCode::Esc => self.mode = Mode::Edit,
+                _ => {}
+            },
+        }
+        None
+    }
+
+    /// Pasted text goes into the key or value being typed, a submitting paste confirms it
+    /// like Enter. Pastes outside of the text inputs are ignored
+    pub fn handle_paste(&mut self, text: &str, submit_single_line: bool) -> Option<Action> {
+        if !self.is_editing_text() {
+            return None;
+        }
+        if paste_into(&mut self.input, text, submit_single_line) {
+            return self.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        }
+        None
+    }
+
+    fn key_hints(&self) -> Line {
+        let hint = |key: &'static str, text: &'static str| {
+            vec![
+                Span::raw("|"),
+                Span::styled(text, Style::default().fg(Color::Yellow)),
+                Span::raw("("),
+                Span::styled(
+                    key,
+                    Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+                ),
+                Span::raw(")"),
+            ]
+        };
+        let hints = match self.mode {
+            Mode::View => vec![
+                hint("e", "edit"),
+                hint("c", "storage class"),
+                hint("u", "presigned url"),
+                hint("a", "aws cli"),
+                hint("Esc", "close"),
+            ],
+            Mode::Edit => vec![
+                hint("a", "add"),
+                hint("Enter", "change value"),
+                hint("d", "remove"),
+                hint("s", "save"),
+                hint("Esc", "discard"),
+            ],
+            Mode::EditKey | Mode::EditValue { .. } => {
+                vec![hint("Enter", "ok"), hint("Esc", "cancel")]
+            }
+            Mode::ConfirmSave => vec![hint("Enter", "save"), hint("Esc", "cancel")],
+            Mode::PickStorageClass { .. } => vec![hint("Enter", "select"), hint("Esc", "cancel")],
+            Mode::ConfirmStorageClass { .. } => {
+                vec![hint("Enter", "change"), hint("Esc", "cancel")]
+            }
+        };
+        let mut spans: Vec<Span> = hints.into_iter().flatten().collect();
+        spans.push(Span::raw("|"));
+        Line::from(spans)
+    }
+
+    fn footer(&self) -> (String, Color) {
+        if self.loading {
+            return ("Loading...".to_string(), Color::Green);
+        }
+        if let Some(error) = &self.error {
+            return (format!("* {}", error), Color::Red);
+        }
+        match &self.mode {
+            Mode::EditKey => (format!("key: {}", self.input.value()), Color::Green),
+            Mode::EditValue { key, .. } => {
+                (format!("{}: {}", key, self.input.value()), Color::Green)
+            }
+            Mode::ConfirmSave => (
+                "Saving copies the object onto itself, its ETag and last modified date will change. Proceed?"
+                    .to_string(),
+                Color::Yellow,
+            ),
+            Mode::ConfirmStorageClass { storage_class } => {
+                (transition_warning(storage_class), Color::Yellow)
+            }
+            _ => (String::new(), Color::White),
+        }
+    }
+
+    fn render_metadata(&self, frame: &mut Frame, area: Rect) {
+        let system_rows = self
+            .metadata
+            .as_ref()
+            .map(|m| m.system_rows())
+            .unwrap_or_default();
+        let mut rows: Vec<Row> = system_rows
+            .into_iter()
+            .map(|(k, v)| Row::new(vec![k, v]).style(Style::default().fg(Color::DarkGray)))
+            .collect();
+        rows.push(
+            Row::new(vec!["User metadata".to_string(), String::new()])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+        let highlight = self.mode != Mode::View || !self.pairs.is_empty();
+        for (i, (key, value)) in self.pairs.iter().enumerate() {
+            let row = Row::new(vec![format!("x-amz-meta-{}", key), value.clone()]);
+            rows.push(if highlight && i == self.selected {
+                row.style(Style::default().fg(Color::Black).bg(Color::Yellow))
+            } else {
+                row
+            });
+        }
+        if self.pairs.is_empty() && self.metadata.is_some() {
+            rows.push(Row::new(vec!["-".to_string(), String::new()]));
+        }
+        let table = Table::new(
+            rows,
+            [Constraint::Percentage(40), Constraint::Percentage(60)],
+        );
+        frame.render_widget(table, area);
+    }
+
+    fn render_storage_classes(&self, frame: &mut Frame, area: Rect, index: usize) {
+        let current = self.current_storage_class();
+        let mut rows = vec![Row::new(vec!["Storage class".to_string(), String::new()])
+            .style(Style::default().add_modifier(Modifier::BOLD))];
+        for (i, storage_class) in STORAGE_CLASSES.iter().enumerate() {
+            let note = if *storage_class == current {
+                "current"
+            } else {
+                ""
+            };
+            let row = Row::new(vec![storage_class.to_string(), note.to_string()]);
+            rows.push(if i == index {
+                row.style(Style::default().fg(Color::Black).bg(Color::Yellow))
+            } else {
+                row
+            });
+        }
+        let table = Table::new(
+            rows,
+            [Constraint::Percentage(40), Constraint::Percentage(60)],
+        );
+        frame.render_widget(table, area);
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let title = self
+            .metadata
+            .as_ref()
+            .map(|m| format!("| Metadata of {} |", m.key))
+            .unwrap_or("| Metadata |".to_string());
+        let block = Block::default().borders(Borders::ALL).title(title).title(
+            block::Title::from(self.key_hints())
+                .alignment(Alignment::Left)
+                .position(block::Position::Bottom),
+        );
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).split(inner);
+        match self.mode {
+            Mode::PickStorageClass { index } => {
+                self.render_storage_classes(frame, chunks[0], index)
+            }
+            Mode::ConfirmStorageClass { storage_class } => {
+                self.render_storage_classes(frame, chunks[0], class_index(storage_class))
+            }
+            _ => self.render_metadata(frame, chunks[0]),
+        }
+
+        let (text, color) = self.footer();
+        frame.render_widget(
+            Paragraph::new(text)
+                .style(Style::default().fg(color))
+                .wrap(Wrap { trim: true }),
+            chunks[1],
+        );
+        if self.is_editing_text() && self.error.is_none() {
+            let prefix = match &self.mode {
+                Mode::EditValue { key, .. } => key.len() + 2,
+                _ => "key: ".len(),
+            };
+            frame.set_cursor(
+                chunks[1].x + (prefix + self.input.visual_cursor()) as u16,
+                chunks[1].y,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(popup: &mut MetadataPopup, code: KeyCode) -> Option<Action> {
+        popup.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(popup: &mut MetadataPopup, text: &str) {
+        for c in text.chars() {
+            press(popup, KeyCode::Char(c));
+        }
+    }
+
+    fn loaded_popup() -> (MetadataPopup, ObjectMetadata) {
+        let metadata = ObjectMetadata {
+            bucket: "bucket".into(),
+            key: "data/file.csv".into(),
+            user_metadata: vec![("pipeline-stage".into(), "raw".into())],
+            ..ObjectMetadata::default()
+        };
+        let mut popup = MetadataPopup::default();
+        popup.refresh(Some(&metadata), false, None);
+        (popup, metadata)
+    }
+
+    #[test]
+    fn presigned_url_is_requested_for_the_viewed_object() {
+        let mut popup = MetadataPopup::default();
+        popup.refresh(None, true, None);
+        assert_eq!(press(&mut popup, KeyCode::Char('u')), None);
+
+        let (mut popup, _) = loaded_popup();
+        assert_eq!(
+            press(&mut popup, KeyCode::Char('u')),
+            Some(Action::PresignObjectUrl {
+                bucket: "bucket".into(),
+                key: "data/file.csv".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn added_pair_is_saved_after_confirmation() {
+        let (mut popup, metadata) = loaded_popup();
+        press(&mut popup, KeyCode::Char('e'));
+        press(&mut popup, KeyCode::Char('a'));
+        type_text(&mut popup, "owner");
+        press(&mut popup, KeyCode::Enter);
+        type_text(&mut popup, "data-team");
+        press(&mut popup, KeyCode::Enter);
+
+        assert_eq!(press(&mut popup, KeyCode::Char('s')), None);
+        assert_eq!(popup.mode, Mode::ConfirmSave);
+        let action = press(&mut popup, KeyCode::Enter);
+        assert_eq!(
+            action,
+            Some(Action::SaveObjectMetadata {
+                metadata: ObjectMetadata {
+                    user_metadata: vec![
+                        ("pipeline-stage".into(), "raw".into()),
+                        ("owner".into(), "data-team".into()),
+                    ],
+                    ..metadata
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn pasted_value_is_inserted_once() {
+        let (mut popup, _) = loaded_popup();
+        // the view mode has no input, 'e' in the text isn't taken for a key
+        assert_eq!(popup.handle_paste("e", false), None);
+        assert_eq!(popup.mode, Mode::View);
+
+        press(&mut popup, KeyCode::Char('e'));
+        press(&mut popup, KeyCode::Char('a'));
+        assert_eq!(popup.handle_paste("owner\n", false), None);
+        assert_eq!(popup.mode, Mode::EditKey);
+        assert_eq!(popup.input.value(), "owner");
+        press(&mut popup, KeyCode::Enter);
+        popup.handle_paste("data-team\n", true);
+        assert_eq!(popup.mode, Mode::Edit);
+        assert_eq!(popup.pairs[1], ("owner".into(), "data-team".into()));
+    }
+
+    #[test]
+    fn invalid_key_is_reported_instead_of_saving() {
+        let (mut popup, _) = loaded_popup();
+        press(&mut popup, KeyCode::Char('e'));
+        press(&mut popup, KeyCode::Char('a'));
+        type_text(&mut popup, "Owner");
+        press(&mut popup, KeyCode::Enter);
+        press(&mut popup, KeyCode::Enter);
+
+        assert_eq!(press(&mut popup, KeyCode::Char('s')), None);
+        assert_eq!(popup.mode, Mode::Edit);
+        assert!(popup
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("Invalid key 'Owner'"));
+    }
+
+    #[test]
+    fn escape_discards_changes_and_then_closes() {
+        let (mut popup, metadata) = loaded_popup();
+        press(&mut popup, KeyCode::Char('e'));
+        press(&mut popup, KeyCode::Char('d'));
+        assert!(popup.pairs.is_empty());
+
+        assert_eq!(press(&mut popup, KeyCode::Esc), None);
+        assert_eq!(popup.pairs, metadata.user_metadata);
+        assert_eq!(
+            press(&mut popup, KeyCode::Esc),
+            Some(Action::CloseObjectMetadata)
+        );
+    }
+
+    #[test]
+    fn storage_class_changes_after_confirmation() {
+        let (mut popup, _) = loaded_popup();
+        press(&mut popup, KeyCode::Char('c'));
+        assert_eq!(popup.mode, Mode::PickStorageClass { index: 0 });
+
+        // the current class is not offered again
+        assert_eq!(press(&mut popup, KeyCode::Enter), None);
+        assert!(popup.error.is_some());
+
+        press(&mut popup, KeyCode::Char('j'));
+        press(&mut popup, KeyCode::Char('j'));
+        assert_eq!(press(&mut popup, KeyCode::Enter), None);
+        assert_eq!(
+            popup.mode,
+            Mode::ConfirmStorageClass {
+                storage_class: "STANDARD_IA"
+            }
+        );
+        assert!(popup.footer().0.contains("minimum storage duration"));
+        assert_eq!(
+            press(&mut popup, KeyCode::Enter),
+            Some(Action::ChangeStorageClass {
+                bucket: "bucket".into(),
+                key: "data/file.csv".into(),
+                storage_class: "STANDARD_IA".into(),
+            })
+        );
+        assert_eq!(popup.mode, Mode::View);
+    }
+
+    #[test]
+    fn archived_object_points_to_the_restore() {
+        let metadata = ObjectMetadata {
+            storage_class: Some("GLACIER".into()),
+            ..ObjectMetadata::default()
+        };
+        let mut popup = MetadataPopup::default();
+        popup.refresh(Some(&metadata), false, None);
+        press(&mut popup, KeyCode::Char('c'));
+        assert_eq!(popup.mode, Mode::PickStorageClass { index: 5 });
+
+        press(&mut popup, KeyCode::Char('k'));
+        press(&mut popup, KeyCode::Char('k'));
+        press(&mut popup, KeyCode::Char('k'));
+        assert_eq!(press(&mut popup, KeyCode::Enter), None);
+        assert!(popup.error.as_ref().unwrap().contains("restore-object"));
+        assert_eq!(press(&mut popup, KeyCode::Esc), None);
+        assert_eq!(popup.mode, Mode::View);
+    }
+
+    #[test]
+    fn failed_save_keeps_edits_and_successful_one_resets_them() {
+        let (mut popup, metadata) = loaded_popup();
+        press(&mut popup, KeyCode::Char('e'));
+        press(&mut popup, KeyCode::Enter);
+        type_text(&mut popup, "-v2");
+        press(&mut popup, KeyCode::Enter);
+        press(&mut popup, KeyCode::Char('s'));
+        press(&mut popup, KeyCode::Enter);
+
+        popup.refresh(Some(&metadata), true, None);
+        popup.refresh(Some(&metadata), false, Some(&"Access Denied".to_string()));
+        assert_eq!(popup.pairs[0].1, "raw-v2");
+        assert_eq!(popup.error, Some("Access Denied".into()));
+        assert_eq!(popup.mode, Mode::Edit);
+
+        let saved = ObjectMetadata {
+            user_metadata: vec![("pipeline-stage".into(), "raw-v2".into())],
+            e_tag: Some("\"new\"".into()),
+            ..metadata
+        };
+        popup.refresh(Some(&saved), false, None);
+        assert_eq!(popup.mode, Mode::View);
+        assert_eq!(popup.pairs, saved.user_metadata);
+    }
+}
@@ -1,9 +1,17 @@
 //! This module provides implementations for different UI components used by s3tui app
 
+pub mod activity_log_pane;
 pub mod app_router;
+pub mod clipboard;
 pub mod component;
+pub mod credential_form;
 pub mod file_manager_page;
 pub mod help_page;
+pub mod keymap;
+pub mod metadata_popup;
+pub mod paste;
+pub mod popup_sync;
 
 pub mod s3_creds_page;
+pub mod stats_page;
 pub mod transfers_page;
@@ -0,0 +1,63 @@
+//! This module provides the handling of text pasted into the inputs, with bracketed paste
+//! the terminal delivers it as one event instead of a key event for every character
+use tui_input::Input;
+
+/// Inserts the pasted text at the cursor in one go, line breaks are dropped so they don't
+/// submit the input. Returns whether the paste submits the input, which happens only with
+/// `submit_single_line` for a single line ended with a line break
+pub fn paste_into(input: &mut Input, text: &str, submit_single_line: bool) -> bool {
+    let pasted: String = text.chars().filter(|c| *c != '\n' && *c != '\r').collect();
+    let value = input.value();
+    let cursor = input.cursor();
+    let at = value
+        .char_indices()
+        .nth(cursor)
+        .map(|(i, _)| i)
+        .unwrap_or(value.len());
+    let new_value = format!("{}{}{}", &value[..at], pasted, &value[at..]);
+    *input = Input::new(new_value).with_cursor(cursor + pasted.chars().count());
+    submit_single_line && is_single_line(text)
+}
+
+fn is_single_line(text: &str) -> bool {
+    match text.strip_suffix('\n') {
+        Some(line) => {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            !line.is_empty() && !line.contains(['\n', '\r'])
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui_input::InputRequest;
+
+    #[test]
+    fn paste_is_inserted_at_the_cursor() {
+        let mut input = Input::new("s3://bucket".into());
+        for _ in 0.."bucket".len() {
+            input.handle(InputRequest::GoToPrevChar);
+        }
+        assert!(!paste_into(&mut input, "my-", false));
+        assert_eq!(input.value(), "s3://my-bucket");
+        assert_eq!(input.cursor(), "s3://my-".len());
+    }
+
+    #[test]
+    fn line_breaks_are_dropped() {
+        let mut input = Input::default();
+        assert!(!paste_into(&mut input, "team/\r\ncurated/\n", false));
+        assert_eq!(input.value(), "team/curated/");
+    }
+
+    #[test]
+    fn only_a_single_line_submits_when_enabled() {
+        assert!(paste_into(&mut Input::default(), "data-bucket\n", true));
+        assert!(paste_into(&mut Input::default(), "data-bucket\r\n", true));
+        assert!(!paste_into(&mut Input::default(), "data-bucket", true));
+        assert!(!paste_into(&mut Input::default(), "data\nbucket\n", true));
+        assert!(!paste_into(&mut Input::default(), "\n", true));
+    }
+}
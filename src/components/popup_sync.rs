@@ -0,0 +1,53 @@
+//! This module keeps the popups shown from the state as the user left them
+//! until the state store handles the actions of their keys
+
+/// A popup opened or closed with a key waits for the state to agree with it, the states
+/// broadcast meanwhile still show it the other way
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PopupSync {
+    /// The popup follows the state
+    #[default]
+    Synced,
+    /// Opened with a key, the state doesn't show it yet
+    Opening,
+    /// Closed with a key, the state still shows it
+    Closing,
+}
+
+impl PopupSync {
+    /// Whether the popup is open once a state showing it as `open_in_state` arrives
+    pub fn resolve(&mut self, open_in_state: bool) -> bool {
+        match self {
+            PopupSync::Opening if !open_in_state => true,
+            PopupSync::Closing if open_in_state => false,
+            _ => {
+                *self = PopupSync::Synced;
+                open_in_state
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opened_popup_waits_for_the_state_to_show_it() {
+        let mut sync = PopupSync::Opening;
+        assert!(sync.resolve(false));
+        assert!(sync.resolve(false));
+        assert!(sync.resolve(true));
+        assert_eq!(sync, PopupSync::Synced);
+        assert!(!sync.resolve(false));
+    }
+
+    #[test]
+    fn closed_popup_waits_for_the_state_to_hide_it() {
+        let mut sync = PopupSync::Closing;
+        assert!(!sync.resolve(true));
+        assert!(!sync.resolve(false));
+        assert_eq!(sync, PopupSync::Synced);
+        assert!(sync.resolve(true));
+    }
+}
@@ -1,15 +1,31 @@
 use crate::components::component::{Component, ComponentRender};
+use crate::components::credential_form::{CredentialForm, FormEvent};
+use crate::components::keymap::hint_spans;
 use crate::model::action::Action;
 use crate::model::state::{ActivePage, State};
-use crate::settings::file_credentials::FileCredential;
+use crate::services::adaptive_concurrency::AccountLoad;
+use crate::services::startup_work::AccountCheck;
+use crate::settings::color_support::{adapt_color, FOCUS_COLOR};
+use crate::settings::file_credentials::{creds_dir, FileCredential};
+use crate::settings::key_bindings::{KeyAction, KeyBindings, KeyScope};
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{prelude::*, widgets::*};
+use std::collections::{HashMap, HashSet};
 use tokio::sync::mpsc::UnboundedSender;
 
 #[derive(Clone)]
 struct Props {
     creds_table_state: TableState,
     creds_data: Vec<FileCredential>,
+    /// Transfer limits of the accounts which ran transfers
+    account_loads: HashMap<String, AccountLoad>,
+    /// Results of the checks run at startup
+    account_checks: HashMap<String, AccountCheck>,
+    /// Accounts whose skipped tls verification was confirmed in this session
+    skip_tls_verify_confirmed: HashSet<String>,
+    focus_color: Color,
+    key_bindings: KeyBindings,
+    paste_submits_single_line: bool,
 }
 
 impl From<&State> for Props {
@@ -18,16 +34,27 @@ impl From<&State> for Props {
         Props {
             creds_table_state: TableState::default(),
             creds_data: st.creds,
+            account_loads: st.account_loads,
+            account_checks: st.account_checks,
+            skip_tls_verify_confirmed: st.skip_tls_verify_confirmed,
+            focus_color: adapt_color(FOCUS_COLOR, st.color_support),
+            key_bindings: st.key_bindings,
+            paste_submits_single_line: st.app_config.paste_submits_single_line,
         }
     }
 }
 
 /// Page displaying different s3 configuration from your config directory
 /// Selecting an item on this page will use specially configured s3 client up to the next change
-/// or quitting the app
+/// or quitting the app. The credentials can be added, edited and deleted here as well
 pub struct S3CredsPage {
     pub action_tx: UnboundedSender<Action>,
     props: Props,
+    form: Option<CredentialForm>,
+    /// Name of the credential waiting for the confirmation of its deletion
+    confirm_delete: Option<String>,
+    /// Credential with `insecure_skip_tls_verify` used for the first time in the session
+    confirm_skip_verify: Option<FileCredential>,
 }
 
 impl Component for S3CredsPage {
@@ -39,6 +66,9 @@ impl Component for S3CredsPage {
             action_tx: action_tx.clone(),
             // set the props
             props: Props::from(state),
+            form: None,
+            confirm_delete: None,
+            confirm_skip_verify: None,
         }
         .move_with_state(state)
     }
@@ -65,74 +95,259 @@ impl Component for S3CredsPage {
         if key.kind != KeyEventKind::Press {
             return;
         }
-        match key.code {
-            KeyCode::Char('j') | KeyCode::Down => self.move_down_creds_table_selection(),
-            KeyCode::Char('k') | KeyCode::Up => self.move_up_creds_table_selection(),
-            KeyCode::Enter => self.set_current_s3_account(),
-            KeyCode::Char('q') => {
+        if let Some(form) = self.form.as_mut() {
+            match form.handle_key_event(key, &self.props.creds_data) {
+                Some(FormEvent::Save(entry)) => {
+                    let original = form.original_name();
+                    let _ = self
+                        .action_tx
+                        .send(Action::SaveCredential { original, entry });
+                    self.form = None;
+                }
+                Some(FormEvent::Cancel) => self.form = None,
+                None => {}
+            }
+            return;
+        }
+        if let Some(item) = self.confirm_skip_verify.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Enter) {
+                let account = item.name.clone();
+                let _ = self
+                    .action_tx
+                    .send(Action::ConfirmSkipTlsVerify { account });
+                let _ = self.action_tx.send(Action::SelectCurrentS3Creds { item });
+            }
+            return;
+        }
+        if let Some(name) = self.confirm_delete.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Enter) {
+                let _ = self.action_tx.send(Action::DeleteCredential { name });
+            }
+            return;
+        }
+        match self.props.key_bindings.action(&key, KeyScope::Accounts) {
+            Some(KeyAction::Quit) => {
                 let _ = self.action_tx.send(Action::Exit);
             }
-            KeyCode::Char('?') => {
+            Some(KeyAction::NavigateHelp) => {
                 let _ = self.action_tx.send(Action::Navigate {
                     page: ActivePage::Help,
                 });
             }
-            KeyCode::Esc => {
+            Some(KeyAction::NavigateStats) => {
                 let _ = self.action_tx.send(Action::Navigate {
-                    page: ActivePage::FileManager,
+                    page: ActivePage::Stats,
                 });
             }
-            _ => {}
+            Some(KeyAction::ActivityLog) => {
+                let _ = self.action_tx.send(Action::ToggleActivityLog);
+            }
+            Some(KeyAction::Delete) => {
+                self.confirm_delete = self.highlighted_creds().map(|cred| cred.name.clone());
+            }
+            Some(KeyAction::AddCredential) => self.form = Some(CredentialForm::new_credential()),
+            Some(KeyAction::EditCredential) => {
+                self.form = self.highlighted_creds().map(CredentialForm::edit);
+            }
+            _ => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.move_down_creds_table_selection(),
+                KeyCode::Char('k') | KeyCode::Up => self.move_up_creds_table_selection(),
+                KeyCode::Enter => self.set_current_s3_account(),
+                KeyCode::Esc => {
+                    let _ = self.action_tx.send(Action::Navigate {
+                        page: ActivePage::FileManager,
+                    });
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn handle_paste(&mut self, text: &str) {
+        let submit = self.props.paste_submits_single_line;
+        if let Some(form) = self.form.as_mut() {
+            if let Some(FormEvent::Save(entry)) =
+                form.handle_paste(text, submit, &self.props.creds_data)
+            {
+                let original = form.original_name();
+                let _ = self
+                    .action_tx
+                    .send(Action::SaveCredential { original, entry });
+                self.form = None;
+            }
         }
     }
 }
 
 impl S3CredsPage {
     fn get_s3_row(&self, item: &FileCredential) -> Row {
-        if item.selected {
-            Row::new(vec![format!("{} (*)", item.name)])
+        let name = if item.selected {
+            format!("{} (*){}", item.name, self.tls_badge(item))
         } else {
-            Row::new(vec![format!("{}", item.name)])
+            format!("{}{}", item.name, self.tls_badge(item))
+        };
+        let check = self
+            .props
+            .account_checks
+            .get(&item.name)
+            .map(|check| check.label().to_string())
+            .unwrap_or_default();
+        let mut columns = vec![name, item.source(), check];
+        columns.extend(Self::load_columns(self.props.account_loads.get(&item.name)));
+        Row::new(columns)
+    }
+
+    /// Badge of the accounts with `insecure_skip_tls_verify`, the certificates are verified
+    /// until it's confirmed when the account is used
+    fn tls_badge(&self, item: &FileCredential) -> &'static str {
+        let confirmed = self.props.skip_tls_verify_confirmed.contains(&item.name);
+        match (item.insecure_skip_tls_verify, confirmed) {
+            (false, _) => "",
+            (true, true) => " [INSECURE TLS]",
+            (true, false) => " [insecure TLS, unconfirmed]",
+        }
+    }
+
+    /// Effective transfer limit, recent overload errors and average latency of the account,
+    /// empty until it ran transfers
+    fn load_columns(load: Option<&AccountLoad>) -> [String; 3] {
+        match load {
+            Some(load) => [
+                format!("{} of {}", load.limit, load.configured),
+                format!("{:.0}%", load.error_rate * 100.0),
+                load.average_latency
+                    .map(|latency| format!("{} ms", latency.as_millis()))
+                    .unwrap_or_default(),
+            ],
+            None => Default::default(),
         }
     }
 
     fn get_s3_table(&self) -> Table {
-        let focus_color = Color::Rgb(98, 114, 164);
-        let header = Row::new(vec!["Account Name"])
-            .bold()
-            .underlined()
-            .height(1)
-            .bottom_margin(0);
+        let focus_color = self.props.focus_color;
+        let header = Row::new(vec![
+            "Account Name",
+            "Source",
+            "Check",
+            "Transfers",
+            "Errors",
+            "Latency",
+        ])
+        .bold()
+        .underlined()
+        .height(1)
+        .bottom_margin(0);
         let rows = self
             .props
             .creds_data
             .iter()
             .map(|item| S3CredsPage::get_s3_row(self, item));
         let widths = [
-            Constraint::Length(10),
-            Constraint::Length(35),
             Constraint::Length(35),
+            Constraint::Length(20),
+            Constraint::Length(10),
+            Constraint::Length(10),
             Constraint::Length(10),
             Constraint::Length(10),
         ];
         let table = Table::new(rows, widths)
             .header(header)
-            .block(Block::default().borders(Borders::ALL).title("Account list"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Account list")
+                    .title(
+                        block::Title::from(self.key_hints())
+                            .alignment(Alignment::Left)
+                            .position(block::Position::Bottom),
+                    ),
+            )
             .highlight_style(
                 Style::default()
                     .fg(focus_color)
                     .add_modifier(Modifier::REVERSED),
             )
             .widths([
-                Constraint::Percentage(10),
-                Constraint::Percentage(35),
-                Constraint::Percentage(35),
-                Constraint::Percentage(10),
-                Constraint::Percentage(10),
+                Constraint::Percentage(28),
+                Constraint::Percentage(20),
+                Constraint::Percentage(13),
+                Constraint::Percentage(13),
+                Constraint::Percentage(13),
+                Constraint::Percentage(13),
             ]);
         table
     }
 
+    fn key_hints(&self) -> Line<'static> {
+        let mut hints = vec![Span::raw("|")];
+        hints.extend(hint_spans("Enter", "use"));
+        for (action, label) in [
+            (KeyAction::AddCredential, "add"),
+            (KeyAction::EditCredential, "edit"),
+            (KeyAction::Delete, "delete"),
+        ] {
+            if let Some(k) = self.props.key_bindings.keys(action).first() {
+                hints.extend(hint_spans(k.to_string(), label));
+            }
+        }
+        Line::from(hints)
+    }
+
+    /// Displayed instead of the table when no credential files were found
+    fn get_no_creds_guidance(&self) -> Paragraph<'static> {
+        let add = match self
+            .props
+            .key_bindings
+            .keys(KeyAction::AddCredential)
+            .first()
+        {
+            Some(key) => format!("Press '{}' to add one, or add", key),
+            None => "Add".to_string(),
+        };
+        Paragraph::new(vec![
+            Line::from("No accounts configured"),
+            Line::from(""),
+            Line::from(format!(
+                "{} a credential file to {},",
+                add,
+                creds_dir().display()
+            )),
+            Line::from("see the README for the file format"),
+        ])
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Account list"))
+    }
+
+    fn get_delete_confirmation(name: &str) -> Paragraph {
+        Paragraph::new(format!(
+            "Delete account {} and its credential file? (y/n)",
+            name
+        ))
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("| Confirm deletion |"),
+        )
+    }
+
+    fn get_skip_verify_confirmation(name: &str) -> Paragraph {
+        Paragraph::new(format!(
+            "Account {} doesn't verify the certificate of its endpoint (insecure_skip_tls_verify), \
+             anyone on the way can read and change the traffic. Use it without the verification \
+             for this session? (y/n)",
+            name
+        ))
+        .style(Style::default().fg(Color::Red))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("| Insecure TLS |"),
+        )
+    }
+
     pub fn move_up_creds_table_selection(&mut self) {
         let i = match self.props.creds_table_state.selected() {
             Some(i) => {
@@ -167,14 +382,21 @@ impl S3CredsPage {
         }
     }
 
-    pub fn set_current_s3_account(&mut self) {
-        if let Some(selected_row) = self
-            .props
+    fn highlighted_creds(&self) -> Option<&FileCredential> {
+        self.props
             .creds_table_state
             .selected()
             .and_then(|index| self.props.creds_data.get(index))
-        {
+    }
+
+    pub fn set_current_s3_account(&mut self) {
+        if let Some(selected_row) = self.highlighted_creds() {
             let sr = selected_row.clone();
+            let confirmed = self.props.skip_tls_verify_confirmed.contains(&sr.name);
+            if sr.insecure_skip_tls_verify && !confirmed {
+                self.confirm_skip_verify = Some(sr);
+                return;
+            }
             let _ = self
                 .action_tx
                 .send(Action::SelectCurrentS3Creds { item: sr.clone() });
@@ -184,21 +406,88 @@ impl S3CredsPage {
 
 impl ComponentRender<()> for S3CredsPage {
     fn render(&self, frame: &mut Frame, _props: ()) {
-        let s3_table = self.get_s3_table();
-        frame.render_stateful_widget(
-            &s3_table,
-            frame.size(),
-            &mut self.props.clone().creds_table_state,
-        )
+        if self.props.creds_data.is_empty() {
+            frame.render_widget(self.get_no_creds_guidance(), frame.size());
+        } else {
+            let s3_table = self.get_s3_table();
+            frame.render_stateful_widget(
+                &s3_table,
+                frame.size(),
+                &mut self.props.clone().creds_table_state,
+            )
+        }
+        let size = frame.size();
+        if let Some(form) = &self.form {
+            // the fields, the footer and the borders
+            let height = 9.min(size.height - size.height / 3);
+            let area = Rect::new(size.width / 8, size.height / 3, size.width * 3 / 4, height);
+            form.render(frame, area);
+        }
+        if let Some(name) = &self.confirm_delete {
+            let height = 4.min(size.height - size.height / 3);
+            let area = Rect::new(size.width / 6, size.height / 3, size.width * 2 / 3, height);
+            frame.render_widget(Clear, area);
+            frame.render_widget(Self::get_delete_confirmation(name), area);
+        }
+        if let Some(item) = &self.confirm_skip_verify {
+            let height = 6.min(size.height - size.height / 3);
+            let area = Rect::new(size.width / 6, size.height / 3, size.width * 2 / 3, height);
+            frame.render_widget(Clear, area);
+            frame.render_widget(Self::get_skip_verify_confirmation(&item.name), area);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::settings::encryption::UploadEncryption;
+    use crate::settings::file_credentials::CredentialEntry;
+    use crate::settings::key_bindings::parse_key_bindings;
+    use crate::settings::proxy::ProxyOverride;
+    use crate::settings::transfer_tuning::TuningOverride;
     use crossterm::event::KeyModifiers;
+    use ratatui::backend::TestBackend;
     use tokio::sync::mpsc::unbounded_channel;
 
+    fn rendered_text(page: &S3CredsPage, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| page.render(frame, ())).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn empty_creds_show_guidance_instead_of_the_table() {
+        let (tx, _rx) = unbounded_channel::<Action>();
+        let page = S3CredsPage::new(&State::default(), tx);
+        let text = rendered_text(&page, 100, 10);
+        assert!(text.contains("No accounts configured"));
+        assert!(text.contains("Press 'n' to add one"));
+        assert!(!text.contains("Account Name"));
+        // a terminal too small for the text doesn't panic
+        rendered_text(&page, 10, 2);
+    }
+
+    #[test]
+    fn rebound_add_key_opens_the_form() {
+        let (tx, _rx) = unbounded_channel::<Action>();
+        let mut state = State::default();
+        state.key_bindings = parse_key_bindings("add_credential = \"+\"").unwrap();
+        let mut page = S3CredsPage::new(&state, tx);
+        assert!(rendered_text(&page, 100, 10).contains("Press '+' to add one"));
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::empty()));
+        assert!(page.form.is_none());
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('+'), KeyModifiers::empty()));
+        assert!(page.form.is_some());
+    }
+
     #[tokio::test]
     async fn test_component_initialization() {
         let (tx, _rx) = unbounded_channel::<Action>();
@@ -208,6 +497,15 @@ mod tests {
             secret_key: "secretKey".to_string(),
             default_region: "eu-north-1".to_string(),
             selected: true,
+            proxy: ProxyOverride::default(),
+            leading_slash_keys: false,
+            tuning: TuningOverride::default(),
+            default_download_dir: None,
+            profile: None,
+            ca_bundle_path: None,
+            insecure_skip_tls_verify: false,
+            encryption: UploadEncryption::default(),
+            endpoint_url: None,
         };
         let state = State::new(vec![creds]);
 
@@ -215,6 +513,49 @@ mod tests {
         assert_eq!(component.name(), "S3CredsPage");
     }
 
+    #[test]
+    fn result_of_the_startup_check_is_shown() {
+        let (tx, _rx) = unbounded_channel::<Action>();
+        let creds = FileCredential {
+            name: "prod".to_string(),
+            ..FileCredential::default()
+        };
+        let mut state = State::new(vec![creds.clone()]);
+        state
+            .account_checks
+            .insert("prod".into(), AccountCheck::Failed("timed out".into()));
+        let component = S3CredsPage::new(&state, tx);
+        assert_eq!(
+            component.get_s3_row(&creds),
+            Row::new(vec![
+                "prod".to_string(),
+                creds.source(),
+                "failed".to_string(),
+                String::new(),
+                String::new(),
+                String::new()
+            ])
+        )
+    }
+
+    #[test]
+    fn load_of_the_account_is_shown_once_it_ran_transfers() {
+        let load = AccountLoad {
+            limit: 4,
+            configured: 8,
+            error_rate: 0.25,
+            average_latency: Some(std::time::Duration::from_millis(120)),
+        };
+        assert_eq!(
+            S3CredsPage::load_columns(Some(&load)),
+            ["4 of 8".to_string(), "25%".into(), "120 ms".into()]
+        );
+        assert_eq!(
+            S3CredsPage::load_columns(None),
+            [String::new(), String::new(), String::new()]
+        );
+    }
+
     #[tokio::test]
     async fn test_key_event_handling() {
         let (tx, mut rx) = unbounded_channel::<Action>();
@@ -224,6 +565,15 @@ mod tests {
             secret_key: "secretKey".to_string(),
             default_region: "eu-north-1".to_string(),
             selected: true,
+            proxy: ProxyOverride::default(),
+            leading_slash_keys: false,
+            tuning: TuningOverride::default(),
+            default_download_dir: None,
+            profile: None,
+            ca_bundle_path: None,
+            insecure_skip_tls_verify: false,
+            encryption: UploadEncryption::default(),
+            endpoint_url: None,
         };
         let state = State::new(vec![creds.clone()]);
         let mut component = S3CredsPage::new(&state, tx);
@@ -251,6 +601,115 @@ mod tests {
         );
     }
 
+    #[test]
+    fn credentials_are_added_edited_and_deleted() {
+        let (tx, mut rx) = unbounded_channel::<Action>();
+        let creds = FileCredential {
+            name: "work".into(),
+            access_key: "AKIAWORK".into(),
+            secret_key: "secret".into(),
+            default_region: "eu-west-1".into(),
+            ..FileCredential::default()
+        };
+        let state = State::new(vec![creds.clone()]);
+        let mut component = S3CredsPage::new(&state, tx);
+        let press = |component: &mut S3CredsPage, code: KeyCode| {
+            component.handle_key_event(KeyEvent::new(code, KeyModifiers::empty()))
+        };
+
+        // the typed letters go to the form instead of the page
+        press(&mut component, KeyCode::Char('n'));
+        for c in "q?".chars() {
+            press(&mut component, KeyCode::Char(c));
+        }
+        press(&mut component, KeyCode::Esc);
+        assert!(component.form.is_none());
+        assert!(rx.try_recv().is_err());
+
+        press(&mut component, KeyCode::Char('j'));
+        press(&mut component, KeyCode::Char('e'));
+        let text = rendered_text(&component, 100, 30);
+        assert!(text.contains("Edit account work"));
+        assert!(text.contains("******"));
+        assert!(!text.contains("secret"));
+        press(&mut component, KeyCode::Enter);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::SaveCredential {
+                original: Some("work".into()),
+                entry: CredentialEntry {
+                    name: "work".into(),
+                    access_key: "AKIAWORK".into(),
+                    secret_key: "secret".into(),
+                    default_region: "eu-west-1".into(),
+                    endpoint_url: String::new(),
+                },
+            }
+        );
+        assert!(component.form.is_none());
+
+        press(&mut component, KeyCode::Delete);
+        press(&mut component, KeyCode::Char('n'));
+        assert!(rx.try_recv().is_err());
+        press(&mut component, KeyCode::Delete);
+        assert!(rendered_text(&component, 100, 30).contains("Delete account work"));
+        press(&mut component, KeyCode::Char('y'));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::DeleteCredential {
+                name: "work".into()
+            }
+        );
+    }
+
+    #[test]
+    fn insecure_account_is_confirmed_before_its_first_use() {
+        let (tx, mut rx) = unbounded_channel::<Action>();
+        let creds = FileCredential {
+            name: "minio".into(),
+            insecure_skip_tls_verify: true,
+            ..FileCredential::default()
+        };
+        let mut state = State::new(vec![creds.clone()]);
+        let mut component = S3CredsPage::new(&state, tx);
+        let press = |component: &mut S3CredsPage, code: KeyCode| {
+            component.handle_key_event(KeyEvent::new(code, KeyModifiers::empty()))
+        };
+        assert_eq!(component.tls_badge(&creds), " [insecure TLS, unconfirmed]");
+
+        press(&mut component, KeyCode::Char('j'));
+        press(&mut component, KeyCode::Enter);
+        assert!(rendered_text(&component, 100, 30).contains("Insecure TLS"));
+        press(&mut component, KeyCode::Char('n'));
+        assert!(rx.try_recv().is_err());
+
+        press(&mut component, KeyCode::Enter);
+        press(&mut component, KeyCode::Char('y'));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::ConfirmSkipTlsVerify {
+                account: "minio".into()
+            }
+        );
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::SelectCurrentS3Creds {
+                item: creds.clone()
+            }
+        );
+
+        // confirmed once per session
+        state.confirm_skip_tls_verify("minio");
+        assert!(state.skips_tls_verify(&creds));
+        let mut component = component.move_with_state(&state);
+        assert_eq!(component.tls_badge(&creds), " [INSECURE TLS]");
+        press(&mut component, KeyCode::Enter);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Action::SelectCurrentS3Creds { item: creds }
+        );
+    }
+
     #[test]
     fn get_s3_row_should_format_selected_row() {
         let (tx, _rx) = unbounded_channel::<Action>();
@@ -260,12 +719,31 @@ mod tests {
             secret_key: "secretKey".to_string(),
             default_region: "eu-north-1".to_string(),
             selected: true,
+            proxy: ProxyOverride::default(),
+            leading_slash_keys: false,
+            tuning: TuningOverride::default(),
+            default_download_dir: None,
+            profile: None,
+            ca_bundle_path: None,
+            insecure_skip_tls_verify: false,
+            encryption: UploadEncryption::default(),
+            endpoint_url: None,
         };
         let state = State::new(vec![creds.clone()]);
         let component = S3CredsPage::new(&state, tx);
         let res = component.get_s3_row(&creds);
 
-        assert_eq!(res, Row::new(vec![format!("{} (*)", creds.name)]))
+        assert_eq!(
+            res,
+            Row::new(vec![
+                format!("{} (*)", creds.name),
+                "static keys".to_string(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new()
+            ])
+        )
     }
 
     #[test]
@@ -277,11 +755,30 @@ mod tests {
             secret_key: "secretKey".to_string(),
             default_region: "eu-north-1".to_string(),
             selected: false,
+            proxy: ProxyOverride::default(),
+            leading_slash_keys: false,
+            tuning: TuningOverride::default(),
+            default_download_dir: None,
+            profile: None,
+            ca_bundle_path: None,
+            insecure_skip_tls_verify: false,
+            encryption: UploadEncryption::default(),
+            endpoint_url: None,
         };
         let state = State::new(vec![creds.clone()]);
         let component = S3CredsPage::new(&state, tx);
         let res = component.get_s3_row(&creds);
 
-        assert_eq!(res, Row::new(vec![format!("{}", creds.name)]))
+        assert_eq!(
+            res,
+            Row::new(vec![
+                creds.name.clone(),
+                "static keys".to_string(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new()
+            ])
+        )
     }
 }
@@ -0,0 +1,211 @@
+use crate::components::component::{Component, ComponentRender};
+use crate::model::action::Action;
+use crate::model::state::{ActivePage, State};
+use crate::model::stats::Stats;
+use crate::settings::key_bindings::{KeyAction, KeyBindings, KeyScope};
+use crate::utils::{format_bytes, format_duration};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
+
+struct Props {
+    rows: Vec<Vec<String>>,
+    key_bindings: KeyBindings,
+}
+
+impl From<&State> for Props {
+    fn from(state: &State) -> Self {
+        Props {
+            rows: stats_rows(&state.stats),
+            key_bindings: state.key_bindings.clone(),
+        }
+    }
+}
+
+fn stats_rows(stats: &Stats) -> Vec<Vec<String>> {
+    vec![
+        vec!["Uptime".to_string(), format_duration(stats.uptime())],
+        vec!["Listings performed".to_string(), stats.listings.to_string()],
+        vec![
+            "Objects browsed".to_string(),
+            stats.objects_browsed.to_string(),
+        ],
+        vec![
+            "Downloads completed / failed".to_string(),
+            format!("{} / {}", stats.downloads_completed, stats.downloads_failed),
+        ],
+        vec![
+            "Uploads completed / failed".to_string(),
+            format!("{} / {}", stats.uploads_completed, stats.uploads_failed),
+        ],
+        vec![
+            "Bytes downloaded".to_string(),
+            format_bytes(stats.bytes_downloaded),
+        ],
+        vec![
+            "Bytes uploaded".to_string(),
+            format_bytes(stats.bytes_uploaded),
+        ],
+        vec!["Deletions".to_string(), stats.deletions.to_string()],
+        vec![
+            "Buckets created".to_string(),
+            stats.buckets_created.to_string(),
+        ],
+        vec![
+            "Timeout errors".to_string(),
+            stats.timeout_errors.to_string(),
+        ],
+        vec![
+            "Connection errors".to_string(),
+            stats.connection_errors.to_string(),
+        ],
+        vec!["Proxy errors".to_string(), stats.proxy_errors.to_string()],
+        vec![
+            "Service errors".to_string(),
+            stats.service_errors.to_string(),
+        ],
+        vec![
+            "Too many open files".to_string(),
+            stats.open_files_errors.to_string(),
+        ],
+        vec!["Other errors".to_string(), stats.other_errors.to_string()],
+    ]
+}
+
+/// Page summarizing the activity since the application was launched, accessible with 'i'
+pub struct StatsPage {
+    pub action_tx: UnboundedSender<Action>,
+    props: Props,
+}
+
+impl Component for StatsPage {
+    fn new(state: &State, action_tx: UnboundedSender<Action>) -> Self
+    where
+        Self: Sized,
+    {
+        StatsPage {
+            action_tx: action_tx.clone(),
+            // set the props
+            props: Props::from(state),
+        }
+        .move_with_state(state)
+    }
+
+    fn move_with_state(self, state: &State) -> Self
+    where
+        Self: Sized,
+    {
+        StatsPage {
+            props: Props::from(state),
+            ..self
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Stats Page"
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        match self.props.key_bindings.action(&key, KeyScope::AllPages) {
+            Some(KeyAction::Quit) => {
+                let _ = self.action_tx.send(Action::Exit);
+            }
+            Some(KeyAction::NavigateHelp) => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::Help,
+                });
+            }
+            Some(KeyAction::ActivityLog) => {
+                let _ = self.action_tx.send(Action::ToggleActivityLog);
+            }
+            _ if key.code == KeyCode::Esc => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::FileManager,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ComponentRender<()> for StatsPage {
+    fn render(&self, frame: &mut Frame, _props: ()) {
+        let v_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(3),
+                Constraint::Percentage(94),
+                Constraint::Percentage(3),
+            ])
+            .split(frame.size());
+        let h_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(3),
+                Constraint::Percentage(94),
+                Constraint::Percentage(3),
+            ])
+            .split(v_layout[1]);
+        let rows: Vec<Row> = self
+            .props
+            .rows
+            .iter()
+            .map(|c| Row::new(c.clone()))
+            .collect();
+        let header = Row::new(vec!["Statistic", "Value"])
+            .bold()
+            .underlined()
+            .height(1)
+            .bottom_margin(0);
+        let table = Table::new(rows, [Constraint::Length(40), Constraint::Length(30)])
+            .block(
+                Block::new()
+                    .borders(Borders::ALL)
+                    .title("Session statistics"),
+            )
+            .header(header);
+        frame.render_widget(table, h_layout[1]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    #[test]
+    fn rows_show_formatted_counters() {
+        let mut stats = Stats::default();
+        stats.record_download(&Ok(2048));
+        stats.record_listing(3);
+
+        let rows = stats_rows(&stats);
+
+        assert!(rows.contains(&vec!["Bytes downloaded".to_string(), "2.0 KB".to_string()]));
+        assert!(rows.contains(&vec!["Objects browsed".to_string(), "3".to_string()]));
+        assert!(rows.contains(&vec![
+            "Downloads completed / failed".to_string(),
+            "1 / 0".to_string()
+        ]));
+    }
+
+    #[tokio::test]
+    async fn esc_goes_back_to_file_manager() {
+        let (tx, mut rx) = unbounded_channel::<Action>();
+        let state = State::default();
+        let mut component = StatsPage::new(&state, tx);
+
+        component.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::Navigate {
+                page: ActivePage::FileManager
+            }
+        );
+    }
+}
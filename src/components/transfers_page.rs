@@ -1,52 +1,427 @@
+use crate::components::clipboard::{copy_to_clipboard, fallback_popup};
 use crate::components::component::{Component, ComponentRender};
+use crate::components::keymap::{
+    displayed_keys, hint_line, hint_spans, rotation_for, Command, HintContext, HintPanel,
+};
+use crate::components::popup_sync::PopupSync;
 use crate::model::action::Action;
+use crate::model::aws_cli_command::CliAccount;
+use crate::model::destination_conflict::{BlockedTransfers, ConflictResolution};
+use crate::model::error_groups::{in_group, ErrorGroups};
+use crate::model::jump_target::JumpTarget;
 use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::notification::Notification;
 use crate::model::s3_selected_item::S3SelectedItem;
 use crate::model::state::{ActivePage, State};
-use crate::model::transfer_item::TransferItem;
+use crate::model::transfer_item::{TransferItem, TransferState};
+use crate::model::transfer_overlap::{OverlapResolution, OverlappingTransfers};
+use crate::model::transfer_rate::TransferRate;
+use crate::model::transfer_sort::{TransferSort, TransferSortColumn, TransferSortKey};
+use crate::services::selection_file::SELECTION_FILE_NAME;
+use crate::services::transfer_control::TransferStop;
+use crate::services::transfer_queue::{DispatchOrder, TransferJob};
+use crate::settings::color_support::{adapt_color, FOCUS_COLOR};
+use crate::settings::file_credentials::FileCredential;
+use crate::settings::key_bindings::{KeyAction, KeyBindings, KeyScope};
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{prelude::*, widgets::*};
+use std::path::Path;
+use std::time::Instant;
 use tokio::sync::mpsc::UnboundedSender;
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+/// Number of rows converted into `TransferItem`s and rendered at once on the transfers page
+const TRANSFERS_WINDOW_SIZE: usize = 100;
+/// Number of rows skipped with PageUp/PageDown
+const TRANSFERS_PAGE_JUMP: usize = 20;
 
 #[derive(Clone)]
 struct Props {
     table_state: TableState,
+    /// Absolute index of the highlighted row across all selected items
+    selected_row: Option<usize>,
+    /// Absolute index of the first row in the rendered window
+    window_offset: usize,
+    /// Rows visible in the current window only
     selected_items: Vec<TransferItem>,
     s3_selected_items: Vec<S3SelectedItem>,
     local_selected_items: Vec<LocalSelectedItem>,
+    transfers_total: usize,
+    transfers_done: usize,
+    transfers_running: bool,
+    in_flight_requests: usize,
+    notification: Option<Notification>,
+    /// Start order of every row in the latest batch
+    dispatch_orders: Vec<Option<DispatchOrder>>,
+    /// State of every row while a batch is running, empty otherwise
+    transfer_states: Vec<TransferState>,
+    /// Whether every row is paused, empty while no transfer is paused
+    paused: Vec<bool>,
+    /// Speed of every row, empty while nothing is transferred
+    rates: Vec<Option<TransferRate>>,
+    /// Combined speed of all the running transfers
+    overall_rate: Option<TransferRate>,
+    sort: TransferSort,
+    /// Index of the item displayed on every row, the downloads then the uploads.
+    /// Empty while the rows are listed in the order they were added
+    row_order: Vec<usize>,
+    focus_color: Color,
+    blocked_transfers: Option<BlockedTransfers>,
+    overlapping_transfers: Option<OverlappingTransfers>,
+    /// Summary of the run waiting for a confirmation and whether `yes` has to be typed,
+    /// its jobs stay in the state
+    large_batch: Option<(Vec<String>, bool)>,
+    /// Failures of the finished batch grouped by their cause
+    failure_summary: Option<ErrorGroups>,
+    /// Normalized error whose transfers are the only ones listed
+    error_filter: Option<String>,
+    /// Downloads of the next run write a manifest
+    write_manifest: bool,
+    osc52_clipboard: bool,
+    /// Directory the new downloads are written to when it's not the one opened on the local panel
+    download_dir: Option<String>,
+    /// Directory opened on the local panel, the selection file is offered there
+    local_path: String,
+    /// Accounts the aws cli commands of the transfers are built with
+    creds: Vec<FileCredential>,
+    key_bindings: KeyBindings,
 }
 
 impl From<&State> for Props {
     fn from(state: &State) -> Self {
-        let st = state.clone();
-        let s3_items: Vec<TransferItem> = st
-            .s3_selected_items
+        Props::with_window(state, None)
+    }
+}
+
+impl Props {
+    /// Builds the props converting only the rows which belong to the window containing
+    /// `selected_row`, so the cost doesn't depend on the number of queued items.
+    /// With an error filter only the items failing with that normalized error are listed
+    fn with_window(state: &State, selected_row: Option<usize>) -> Self {
+        let (s3_selected_items, local_selected_items) = match &state.transfer_error_filter {
+            Some(message) => (
+                state
+                    .s3_selected_items
+                    .iter()
+                    .filter(|i| Self::s3_item_in_group(i, message))
+                    .cloned()
+                    .collect(),
+                state
+                    .local_selected_items
+                    .iter()
+                    .filter(|i| in_group(i.error.as_deref(), message))
+                    .cloned()
+                    .collect(),
+            ),
+            None => (
+                state.s3_selected_items.clone(),
+                state.local_selected_items.clone(),
+            ),
+        };
+        let total_rows = s3_selected_items.len() + local_selected_items.len();
+        let selected_row = selected_row.map(|row| row.min(total_rows.saturating_sub(1)));
+        let selected_row = if total_rows == 0 { None } else { selected_row };
+        let window_offset = Self::window_offset_for(selected_row.unwrap_or(0));
+        let mut table_state = TableState::default();
+        table_state.select(selected_row.map(|row| row - window_offset));
+        let transfer_states =
+            Self::transfer_states(state, &s3_selected_items, &local_selected_items);
+        let paused = Self::paused(state, &s3_selected_items, &local_selected_items);
+        let rates = Self::rates(state, &s3_selected_items, &local_selected_items);
+        let row_order = Self::row_order(
+            state.transfer_sort,
+            &s3_selected_items,
+            &local_selected_items,
+            &transfer_states,
+            &paused,
+        );
+        Props {
+            table_state,
+            selected_row,
+            window_offset,
+            selected_items: Self::window_items(
+                &s3_selected_items,
+                &local_selected_items,
+                &transfer_states,
+                &paused,
+                &rates,
+                &row_order,
+                window_offset,
+            ),
+            dispatch_orders: Self::dispatch_orders(
+                state,
+                &s3_selected_items,
+                &local_selected_items,
+            ),
+            s3_selected_items,
+            local_selected_items,
+            transfers_total: state.transfers_total,
+            transfers_done: state.transfers_done,
+            transfers_running: state.has_transfers_in_progress(),
+            in_flight_requests: state.in_flight_requests,
+            notification: state.notification.clone(),
+            transfer_states,
+            paused,
+            rates,
+            overall_rate: state.transfer_rates.overall(),
+            sort: state.transfer_sort,
+            row_order,
+            focus_color: adapt_color(FOCUS_COLOR, state.color_support),
+            blocked_transfers: state.blocked_transfers.clone(),
+            overlapping_transfers: state.overlapping_transfers.clone(),
+            large_batch: state
+                .large_batch
+                .as_ref()
+                .map(|batch| (batch.summary(), batch.typed_confirmation)),
+            failure_summary: state.failure_summary.clone(),
+            error_filter: state.transfer_error_filter.clone(),
+            write_manifest: state.write_manifest,
+            osc52_clipboard: state.app_config.osc52_clipboard,
+            download_dir: state.configured_download_dir(),
+            local_path: state.current_local_path.clone(),
+            creds: state.creds.clone(),
+            key_bindings: state.key_bindings.clone(),
+        }
+    }
+
+    /// A directory download belongs to the group when any of its files failed that way
+    fn s3_item_in_group(item: &S3SelectedItem, message: &str) -> bool {
+        in_group(item.error.as_deref(), message)
+            || item.children.as_ref().is_some_and(|children| {
+                children
+                    .iter()
+                    .any(|c| in_group(c.error.as_deref(), message))
+            })
+    }
+
+    fn dispatch_orders(
+        state: &State,
+        s3_selected_items: &[S3SelectedItem],
+        local_selected_items: &[LocalSelectedItem],
+    ) -> Vec<Option<DispatchOrder>> {
+        if state.transfer_dispatch.is_empty() {
+            return Vec::new();
+        }
+        s3_selected_items
             .iter()
-            .map(|i| TransferItem::from_s3_selected_item(i.clone()))
-            .collect();
-        let local_items: Vec<TransferItem> = st
-            .local_selected_items
+            .map(|i| state.s3_item_dispatch_order(i))
+            .chain(
+                local_selected_items
+                    .iter()
+                    .map(|i| state.local_item_dispatch_order(i)),
+            )
+            .collect()
+    }
+
+    fn transfer_states(
+        state: &State,
+        s3_selected_items: &[S3SelectedItem],
+        local_selected_items: &[LocalSelectedItem],
+    ) -> Vec<TransferState> {
+        if !state.transfer_batch.is_running() {
+            return Vec::new();
+        }
+        let busy = state.busy_transfer_slots();
+        s3_selected_items
+            .iter()
+            .map(|i| state.s3_item_transfer_state(i, busy))
+            .chain(
+                local_selected_items
+                    .iter()
+                    .map(|i| state.local_item_transfer_state(i, busy)),
+            )
+            .collect()
+    }
+
+    fn paused(
+        state: &State,
+        s3_selected_items: &[S3SelectedItem],
+        local_selected_items: &[LocalSelectedItem],
+    ) -> Vec<bool> {
+        if state.paused_transfers.is_empty() {
+            return Vec::new();
+        }
+        s3_selected_items
             .iter()
-            .map(|i| TransferItem::from_local_selected_item(i.clone()))
+            .map(|i| state.s3_item_paused(i))
+            .chain(
+                local_selected_items
+                    .iter()
+                    .map(|i| state.local_item_paused(i)),
+            )
+            .collect()
+    }
+
+    fn rates(
+        state: &State,
+        s3_selected_items: &[S3SelectedItem],
+        local_selected_items: &[LocalSelectedItem],
+    ) -> Vec<Option<TransferRate>> {
+        if !state.transfer_batch.is_running() {
+            return Vec::new();
+        }
+        s3_selected_items
+            .iter()
+            .map(|i| state.s3_item_rate(i))
+            .chain(
+                local_selected_items
+                    .iter()
+                    .map(|i| state.local_item_rate(i)),
+            )
+            .collect()
+    }
+
+    /// Only the sort keys of the items are built, the rows are converted for the window
+    fn row_order(
+        sort: TransferSort,
+        s3_selected_items: &[S3SelectedItem],
+        local_selected_items: &[LocalSelectedItem],
+        transfer_states: &[TransferState],
+        paused: &[bool],
+    ) -> Vec<usize> {
+        if !sort.is_active() {
+            return Vec::new();
+        }
+        let state = |index: usize| transfer_states.get(index).copied().unwrap_or_default();
+        let paused = |index: usize| paused.get(index).copied().unwrap_or_default();
+        let uploads_offset = s3_selected_items.len();
+        let keys: Vec<TransferSortKey> = s3_selected_items
+            .iter()
+            .enumerate()
+            .map(|(index, i)| TransferSortKey::of_download(i, state(index), paused(index)))
+            .chain(local_selected_items.iter().enumerate().map(|(index, i)| {
+                let index = uploads_offset + index;
+                TransferSortKey::of_upload(i, state(index), paused(index))
+            }))
             .collect();
+        sort.order(&keys)
+    }
 
-        Props {
-            table_state: TableState::default(),
-            s3_selected_items: st.s3_selected_items,
-            local_selected_items: st.local_selected_items,
-            selected_items: {
-                let mut all_vec = s3_items.clone();
-                all_vec.extend(local_items);
-                all_vec
-            },
+    fn window_offset_for(row: usize) -> usize {
+        (row / TRANSFERS_WINDOW_SIZE) * TRANSFERS_WINDOW_SIZE
+    }
+
+    fn window_items(
+        s3_selected_items: &[S3SelectedItem],
+        local_selected_items: &[LocalSelectedItem],
+        transfer_states: &[TransferState],
+        paused: &[bool],
+        rates: &[Option<TransferRate>],
+        row_order: &[usize],
+        window_offset: usize,
+    ) -> Vec<TransferItem> {
+        let total_rows = s3_selected_items.len() + local_selected_items.len();
+        (window_offset..total_rows.min(window_offset + TRANSFERS_WINDOW_SIZE))
+            .map(|row| {
+                let index = row_order.get(row).copied().unwrap_or(row);
+                let item = match s3_selected_items.get(index) {
+                    Some(i) => TransferItem::from_s3_selected_item(i.clone()),
+                    None => TransferItem::from_local_selected_item(
+                        local_selected_items[index - s3_selected_items.len()].clone(),
+                    ),
+                };
+                item.with_state(
+                    transfer_states.get(index).copied().unwrap_or_default(),
+                    paused.get(index).copied().unwrap_or_default(),
+                )
+                .with_rate(rates.get(index).copied().flatten())
+            })
+            .collect()
+    }
+
+    /// Index of the item displayed on the row, see `row_order`
+    fn item_index(&self, row: usize) -> usize {
+        self.row_order.get(row).copied().unwrap_or(row)
+    }
+
+    fn total_rows(&self) -> usize {
+        self.s3_selected_items.len() + self.local_selected_items.len()
+    }
+
+    fn page_count(&self) -> usize {
+        self.total_rows().div_ceil(TRANSFERS_WINDOW_SIZE).max(1)
+    }
+
+    /// Moves the highlight to the given absolute row, re-converting the window only when
+    /// the row falls outside of the currently rendered one
+    fn select_row(&mut self, row: usize) {
+        let new_offset = Self::window_offset_for(row);
+        if new_offset != self.window_offset || self.selected_items.is_empty() {
+            self.window_offset = new_offset;
+            self.selected_items = Self::window_items(
+                &self.s3_selected_items,
+                &self.local_selected_items,
+                &self.transfer_states,
+                &self.paused,
+                &self.rates,
+                &self.row_order,
+                new_offset,
+            );
         }
+        self.selected_row = Some(row);
+        self.table_state.select(Some(row - self.window_offset));
     }
 }
 
+/// Popups of the page closed with a key, the state shows them until the store handles the keys
+#[derive(Default)]
+struct ClosedPopups {
+    blocked: PopupSync,
+    overlapping: PopupSync,
+    large_batch: PopupSync,
+    failure_summary: PopupSync,
+}
+
+impl ClosedPopups {
+    /// Hides the popups of the state still waiting to be closed
+    fn apply(&mut self, props: &mut Props) {
+        if !self.blocked.resolve(props.blocked_transfers.is_some()) {
+            props.blocked_transfers = None;
+        }
+        if !self
+            .overlapping
+            .resolve(props.overlapping_transfers.is_some())
+        {
+            props.overlapping_transfers = None;
+        }
+        if !self.large_batch.resolve(props.large_batch.is_some()) {
+            props.large_batch = None;
+        }
+        if !self
+            .failure_summary
+            .resolve(props.failure_summary.is_some())
+        {
+            props.failure_summary = None;
+        }
+    }
+}
+
+/// What the path typed in the selection file input is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SelectionFileMode {
+    Export,
+    Import,
+}
+
 /// Page displaying selected transfers and their status
 pub struct TransfersPage {
     pub action_tx: UnboundedSender<Action>,
     props: Props,
+    /// Items marked with space, 'r' runs only these when any are marked
+    marked_s3_items: Vec<S3SelectedItem>,
+    marked_local_items: Vec<LocalSelectedItem>,
+    /// Moment the hint line started cycling through the contextual hints
+    hints_shown_at: Instant,
+    /// Text typed to confirm a very large run
+    typed_confirmation: String,
+    /// Highlighted group of the failure summary
+    failure_group: usize,
+    closed_popups: ClosedPopups,
+    /// Text the clipboard didn't take, shown in a popup until Esc
+    copy_fallback: Option<String>,
+    /// Path input of the selection file being exported or imported
+    selection_input: Option<(SelectionFileMode, Input)>,
 }
 
 impl Component for TransfersPage {
@@ -58,6 +433,14 @@ impl Component for TransfersPage {
             action_tx: action_tx.clone(),
             // set the props
             props: Props::from(state),
+            marked_s3_items: Vec::new(),
+            marked_local_items: Vec::new(),
+            hints_shown_at: Instant::now(),
+            typed_confirmation: String::new(),
+            failure_group: 0,
+            closed_popups: ClosedPopups::default(),
+            copy_fallback: None,
+            selection_input: None,
         }
         .move_with_state(state)
     }
@@ -66,12 +449,30 @@ impl Component for TransfersPage {
     where
         Self: Sized,
     {
-        let new_props = Props::from(state);
+        let mut props = Props::with_window(state, self.props.selected_row);
+        let mut closed_popups = self.closed_popups;
+        closed_popups.apply(&mut props);
+        let marked_s3_items = self
+            .marked_s3_items
+            .into_iter()
+            .filter(|it| props.s3_selected_items.contains(it))
+            .collect();
+        let marked_local_items = self
+            .marked_local_items
+            .into_iter()
+            .filter(|it| props.local_selected_items.contains(it))
+            .collect();
+        let typed_confirmation = if props.large_batch.is_some() {
+            self.typed_confirmation
+        } else {
+            String::new()
+        };
         TransfersPage {
-            props: Props {
-                table_state: self.props.table_state,
-                ..new_props
-            },
+            props,
+            marked_s3_items,
+            marked_local_items,
+            typed_confirmation,
+            closed_popups,
             ..self
         }
     }
@@ -84,7 +485,37 @@ impl Component for TransfersPage {
         if key.kind != KeyEventKind::Press {
             return;
         }
+        if self.copy_fallback.is_some() {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                self.copy_fallback = None;
+            }
+            return;
+        }
+        if self.selection_input.is_some() {
+            self.handle_selection_input_key_event(key);
+            return;
+        }
+        if let Some((_, typed)) = self.props.large_batch {
+            self.handle_large_batch_key_event(key, typed);
+            return;
+        }
+        if self.props.blocked_transfers.is_some() {
+            self.handle_conflicts_key_event(key);
+            return;
+        }
+        if self.props.overlapping_transfers.is_some() {
+            self.handle_overlaps_key_event(key);
+            return;
+        }
+        if let Some(summary) = self.props.failure_summary.clone() {
+            self.handle_failure_summary_key_event(key, &summary);
+            return;
+        }
 
+        if let Some(action) = self.props.key_bindings.action(&key, KeyScope::Transfers) {
+            self.handle_key_action(action);
+            return;
+        }
         match key.code {
             KeyCode::Char('j') | KeyCode::Down => {
                 self.move_down_table_selection();
@@ -92,68 +523,421 @@ impl Component for TransfersPage {
             KeyCode::Char('k') | KeyCode::Up => {
                 self.move_up_table_selection();
             }
-            KeyCode::Delete | KeyCode::Backspace => {
+            KeyCode::PageDown => {
+                self.move_page_down();
+            }
+            KeyCode::PageUp => {
+                self.move_page_up();
+            }
+            KeyCode::Esc => {
+                let _ = self.action_tx.send(Action::Navigate {
+                    page: ActivePage::FileManager,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl TransfersPage {
+    pub fn move_up_table_selection(&mut self) {
+        let total = self.props.total_rows();
+        if total == 0 {
+            return;
+        }
+        let i = match self.props.selected_row {
+            Some(0) => total - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.props.select_row(i);
+    }
+
+    pub fn move_down_table_selection(&mut self) {
+        let total = self.props.total_rows();
+        if total == 0 {
+            return;
+        }
+        let i = match self.props.selected_row {
+            Some(i) if i >= total - 1 => 0,
+            Some(i) => i + 1,
+            None => 0,
+        };
+        self.props.select_row(i);
+    }
+
+    pub fn move_page_down(&mut self) {
+        let total = self.props.total_rows();
+        if total == 0 {
+            return;
+        }
+        let i = self
+            .props
+            .selected_row
+            .map_or(0, |i| (i + TRANSFERS_PAGE_JUMP).min(total - 1));
+        self.props.select_row(i);
+    }
+
+    pub fn move_page_up(&mut self) {
+        if self.props.total_rows() == 0 {
+            return;
+        }
+        let i = self
+            .props
+            .selected_row
+            .map_or(0, |i| i.saturating_sub(TRANSFERS_PAGE_JUMP));
+        self.props.select_row(i);
+    }
+
+    fn highlighted_transfer_item(&self) -> Option<TransferItem> {
+        self.props
+            .table_state
+            .selected()
+            .and_then(|index| self.props.selected_items.get(index))
+            .cloned()
+    }
+
+    fn is_marked(&self, item: &TransferItem) -> bool {
+        if let Some(s3_item) = self.find_s3_item_from_transfer_item(item) {
+            self.marked_s3_items.contains(&s3_item)
+        } else if let Some(local_item) = self.find_local_item_from_transfer_item(item) {
+            self.marked_local_items.contains(&local_item)
+        } else {
+            false
+        }
+    }
+
+    /// Items which already started transferring cannot be marked
+    pub fn toggle_mark_on_highlighted_item(&mut self) {
+        if let Some(item) = self.highlighted_transfer_item() {
+            if item.progress > 0f64 && !item.transferred && item.error.is_none() {
+                return;
+            }
+            if let Some(s3_item) = self.find_s3_item_from_transfer_item(&item) {
+                if self.marked_s3_items.contains(&s3_item) {
+                    self.marked_s3_items.retain(|it| it != &s3_item);
+                } else {
+                    self.marked_s3_items.push(s3_item);
+                }
+            } else if let Some(local_item) = self.find_local_item_from_transfer_item(&item) {
+                if self.marked_local_items.contains(&local_item) {
+                    self.marked_local_items.retain(|it| it != &local_item);
+                } else {
+                    self.marked_local_items.push(local_item);
+                }
+            }
+        }
+    }
+
+    fn handle_conflicts_key_event(&mut self, key: KeyEvent) {
+        let resolution = match key.code {
+            KeyCode::Char('f') => Some(ConflictResolution::KeepFirst),
+            KeyCode::Char('l') => Some(ConflictResolution::KeepLast),
+            KeyCode::Char('r') => Some(ConflictResolution::RenameWithSuffix),
+            KeyCode::Esc => None,
+            _ => return,
+        };
+        self.props.blocked_transfers = None;
+        self.closed_popups.blocked = PopupSync::Closing;
+        let _ = self
+            .action_tx
+            .send(Action::ResolveTransferConflicts { resolution });
+    }
+
+    fn handle_overlaps_key_event(&mut self, key: KeyEvent) {
+        let resolution = match key.code {
+            KeyCode::Char('d') => Some(OverlapResolution::KeepDownload),
+            KeyCode::Char('u') => Some(OverlapResolution::KeepUpload),
+            KeyCode::Char('s') => Some(OverlapResolution::DownloadThenUpload),
+            KeyCode::Esc => None,
+            _ => return,
+        };
+        self.props.overlapping_transfers = None;
+        self.closed_popups.overlapping = PopupSync::Closing;
+        let _ = self
+            .action_tx
+            .send(Action::ResolveTransferOverlaps { resolution });
+    }
+
+    /// 'y' starts the run, or `yes` followed by Enter when it has to be typed.
+    /// 'M' switches the manifest of the run unless the confirmation is typed
+    fn handle_large_batch_key_event(&mut self, key: KeyEvent, typed: bool) {
+        let confirmed = match (key.code, typed) {
+            (KeyCode::Esc, _) | (KeyCode::Char('n'), false) => false,
+            (KeyCode::Char('y'), false) => true,
+            (KeyCode::Char('M'), false) => {
+                let _ = self.action_tx.send(Action::ToggleManifest);
+                return;
+            }
+            (KeyCode::Char(c), true) => {
+                self.typed_confirmation.push(c);
+                return;
+            }
+            (KeyCode::Backspace, true) => {
+                self.typed_confirmation.pop();
+                return;
+            }
+            (KeyCode::Enter, true) if self.typed_confirmation == "yes" => true,
+            _ => return,
+        };
+        self.props.large_batch = None;
+        self.closed_popups.large_batch = PopupSync::Closing;
+        self.typed_confirmation.clear();
+        let _ = self.action_tx.send(Action::ConfirmLargeBatch { confirmed });
+    }
+
+    /// 'j' / 'k' highlight a group, Enter lists only its items and Esc closes the summary
+    fn handle_failure_summary_key_event(&mut self, key: KeyEvent, summary: &ErrorGroups) {
+        let groups = summary.groups();
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.failure_group = (self.failure_group + 1) % groups.len().max(1);
+                return;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.failure_group = self
+                    .failure_group
+                    .checked_sub(1)
+                    .unwrap_or(groups.len().saturating_sub(1));
+                return;
+            }
+            KeyCode::Enter => {
+                let group = groups.get(self.failure_group).map(|g| g.message.clone());
+                self.filter_by_error(group);
+            }
+            KeyCode::Esc => {
+                let _ = self.action_tx.send(Action::CloseFailureSummary);
+            }
+            _ => return,
+        }
+        self.props.failure_summary = None;
+        self.closed_popups.failure_summary = PopupSync::Closing;
+        self.failure_group = 0;
+    }
+
+    /// Runs the action of a key pressed while no popup is open
+    fn handle_key_action(&mut self, action: KeyAction) {
+        match action {
+            KeyAction::Delete => {
                 self.unselect_transfer_item();
             }
-            KeyCode::Char('r') => {
-                let _ = self.action_tx.send(Action::RunTransfers);
+            KeyAction::RunTransfers => {
+                self.run_transfers();
+            }
+            KeyAction::RunHighlighted => {
+                self.run_highlighted_transfer();
+            }
+            KeyAction::MarkTransfer => {
+                self.toggle_mark_on_highlighted_item();
+            }
+            KeyAction::OpenSource => {
+                self.open_highlighted_location(false);
+            }
+            KeyAction::OpenDestination => {
+                self.open_highlighted_location(true);
             }
-            KeyCode::Char('s') => {
+            KeyAction::AwsCli => {
+                if let Some(command) = self.highlighted_cli_command() {
+                    self.copy_text(command);
+                }
+            }
+            KeyAction::NavigateAccounts => {
                 let _ = self.action_tx.send(Action::Navigate {
                     page: ActivePage::S3Creds,
                 });
             }
-            KeyCode::Char('q') => {
+            KeyAction::Quit => {
                 let _ = self.action_tx.send(Action::Exit);
             }
-            KeyCode::Char('?') => {
+            KeyAction::NavigateHelp => {
                 let _ = self.action_tx.send(Action::Navigate {
                     page: ActivePage::Help,
                 });
             }
-            KeyCode::Esc => {
+            KeyAction::NavigateStats => {
                 let _ = self.action_tx.send(Action::Navigate {
-                    page: ActivePage::FileManager,
+                    page: ActivePage::Stats,
                 });
             }
+            KeyAction::ToggleManifest => {
+                let _ = self.action_tx.send(Action::ToggleManifest);
+            }
+            KeyAction::AllTransfers if self.props.error_filter.is_some() => {
+                self.filter_by_error(None);
+            }
+            KeyAction::SortTransfers => {
+                self.sort_transfers(self.props.sort.next_column());
+            }
+            KeyAction::ReverseSort => {
+                self.sort_transfers(self.props.sort.reversed());
+            }
+            KeyAction::PauseTransfer => {
+                self.stop_highlighted_transfer(TransferStop::Paused);
+            }
+            KeyAction::CancelTransfer => {
+                self.stop_highlighted_transfer(TransferStop::Cancelled);
+            }
+            KeyAction::ResumeTransfer => {
+                let keys = self.highlighted_job_keys();
+                if !keys.is_empty() {
+                    let _ = self.action_tx.send(Action::ResumeTransfers { keys });
+                }
+            }
+            KeyAction::ActivityLog => {
+                let _ = self.action_tx.send(Action::ToggleActivityLog);
+            }
+            KeyAction::ExportSelection if self.props.total_rows() > 0 => {
+                self.open_selection_input(SelectionFileMode::Export);
+            }
+            KeyAction::ImportSelection => {
+                self.open_selection_input(SelectionFileMode::Import);
+            }
+            // actions of the file manager
             _ => {}
         }
     }
-}
 
-impl TransfersPage {
-    pub fn move_up_table_selection(&mut self) {
-        let i = match self.props.table_state.selected() {
-            Some(i) => {
-                if i == 0_usize && !self.props.selected_items.is_empty() {
-                    self.props.selected_items.len() - 1
-                } else if i > 0 {
-                    i - 1
-                } else {
-                    0
+    /// Opens the path input filled with the selection file in the directory of the local panel
+    fn open_selection_input(&mut self, mode: SelectionFileMode) {
+        let path = Path::new(&self.props.local_path).join(SELECTION_FILE_NAME);
+        let input = Input::new(path.to_string_lossy().into_owned());
+        self.selection_input = Some((mode, input));
+    }
+
+    /// Enter exports or imports the typed file, Esc closes the input
+    fn handle_selection_input_key_event(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.selection_input = None,
+            KeyCode::Enter => {
+                let Some((mode, input)) = self.selection_input.take() else {
+                    return;
+                };
+                let path = input.value().trim().to_string();
+                if path.is_empty() {
+                    return;
                 }
+                let _ = self.action_tx.send(match mode {
+                    SelectionFileMode::Export => Action::ExportSelection { path },
+                    SelectionFileMode::Import => Action::ImportSelection { path },
+                });
             }
-            None => 0,
+            _ => {
+                if let Some((_, input)) = self.selection_input.as_mut() {
+                    let _ = input.handle_event(&crossterm::event::Event::Key(key));
+                }
+            }
+        }
+    }
+
+    /// Lists only the items failing with the normalized error, or all the items with `None`,
+    /// starting from the first row
+    fn filter_by_error(&mut self, error: Option<String>) {
+        self.props.selected_row = Some(0);
+        self.marked_s3_items.clear();
+        self.marked_local_items.clear();
+        let _ = self
+            .action_tx
+            .send(Action::FilterTransfersByError { error });
+    }
+
+    /// Lists the transfers in the new order starting from the first row, the filter and
+    /// the marks stay
+    fn sort_transfers(&mut self, sort: TransferSort) {
+        self.props.sort = sort;
+        self.props.selected_row = Some(0);
+        let _ = self.action_tx.send(Action::SortTransfers { sort });
+    }
+
+    /// Keys of the files of the highlighted transfer, all the files of a directory
+    fn highlighted_job_keys(&self) -> Vec<String> {
+        let Some(item) = self.highlighted_transfer_item() else {
+            return Vec::new();
         };
-        if !self.props.selected_items.len() > i {
-            self.props.table_state.select(Some(i));
+        let s3_items: Vec<S3SelectedItem> = self
+            .find_s3_item_from_transfer_item(&item)
+            .into_iter()
+            .collect();
+        let local_items: Vec<LocalSelectedItem> = if s3_items.is_empty() {
+            self.find_local_item_from_transfer_item(&item)
+                .into_iter()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        TransferJob::queue(s3_items, local_items)
+            .iter()
+            .map(TransferJob::key)
+            .collect()
+    }
+
+    /// Pauses ('p') or cancels ('X') the highlighted transfer, the finished files of a
+    /// directory stay done
+    fn stop_highlighted_transfer(&mut self, stop: TransferStop) {
+        let keys = self.highlighted_job_keys();
+        if !keys.is_empty() {
+            let _ = self.action_tx.send(Action::StopTransfers { keys, stop });
         }
     }
 
-    pub fn move_down_table_selection(&mut self) {
-        let i = match self.props.table_state.selected() {
-            Some(i) => {
-                if !self.props.selected_items.is_empty() && i >= self.props.selected_items.len() - 1
-                {
-                    0
-                } else {
-                    i + 1
-                }
+    /// Runs the marked items or everything when nothing is marked
+    pub fn run_transfers(&mut self) {
+        if self.marked_s3_items.is_empty() && self.marked_local_items.is_empty() {
+            let _ = self.action_tx.send(Action::RunTransfers);
+        } else {
+            let _ = self.action_tx.send(Action::RunSelectedTransfers {
+                s3_items: std::mem::take(&mut self.marked_s3_items),
+                local_items: std::mem::take(&mut self.marked_local_items),
+            });
+        }
+    }
+
+    pub fn run_highlighted_transfer(&mut self) {
+        if let Some(item) = self.highlighted_transfer_item() {
+            let s3_items: Vec<S3SelectedItem> = self
+                .find_s3_item_from_transfer_item(&item)
+                .into_iter()
+                .collect();
+            let local_items: Vec<LocalSelectedItem> = if s3_items.is_empty() {
+                self.find_local_item_from_transfer_item(&item)
+                    .into_iter()
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            if !s3_items.is_empty() || !local_items.is_empty() {
+                let _ = self.action_tx.send(Action::RunSelectedTransfers {
+                    s3_items,
+                    local_items,
+                });
             }
-            None => 0,
-        };
-        if !self.props.selected_items.len() > i {
-            self.props.table_state.select(Some(i));
+        }
+    }
+
+    /// Source or destination location of the highlighted transfer
+    fn highlighted_jump_target(&self, destination: bool) -> Option<JumpTarget> {
+        let item = self.highlighted_transfer_item()?;
+        if let Some(s3_item) = self.find_s3_item_from_transfer_item(&item) {
+            Some(if destination {
+                JumpTarget::download_destination(&s3_item)
+            } else {
+                JumpTarget::download_source(&s3_item)
+            })
+        } else {
+            self.find_local_item_from_transfer_item(&item)
+                .map(|local_item| {
+                    if destination {
+                        JumpTarget::upload_destination(&local_item)
+                    } else {
+                        JumpTarget::upload_source(&local_item)
+                    }
+                })
+        }
+    }
+
+    /// Shows the source ('o') or destination ('O') of the highlighted transfer in the file manager
+    pub fn open_highlighted_location(&mut self, destination: bool) {
+        if let Some(target) = self.highlighted_jump_target(destination) {
+            let _ = self.action_tx.send(Action::JumpTo { target });
         }
     }
 
@@ -208,7 +992,7 @@ impl TransfersPage {
                     && item.name == transfer_item.name
                     && item.path.as_str() == transfer_item.path.as_deref().unwrap_or("")
                     && item.destination_path == transfer_item.destination_dir
-                    && item.s3_creds == transfer_item.s3_creds
+                    && item.account == transfer_item.account
             })
             .cloned()
     }
@@ -218,76 +1002,342 @@ impl TransfersPage {
             Row::new(item.to_columns().clone()).fg(Color::Red)
         } else if item.transferred {
             Row::new(item.to_columns().clone()).fg(Color::Blue)
+        } else if self.is_marked(item) {
+            Row::new(item.to_columns().clone())
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
         } else {
             Row::new(item.to_columns().clone())
         }
     }
 
-    fn flatten_s3_items(&self, s3_selected_items: Vec<S3SelectedItem>) -> Vec<S3SelectedItem> {
-        let nested: Vec<Vec<S3SelectedItem>> = s3_selected_items
-            .iter()
-            .map(|i| i.clone().children.unwrap_or_default())
-            .collect();
-        let mut children: Vec<S3SelectedItem> = nested.into_iter().flatten().collect();
-        let single_files: Vec<S3SelectedItem> = s3_selected_items
-            .into_iter()
-            .filter(|i| i.children.is_none())
-            .collect();
-        children.extend(single_files);
-        children
+    fn account_creds(&self, account: &str) -> Option<&FileCredential> {
+        self.props.creds.iter().find(|c| c.name == account)
     }
 
-    fn flatten_local_items(
-        &self,
-        local_selected_items: Vec<LocalSelectedItem>,
-    ) -> Vec<LocalSelectedItem> {
-        let nested: Vec<Vec<LocalSelectedItem>> = local_selected_items
-            .iter()
-            .map(|i| i.clone().children.unwrap_or_default())
-            .collect();
-        let mut children: Vec<LocalSelectedItem> = nested.into_iter().flatten().collect();
-        let single_files: Vec<LocalSelectedItem> = local_selected_items
-            .into_iter()
-            .filter(|i| i.children.is_none())
-            .collect();
-        children.extend(single_files);
-        children
+    /// aws cli command repeating the highlighted transfer of a single file
+    fn highlighted_cli_command(&self) -> Option<String> {
+        let item = self.highlighted_transfer_item()?;
+        let env = |name: &str| std::env::var(name).ok();
+        if let Some(s3_item) = self.find_s3_item_from_transfer_item(&item) {
+            if s3_item.is_bucket || s3_item.is_directory {
+                return None;
+            }
+            let key = s3_item.path.clone().unwrap_or(s3_item.name.clone());
+            let creds = self.account_creds(&s3_item.account)?;
+            return Some(CliAccount::new(creds, env).get_object(
+                s3_item.bucket.as_deref()?,
+                &key,
+                &s3_item.local_destination().to_string_lossy(),
+            ));
+        }
+        let local_item = self
+            .find_local_item_from_transfer_item(&item)
+            .filter(|i| !i.is_directory)?;
+        let creds = self.account_creds(&local_item.account)?;
+        Some(CliAccount::new(creds, env).put_object(
+            &local_item.destination_bucket,
+            &local_item.destination_key(),
+            &local_item.path,
+        ))
+    }
+
+    /// Copies the text to the clipboard, the popup shows it when it cannot be copied
+    fn copy_text(&mut self, text: String) {
+        if copy_to_clipboard(&text, self.props.osc52_clipboard) {
+            let _ = self.action_tx.send(Action::CopiedToClipboard { text });
+        } else {
+            self.copy_fallback = Some(text);
+        }
+    }
+
+    fn highlighted_dispatch_order(&self) -> Option<DispatchOrder> {
+        self.props
+            .selected_row
+            .map(|row| self.props.item_index(row))
+            .and_then(|index| self.props.dispatch_orders.get(index).copied().flatten())
     }
+
     fn get_status_line(&self) -> Paragraph {
-        let s3_items = self.flatten_s3_items(self.props.s3_selected_items.clone());
-        let local_items = self.flatten_local_items(self.props.local_selected_items.clone());
-        let to_transfer = s3_items.len() + local_items.len();
-        let transferred = s3_items.iter().filter(|i| i.transferred).count()
-            + local_items.iter().filter(|i| i.transferred).count();
-        Paragraph::new(format!(" Transfers: {}/{}", to_transfer, transferred))
+        match self.props.notification.as_ref().filter(|n| !n.is_expired()) {
+            Some(notification) => Paragraph::new(format!(" {}", notification.message))
+                .style(Style::default().fg(Color::White))
+                .bg(Color::Red),
+            None => Paragraph::new(format!(
+                " Transfers: {}/{}{}{}{}{}",
+                self.props.transfers_total,
+                self.props.transfers_done,
+                self.props
+                    .overall_rate
+                    .map(|rate| format!(" • {}, {} left", rate.speed_label(), rate.eta_label()))
+                    .unwrap_or_default(),
+                if self.props.in_flight_requests > 0 {
+                    format!(" • Requests: {}", self.props.in_flight_requests)
+                } else {
+                    String::new()
+                },
+                self.highlighted_dispatch_order()
+                    .map(|o| format!(" • highlighted {}", o))
+                    .unwrap_or_default(),
+                if self.props.write_manifest {
+                    " • manifest on"
+                } else {
+                    ""
+                }
+            ))
             .style(Style::default().fg(Color::White))
-            .bg(Color::Blue)
+            .bg(Color::Blue),
+        }
     }
 
     fn get_help_line(&self) -> Paragraph {
-        if self.props.s3_selected_items.is_empty() && self.props.local_selected_items.is_empty() {
-            Paragraph::new("| 'Esc' - file manager, 's' to select s3 account, ⌫ to remove ")
-                .style(Style::default().fg(Color::White))
-                .bg(Color::Blue)
-                .alignment(Alignment::Right)
+        let context = HintContext {
+            panel: HintPanel::Transfers,
+            popup_open: self.props.blocked_transfers.is_some()
+                || self.props.overlapping_transfers.is_some()
+                || self.props.large_batch.is_some()
+                || self.props.failure_summary.is_some()
+                || self.copy_fallback.is_some()
+                || self.selection_input.is_some(),
+            has_selection: self.props.total_rows() > 0,
+            transfers_running: self.props.transfers_running,
+            inside_bucket: false,
+        };
+        let rotation = rotation_for(self.hints_shown_at.elapsed());
+        Paragraph::new(hint_line(&context, rotation, &self.props.key_bindings))
+            .style(Style::default().fg(Color::White))
+            .bg(Color::Blue)
+            .alignment(Alignment::Right)
+    }
+
+    /// Displayed instead of the table while nothing is selected for transfer
+    fn get_nothing_queued(&self) -> Paragraph<'static> {
+        Paragraph::new(match self.props.error_filter {
+            Some(_) => format!(
+                "No transfer fails with this error anymore - press '{}' to list all transfers",
+                displayed_keys(Command::ErrorFilter, &self.props.key_bindings)
+            ),
+            None => format!(
+                "Nothing queued - press '{}' in the file manager to select items",
+                displayed_keys(Command::ToggleTransfer, &self.props.key_bindings)
+            ),
+        })
+        .wrap(Wrap { trim: false })
+        .block(self.transfers_block("Transfers List".to_string()))
+    }
+
+    /// Border of the transfers list showing the configured download directory at the bottom
+    fn transfers_block(&self, title: String) -> Block<'static> {
+        let block = Block::default().borders(Borders::ALL).title(title);
+        match &self.props.download_dir {
+            Some(dir) => block.title(
+                block::Title::from(format!("| downloads go to {} |", dir))
+                    .alignment(Alignment::Left)
+                    .position(block::Position::Bottom),
+            ),
+            None => block,
+        }
+    }
+
+    fn get_selection_input(
+        &self,
+        mode: SelectionFileMode,
+        input: &Input,
+        scroll: usize,
+    ) -> Paragraph {
+        let title = match mode {
+            SelectionFileMode::Export => "Export the selection to",
+            SelectionFileMode::Import => "Import a selection from",
+        };
+        let hint = |label: &'static str, key: &'static str| {
+            let mut spans = vec![Span::raw("|")];
+            spans.extend(hint_spans(key, label));
+            Line::from(spans)
+        };
+        Paragraph::new(input.value())
+            .style(Style::default().fg(Color::Green))
+            .scroll((0, scroll as u16))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title(
+                        block::Title::from(hint("ok", "Enter"))
+                            .alignment(Alignment::Right)
+                            .position(block::Position::Bottom),
+                    )
+                    .title(
+                        block::Title::from(hint("cancel", "Esc"))
+                            .alignment(Alignment::Left)
+                            .position(block::Position::Bottom),
+                    ),
+            )
+    }
+
+    fn get_conflicts_popup(&self, blocked: &BlockedTransfers) -> Paragraph {
+        let mut lines = vec![
+            Line::from("Some transfers write to the same destination, the last one would win:"),
+            Line::from(""),
+        ];
+        for conflict in &blocked.conflicts {
+            lines.push(Line::styled(
+                conflict.destination.clone(),
+                Style::default().fg(Color::Yellow),
+            ));
+            lines.extend(
+                conflict
+                    .sources
+                    .iter()
+                    .map(|source| Line::from(format!("  ← {}", source))),
+            );
+        }
+        let mut hints = vec![Span::raw("|")];
+        hints.extend(hint_spans("f", "keep first"));
+        hints.extend(hint_spans("l", "keep last"));
+        hints.extend(hint_spans("r", "rename with suffix"));
+        hints.extend(hint_spans("Esc", "cancel"));
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "| {} destination conflict(s) |",
+                    blocked.conflicts.len()
+                ))
+                .title(
+                    block::Title::from(Line::from(hints))
+                        .alignment(Alignment::Right)
+                        .position(block::Position::Bottom),
+                ),
+        )
+    }
+
+    fn get_overlaps_popup(&self, overlapping: &OverlappingTransfers) -> Paragraph {
+        let mut lines = vec![
+            Line::from("Some downloads and uploads touch the same object or local file:"),
+            Line::from(""),
+        ];
+        for overlap in &overlapping.overlaps {
+            lines.push(Line::styled(
+                format!("↓ {}", overlap.download),
+                Style::default().fg(Color::Yellow),
+            ));
+            lines.push(Line::from(format!("↑ {}", overlap.upload)));
+        }
+        let mut hints = vec![Span::raw("|")];
+        hints.extend(hint_spans("d", "download only"));
+        hints.extend(hint_spans("u", "upload only"));
+        hints.extend(hint_spans("s", "download, then upload"));
+        hints.extend(hint_spans("Esc", "cancel"));
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "| {} overlapping download/upload pair(s) |",
+                    overlapping.overlaps.len()
+                ))
+                .title(
+                    block::Title::from(Line::from(hints))
+                        .alignment(Alignment::Right)
+                        .position(block::Position::Bottom),
+                ),
+        )
+    }
+
+    fn get_failure_summary_popup(&self, summary: &ErrorGroups) -> Paragraph {
+        let mut lines = vec![
+            Line::from("Failed transfers of the last run grouped by their cause:"),
+            Line::from(""),
+        ];
+        for (i, line) in summary.summary().into_iter().enumerate() {
+            lines.push(if i == self.failure_group {
+                Line::styled(
+                    line,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::REVERSED),
+                )
+            } else {
+                Line::styled(line, Style::default().fg(Color::Yellow))
+            });
+        }
+        let mut hints = vec![Span::raw("|")];
+        hints.extend(hint_spans("j/k", "select"));
+        hints.extend(hint_spans("Enter", "list only these"));
+        hints.extend(hint_spans("Esc", "close"));
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("| {} transfer(s) failed |", summary.failed()))
+                .title(
+                    block::Title::from(Line::from(hints))
+                        .alignment(Alignment::Right)
+                        .position(block::Position::Bottom),
+                ),
+        )
+    }
+
+    fn get_large_batch_popup(&self, summary: &[String], typed: bool) -> Paragraph {
+        let mut lines = vec![
+            Line::styled(
+                "This run is very large, it may take a long time:",
+                Style::default().fg(Color::Yellow),
+            ),
+            Line::from(""),
+        ];
+        lines.extend(summary.iter().map(|line| Line::from(line.clone())));
+        lines.push(Line::from(if self.props.write_manifest {
+            "Downloads are listed in manifest.csv of their destination directory"
         } else {
-            Paragraph::new("| Press 'r' to run the transfers ")
-                .style(Style::default().fg(Color::White))
-                .bg(Color::Blue)
-                .alignment(Alignment::Right)
+            "No manifest is written for the downloads"
+        }));
+        lines.push(Line::from(""));
+        if typed {
+            lines.push(Line::from(vec![
+                Span::raw("Type yes and press Enter to start: "),
+                Span::styled(
+                    self.typed_confirmation.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+            ]));
         }
+        let mut hints = vec![Span::raw("|")];
+        if !typed {
+            hints.extend(hint_spans("y", "start"));
+            hints.extend(hint_spans("M", "manifest"));
+        }
+        hints.extend(hint_spans("Esc", "cancel"));
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("| Confirm large run |")
+                .title(
+                    block::Title::from(Line::from(hints))
+                        .alignment(Alignment::Right)
+                        .position(block::Position::Bottom),
+                ),
+        )
     }
 
     fn get_transfers_table(&self) -> Table {
-        let focus_color = Color::Rgb(98, 114, 164);
+        let focus_color = self.props.focus_color;
+        let sort = self.props.sort;
+        let sorted = |title: &str, column: TransferSortColumn| match sort.column == column {
+            true => format!("{} {}", title, sort.arrow()),
+            false => title.to_string(),
+        };
         let header = Row::new(vec![
-            "Up/Down",
-            "Bucket",
-            "Item",
-            "Destination",
-            "S3 Account",
-            "Progress",
-            "Error?",
+            "Up/Down".to_string(),
+            "Bucket".to_string(),
+            sorted("Item", TransferSortColumn::Name),
+            "Destination".to_string(),
+            sorted("S3 Account", TransferSortColumn::Account),
+            sorted("Progress", TransferSortColumn::Progress),
+            "Files".to_string(),
+            "Speed".to_string(),
+            "ETA".to_string(),
+            sorted("Status", TransferSortColumn::Status),
+            "Verification".to_string(),
+            "Error?".to_string(),
         ])
         .fg(focus_color)
         .bold()
@@ -300,34 +1350,57 @@ impl TransfersPage {
             .iter()
             .map(|item| TransfersPage::get_row(self, item));
         let widths = [
-            Constraint::Length(5),
-            Constraint::Length(15),
-            Constraint::Length(20),
-            Constraint::Length(20),
-            Constraint::Length(10),
+            Constraint::Length(4),
             Constraint::Length(10),
-            Constraint::Length(10),
-        ];
-        let table = Table::new(rows, widths)
-            .header(header)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Transfers List"),
-            )
+            Constraint::Length(15),
+            Constraint::Length(13),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(7),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Length(6),
+            Constraint::Length(6),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(self.transfers_block(format!(
+                "Transfers List (page {}/{}){}{}",
+                self.props.window_offset / TRANSFERS_WINDOW_SIZE + 1,
+                self.props.page_count(),
+                match (sort.column, sort.descending) {
+                    (TransferSortColumn::Added, true) => " newest first",
+                    _ => "",
+                },
+                self.props
+                    .error_filter
+                    .as_ref()
+                    .map(|error| format!(
+                        " failing with: {} ('{}' lists all)",
+                        error,
+                        displayed_keys(Command::ErrorFilter, &self.props.key_bindings)
+                    ))
+                    .unwrap_or_default()
+            )))
             .highlight_style(
                 Style::default()
                     .fg(focus_color)
                     .add_modifier(Modifier::REVERSED),
             )
             .widths([
-                Constraint::Percentage(5),
-                Constraint::Percentage(15),
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(10),
-                Constraint::Percentage(10),
+                Constraint::Percentage(4),
                 Constraint::Percentage(10),
+                Constraint::Percentage(15),
+                Constraint::Percentage(13),
+                Constraint::Percentage(8),
+                Constraint::Percentage(9),
+                Constraint::Percentage(9),
+                Constraint::Percentage(7),
+                Constraint::Percentage(6),
+                Constraint::Percentage(7),
+                Constraint::Percentage(6),
+                Constraint::Percentage(6),
             ]);
         table
     }
@@ -342,12 +1415,16 @@ impl ComponentRender<()> for TransfersPage {
                 Constraint::Length(1), // Exactly one line for the bottom
             ])
             .split(frame.size());
-        let table = self.get_transfers_table();
-        frame.render_stateful_widget(
-            &table,
-            vertical_chunks[0],
-            &mut self.props.clone().table_state,
-        );
+        if self.props.total_rows() == 0 {
+            frame.render_widget(self.get_nothing_queued(), vertical_chunks[0]);
+        } else {
+            let table = self.get_transfers_table();
+            frame.render_stateful_widget(
+                &table,
+                vertical_chunks[0],
+                &mut self.props.clone().table_state,
+            );
+        }
         let status_line = self.get_status_line();
         let help_line = self.get_help_line();
         let status_line_layout = Layout::default()
@@ -356,17 +1433,100 @@ impl ComponentRender<()> for TransfersPage {
             .split(vertical_chunks[1]);
         frame.render_widget(status_line, status_line_layout[0]);
         frame.render_widget(help_line, status_line_layout[1]);
+
+        if let Some(blocked) = &self.props.blocked_transfers {
+            let size = frame.size();
+            let area = Rect::new(
+                size.width / 8,
+                size.height / 6,
+                size.width * 3 / 4,
+                size.height * 2 / 3,
+            );
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.get_conflicts_popup(blocked), area);
+        }
+        if let Some(overlapping) = &self.props.overlapping_transfers {
+            let size = frame.size();
+            let area = Rect::new(
+                size.width / 8,
+                size.height / 6,
+                size.width * 3 / 4,
+                size.height * 2 / 3,
+            );
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.get_overlaps_popup(overlapping), area);
+        }
+        if let Some(summary) = &self.props.failure_summary {
+            let size = frame.size();
+            // the groups with the title and hint lines and the borders
+            let height = (summary.groups().len() as u16 + 4).min(size.height - size.height / 3);
+            let area = Rect::new(size.width / 8, size.height / 3, size.width * 3 / 4, height);
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.get_failure_summary_popup(summary), area);
+        }
+        if let Some((summary, typed)) = &self.props.large_batch {
+            let size = frame.size();
+            // the summary with the title, manifest, confirmation and hint lines and the borders
+            let height = (summary.len() as u16 + 8).min(size.height - size.height / 3);
+            let area = Rect::new(size.width / 6, size.height / 3, size.width * 2 / 3, height);
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.get_large_batch_popup(summary, *typed), area);
+        }
+        if let Some(text) = &self.copy_fallback {
+            let size = frame.size();
+            let height = 8.min(size.height - size.height / 3);
+            let area = Rect::new(size.width / 8, size.height / 3, size.width * 3 / 4, height);
+            frame.render_widget(Clear, area);
+            frame.render_widget(fallback_popup(text), area);
+        }
+        if let Some((mode, input)) = &self.selection_input {
+            let size = frame.size();
+            let area = Rect::new(size.width / 8, size.height / 3, size.width * 3 / 4, 3);
+            let scroll = input.visual_scroll(area.width.saturating_sub(2) as usize);
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.get_selection_input(*mode, input, scroll), area);
+            frame.set_cursor(
+                area.x + (input.visual_cursor() - scroll) as u16 + 1,
+                area.y + 1,
+            );
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::large_batch::LargeBatch;
     use crate::model::local_selected_item::LocalSelectedItem;
     use crate::model::s3_selected_item::S3SelectedItem;
+    use crate::services::transfer_queue::TransferJob;
     use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+    use ratatui::backend::TestBackend;
     use tokio::sync::mpsc;
 
+    fn rendered_text(page: &TransfersPage, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| page.render(frame, ())).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn empty_transfers_show_how_to_queue_items() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let page = TransfersPage::new(&State::default(), tx);
+        let text = rendered_text(&page, 100, 10);
+        assert!(text.contains("Nothing queued - press 't' in the file manager to select items"));
+        assert!(!text.contains("Destination"));
+        assert!(text.contains(" Transfers: 0/0"));
+        rendered_text(&page, 10, 2);
+    }
+
     #[tokio::test]
     async fn test_key_event_handling() {
         let (tx, mut rx) = mpsc::unbounded_channel();
@@ -463,6 +1623,205 @@ mod tests {
         );
     }
 
+    fn state_with_s3_items(count: usize) -> State {
+        let mut state = State::default();
+        for i in 0..count {
+            state.add_s3_selected_item(S3SelectedItem {
+                bucket: Some("test-bucket".into()),
+                name: format!("file{}.txt", i),
+                path: Some(format!("path/to/file{}.txt", i)),
+                is_directory: false,
+                is_bucket: false,
+                destination_dir: "".to_string(),
+                transferred: false,
+                account: Default::default(),
+                progress: 0f64,
+                children: None,
+                error: None,
+                destination_path: None,
+                verification: None,
+                completed_children: 0,
+            });
+        }
+        state
+    }
+
+    #[test]
+    fn props_convert_only_the_visible_window() {
+        let state = state_with_s3_items(TRANSFERS_WINDOW_SIZE * 3 + 5);
+        let props = Props::from(&state);
+        assert_eq!(props.selected_items.len(), TRANSFERS_WINDOW_SIZE);
+        assert_eq!(props.total_rows(), TRANSFERS_WINDOW_SIZE * 3 + 5);
+        assert_eq!(props.page_count(), 4);
+        assert_eq!(props.transfers_total, TRANSFERS_WINDOW_SIZE * 3 + 5);
+    }
+
+    #[test]
+    fn moving_up_from_first_row_wraps_to_last_window() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let total = TRANSFERS_WINDOW_SIZE * 2 + 50;
+        let state = state_with_s3_items(total);
+        let mut page = TransfersPage::new(&state, tx);
+
+        page.move_down_table_selection();
+        assert_eq!(page.props.selected_row, Some(0));
+        page.move_up_table_selection();
+
+        assert_eq!(page.props.selected_row, Some(total - 1));
+        assert_eq!(page.props.window_offset, TRANSFERS_WINDOW_SIZE * 2);
+        assert_eq!(page.props.selected_items.len(), 50);
+        assert_eq!(page.props.table_state.selected(), Some(49));
+        assert_eq!(
+            page.props.selected_items[49].name,
+            format!("file{}.txt", total - 1)
+        );
+
+        page.move_down_table_selection();
+        assert_eq!(page.props.selected_row, Some(0));
+        assert_eq!(page.props.window_offset, 0);
+        assert_eq!(page.props.table_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn window_survives_state_updates_and_clamps_to_new_size() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let state = state_with_s3_items(TRANSFERS_WINDOW_SIZE + 10);
+        let mut page = TransfersPage::new(&state, tx);
+        page.props.select_row(TRANSFERS_WINDOW_SIZE + 5);
+
+        let page = page.move_with_state(&state);
+        assert_eq!(page.props.selected_row, Some(TRANSFERS_WINDOW_SIZE + 5));
+        assert_eq!(page.props.table_state.selected(), Some(5));
+
+        let smaller_state = state_with_s3_items(3);
+        let page = page.move_with_state(&smaller_state);
+        assert_eq!(page.props.selected_row, Some(2));
+        assert_eq!(page.props.window_offset, 0);
+        assert_eq!(page.props.table_state.selected(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn unselect_resolves_item_in_later_window() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let state = state_with_s3_items(TRANSFERS_WINDOW_SIZE + 10);
+        let mut page = TransfersPage::new(&state, tx);
+        page.props.select_row(TRANSFERS_WINDOW_SIZE + 7);
+        page.move_page_down();
+        assert_eq!(page.props.selected_row, Some(TRANSFERS_WINDOW_SIZE + 9));
+
+        page.unselect_transfer_item();
+        match rx.recv().await.unwrap() {
+            Action::UnselectS3Item { item } => {
+                assert_eq!(item.name, format!("file{}.txt", TRANSFERS_WINDOW_SIZE + 9))
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn sorted_rows_act_on_the_displayed_item() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(4);
+        let progress = [40.0, 75.0, 40.0, 10.0];
+        for (item, progress) in state.s3_selected_items.iter_mut().zip(progress) {
+            item.progress = progress;
+        }
+        let mut page = TransfersPage::new(&state, tx);
+        // Added -> Name -> Progress
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT));
+        let sort = match (rx.recv().await.unwrap(), rx.recv().await.unwrap()) {
+            (Action::SortTransfers { .. }, Action::SortTransfers { sort }) => sort,
+            other => panic!("unexpected actions {:?}", other),
+        };
+        assert_eq!(sort.column, TransferSortColumn::Progress);
+        state.transfer_sort = sort.reversed();
+        let mut page = page.move_with_state(&state);
+        let names: Vec<&str> = page
+            .props
+            .selected_items
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        // the tie keeps the order the items were added in
+        assert_eq!(names, ["file1.txt", "file0.txt", "file2.txt", "file3.txt"]);
+        assert!(rendered_text(&page, 150, 10).contains("Progress ▼"));
+
+        page.move_down_table_selection();
+        page.move_down_table_selection();
+        page.unselect_transfer_item();
+        match rx.recv().await.unwrap() {
+            Action::UnselectS3Item { item } => assert_eq!(item.name, "file2.txt"),
+            other => panic!("unexpected action {:?}", other),
+        }
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('R'), KeyModifiers::SHIFT));
+        match rx.recv().await.unwrap() {
+            Action::RunSelectedTransfers { s3_items, .. } => {
+                assert_eq!(s3_items[0].name, "file2.txt")
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+    }
+
+    #[test]
+    fn running_transfers_show_their_speed_and_time_left() {
+        let mut state = state_with_s3_items(2);
+        let jobs = TransferJob::queue(state.s3_selected_items.clone(), Vec::new());
+        state.start_transfer_batch(&jobs);
+        let start = Instant::now();
+        state.transfer_rates.record(jobs[0].key(), 0, 1000, start);
+        let later = start + std::time::Duration::from_secs(2);
+        state.transfer_rates.record(jobs[0].key(), 200, 1000, later);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let page = TransfersPage::new(&state, tx);
+
+        assert_eq!(
+            page.props.selected_items[0].to_columns()[7..9],
+            ["100 B/s", "8s"]
+        );
+        // the queued transfer isn't measured yet
+        assert_eq!(page.props.selected_items[1].to_columns()[7..9], ["-", "-"]);
+        assert!(rendered_text(&page, 150, 10).contains("100 B/s, 8s left"));
+    }
+
+    #[tokio::test]
+    async fn pause_resume_and_cancel_the_highlighted_transfer() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(3);
+        let mut page = TransfersPage::new(&state, tx);
+        page.move_down_table_selection();
+        let key = "↓test-bucket/path/to/file1.txt".to_string();
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::StopTransfers {
+                keys: vec![key.clone()],
+                stop: TransferStop::Paused
+            }
+        );
+        state.paused_transfers.insert(key.clone());
+        let mut page = page.move_with_state(&state);
+        assert_eq!(page.props.selected_items[1].to_columns()[5], "paused");
+        assert_eq!(page.props.selected_items[0].to_columns()[5], "0.00%");
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ResumeTransfers {
+                keys: vec![key.clone()]
+            }
+        );
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::StopTransfers {
+                keys: vec![key],
+                stop: TransferStop::Cancelled
+            }
+        );
+    }
+
     #[test]
     fn get_s3_row_no_modifiers_constructs_plain_row() {
         let (tx, _rx) = mpsc::unbounded_channel();
@@ -476,10 +1835,13 @@ mod tests {
             is_bucket: true,
             destination_dir: "".to_string(),
             transferred: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             progress: 0f64,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
         let transfer_item = TransferItem::from_s3_selected_item(item);
         let res = page.get_row(&transfer_item);
@@ -499,10 +1861,13 @@ mod tests {
             is_bucket: true,
             destination_dir: "".to_string(),
             transferred: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             progress: 0f64,
             children: None,
             error: Some("Error".into()),
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
         let transfer_item = TransferItem::from_s3_selected_item(item);
         let res = page.get_row(&transfer_item);
@@ -525,10 +1890,13 @@ mod tests {
             is_bucket: true,
             destination_dir: "".to_string(),
             transferred: true,
-            s3_creds: Default::default(),
+            account: Default::default(),
             progress: 0f64,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
         let transfer_item = TransferItem::from_s3_selected_item(item);
         let res = page.get_row(&transfer_item);
@@ -551,9 +1919,11 @@ mod tests {
             path: "path/to/file1.txt".into(),
             progress: 0.0,
             is_directory: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             children: None,
             error: None,
+            verification: None,
+            completed_children: 0,
         };
         let transfer_item = TransferItem::from_local_selected_item(item);
         let res = page.get_row(&transfer_item);
@@ -573,9 +1943,11 @@ mod tests {
             path: "path/to/file1.txt".into(),
             progress: 0.0,
             is_directory: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             children: None,
             error: None,
+            verification: None,
+            completed_children: 0,
         };
         let transfer_item = TransferItem::from_local_selected_item(item);
         let res = page.get_row(&transfer_item);
@@ -598,9 +1970,11 @@ mod tests {
             path: "path/to/file1.txt".into(),
             progress: 0.0,
             is_directory: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             children: None,
             error: Some("Error".into()),
+            verification: None,
+            completed_children: 0,
         };
         let transfer_item = TransferItem::from_local_selected_item(item);
         let res = page.get_row(&transfer_item);
@@ -609,4 +1983,404 @@ mod tests {
             Row::new(transfer_item.to_columns().clone()).fg(Color::Red)
         );
     }
+
+    #[tokio::test]
+    async fn marked_items_are_run_alone_and_marks_are_cleared() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let state = state_with_s3_items(5);
+        let mut page = TransfersPage::new(&state, tx);
+        page.props.select_row(1);
+        page.toggle_mark_on_highlighted_item();
+        page.props.select_row(3);
+        page.toggle_mark_on_highlighted_item();
+
+        page.run_transfers();
+        match rx.recv().await.unwrap() {
+            Action::RunSelectedTransfers {
+                s3_items,
+                local_items,
+            } => {
+                let names: Vec<String> = s3_items.iter().map(|i| i.name.clone()).collect();
+                assert_eq!(names, vec!["file1.txt", "file3.txt"]);
+                assert!(local_items.is_empty());
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+
+        page.run_transfers();
+        assert_eq!(rx.recv().await.unwrap(), Action::RunTransfers);
+    }
+
+    #[tokio::test]
+    async fn run_highlighted_sends_only_that_item() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let state = state_with_s3_items(3);
+        let mut page = TransfersPage::new(&state, tx);
+        page.props.select_row(2);
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('R'), KeyModifiers::SHIFT));
+        match rx.recv().await.unwrap() {
+            Action::RunSelectedTransfers { s3_items, .. } => {
+                assert_eq!(s3_items.len(), 1);
+                assert_eq!(s3_items[0].name, "file2.txt");
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn open_keys_jump_to_source_and_destination() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let state = state_with_s3_items(2);
+        let mut page = TransfersPage::new(&state, tx);
+        page.props.select_row(1);
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::JumpTo {
+                target: JumpTarget::S3 {
+                    bucket: "test-bucket".into(),
+                    prefix: Some("path/to/".into()),
+                    item_name: Some("file1.txt".into()),
+                    account: Default::default(),
+                }
+            }
+        );
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('O'), KeyModifiers::SHIFT));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::JumpTo {
+                target: JumpTarget::Local {
+                    directory: "path/to".into(),
+                    item_name: Some("file1.txt".into()),
+                }
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn conflict_popup_keys_resolve_the_blocked_run() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(2);
+        state.blocked_transfers = Some(BlockedTransfers {
+            jobs: Vec::new(),
+            conflicts: Vec::new(),
+        });
+        let mut page = TransfersPage::new(&state, tx);
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ResolveTransferConflicts {
+                resolution: Some(ConflictResolution::KeepLast)
+            }
+        );
+
+        // the store closes the popup, then another run is blocked
+        let blocked = state.blocked_transfers.take();
+        let page = page.move_with_state(&state);
+        state.blocked_transfers = blocked;
+        let mut page = page.move_with_state(&state);
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ResolveTransferConflicts { resolution: None }
+        );
+    }
+
+    #[tokio::test]
+    async fn overlap_popup_keys_resolve_the_blocked_run() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(2);
+        state.overlapping_transfers = Some(OverlappingTransfers {
+            jobs: Vec::new(),
+            overlaps: Vec::new(),
+        });
+        let mut page = TransfersPage::new(&state, tx);
+
+        // 'r' doesn't start the blocked run
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ResolveTransferOverlaps {
+                resolution: Some(OverlapResolution::DownloadThenUpload)
+            }
+        );
+
+        // the store closes the popup, then another run is blocked
+        let overlaps = state.overlapping_transfers.take();
+        let page = page.move_with_state(&state);
+        state.overlapping_transfers = overlaps;
+        let mut page = page.move_with_state(&state);
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ResolveTransferOverlaps { resolution: None }
+        );
+    }
+
+    fn failed_batch() -> State {
+        let mut state = state_with_s3_items(4);
+        let refused = "cannot connect to endpoint https://minio.local:9000";
+        for (i, item) in state.s3_selected_items.iter_mut().enumerate() {
+            item.error = Some(match i {
+                3 => "AccessDenied: Access Denied (Request ID: 4442587FB7D0A2F9)".into(),
+                _ => refused.into(),
+            });
+        }
+        let mut failures = ErrorGroups::default();
+        for item in &state.s3_selected_items {
+            failures.add(item.error.as_deref().unwrap());
+        }
+        state.failure_summary = Some(failures);
+        state
+    }
+
+    #[test]
+    fn failure_summary_popup_lists_the_groups() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let page = TransfersPage::new(&failed_batch(), tx);
+        let text = rendered_text(&page, 120, 24);
+        assert!(text.contains("| 4 transfer(s) failed |"), "{}", text);
+        assert!(
+            text.contains("3 item(s) failed: cannot connect to endpoint https://minio.local:9000"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("1 item(s) failed: AccessDenied: Access Denied (Request ID: <id>)"),
+            "{}",
+            text
+        );
+        assert!(text.contains("list only these"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn picked_failure_group_filters_the_transfers() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = failed_batch();
+        let mut page = TransfersPage::new(&state, tx);
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+        let error = "AccessDenied: Access Denied (Request ID: <id>)".to_string();
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::FilterTransfersByError {
+                error: Some(error.clone())
+            }
+        );
+
+        state.failure_summary = None;
+        state.transfer_error_filter = Some(error);
+        let mut page = page.move_with_state(&state);
+        assert_eq!(page.props.total_rows(), 1);
+        assert_eq!(page.props.selected_items[0].name, "file3.txt");
+        assert!(rendered_text(&page, 120, 24).contains("failing with: AccessDenied"));
+
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::FilterTransfersByError { error: None }
+        );
+    }
+
+    #[tokio::test]
+    async fn large_run_needs_the_typed_confirmation() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(2);
+        state.large_batch = Some(LargeBatch {
+            jobs: Vec::new(),
+            concurrency: 8,
+            estimate: None,
+            typed_confirmation: true,
+            adjusted_names: 0,
+        });
+        let mut page = TransfersPage::new(&state, tx);
+
+        // a single key doesn't start the largest runs
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+        assert!(rx.try_recv().is_err());
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ConfirmLargeBatch { confirmed: true }
+        );
+
+        // the store closes the popup, then another large run waits for a confirmation
+        let mut large_batch = state.large_batch.take();
+        let page = page.move_with_state(&state);
+        large_batch.as_mut().unwrap().typed_confirmation = false;
+        state.large_batch = large_batch;
+        let mut page = page.move_with_state(&state);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('M'), KeyModifiers::SHIFT));
+        assert_eq!(rx.recv().await.unwrap(), Action::ToggleManifest);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ConfirmLargeBatch { confirmed: false }
+        );
+    }
+
+    #[tokio::test]
+    async fn popups_stay_as_left_while_unrelated_states_arrive() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(2);
+        state.large_batch = Some(LargeBatch {
+            jobs: Vec::new(),
+            concurrency: 8,
+            estimate: None,
+            typed_confirmation: true,
+            adjusted_names: 0,
+        });
+        let mut page = TransfersPage::new(&state, tx);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::empty()));
+
+        // progress of the other transfers doesn't touch the typed confirmation
+        for done in 1..=3 {
+            state.transfers_done = done;
+            page = page.move_with_state(&state);
+        }
+        assert!(page.props.large_batch.is_some());
+        assert_eq!(page.typed_confirmation, "ye");
+
+        // states broadcast before the store handles Esc don't bring the popup back
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ConfirmLargeBatch { confirmed: false }
+        );
+        state.transfers_done = 4;
+        let page = page.move_with_state(&state);
+        assert!(page.props.large_batch.is_none());
+        assert!(page.typed_confirmation.is_empty());
+    }
+
+    #[test]
+    fn aws_cli_command_is_shown_without_a_clipboard() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(1);
+        state.creds = vec![FileCredential::default()];
+        state.app_config.osc52_clipboard = false;
+        state.s3_selected_items[0].destination_dir = "/home/me".into();
+        let mut page = TransfersPage::new(&state, tx);
+        page.handle_key_event(KeyEvent::new(KeyCode::Down, KeyModifiers::empty()));
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()));
+
+        let command = page.copy_fallback.clone().unwrap();
+        assert!(command.starts_with(
+            "aws s3api get-object --bucket test-bucket --key path/to/file0.txt \
+             /home/me/path/to/file0.txt"
+        ));
+        assert!(rendered_text(&page, 120, 24).contains("No clipboard"));
+
+        // Esc closes the popup without leaving the page
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert!(page.copy_fallback.is_none());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn highlighted_row_shows_its_dispatch_order() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(3);
+        let order = DispatchOrder {
+            sequence: 2,
+            total: 3,
+        };
+        let key = crate::services::transfer_queue::download_key(&state.s3_selected_items[1]);
+        state.record_dispatch(key, order);
+        let mut page = TransfersPage::new(&state, tx);
+
+        page.props.select_row(0);
+        assert_eq!(page.highlighted_dispatch_order(), None);
+        page.props.select_row(1);
+        assert_eq!(page.highlighted_dispatch_order(), Some(order));
+    }
+
+    #[test]
+    fn rows_tell_waiting_transfers_from_queued_ones() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(6);
+        state.app_config.transfer_concurrency = 2;
+        let jobs = TransferJob::queue(state.s3_selected_items[..4].to_vec(), Vec::new());
+        state.start_transfer_batch(&jobs);
+        for (i, job) in jobs.iter().take(2).enumerate() {
+            let order = DispatchOrder {
+                sequence: i + 1,
+                total: 4,
+            };
+            state.record_dispatch(job.key(), order);
+        }
+        state.s3_selected_items[0].progress = 25f64;
+        let page = TransfersPage::new(&state, tx);
+
+        let progress: Vec<String> = page
+            .props
+            .selected_items
+            .iter()
+            .map(|item| item.to_columns()[5].clone())
+            .collect();
+        assert_eq!(
+            progress,
+            vec![
+                "25.00%",
+                "0.00%",
+                "waiting (2 of 2 slots busy)",
+                "waiting (2 of 2 slots busy)",
+                "queued",
+                "queued",
+            ]
+        );
+    }
+
+    #[test]
+    fn items_in_progress_cannot_be_marked() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(2);
+        state.s3_selected_items[0].progress = 40f64;
+        let mut page = TransfersPage::new(&state, tx);
+        page.props.select_row(0);
+        page.toggle_mark_on_highlighted_item();
+        assert!(page.marked_s3_items.is_empty());
+
+        page.props.select_row(1);
+        page.toggle_mark_on_highlighted_item();
+        assert_eq!(page.marked_s3_items.len(), 1);
+        page.toggle_mark_on_highlighted_item();
+        assert!(page.marked_s3_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn selection_file_path_is_asked_for() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = state_with_s3_items(1);
+        state.current_local_path = "/home/me".into();
+        let mut page = TransfersPage::new(&state, tx);
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::empty()));
+        assert!(rendered_text(&page, 120, 24).contains("Export the selection to"));
+        page.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Action::ExportSelection {
+                path: "/home/me/s3tui-selection.json".into()
+            }
+        );
+
+        // Esc leaves without an import
+        page.handle_key_event(KeyEvent::new(KeyCode::Char('L'), KeyModifiers::SHIFT));
+        assert!(page.selection_input.is_some());
+        page.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert!(page.selection_input.is_none());
+        assert!(rx.try_recv().is_err());
+    }
 }
@@ -0,0 +1,498 @@
+//! This module provides the `upload` and `download` subcommands, running the transfers without
+//! the user interface. The progress goes to stderr and a failed transfer makes the process exit
+//! with a non-zero status, so they can be used in scripts
+use crate::cli::Command;
+use crate::model::download_progress_item::DownloadProgressItem;
+use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::s3_selected_item::S3SelectedItem;
+use crate::model::upload_progress_item::UploadProgressItem;
+use crate::model::verification::Verification;
+use crate::services::local_walker::{IgnoreRules, WalkOptions};
+use crate::services::region_redirect::BucketRegions;
+use crate::services::s3_data_fetcher::S3DataFetcher;
+use crate::services::selection_limit::SelectionLimit;
+use crate::services::task_registry::InFlightRequests;
+use crate::settings::app_config::AppConfig;
+use crate::settings::file_credentials::{load_credentials, FileCredential};
+use crate::utils::format_bytes;
+use color_eyre::eyre::{self, eyre};
+use std::collections::HashSet;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Bucket and key of an `s3://bucket/key` argument, the key is empty for the whole bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Url {
+    pub bucket: String,
+    pub key: String,
+}
+
+impl S3Url {
+    pub fn parse(url: &str) -> eyre::Result<S3Url> {
+        let location = url
+            .strip_prefix("s3://")
+            .ok_or_else(|| eyre!("'{}' is not an s3://bucket/key location", url))?;
+        let (bucket, key) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            return Err(eyre!("'{}' has no bucket", url));
+        }
+        Ok(S3Url {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    fn describe(&self, key: &str) -> String {
+        format!("s3://{}/{}", self.bucket, key)
+    }
+}
+
+/// Runs the subcommand, the error tells why the process exits with a failure
+pub async fn run(command: Command, app_config: &AppConfig) -> eyre::Result<()> {
+    match command {
+        Command::Upload {
+            local_path,
+            destination,
+            creds,
+            recursive,
+        } => {
+            let creds = pick_credential(load_credentials()?, creds.as_deref())?;
+            let url = S3Url::parse(&destination)?;
+            let items = upload_items(&local_path, &url, &creds.name, recursive, app_config)?;
+            let fetcher = fetcher_for(creds.clone(), app_config);
+            let mut failed = 0;
+            for item in &items {
+                let label = format!("{} -> {}", item.path, url.describe(&item.destination_key()));
+                let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+                let upload = fetcher.upload_item(item.clone(), creds.clone(), progress_tx);
+                let bytes = |p: &UploadProgressItem| (p.bytes, p.total_bytes);
+                match with_progress(&label, upload, progress_rx, bytes).await {
+                    Ok(uploaded) => report_done(&label, uploaded.bytes, uploaded.verification),
+                    Err(e) => {
+                        eprintln!("{} failed: {}", label, e);
+                        failed += 1;
+                    }
+                }
+            }
+            finish("upload", items.len(), failed)
+        }
+        Command::Download {
+            source,
+            directory,
+            creds,
+            recursive,
+        } => {
+            let creds = pick_credential(load_credentials()?, creds.as_deref())?;
+            let url = S3Url::parse(&source)?;
+            let fetcher = fetcher_for(creds.clone(), app_config);
+            let keys = if recursive {
+                let prefix = (!url.key.is_empty()).then(|| url.key.clone());
+                let listed = fetcher
+                    .list_all_objects(&url.bucket, prefix, SelectionLimit::new(app_config))
+                    .await?;
+                if let Some(exceeded) = listed.exceeded {
+                    return Err(eyre!(exceeded));
+                }
+                listed
+                    .items
+                    .into_iter()
+                    .filter(|item| !item.is_directory)
+                    .map(|item| item.path)
+                    .collect()
+            } else if url.key.is_empty() || url.key.ends_with('/') {
+                return Err(eyre!("'{}' is a prefix, use --recursive", source));
+            } else {
+                vec![url.key.clone()]
+            };
+            if keys.is_empty() {
+                eprintln!("Nothing to download under {}", source);
+            }
+            let mut failed = 0;
+            for key in &keys {
+                let item = download_item(&url, key, &creds.name, &directory);
+                let destination = item.local_destination();
+                let label = format!("{} -> {}", url.describe(key), destination.display());
+                let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+                let download = fetcher.download_item(item, creds.clone(), progress_tx);
+                let bytes = |p: &DownloadProgressItem| (p.bytes, p.total_bytes);
+                match with_progress(&label, download, progress_rx, bytes).await {
+                    Ok(downloaded) => {
+                        report_done(&label, downloaded.bytes, downloaded.verification);
+                    }
+                    Err(e) => {
+                        eprintln!("{} failed: {}", label, e);
+                        failed += 1;
+                    }
+                }
+            }
+            finish("download", keys.len(), failed)
+        }
+    }
+}
+
+/// Without the popup of the user interface, a skipped tls verification is only announced
+fn fetcher_for(creds: FileCredential, app_config: &AppConfig) -> S3DataFetcher {
+    let mut skip_verify_confirmed = HashSet::new();
+    if creds.insecure_skip_tls_verify {
+        eprintln!(
+            "Warning: the certificates of the endpoint of {} are not verified \
+             (insecure_skip_tls_verify)",
+            creds.name
+        );
+        skip_verify_confirmed.insert(creds.name.clone());
+    }
+    S3DataFetcher::new(
+        creds,
+        app_config,
+        InFlightRequests::default(),
+        BucketRegions::default(),
+        &skip_verify_confirmed,
+    )
+}
+
+/// The credential with the given name, otherwise the one the user interface starts with
+fn pick_credential(
+    accounts: Vec<FileCredential>,
+    name: Option<&str>,
+) -> eyre::Result<FileCredential> {
+    let names = || {
+        accounts
+            .iter()
+            .map(|account| account.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let found = match name {
+        Some(name) => accounts.iter().find(|account| account.name == name),
+        None => accounts
+            .iter()
+            .find(|account| account.selected)
+            .or(accounts.first()),
+    };
+    match (found, name) {
+        (Some(account), _) => Ok(account.clone()),
+        (None, Some(name)) => Err(eyre!(
+            "No account named '{}', the accounts are: {}",
+            name,
+            names()
+        )),
+        (None, None) => Err(eyre!("No credentials found in the creds directory")),
+    }
+}
+
+/// Key of an uploaded file, a key ending with `/` (or none) is the prefix of the file name
+fn upload_key(key: &str, file_name: &str) -> String {
+    if key.is_empty() || key.ends_with('/') {
+        format!("{}{}", key, file_name)
+    } else {
+        key.to_string()
+    }
+}
+
+/// Files to upload, a directory is walked with the `exclude_patterns` of the settings and its
+/// files keep their path relative to it below the key
+fn upload_items(
+    local_path: &str,
+    url: &S3Url,
+    account: &str,
+    recursive: bool,
+    app_config: &AppConfig,
+) -> eyre::Result<Vec<LocalSelectedItem>> {
+    let path = Path::new(local_path);
+    let metadata = path
+        .metadata()
+        .map_err(|e| eyre!("Cannot read {}: {}", local_path, e))?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !metadata.is_dir() {
+        let key = upload_key(&url.key, &name);
+        let item = LocalSelectedItem::new(
+            name,
+            local_path.to_string(),
+            false,
+            url.bucket.clone(),
+            key,
+            account.to_string(),
+            None,
+        );
+        return Ok(vec![item]);
+    }
+    if !recursive {
+        return Err(eyre!("{} is a directory, use --recursive", local_path));
+    }
+    let directory = LocalSelectedItem::new(
+        name,
+        local_path.to_string(),
+        true,
+        url.bucket.clone(),
+        url.key.clone(),
+        account.to_string(),
+        None,
+    );
+    let options = WalkOptions::with_rules(IgnoreRules::new(&app_config.exclude_patterns));
+    Ok(LocalSelectedItem::list_directory_items(
+        &directory, &options,
+    ))
+}
+
+/// Path of a downloaded object below the directory, relative to the last `/` of the listed key
+/// or prefix like `aws s3 cp` does
+fn relative_destination(listed: &str, key: &str) -> String {
+    let base = listed.rfind('/').map_or(0, |i| i + 1);
+    key.get(base..)
+        .filter(|relative| key.starts_with(&listed[..base]) && !relative.is_empty())
+        .unwrap_or(key)
+        .to_string()
+}
+
+fn download_item(url: &S3Url, key: &str, account: &str, directory: &str) -> S3SelectedItem {
+    let relative = relative_destination(&url.key, key);
+    S3SelectedItem {
+        bucket: Some(url.bucket.clone()),
+        name: key.rsplit('/').next().unwrap_or(key).to_string(),
+        path: Some(key.to_string()),
+        is_directory: false,
+        is_bucket: false,
+        destination_dir: directory.to_string(),
+        transferred: false,
+        account: account.to_string(),
+        progress: 0f64,
+        children: None,
+        error: None,
+        destination_path: Some(relative),
+        verification: None,
+        completed_children: 0,
+    }
+}
+
+/// Percent of the transferred bytes, an empty file is done from the start
+fn percent(bytes: u64, total_bytes: u64) -> u64 {
+    if total_bytes == 0 {
+        100
+    } else {
+        (bytes.min(total_bytes) * 100) / total_bytes
+    }
+}
+
+fn progress_line(label: &str, bytes: u64, total_bytes: u64) -> String {
+    format!(
+        "{} {:>3}% ({} of {})",
+        label,
+        percent(bytes, total_bytes),
+        format_bytes(bytes),
+        format_bytes(total_bytes)
+    )
+}
+
+/// Runs the transfer and rewrites its line on stderr whenever the percent changes
+async fn with_progress<T, P>(
+    label: &str,
+    transfer: impl Future<Output = eyre::Result<T>>,
+    mut progress_rx: UnboundedReceiver<P>,
+    progress_bytes: impl Fn(&P) -> (u64, u64),
+) -> eyre::Result<T> {
+    tokio::pin!(transfer);
+    let mut shown = None;
+    let result = loop {
+        tokio::select! {
+            result = &mut transfer => break result,
+            Some(progress) = progress_rx.recv() => {
+                let (bytes, total_bytes) = progress_bytes(&progress);
+                if shown != Some(percent(bytes, total_bytes)) {
+                    shown = Some(percent(bytes, total_bytes));
+                    eprint!("\r{}", progress_line(label, bytes, total_bytes));
+                    let _ = std::io::stderr().flush();
+                }
+            }
+        }
+    };
+    if shown.is_some() {
+        eprintln!();
+    }
+    result
+}
+
+fn report_done(label: &str, bytes: u64, verification: Option<Verification>) {
+    match verification {
+        Some(verification) => eprintln!(
+            "{} done, {} ({})",
+            label,
+            format_bytes(bytes),
+            verification.label()
+        ),
+        None => eprintln!("{} done, {}", label, format_bytes(bytes)),
+    }
+}
+
+fn finish(direction: &str, total: usize, failed: usize) -> eyre::Result<()> {
+    if failed > 0 {
+        Err(eyre!(
+            "{} of {} files failed to {}",
+            failed,
+            total,
+            direction
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn account(name: &str, selected: bool) -> FileCredential {
+        FileCredential {
+            name: name.into(),
+            selected,
+            ..FileCredential::default()
+        }
+    }
+
+    #[test]
+    fn subcommands_are_parsed() {
+        let cli = Cli::try_parse_from([
+            "s3tui",
+            "upload",
+            "reports",
+            "s3://backup/2024/",
+            "--creds",
+            "work",
+            "--recursive",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Command::Upload {
+                local_path: "reports".into(),
+                destination: "s3://backup/2024/".into(),
+                creds: Some("work".into()),
+                recursive: true,
+            })
+        );
+        let cli = Cli::try_parse_from(["s3tui", "download", "s3://logs/a.log", "."]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Download {
+                recursive: false,
+                ..
+            })
+        ));
+        assert_eq!(Cli::try_parse_from(["s3tui"]).unwrap().command, None);
+    }
+
+    #[test]
+    fn s3_locations_are_parsed() {
+        assert_eq!(
+            S3Url::parse("s3://logs/2024/a.log").unwrap(),
+            S3Url {
+                bucket: "logs".into(),
+                key: "2024/a.log".into()
+            }
+        );
+        assert_eq!(S3Url::parse("s3://logs").unwrap().key, "");
+        assert!(S3Url::parse("logs/a.log").is_err());
+        assert!(S3Url::parse("s3:///a.log").is_err());
+    }
+
+    #[test]
+    fn credential_is_picked_by_name_or_the_selected_one() {
+        let accounts = vec![account("home", true), account("work", false)];
+        assert_eq!(
+            pick_credential(accounts.clone(), Some("work"))
+                .unwrap()
+                .name,
+            "work"
+        );
+        assert_eq!(
+            pick_credential(accounts.clone(), None).unwrap().name,
+            "home"
+        );
+        let error = pick_credential(accounts, Some("prod"))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "No account named 'prod', the accounts are: home, work"
+        );
+    }
+
+    #[test]
+    fn uploads_go_below_prefixes_ending_with_a_slash() {
+        assert_eq!(upload_key("", "a.txt"), "a.txt");
+        assert_eq!(upload_key("docs/", "a.txt"), "docs/a.txt");
+        assert_eq!(upload_key("docs/b.txt", "a.txt"), "docs/b.txt");
+    }
+
+    #[test]
+    fn directories_are_uploaded_only_with_recursive() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("nested").join("b.txt"), "b").unwrap();
+        let url = S3Url::parse("s3://backup/2024").unwrap();
+        let local_path = dir.path().to_string_lossy().into_owned();
+        let config = AppConfig::default();
+
+        assert!(upload_items(&local_path, &url, "", false, &config).is_err());
+        let items = upload_items(&local_path, &url, "", true, &config).unwrap();
+        let mut keys: Vec<String> = items.iter().map(|item| item.destination_key()).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["2024/a.txt", "2024/nested/b.txt"]);
+    }
+
+    #[test]
+    fn downloads_keep_the_path_below_the_prefix() {
+        assert_eq!(
+            relative_destination("logs/2024/", "logs/2024/01/a.log"),
+            "01/a.log"
+        );
+        assert_eq!(
+            relative_destination("logs/2024", "logs/2024-old/a.log"),
+            "2024-old/a.log"
+        );
+        assert_eq!(relative_destination("logs/a.log", "logs/a.log"), "a.log");
+        assert_eq!(relative_destination("", "a.log"), "a.log");
+
+        let url = S3Url::parse("s3://logs/2024/").unwrap();
+        let item = download_item(&url, "2024/01/a.log", "", "/tmp/out");
+        assert_eq!(item.local_destination(), Path::new("/tmp/out/01/a.log"));
+    }
+
+    #[test]
+    fn progress_is_shown_in_percent() {
+        assert_eq!(
+            progress_line("a.log", 512, 2048),
+            "a.log  25% (512 B of 2.0 KB)"
+        );
+        assert_eq!(percent(0, 0), 100);
+        assert_eq!(percent(3000, 2048), 100);
+    }
+
+    #[tokio::test]
+    async fn transfer_result_is_returned_once_it_ends() {
+        let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+        let transfer = async move {
+            for bytes in [10u64, 50, 100] {
+                progress_tx.send(bytes).unwrap();
+                tokio::task::yield_now().await;
+            }
+            Ok(100u64)
+        };
+        let result = with_progress("a.log", transfer, progress_rx, |bytes| (*bytes, 100)).await;
+        assert_eq!(result.unwrap(), 100);
+
+        let (_progress_tx, progress_rx) = mpsc::unbounded_channel::<u64>();
+        let failing = async { Err::<(), _>(eyre!("access denied")) };
+        let result = with_progress("b.log", failing, progress_rx, |bytes| (*bytes, 100)).await;
+        assert_eq!(result.unwrap_err().to_string(), "access denied");
+    }
+}
@@ -10,15 +10,17 @@
 #![forbid(unsafe_code)]
 mod cli;
 mod components;
+mod headless;
 mod model;
 mod services;
 mod settings;
 mod state_store;
+mod suspension;
 mod termination;
 mod ui_manager;
 mod utils;
 
-use crate::settings::file_credentials;
+use crate::settings::{app_config, file_credentials, key_bindings};
 use crate::state_store::StateStore;
 use crate::termination::{create_termination, Interrupted};
 use crate::ui_manager::UiManager;
@@ -29,19 +31,47 @@ use color_eyre::eyre;
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
-    initialize_logging()?;
+    let (app_config, app_config_error) = app_config::load_app_config();
+    initialize_logging(&app_config)?;
+    if let Some(e) = app_config_error {
+        tracing::warn!("{}", e);
+    }
+    if let Some(warning) = app_config.transfer_tuning.correction() {
+        tracing::warn!("{} in {}", warning, app_config::APP_CONFIG_FILE);
+    }
+    let args = Cli::parse();
+    if let Some(command) = args.command {
+        // scripts get the reason on stderr and a failure status, without the user interface
+        if let Err(e) = headless::run(command, &app_config).await {
+            eprintln!("s3tui: {}", e);
+            std::process::exit(libc::EXIT_FAILURE);
+        }
+        return Ok(());
+    }
+    let key_bindings = key_bindings::load_key_bindings()?;
+    key_bindings.check_custom_commands(&app_config.custom_commands)?;
     initialize_panic_handler()?;
-    let _args = Cli::parse();
     let (terminator, mut interrupt_rx) = create_termination();
     let (state_store, state_rx) = StateStore::new();
     let (ui_manager, action_rx) = UiManager::new();
 
     if let Ok(creds) = file_credentials::load_credentials() {
         if !creds.is_empty() {
-            tokio::try_join!(
-                state_store.main_loop(terminator, action_rx, interrupt_rx.resubscribe(), creds),
+            let ((_, stats), _) = tokio::try_join!(
+                state_store.main_loop(
+                    terminator,
+                    action_rx,
+                    interrupt_rx.resubscribe(),
+                    creds,
+                    app_config,
+                    key_bindings
+                ),
                 ui_manager.main_loop(state_rx, interrupt_rx.resubscribe()),
             )?;
+            // the terminal is restored by now, the summary stays in the shell
+            if let Some(summary) = stats.exit_summary() {
+                println!("{}", summary);
+            }
         } else {
             eprintln!("No credentials file found, add credentials file into your $S3TUI_DATA/creds directory in your home directory.");
         }
@@ -53,6 +83,7 @@ async fn main() -> eyre::Result<()> {
         match reason {
             Interrupted::UserInt => tracing::info!("exited per user request"),
             Interrupted::OsSigInt => tracing::info!("exited because of an os sig int"),
+            Interrupted::OsSigTerm => tracing::info!("exited because of an os sig term"),
         }
     } else {
         tracing::error!("exited because of an unexpected error");
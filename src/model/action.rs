@@ -1,8 +1,19 @@
 //! This module provides list of all possible actions which can be executed on the UI
+use crate::model::destination_conflict::ConflictResolution;
+use crate::model::jump_target::JumpTarget;
+use crate::model::local_data_item::LocalDataItem;
 use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::navigation_history::NavigationStep;
+use crate::model::navigation_state::NavigationState;
+use crate::model::object_metadata::ObjectMetadata;
 use crate::model::s3_selected_item::S3SelectedItem;
 use crate::model::state::ActivePage;
-use crate::settings::file_credentials::FileCredential;
+use crate::model::transfer_overlap::OverlapResolution;
+use crate::model::transfer_sort::TransferSort;
+use crate::services::transfer_control::TransferStop;
+use crate::services::transfer_persistence::ResumableUpload;
+use crate::settings::custom_command::{CustomCommand, RowValues};
+use crate::settings::file_credentials::{CredentialEntry, FileCredential};
 
 /// List of all possible actions a user can execute
 #[derive(Debug, Clone, PartialEq)]
@@ -13,10 +24,23 @@ pub enum Action {
     FetchLocalData {
         path: String,
     },
-    FetchS3Data {
-        bucket: Option<String>,
-        prefix: Option<String>,
+    /// Moves the s3 panel through its history, `row` is the row highlighted before moving
+    NavigateS3 {
+        step: NavigationStep,
+        row: Option<usize>,
+    },
+    /// Opens the location typed into the go to input once a listing shows it exists,
+    /// `row` is the row highlighted before moving
+    GoToS3Path {
+        location: NavigationState,
+        row: Option<usize>,
     },
+    /// Lists the buckets of the current account again instead of showing the kept list
+    RefreshBuckets,
+    /// Lists the location opened on the s3 panel again, e.g. after a partial listing
+    RefreshS3Location,
+    /// Stops the running listing of the s3 panel, keeping the rows listed so far
+    CancelS3Listing,
     ListS3DataRecursiveForItem {
         item: S3SelectedItem,
     },
@@ -36,16 +60,188 @@ pub enum Action {
     SelectCurrentS3Creds {
         item: FileCredential,
     },
+    /// The user accepted the skipped certificate verification of the account for the session,
+    /// sent before selecting it
+    ConfirmSkipTlsVerify {
+        account: String,
+    },
+    /// Writes the credential file of the accounts page form, `original` is the name of the
+    /// edited credential
+    SaveCredential {
+        original: Option<String>,
+        entry: CredentialEntry,
+    },
+    DeleteCredential {
+        name: String,
+    },
     DeleteS3Item {
         item: S3SelectedItem,
     },
     DeleteLocalItem {
         item: LocalSelectedItem,
     },
+    /// Deletes all the selected s3 objects and local items
+    DeleteSelectedItems,
     CreateBucket {
         name: String,
     },
+    /// Creates the folder `name` under `prefix` of the bucket, the bucket root when `None`
+    CreateFolder {
+        bucket: String,
+        prefix: Option<String>,
+        name: String,
+    },
+    /// Copies the object server side under `destination_prefix` of the bucket, the bucket
+    /// root when `None`. `delete_source` moves it
+    CopyS3Item {
+        item: S3SelectedItem,
+        destination_bucket: String,
+        destination_prefix: Option<String>,
+        delete_source: bool,
+    },
+    /// Moves the object to `new_key` of its bucket, an object already at `new_key` is
+    /// replaced only with `overwrite`
+    RenameS3Item {
+        item: S3SelectedItem,
+        new_key: String,
+        overwrite: bool,
+    },
+    /// Renames the local file or directory within its directory, a file already at
+    /// `new_name` is replaced only with `overwrite`
+    RenameLocalItem {
+        item: LocalDataItem,
+        new_name: String,
+        overwrite: bool,
+    },
     ClearDeletionErrors,
+    ClearCredentialsError,
+    /// Keeps the transfers restored from the previous session in the selection
+    ResumeRestoredTransfers,
+    /// Removes the restored transfers from the selection and the saved queue
+    DiscardRestoredTransfers,
+    /// Runs the restored transfers of the missing account with the replacement account,
+    /// they are dropped without one
+    RemapRestoredAccount {
+        account: String,
+        replacement: Option<String>,
+    },
+    /// Uploads the rest of the upload interrupted by an earlier session, the parts s3 still
+    /// holds are skipped
+    ResumeUpload(ResumableUpload),
+    /// Aborts the interrupted upload so its parts aren't billed anymore
+    AbortUpload(ResumableUpload),
+    /// Keeps the interrupted uploads for the next start
+    PostponeResumableUploads,
     RunTransfers,
+    /// Runs only the given items, the rest of the selected transfers stays queued
+    RunSelectedTransfers {
+        s3_items: Vec<S3SelectedItem>,
+        local_items: Vec<LocalSelectedItem>,
+    },
+    /// Continues the run blocked by destination conflicts, `None` cancels it
+    ResolveTransferConflicts {
+        resolution: Option<ConflictResolution>,
+    },
+    /// Continues the run blocked by overlapping downloads and uploads, `None` cancels it
+    ResolveTransferOverlaps {
+        resolution: Option<OverlapResolution>,
+    },
+    /// Starts or cancels the run waiting for the confirmation of its size
+    ConfirmLargeBatch {
+        confirmed: bool,
+    },
+    /// Hides the summary of the failures of the finished batch
+    CloseFailureSummary,
+    /// Lists only the transfers failing with the normalized error, all of them with `None`,
+    /// and hides the summary of the failures
+    FilterTransfersByError {
+        error: Option<String>,
+    },
+    /// Lists the transfers in the given order
+    SortTransfers {
+        sort: TransferSort,
+    },
+    /// Pauses or cancels the transfers with the given keys
+    StopTransfers {
+        keys: Vec<String>,
+        stop: TransferStop,
+    },
+    /// Runs the paused transfers with the given keys again
+    ResumeTransfers {
+        keys: Vec<String>,
+    },
+    /// Switches writing the manifest of the downloads for the next runs
+    ToggleManifest,
+    /// Shows or hides the activity log pane
+    ToggleActivityLog,
+    /// Shows or hides the hidden files on the local panel
+    ToggleHiddenFiles,
+    /// Writes the selection to a JSON file, see `selection_file`
+    ExportSelection {
+        path: String,
+    },
+    /// Adds the entries of a selection file to the selection with the current account
+    ImportSelection {
+        path: String,
+    },
+    FetchObjectMetadata {
+        bucket: String,
+        key: String,
+    },
+    /// Rewrites the object with the user metadata replaced
+    SaveObjectMetadata {
+        metadata: ObjectMetadata,
+    },
+    /// Copies the object onto itself with the new storage class
+    ChangeStorageClass {
+        bucket: String,
+        key: String,
+        storage_class: String,
+    },
+    CloseObjectMetadata,
+    /// Reads the lock status of the object shown in the delete confirmation
+    FetchObjectLockInfo {
+        bucket: String,
+        key: String,
+    },
+    /// Compares the object with the local file, `full_hash` streams the whole object
+    CompareObjectWithLocal {
+        bucket: String,
+        key: String,
+        local_path: String,
+        full_hash: bool,
+    },
+    CloseObjectComparison,
+    /// Reads the beginning of the object for the preview popup
+    PreviewS3Item {
+        bucket: String,
+        key: String,
+    },
+    ClosePreview,
+    /// Reads the whole object as text to copy it to the clipboard
+    CopyS3ObjectText {
+        bucket: String,
+        key: String,
+    },
+    /// Signs a GET url of the object to copy it to the clipboard
+    PresignObjectUrl {
+        bucket: String,
+        key: String,
+    },
+    /// Tells on the status line that the text was copied to the clipboard
+    CopiedToClipboard {
+        text: String,
+    },
+    /// Shows the location of a transfer in the file manager
+    JumpTo {
+        target: JumpTarget,
+    },
+    /// Runs the custom command of the config file with the values of the highlighted row
+    RunCustomCommand {
+        command: CustomCommand,
+        row: RowValues,
+    },
+    /// Cancels the running s3 requests and transfers without quitting
+    StopNetworkActivity,
     Exit,
 }
@@ -0,0 +1,126 @@
+//! This module provides the recent events of the application shown in the activity log pane
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of entries kept, the oldest ones are dropped
+pub const ACTIVITY_LOG_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityEntry {
+    pub at: SystemTime,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ActivityEntry {
+    /// Time of the entry as `HH:MM:SS` in UTC
+    pub fn time(&self) -> String {
+        let seconds = self
+            .at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+            % 86_400;
+        format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+/// Bounded log of the recent events. The entries are shared between the clones of the state
+/// sent to the UI, an append copies them only while a clone still holds the previous ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActivityLog {
+    entries: Arc<VecDeque<ActivityEntry>>,
+    /// Entries appended since the start, including the dropped ones
+    appended: u64,
+}
+
+impl ActivityLog {
+    pub fn push(&mut self, severity: Severity, message: String) {
+        self.push_entry(ActivityEntry {
+            at: SystemTime::now(),
+            severity,
+            message,
+        });
+    }
+
+    fn push_entry(&mut self, entry: ActivityEntry) {
+        let entries = Arc::make_mut(&mut self.entries);
+        if entries.len() == ACTIVITY_LOG_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+        self.appended += 1;
+    }
+
+    /// Kept entries, the oldest first
+    pub fn entries(&self) -> &VecDeque<ActivityEntry> {
+        &self.entries
+    }
+
+    /// Sequence number of the first kept entry, entries keep their number while newer
+    /// ones are appended
+    pub fn first_sequence(&self) -> u64 {
+        self.appended - self.entries.len() as u64
+    }
+
+    /// Sequence number the next entry gets
+    pub fn end_sequence(&self) -> u64 {
+        self.appended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn oldest_entries_are_dropped_above_the_capacity() {
+        let mut log = ActivityLog::default();
+        for i in 0..ACTIVITY_LOG_CAPACITY + 5 {
+            log.push(Severity::Info, format!("event {}", i));
+        }
+        assert_eq!(log.entries().len(), ACTIVITY_LOG_CAPACITY);
+        assert_eq!(log.entries()[0].message, "event 5");
+        assert_eq!(
+            log.entries().back().unwrap().message,
+            format!("event {}", ACTIVITY_LOG_CAPACITY + 4)
+        );
+        assert_eq!(log.first_sequence(), 5);
+        assert_eq!(log.end_sequence(), ACTIVITY_LOG_CAPACITY as u64 + 5);
+    }
+
+    #[test]
+    fn clones_keep_their_entries() {
+        let mut log = ActivityLog::default();
+        log.push(Severity::Info, "listed".into());
+        let sent = log.clone();
+        log.push(Severity::Error, "failed".into());
+        assert_eq!(sent.entries().len(), 1);
+        assert_eq!(log.entries().len(), 2);
+        assert_eq!(log.entries()[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn time_is_shown_in_utc() {
+        let entry = ActivityEntry {
+            at: UNIX_EPOCH + Duration::from_secs(86_400 * 3 + 13 * 3600 + 5 * 60 + 9),
+            severity: Severity::Warning,
+            message: String::new(),
+        };
+        assert_eq!(entry.time(), "13:05:09");
+    }
+}
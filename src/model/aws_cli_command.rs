@@ -0,0 +1,192 @@
+//! This module provides the aws cli commands doing what the application does,
+//! to reproduce problems outside of it
+use crate::services::s3_keys::api_key;
+use crate::settings::file_credentials::FileCredential;
+
+/// Quotes the value for POSIX shells unless it's made only of characters they take literally
+pub fn shell_quote(value: &str) -> String {
+    let literal = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+    if literal {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// ` --name value`, values starting with a dash are joined with `=` so the cli doesn't
+/// take them for options
+fn option(name: &str, value: &str) -> String {
+    if value.starts_with('-') {
+        format!(" {}={}", name, shell_quote(value))
+    } else {
+        format!(" {} {}", name, shell_quote(value))
+    }
+}
+
+/// Account the commands run with. The profile is the one of the credential, or is named after
+/// the credential and is expected to hold its keys, the secret key never appears in the commands
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliAccount {
+    profile: String,
+    region: String,
+    endpoint_url: Option<String>,
+    leading_slash_keys: bool,
+}
+
+impl CliAccount {
+    /// `env` looks up the environment variables of the application
+    pub fn new(creds: &FileCredential, env: impl Fn(&str) -> Option<String>) -> Self {
+        CliAccount {
+            profile: creds.profile.clone().unwrap_or_else(|| creds.name.clone()),
+            region: creds.default_region.clone(),
+            endpoint_url: creds.endpoint(env),
+            leading_slash_keys: creds.leading_slash_keys,
+        }
+    }
+
+    /// The region of the bucket when it's known, the one of the credential otherwise
+    pub fn in_region(self, region: Option<&str>) -> Self {
+        CliAccount {
+            region: region.unwrap_or(&self.region).to_string(),
+            ..self
+        }
+    }
+
+    fn account_options(&self) -> String {
+        let mut options = String::new();
+        if let Some(url) = &self.endpoint_url {
+            options.push_str(&option("--endpoint-url", url));
+        }
+        options.push_str(&option("--region", &self.region));
+        options.push_str(&option("--profile", &self.profile));
+        options
+    }
+
+    fn object_options(&self, bucket: &str, key: &str) -> String {
+        format!(
+            "{}{}",
+            option("--bucket", bucket),
+            option("--key", &api_key(key, self.leading_slash_keys))
+        )
+    }
+
+    /// Downloads the object into the local file
+    pub fn get_object(&self, bucket: &str, key: &str, local_path: &str) -> String {
+        format!(
+            "aws s3api get-object{} {}{}",
+            self.object_options(bucket, key),
+            shell_quote(local_path),
+            self.account_options()
+        )
+    }
+
+    /// Uploads the local file as the object
+    pub fn put_object(&self, bucket: &str, key: &str, local_path: &str) -> String {
+        format!(
+            "aws s3api put-object{}{}{}",
+            self.object_options(bucket, key),
+            option("--body", local_path),
+            self.account_options()
+        )
+    }
+
+    /// Lists the location the way the s3 panel does, the buckets of the account without a bucket
+    pub fn list_objects(&self, bucket: Option<&str>, prefix: Option<&str>) -> String {
+        let bucket = match bucket {
+            Some(bucket) => bucket,
+            None => return format!("aws s3api list-buckets{}", self.account_options()),
+        };
+        let prefix = prefix
+            .filter(|p| !p.is_empty())
+            .map(|p| option("--prefix", &api_key(p, self.leading_slash_keys)))
+            .unwrap_or_default();
+        format!(
+            "aws s3api list-objects-v2{} --delimiter /{}{}",
+            option("--bucket", bucket),
+            prefix,
+            self.account_options()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account() -> CliAccount {
+        let creds = FileCredential {
+            name: "staging".into(),
+            access_key: "AKIAEXAMPLE".into(),
+            secret_key: "very-secret".into(),
+            default_region: "eu-west-1".into(),
+            ..FileCredential::default()
+        };
+        CliAccount::new(&creds, |_| None)
+    }
+
+    #[test]
+    fn values_are_quoted_only_when_the_shell_would_interpret_them() {
+        assert_eq!(shell_quote("logs/2024/app.log"), "logs/2024/app.log");
+        assert_eq!(shell_quote("my report.pdf"), "'my report.pdf'");
+        assert_eq!(shell_quote("it's.txt"), r"'it'\''s.txt'");
+        assert_eq!(shell_quote("$HOME`id`;*.csv"), "'$HOME`id`;*.csv'");
+        assert_eq!(shell_quote("zdjęcie.jpg"), "'zdjęcie.jpg'");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(option("--key", "-v.txt"), " --key=-v.txt");
+    }
+
+    #[test]
+    fn download_names_the_account_without_its_keys() {
+        let command = account().get_object("data", "reports/q1 final.csv", "/tmp/q1 final.csv");
+        assert_eq!(
+            command,
+            "aws s3api get-object --bucket data --key 'reports/q1 final.csv' \
+             '/tmp/q1 final.csv' --region eu-west-1 --profile staging"
+        );
+        assert!(!command.contains("very-secret"));
+        assert!(!command.contains("AKIAEXAMPLE"));
+    }
+
+    #[test]
+    fn upload_uses_the_endpoint_and_the_bucket_region() {
+        let creds = FileCredential {
+            name: "minio".into(),
+            default_region: "us-east-1".into(),
+            leading_slash_keys: true,
+            ..FileCredential::default()
+        };
+        let account = CliAccount::new(&creds, |name| match name {
+            "AWS_ENDPOINT_URL_S3" => Some("https://minio.local:9000".into()),
+            "AWS_ENDPOINT_URL" => Some("https://other.local".into()),
+            _ => None,
+        })
+        .in_region(Some("eu-central-1"));
+        assert_eq!(
+            account.put_object("media", "-draft.txt", "/home/me/-draft.txt"),
+            "aws s3api put-object --bucket media --key /-draft.txt --body /home/me/-draft.txt \
+             --endpoint-url https://minio.local:9000 --region eu-central-1 --profile minio"
+        );
+    }
+
+    #[test]
+    fn listing_follows_the_location() {
+        let account = account();
+        assert_eq!(
+            account.list_objects(None, None),
+            "aws s3api list-buckets --region eu-west-1 --profile staging"
+        );
+        assert_eq!(
+            account.list_objects(Some("data"), Some("")),
+            "aws s3api list-objects-v2 --bucket data --delimiter / \
+             --region eu-west-1 --profile staging"
+        );
+        assert_eq!(
+            account.list_objects(Some("data"), Some("team a/")),
+            "aws s3api list-objects-v2 --bucket data --delimiter / --prefix 'team a/' \
+             --region eu-west-1 --profile staging"
+        );
+    }
+}
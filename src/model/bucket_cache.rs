@@ -0,0 +1,148 @@
+//! This module provides the bucket lists kept for the accounts, so switching back to
+//! an account doesn't list its buckets again
+use crate::model::s3_data_item::S3DataItem;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Time during which a failed bucket listing isn't retried on switching accounts
+pub const FAILED_BUCKET_LIST_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+enum CachedBuckets {
+    Listed(Vec<S3DataItem>),
+    Failed(String),
+}
+
+/// Result of looking up the bucket list of an account
+#[derive(Debug, Clone)]
+pub enum CachedBucketList {
+    /// Listed `age` ago
+    Listed {
+        buckets: Vec<S3DataItem>,
+        age: Duration,
+    },
+    /// Listing failed `age` ago
+    Failed { error: String, age: Duration },
+}
+
+/// Bucket lists by account name with the time they were listed
+#[derive(Debug, Clone, Default)]
+pub struct BucketCache {
+    entries: HashMap<String, (CachedBuckets, Instant)>,
+}
+
+impl BucketCache {
+    pub fn store(&mut self, account: &str, buckets: Vec<S3DataItem>, now: Instant) {
+        self.entries
+            .insert(account.to_string(), (CachedBuckets::Listed(buckets), now));
+    }
+
+    pub fn store_failure(&mut self, account: &str, error: String, now: Instant) {
+        self.entries
+            .insert(account.to_string(), (CachedBuckets::Failed(error), now));
+    }
+
+    /// Fills in the region of a bucket of the listed buckets, their age is kept
+    pub fn set_region(&mut self, account: &str, bucket: &str, region: &str) {
+        if let Some((CachedBuckets::Listed(buckets), _)) = self.entries.get_mut(account) {
+            for item in buckets.iter_mut().filter(|item| item.name == bucket) {
+                item.region = Some(region.to_string());
+            }
+        }
+    }
+
+    /// Forgets the bucket list, e.g. after a bucket was created or deleted
+    pub fn invalidate(&mut self, account: &str) {
+        self.entries.remove(account);
+    }
+
+    /// The bucket list of the account unless it's older than `ttl`, failures are kept
+    /// for `FAILED_BUCKET_LIST_TTL`
+    pub fn lookup(&self, account: &str, now: Instant, ttl: Duration) -> Option<CachedBucketList> {
+        let (cached, listed_at) = self.entries.get(account)?;
+        let age = now.saturating_duration_since(*listed_at);
+        match cached {
+            CachedBuckets::Listed(buckets) if age < ttl => Some(CachedBucketList::Listed {
+                buckets: buckets.clone(),
+                age,
+            }),
+            CachedBuckets::Failed(error) if age < FAILED_BUCKET_LIST_TTL => {
+                Some(CachedBucketList::Failed {
+                    error: error.clone(),
+                    age,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::s3_data_item::{BucketInfo, FileInfo};
+
+    fn bucket(name: &str) -> S3DataItem {
+        S3DataItem::init(
+            BucketInfo {
+                bucket: None,
+                region: None,
+                is_bucket: true,
+                creation_date: None,
+            },
+            FileInfo {
+                file_name: name.into(),
+                size: "".into(),
+                file_type: "Bucket".into(),
+                path: name.into(),
+                is_directory: false,
+                last_modified: None,
+                storage_class: None,
+            },
+        )
+    }
+
+    #[test]
+    fn bucket_list_expires_after_the_ttl() {
+        let mut cache = BucketCache::default();
+        let listed_at = Instant::now();
+        let ttl = Duration::from_secs(300);
+        cache.store("prod", vec![bucket("data")], listed_at);
+
+        assert!(matches!(
+            cache.lookup("prod", listed_at + Duration::from_secs(120), ttl),
+            Some(CachedBucketList::Listed { buckets, age })
+                if buckets[0].name == "data" && age == Duration::from_secs(120)
+        ));
+        assert!(cache.lookup("prod", listed_at + ttl, ttl).is_none());
+        assert!(cache.lookup("dev", listed_at, ttl).is_none());
+    }
+
+    #[test]
+    fn failures_are_kept_for_a_short_time() {
+        let mut cache = BucketCache::default();
+        let failed_at = Instant::now();
+        let ttl = Duration::from_secs(300);
+        cache.store_failure("restricted", "AccessDenied".into(), failed_at);
+
+        assert!(matches!(
+            cache.lookup("restricted", failed_at + Duration::from_secs(10), ttl),
+            Some(CachedBucketList::Failed { error, age })
+                if error == "AccessDenied" && age == Duration::from_secs(10)
+        ));
+        assert!(cache
+            .lookup("restricted", failed_at + FAILED_BUCKET_LIST_TTL, ttl)
+            .is_none());
+    }
+
+    #[test]
+    fn invalidated_list_is_fetched_again() {
+        let mut cache = BucketCache::default();
+        let now = Instant::now();
+        cache.store("prod", vec![bucket("data")], now);
+        cache.invalidate("prod");
+        assert!(cache
+            .lookup("prod", now, Duration::from_secs(300))
+            .is_none());
+    }
+}
@@ -0,0 +1,205 @@
+//! This module provides the plan and the outcome of deleting all the selected items at once
+
+use crate::model::s3_selected_item::S3SelectedItem;
+
+/// Most failed keys listed in the error popup, the rest is only counted
+const LISTED_FAILURES: usize = 10;
+
+/// Objects of one bucket deleted with DeleteObjects requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3Deletion {
+    /// Name of the credential the objects were selected with
+    pub account: String,
+    pub bucket: String,
+    pub keys: Vec<String>,
+}
+
+/// Groups the selected objects by account and bucket. Directories and buckets are deleted
+/// through the objects listed when they were selected, the buckets themselves are kept
+pub fn group_s3_deletions(items: &[S3SelectedItem]) -> Vec<S3Deletion> {
+    let mut deletions: Vec<S3Deletion> = Vec::new();
+    let objects = items.iter().flat_map(|item| match &item.children {
+        Some(children) => children.iter().collect::<Vec<_>>(),
+        None => vec![item],
+    });
+    for object in objects.filter(|o| !o.is_directory && !o.is_bucket) {
+        let bucket = match object.bucket.clone() {
+            Some(bucket) => bucket,
+            None => continue,
+        };
+        let key = object.path.clone().unwrap_or(object.name.clone());
+        match deletions
+            .iter_mut()
+            .find(|d| d.bucket == bucket && d.account == object.account)
+        {
+            Some(deletion) => deletion.keys.push(key),
+            None => deletions.push(S3Deletion {
+                account: object.account.clone(),
+                bucket,
+                keys: vec![key],
+            }),
+        }
+    }
+    deletions
+}
+
+/// Keys deleted with the item, the objects listed under a directory or a bucket when
+/// the delete was confirmed or the key of a single object
+pub fn recursive_delete_keys(item: &S3SelectedItem) -> Vec<String> {
+    match &item.children {
+        Some(children) => children
+            .iter()
+            .filter(|child| !child.is_bucket)
+            .map(|child| child.path.clone().unwrap_or(child.name.clone()))
+            .collect(),
+        None if item.is_directory || item.is_bucket => Vec::new(),
+        None => vec![item.path.clone().unwrap_or(item.name.clone())],
+    }
+}
+
+/// Progress of deleting the objects of a directory or a bucket, shown on the s3 panel
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteProgress {
+    pub location: String,
+    /// Keys sent in the finished DeleteObjects requests, failed ones included
+    pub deleted: usize,
+    pub total: usize,
+}
+
+/// Outcome of deleting the selected items
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkDeletion {
+    /// `(bucket, key)` of the deleted objects
+    pub deleted_s3: Vec<(String, String)>,
+    /// Paths of the deleted local files and directories
+    pub deleted_local: Vec<String>,
+    /// Location of the items which couldn't be deleted together with the reason
+    pub failed: Vec<(String, String)>,
+}
+
+impl BulkDeletion {
+    /// Number of deleted items
+    pub fn deleted(&self) -> usize {
+        self.deleted_s3.len() + self.deleted_local.len()
+    }
+
+    /// Text of the error popup listing the failed items, `None` when everything was deleted
+    pub fn error_message(&self) -> Option<String> {
+        if self.failed.is_empty() {
+            return None;
+        }
+        let mut lines = vec![format!(
+            "{} of {} items could not be deleted:",
+            self.failed.len(),
+            self.failed.len() + self.deleted()
+        )];
+        lines.extend(
+            self.failed
+                .iter()
+                .take(LISTED_FAILURES)
+                .map(|(location, reason)| format!("{}: {}", location, reason)),
+        );
+        if self.failed.len() > LISTED_FAILURES {
+            lines.push(format!(
+                "... and {} more, see the activity log",
+                self.failed.len() - LISTED_FAILURES
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected(bucket: &str, key: &str, account: &str) -> S3SelectedItem {
+        S3SelectedItem {
+            bucket: Some(bucket.into()),
+            name: key.rsplit('/').next().unwrap_or(key).into(),
+            path: Some(key.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "".into(),
+            transferred: false,
+            account: account.into(),
+            progress: 0f64,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        }
+    }
+
+    #[test]
+    fn objects_are_grouped_by_account_and_bucket() {
+        let mut directory = selected("logs", "2024/", "prod");
+        directory.is_directory = true;
+        directory.children = Some(vec![
+            selected("logs", "2024/a.log", "prod"),
+            selected("logs", "2024/b.log", "prod"),
+        ]);
+        let items = vec![
+            directory,
+            selected("logs", "c.log", "prod"),
+            selected("logs", "c.log", "staging"),
+            selected("media", "cat.png", "prod"),
+        ];
+        let deletions = group_s3_deletions(&items);
+        let groups: Vec<(&str, &str, usize)> = deletions
+            .iter()
+            .map(|d| (d.account.as_str(), d.bucket.as_str(), d.keys.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("prod", "logs", 3),
+                ("staging", "logs", 1),
+                ("prod", "media", 1)
+            ]
+        );
+        assert_eq!(deletions[0].keys, vec!["2024/a.log", "2024/b.log", "c.log"]);
+    }
+
+    #[test]
+    fn bucket_is_emptied_through_the_nested_objects() {
+        let mut bucket = selected("logs", "logs", "prod");
+        bucket.bucket = None;
+        bucket.path = None;
+        bucket.is_bucket = true;
+        assert!(recursive_delete_keys(&bucket).is_empty());
+        bucket.children = Some(vec![
+            selected("logs", "2024/", "prod"),
+            selected("logs", "2024/01/a.log", "prod"),
+            selected("logs", "2024/02/b.log", "prod"),
+            selected("logs", "c.log", "prod"),
+        ]);
+        assert_eq!(
+            recursive_delete_keys(&bucket),
+            vec!["2024/", "2024/01/a.log", "2024/02/b.log", "c.log"]
+        );
+        assert_eq!(
+            recursive_delete_keys(&selected("logs", "2024/01/a.log", "prod")),
+            vec!["2024/01/a.log"]
+        );
+    }
+
+    #[test]
+    fn error_message_lists_the_failed_items() {
+        let mut deletion = BulkDeletion {
+            deleted_s3: vec![("logs".into(), "a.log".into())],
+            ..BulkDeletion::default()
+        };
+        assert_eq!(deletion.error_message(), None);
+        deletion.failed = (0..12)
+            .map(|i| (format!("s3://logs/{}.log", i), "AccessDenied".into()))
+            .collect();
+        let message = deletion.error_message().unwrap();
+        let lines: Vec<&str> = message.lines().collect();
+        assert_eq!(lines[0], "12 of 13 items could not be deleted:");
+        assert_eq!(lines[1], "s3://logs/0.log: AccessDenied");
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[11], "... and 2 more, see the activity log");
+    }
+}
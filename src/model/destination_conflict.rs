@@ -0,0 +1,295 @@
+//! This module provides the detection of queued transfers writing to the same destination
+use crate::services::transfer_queue::TransferJob;
+use std::collections::{HashMap, HashSet};
+
+/// How the transfers sharing a destination are run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Only the first transfer of each conflict is run
+    KeepFirst,
+    /// Only the last transfer of each conflict is run
+    KeepLast,
+    /// The subsequent transfers get a numeric suffix, e.g. `report-1.txt`
+    RenameWithSuffix,
+}
+
+/// Transfers of the queue writing to the same destination
+#[derive(Debug, Clone, PartialEq)]
+pub struct DestinationConflict {
+    /// `s3://bucket/key` or the local path
+    pub destination: String,
+    /// Sources of the conflicting transfers in the queue order
+    pub sources: Vec<String>,
+    /// Positions of the conflicting transfers in the queue
+    positions: Vec<usize>,
+}
+
+/// Run waiting for the user to resolve the destination conflicts
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockedTransfers {
+    pub jobs: Vec<TransferJob>,
+    pub conflicts: Vec<DestinationConflict>,
+}
+
+/// Grouping key of the destination and its displayed form. Local paths are compared
+/// ignoring the case on case-insensitive filesystems, s3 keys are always case sensitive
+fn destination(job: &TransferJob, case_insensitive: bool) -> (String, String) {
+    match job {
+        TransferJob::Download(item) => {
+            let path = item.local_destination().to_string_lossy().into_owned();
+            let key = if case_insensitive {
+                path.to_lowercase()
+            } else {
+                path.clone()
+            };
+            (format!("local:{}", key), path)
+        }
+        TransferJob::Upload(item) => {
+            let display = format!(
+                "s3://{}/{}",
+                item.destination_bucket,
+                item.destination_key()
+            );
+            (format!("s3:{}:{}", item.account, display), display)
+        }
+    }
+}
+
+fn source(job: &TransferJob) -> String {
+    match job {
+        TransferJob::Download(item) => format!(
+            "s3://{}/{}",
+            item.bucket.clone().unwrap_or_default(),
+            item.path.clone().unwrap_or(item.name.clone())
+        ),
+        TransferJob::Upload(item) => item.path.clone(),
+    }
+}
+
+/// Destinations targeted by more than one transfer, in the order of their first transfer
+pub fn find_conflicts(jobs: &[TransferJob], case_insensitive: bool) -> Vec<DestinationConflict> {
+    let mut groups: Vec<DestinationConflict> = Vec::new();
+    let mut group_by_key: HashMap<String, usize> = HashMap::new();
+    for (position, job) in jobs.iter().enumerate() {
+        let (key, display) = destination(job, case_insensitive);
+        let index = *group_by_key.entry(key).or_insert_with(|| {
+            groups.push(DestinationConflict {
+                destination: display,
+                sources: Vec::new(),
+                positions: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[index].sources.push(source(job));
+        groups[index].positions.push(position);
+    }
+    groups.retain(|group| group.positions.len() > 1);
+    groups
+}
+
+/// `dir/report.txt` becomes `dir/report-1.txt`, the suffix goes before the extension
+fn with_suffix(path: &str, n: usize) -> String {
+    let (dir, name) = match path.rfind('/') {
+        Some(i) => path.split_at(i + 1),
+        None => ("", path),
+    };
+    match name.rfind('.').filter(|i| *i > 0) {
+        Some(i) => format!("{}{}-{}{}", dir, &name[..i], n, &name[i..]),
+        None => format!("{}{}-{}", dir, name, n),
+    }
+}
+
+/// Path of the download relative to its destination directory, or the key of the upload
+fn relative_destination(job: &TransferJob) -> String {
+    match job {
+        TransferJob::Download(item) => item
+            .destination_path
+            .clone()
+            .or(item.path.clone())
+            .unwrap_or(item.name.clone()),
+        TransferJob::Upload(item) => item.destination_key(),
+    }
+}
+
+fn rename(job: &mut TransferJob, relative: String) {
+    match job {
+        TransferJob::Download(item) => item.destination_path = Some(relative),
+        TransferJob::Upload(item) => item.destination_path = relative,
+    }
+}
+
+/// Applies the resolution to the conflicts found in the jobs, the remaining jobs keep their order
+pub fn resolve_conflicts(
+    jobs: Vec<TransferJob>,
+    resolution: ConflictResolution,
+    case_insensitive: bool,
+) -> Vec<TransferJob> {
+    let conflicts = find_conflicts(&jobs, case_insensitive);
+    let mut jobs: Vec<Option<TransferJob>> = jobs.into_iter().map(Some).collect();
+    let mut taken: HashSet<String> = jobs
+        .iter()
+        .flatten()
+        .map(|job| destination(job, case_insensitive).0)
+        .collect();
+    for conflict in conflicts {
+        let positions = &conflict.positions;
+        let others = match resolution {
+            ConflictResolution::KeepLast => &positions[..positions.len() - 1],
+            _ => &positions[1..],
+        };
+        for &position in others {
+            if resolution != ConflictResolution::RenameWithSuffix {
+                jobs[position] = None;
+                continue;
+            }
+            if let Some(job) = jobs[position].as_mut() {
+                let relative = relative_destination(job);
+                let mut n = 1;
+                loop {
+                    rename(job, with_suffix(&relative, n));
+                    if taken.insert(destination(job, case_insensitive).0) {
+                        break;
+                    }
+                    n += 1;
+                }
+            }
+        }
+    }
+    jobs.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::local_selected_item::LocalSelectedItem;
+    use crate::model::s3_selected_item::S3SelectedItem;
+
+    fn upload(path: &str, key: &str) -> TransferJob {
+        TransferJob::Upload(LocalSelectedItem::new(
+            path.rsplit('/').next().unwrap().into(),
+            path.into(),
+            false,
+            "bucket".into(),
+            key.into(),
+            String::new(),
+            None,
+        ))
+    }
+
+    fn download(bucket: &str, key: &str) -> TransferJob {
+        TransferJob::Download(S3SelectedItem {
+            bucket: Some(bucket.into()),
+            name: key.rsplit('/').next().unwrap().into(),
+            path: Some(key.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/home/user/downloads".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        })
+    }
+
+    fn destinations(jobs: &[TransferJob]) -> Vec<String> {
+        jobs.iter().map(|job| destination(job, false).1).collect()
+    }
+
+    #[test]
+    fn uploads_to_the_same_key_conflict() {
+        let jobs = vec![
+            upload("/a/report.txt", "docs/report.txt"),
+            upload("/b/report.txt", "docs/report.txt"),
+            upload("/c/Report.txt", "docs/Report.txt"),
+        ];
+        // s3 keys are case sensitive even when the local filesystem is not
+        let conflicts = find_conflicts(&jobs, true);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].destination, "s3://bucket/docs/report.txt");
+        assert_eq!(conflicts[0].sources, vec!["/a/report.txt", "/b/report.txt"]);
+    }
+
+    #[test]
+    fn local_case_collisions_depend_on_the_filesystem() {
+        let jobs = vec![
+            download("bucket", "data/Report.csv"),
+            download("bucket", "data/report.csv"),
+            download("other", "data/report.csv"),
+        ];
+        let insensitive = find_conflicts(&jobs, true);
+        assert_eq!(insensitive.len(), 1);
+        assert_eq!(insensitive[0].sources.len(), 3);
+
+        let sensitive = find_conflicts(&jobs, false);
+        assert_eq!(sensitive.len(), 1);
+        assert_eq!(
+            sensitive[0].sources,
+            vec!["s3://bucket/data/report.csv", "s3://other/data/report.csv"]
+        );
+    }
+
+    #[test]
+    fn keep_first_and_keep_last_drop_the_other_transfers() {
+        let jobs = vec![
+            upload("/a/x.txt", "x.txt"),
+            upload("/b/y.txt", "y.txt"),
+            upload("/c/x.txt", "x.txt"),
+        ];
+        let first = resolve_conflicts(jobs.clone(), ConflictResolution::KeepFirst, false);
+        assert_eq!(first, vec![jobs[0].clone(), jobs[1].clone()]);
+        let last = resolve_conflicts(jobs.clone(), ConflictResolution::KeepLast, false);
+        assert_eq!(last, vec![jobs[1].clone(), jobs[2].clone()]);
+    }
+
+    #[test]
+    fn rename_adds_a_free_numeric_suffix() {
+        let jobs = vec![
+            upload("/a/report.txt", "docs/report.txt"),
+            upload("/b/report.txt", "docs/report.txt"),
+            upload("/c/report-1.txt", "docs/report-1.txt"),
+            upload("/d/report.txt", "docs/report.txt"),
+        ];
+        let renamed = resolve_conflicts(jobs, ConflictResolution::RenameWithSuffix, false);
+        assert_eq!(
+            destinations(&renamed),
+            vec![
+                "s3://bucket/docs/report.txt",
+                "s3://bucket/docs/report-2.txt",
+                "s3://bucket/docs/report-1.txt",
+                "s3://bucket/docs/report-3.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn renamed_downloads_keep_their_source() {
+        let jobs = vec![
+            download("bucket", "archive.tar.gz"),
+            download("bucket", "ARCHIVE.tar.gz"),
+        ];
+        let renamed = resolve_conflicts(jobs, ConflictResolution::RenameWithSuffix, true);
+        assert_eq!(
+            destinations(&renamed),
+            vec![
+                "/home/user/downloads/archive.tar.gz",
+                "/home/user/downloads/ARCHIVE.tar-1.gz",
+            ]
+        );
+        match &renamed[1] {
+            TransferJob::Download(item) => assert_eq!(item.path, Some("ARCHIVE.tar.gz".into())),
+            _ => panic!("expected a download"),
+        }
+        assert!(find_conflicts(&renamed, true).is_empty());
+    }
+
+    #[test]
+    fn suffix_goes_before_the_extension() {
+        assert_eq!(with_suffix("a/b/report.txt", 1), "a/b/report-1.txt");
+        assert_eq!(with_suffix("README", 2), "README-2");
+        assert_eq!(with_suffix("dir.d/.env", 1), "dir.d/.env-1");
+    }
+}
@@ -3,5 +3,9 @@
 pub struct DownloadProgressItem {
     pub progress: f64,
     pub bucket: String,
-    pub name: String,
+    /// Key of the downloaded object, as given by `S3SelectedItem::object_key`
+    pub key: String,
+    /// Bytes of the object written so far, including the ones of a resumed partial file
+    pub bytes: u64,
+    pub total_bytes: u64,
 }
@@ -0,0 +1,195 @@
+//! This module provides the grouping of the failed transfers of a batch by their cause, so a
+//! dead endpoint failing hundreds of transfers is reported once
+use std::collections::HashMap;
+
+/// Characters around the parts of the messages replaced by `normalize_error`
+const PUNCTUATION: &[char] = &[
+    '"', '\'', '`', '(', ')', '[', ']', '{', '}', ',', ';', ':', '.',
+];
+
+/// Request ids, UUIDs and similar values, words of letters only aren't taken for ids
+fn looks_like_id(token: &str) -> bool {
+    token.len() >= 12
+        && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// `2024-05-01T12:00:00Z`, `2024-05-01` or `12:00:00`
+fn looks_like_time(token: &str) -> bool {
+    token.chars().any(|c| c.is_ascii_digit())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_digit() || "-:TZ+.".contains(c))
+        && (token.contains(':') || token.matches('-').count() >= 2)
+}
+
+/// Local paths and s3 keys, the URLs of the endpoints are kept as they tell the failures apart
+fn looks_like_path(token: &str) -> bool {
+    token.starts_with("s3://")
+        || token.contains('\\')
+        || (token.contains('/') && !token.contains("://"))
+}
+
+/// `request id:`, `RequestId:`, `aws_request_id:` or `Request ID:` is followed by the id
+fn is_id_label(token: &str) -> bool {
+    token.to_ascii_lowercase().ends_with("id:")
+}
+
+/// Message of the failure without the parts differing between the items failing for the same
+/// reason: request ids, timestamps, keys and paths are replaced with placeholders
+pub fn normalize_error(message: &str) -> String {
+    let mut after_id_label = false;
+    let mut tokens = Vec::new();
+    for token in message.split_whitespace() {
+        let core = token.trim_matches(PUNCTUATION);
+        let placeholder = if core.is_empty() {
+            None
+        } else if after_id_label || looks_like_id(core) {
+            Some("<id>")
+        } else if looks_like_time(core) {
+            Some("<time>")
+        } else if looks_like_path(core) {
+            Some("<path>")
+        } else {
+            None
+        };
+        after_id_label = is_id_label(token);
+        tokens.push(match placeholder {
+            Some(placeholder) => token.replacen(core, placeholder, 1),
+            None => token.to_string(),
+        });
+    }
+    tokens.join(" ")
+}
+
+/// Failures sharing the normalized message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorGroup {
+    pub message: String,
+    pub count: usize,
+}
+
+/// Failed transfers of a batch grouped by `normalize_error`, the largest group first
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ErrorGroups {
+    groups: Vec<ErrorGroup>,
+    index: HashMap<String, usize>,
+}
+
+impl ErrorGroups {
+    pub fn add(&mut self, error: &str) {
+        let message = normalize_error(error);
+        match self.index.get(&message) {
+            Some(&i) => self.groups[i].count += 1,
+            None => {
+                self.index.insert(message.clone(), self.groups.len());
+                self.groups.push(ErrorGroup { message, count: 1 });
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.index.clear();
+    }
+
+    /// Number of failed transfers
+    pub fn failed(&self) -> usize {
+        self.groups.iter().map(|g| g.count).sum()
+    }
+
+    /// Groups ordered from the largest, groups of the same size in the order they failed
+    pub fn groups(&self) -> Vec<ErrorGroup> {
+        let mut groups = self.groups.clone();
+        groups.sort_by(|a, b| b.count.cmp(&a.count));
+        groups
+    }
+
+    /// Lines of the summary popup, e.g. `497 item(s) failed: cannot connect to endpoint X`
+    pub fn summary(&self) -> Vec<String> {
+        self.groups()
+            .iter()
+            .map(|g| format!("{} item(s) failed: {}", g.count, g.message))
+            .collect()
+    }
+}
+
+/// Whether the error of a transfer belongs to the group with the normalized `message`
+pub fn in_group(error: Option<&str>, message: &str) -> bool {
+    error.is_some_and(|error| normalize_error(error) == message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_ids_differing_between_failures_are_removed() {
+        let first = r#"service error: unhandled error (AccessDenied): Error { code: "AccessDenied", message: "Access Denied", aws_request_id: "4442587FB7D0A2F9", s3_extended_request_id: "Uuag1LuByRx9e6j5Onimru9pO4ZVKnJ2Qz7/C1NPcfTWAtRPfTaOFg==" }"#;
+        let second = r#"service error: unhandled error (AccessDenied): Error { code: "AccessDenied", message: "Access Denied", aws_request_id: "9F2A1C77B0D3E514", s3_extended_request_id: "x3Kq8vZ0pL2mN7tR5wY1aB4cD6eF8gH0iJ2kL4mN6oP8qR0sT2uV4w==" }"#;
+        assert_eq!(normalize_error(first), normalize_error(second));
+        assert_eq!(
+            normalize_error(first),
+            r#"service error: unhandled error (AccessDenied): Error { code: "AccessDenied", message: "Access Denied", aws_request_id: "<id>", s3_extended_request_id: "<id>" }"#
+        );
+        assert_eq!(
+            normalize_error("Access Denied (Service: Amazon S3; Status Code: 403; Error Code: AccessDenied; Request ID: 1F2E3D4C5B6A7988)"),
+            "Access Denied (Service: Amazon S3; Status Code: 403; Error Code: AccessDenied; Request ID: <id>)"
+        );
+    }
+
+    #[test]
+    fn keys_paths_and_timestamps_are_removed() {
+        assert_eq!(
+            normalize_error("NoSuchKey: The specified key 'reports/2024/q1.csv' does not exist."),
+            "NoSuchKey: The specified key '<path>' does not exist."
+        );
+        assert_eq!(
+            normalize_error("cannot open /home/user/docs/a.txt: Permission denied (os error 13)"),
+            normalize_error("cannot open /home/user/docs/b.txt: Permission denied (os error 13)")
+        );
+        assert_eq!(
+            normalize_error("RequestTimeTooSkewed: the request time 2024-05-01T12:00:00Z differs from the server time"),
+            "RequestTimeTooSkewed: the request time <time> differs from the server time"
+        );
+    }
+
+    #[test]
+    fn endpoints_and_causes_are_kept() {
+        let refused = "cannot connect to endpoint https://minio.local:9000";
+        assert_eq!(normalize_error(refused), refused);
+        let timeout = "request to endpoint eu-west-1 timed out after 60s";
+        assert_eq!(normalize_error(timeout), timeout);
+        assert_ne!(
+            normalize_error("cannot connect to endpoint https://a.example.com"),
+            normalize_error("cannot connect to endpoint https://b.example.com")
+        );
+    }
+
+    #[test]
+    fn failures_are_grouped_largest_first() {
+        let mut groups = ErrorGroups::default();
+        groups.add("AccessDenied: Access Denied");
+        for _ in 0..3 {
+            groups.add("cannot connect to endpoint https://minio.local:9000");
+        }
+        groups.add("AccessDenied: Access Denied");
+        groups.add("cannot open /tmp/a.txt: No space left on device (os error 28)");
+
+        assert_eq!(groups.failed(), 6);
+        assert_eq!(
+            groups.summary(),
+            vec![
+                "3 item(s) failed: cannot connect to endpoint https://minio.local:9000",
+                "2 item(s) failed: AccessDenied: Access Denied",
+                "1 item(s) failed: cannot open <path>: No space left on device (os error 28)",
+            ]
+        );
+        assert!(in_group(
+            Some("cannot open /tmp/b.txt: No space left on device (os error 28)"),
+            &groups.groups()[2].message
+        ));
+        assert!(!in_group(None, &groups.groups()[2].message));
+    }
+}
@@ -0,0 +1,176 @@
+//! This module provides the comparison of an s3 object with a local file
+use std::time::Duration;
+
+/// Outcome of the comparison shown in the popup
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComparisonVerdict {
+    Identical,
+    DifferAtSize {
+        local: u64,
+        remote: u64,
+    },
+    DifferByHash,
+    /// The ETag of a multipart upload is not the md5 of the content, only a full hash can tell
+    UndeterminedMultipartEtag,
+}
+
+/// How the verdict was reached, from the cheapest to the most expensive check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComparisonMethod {
+    Size,
+    Etag,
+    /// Only the first and the last bytes were downloaded
+    Sampled,
+    FullHash,
+}
+
+/// Next step of the comparison once both sizes and the ETag are known
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComparisonStep {
+    Done(ComparisonVerdict),
+    /// The ETag is the md5 of the content, the local file has to be hashed
+    CompareMd5(String),
+    /// Download the samples, or the whole object when a full hash was requested
+    CompareContent,
+}
+
+/// The md5 carried by a single part upload ETag, `None` for multipart ETags (`<md5>-<parts>`)
+pub fn plain_md5_etag(etag: &str) -> Option<String> {
+    let etag = etag.trim_matches('"');
+    (etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| etag.to_ascii_lowercase())
+}
+
+/// The cheap part of the decision ladder: size first, then the ETag when it's a plain md5
+pub fn first_step(local_size: u64, remote_size: u64, etag: Option<&str>) -> ComparisonStep {
+    if local_size != remote_size {
+        return ComparisonStep::Done(ComparisonVerdict::DifferAtSize {
+            local: local_size,
+            remote: remote_size,
+        });
+    }
+    if local_size == 0 {
+        return ComparisonStep::Done(ComparisonVerdict::Identical);
+    }
+    match etag.and_then(plain_md5_etag) {
+        Some(md5) => ComparisonStep::CompareMd5(md5),
+        None => ComparisonStep::CompareContent,
+    }
+}
+
+/// Matching samples don't prove the files are identical, differing ones prove they are not
+pub fn sampled_verdict(local: &[Vec<u8>], remote: &[Vec<u8>]) -> ComparisonVerdict {
+    if local == remote {
+        ComparisonVerdict::UndeterminedMultipartEtag
+    } else {
+        ComparisonVerdict::DifferByHash
+    }
+}
+
+pub fn hash_verdict(local_md5: &str, remote_md5: &str) -> ComparisonVerdict {
+    if local_md5.eq_ignore_ascii_case(remote_md5) {
+        ComparisonVerdict::Identical
+    } else {
+        ComparisonVerdict::DifferByHash
+    }
+}
+
+/// Result of comparing an s3 object with a local file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileComparison {
+    pub bucket: String,
+    pub key: String,
+    pub local_path: String,
+    pub verdict: ComparisonVerdict,
+    pub method: ComparisonMethod,
+    pub elapsed: Duration,
+}
+
+impl FileComparison {
+    /// Whether a full streaming hash can still change the verdict
+    pub fn can_hash_fully(&self) -> bool {
+        self.verdict == ComparisonVerdict::UndeterminedMultipartEtag
+    }
+
+    pub fn verdict_description(&self) -> String {
+        match (&self.verdict, self.method) {
+            (ComparisonVerdict::Identical, ComparisonMethod::FullHash) => {
+                "Identical (full md5 hash)".to_string()
+            }
+            (ComparisonVerdict::Identical, ComparisonMethod::Etag) => {
+                "Identical (ETag matches the md5 of the local file)".to_string()
+            }
+            (ComparisonVerdict::Identical, _) => "Identical".to_string(),
+            (ComparisonVerdict::DifferAtSize { local, remote }, _) => format!(
+                "Different sizes: local {} bytes, s3 {} bytes",
+                local, remote
+            ),
+            (ComparisonVerdict::DifferByHash, ComparisonMethod::Sampled) => {
+                "Different content (first/last bytes differ)".to_string()
+            }
+            (ComparisonVerdict::DifferByHash, _) => "Different content (md5 differs)".to_string(),
+            (ComparisonVerdict::UndeterminedMultipartEtag, _) => {
+                "Undetermined: multipart ETag, the sizes and the first/last bytes match".to_string()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD5: &str = "5eb63bbbe01eeed093cb22bb8f5acdc3";
+
+    #[test]
+    fn sizes_are_compared_first() {
+        assert_eq!(
+            first_step(10, 11, Some(MD5)),
+            ComparisonStep::Done(ComparisonVerdict::DifferAtSize {
+                local: 10,
+                remote: 11
+            })
+        );
+        assert_eq!(
+            first_step(0, 0, None),
+            ComparisonStep::Done(ComparisonVerdict::Identical)
+        );
+    }
+
+    #[test]
+    fn plain_etag_is_compared_with_the_local_md5() {
+        assert_eq!(
+            first_step(10, 10, Some(&format!("\"{}\"", MD5.to_uppercase()))),
+            ComparisonStep::CompareMd5(MD5.to_string())
+        );
+        assert_eq!(hash_verdict(MD5, MD5), ComparisonVerdict::Identical);
+        assert_eq!(
+            hash_verdict(MD5, "d41d8cd98f00b204e9800998ecf8427e"),
+            ComparisonVerdict::DifferByHash
+        );
+    }
+
+    #[test]
+    fn multipart_or_missing_etag_needs_the_content() {
+        assert_eq!(
+            first_step(10, 10, Some(&format!("\"{}-3\"", MD5))),
+            ComparisonStep::CompareContent
+        );
+        assert_eq!(first_step(10, 10, None), ComparisonStep::CompareContent);
+        assert_eq!(plain_md5_etag("not-an-md5"), None);
+    }
+
+    #[test]
+    fn samples_can_only_prove_a_difference() {
+        let head = vec![b"abcd".to_vec(), b"wxyz".to_vec()];
+        assert_eq!(
+            sampled_verdict(&head, &head),
+            ComparisonVerdict::UndeterminedMultipartEtag
+        );
+        let other = vec![b"abcd".to_vec(), b"wxyZ".to_vec()];
+        assert_eq!(
+            sampled_verdict(&head, &other),
+            ComparisonVerdict::DifferByHash
+        );
+    }
+}
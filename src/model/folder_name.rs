@@ -0,0 +1,43 @@
+//! This module provides the keys of the folders created in the s3 panel
+
+/// Key of the folder `name` created under `prefix`, the zero-byte object ending with '/'
+/// the s3 panel lists as a directory. The trailing slash of the name is optional and nested
+/// names like `2024/q1` are kept. Returns the error shown in the popup for invalid names
+pub fn folder_key(prefix: Option<&str>, name: &str) -> Result<String, String> {
+    if name.is_empty() || name == "/" {
+        return Err("Folder name cannot be empty".into());
+    }
+    if name.starts_with('/') {
+        return Err("Folder name cannot start with '/'".into());
+    }
+    if name.contains("//") {
+        return Err("Folder name cannot contain '//'".into());
+    }
+    Ok(format!(
+        "{}{}/",
+        prefix.unwrap_or_default(),
+        name.strip_suffix('/').unwrap_or(name)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folder_is_created_under_the_prefix() {
+        assert_eq!(folder_key(None, "reports"), Ok("reports/".into()));
+        assert_eq!(folder_key(Some("2024/"), "q1/"), Ok("2024/q1/".into()));
+        assert_eq!(
+            folder_key(Some("2024/"), "q1/march"),
+            Ok("2024/q1/march/".into())
+        );
+    }
+
+    #[test]
+    fn invalid_names_are_rejected() {
+        for name in ["", "/", "/reports", "reports//q1", "reports//"] {
+            assert!(folder_key(Some("2024/"), name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! This module provides locations the file manager can jump to from the transfers page
+use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::s3_selected_item::S3SelectedItem;
+use std::path::Path;
+
+/// Location shown by the file manager, `item_name` is the row highlighted after the jump
+#[derive(Debug, Clone, PartialEq)]
+pub enum JumpTarget {
+    Local {
+        directory: String,
+        item_name: Option<String>,
+    },
+    S3 {
+        bucket: String,
+        prefix: Option<String>,
+        item_name: Option<String>,
+        /// Name of the credential the location is listed with
+        account: String,
+    },
+}
+
+/// Request to show the target of a finished jump, handled once by the file manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileManagerFocus {
+    pub id: u64,
+    pub target: JumpTarget,
+}
+
+/// Splits the s3 key into its prefix and the name displayed on the s3 panel,
+/// directories keep the trailing slash
+fn split_s3_key(key: &str, is_directory: bool) -> (Option<String>, Option<String>) {
+    let trimmed = key.trim_end_matches('/');
+    let (prefix, name) = match trimmed.rfind('/') {
+        Some(i) => (Some(trimmed[..=i].to_string()), &trimmed[i + 1..]),
+        None => (None, trimmed),
+    };
+    if name.is_empty() {
+        return (prefix, None);
+    }
+    let name = if is_directory {
+        format!("{}/", name)
+    } else {
+        name.to_string()
+    };
+    (prefix, Some(name))
+}
+
+fn local_target(path: &Path) -> JumpTarget {
+    JumpTarget::Local {
+        directory: path.parent().unwrap_or(path).to_string_lossy().into_owned(),
+        item_name: path.file_name().map(|n| n.to_string_lossy().into_owned()),
+    }
+}
+
+impl JumpTarget {
+    /// The s3 location the download is read from
+    pub fn download_source(item: &S3SelectedItem) -> Self {
+        if item.is_bucket {
+            return JumpTarget::S3 {
+                bucket: item.name.clone(),
+                prefix: None,
+                item_name: None,
+                account: item.account.clone(),
+            };
+        }
+        let key = item.path.clone().unwrap_or(item.name.clone());
+        let (prefix, item_name) = split_s3_key(&key, item.is_directory);
+        JumpTarget::S3 {
+            bucket: item.bucket.clone().unwrap_or_default(),
+            prefix,
+            item_name,
+            account: item.account.clone(),
+        }
+    }
+
+    /// The local directory the download is written to
+    pub fn download_destination(item: &S3SelectedItem) -> Self {
+        if item.is_bucket {
+            return JumpTarget::Local {
+                directory: item.destination_dir.clone(),
+                item_name: None,
+            };
+        }
+        local_target(&item.local_destination())
+    }
+
+    /// The local directory the upload is read from
+    pub fn upload_source(item: &LocalSelectedItem) -> Self {
+        local_target(Path::new(&item.path))
+    }
+
+    /// The s3 location the upload is written to
+    pub fn upload_destination(item: &LocalSelectedItem) -> Self {
+        let (prefix, item_name) = split_s3_key(&item.destination_key(), item.is_directory);
+        JumpTarget::S3 {
+            bucket: item.destination_bucket.clone(),
+            prefix,
+            item_name,
+            account: item.account.clone(),
+        }
+    }
+
+    pub fn item_name(&self) -> Option<&str> {
+        match self {
+            JumpTarget::Local { item_name, .. } | JumpTarget::S3 { item_name, .. } => {
+                item_name.as_deref()
+            }
+        }
+    }
+
+    /// Human readable location used in notifications
+    pub fn describe(&self) -> String {
+        match self {
+            JumpTarget::Local { directory, .. } => directory.clone(),
+            JumpTarget::S3 { bucket, prefix, .. } => {
+                format!("s3://{}/{}", bucket, prefix.clone().unwrap_or_default())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s3_item(path: &str) -> S3SelectedItem {
+        S3SelectedItem {
+            bucket: Some("bucket".into()),
+            name: "file.txt".into(),
+            path: Some(path.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/home/user/downloads".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        }
+    }
+
+    fn local_item(destination_path: &str) -> LocalSelectedItem {
+        LocalSelectedItem::new(
+            "file.txt".into(),
+            "/home/user/docs/file.txt".into(),
+            false,
+            "bucket".into(),
+            destination_path.into(),
+            String::new(),
+            None,
+        )
+    }
+
+    #[test]
+    fn download_targets_point_to_prefix_and_local_directory() {
+        let item = s3_item("reports/2024/file.txt");
+        assert_eq!(
+            JumpTarget::download_source(&item),
+            JumpTarget::S3 {
+                bucket: "bucket".into(),
+                prefix: Some("reports/2024/".into()),
+                item_name: Some("file.txt".into()),
+                account: String::new(),
+            }
+        );
+        assert_eq!(
+            JumpTarget::download_destination(&item),
+            JumpTarget::Local {
+                directory: "/home/user/downloads/reports/2024".into(),
+                item_name: Some("file.txt".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn upload_targets_point_to_local_directory_and_prefix() {
+        let item = local_item("backup/file.txt");
+        assert_eq!(
+            JumpTarget::upload_source(&item),
+            JumpTarget::Local {
+                directory: "/home/user/docs".into(),
+                item_name: Some("file.txt".into()),
+            }
+        );
+        assert_eq!(
+            JumpTarget::upload_destination(&item),
+            JumpTarget::S3 {
+                bucket: "bucket".into(),
+                prefix: Some("backup/".into()),
+                item_name: Some("file.txt".into()),
+                account: String::new(),
+            }
+        );
+        let root = JumpTarget::upload_destination(&local_item("/"));
+        assert!(matches!(root, JumpTarget::S3 { prefix: None, .. }));
+        assert_eq!(root.item_name(), Some("file.txt"));
+    }
+
+    #[test]
+    fn directories_keep_trailing_slash_in_item_name() {
+        let item = S3SelectedItem {
+            is_directory: true,
+            ..s3_item("reports/2024/")
+        };
+        let target = JumpTarget::download_source(&item);
+        assert_eq!(target.item_name(), Some("2024/"));
+        assert_eq!(target.describe(), "s3://bucket/reports/");
+    }
+}
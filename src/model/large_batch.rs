@@ -0,0 +1,145 @@
+//! This module provides the confirmation asked before running a very large transfer batch
+use crate::services::transfer_queue::TransferJob;
+use crate::settings::app_config::AppConfig;
+use crate::utils::{estimate_batch_duration, format_duration};
+use std::time::Duration;
+
+/// Run waiting for the user to confirm the number of transfers
+#[derive(Debug, Clone, PartialEq)]
+pub struct LargeBatch {
+    pub jobs: Vec<TransferJob>,
+    /// Transfers running at once
+    pub concurrency: usize,
+    /// Time needed at the average duration of the transfers finished in this session
+    pub estimate: Option<Duration>,
+    /// Whether `yes` has to be typed instead of a single key press
+    pub typed_confirmation: bool,
+    /// Downloads renamed to file names valid on the local filesystem
+    pub adjusted_names: usize,
+}
+
+impl LargeBatch {
+    /// Holds the jobs back when there are more of them than `confirm_batch_objects`,
+    /// `average` is the average duration of the transfers finished so far
+    pub fn check(
+        jobs: Vec<TransferJob>,
+        config: &AppConfig,
+        average: Option<Duration>,
+    ) -> Result<Vec<TransferJob>, LargeBatch> {
+        if jobs.len() <= config.confirm_batch_objects {
+            return Ok(jobs);
+        }
+        let concurrency = config.transfer_concurrency;
+        Err(LargeBatch {
+            estimate: average.map(|avg| estimate_batch_duration(jobs.len(), concurrency, avg)),
+            typed_confirmation: jobs.len() > config.typed_confirm_batch_objects,
+            adjusted_names: 0,
+            concurrency,
+            jobs,
+        })
+    }
+
+    fn downloads(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|job| matches!(job, TransferJob::Download(_)))
+            .count()
+    }
+
+    /// Lines of the confirmation popup
+    pub fn summary(&self) -> Vec<String> {
+        let downloads = self.downloads();
+        let mut summary = vec![
+            format!(
+                "{} transfers: {} download(s), {} upload(s)",
+                self.jobs.len(),
+                downloads,
+                self.jobs.len() - downloads
+            ),
+            format!("{} running at once", self.concurrency),
+            match self.estimate {
+                Some(estimate) => format!("Estimated duration: {}", format_duration(estimate)),
+                None => "Estimated duration: unknown, no transfer finished yet".into(),
+            },
+        ];
+        if self.adjusted_names > 0 {
+            summary.push(format!(
+                "{} download(s) renamed to valid local file names",
+                self.adjusted_names
+            ));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::local_selected_item::LocalSelectedItem;
+
+    fn uploads(n: usize) -> Vec<TransferJob> {
+        (0..n)
+            .map(|i| {
+                TransferJob::Upload(LocalSelectedItem::new(
+                    format!("{}.txt", i),
+                    format!("/tmp/{}.txt", i),
+                    false,
+                    "bucket".into(),
+                    format!("{}.txt", i),
+                    String::new(),
+                    None,
+                ))
+            })
+            .collect()
+    }
+
+    fn config() -> AppConfig {
+        AppConfig {
+            confirm_batch_objects: 10,
+            typed_confirm_batch_objects: 100,
+            transfer_concurrency: 4,
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn small_batches_run_without_confirmation() {
+        assert_eq!(
+            LargeBatch::check(uploads(10), &config(), None),
+            Ok(uploads(10))
+        );
+    }
+
+    #[test]
+    fn large_batches_are_held_back() {
+        let batch =
+            LargeBatch::check(uploads(11), &config(), Some(Duration::from_secs(2))).unwrap_err();
+        assert!(!batch.typed_confirmation);
+        // 11 transfers, 4 at once take 3 rounds
+        assert_eq!(batch.estimate, Some(Duration::from_secs(6)));
+        assert_eq!(
+            batch.summary(),
+            vec![
+                "11 transfers: 0 download(s), 11 upload(s)",
+                "4 running at once",
+                "Estimated duration: 6s",
+            ]
+        );
+
+        let huge = LargeBatch::check(uploads(101), &config(), None).unwrap_err();
+        assert!(huge.typed_confirmation);
+        assert!(huge.summary()[2].contains("unknown"));
+    }
+
+    #[test]
+    fn renamed_downloads_are_counted_in_the_summary() {
+        let batch = LargeBatch {
+            adjusted_names: 3,
+            ..LargeBatch::check(uploads(11), &config(), None).unwrap_err()
+        };
+        assert_eq!(
+            batch.summary()[3],
+            "3 download(s) renamed to valid local file names"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! This module provides the filtering of the rows of the s3 and local panels by their names
+
+/// How well the name matches the typed query, ignoring the case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Match {
+    /// The query is a part of the name
+    Substring,
+    /// The characters of the query appear in the name in the same order, e.g. `rpt24` in
+    /// `report-2024.csv`
+    Fuzzy,
+}
+
+fn match_name(name: &str, query: &str) -> Option<Match> {
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    if name.contains(&query) {
+        return Some(Match::Substring);
+    }
+    let mut chars = name.chars();
+    query
+        .chars()
+        .all(|q| chars.any(|c| c == q))
+        .then_some(Match::Fuzzy)
+}
+
+/// Rows whose names match the query, the ones containing it first, each group in the order
+/// of the listing. Every row matches an empty query
+pub fn filter_rows<T: Clone>(rows: &[T], query: &str, name: impl Fn(&T) -> &str) -> Vec<T> {
+    if query.is_empty() {
+        return rows.to_vec();
+    }
+    let mut matches: Vec<(Match, &T)> = rows
+        .iter()
+        .filter_map(|row| match_name(name(row), query).map(|m| (m, row)))
+        .collect();
+    // the sort is stable, so the listing order is kept within the groups
+    matches.sort_by_key(|(m, _)| *m);
+    matches.into_iter().map(|(_, row)| row.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(rows: &[&str], query: &str) -> Vec<String> {
+        let rows: Vec<String> = rows.iter().map(|r| r.to_string()).collect();
+        filter_rows(&rows, query, |r| r.as_str())
+    }
+
+    #[test]
+    fn substring_matches_come_first_in_listing_order() {
+        let rows = [
+            "reports/",
+            "release-notes.pdf",
+            "q1-report.pdf",
+            "README.md",
+            "photo.jpg",
+        ];
+        assert_eq!(
+            names(&rows, "rep"),
+            vec!["reports/", "q1-report.pdf", "release-notes.pdf"]
+        );
+        assert_eq!(names(&rows, "REPORT"), vec!["reports/", "q1-report.pdf"]);
+    }
+
+    #[test]
+    fn characters_have_to_appear_in_the_typed_order() {
+        let rows = ["report-2024.csv", "2024-report.csv"];
+        assert_eq!(names(&rows, "rpt24"), vec!["report-2024.csv"]);
+        assert!(names(&rows, "xyz").is_empty());
+    }
+
+    #[test]
+    fn empty_query_keeps_every_row() {
+        let rows = ["b", "a"];
+        assert_eq!(names(&rows, ""), vec!["b", "a"]);
+    }
+}
@@ -1,11 +1,15 @@
 /// Represents an item (file/directory) on your local machine
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LocalDataItem {
     pub name: String,
     pub size: String,
     pub file_type: String,
     pub path: String,
     pub is_directory: bool,
+    /// Time of the last modification, `2024-05-01 12:00` in UTC like on the s3 panel
+    pub modified: Option<String>,
+    /// Dotfiles, and on Windows the entries with the hidden attribute
+    pub is_hidden: bool,
 }
 
 impl LocalDataItem {
@@ -17,15 +21,27 @@ impl LocalDataItem {
         is_directory: bool,
     ) -> LocalDataItem {
         LocalDataItem {
+            is_hidden: file_name.starts_with('.'),
             name: file_name,
             size,
             file_type: String::from(file_type),
             path: String::from(path),
             is_directory,
+            modified: None,
         }
     }
+
+    pub fn with_modified(self, modified: Option<String>) -> Self {
+        LocalDataItem { modified, ..self }
+    }
+
     pub fn to_columns(&self) -> Vec<String> {
-        vec![self.name.clone(), self.size.clone(), self.file_type.clone()]
+        vec![
+            self.name.clone(),
+            self.size.clone(),
+            self.file_type.clone(),
+            self.modified.clone().unwrap_or("-".to_string()),
+        ]
     }
 }
 
@@ -41,6 +57,8 @@ mod tests {
             file_type: "txt".into(),
             path: "/some/path".into(),
             is_directory: false,
+            modified: None,
+            is_hidden: false,
         };
         let local_data_item =
             LocalDataItem::init("name".into(), "1 MB".into(), "txt", "/some/path", false);
@@ -59,11 +77,23 @@ mod tests {
             file_type: "txt".into(),
             path: "/some/path".into(),
             is_directory: false,
+            modified: Some("2024-05-01 12:00".into()),
+            is_hidden: false,
         };
         let res = item.to_columns();
-        assert_eq!(res.len(), 3);
+        assert_eq!(res.len(), 4);
         assert_eq!(res[0], item.name);
         assert_eq!(res[1], item.size);
         assert_eq!(res[2], item.file_type);
+        assert_eq!(res[3], "2024-05-01 12:00");
+    }
+
+    #[test]
+    fn dotfiles_are_hidden() {
+        let item = LocalDataItem::init(".bashrc".into(), "1 KB".into(), "", "/home/.bashrc", false);
+        assert!(item.is_hidden);
+        assert_eq!(item.to_columns()[3], "-");
+        let item = LocalDataItem::init("notes.txt".into(), "1 KB".into(), "txt", "/n.txt", false);
+        assert!(!item.is_hidden);
     }
 }
@@ -1,7 +1,7 @@
 use crate::model::local_data_item::LocalDataItem;
-use crate::settings::file_credentials::FileCredential;
-use std::fs;
-use std::path::{Path, PathBuf};
+use crate::model::verification::Verification;
+use crate::services::local_walker::{walk, WalkOptions};
+use std::path::Path;
 
 /// Keeps the information about the selected file which is later displayed on the transfers page
 #[derive(Debug, Clone)]
@@ -12,10 +12,15 @@ pub struct LocalSelectedItem {
     pub destination_bucket: String,
     pub destination_path: String,
     pub transferred: bool,
-    pub s3_creds: FileCredential,
+    /// Name of the credential the item was selected with, see `S3SelectedItem::account`
+    pub account: String,
     pub progress: f64,
     pub children: Option<Vec<LocalSelectedItem>>,
     pub error: Option<String>,
+    /// Check of the uploaded object against the file, see `upload_verification`
+    pub verification: Option<Verification>,
+    /// Uploaded files of a directory, counted by `update_selected_local_transfers`
+    pub completed_children: usize,
 }
 
 impl LocalSelectedItem {
@@ -25,7 +30,7 @@ impl LocalSelectedItem {
         is_directory: bool,
         destination_bucket: String,
         destination_path: String,
-        s3_creds: FileCredential,
+        account: String,
         children: Option<Vec<LocalSelectedItem>>,
     ) -> LocalSelectedItem {
         LocalSelectedItem {
@@ -35,10 +40,12 @@ impl LocalSelectedItem {
             destination_bucket,
             destination_path,
             transferred: false,
-            s3_creds,
+            account,
             progress: 0f64,
             children,
             error: None,
+            verification: None,
+            completed_children: 0,
         }
     }
     /*
@@ -48,7 +55,7 @@ impl LocalSelectedItem {
                     sr.is_directory,
                     selected_bucket,
                     destination_path,
-                    self.props.current_s3_creds.clone(),
+                    self.props.current_s3_creds.name.clone(),
                     None,
                 );
     let selected_item = LocalSelectedItem::new(
@@ -57,12 +64,21 @@ impl LocalSelectedItem {
                 sr.is_directory,
                 "".to_string(),
                 self.props.current_s3_path.clone(),
-                self.props.current_s3_creds.clone(),
+                self.props.current_s3_creds.name.clone(),
                 None,
             );
      */
 
-    pub fn from_local_data_item(item: LocalDataItem, s3_creds: FileCredential) -> Self {
+    /// Key the upload is written to, the file name when no destination path was chosen
+    pub fn destination_key(&self) -> String {
+        if self.destination_path == "/" || self.destination_path.is_empty() {
+            self.name.clone()
+        } else {
+            self.destination_path.clone()
+        }
+    }
+
+    pub fn from_local_data_item(item: LocalDataItem, account: String) -> Self {
         LocalSelectedItem {
             name: item.name,
             path: item.path,
@@ -70,65 +86,68 @@ impl LocalSelectedItem {
             destination_bucket: String::new(),
             destination_path: String::new(),
             transferred: false,
-            s3_creds,
+            account,
             progress: 0f64,
             children: None,
             error: None,
+            verification: None,
+            completed_children: 0,
         }
     }
 
-    pub fn list_directory_items(item: &LocalSelectedItem) -> Vec<LocalSelectedItem> {
-        let path = Path::new(&item.path);
-
+    /// Lists all files below the selected directory, entries excluded by the options are skipped
+    pub fn list_directory_items(
+        item: &LocalSelectedItem,
+        options: &WalkOptions,
+    ) -> Vec<LocalSelectedItem> {
         if item.is_directory {
-            let mut items = Vec::new();
-            if let Ok(entries) = fs::read_dir(path) {
-                for entry in entries.filter_map(Result::ok) {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        // Recursively process subdirectories
-                        items.extend(Self::list_directory_items(&LocalSelectedItem {
-                            name: path.file_name().unwrap().to_string_lossy().into_owned(),
-                            path: path.to_string_lossy().into(),
-                            is_directory: true,
-                            destination_bucket: item.destination_bucket.clone(),
-                            destination_path: PathBuf::from(&item.destination_path)
-                                .join(path.file_name().unwrap().to_string_lossy().into_owned())
-                                .to_string_lossy()
-                                .into(),
-                            transferred: false,
-                            s3_creds: item.s3_creds.clone(),
-                            progress: 0.0,
-                            children: None,
-                            error: None,
-                        }));
-                    } else {
-                        // Process files
-                        items.push(LocalSelectedItem {
-                            name: path.file_name().unwrap().to_string_lossy().into_owned(),
-                            path: path.to_string_lossy().into(),
-                            is_directory: false,
-                            destination_bucket: item.destination_bucket.clone(),
-                            destination_path: PathBuf::from(&item.destination_path)
-                                .join(path.file_name().unwrap().to_string_lossy().into_owned())
-                                .to_string_lossy()
-                                .into(),
-                            transferred: false,
-                            s3_creds: item.s3_creds.clone(),
-                            progress: 0.0,
-                            children: None,
-                            error: None,
-                        });
-                    }
-                }
-            }
-            items
+            let options = WalkOptions {
+                collect_entries: true,
+                ..options.clone()
+            };
+            walk(Path::new(&item.path), &options)
+                .entries
+                .into_iter()
+                .filter(|entry| !entry.is_directory)
+                .map(|entry| LocalSelectedItem {
+                    name: entry
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    path: entry.path.to_string_lossy().into(),
+                    is_directory: false,
+                    destination_bucket: item.destination_bucket.clone(),
+                    destination_path: child_destination(
+                        &item.destination_path,
+                        &entry.relative_path,
+                    ),
+                    transferred: false,
+                    account: item.account.clone(),
+                    progress: 0.0,
+                    children: None,
+                    error: None,
+                    verification: None,
+                    completed_children: 0,
+                })
+                .collect()
         } else {
             vec![item.clone()]
         }
     }
 }
 
+/// Key of a file of an uploaded directory, the path of the file relative to the directory goes
+/// after the directory's destination. Keys are always separated with `/`, whatever the platform
+fn child_destination(destination_path: &str, relative_path: &str) -> String {
+    let prefix = destination_path.trim_end_matches('/');
+    if prefix.is_empty() {
+        relative_path.to_string()
+    } else {
+        format!("{}/{}", prefix, relative_path)
+    }
+}
+
 impl PartialEq for LocalSelectedItem {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
@@ -151,9 +170,11 @@ mod tests {
             path: "path/to/file1.txt".into(),
             progress: 0.0,
             is_directory: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             children: None,
             error: None,
+            verification: None,
+            completed_children: 0,
         };
         let res = LocalSelectedItem::new(
             "file1.txt".into(),
@@ -166,4 +187,84 @@ mod tests {
         );
         assert_eq!(item, res);
     }
+
+    #[test]
+    fn directory_items_agree_with_walk_summary() {
+        use crate::services::local_walker::IgnoreRules;
+        use std::fs;
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("photos/raw")).unwrap();
+        fs::create_dir_all(dir.path().join("photos/.cache")).unwrap();
+        fs::write(dir.path().join("photos/a.jpg"), b"a").unwrap();
+        fs::write(dir.path().join("photos/raw/b.cr2"), b"b").unwrap();
+        fs::write(dir.path().join("photos/raw/b.tmp"), b"b").unwrap();
+        fs::write(dir.path().join("photos/.cache/thumb"), b"c").unwrap();
+        let item = LocalSelectedItem::new(
+            "photos".into(),
+            dir.path().join("photos").to_string_lossy().into(),
+            true,
+            "test-bucket".into(),
+            "photos".into(),
+            Default::default(),
+            None,
+        );
+        let options =
+            WalkOptions::with_rules(IgnoreRules::new(&["*.tmp".into(), ".cache/".into()]));
+
+        let children = LocalSelectedItem::list_directory_items(&item, &options);
+        let summary = walk(Path::new(&item.path), &options).summary;
+
+        assert_eq!(children.len(), summary.files);
+        assert_eq!(children.len(), 2);
+        assert_eq!(summary.excluded_files, 1);
+        assert_eq!(summary.excluded_dirs, 1);
+        let raw = children.iter().find(|c| c.name == "b.cr2").unwrap();
+        assert_eq!(raw.destination_key(), "photos/raw/b.cr2");
+    }
+
+    #[test]
+    fn directory_upload_keeps_the_nested_paths() {
+        use std::fs;
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("photos/2023")).unwrap();
+        fs::create_dir_all(dir.path().join("photos/2024/empty")).unwrap();
+        fs::write(dir.path().join("photos/img.jpg"), b"a").unwrap();
+        fs::write(dir.path().join("photos/2023/img.jpg"), b"b").unwrap();
+        let item = LocalSelectedItem::new(
+            "photos".into(),
+            dir.path().join("photos").to_string_lossy().into(),
+            true,
+            "test-bucket".into(),
+            "photos".into(),
+            Default::default(),
+            None,
+        );
+
+        let mut keys: Vec<String> =
+            LocalSelectedItem::list_directory_items(&item, &WalkOptions::default())
+                .iter()
+                .map(|child| child.destination_key())
+                .collect();
+        keys.sort();
+        // files of the same name stay apart, empty directories aren't uploaded
+        assert_eq!(keys, vec!["photos/2023/img.jpg", "photos/img.jpg"]);
+    }
+
+    #[test]
+    fn child_destination_is_joined_with_slashes() {
+        assert_eq!(
+            child_destination("photos", "2023/img.jpg"),
+            "photos/2023/img.jpg"
+        );
+        assert_eq!(
+            child_destination("backup/photos/", "img.jpg"),
+            "backup/photos/img.jpg"
+        );
+        assert_eq!(child_destination("/", "2023/img.jpg"), "2023/img.jpg");
+        assert_eq!(child_destination("", "img.jpg"), "img.jpg");
+    }
 }
@@ -1,12 +1,39 @@
 //! This module provides common objects used throughout the entire application
 
 pub mod action;
+pub mod activity_log;
+pub mod aws_cli_command;
+pub mod bucket_cache;
+pub mod bulk_deletion;
+pub mod destination_conflict;
 pub mod download_progress_item;
+pub mod error_groups;
+pub mod file_comparison;
+pub mod folder_name;
+pub mod jump_target;
+pub mod large_batch;
+pub mod listing_filter;
 pub mod local_data_item;
 pub mod local_selected_item;
+pub mod navigation_history;
 pub mod navigation_state;
+pub mod notification;
+pub mod object_copy;
+pub mod object_lock;
+pub mod object_metadata;
+pub mod object_preview;
+pub mod rename;
 pub mod s3_data_item;
+pub mod s3_path;
 pub mod s3_selected_item;
 pub mod state;
+pub mod stats;
+pub mod storage_class;
+pub mod transfer_batch;
 pub mod transfer_item;
+pub mod transfer_overlap;
+pub mod transfer_progress;
+pub mod transfer_rate;
+pub mod transfer_sort;
 pub mod upload_progress_item;
+pub mod verification;
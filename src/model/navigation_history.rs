@@ -0,0 +1,284 @@
+//! This module provides the back/forward history of the locations shown on the s3 panel
+use crate::model::navigation_state::NavigationState;
+
+/// Maximum number of locations kept behind the current one
+pub const MAX_NAVIGATION_HISTORY: usize = 50;
+
+/// How the s3 panel moves to another location
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavigationStep {
+    /// Opens a bucket or a directory, the locations ahead are discarded
+    Into(NavigationState),
+    /// Opens the parent of the current location
+    Parent,
+    Back,
+    Forward,
+}
+
+/// Location left for another one, with the row highlighted at that moment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visit {
+    pub location: NavigationState,
+    pub row: Option<usize>,
+}
+
+/// Browser like history of the s3 panel for one account, it starts at the bucket list
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationHistory {
+    back: Vec<Visit>,
+    current: NavigationState,
+    forward: Vec<Visit>,
+}
+
+impl Default for NavigationHistory {
+    fn default() -> Self {
+        NavigationHistory {
+            back: Vec::new(),
+            current: NavigationState::new(None, None),
+            forward: Vec::new(),
+        }
+    }
+}
+
+impl NavigationHistory {
+    pub fn current(&self) -> &NavigationState {
+        &self.current
+    }
+
+    /// Moves to the location, `row` is the row highlighted in the location being left
+    pub fn visit(&mut self, location: NavigationState, row: Option<usize>) {
+        if location == self.current {
+            return;
+        }
+        let left = std::mem::replace(&mut self.current, location);
+        self.back.push(Visit {
+            location: left,
+            row,
+        });
+        if self.back.len() > MAX_NAVIGATION_HISTORY {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    /// Returns to the previous location, `None` when there is nothing behind
+    pub fn back(&mut self, row: Option<usize>) -> Option<Visit> {
+        let previous = self.back.pop()?;
+        let left = std::mem::replace(&mut self.current, previous.location.clone());
+        self.forward.push(Visit {
+            location: left,
+            row,
+        });
+        Some(previous)
+    }
+
+    /// Goes again to the location left with `back`, `None` when there is nothing ahead
+    pub fn forward(&mut self, row: Option<usize>) -> Option<Visit> {
+        let next = self.forward.pop()?;
+        let left = std::mem::replace(&mut self.current, next.location.clone());
+        self.back.push(Visit {
+            location: left,
+            row,
+        });
+        Some(next)
+    }
+
+    /// Goes back to the location visited earlier, the locations left are discarded
+    pub fn rewind_to(&mut self, location: &NavigationState) {
+        while self.current != *location {
+            match self.back.pop() {
+                Some(previous) => self.current = previous.location,
+                None => break,
+            }
+        }
+        self.forward.clear();
+    }
+
+    /// Returns to the bucket list and drops the locations in the bucket from the history,
+    /// e.g. after the bucket was deleted by another client
+    pub fn forget_bucket(&mut self, bucket: &str) {
+        let in_bucket =
+            |location: &NavigationState| location.current_bucket.as_deref() == Some(bucket);
+        self.back.retain(|visit| !in_bucket(&visit.location));
+        self.forward.retain(|visit| !in_bucket(&visit.location));
+        if in_bucket(&self.current) {
+            self.current = NavigationState::new(None, None);
+        }
+        // the locations around the dropped ones may be the same now
+        self.back.dedup_by(|a, b| a.location == b.location);
+        if self
+            .back
+            .last()
+            .is_some_and(|visit| visit.location == self.current)
+        {
+            self.back.pop();
+        }
+    }
+
+    /// Applies the step, returning the location to show and the row to highlight in it
+    pub fn navigate(
+        &mut self,
+        step: NavigationStep,
+        row: Option<usize>,
+    ) -> Option<(NavigationState, Option<usize>)> {
+        match step {
+            NavigationStep::Into(location) => {
+                self.visit(location.clone(), row);
+                Some((location, None))
+            }
+            NavigationStep::Parent => {
+                let parent = self.current.parent();
+                if parent == self.current {
+                    return None;
+                }
+                self.visit(parent.clone(), row);
+                Some((parent, None))
+            }
+            NavigationStep::Back => self.back(row).map(|visit| (visit.location, visit.row)),
+            NavigationStep::Forward => self.forward(row).map(|visit| (visit.location, visit.row)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(bucket: &str, prefix: Option<&str>) -> NavigationState {
+        NavigationState::new(Some(bucket.into()), prefix.map(String::from))
+    }
+
+    #[test]
+    fn back_and_forward_return_to_the_visited_locations() {
+        let mut history = NavigationHistory::default();
+        history.visit(location("bucket", None), Some(3));
+        history.visit(location("bucket", Some("a/")), Some(1));
+        history.visit(location("bucket", Some("a/b/")), Some(0));
+
+        let previous = history.back(Some(5)).unwrap();
+        assert_eq!(previous.location, location("bucket", Some("a/")));
+        assert_eq!(previous.row, Some(0));
+        assert_eq!(
+            history.back(None).unwrap().location,
+            location("bucket", None)
+        );
+
+        let next = history.forward(Some(2)).unwrap();
+        assert_eq!(next.location, location("bucket", Some("a/")));
+        let next = history.forward(None).unwrap();
+        assert_eq!(next.location, location("bucket", Some("a/b/")));
+        assert_eq!(next.row, Some(5));
+        assert!(history.forward(None).is_none());
+    }
+
+    #[test]
+    fn new_navigation_discards_the_forward_branch() {
+        let mut history = NavigationHistory::default();
+        history.visit(location("bucket", None), None);
+        history.visit(location("bucket", Some("a/")), None);
+        history.back(None);
+        history.visit(location("other", None), None);
+
+        assert!(history.forward(None).is_none());
+        assert_eq!(
+            history.back(None).unwrap().location,
+            location("bucket", None)
+        );
+        assert_eq!(
+            history.back(None).unwrap().location,
+            NavigationState::new(None, None)
+        );
+        assert!(history.back(None).is_none());
+    }
+
+    #[test]
+    fn jumps_can_be_undone_with_back() {
+        let mut history = NavigationHistory::default();
+        history.visit(location("bucket", Some("logs/2024/")), Some(7));
+        history.visit(location("other", Some("deep/path/")), None);
+
+        assert_eq!(
+            history.navigate(NavigationStep::Back, None),
+            Some((location("bucket", Some("logs/2024/")), Some(7)))
+        );
+    }
+
+    #[test]
+    fn parent_is_a_new_visit() {
+        let mut history = NavigationHistory::default();
+        history.visit(location("bucket", Some("a/b/")), None);
+
+        assert_eq!(
+            history.navigate(NavigationStep::Parent, Some(2)),
+            Some((location("bucket", Some("a/")), None))
+        );
+        assert_eq!(
+            history.navigate(NavigationStep::Back, None),
+            Some((location("bucket", Some("a/b/")), Some(2)))
+        );
+        assert_eq!(
+            NavigationHistory::default().navigate(NavigationStep::Parent, None),
+            None
+        );
+    }
+
+    #[test]
+    fn rewind_discards_the_locations_left() {
+        let mut history = NavigationHistory::default();
+        history.visit(location("bucket", None), Some(4));
+        history.visit(location("bucket", Some("a/")), None);
+        history.visit(location("bucket", Some("a/b/")), None);
+
+        history.rewind_to(&location("bucket", None));
+        assert_eq!(history.current(), &location("bucket", None));
+        assert!(history.forward(None).is_none());
+        assert_eq!(
+            history.back(None).unwrap().location,
+            NavigationState::new(None, None)
+        );
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut history = NavigationHistory::default();
+        for i in 0..MAX_NAVIGATION_HISTORY + 10 {
+            history.visit(location(&format!("bucket-{}", i), None), None);
+        }
+        let mut steps = 0;
+        while history.back(None).is_some() {
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_NAVIGATION_HISTORY);
+        assert_eq!(history.current(), &location("bucket-9", None));
+    }
+
+    #[test]
+    fn visiting_the_current_location_is_ignored() {
+        let mut history = NavigationHistory::default();
+        history.visit(location("bucket", None), None);
+        history.visit(location("bucket", None), None);
+        assert_eq!(
+            history.back(None).unwrap().location,
+            NavigationState::new(None, None)
+        );
+        assert!(history.back(None).is_none());
+    }
+
+    #[test]
+    fn forgotten_bucket_leaves_the_history() {
+        let mut history = NavigationHistory::default();
+        history.visit(location("logs", None), None);
+        history.visit(NavigationState::new(None, None), None);
+        history.visit(location("media", None), Some(2));
+        history.visit(location("media", Some("2024/")), Some(0));
+
+        history.forget_bucket("media");
+        assert_eq!(history.current(), &NavigationState::new(None, None));
+        assert_eq!(history.back(None).unwrap().location, location("logs", None));
+        assert_eq!(
+            history.back(None).unwrap().location,
+            NavigationState::new(None, None)
+        );
+        assert!(history.back(None).is_none());
+    }
+}
@@ -1,6 +1,6 @@
 /// Keeps the information about currently selected bucket and directory (prefix)
 /// to help with navigation on s3
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct NavigationState {
     pub current_bucket: Option<String>,
     pub current_prefix: Option<String>,
@@ -13,6 +13,42 @@ impl NavigationState {
             current_prefix: prefix,
         }
     }
+
+    /// The enclosing directory, the bucket root for top level directories and
+    /// the bucket list for a bucket root
+    pub fn parent(&self) -> NavigationState {
+        match &self.current_prefix {
+            Some(prefix) => {
+                let trimmed = prefix.trim_end_matches('/');
+                let parent = trimmed.rfind('/').map(|i| trimmed[..=i].to_string());
+                NavigationState::new(self.current_bucket.clone(), parent)
+            }
+            None => NavigationState::new(None, None),
+        }
+    }
+
+    /// Locations from the bucket root down to this one, one for every directory,
+    /// empty for the bucket list
+    pub fn levels(&self) -> Vec<NavigationState> {
+        let Some(bucket) = &self.current_bucket else {
+            return Vec::new();
+        };
+        let mut levels = vec![NavigationState::new(Some(bucket.clone()), None)];
+        let mut path = String::new();
+        for dir in self
+            .current_prefix
+            .iter()
+            .flat_map(|p| p.split_terminator('/'))
+        {
+            path.push_str(dir);
+            path.push('/');
+            levels.push(NavigationState::new(
+                Some(bucket.clone()),
+                Some(path.clone()),
+            ));
+        }
+        levels
+    }
 }
 
 #[cfg(test)]
@@ -31,4 +67,41 @@ mod tests {
         assert_eq!(ns.current_bucket, res.current_bucket);
         assert_eq!(ns.current_prefix, res.current_prefix);
     }
+
+    #[test]
+    fn parent_goes_up_one_level() {
+        let at = |bucket: Option<&str>, prefix: Option<&str>| {
+            NavigationState::new(bucket.map(String::from), prefix.map(String::from))
+        };
+        assert_eq!(
+            at(Some("bucket"), Some("a/b/")).parent(),
+            at(Some("bucket"), Some("a/"))
+        );
+        assert_eq!(
+            at(Some("bucket"), Some("a/")).parent(),
+            at(Some("bucket"), None)
+        );
+        assert_eq!(at(Some("bucket"), None).parent(), at(None, None));
+        assert_eq!(at(None, None).parent(), at(None, None));
+    }
+
+    #[test]
+    fn levels_go_from_the_bucket_root_down() {
+        let at = |bucket: Option<&str>, prefix: Option<&str>| {
+            NavigationState::new(bucket.map(String::from), prefix.map(String::from))
+        };
+        assert_eq!(
+            at(Some("bucket"), Some("a/b/")).levels(),
+            vec![
+                at(Some("bucket"), None),
+                at(Some("bucket"), Some("a/")),
+                at(Some("bucket"), Some("a/b/")),
+            ]
+        );
+        assert_eq!(
+            at(Some("bucket"), None).levels(),
+            vec![at(Some("bucket"), None)]
+        );
+        assert!(at(None, None).levels().is_empty());
+    }
 }
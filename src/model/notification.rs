@@ -0,0 +1,41 @@
+//! This module provides short lived messages displayed on the status lines
+use std::time::{Duration, Instant};
+
+/// Time for which a notification stays visible
+pub const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub message: String,
+    pub created_at: Instant,
+}
+
+impl Notification {
+    pub fn new(message: String) -> Self {
+        Notification {
+            message,
+            created_at: Instant::now(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.created_at.elapsed() >= NOTIFICATION_TIMEOUT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_expires_after_timeout() {
+        let fresh = Notification::new("saved".into());
+        assert!(!fresh.is_expired());
+
+        let old = Notification {
+            created_at: Instant::now() - NOTIFICATION_TIMEOUT,
+            ..fresh
+        };
+        assert!(old.is_expired());
+    }
+}
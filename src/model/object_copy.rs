@@ -0,0 +1,174 @@
+//! This module provides the server side copy of an s3 object into another bucket or prefix
+use crate::model::s3_selected_item::S3SelectedItem;
+
+/// Largest object copied with a single CopyObject request, S3 rejects bigger ones
+pub const MAX_SINGLE_COPY_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+/// Size of the parts of a multipart copy, well above the 5MB minimum and below 5GB maximum
+pub const COPY_PART_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Copy of an object, a move deletes the source once the copy is written
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectCopy {
+    pub source_bucket: String,
+    pub source_key: String,
+    pub destination_bucket: String,
+    pub destination_key: String,
+    pub delete_source: bool,
+}
+
+impl ObjectCopy {
+    /// Copy of the object keeping its name under `destination_prefix`, the bucket root when
+    /// `None`. Directories and buckets cannot be copied
+    pub fn new(
+        item: &S3SelectedItem,
+        destination_bucket: String,
+        destination_prefix: Option<String>,
+        delete_source: bool,
+    ) -> Option<ObjectCopy> {
+        if item.is_directory || item.is_bucket {
+            return None;
+        }
+        let source_key = item.path.clone().unwrap_or(item.name.clone());
+        let name = source_key.rsplit('/').next().unwrap_or_default();
+        Some(ObjectCopy {
+            source_bucket: item.bucket.clone()?,
+            destination_key: format!("{}{}", destination_prefix.unwrap_or_default(), name),
+            source_key,
+            destination_bucket,
+            delete_source,
+        })
+    }
+
+    /// Move of the object to another key of its bucket. Directories and buckets cannot
+    /// be renamed
+    pub fn renamed(item: &S3SelectedItem, destination_key: String) -> Option<ObjectCopy> {
+        if item.is_directory || item.is_bucket {
+            return None;
+        }
+        let bucket = item.bucket.clone()?;
+        Some(ObjectCopy {
+            source_bucket: bucket.clone(),
+            source_key: item.path.clone().unwrap_or(item.name.clone()),
+            destination_bucket: bucket,
+            destination_key,
+            delete_source: true,
+        })
+    }
+
+    /// Whether the destination is the object itself
+    pub fn is_in_place(&self) -> bool {
+        self.source_bucket == self.destination_bucket && self.source_key == self.destination_key
+    }
+
+    pub fn source_url(&self) -> String {
+        format!("s3://{}/{}", self.source_bucket, self.source_key)
+    }
+
+    pub fn destination_url(&self) -> String {
+        format!("s3://{}/{}", self.destination_bucket, self.destination_key)
+    }
+
+    /// Short description used in the activity log, e.g. `Moving s3://a/x.csv to s3://b/x.csv`
+    pub fn describe(&self, verb: &str) -> String {
+        format!(
+            "{} {} to {}",
+            verb,
+            self.source_url(),
+            self.destination_url()
+        )
+    }
+}
+
+/// `bytes=first-last` ranges of the parts copied from an object of `size` bytes
+pub fn copy_part_ranges(size: u64, part_size: u64) -> Vec<String> {
+    (0..size)
+        .step_by(part_size as usize)
+        .map(|first| format!("bytes={}-{}", first, (first + part_size).min(size) - 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str, is_directory: bool) -> S3SelectedItem {
+        S3SelectedItem {
+            bucket: Some("staging".into()),
+            name: path.rsplit('/').next().unwrap().into(),
+            path: Some(path.into()),
+            is_directory,
+            is_bucket: false,
+            destination_dir: String::new(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        }
+    }
+
+    #[test]
+    fn copied_object_keeps_its_name_under_the_destination_prefix() {
+        let copy = ObjectCopy::new(
+            &item("reports/2024/q1.csv", false),
+            "production".into(),
+            Some("archive/".into()),
+            true,
+        )
+        .unwrap();
+        assert_eq!(copy.source_url(), "s3://staging/reports/2024/q1.csv");
+        assert_eq!(copy.destination_url(), "s3://production/archive/q1.csv");
+        assert!(copy.delete_source);
+
+        let root = ObjectCopy::new(&item("q1.csv", false), "production".into(), None, false);
+        assert_eq!(root.unwrap().destination_key, "q1.csv");
+    }
+
+    #[test]
+    fn renamed_object_is_moved_within_its_bucket() {
+        let rename =
+            ObjectCopy::renamed(&item("reports/2024/q1.csv", false), "archive/q1.csv".into())
+                .unwrap();
+        assert_eq!(rename.destination_url(), "s3://staging/archive/q1.csv");
+        assert!(rename.delete_source);
+        assert_eq!(
+            ObjectCopy::renamed(&item("reports/", true), "archive/".into()),
+            None
+        );
+    }
+
+    #[test]
+    fn directories_are_not_copied() {
+        assert_eq!(
+            ObjectCopy::new(&item("reports/", true), "production".into(), None, false),
+            None
+        );
+    }
+
+    #[test]
+    fn pasting_into_the_same_location_is_detected() {
+        let copy = ObjectCopy::new(
+            &item("reports/q1.csv", false),
+            "staging".into(),
+            Some("reports/".into()),
+            false,
+        )
+        .unwrap();
+        assert!(copy.is_in_place());
+    }
+
+    #[test]
+    fn parts_cover_the_whole_object() {
+        assert_eq!(
+            copy_part_ranges(25, 10),
+            vec!["bytes=0-9", "bytes=10-19", "bytes=20-24"]
+        );
+        assert_eq!(copy_part_ranges(20, 10), vec!["bytes=0-9", "bytes=10-19"]);
+        let parts = copy_part_ranges(MAX_SINGLE_COPY_SIZE + 1, COPY_PART_SIZE);
+        assert_eq!(parts.len(), 11);
+        assert_eq!(parts[10], format!("bytes={0}-{0}", MAX_SINGLE_COPY_SIZE));
+    }
+}
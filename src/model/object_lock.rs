@@ -0,0 +1,143 @@
+//! This module provides the object lock (retention and legal hold) status of a single s3 object
+use aws_smithy_types::date_time::Format as DateTimeFormat;
+use aws_smithy_types::DateTime;
+
+/// Lock status returned by HeadObject, the values are missing when the bucket has no object lock
+/// or the credentials are not allowed to read the retention or the legal hold
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectLockInfo {
+    /// `GOVERNANCE` or `COMPLIANCE`
+    pub retention_mode: Option<String>,
+    pub retain_until: Option<String>,
+    /// Whether the retention date was still in the future when the object was read
+    pub retention_active: bool,
+    pub legal_hold: Option<bool>,
+}
+
+impl ObjectLockInfo {
+    pub fn from_head(
+        retention_mode: Option<&str>,
+        retain_until: Option<&DateTime>,
+        legal_hold: Option<&str>,
+        now: DateTime,
+    ) -> Self {
+        ObjectLockInfo {
+            retention_mode: retention_mode.map(String::from),
+            retain_until: retain_until.and_then(|d| d.fmt(DateTimeFormat::DateTime).ok()),
+            retention_active: retention_mode.is_some()
+                && retain_until.is_some_and(|d| d.secs() > now.secs()),
+            legal_hold: legal_hold.map(|s| s.eq_ignore_ascii_case("ON")),
+        }
+    }
+
+    fn retention_description(&self) -> Option<String> {
+        match (&self.retention_mode, &self.retain_until) {
+            (Some(mode), Some(until)) => Some(format!("{} until {}", mode, until)),
+            (Some(mode), None) => Some(mode.clone()),
+            _ => None,
+        }
+    }
+
+    /// Rows displayed in the metadata popup
+    pub fn rows(&self) -> Vec<(String, String)> {
+        let retention = match self.retention_description() {
+            Some(retention) if !self.retention_active => format!("{} (expired)", retention),
+            Some(retention) => retention,
+            None => "-".to_string(),
+        };
+        let legal_hold = match self.legal_hold {
+            Some(true) => "ON",
+            Some(false) => "OFF",
+            None => "-",
+        };
+        vec![
+            ("Retention".to_string(), retention),
+            ("Legal hold".to_string(), legal_hold.to_string()),
+        ]
+    }
+
+    /// Warning displayed in the delete confirmation of a protected object
+    pub fn delete_warning(&self) -> Option<String> {
+        let retention = format!(
+            "{} retention until {}",
+            self.retention_mode.clone().unwrap_or_default(),
+            self.retain_until.clone().unwrap_or_default()
+        );
+        let reason = match (self.legal_hold == Some(true), self.retention_active) {
+            (true, true) => format!("a legal hold and {}", retention),
+            (true, false) => "a legal hold".to_string(),
+            (false, true) => retention,
+            (false, false) => return None,
+        };
+        Some(format!(
+            "The object is under {}. The delete will only create a delete marker, deleting the locked version will fail.",
+            reason
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn active_retention_and_legal_hold_are_parsed() {
+        let until = DateTime::from_secs(NOW + 3600);
+        let info = ObjectLockInfo::from_head(
+            Some("COMPLIANCE"),
+            Some(&until),
+            Some("ON"),
+            DateTime::from_secs(NOW),
+        );
+        assert!(info.retention_active);
+        assert_eq!(info.legal_hold, Some(true));
+        assert_eq!(
+            info.rows()[0].1,
+            "COMPLIANCE until 2023-11-14T23:13:20Z".to_string()
+        );
+        assert!(info
+            .delete_warning()
+            .unwrap()
+            .contains("a legal hold and COMPLIANCE retention until 2023-11-14T23:13:20Z."));
+    }
+
+    #[test]
+    fn expired_retention_does_not_warn() {
+        let until = DateTime::from_secs(NOW - 1);
+        let info = ObjectLockInfo::from_head(
+            Some("GOVERNANCE"),
+            Some(&until),
+            Some("OFF"),
+            DateTime::from_secs(NOW),
+        );
+        assert!(!info.retention_active);
+        assert_eq!(info.legal_hold, Some(false));
+        assert!(info.rows()[0].1.ends_with("(expired)"));
+        assert_eq!(info.delete_warning(), None);
+    }
+
+    #[test]
+    fn missing_headers_are_shown_as_unknown() {
+        let info = ObjectLockInfo::from_head(None, None, None, DateTime::from_secs(NOW));
+        assert_eq!(info, ObjectLockInfo::default());
+        assert_eq!(
+            info.rows(),
+            vec![
+                ("Retention".to_string(), "-".to_string()),
+                ("Legal hold".to_string(), "-".to_string())
+            ]
+        );
+        assert_eq!(info.delete_warning(), None);
+    }
+
+    #[test]
+    fn legal_hold_alone_warns() {
+        let info = ObjectLockInfo::from_head(None, None, Some("ON"), DateTime::from_secs(NOW));
+        assert!(info
+            .delete_warning()
+            .unwrap()
+            .contains("under a legal hold."));
+    }
+}
@@ -0,0 +1,130 @@
+//! This module provides the metadata of a single s3 object displayed in the metadata popup
+use crate::model::object_lock::ObjectLockInfo;
+
+/// Limit of the user defined metadata, the sum of the keys and values in bytes
+pub const MAX_USER_METADATA_BYTES: usize = 2048;
+
+/// Metadata returned by HeadObject, the system headers are kept so they can be
+/// preserved when the object is copied onto itself with new user metadata
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectMetadata {
+    pub bucket: String,
+    pub key: String,
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub content_disposition: Option<String>,
+    pub cache_control: Option<String>,
+    pub storage_class: Option<String>,
+    /// `AES256`, `aws:kms` or `aws:kms:dsse`, with the id of the KMS key when there's one
+    pub server_side_encryption: Option<String>,
+    pub e_tag: Option<String>,
+    pub last_modified: Option<String>,
+    pub lock: ObjectLockInfo,
+    /// The `x-amz-meta-*` pairs without the prefix, sorted by key
+    pub user_metadata: Vec<(String, String)>,
+}
+
+impl ObjectMetadata {
+    /// Rows describing the object above the user metadata in the popup
+    pub fn system_rows(&self) -> Vec<(String, String)> {
+        let value = |v: &Option<String>| v.clone().unwrap_or("-".to_string());
+        let mut rows = vec![
+            ("Content-Type".to_string(), value(&self.content_type)),
+            ("Storage class".to_string(), value(&self.storage_class)),
+            (
+                "Encryption".to_string(),
+                value(&self.server_side_encryption),
+            ),
+            ("ETag".to_string(), value(&self.e_tag)),
+            ("Last modified".to_string(), value(&self.last_modified)),
+        ];
+        rows.extend(self.lock.rows());
+        rows
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"-_.".contains(&b))
+}
+
+/// Checks the pairs before they are sent to s3, returns the reason of the first problem found
+pub fn validate_user_metadata(pairs: &[(String, String)]) -> Result<(), String> {
+    let mut size = 0;
+    for (i, (key, value)) in pairs.iter().enumerate() {
+        if !is_valid_key(key) {
+            return Err(format!(
+                "Invalid key '{}', use lowercase letters, digits, '-', '_' or '.'",
+                key
+            ));
+        }
+        if pairs[..i].iter().any(|(k, _)| k == key) {
+            return Err(format!("Duplicated key '{}'", key));
+        }
+        if !value.is_ascii() {
+            return Err(format!("Value of '{}' must be ASCII", key));
+        }
+        size += key.len() + value.len();
+    }
+    if size > MAX_USER_METADATA_BYTES {
+        return Err(format!(
+            "Metadata takes {} bytes, the limit is {} bytes",
+            size, MAX_USER_METADATA_BYTES
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn valid_metadata_is_accepted() {
+        assert!(validate_user_metadata(&[]).is_ok());
+        assert!(
+            validate_user_metadata(&[pair("pipeline-stage", "raw"), pair("v1.run_id", "42")])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn keys_must_be_lowercase_ascii_and_unique() {
+        assert!(validate_user_metadata(&[pair("Owner", "me")]).is_err());
+        assert!(validate_user_metadata(&[pair("", "me")]).is_err());
+        assert!(validate_user_metadata(&[pair("zażółć", "me")]).is_err());
+        assert!(validate_user_metadata(&[pair("has space", "me")]).is_err());
+        assert_eq!(
+            validate_user_metadata(&[pair("owner", "a"), pair("owner", "b")]),
+            Err("Duplicated key 'owner'".to_string())
+        );
+    }
+
+    #[test]
+    fn combined_size_is_limited() {
+        let at_limit = [pair("k", &"v".repeat(MAX_USER_METADATA_BYTES - 1))];
+        assert!(validate_user_metadata(&at_limit).is_ok());
+        let over = [pair("a", &"v".repeat(1024)), pair("b", &"v".repeat(1024))];
+        assert_eq!(
+            validate_user_metadata(&over),
+            Err("Metadata takes 2050 bytes, the limit is 2048 bytes".to_string())
+        );
+    }
+
+    #[test]
+    fn system_rows_show_missing_values_as_dash() {
+        let metadata = ObjectMetadata {
+            content_type: Some("text/plain".into()),
+            ..ObjectMetadata::default()
+        };
+        let rows = metadata.system_rows();
+        assert_eq!(rows[0], pair("Content-Type", "text/plain"));
+        assert_eq!(rows[1], pair("Storage class", "-"));
+    }
+}
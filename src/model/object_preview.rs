@@ -0,0 +1,127 @@
+//! This module provides the preview of the beginning of an s3 object shown in the file manager
+
+/// Content of the previewed bytes, text only when it can be displayed as is
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreviewContent {
+    Text(String),
+    /// Contains NUL bytes or isn't valid UTF-8
+    Binary,
+}
+
+/// Beginning of an s3 object read with a ranged request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectPreview {
+    pub bucket: String,
+    pub key: String,
+    pub content: PreviewContent,
+    /// Number of bytes read from the beginning of the object
+    pub read_bytes: u64,
+    pub object_size: u64,
+}
+
+impl ObjectPreview {
+    pub fn from_bytes(bucket: &str, key: &str, bytes: &[u8], object_size: u64) -> Self {
+        ObjectPreview {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            content: detect_content(bytes),
+            read_bytes: bytes.len() as u64,
+            object_size,
+        }
+    }
+
+    /// Whether only the beginning of the object is shown
+    pub fn truncated(&self) -> bool {
+        self.read_bytes < self.object_size
+    }
+
+    pub fn line_count(&self) -> usize {
+        match &self.content {
+            PreviewContent::Text(text) => text.lines().count(),
+            PreviewContent::Binary => 1,
+        }
+    }
+}
+
+fn detect_content(bytes: &[u8]) -> PreviewContent {
+    if bytes.contains(&0) {
+        return PreviewContent::Binary;
+    }
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        // the range can end in the middle of a multi-byte character
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return PreviewContent::Binary,
+    };
+    PreviewContent::Text(printable(text))
+}
+
+/// Tabs are expanded and other control characters replaced, so the content can't move
+/// the cursor or change the colors of the terminal
+fn printable(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' => result.push('\n'),
+            '\t' => result.push_str("    "),
+            '\r' => {}
+            c if c.is_control() => result.push('\u{fffd}'),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_shown_with_control_characters_replaced() {
+        let preview = ObjectPreview::from_bytes(
+            "logs",
+            "app.log",
+            b"level=info\r\n\tmsg=\x1b[31mstarted\n",
+            100,
+        );
+        assert_eq!(
+            preview.content,
+            PreviewContent::Text("level=info\n    msg=\u{fffd}[31mstarted\n".into())
+        );
+        assert_eq!(preview.line_count(), 2);
+        assert!(preview.truncated());
+    }
+
+    #[test]
+    fn binary_content_is_detected() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(
+            ObjectPreview::from_bytes("b", "image.png", png, 16).content,
+            PreviewContent::Binary
+        );
+        let latin1 = b"caf\xe9 au lait";
+        assert_eq!(
+            ObjectPreview::from_bytes("b", "menu.txt", latin1, 12).content,
+            PreviewContent::Binary
+        );
+    }
+
+    #[test]
+    fn character_cut_by_the_range_is_dropped() {
+        // "zółw" cut after the first byte of 'ł'
+        let bytes = &"zółw".as_bytes()[..4];
+        let preview = ObjectPreview::from_bytes("b", "animals.txt", bytes, 7);
+        assert_eq!(preview.content, PreviewContent::Text("zó".into()));
+    }
+
+    #[test]
+    fn whole_small_object_is_not_truncated() {
+        let preview = ObjectPreview::from_bytes("b", "a.json", b"{}", 2);
+        assert!(!preview.truncated());
+        let preview = ObjectPreview::from_bytes("b", "empty.csv", b"", 0);
+        assert_eq!(preview.content, PreviewContent::Text(String::new()));
+        assert!(!preview.truncated());
+    }
+}
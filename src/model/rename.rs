@@ -0,0 +1,94 @@
+//! This module provides the new keys of the objects renamed on the s3 panel and the new paths
+//! of the files renamed on the local panel
+use std::path::{is_separator, Path, PathBuf};
+
+/// Name shown in the rename input, the key relative to the opened prefix
+pub fn relative_name<'a>(prefix: Option<&str>, key: &'a str) -> &'a str {
+    key.strip_prefix(prefix.unwrap_or_default()).unwrap_or(key)
+}
+
+/// New key of an object renamed in the location `prefix`. Names are relative to the prefix,
+/// names starting with '/' are absolute keys in the bucket, so objects can be moved between
+/// prefixes. Returns the error shown in the popup for invalid names
+pub fn renamed_key(prefix: Option<&str>, name: &str) -> Result<String, String> {
+    let (prefix, name) = match name.strip_prefix('/') {
+        Some(absolute) => ("", absolute),
+        None => (prefix.unwrap_or_default(), name),
+    };
+    if name.is_empty() {
+        return Err("Name cannot be empty".into());
+    }
+    if name.ends_with('/') {
+        return Err("Name cannot end with '/'".into());
+    }
+    if name.contains("//") {
+        return Err("Name cannot contain '//'".into());
+    }
+    Ok(format!("{}{}", prefix, name))
+}
+
+/// New path of a local file or directory renamed in place. Local renames don't move entries
+/// between directories, so names with a path separator are rejected
+pub fn renamed_path(path: &Path, name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() {
+        return Err("Name cannot be empty".into());
+    }
+    if name.chars().any(is_separator) {
+        return Err("Name cannot contain a path separator".into());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("Name cannot be '{}'", name));
+    }
+    let parent = path.parent().unwrap_or(Path::new(""));
+    Ok(parent.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_relative_to_the_prefix() {
+        assert_eq!(
+            relative_name(Some("reports/2024/"), "reports/2024/q1.csv"),
+            "q1.csv"
+        );
+        assert_eq!(relative_name(None, "q1.csv"), "q1.csv");
+        assert_eq!(
+            renamed_key(Some("reports/2024/"), "q1-final.csv"),
+            Ok("reports/2024/q1-final.csv".into())
+        );
+        assert_eq!(
+            renamed_key(Some("reports/2024/"), "archive/q1.csv"),
+            Ok("reports/2024/archive/q1.csv".into())
+        );
+        assert_eq!(renamed_key(None, "q1.csv"), Ok("q1.csv".into()));
+    }
+
+    #[test]
+    fn leading_slash_makes_the_key_absolute() {
+        assert_eq!(
+            renamed_key(Some("reports/2024/"), "/archive/q1.csv"),
+            Ok("archive/q1.csv".into())
+        );
+    }
+
+    #[test]
+    fn invalid_names_are_rejected() {
+        for name in ["", "/", "q1/", "archive//q1.csv"] {
+            assert!(renamed_key(Some("reports/"), name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn local_names_stay_in_the_directory() {
+        let path = Path::new("/home/user/notes.txt");
+        assert_eq!(
+            renamed_path(path, "todo.txt"),
+            Ok(PathBuf::from("/home/user/todo.txt"))
+        );
+        for name in ["", ".", "..", "docs/todo.txt", "/todo.txt"] {
+            assert!(renamed_path(path, name).is_err(), "{}", name);
+        }
+    }
+}
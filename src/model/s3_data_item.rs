@@ -1,4 +1,9 @@
 //! This module provides functionality for representing s3 data
+use crate::model::storage_class::current_class;
+use crate::services::s3_keys::normalize_key;
+use crate::services::s3_listing::ListedObject;
+use crate::utils::{format_bytes, short_time};
+use std::path::Path;
 
 /// Represents a file in s3 bucket
 #[derive(Debug, Clone)]
@@ -8,6 +13,9 @@ pub struct FileInfo {
     pub file_type: String,
     pub path: String,
     pub is_directory: bool,
+    /// e.g. `2024-05-01T12:00:00Z`, objects only
+    pub last_modified: Option<String>,
+    pub storage_class: Option<String>,
 }
 /// Represents a bucket on s3
 #[derive(Debug, Clone)]
@@ -15,6 +23,8 @@ pub struct BucketInfo {
     pub bucket: Option<String>,
     pub region: Option<String>,
     pub is_bucket: bool,
+    /// e.g. `2024-05-01T12:00:00Z`, buckets of the bucket list only
+    pub creation_date: Option<String>,
 }
 /// Keeps the information about fetched data from s3
 #[derive(Debug, Clone)]
@@ -27,6 +37,13 @@ pub struct S3DataItem {
     pub is_directory: bool,
     pub is_bucket: bool,
     pub region: Option<String>,
+    /// The backend listed the key with a leading slash, it's removed from `path`
+    pub leading_slash: bool,
+    /// e.g. `2024-05-01T12:00:00Z`, objects only
+    pub last_modified: Option<String>,
+    pub storage_class: Option<String>,
+    /// Creation of the bucket, the `region` of a bucket row is `None` until it's resolved
+    pub creation_date: Option<String>,
 }
 
 impl S3DataItem {
@@ -40,13 +57,110 @@ impl S3DataItem {
             is_directory: file_info.is_directory,
             is_bucket: bucket_info.is_bucket,
             region: bucket_info.region,
+            leading_slash: false,
+            last_modified: file_info.last_modified,
+            storage_class: file_info.storage_class,
+            creation_date: bucket_info.creation_date,
         }
     }
+
+    /// Item of a bucket listing, `raw_key` is the object key or the common prefix
+    /// as returned by the backend
+    pub fn listed(
+        bucket_info: BucketInfo,
+        raw_key: &str,
+        size: String,
+        is_directory: bool,
+    ) -> S3DataItem {
+        let (key, leading_slash) = normalize_key(raw_key);
+        let file_info = FileInfo {
+            file_name: if is_directory {
+                get_last_directory(key)
+            } else {
+                get_filename(key)
+            }
+            .unwrap_or_default(),
+            size,
+            file_type: if is_directory {
+                "Dir".to_string()
+            } else {
+                Path::new(key)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .unwrap_or("")
+                    .to_string()
+            },
+            path: key.to_string(),
+            is_directory,
+            last_modified: None,
+            storage_class: None,
+        };
+        S3DataItem {
+            leading_slash,
+            ..S3DataItem::init(bucket_info, file_info)
+        }
+    }
+
+    /// Object of a listed page with its size, last modification and storage class
+    pub fn listed_object(bucket_info: BucketInfo, object: &ListedObject) -> S3DataItem {
+        let size = object.size.map_or(String::new(), |value| value.to_string());
+        S3DataItem {
+            last_modified: object.last_modified.clone(),
+            storage_class: object.storage_class.clone(),
+            ..S3DataItem::listed(bucket_info, &object.key, size, false)
+        }
+    }
+
+    /// Name, size, type, last modification and storage class, buckets and directories show
+    /// `-` for the values only objects have. Buckets show their creation and region instead
     pub fn to_columns(&self) -> Vec<String> {
-        vec![self.name.clone(), self.size.clone(), self.file_type.clone()]
+        if self.is_bucket {
+            return vec![
+                self.name.clone(),
+                "-".to_string(),
+                self.file_type.clone(),
+                self.creation_date.as_deref().map_or("-".into(), short_time),
+                self.region.clone().unwrap_or("-".into()),
+            ];
+        }
+        let is_object = !self.is_bucket && !self.is_directory;
+        let object_value = |value: Option<String>| match value {
+            Some(value) if is_object => value,
+            _ => "-".to_string(),
+        };
+        let size = match self.size.parse::<u64>() {
+            Ok(bytes) => Some(format_bytes(bytes)),
+            Err(_) => Some(self.size.clone()).filter(|size| !size.is_empty()),
+        };
+        let storage_class = current_class(self.storage_class.as_deref()).to_string();
+        vec![
+            self.name.clone(),
+            object_value(size),
+            self.file_type.clone(),
+            object_value(self.last_modified.as_deref().map(short_time)),
+            object_value(Some(storage_class)),
+        ]
     }
 }
 
+fn get_last_directory(path: &str) -> Option<String> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let parts: Vec<&str> = parts.into_iter().filter(|&part| !part.is_empty()).collect();
+    parts.last().map(|&last| format!("{}/", last))
+}
+
+fn get_filename(path: &str) -> Option<String> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let parts: Vec<&str> = parts.into_iter().filter(|&part| !part.is_empty()).collect();
+    parts.last().and_then(|&last| {
+        if path.ends_with('/') {
+            None
+        } else {
+            Some(last.to_string())
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,6 +171,7 @@ mod tests {
             bucket: Some("bucket".to_string()),
             region: Some("region".to_string()),
             is_bucket: true,
+            creation_date: None,
         };
         let file_info = FileInfo {
             file_name: "file_name.txt".into(),
@@ -64,6 +179,8 @@ mod tests {
             file_type: "txt".into(),
             path: "/some/path".into(),
             is_directory: false,
+            last_modified: Some("2024-05-01T12:00:00Z".into()),
+            storage_class: None,
         };
         let s3_data_item = S3DataItem::init(bucket_info.clone(), file_info.clone());
         assert_eq!(s3_data_item.bucket, bucket_info.bucket);
@@ -80,20 +197,106 @@ mod tests {
         let bucket_info = BucketInfo {
             bucket: Some("bucket".to_string()),
             region: Some("region".to_string()),
-            is_bucket: true,
+            is_bucket: false,
+            creation_date: None,
         };
         let file_info = FileInfo {
             file_name: "file_name.txt".into(),
-            size: "23 MB".into(),
+            size: "24117248".into(),
             file_type: "txt".into(),
             path: "/some/path".into(),
             is_directory: false,
+            last_modified: Some("2024-05-01T12:00:00Z".into()),
+            storage_class: None,
         };
         let s3_data_item = S3DataItem::init(bucket_info.clone(), file_info.clone());
         let res = s3_data_item.to_columns();
-        assert_eq!(res.len(), 3);
+        assert_eq!(res.len(), 5);
         assert_eq!(res[0], file_info.file_name);
-        assert_eq!(res[1], file_info.size);
+        assert_eq!(res[1], "23.0 MB");
         assert_eq!(res[2], file_info.file_type);
+        assert_eq!(res[3], "2024-05-01 12:00");
+        assert_eq!(res[4], "STANDARD");
+    }
+
+    fn listed(raw_key: &str) -> S3DataItem {
+        let bucket_info = BucketInfo {
+            bucket: Some("bucket".to_string()),
+            region: Some("region".to_string()),
+            is_bucket: false,
+            creation_date: None,
+        };
+        S3DataItem::listed(bucket_info, raw_key, "".into(), raw_key.ends_with('/'))
+    }
+
+    #[test]
+    fn listed_objects_keep_their_details_and_the_rest_shows_dashes() {
+        let object = ListedObject {
+            key: "logs/app.log".into(),
+            size: Some(512),
+            last_modified: Some("2023-11-30T08:15:42Z".into()),
+            storage_class: Some("GLACIER".into()),
+        };
+        let bucket_info = BucketInfo {
+            bucket: Some("bucket".to_string()),
+            region: None,
+            is_bucket: false,
+            creation_date: None,
+        };
+        assert_eq!(
+            S3DataItem::listed_object(bucket_info, &object).to_columns(),
+            vec!["app.log", "512 B", "log", "2023-11-30 08:15", "GLACIER"]
+        );
+        assert_eq!(
+            listed("logs/").to_columns(),
+            vec!["logs/", "-", "Dir", "-", "-"]
+        );
+        let bucket = S3DataItem::init(
+            BucketInfo {
+                bucket: None,
+                region: None,
+                is_bucket: true,
+                creation_date: Some("2021-03-04T05:06:07Z".into()),
+            },
+            FileInfo {
+                file_name: "bucket".into(),
+                size: "".into(),
+                file_type: "Bucket".into(),
+                path: "bucket".into(),
+                is_directory: false,
+                last_modified: None,
+                storage_class: None,
+            },
+        );
+        assert_eq!(
+            bucket.to_columns(),
+            vec!["bucket", "-", "Bucket", "2021-03-04 05:06", "-"]
+        );
+        let resolved = S3DataItem {
+            region: Some("eu-west-1".into()),
+            ..bucket
+        };
+        assert_eq!(resolved.to_columns()[4], "eu-west-1");
+    }
+
+    #[test]
+    fn leading_slash_keys_are_listed_like_the_others() {
+        let file = listed("/photos/2024/a.jpg");
+        assert_eq!(file.path, "photos/2024/a.jpg");
+        assert_eq!(file.name, "a.jpg");
+        assert_eq!(file.file_type, "jpg");
+        assert!(!file.is_directory);
+        assert!(file.leading_slash);
+
+        let dir = listed("/photos/");
+        assert_eq!(dir.path, "photos/");
+        assert_eq!(dir.name, "photos/");
+        assert_eq!(dir.file_type, "Dir");
+        assert!(dir.is_directory);
+        assert!(dir.leading_slash);
+
+        let plain = listed("photos/a.jpg");
+        assert_eq!(plain.path, "photos/a.jpg");
+        assert!(!plain.leading_slash);
     }
 }
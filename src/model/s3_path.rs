@@ -0,0 +1,102 @@
+//! This module provides the s3 paths typed into the go to input and the breadcrumb
+//! of the location shown by the s3 panel
+use crate::model::navigation_state::NavigationState;
+use crate::utils::{display_width, truncate_to_width};
+
+const SEPARATOR: &str = " / ";
+
+/// Location of `s3://bucket/prefix`, or of a prefix of `current_bucket` when the path
+/// has no scheme. An empty prefix is the bucket root
+pub fn parse_s3_path(input: &str, current_bucket: Option<&str>) -> Result<NavigationState, String> {
+    let input = input.trim();
+    let (bucket, prefix) = match input.strip_prefix("s3://") {
+        Some(path) => path.split_once('/').unwrap_or((path, "")),
+        None => match current_bucket {
+            Some(bucket) => (bucket, input.trim_start_matches('/')),
+            None => return Err("Open a bucket first or type s3://bucket/prefix".to_string()),
+        },
+    };
+    if bucket.is_empty() {
+        return Err("The path has no bucket, type s3://bucket/prefix".to_string());
+    }
+    let trimmed = prefix.trim_end_matches('/');
+    if !trimmed.is_empty() && trimmed.split('/').any(|dir| dir.is_empty()) {
+        return Err(format!(
+            "Invalid prefix '{}', it has an empty directory",
+            prefix
+        ));
+    }
+    let prefix = (!trimmed.is_empty()).then(|| format!("{}/", trimmed));
+    Ok(NavigationState::new(Some(bucket.to_string()), prefix))
+}
+
+/// `bucket / pre / fix` of the location, the top directories are replaced with `…`
+/// when it doesn't fit into `max_width` columns
+pub fn breadcrumb(location: &NavigationState, max_width: usize) -> String {
+    let Some(bucket) = &location.current_bucket else {
+        return truncate_to_width("Buckets", max_width);
+    };
+    let mut segments = vec![bucket.as_str()];
+    segments.extend(
+        location
+            .current_prefix
+            .iter()
+            .flat_map(|p| p.split_terminator('/')),
+    );
+    let full = segments.join(SEPARATOR);
+    if display_width(&full) <= max_width {
+        return full;
+    }
+    for start in 1..segments.len() {
+        let shortened = format!("…{}{}", SEPARATOR, segments[start..].join(SEPARATOR));
+        if display_width(&shortened) <= max_width {
+            return shortened;
+        }
+    }
+    truncate_to_width(&full, max_width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(bucket: &str, prefix: Option<&str>) -> NavigationState {
+        NavigationState::new(Some(bucket.to_string()), prefix.map(String::from))
+    }
+
+    #[test]
+    fn parses_s3_urls_and_prefixes_of_the_bucket() {
+        assert_eq!(
+            parse_s3_path("s3://logs/2024/01", None),
+            Ok(at("logs", Some("2024/01/")))
+        );
+        assert_eq!(
+            parse_s3_path("s3://logs", Some("other")),
+            Ok(at("logs", None))
+        );
+        assert_eq!(parse_s3_path(" s3://logs/ ", None), Ok(at("logs", None)));
+        assert_eq!(
+            parse_s3_path("/2024/01/", Some("logs")),
+            Ok(at("logs", Some("2024/01/")))
+        );
+        assert_eq!(parse_s3_path("", Some("logs")), Ok(at("logs", None)));
+        assert!(parse_s3_path("2024", None).is_err());
+        assert!(parse_s3_path("s3:///2024", None).is_err());
+        assert_eq!(
+            parse_s3_path("2024//01", Some("logs")),
+            Err("Invalid prefix '2024//01', it has an empty directory".to_string())
+        );
+    }
+
+    #[test]
+    fn breadcrumb_drops_the_top_directories_when_too_long() {
+        let location = at("logs", Some("2024/01/"));
+        assert_eq!(breadcrumb(&location, 80), "logs / 2024 / 01");
+        assert_eq!(breadcrumb(&location, 13), "… / 2024 / 01");
+        assert_eq!(breadcrumb(&location, 12), "… / 01");
+        assert_eq!(breadcrumb(&location, 6), "… / 01");
+        assert_eq!(breadcrumb(&location, 4), "log…");
+        assert_eq!(breadcrumb(&at("logs", None), 80), "logs");
+        assert_eq!(breadcrumb(&NavigationState::new(None, None), 80), "Buckets");
+    }
+}
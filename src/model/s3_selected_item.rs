@@ -1,5 +1,7 @@
 use crate::model::s3_data_item::S3DataItem;
-use crate::settings::file_credentials::FileCredential;
+use crate::model::verification::Verification;
+use crate::services::local_names::contained_path;
+use std::path::PathBuf;
 
 /// Represents an item (file/directory/bucket) on your s3 account
 #[derive(Debug, Clone)]
@@ -11,16 +13,27 @@ pub struct S3SelectedItem {
     pub is_bucket: bool,
     pub destination_dir: String,
     pub transferred: bool,
-    pub s3_creds: FileCredential,
+    /// Name of the credential the item was selected with, its keys are looked up when the
+    /// transfer starts so an edited credential is used without selecting the item again
+    pub account: String,
     pub progress: f64,
     pub children: Option<Vec<S3SelectedItem>>,
     pub error: Option<String>,
+    /// Path relative to `destination_dir` replacing the key, set when the download is renamed
+    /// because another transfer writes to the same local file or the key isn't a valid file
+    /// name on the local filesystem
+    pub destination_path: Option<String>,
+    /// Check of the downloaded file against the object, see `verify_downloads`
+    pub verification: Option<Verification>,
+    /// Downloaded children, counted as they finish instead of going through all of them
+    /// on every frame
+    pub completed_children: usize,
 }
 
 impl S3SelectedItem {
     pub fn from_s3_data_item(
         item: S3DataItem,
-        creds: FileCredential,
+        account: String,
         destination_dir: String,
     ) -> S3SelectedItem {
         S3SelectedItem {
@@ -31,15 +44,18 @@ impl S3SelectedItem {
             is_bucket: item.is_bucket,
             destination_dir: destination_dir.clone(),
             transferred: false,
-            s3_creds: creds,
+            account,
             progress: 0f64,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         }
     }
     pub fn from_s3_data_item_with_children(
         item: S3DataItem,
-        creds: FileCredential,
+        account: String,
         destination_dir: String,
         children: Vec<S3SelectedItem>,
     ) -> S3SelectedItem {
@@ -51,12 +67,33 @@ impl S3SelectedItem {
             is_bucket: item.is_bucket,
             destination_dir: destination_dir.clone(),
             transferred: false,
-            s3_creds: creds,
+            account,
             progress: 0f64,
             children: Some(children),
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         }
     }
+
+    /// Local file the download is written to, always under `destination_dir` whatever the key
+    pub fn local_destination(&self) -> PathBuf {
+        let mut path = PathBuf::from(&self.destination_dir);
+        path.push(contained_path(
+            &self
+                .destination_path
+                .clone()
+                .or(self.path.clone())
+                .unwrap_or(self.name.clone()),
+        ));
+        path
+    }
+
+    /// Key of the object, the name is only used by items that were never listed with their path
+    pub fn object_key(&self) -> &str {
+        self.path.as_deref().unwrap_or(&self.name)
+    }
 }
 
 impl PartialEq for S3SelectedItem {
@@ -72,6 +109,7 @@ impl PartialEq for S3SelectedItem {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::s3_data_item::BucketInfo;
 
     #[test]
     fn create_s3_selected_item_correctly() {
@@ -83,10 +121,13 @@ mod tests {
             is_bucket: false,
             destination_dir: "".to_string(),
             transferred: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             progress: 0f64,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
         let s3_data_item = S3DataItem {
             bucket: Some("test-bucket".into()),
@@ -97,16 +138,14 @@ mod tests {
             is_directory: false,
             is_bucket: false,
             region: Some("eu-west-1".into()),
-        };
-        let creds = FileCredential {
-            name: "personal".into(),
-            access_key: "abc".into(),
-            secret_key: "abc".into(),
-            default_region: "abc".into(),
-            selected: true,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
         };
         let destination_dir = "/".into();
-        let res = S3SelectedItem::from_s3_data_item(s3_data_item, creds, destination_dir);
+        let res =
+            S3SelectedItem::from_s3_data_item(s3_data_item, "personal".into(), destination_dir);
         assert_eq!(item, res);
     }
 
@@ -120,10 +159,13 @@ mod tests {
             is_bucket: false,
             destination_dir: "".to_string(),
             transferred: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             progress: 0f64,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
         let item = S3SelectedItem {
             bucket: Some("test-bucket".into()),
@@ -133,10 +175,13 @@ mod tests {
             is_bucket: false,
             destination_dir: "".to_string(),
             transferred: false,
-            s3_creds: Default::default(),
+            account: Default::default(),
             progress: 0f64,
             children: Some(vec![child.clone()]),
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
         let s3_data_item = S3DataItem {
             bucket: Some("test-bucket".into()),
@@ -147,22 +192,64 @@ mod tests {
             is_directory: false,
             is_bucket: false,
             region: Some("eu-west-1".into()),
-        };
-        let creds = FileCredential {
-            name: "personal".into(),
-            access_key: "abc".into(),
-            secret_key: "abc".into(),
-            default_region: "abc".into(),
-            selected: true,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
         };
         let destination_dir = "/".into();
         let res = S3SelectedItem::from_s3_data_item_with_children(
             s3_data_item,
-            creds,
+            "personal".into(),
             destination_dir,
             vec![child],
         );
         assert_eq!(item, res);
         assert_eq!(item.children.unwrap(), res.children.unwrap());
     }
+
+    #[test]
+    fn leading_slash_keys_download_below_the_destination() {
+        let bucket_info = BucketInfo {
+            bucket: Some("test-bucket".into()),
+            region: None,
+            is_bucket: false,
+            creation_date: None,
+        };
+        let s3_data_item =
+            S3DataItem::listed(bucket_info, "/photos/2024/a.jpg", "10".into(), false);
+        let res = S3SelectedItem::from_s3_data_item(
+            s3_data_item,
+            String::new(),
+            "/home/user/downloads".into(),
+        );
+        assert_eq!(
+            res.local_destination(),
+            PathBuf::from("/home/user/downloads/photos/2024/a.jpg")
+        );
+    }
+
+    #[test]
+    fn keys_escaping_the_destination_are_kept_under_it() {
+        let item = S3SelectedItem {
+            bucket: Some("test-bucket".into()),
+            name: "passwd".into(),
+            path: Some("/../../etc/passwd".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/home/user/downloads".into(),
+            transferred: false,
+            account: Default::default(),
+            progress: 0f64,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        assert_eq!(
+            item.local_destination(),
+            PathBuf::from("/home/user/downloads/%2E%2E/%2E%2E/etc/passwd")
+        );
+    }
 }
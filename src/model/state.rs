@@ -1,13 +1,51 @@
 //! This module provides functionality for keeping the application state
+use crate::model::activity_log::{ActivityLog, Severity};
+use crate::model::bucket_cache::{BucketCache, CachedBucketList};
+use crate::model::bulk_deletion::{BulkDeletion, DeleteProgress};
+use crate::model::destination_conflict::BlockedTransfers;
 use crate::model::download_progress_item::DownloadProgressItem;
+use crate::model::error_groups::ErrorGroups;
+use crate::model::file_comparison::FileComparison;
+use crate::model::jump_target::{FileManagerFocus, JumpTarget};
+use crate::model::large_batch::LargeBatch;
 use crate::model::local_data_item::LocalDataItem;
 use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::navigation_history::{NavigationHistory, NavigationStep};
+use crate::model::navigation_state::NavigationState;
+use crate::model::notification::Notification;
+use crate::model::object_copy::ObjectCopy;
+use crate::model::object_lock::ObjectLockInfo;
+use crate::model::object_metadata::ObjectMetadata;
+use crate::model::object_preview::ObjectPreview;
 use crate::model::s3_data_item::S3DataItem;
 use crate::model::s3_selected_item::S3SelectedItem;
+use crate::model::stats::Stats;
+use crate::model::transfer_batch::{BatchRefresh, TransferBatch};
+use crate::model::transfer_item::{TransferState, CANCELLED};
+use crate::model::transfer_overlap::OverlappingTransfers;
+use crate::model::transfer_progress::overall_progress;
+use crate::model::transfer_rate::{TransferRate, TransferRates};
+use crate::model::transfer_sort::TransferSort;
 use crate::model::upload_progress_item::UploadProgressItem;
+use crate::services::adaptive_concurrency::AccountLoad;
+use crate::services::external_command::CommandOutput;
+use crate::services::local_names::exceeds_path_limits;
+use crate::services::local_walker::{IgnoreRules, WalkOptions};
+use crate::services::selection_file::{ImportSummary, SelectionEntry, SelectionImport};
+use crate::services::selection_limit::LimitedSelection;
+use crate::services::startup_work::AccountCheck;
+use crate::services::transfer_control::TransferStop;
+use crate::services::transfer_persistence::{QueueSnapshot, RestoredQueue, ResumableUpload};
+use crate::services::transfer_queue::{download_key, upload_key, DispatchOrder, TransferJob};
+use crate::settings::app_config::AppConfig;
+use crate::settings::color_support::ColorSupport;
+use crate::settings::default_prefix::find_default_prefix;
 use crate::settings::file_credentials::FileCredential;
-use percent_encoding::percent_decode;
-use url::Url;
+use crate::settings::key_bindings::KeyBindings;
+use crate::utils::format_duration;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Instant;
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum ActivePage {
@@ -16,6 +54,7 @@ pub enum ActivePage {
     Transfers,
     S3Creds,
     Help,
+    Stats,
 }
 
 /// Represents entire state of the application, each page transforms this information for
@@ -26,6 +65,10 @@ pub struct State {
     pub local_data: Vec<LocalDataItem>,
     pub s3_data: Vec<S3DataItem>,
     pub s3_data_full_list: Vec<S3DataItem>,
+    /// Why the recursive listing of the selection stopped, nothing is selected then
+    pub selection_limit_error: Option<String>,
+    /// Objects of the recursive listing whose keys are too long for the local file system
+    pub long_download_keys: usize,
     pub s3_loading: bool,
     pub s3_list_recursive_loading: bool,
     pub s3_selected_items: Vec<S3SelectedItem>,
@@ -37,13 +80,130 @@ pub struct State {
     pub current_creds: FileCredential,
     pub local_delete_state: Option<String>,
     pub s3_delete_state: Option<String>,
+    /// Directory or bucket being emptied, `None` once all of its objects were sent
+    pub s3_delete_progress: Option<DeleteProgress>,
     pub create_bucket_state: Option<String>,
+    /// Increased with every result of a bucket creation, the input is reopened only on failures
+    pub create_bucket_result: u64,
+    pub create_folder_state: Option<String>,
+    /// Increased with every result of a folder creation, the input is reopened only on failures
+    pub create_folder_result: u64,
+    /// Why the location typed into the go to input can't be opened
+    pub go_to_path_error: Option<String>,
+    /// Increased with every checked location, the input stays open only on failures
+    pub go_to_path_result: u64,
+    /// Rename waiting for the confirmation to replace the object already at its new key
+    pub rename_conflict: Option<(S3SelectedItem, String)>,
+    /// Local rename waiting for the confirmation to replace the file already at its new name
+    pub local_rename_conflict: Option<(LocalDataItem, String)>,
+    /// Increased with every result of a rename, the confirmation is opened only on conflicts
+    pub rename_result: u64,
+    /// Increased with every result of a deletion so its error is shown only once
+    pub delete_result: u64,
+    pub transfers_total: usize,
+    pub transfers_done: usize,
+    pub app_config: AppConfig,
+    /// Keys of the actions, the defaults unless replaced in the key bindings file
+    pub key_bindings: KeyBindings,
+    /// Colors the terminal can display, detected at startup unless set in the config
+    pub color_support: ColorSupport,
+    pub stats: Stats,
+    /// Id of the newest s3 listing request, results of older ones are ignored.
+    /// 0 once it's cancelled
+    pub s3_listing_request: u64,
+    /// Request whose batches the s3 panel shows while it's still listed
+    pub streamed_s3_listing: Option<u64>,
+    /// The rows of the s3 panel are only a part of the location, its listing failed or was
+    /// stopped after the first pages
+    pub s3_listing_partial: bool,
+    pub notification: Option<Notification>,
+    /// Jump from the transfers page waiting for the listing of its target
+    pub pending_jump: Option<JumpTarget>,
+    /// Location the file manager should show, set once the listing of a jump arrives
+    pub file_manager_focus: Option<FileManagerFocus>,
+    /// Back/forward history of the s3 panel by account name
+    pub s3_navigation: HashMap<String, NavigationHistory>,
+    /// Row to highlight once the listing of a back/forward step arrives
+    pub pending_s3_row: Option<usize>,
+    /// Bucket root and the configured prefix opened instead of it, until its listing arrives
+    pub s3_landing: Option<(NavigationState, NavigationState)>,
+    /// Row the s3 panel highlights, with an increasing id so it's applied only once
+    pub s3_row_restore: Option<(u64, usize)>,
+    /// Start order of the transfers in the latest batch, by transfer key
+    pub transfer_dispatch: HashMap<String, DispatchOrder>,
+    /// Transfers of the running batch and the locations they changed
+    pub transfer_batch: TransferBatch,
+    /// Transfers paused with 'p' by transfer key, runs skip them until they are resumed
+    pub paused_transfers: HashSet<String>,
+    /// Speed of the running transfers measured from their progress
+    pub transfer_rates: TransferRates,
+    /// Metadata shown in the metadata popup of the file manager
+    pub object_metadata: Option<ObjectMetadata>,
+    pub object_metadata_loading: bool,
+    pub object_metadata_error: Option<String>,
+    /// Lock status of the last object opened in the delete confirmation, with its bucket and key
+    pub delete_lock_info: Option<(String, String, ObjectLockInfo)>,
+    /// Result of comparing an s3 object with a local file, shown in the file manager
+    pub object_comparison: Option<FileComparison>,
+    pub object_comparison_loading: bool,
+    pub object_comparison_error: Option<String>,
+    /// Beginning of the object shown in the preview popup of the file manager
+    pub object_preview: Option<ObjectPreview>,
+    pub object_preview_loading: bool,
+    pub object_preview_error: Option<String>,
+    /// Location and text of the object read for the clipboard, until it's copied
+    pub copied_object_text: Option<(String, String)>,
+    /// Increased with every object read for the clipboard, the file manager copies it once
+    pub copied_object_result: u64,
+    /// The profile of the account couldn't give credentials, shown in a popup
+    pub credentials_error: Option<String>,
+    /// Transfers of the previous session added to the selection, the file manager asks
+    /// whether to resume or discard them while they're set
+    pub restored_transfers: Option<QueueSnapshot>,
+    /// Restored transfers of accounts missing from the credentials, the file manager asks
+    /// for the account to run them with before they join the selection
+    pub orphaned_transfers: Option<RestoredQueue>,
+    /// Multipart uploads interrupted by an earlier session which s3 still holds, the file
+    /// manager asks whether to resume or abort them while there are any
+    pub resumable_uploads: Vec<ResumableUpload>,
+    /// Run stopped because some of its transfers write to the same destination
+    pub blocked_transfers: Option<BlockedTransfers>,
+    /// Run stopped because a download and an upload of it touch the same object or local file
+    pub overlapping_transfers: Option<OverlappingTransfers>,
+    /// Run stopped until the user confirms its number of transfers
+    pub large_batch: Option<LargeBatch>,
+    /// Failures of the finished batch grouped by their cause, shown when several transfers failed
+    pub failure_summary: Option<ErrorGroups>,
+    /// Normalized error whose transfers are the only ones listed on the transfers page
+    pub transfer_error_filter: Option<String>,
+    /// Order of the rows on the transfers page
+    pub transfer_sort: TransferSort,
+    /// Requests to s3 running at the last check of the state store
+    pub in_flight_requests: usize,
+    /// Bucket lists of the accounts, shown again when switching back to an account
+    pub bucket_cache: BucketCache,
+    /// When the displayed bucket list was listed, set when it was taken from `bucket_cache`
+    pub cached_buckets_listed_at: Option<Instant>,
+    /// Downloads of the next runs write a manifest into their destination directory,
+    /// see `manifest`
+    pub write_manifest: bool,
+    /// Transfer limits of the accounts which ran transfers, by account name
+    pub account_loads: HashMap<String, AccountLoad>,
+    /// Results of the checks of the accounts run at startup, by account name
+    pub account_checks: HashMap<String, AccountCheck>,
+    /// Accounts whose `insecure_skip_tls_verify` was confirmed in this session
+    pub skip_tls_verify_confirmed: HashSet<String>,
+    /// Recent events shown in the activity log pane
+    pub activity_log: ActivityLog,
+    pub show_activity_log: bool,
+    /// Dotfiles and hidden entries are listed on the local panel, kept while navigating
+    pub show_hidden_files: bool,
 }
 
 impl State {
     pub fn new(creds: Vec<FileCredential>) -> State {
         let st = State::default();
-        if let Some(current_creds) = creds.iter().find(|cred| cred.selected) {
+        let mut state = if let Some(current_creds) = creds.iter().find(|cred| cred.selected) {
             State {
                 creds: creds.clone(),
                 current_creds: current_creds.to_owned(),
@@ -54,817 +214,5123 @@ impl State {
                 creds: creds.clone(),
                 ..st
             }
+        };
+        if state.current_creds.insecure_skip_tls_verify {
+            state.notify(format!(
+                "Account {} verifies the certificates until insecure_skip_tls_verify is \
+                 confirmed, select it on the accounts page",
+                state.current_creds.name
+            ));
         }
+        state
     }
     pub fn set_active_page(&mut self, page: ActivePage) {
         self.active_page = page;
     }
 
     pub fn update_selected_s3_transfers(&mut self, item: S3SelectedItem) {
+        // objects of the same name are downloaded from different folders
+        let is_finished =
+            |it: &S3SelectedItem| it.bucket == item.bucket && it.object_key() == item.object_key();
         for it in self.s3_selected_items.iter_mut() {
-            if it.name == item.name && item.error.is_none() {
+            if is_finished(it) && item.error.is_none() {
                 it.transferred = true;
                 it.progress = 100f64;
-            } else if it.name == item.name && item.error.is_some() {
+                it.verification = item.verification;
+            } else if is_finished(it) && item.error.is_some() {
                 it.transferred = false;
                 it.progress = 0f64;
                 it.error.clone_from(&item.error);
+                it.verification = item.verification;
             }
             if let Some(children) = it.children.as_mut() {
-                let mut all_transferred = true;
                 for itc in children.iter_mut() {
-                    if (itc.name == item.name && item.error.is_none()) || itc.transferred {
+                    if is_finished(itc) {
+                        itc.verification = item.verification;
+                    }
+                    if (is_finished(itc) && item.error.is_none()) || itc.transferred {
+                        if !itc.transferred {
+                            it.completed_children += 1;
+                        }
                         itc.transferred = true;
                         itc.progress = 100f64;
-                    } else if itc.name == item.name && item.error.is_some() {
+                    } else if is_finished(itc) && item.error.is_some() {
                         itc.transferred = false;
                         itc.progress = 0f64;
                         itc.error.clone_from(&item.error);
-                        all_transferred = false
                     }
                 }
-                it.transferred = all_transferred;
+                it.transferred = it.completed_children == children.len();
+                if children.is_empty() {
+                    it.progress = 100f64;
+                }
             }
         }
+        self.refresh_transfers_summary();
     }
 
     pub fn update_selected_local_transfers(&mut self, item: LocalSelectedItem) {
+        // files of the same name are uploaded to different keys
+        let key = item.destination_key();
+        let is_finished = |it: &LocalSelectedItem| {
+            it.destination_bucket == item.destination_bucket && it.destination_key() == key
+        };
         for it in self.local_selected_items.iter_mut() {
-            if it.name == item.name && item.error.is_none() {
+            if is_finished(it) && item.error.is_none() {
                 it.transferred = true;
                 it.progress = 100f64;
-            } else if it.name == item.name && item.error.is_some() {
+                it.verification = item.verification;
+            } else if is_finished(it) && item.error.is_some() {
                 it.transferred = false;
                 it.progress = 0f64;
                 it.error.clone_from(&item.error);
+                it.verification = item.verification;
             }
             if let Some(children) = it.children.as_mut() {
-                let mut all_transferred = true;
                 for itc in children.iter_mut() {
-                    if (itc.name == item.name && item.error.is_none()) || itc.transferred {
+                    if is_finished(itc) {
+                        itc.verification = item.verification;
+                    }
+                    if (is_finished(itc) && item.error.is_none()) || itc.transferred {
+                        if !itc.transferred {
+                            it.completed_children += 1;
+                        }
                         itc.transferred = true;
                         itc.progress = 100f64;
-                    } else if itc.name == item.name && item.error.is_some() {
+                    } else if is_finished(itc) && item.error.is_some() {
                         itc.transferred = false;
                         itc.progress = 0f64;
                         itc.error.clone_from(&item.error);
-                        all_transferred = false;
                     }
                 }
-                it.transferred = all_transferred;
+                it.transferred = it.completed_children == children.len();
             }
         }
+        self.refresh_transfers_summary();
     }
 
     pub fn remove_already_transferred_items(&mut self) {
         self.s3_selected_items.retain(|it| !it.transferred);
         self.local_selected_items.retain(|it| !it.transferred);
+        self.refresh_transfers_summary();
     }
 
-    pub fn update_buckets(
-        &mut self,
-        bucket: Option<String>,
-        prefix: Option<String>,
-        bucket_list: Vec<S3DataItem>,
-    ) {
-        self.s3_data = bucket_list;
-        self.s3_loading = false;
-        self.current_s3_bucket = bucket;
-        self.current_s3_path = prefix;
+    /// Checks if any of the selected items has started but not finished transferring
+    pub fn has_transfers_in_progress(&self) -> bool {
+        self.s3_selected_items
+            .iter()
+            .any(|i| i.progress > 0.0 && !i.transferred && i.error.is_none())
+            || self
+                .local_selected_items
+                .iter()
+                .any(|i| i.progress > 0.0 && !i.transferred && i.error.is_none())
     }
 
-    pub fn update_s3_recursive_list(&mut self, bucket_list: Vec<S3DataItem>) {
-        self.s3_data_full_list = bucket_list;
-        self.s3_list_recursive_loading = false;
+    /// Forgets the start order of the previous batch
+    pub fn start_dispatch_batch(&mut self) {
+        self.transfer_dispatch.clear();
     }
 
-    pub fn update_files(&mut self, path: String, files: Vec<LocalDataItem>) {
-        self.local_data = files;
-        self.current_local_path = path;
+    /// A new batch lists all the transfers again
+    pub fn start_transfer_batch(&mut self, jobs: &[TransferJob]) {
+        self.transfer_batch.start(jobs);
+        self.transfer_error_filter = None;
+        self.log_activity(
+            Severity::Info,
+            format!("Started {} transfer(s)", jobs.len()),
+        );
     }
 
-    pub fn set_local_delete_error(&mut self, error_str: Option<String>) {
-        self.local_delete_state = error_str;
+    /// Applies the cancellation of all the network activity: the started transfers are marked
+    /// as cancelled, the ones still queued stay selected and can be run again
+    pub fn stop_network_activity(&mut self, cancelled_tasks: usize) {
+        let dispatched = &self.transfer_dispatch;
+        let mut cancelled_transfers = 0;
+        for item in self.s3_selected_items.iter_mut() {
+            let leaves: Vec<&mut S3SelectedItem> = match item.children.as_mut() {
+                Some(children) => children.iter_mut().collect(),
+                None => vec![item],
+            };
+            for leaf in leaves {
+                if !leaf.transferred
+                    && leaf.error.is_none()
+                    && dispatched.contains_key(&download_key(leaf))
+                {
+                    leaf.error = Some(CANCELLED.into());
+                    leaf.progress = 0f64;
+                    cancelled_transfers += 1;
+                }
+            }
+        }
+        for item in self.local_selected_items.iter_mut() {
+            let leaves: Vec<&mut LocalSelectedItem> = match item.children.as_mut() {
+                Some(children) => children.iter_mut().collect(),
+                None => vec![item],
+            };
+            for leaf in leaves {
+                if !leaf.transferred
+                    && leaf.error.is_none()
+                    && dispatched.contains_key(&upload_key(leaf))
+                {
+                    leaf.error = Some(CANCELLED.into());
+                    leaf.progress = 0f64;
+                    cancelled_transfers += 1;
+                }
+            }
+        }
+        self.transfer_batch = TransferBatch::default();
+        self.transfer_rates.clear();
+        self.blocked_transfers = None;
+        self.overlapping_transfers = None;
+        self.s3_loading = false;
+        self.s3_list_recursive_loading = false;
+        self.object_metadata_loading = false;
+        self.object_comparison_loading = false;
+        self.object_preview_loading = false;
+        let message = format!(
+            "Network activity stopped: {} task(s) and {} transfer(s) cancelled",
+            cancelled_tasks, cancelled_transfers
+        );
+        self.log_activity(Severity::Warning, message.clone());
+        self.notify(message);
     }
 
-    pub fn set_s3_delete_error(&mut self, error_str: Option<String>) {
-        self.s3_delete_state = error_str;
+    /// Pauses or cancels the unfinished transfers with the given keys. Returns the keys of
+    /// the ones of the running batch, they have to be stopped where they run
+    pub fn stop_transfers(&mut self, keys: &[String], stop: TransferStop) -> Vec<String> {
+        let mut leaves: Vec<(String, bool, &mut Option<String>, &mut f64)> = Vec::new();
+        for item in self.s3_selected_items.iter_mut() {
+            match item.children.as_mut() {
+                Some(children) => leaves.extend(children.iter_mut().map(|c| {
+                    (
+                        download_key(c),
+                        c.transferred,
+                        &mut c.error,
+                        &mut c.progress,
+                    )
+                })),
+                None => leaves.push((
+                    download_key(item),
+                    item.transferred,
+                    &mut item.error,
+                    &mut item.progress,
+                )),
+            }
+        }
+        for item in self.local_selected_items.iter_mut() {
+            match item.children.as_mut() {
+                Some(children) => leaves.extend(
+                    children
+                        .iter_mut()
+                        .map(|c| (upload_key(c), c.transferred, &mut c.error, &mut c.progress)),
+                ),
+                None => leaves.push((
+                    upload_key(item),
+                    item.transferred,
+                    &mut item.error,
+                    &mut item.progress,
+                )),
+            }
+        }
+        let mut running = Vec::new();
+        let mut stopped = 0;
+        for (key, transferred, error, progress) in leaves {
+            if transferred || error.is_some() || !keys.contains(&key) {
+                continue;
+            }
+            if self.transfer_batch.is_pending(&key) {
+                running.push(key.clone());
+            }
+            match stop {
+                TransferStop::Paused => {
+                    self.paused_transfers.insert(key);
+                }
+                TransferStop::Cancelled => {
+                    self.paused_transfers.remove(&key);
+                    *error = Some(CANCELLED.into());
+                    *progress = 0f64;
+                }
+            }
+            stopped += 1;
+        }
+        if stopped > 0 {
+            let verb = match stop {
+                TransferStop::Paused => "Paused",
+                TransferStop::Cancelled => "Cancelled",
+            };
+            self.log_activity(Severity::Info, format!("{} {} transfer(s)", verb, stopped));
+            self.refresh_transfers_summary();
+        }
+        running
     }
 
-    pub fn set_create_bucket_error(&mut self, error_str: Option<String>) {
-        self.create_bucket_state = error_str;
+    /// Records the transfer which stopped after a pause or a cancellation, after the last
+    /// transfer of the batch the displayed listings which changed are returned
+    pub fn finish_stopped_transfer(&mut self, key: &str) -> BatchRefresh {
+        self.transfer_rates.finish(key);
+        if self.transfer_batch.stop(key) {
+            self.finish_batch()
+        } else {
+            BatchRefresh::default()
+        }
     }
 
-    pub fn set_current_local_path(&mut self, path: String) {
-        self.current_local_path = path;
+    /// Resumes the paused transfers with the given keys. Returns the jobs to run again,
+    /// the ones still waiting for their turn in the running batch run when it comes
+    pub fn resume_transfers(&mut self, keys: &[String]) -> Vec<TransferJob> {
+        let resumed: Vec<&String> = keys
+            .iter()
+            .filter(|key| self.paused_transfers.remove(*key))
+            .collect();
+        if !resumed.is_empty() {
+            self.log_activity(
+                Severity::Info,
+                format!("Resumed {} transfer(s)", resumed.len()),
+            );
+        }
+        let waiting = |key: &String| {
+            self.transfer_batch.is_pending(key) && !self.transfer_dispatch.contains_key(key)
+        };
+        TransferJob::queue(
+            self.s3_selected_items.clone(),
+            self.local_selected_items.clone(),
+        )
+        .into_iter()
+        .filter(|job| {
+            let key = job.key();
+            resumed.contains(&&key) && !waiting(&key)
+        })
+        .collect()
     }
 
-    pub fn set_s3_loading(&mut self, loading: bool) {
-        self.s3_loading = loading;
+    /// Jobs of a run of all the transfers, the paused ones stay paused
+    pub fn without_paused(&self, jobs: Vec<TransferJob>) -> Vec<TransferJob> {
+        jobs.into_iter()
+            .filter(|job| !self.paused_transfers.contains(&job.key()))
+            .collect()
     }
 
-    pub fn set_s3_list_recursive_loading(&mut self, loading: bool) {
-        self.s3_list_recursive_loading = loading;
+    /// Transfers run explicitly aren't paused anymore
+    pub fn unpause(&mut self, jobs: &[TransferJob]) {
+        for job in jobs {
+            self.paused_transfers.remove(&job.key());
+        }
     }
 
-    pub fn add_s3_selected_item(&mut self, item: S3SelectedItem) {
-        self.s3_selected_items.push(item.clone());
+    /// Marks the transfer failed without starting it, its credential is no longer configured
+    pub fn fail_missing_credentials(&mut self, job: TransferJob) {
+        self.log_activity(
+            Severity::Error,
+            format!(
+                "Transfer not started, credential '{}' is no longer configured",
+                job.account()
+            ),
+        );
+        match job {
+            TransferJob::Download(item) => {
+                let error = format!("credential '{}' is no longer configured", item.account);
+                self.update_selected_s3_transfers(S3SelectedItem {
+                    error: Some(error),
+                    ..item
+                });
+            }
+            TransferJob::Upload(item) => {
+                let error = format!("credential '{}' is no longer configured", item.account);
+                self.update_selected_local_transfers(LocalSelectedItem {
+                    error: Some(error),
+                    ..item
+                });
+            }
+        }
     }
 
-    pub fn add_local_selected_item(&mut self, it: LocalSelectedItem) {
-        if it.is_directory {
-            let items = LocalSelectedItem::list_directory_items(&it);
-            let item = LocalSelectedItem {
-                children: Some(items),
-                ..it
-            };
-            self.local_selected_items.push(item);
+    /// Records the finished download, after the last transfer of the batch
+    /// the displayed listings which changed are returned
+    pub fn finish_download(&mut self, item: &S3SelectedItem) -> BatchRefresh {
+        let source = format!(
+            "s3://{}/{}",
+            item.bucket.clone().unwrap_or_default(),
+            item.path.clone().unwrap_or(item.name.clone())
+        );
+        match &item.error {
+            Some(error) => self.log_activity(
+                Severity::Error,
+                format!("Download of {} failed: {}", source, error),
+            ),
+            None => self.log_activity(
+                Severity::Info,
+                format!(
+                    "Downloaded {} to {}",
+                    source,
+                    item.local_destination().display()
+                ),
+            ),
+        }
+        self.transfer_rates.finish(&download_key(item));
+        if self.transfer_batch.finish_download(item) {
+            self.finish_batch()
         } else {
-            self.local_selected_items.push(it);
+            BatchRefresh::default()
         }
     }
 
-    pub fn remove_s3_selected_item(&mut self, item: S3SelectedItem) {
-        self.s3_selected_items
-            .retain(|it| it.bucket != item.bucket || it.name != item.name || it.path != item.path);
+    /// Records the finished upload, after the last transfer of the batch
+    /// the displayed listings which changed are returned
+    pub fn finish_upload(&mut self, item: &LocalSelectedItem) -> BatchRefresh {
+        let destination = format!(
+            "s3://{}/{}",
+            item.destination_bucket,
+            item.destination_key()
+        );
+        match &item.error {
+            Some(error) => self.log_activity(
+                Severity::Error,
+                format!("Upload of {} failed: {}", item.path, error),
+            ),
+            None => self.log_activity(
+                Severity::Info,
+                format!("Uploaded {} to {}", item.path, destination),
+            ),
+        }
+        self.transfer_rates.finish(&upload_key(item));
+        if self.transfer_batch.finish_upload(item) {
+            self.finish_batch()
+        } else {
+            BatchRefresh::default()
+        }
     }
 
-    pub fn remove_local_selected_item(&mut self, item: LocalSelectedItem) {
-        self.local_selected_items
-            .retain(|it| it.name != item.name || it.path != item.path);
+    /// Summarizes the failures of the finished batch, a single failure is reported by the
+    /// transfer itself
+    fn finish_batch(&mut self) -> BatchRefresh {
+        let failures = self.transfer_batch.failures();
+        if failures.failed() >= 2 {
+            let summary = failures.clone();
+            self.log_activity(
+                Severity::Warning,
+                format!(
+                    "{} transfer(s) of the batch failed: {}",
+                    summary.failed(),
+                    summary.summary().join("; ")
+                ),
+            );
+            self.failure_summary = Some(summary);
+        }
+        self.batch_refresh()
     }
 
-    pub fn set_current_s3_creds(&mut self, item: FileCredential) {
-        for cred in self.creds.iter_mut() {
-            if cred.name == item.name {
-                cred.selected = true;
-                self.current_creds = cred.clone();
-            } else {
-                cred.selected = false;
+    fn batch_refresh(&self) -> BatchRefresh {
+        self.transfer_batch.refresh_for(
+            &self.current_creds.name,
+            self.current_s3_bucket.as_deref(),
+            self.current_s3_path.as_deref(),
+            &self.current_local_path,
+        )
+    }
+
+    pub fn record_dispatch(&mut self, key: String, order: DispatchOrder) {
+        self.transfer_dispatch.insert(key, order);
+    }
+
+    /// Start order of the download, for directories the order of their first started file
+    pub fn s3_item_dispatch_order(&self, item: &S3SelectedItem) -> Option<DispatchOrder> {
+        match &item.children {
+            Some(children) => children
+                .iter()
+                .filter_map(|c| self.transfer_dispatch.get(&download_key(c)))
+                .min_by_key(|o| o.sequence)
+                .copied(),
+            None => self.transfer_dispatch.get(&download_key(item)).copied(),
+        }
+    }
+
+    /// Start order of the upload, for directories the order of their first started file
+    pub fn local_item_dispatch_order(&self, item: &LocalSelectedItem) -> Option<DispatchOrder> {
+        match &item.children {
+            Some(children) => children
+                .iter()
+                .filter_map(|c| self.transfer_dispatch.get(&upload_key(c)))
+                .min_by_key(|o| o.sequence)
+                .copied(),
+            None => self.transfer_dispatch.get(&upload_key(item)).copied(),
+        }
+    }
+
+    /// Whether the download or one of the files of the directory is paused
+    pub fn s3_item_paused(&self, item: &S3SelectedItem) -> bool {
+        match &item.children {
+            Some(children) => children
+                .iter()
+                .any(|c| self.paused_transfers.contains(&download_key(c))),
+            None => self.paused_transfers.contains(&download_key(item)),
+        }
+    }
+
+    /// Whether the upload or one of the files of the directory is paused
+    pub fn local_item_paused(&self, item: &LocalSelectedItem) -> bool {
+        match &item.children {
+            Some(children) => children
+                .iter()
+                .any(|c| self.paused_transfers.contains(&upload_key(c))),
+            None => self.paused_transfers.contains(&upload_key(item)),
+        }
+    }
+
+    /// Transfers of the running batch which got a slot and haven't finished yet
+    pub fn busy_transfer_slots(&self) -> usize {
+        self.transfer_batch
+            .count_pending(|key| self.transfer_dispatch.contains_key(key))
+    }
+
+    fn transfer_state(&self, key: &str, progress: f64, busy: usize) -> TransferState {
+        if !self.transfer_batch.is_pending(key) {
+            TransferState::Queued
+        } else if self.transfer_dispatch.contains_key(key) || progress > 0f64 {
+            TransferState::Active
+        } else {
+            TransferState::Waiting {
+                busy,
+                slots: self.app_config.transfer_concurrency,
             }
         }
     }
 
-    /*
-    The url can look smth like this:
-    "https://maluchyplywaja.s3.eu-west-1.amazonaws.com/IMG_8123.HEIC?x-id=PutObject"
-     */
-    fn update_local_item_with_progress(
-        &mut self,
-        selected_items: Vec<LocalSelectedItem>,
-        progress_item: UploadProgressItem,
-    ) {
-        let url = match Url::parse(progress_item.uri.as_str()) {
-            Ok(url) => url,
-            Err(_) => return, // Exit the function if URL parsing fails
-        };
-        let host = url.host_str().unwrap_or_default();
-        let path_segments = url
-            .path_segments()
-            .map(|c| c.collect::<Vec<_>>())
-            .unwrap_or_default();
-        let name = path_segments.last().unwrap_or(&"");
+    /// State of the download, directories take the most advanced one of their files.
+    /// `busy` is the number of slots taken, see `busy_transfer_slots`
+    pub fn s3_item_transfer_state(&self, item: &S3SelectedItem, busy: usize) -> TransferState {
+        match &item.children {
+            Some(children) => most_advanced(
+                children
+                    .iter()
+                    .map(|c| self.transfer_state(&download_key(c), c.progress, busy)),
+            ),
+            None => self.transfer_state(&download_key(item), item.progress, busy),
+        }
+    }
 
-        // Assume bucket name is the first segment of the host
-        let bucket_parts = host.split('.').collect::<Vec<_>>();
-        let bucket_name = bucket_parts.first().unwrap_or(&"");
-        let mut mutated_items: Vec<LocalSelectedItem> = Vec::new();
-        for item in selected_items.clone().iter_mut() {
-            if item.children.is_none() {
-                let encoded_name = percent_decode(name.as_bytes())
-                    .decode_utf8() // This returns a `Result<Cow<str>, Utf8Error>`
-                    .unwrap_or_else(|e| panic!("Decoding error: {}", e)) // Handle the error case
-                    .to_string(); // Convert `Cow<str>` to `String`
-                let name = String::from(&item.name);
-                if item.destination_bucket == *bucket_name && name == encoded_name {
-                    item.progress = progress_item.progress;
-                    mutated_items.push(item.clone());
-                } else {
-                    mutated_items.push(item.clone());
+    /// State of the upload, directories take the most advanced one of their files.
+    /// `busy` is the number of slots taken, see `busy_transfer_slots`
+    pub fn local_item_transfer_state(
+        &self,
+        item: &LocalSelectedItem,
+        busy: usize,
+    ) -> TransferState {
+        match &item.children {
+            Some(children) => most_advanced(
+                children
+                    .iter()
+                    .map(|c| self.transfer_state(&upload_key(c), c.progress, busy)),
+            ),
+            None => self.transfer_state(&upload_key(item), item.progress, busy),
+        }
+    }
+
+    /// Recomputes the transfer counters displayed on the status lines.
+    /// Called whenever the selected items change so the pages don't have to flatten
+    /// all the children on every frame
+    pub fn refresh_transfers_summary(&mut self) {
+        let mut total = 0;
+        let mut done = 0;
+        for item in self.s3_selected_items.iter() {
+            match &item.children {
+                Some(children) => {
+                    total += children.len();
+                    done += item.completed_children;
                 }
-            } else {
-                let mut mutated_children: Vec<LocalSelectedItem> = Vec::new();
-                if let Some(mut children) = item.clone().children {
-                    for child in children.iter_mut() {
-                        let encoded_name = percent_decode(name.as_bytes())
-                            .decode_utf8() // This returns a `Result<Cow<str>, Utf8Error>`
-                            .unwrap_or_else(|e| panic!("Decoding error: {}", e)) // Handle the error case
-                            .to_string();
-                        let name = String::from(&child.name);
-                        if child.destination_bucket == *bucket_name && name == encoded_name {
-                            child.progress = progress_item.progress;
-                            mutated_children.push(child.clone());
-                        } else {
-                            mutated_children.push(child.clone());
-                        }
-                    }
-                    item.children = Some(mutated_children.clone());
-                    item.progress = Self::calculate_overall_progress_local(mutated_children);
-                    item.transferred = item.progress == 100f64;
-                    mutated_items.push(item.clone());
+                None => {
+                    total += 1;
+                    done += usize::from(item.transferred);
                 }
             }
         }
-        self.local_selected_items = mutated_items;
+        for item in self.local_selected_items.iter() {
+            match &item.children {
+                Some(children) => {
+                    total += children.len();
+                    done += item.completed_children;
+                }
+                None => {
+                    total += 1;
+                    done += usize::from(item.transferred);
+                }
+            }
+        }
+        self.transfers_total = total;
+        self.transfers_done = done;
     }
 
-    fn update_s3_item_with_progress(
+    pub fn update_buckets(
         &mut self,
-        selected_items: Vec<S3SelectedItem>,
-        progress_item: DownloadProgressItem,
+        bucket: Option<String>,
+        prefix: Option<String>,
+        bucket_list: Vec<S3DataItem>,
     ) {
-        let mut mutated_items: Vec<S3SelectedItem> = Vec::new();
-        for item in selected_items.clone().iter_mut() {
-            if item.children.is_none() {
-                if item.name == progress_item.name
-                    && item.bucket == Some(progress_item.bucket.clone())
-                {
-                    item.progress = progress_item.progress;
-                    mutated_items.push(item.clone());
-                } else {
-                    mutated_items.push(item.clone());
-                }
-            } else {
-                let mut mutated_children: Vec<S3SelectedItem> = Vec::new();
-                if let Some(mut children) = item.clone().children {
-                    for child in children.iter_mut() {
-                        if child.name == progress_item.name
-                            && child.bucket == Some(progress_item.bucket.clone())
-                        {
-                            child.progress = progress_item.progress;
-                            mutated_children.push(child.clone());
-                        } else {
-                            mutated_children.push(child.clone());
-                        }
-                    }
-                    item.children = Some(mutated_children.clone());
-                    item.progress = Self::calculate_overall_progress_s3(mutated_children);
-                    item.transferred = item.progress == 100f64;
-                    mutated_items.push(item.clone());
-                }
+        self.stats.record_listing(bucket_list.len());
+        self.s3_data = bucket_list;
+        self.s3_listing_partial = false;
+        self.s3_loading = false;
+        self.current_s3_bucket = bucket;
+        self.current_s3_path = prefix;
+        self.cached_buckets_listed_at = None;
+    }
+
+    /// Shows the bucket list kept for the current account, returns false when it's missing
+    /// or older than `bucket_cache_ttl` and has to be listed. A recent failure is shown
+    /// again instead of retrying
+    pub fn show_cached_buckets(&mut self, now: Instant) -> bool {
+        let cached = self.bucket_cache.lookup(
+            &self.current_creds.name,
+            now,
+            self.app_config.bucket_cache_ttl,
+        );
+        match cached {
+            Some(CachedBucketList::Listed { buckets, age }) => {
+                self.s3_data = buckets;
+                self.s3_listing_partial = false;
+                self.cached_buckets_listed_at = now.checked_sub(age);
+            }
+            Some(CachedBucketList::Failed { error, age }) => {
+                self.s3_data = Vec::new();
+                self.s3_listing_partial = false;
+                self.cached_buckets_listed_at = None;
+                self.notify(format!(
+                    "Listing the buckets failed {} ago: {} - press F5 to retry",
+                    format_duration(age),
+                    error
+                ));
             }
+            None => return false,
         }
-        self.s3_selected_items = mutated_items;
+        self.s3_loading = false;
+        self.current_s3_bucket = None;
+        self.current_s3_path = None;
+        true
     }
 
-    fn calculate_overall_progress_s3(items: Vec<S3SelectedItem>) -> f64 {
-        let all_progress: f64 = items
-            .clone()
-            .into_iter()
-            .map(|i| i.progress)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .sum();
-        if all_progress > 0.0 {
-            all_progress / items.len() as f64
-        } else {
-            0.0
+    /// Forgets the bucket list kept for the current account, e.g. after creating a bucket
+    pub fn invalidate_bucket_list(&mut self) {
+        self.bucket_cache.invalidate(&self.current_creds.name);
+    }
+
+    /// Buckets of the displayed bucket list whose region isn't known yet
+    pub fn unresolved_bucket_regions(&self) -> Vec<String> {
+        if self.current_s3_bucket.is_some() {
+            return Vec::new();
         }
+        self.s3_data
+            .iter()
+            .filter(|row| row.is_bucket && row.region.is_none())
+            .map(|row| row.name.clone())
+            .collect()
     }
-    fn calculate_overall_progress_local(items: Vec<LocalSelectedItem>) -> f64 {
-        let all_progress: f64 = items
-            .clone()
-            .into_iter()
-            .map(|i| i.progress)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .sum();
-        if all_progress > 0.0 {
-            all_progress / items.len() as f64
-        } else {
-            0.0
+
+    /// Shows the resolved region on the row of the bucket and keeps it with the bucket list
+    /// of the account
+    pub fn set_bucket_region(&mut self, account: &str, bucket: &str, region: String) {
+        self.bucket_cache.set_region(account, bucket, &region);
+        if account != self.current_creds.name || self.current_s3_bucket.is_some() {
+            return;
+        }
+        for row in self.s3_data.iter_mut() {
+            if row.is_bucket && row.name == bucket {
+                row.region = Some(region.clone());
+            }
         }
     }
 
-    pub fn update_progress_on_selected_local_item(&mut self, item: UploadProgressItem) {
-        self.update_local_item_with_progress(self.local_selected_items.clone(), item.clone());
+    /// Bucket listing of an account whose profile couldn't give credentials, returns whether
+    /// it answers the newest request
+    pub fn fail_credentials(&mut self, request_id: u64, error: String) -> bool {
+        if request_id != self.s3_listing_request {
+            return false;
+        }
+        self.s3_loading = false;
+        self.pending_jump = None;
+        let message = format!("Cannot use account {}: {}", self.current_creds.name, error);
+        self.log_activity(Severity::Error, message.clone());
+        self.credentials_error = Some(message);
+        true
     }
 
-    pub fn update_progress_on_selected_s3_item(&mut self, item: DownloadProgressItem) {
-        self.update_s3_item_with_progress(self.s3_selected_items.clone(), item.clone());
+    /// Records the result of the startup check of an account, failures are logged
+    pub fn finish_account_check(&mut self, account: String, check: AccountCheck) {
+        if let AccountCheck::Failed(e) = &check {
+            let message = format!("Account {} doesn't respond: {}", account, e);
+            self.log_activity(Severity::Warning, message);
+        }
+        self.account_checks.insert(account, check);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Records the failed listing when it answers the newest request, returns whether it did.
+    /// The rows of its first pages stay in the panel marked as partial. A failed bucket list
+    /// isn't listed again for a while on switching accounts
+    pub fn fail_s3_listing(
+        &mut self,
+        request_id: u64,
+        bucket: Option<String>,
+        prefix: Option<String>,
+        error: String,
+    ) -> bool {
+        if request_id != self.s3_listing_request {
+            return false;
+        }
+        if self.streamed_s3_listing == Some(request_id) {
+            self.s3_listing_partial = true;
+        }
+        self.s3_loading = false;
+        self.pending_jump = None;
+        let location = match (&bucket, &prefix) {
+            (Some(bucket), Some(prefix)) => format!("{}/{}", bucket, prefix),
+            (Some(bucket), None) => bucket.clone(),
+            (None, _) => {
+                self.bucket_cache.store_failure(
+                    &self.current_creds.name,
+                    error.clone(),
+                    Instant::now(),
+                );
+                "the buckets".into()
+            }
+        };
+        let message = format!("Cannot list {}: {}", location, error);
+        self.log_activity(Severity::Error, message.clone());
+        self.notify(message);
+        true
+    }
 
-    #[test]
-    fn default_active_page_is_file_manager_page() {
-        let state = State::default();
-        assert_eq!(state.active_page, ActivePage::FileManager);
+    /// Shows a batch of the newest listing while the rest is still listed, the first one with
+    /// any rows replaces the panel. Returns whether the panel changed
+    pub fn receive_s3_batch(
+        &mut self,
+        request_id: u64,
+        bucket: Option<String>,
+        prefix: Option<String>,
+        batch: Vec<S3DataItem>,
+    ) -> bool {
+        if request_id != self.s3_listing_request || batch.is_empty() {
+            return false;
+        }
+        if self.streamed_s3_listing == Some(request_id) {
+            self.s3_data.extend(batch);
+        } else {
+            self.streamed_s3_listing = Some(request_id);
+            self.s3_data = batch;
+            self.s3_listing_partial = false;
+            self.current_s3_bucket = bucket;
+            self.current_s3_path = prefix;
+            self.cached_buckets_listed_at = None;
+        }
+        true
     }
 
-    #[test]
-    fn set_active_page_changes_page_correctly() {
-        let mut state = State::default();
-        state.set_active_page(ActivePage::Help);
-        assert_eq!(state.active_page, ActivePage::Help);
+    /// Rows of the newest listing shown batch by batch, handed over to `receive_s3_listing`
+    /// once the listing completes
+    pub fn take_streamed_s3_data(&mut self, request_id: u64) -> Vec<S3DataItem> {
+        if request_id != self.s3_listing_request
+            || self.streamed_s3_listing.take() != Some(request_id)
+        {
+            return Vec::new();
+        }
+        std::mem::take(&mut self.s3_data)
     }
 
-    #[test]
-    fn new_state_with_selected_credential_sets_current_creds_correctly() {
-        let creds = vec![
-            FileCredential {
-                name: "AWS".into(),
-                access_key: "".to_string(),
-                secret_key: "".to_string(),
-                default_region: "".to_string(),
-                selected: false,
-            },
-            FileCredential {
-                name: "Azure".into(),
-                access_key: "".to_string(),
-                secret_key: "".to_string(),
-                default_region: "".to_string(),
-                selected: true,
-            },
-        ];
-        let state = State::new(creds.clone());
-        assert_eq!(state.current_creds, creds[1]);
+    /// Stops waiting for the running listing, the rows listed so far stay in the panel marked
+    /// as partial. Returns false when nothing was listed
+    pub fn cancel_s3_listing(&mut self) -> bool {
+        if !self.s3_loading {
+            return false;
+        }
+        if self.streamed_s3_listing == Some(self.s3_listing_request) {
+            self.s3_listing_partial = true;
+        }
+        self.s3_loading = false;
+        self.s3_listing_request = 0;
+        self.pending_jump = None;
+        self.pending_s3_row = None;
+        self.notify(format!(
+            "Listing stopped, {} rows listed so far",
+            self.s3_data.len()
+        ));
+        true
     }
 
-    #[test]
-    fn new_state_without_selected_credential_sets_current_creds_correctly() {
+    /// Applies the listing only when it answers the newest request, returns whether it was applied
+    pub fn update_buckets_for_request(
+        &mut self,
+        request_id: u64,
+        bucket: Option<String>,
+        prefix: Option<String>,
+        bucket_list: Vec<S3DataItem>,
+    ) -> bool {
+        if request_id != self.s3_listing_request {
+            tracing::info!("Ignoring stale s3 listing {}", request_id);
+            return false;
+        }
+        self.update_buckets(bucket, prefix, bucket_list);
+        true
+    }
+
+    /// Starts a jump to the target, returns false when the local directory or the account
+    /// doesn't exist anymore
+    pub fn begin_jump(&mut self, target: JumpTarget) -> bool {
+        match &target {
+            JumpTarget::Local { directory, .. } => {
+                if !Path::new(directory).is_dir() {
+                    self.notify(format!("{} no longer exists", target.describe()));
+                    return false;
+                }
+            }
+            JumpTarget::S3 { account, .. } => {
+                if *account != self.current_creds.name {
+                    match self.creds.iter().find(|c| c.name == *account).cloned() {
+                        Some(creds) => self.set_current_s3_creds(creds),
+                        None => {
+                            self.notify(format!(
+                                "Credential '{}' is no longer configured",
+                                account
+                            ));
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        self.pending_jump = Some(target);
+        true
+    }
+
+    /// Updates the s3 panel with the listing, completing the pending jump when it waited for it.
+    /// An empty listing of a prefix means the jump target is gone, the current listing is kept.
+    /// Keys listed with a leading slash switch the account to `leading_slash_keys`
+    pub fn receive_s3_listing(
+        &mut self,
+        request_id: u64,
+        bucket: Option<String>,
+        prefix: Option<String>,
+        bucket_list: Vec<S3DataItem>,
+    ) -> bool {
+        let jump_listing = request_id == self.s3_listing_request
+            && matches!(self.pending_jump, Some(JumpTarget::S3 { .. }));
+        if jump_listing && prefix.is_some() && bucket_list.is_empty() {
+            if let Some(target) = self.pending_jump.take() {
+                self.notify(format!("{} no longer exists", target.describe()));
+            }
+            self.s3_loading = false;
+            return true;
+        }
+        let leading_slash = bucket_list.iter().any(|item| item.leading_slash);
+        let bucket_list_shown = bucket.is_none();
+        if !self.update_buckets_for_request(request_id, bucket, prefix, bucket_list) {
+            return false;
+        }
+        if bucket_list_shown {
+            self.bucket_cache.store(
+                &self.current_creds.name,
+                self.s3_data.clone(),
+                Instant::now(),
+            );
+        }
+        if leading_slash && !self.current_creds.leading_slash_keys {
+            self.detect_leading_slash_keys();
+        }
+        if let Some(row) = self.pending_s3_row.take() {
+            let id = self.s3_row_restore.map_or(1, |(id, _)| id + 1);
+            self.s3_row_restore = Some((id, row));
+        }
+        if jump_listing {
+            self.complete_jump();
+        }
+        true
+    }
+
+    pub fn receive_local_listing(&mut self, path: String, files: Vec<LocalDataItem>) {
+        let jump_listing = matches!(
+            &self.pending_jump,
+            Some(JumpTarget::Local { directory, .. }) if *directory == path
+        );
+        self.update_files(path, files);
+        if jump_listing {
+            self.complete_jump();
+        }
+    }
+
+    fn complete_jump(&mut self) {
+        if let Some(target) = self.pending_jump.take() {
+            if let JumpTarget::S3 { bucket, prefix, .. } = &target {
+                self.current_s3_navigation().visit(
+                    NavigationState::new(Some(bucket.clone()), prefix.clone()),
+                    None,
+                );
+            }
+            let id = self.file_manager_focus.as_ref().map_or(1, |f| f.id + 1);
+            self.file_manager_focus = Some(FileManagerFocus { id, target });
+            self.active_page = ActivePage::FileManager;
+        }
+    }
+
+    pub fn start_object_metadata_request(&mut self) {
+        self.object_metadata_loading = true;
+        self.object_metadata_error = None;
+    }
+
+    /// Shows the fetched metadata, on error the previously shown metadata stays in place
+    pub fn set_object_metadata(&mut self, result: Result<ObjectMetadata, String>) {
+        self.object_metadata_loading = false;
+        match result {
+            Ok(metadata) => {
+                self.object_metadata = Some(metadata);
+                self.object_metadata_error = None;
+            }
+            Err(e) => self.object_metadata_error = Some(e),
+        }
+    }
+
+    pub fn clear_object_metadata(&mut self) {
+        self.object_metadata = None;
+        self.object_metadata_loading = false;
+        self.object_metadata_error = None;
+    }
+
+    pub fn start_object_comparison(&mut self) {
+        self.object_comparison = None;
+        self.object_comparison_loading = true;
+        self.object_comparison_error = None;
+    }
+
+    pub fn set_object_comparison(&mut self, result: Result<FileComparison, String>) {
+        self.object_comparison_loading = false;
+        match result {
+            Ok(comparison) => self.object_comparison = Some(comparison),
+            Err(e) => self.object_comparison_error = Some(e),
+        }
+    }
+
+    pub fn clear_object_comparison(&mut self) {
+        self.object_comparison = None;
+        self.object_comparison_loading = false;
+        self.object_comparison_error = None;
+    }
+
+    pub fn start_object_preview(&mut self) {
+        self.object_preview = None;
+        self.object_preview_loading = true;
+        self.object_preview_error = None;
+    }
+
+    pub fn set_object_preview(&mut self, result: Result<ObjectPreview, String>) {
+        self.object_preview_loading = false;
+        match result {
+            Ok(preview) => self.object_preview = Some(preview),
+            Err(e) => self.object_preview_error = Some(e),
+        }
+    }
+
+    pub fn clear_object_preview(&mut self) {
+        self.object_preview = None;
+        self.object_preview_loading = false;
+        self.object_preview_error = None;
+    }
+
+    /// Adds the transfers saved by the previous session to the selection, they wait in
+    /// `orphaned_transfers` while any of their accounts is missing
+    pub fn restore_transfers(&mut self, mut restored: RestoredQueue) {
+        if let Some(backup) = restored.recovered_from.take() {
+            self.log_activity(
+                Severity::Warning,
+                format!(
+                    "The saved transfers were damaged, restored them from {}",
+                    backup.display()
+                ),
+            );
+        }
+        if !restored.missing_accounts.is_empty() {
+            self.orphaned_transfers = Some(restored);
+            return;
+        }
+        if restored.queue.is_empty() && restored.skipped == 0 {
+            return;
+        }
+        let count = restored.queue.len();
+        self.s3_selected_items
+            .extend(restored.queue.downloads.iter().cloned());
+        self.local_selected_items
+            .extend(restored.queue.uploads.iter().cloned());
+        self.refresh_transfers_summary();
+        if count > 0 {
+            self.restored_transfers = Some(restored.queue);
+        }
+        let mut message = format!("Restored {} transfer(s) of the previous session", count);
+        if restored.skipped > 0 {
+            message.push_str(&format!(
+                ", {} dropped with their missing account",
+                restored.skipped
+            ));
+        }
+        self.log_activity(Severity::Info, message.clone());
+        self.notify(message);
+    }
+
+    /// Runs the restored transfers of the missing `account` with the `replacement` account,
+    /// they are dropped without one
+    pub fn remap_restored_account(&mut self, account: &str, replacement: Option<&str>) {
+        let Some(mut restored) = self.orphaned_transfers.take() else {
+            return;
+        };
+        let replacement = replacement.filter(|name| self.creds.iter().any(|c| c.name == *name));
+        let message = match replacement {
+            Some(name) => format!(
+                "Transfers of the missing account '{}' use the account '{}'",
+                account, name
+            ),
+            None => format!("Dropped the transfers of the missing account '{}'", account),
+        };
+        restored.remap(account, replacement);
+        self.log_activity(Severity::Info, message);
+        self.restore_transfers(restored);
+    }
+
+    /// Offers the checked uploads of the earlier sessions to be resumed or aborted
+    pub fn offer_resumable_uploads(&mut self, uploads: Vec<ResumableUpload>) {
+        self.log_activity(
+            Severity::Info,
+            format!(
+                "Found {} interrupted upload(s) of the previous session",
+                uploads.len()
+            ),
+        );
+        self.resumable_uploads = uploads;
+    }
+
+    /// Selected item uploading the rest of the file, the restored transfer of the same
+    /// file is reused when there is one
+    pub fn resume_upload(&mut self, upload: &ResumableUpload) -> LocalSelectedItem {
+        self.resumable_uploads
+            .retain(|u| u.upload_id != upload.upload_id);
+        self.log_activity(
+            Severity::Info,
+            format!(
+                "Resuming the upload of {} after {} of its {} parts",
+                upload.path,
+                upload.parts.len(),
+                upload.part_count()
+            ),
+        );
+        let selected = self
+            .local_selected_items
+            .iter()
+            .find(|item| !item.transferred && upload.is_upload_of(item))
+            .cloned();
+        selected.unwrap_or_else(|| {
+            let item = upload.to_item();
+            self.local_selected_items.push(item.clone());
+            self.refresh_transfers_summary();
+            item
+        })
+    }
+
+    /// Drops the upload the user chose to abort from the offered ones
+    pub fn abort_resumable_upload(&mut self, upload: &ResumableUpload) {
+        self.resumable_uploads
+            .retain(|u| u.upload_id != upload.upload_id);
+        let message = format!("Aborted the interrupted upload of {}", upload.path);
+        self.log_activity(Severity::Info, message.clone());
+        self.notify(message);
+    }
+
+    /// Removes the restored transfers from the selection, returns whether any were waiting
+    /// for the answer
+    pub fn discard_restored_transfers(&mut self) -> bool {
+        let Some(restored) = self.restored_transfers.take() else {
+            return false;
+        };
+        self.s3_selected_items
+            .retain(|item| !restored.downloads.contains(item));
+        self.local_selected_items
+            .retain(|item| !restored.uploads.contains(item));
+        self.refresh_transfers_summary();
+        let message = format!(
+            "Discarded {} transfer(s) of the previous session",
+            restored.len()
+        );
+        self.log_activity(Severity::Info, message.clone());
+        self.notify(message);
+        true
+    }
+
+    /// Keeps the text of the object for the file manager to copy it, a failure is shown
+    /// on the status line
+    pub fn finish_object_text_read(
+        &mut self,
+        bucket: &str,
+        key: &str,
+        result: Result<String, String>,
+    ) {
+        let location = format!("s3://{}/{}", bucket, key);
+        match result {
+            Ok(text) => {
+                self.copied_object_text = Some((location, text));
+                self.copied_object_result += 1;
+            }
+            Err(e) => {
+                let message = format!("Cannot copy {} to the clipboard: {}", location, e);
+                self.log_activity(Severity::Error, message.clone());
+                self.notify(message);
+            }
+        }
+    }
+
+    /// Keeps the presigned url for the file manager to copy it, a failure is shown on the
+    /// status line
+    pub fn finish_presigned_url(
+        &mut self,
+        bucket: &str,
+        key: &str,
+        result: Result<String, String>,
+    ) {
+        let location = format!("s3://{}/{}", bucket, key);
+        match result {
+            Ok(url) => {
+                let expiry = format_duration(self.app_config.presigned_url_expiry);
+                self.log_activity(
+                    Severity::Info,
+                    format!("Presigned url of {} valid for {}", location, expiry),
+                );
+                let description = format!("url of {} valid for {}", location, expiry);
+                self.copied_object_text = Some((description, url));
+                self.copied_object_result += 1;
+            }
+            Err(e) => {
+                let message = format!("Cannot presign the url of {}: {}", location, e);
+                self.log_activity(Severity::Error, message.clone());
+                self.notify(message);
+            }
+        }
+    }
+
+    /// Shows how the custom command ended with the first line of its output, the activity log
+    /// gets all the kept lines
+    pub fn finish_custom_command(&mut self, name: &str, result: Result<CommandOutput, String>) {
+        let (severity, message, lines) = match result {
+            Ok(output) if output.success => {
+                (Severity::Info, format!("'{}' finished", name), output.lines)
+            }
+            Ok(output) => {
+                let message = match output.code {
+                    Some(code) => format!("'{}' failed with exit status {}", name, code),
+                    None => format!("'{}' was stopped by a signal", name),
+                };
+                (Severity::Error, message, output.lines)
+            }
+            Err(e) => (
+                Severity::Error,
+                format!("Cannot run '{}': {}", name, e),
+                Vec::new(),
+            ),
+        };
+        match lines.first() {
+            Some(first) => {
+                self.notify(format!("{}: {}", message, first));
+                self.log_activity(severity, format!("{}: {}", message, lines.join(" | ")));
+            }
+            None => {
+                self.notify(message.clone());
+                self.log_activity(severity, message);
+            }
+        }
+    }
+
+    pub fn log_activity(&mut self, severity: Severity, message: String) {
+        self.activity_log.push(severity, message);
+    }
+
+    pub fn notify(&mut self, message: String) {
+        self.notification = Some(Notification::new(message));
+    }
+
+    /// Records the result of a copy or a move, a failure is shown on the status line as well.
+    /// Returns whether the displayed bucket is the source or the destination of the copy
+    pub fn finish_s3_copy(&mut self, copy: &ObjectCopy, result: Result<(), String>) -> bool {
+        let (finished, running) = match copy.delete_source {
+            true => ("Moved", "Moving"),
+            false => ("Copied", "Copying"),
+        };
+        match result {
+            Ok(()) => self.log_activity(Severity::Info, copy.describe(finished)),
+            Err(e) => {
+                let message = format!("{} failed: {}", copy.describe(running), e);
+                self.log_activity(Severity::Error, message.clone());
+                self.notify(message);
+            }
+        }
+        self.current_s3_bucket.as_ref().is_some_and(|bucket| {
+            *bucket == copy.destination_bucket || *bucket == copy.source_bucket
+        })
+    }
+
+    /// Records the result of a rename, `Ok(false)` when an object already is at the new key
+    /// and the rename waits for the confirmation to replace it. Returns whether the displayed
+    /// bucket has to be listed again
+    pub fn finish_s3_rename(
+        &mut self,
+        item: S3SelectedItem,
+        rename: &ObjectCopy,
+        result: Result<bool, String>,
+    ) -> bool {
+        self.rename_result += 1;
+        self.rename_conflict = None;
+        match result {
+            Ok(true) => self.finish_s3_copy(rename, Ok(())),
+            Ok(false) => {
+                self.log_activity(
+                    Severity::Warning,
+                    format!("{} already exists", rename.destination_url()),
+                );
+                self.rename_conflict = Some((item, rename.destination_key.clone()));
+                false
+            }
+            Err(e) => self.finish_s3_copy(rename, Err(e)),
+        }
+    }
+
+    /// Returns whether the local panel should be listed again. The renamed entry is highlighted
+    /// once the listing arrives, failures are shown in the error popup of the local panel
+    pub fn finish_local_rename(
+        &mut self,
+        item: LocalDataItem,
+        new_name: String,
+        result: Result<bool, String>,
+    ) -> bool {
+        self.rename_result += 1;
+        self.local_rename_conflict = None;
+        match result {
+            Ok(true) => {
+                self.log_activity(
+                    Severity::Info,
+                    format!("Renamed {} to {}", item.path, new_name),
+                );
+                let directory = Path::new(&item.path).parent();
+                if directory != Some(Path::new(&self.current_local_path)) {
+                    return false;
+                }
+                self.pending_jump = Some(JumpTarget::Local {
+                    directory: self.current_local_path.clone(),
+                    item_name: Some(new_name),
+                });
+                true
+            }
+            Ok(false) => {
+                self.log_activity(
+                    Severity::Warning,
+                    format!("{} already exists next to {}", new_name, item.path),
+                );
+                self.local_rename_conflict = Some((item, new_name));
+                false
+            }
+            Err(e) => {
+                let error = format!("Renaming {} failed: {}", item.path, e);
+                self.log_activity(Severity::Error, error.clone());
+                self.delete_result += 1;
+                self.local_delete_state = Some(error);
+                false
+            }
+        }
+    }
+
+    /// A failed listing leaves the list empty, so the confirmation waiting for it doesn't
+    /// offer to transfer or delete anything
+    pub fn update_s3_recursive_list(&mut self, bucket_list: Result<LimitedSelection, String>) {
+        self.selection_limit_error = None;
+        self.long_download_keys = 0;
+        self.s3_data_full_list = match bucket_list {
+            Ok(LimitedSelection {
+                exceeded: Some(error),
+                ..
+            }) => {
+                self.log_activity(Severity::Warning, error.clone());
+                self.selection_limit_error = Some(error);
+                Vec::new()
+            }
+            Ok(selection) => {
+                let download_dir = self.download_dir();
+                self.long_download_keys = selection
+                    .items
+                    .iter()
+                    .filter(|item| exceeds_path_limits(&download_dir, &item.path))
+                    .count();
+                selection.items
+            }
+            Err(e) => {
+                self.notify(format!("Cannot list the selected location: {}", e));
+                Vec::new()
+            }
+        };
+        self.s3_list_recursive_loading = false;
+    }
+
+    pub fn update_files(&mut self, path: String, files: Vec<LocalDataItem>) {
+        self.stats.record_listing(files.len());
+        self.local_data = files;
+        self.current_local_path = path;
+    }
+
+    pub fn set_local_delete_error(&mut self, error_str: Option<String>) {
+        match &error_str {
+            None => self.stats.record_deletion(),
+            Some(error) => self.log_activity(
+                Severity::Error,
+                format!("Deletion of the local item failed: {}", error),
+            ),
+        }
+        self.delete_result += 1;
+        self.local_delete_state = error_str;
+    }
+
+    pub fn set_s3_delete_error(&mut self, error_str: Option<String>) {
+        match &error_str {
+            None => self.stats.record_deletion(),
+            Some(error) => self.log_activity(
+                Severity::Error,
+                format!("Deletion of the s3 item failed: {}", error),
+            ),
+        }
+        self.delete_result += 1;
+        self.s3_delete_state = error_str;
+        self.s3_delete_progress = None;
+    }
+
+    /// Drops the selected downloads of the deleted object, or of every object under the
+    /// deleted directory or bucket, children of selected directories included. Finished
+    /// transfers stay on the list
+    pub fn forget_deleted_s3_source(&mut self, deleted: &S3SelectedItem) {
+        let Some((bucket, key)) = selected_s3_location(deleted) else {
+            return;
+        };
+        let prefix = match (deleted.is_bucket, deleted.is_directory) {
+            (true, _) => Some(String::new()),
+            (false, true) => Some(format!("{}/", key.trim_end_matches('/'))),
+            (false, false) => None,
+        };
+        let account = deleted.account.as_str();
+        let covers = |item: &S3SelectedItem| {
+            !item.transferred
+                && item.account == account
+                && selected_s3_location(item).is_some_and(|(item_bucket, item_key)| {
+                    item_bucket == bucket
+                        && match &prefix {
+                            Some(prefix) => item_key.starts_with(prefix.as_str()),
+                            None => item_key == key,
+                        }
+                })
+        };
+        let mut removed = 0;
+        self.s3_selected_items.retain_mut(|item| {
+            if covers(item) {
+                removed += item.children.as_ref().map_or(1, Vec::len);
+                return false;
+            }
+            match &mut item.children {
+                Some(children) => {
+                    let before = children.len();
+                    children.retain(|child| !covers(child));
+                    removed += before - children.len();
+                    // the directory is done once the children left are transferred
+                    if children.len() != before {
+                        item.completed_children = children.iter().filter(|c| c.transferred).count();
+                        item.transferred = item.completed_children == children.len();
+                    }
+                    children.len() == before || !children.is_empty()
+                }
+                None => true,
+            }
+        });
+        self.report_forgotten_transfers(removed);
+    }
+
+    /// Drops the selected uploads of the deleted file or of the files under the deleted
+    /// directory, the paths are compared by their components
+    pub fn forget_deleted_local_source(&mut self, deleted: &str) {
+        let deleted = Path::new(deleted);
+        let covers = |item: &LocalSelectedItem| {
+            !item.transferred && Path::new(&item.path).starts_with(deleted)
+        };
+        let mut removed = 0;
+        self.local_selected_items.retain_mut(|item| {
+            if covers(item) {
+                removed += item.children.as_ref().map_or(1, Vec::len);
+                return false;
+            }
+            match &mut item.children {
+                Some(children) => {
+                    let before = children.len();
+                    children.retain(|child| !covers(child));
+                    removed += before - children.len();
+                    // the directory is done once the children left are transferred
+                    if children.len() != before {
+                        item.completed_children = children.iter().filter(|c| c.transferred).count();
+                        item.transferred = item.completed_children == children.len();
+                    }
+                    children.len() == before || !children.is_empty()
+                }
+                None => true,
+            }
+        });
+        self.report_forgotten_transfers(removed);
+    }
+
+    fn report_forgotten_transfers(&mut self, removed: usize) {
+        if removed == 0 {
+            return;
+        }
+        self.refresh_transfers_summary();
+        let message = format!(
+            "Also removed {} queued transfer(s) referencing the deleted item",
+            removed
+        );
+        self.log_activity(Severity::Info, message.clone());
+        self.notify(message);
+    }
+
+    /// Keeps the progress of the recursive delete until its last batch finished
+    pub fn set_s3_delete_progress(&mut self, progress: DeleteProgress) {
+        self.s3_delete_progress = Some(progress).filter(|p| p.deleted < p.total);
+    }
+
+    /// Removes the deleted items from the selections and from both panels, the items which
+    /// couldn't be deleted stay selected and are listed in the error popup
+    pub fn finish_bulk_deletion(&mut self, deletion: BulkDeletion) {
+        let deleted_s3: HashSet<(&str, &str)> = deletion
+            .deleted_s3
+            .iter()
+            .map(|(bucket, key)| (bucket.as_str(), key.as_str()))
+            .collect();
+        let is_deleted = |item: &S3SelectedItem| {
+            item.bucket.as_deref().is_some_and(|bucket| {
+                deleted_s3.contains(&(bucket, item.path.as_deref().unwrap_or(&item.name)))
+            })
+        };
+        let mut emptied_directories = Vec::new();
+        self.s3_selected_items
+            .retain_mut(|item| match &mut item.children {
+                Some(children) => {
+                    children.retain(|child| !is_deleted(child));
+                    item.completed_children = children.iter().filter(|c| c.transferred).count();
+                    if children.is_empty() && item.is_directory && !item.is_bucket {
+                        emptied_directories.push((item.bucket.clone(), item.path.clone()));
+                    }
+                    !children.is_empty()
+                }
+                None => !is_deleted(item),
+            });
+        self.s3_data.retain(|row| {
+            if row.is_directory {
+                !emptied_directories.contains(&(row.bucket.clone(), Some(row.path.clone())))
+            } else {
+                !row.bucket
+                    .as_deref()
+                    .is_some_and(|bucket| deleted_s3.contains(&(bucket, row.path.as_str())))
+            }
+        });
+        let deleted_local: HashSet<&str> = deletion
+            .deleted_local
+            .iter()
+            .map(|path| path.as_str())
+            .collect();
+        self.local_selected_items
+            .retain(|item| !deleted_local.contains(item.path.as_str()));
+        self.local_data
+            .retain(|row| !deleted_local.contains(row.path.as_str()));
+        self.refresh_transfers_summary();
+
+        for _ in 0..deletion.deleted() {
+            self.stats.record_deletion();
+        }
+        self.log_activity(
+            Severity::Info,
+            format!("Deleted {} selected items", deletion.deleted()),
+        );
+        for (location, reason) in &deletion.failed {
+            self.log_activity(
+                Severity::Error,
+                format!("Deletion of {} failed: {}", location, reason),
+            );
+        }
+        self.delete_result += 1;
+        self.s3_delete_state = deletion.error_message();
+        self.local_delete_state = None;
+    }
+
+    pub fn set_create_bucket_error(&mut self, error_str: Option<String>) {
+        match &error_str {
+            None => self.stats.record_bucket_created(),
+            Some(error) => self.log_activity(
+                Severity::Error,
+                format!("Bucket creation failed: {}", error),
+            ),
+        }
+        self.create_bucket_result += 1;
+        self.create_bucket_state = error_str;
+    }
+
+    /// Applies the result of creating the folder `name` under `prefix` of the bucket, returns
+    /// whether it was created in the location the s3 panel shows, which has to be listed again
+    pub fn finish_folder_creation(
+        &mut self,
+        bucket: &str,
+        prefix: &Option<String>,
+        name: &str,
+        error: Option<String>,
+    ) -> bool {
+        let location = format!("s3://{}/{}", bucket, prefix.clone().unwrap_or_default());
+        match &error {
+            None => self.log_activity(
+                Severity::Info,
+                format!("Created folder {} in {}", name, location),
+            ),
+            Some(error) => self.log_activity(
+                Severity::Error,
+                format!("Creating folder {} in {} failed: {}", name, location, error),
+            ),
+        }
+        self.create_folder_result += 1;
+        self.create_folder_state = error;
+        self.create_folder_state.is_none()
+            && self.current_s3_bucket.as_deref() == Some(bucket)
+            && self.current_s3_path == *prefix
+    }
+
+    pub fn set_current_local_path(&mut self, path: String) {
+        self.current_local_path = path;
+    }
+
+    /// Download directory configured for the current credential or in the application settings
+    pub fn configured_download_dir(&self) -> Option<String> {
+        self.current_creds
+            .default_download_dir
+            .clone()
+            .or(self.app_config.default_download_dir.clone())
+    }
+
+    /// Directory the new downloads are written to, the one opened on the local panel unless
+    /// another one is configured
+    pub fn download_dir(&self) -> String {
+        self.configured_download_dir()
+            .unwrap_or(self.current_local_path.clone())
+    }
+
+    pub fn set_s3_loading(&mut self, loading: bool) {
+        self.s3_loading = loading;
+    }
+
+    pub fn set_s3_list_recursive_loading(&mut self, loading: bool) {
+        self.s3_list_recursive_loading = loading;
+    }
+
+    /// A directory or bucket selected without any objects is completed right away,
+    /// no transfer would ever finish it. Its children already transferred are counted here,
+    /// the transfers count the others as they finish
+    pub fn add_s3_selected_item(&mut self, item: S3SelectedItem) {
+        let mut item = match &item.children {
+            Some(children) if children.is_empty() => {
+                self.notify(format!("Nothing to transfer in {}", item.name));
+                S3SelectedItem {
+                    transferred: true,
+                    progress: 100f64,
+                    ..item
+                }
+            }
+            _ => item,
+        };
+        item.completed_children = item
+            .children
+            .iter()
+            .flatten()
+            .filter(|c| c.transferred)
+            .count();
+        self.s3_selected_items.push(item);
+        self.refresh_transfers_summary();
+    }
+
+    /// Adds the entries of an imported selection file with the current account, the ones
+    /// already selected or found missing by the checks of the import are skipped
+    pub fn merge_selection(&mut self, import: SelectionImport) -> ImportSummary {
+        let mut downloads: HashSet<(String, String)> = self
+            .s3_selected_items
+            .iter()
+            .flat_map(|item| std::iter::once(item).chain(item.children.iter().flatten()))
+            .filter_map(|item| Some((item.bucket.clone()?, item.path.clone()?)))
+            .collect();
+        let mut uploads: HashSet<(String, String, String)> = self
+            .local_selected_items
+            .iter()
+            .flat_map(|item| std::iter::once(item).chain(item.children.iter().flatten()))
+            .map(|item| {
+                let bucket = item.destination_bucket.clone();
+                (item.path.clone(), bucket, item.destination_key())
+            })
+            .collect();
+        let mut summary = ImportSummary::default();
+        for entry in import.entries {
+            if !import.checks.is_valid(&entry) {
+                summary.invalid += 1;
+                continue;
+            }
+            match entry {
+                SelectionEntry::Download {
+                    bucket,
+                    key,
+                    destination,
+                } => {
+                    if !downloads.insert((bucket.clone(), key.clone())) {
+                        summary.duplicates += 1;
+                        continue;
+                    }
+                    self.s3_selected_items.push(S3SelectedItem {
+                        bucket: Some(bucket),
+                        name: key.rsplit('/').next().unwrap_or_default().to_string(),
+                        path: Some(key),
+                        is_directory: false,
+                        is_bucket: false,
+                        destination_dir: self.download_dir(),
+                        transferred: false,
+                        account: self.current_creds.name.clone(),
+                        progress: 0f64,
+                        children: None,
+                        error: None,
+                        destination_path: destination,
+                        verification: None,
+                        completed_children: 0,
+                    });
+                }
+                SelectionEntry::Upload {
+                    path,
+                    bucket,
+                    destination,
+                } => {
+                    if !uploads.insert((path.clone(), bucket.clone(), destination.clone())) {
+                        summary.duplicates += 1;
+                        continue;
+                    }
+                    let name = Path::new(&path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    self.local_selected_items.push(LocalSelectedItem::new(
+                        name,
+                        path,
+                        false,
+                        bucket,
+                        destination,
+                        self.current_creds.name.clone(),
+                        None,
+                    ));
+                }
+            }
+            summary.added += 1;
+        }
+        self.refresh_transfers_summary();
+        let message = summary.describe(&import.path);
+        self.log_activity(Severity::Info, message.clone());
+        self.notify(message);
+        summary
+    }
+
+    pub fn add_local_selected_item(&mut self, mut it: LocalSelectedItem) {
+        if it.is_directory && it.children.is_none() {
+            let items = LocalSelectedItem::list_directory_items(&it, &self.walk_options());
+            it.children = Some(items);
+        }
+        it.completed_children = it
+            .children
+            .iter()
+            .flatten()
+            .filter(|c| c.transferred)
+            .count();
+        self.local_selected_items.push(it);
+        self.refresh_transfers_summary();
+    }
+
+    /// Resolves the requested items against the current selection, skipping the ones which
+    /// were already transferred or are no longer selected
+    pub fn pending_transfers_subset(
+        &self,
+        s3_items: &[S3SelectedItem],
+        local_items: &[LocalSelectedItem],
+    ) -> (Vec<S3SelectedItem>, Vec<LocalSelectedItem>) {
+        let s3 = self
+            .s3_selected_items
+            .iter()
+            .filter(|it| !it.transferred && s3_items.contains(it))
+            .cloned()
+            .collect();
+        let local = self
+            .local_selected_items
+            .iter()
+            .filter(|it| !it.transferred && local_items.contains(it))
+            .cloned()
+            .collect();
+        (s3, local)
+    }
+
+    /// Options used for walking local directories, with the configured exclusions applied
+    pub fn walk_options(&self) -> WalkOptions {
+        WalkOptions::with_rules(IgnoreRules::new(&self.app_config.exclude_patterns))
+    }
+
+    pub fn remove_s3_selected_item(&mut self, item: S3SelectedItem) {
+        self.s3_selected_items
+            .retain(|it| it.bucket != item.bucket || it.name != item.name || it.path != item.path);
+        self.refresh_transfers_summary();
+    }
+
+    pub fn remove_local_selected_item(&mut self, item: LocalSelectedItem) {
+        self.local_selected_items
+            .retain(|it| it.name != item.name || it.path != item.path);
+        self.refresh_transfers_summary();
+    }
+
+    fn current_s3_navigation(&mut self) -> &mut NavigationHistory {
+        self.s3_navigation
+            .entry(self.current_creds.name.clone())
+            .or_default()
+    }
+
+    /// Location of the s3 panel in the history of the current account
+    pub fn s3_location(&self) -> NavigationState {
+        self.s3_navigation
+            .get(&self.current_creds.name)
+            .map(|history| history.current().clone())
+            .unwrap_or(NavigationState::new(None, None))
+    }
+
+    /// Moves the s3 panel of the current account, returns the location to list.
+    /// `row` is the row highlighted in the location being left
+    pub fn navigate_s3(
+        &mut self,
+        step: NavigationStep,
+        row: Option<usize>,
+    ) -> Option<NavigationState> {
+        let location = self.step_s3_navigation(step, row)?;
+        let opened = match &location.current_bucket {
+            Some(bucket) => format!(
+                "Opened s3://{}/{}",
+                bucket,
+                location.current_prefix.clone().unwrap_or_default()
+            ),
+            None => "Opened the bucket list".into(),
+        };
+        self.log_activity(Severity::Info, opened);
+        Some(location)
+    }
+
+    fn step_s3_navigation(
+        &mut self,
+        step: NavigationStep,
+        row: Option<usize>,
+    ) -> Option<NavigationState> {
+        if let NavigationStep::Into(location) = &step {
+            // only a bucket entered from the bucket list opens its configured prefix
+            if location.current_prefix.is_none() && self.s3_location().current_bucket.is_none() {
+                if let Some(bucket) = &location.current_bucket {
+                    if let Some(landing) = self.land_on_default_prefix(bucket, row) {
+                        return Some(landing);
+                    }
+                }
+            }
+        }
+        self.s3_landing = None;
+        let (location, restored_row) = self.current_s3_navigation().navigate(step, row)?;
+        self.pending_s3_row = restored_row;
+        Some(location)
+    }
+
+    /// Applies the check of the location typed into the go to input. An existing location is
+    /// opened with every directory above it visited so back goes up level by level, it's
+    /// returned to be listed. Results for an account that's no longer used are dropped
+    pub fn finish_go_to_s3_path(
+        &mut self,
+        account: &str,
+        location: NavigationState,
+        row: Option<usize>,
+        result: Result<(), String>,
+    ) -> Option<NavigationState> {
+        if account != self.current_creds.name {
+            return None;
+        }
+        self.go_to_path_result += 1;
+        if let Err(error) = result {
+            self.go_to_path_error = Some(error);
+            return None;
+        }
+        self.go_to_path_error = None;
+        self.s3_landing = None;
+        let mut row = row;
+        let history = self.current_s3_navigation();
+        for level in location.levels() {
+            history.visit(level, row.take());
+        }
+        self.pending_s3_row = None;
+        self.log_activity(
+            Severity::Info,
+            format!(
+                "Opened s3://{}/{}",
+                location.current_bucket.clone().unwrap_or_default(),
+                location.current_prefix.clone().unwrap_or_default()
+            ),
+        );
+        Some(location)
+    }
+
+    /// Opens the prefix configured for the bucket, visiting every directory above it
+    /// so back goes up level by level. `None` when the bucket root should be opened
+    fn land_on_default_prefix(
+        &mut self,
+        bucket: &str,
+        row: Option<usize>,
+    ) -> Option<NavigationState> {
+        let default_prefix = find_default_prefix(
+            &self.app_config.default_prefixes,
+            &self.current_creds.name,
+            bucket,
+        )?;
+        let levels = match default_prefix.levels() {
+            Ok(levels) => levels,
+            Err(e) => {
+                tracing::warn!("{}", e);
+                self.notify(format!("{}, opening the bucket root", e));
+                return None;
+            }
+        };
+        let mut row = row;
+        let history = self.current_s3_navigation();
+        for level in levels.iter() {
+            history.visit(level.clone(), row.take());
+        }
+        self.pending_s3_row = None;
+        let landing = levels.last().cloned()?;
+        self.s3_landing = Some((levels[0].clone(), landing.clone()));
+        self.notify("Jumped to configured prefix - press Esc to go up".into());
+        Some(landing)
+    }
+
+    /// Bucket root to list instead of the configured prefix when its listing is empty,
+    /// the directories visited on the way to the prefix are left
+    pub fn missing_landing_fallback(
+        &mut self,
+        request_id: u64,
+        bucket: &Option<String>,
+        prefix: &Option<String>,
+        bucket_list: &[S3DataItem],
+    ) -> Option<NavigationState> {
+        if request_id != self.s3_listing_request {
+            return None;
+        }
+        let (root, landing) = self.s3_landing.take()?;
+        if landing.current_bucket != *bucket
+            || landing.current_prefix != *prefix
+            || !bucket_list.is_empty()
+        {
+            return None;
+        }
+        tracing::warn!(
+            "Configured prefix {:?} not found, listing the bucket root",
+            landing
+        );
+        self.current_s3_navigation().rewind_to(&root);
+        self.notify(format!(
+            "Configured prefix {} not found in {}, showing the bucket root",
+            prefix.clone().unwrap_or_default(),
+            bucket.clone().unwrap_or_default()
+        ));
+        Some(root)
+    }
+
+    /// Leaves the browsed bucket after s3 answered that it doesn't exist, e.g. it was deleted
+    /// by another client: the history returns to the bucket list, which is listed again, and
+    /// the transfers not started yet to or from the bucket fail. `None` when it's not the
+    /// browsed bucket, otherwise the keys of the failed transfers waiting in the running batch
+    pub fn forget_missing_bucket(&mut self, bucket: &str) -> Option<Vec<String>> {
+        let browsed = self.s3_location().current_bucket.as_deref() == Some(bucket)
+            || self.current_s3_bucket.as_deref() == Some(bucket);
+        if !browsed {
+            return None;
+        }
+        let reason = format!("bucket '{}' no longer exists", bucket);
+        self.current_s3_navigation().forget_bucket(bucket);
+        self.invalidate_bucket_list();
+        // the rows of the bucket are gone until the bucket list replaces them
+        self.s3_data = Vec::new();
+        self.current_s3_bucket = None;
+        self.current_s3_path = None;
+        self.s3_landing = None;
+        self.pending_jump = None;
+        self.pending_s3_row = None;
+
+        let mut leaves: Vec<(String, bool, &mut Option<String>, &mut f64)> = Vec::new();
+        for item in self.s3_selected_items.iter_mut() {
+            match item.children.as_mut() {
+                Some(children) => leaves.extend(
+                    children
+                        .iter_mut()
+                        .filter(|c| c.bucket.as_deref() == Some(bucket))
+                        .map(|c| {
+                            (
+                                download_key(c),
+                                c.transferred,
+                                &mut c.error,
+                                &mut c.progress,
+                            )
+                        }),
+                ),
+                None if item.bucket.as_deref() == Some(bucket) => leaves.push((
+                    download_key(item),
+                    item.transferred,
+                    &mut item.error,
+                    &mut item.progress,
+                )),
+                None => {}
+            }
+        }
+        for item in self.local_selected_items.iter_mut() {
+            if item.destination_bucket != bucket {
+                continue;
+            }
+            match item.children.as_mut() {
+                Some(children) => leaves.extend(
+                    children
+                        .iter_mut()
+                        .map(|c| (upload_key(c), c.transferred, &mut c.error, &mut c.progress)),
+                ),
+                None => leaves.push((
+                    upload_key(item),
+                    item.transferred,
+                    &mut item.error,
+                    &mut item.progress,
+                )),
+            }
+        }
+        let mut waiting = Vec::new();
+        let mut failed = 0;
+        for (key, transferred, error, progress) in leaves {
+            // the started ones fail with the error of their own requests
+            if transferred || error.is_some() || self.transfer_dispatch.contains_key(&key) {
+                continue;
+            }
+            *error = Some(reason.clone());
+            *progress = 0f64;
+            self.paused_transfers.remove(&key);
+            if self.transfer_batch.is_pending(&key) {
+                waiting.push(key);
+            }
+            failed += 1;
+        }
+        if failed > 0 {
+            self.refresh_transfers_summary();
+        }
+        let message = match failed {
+            0 => format!("Bucket '{}' no longer exists", bucket),
+            _ => format!(
+                "Bucket '{}' no longer exists, {} transfer(s) to or from it failed",
+                bucket, failed
+            ),
+        };
+        self.log_activity(Severity::Error, message.clone());
+        self.notify(message);
+        Some(waiting)
+    }
+
+    fn detect_leading_slash_keys(&mut self) {
+        tracing::info!(
+            "keys listed with a leading slash, enabling leading_slash_keys for {}",
+            self.current_creds.name
+        );
+        self.current_creds.leading_slash_keys = true;
+        for cred in self.creds.iter_mut() {
+            if cred.name == self.current_creds.name {
+                cred.leading_slash_keys = true;
+            }
+        }
+    }
+
+    /// Records the confirmation of `insecure_skip_tls_verify` given on the accounts page
+    pub fn confirm_skip_tls_verify(&mut self, account: &str) {
+        self.skip_tls_verify_confirmed.insert(account.to_string());
+    }
+
+    /// Whether the certificates of the endpoint of the account are not verified, which is
+    /// badged on the accounts page and the status line
+    pub fn skips_tls_verify(&self, creds: &FileCredential) -> bool {
+        creds.insecure_skip_tls_verify && self.skip_tls_verify_confirmed.contains(&creds.name)
+    }
+
+    pub fn set_current_s3_creds(&mut self, item: FileCredential) {
+        for cred in self.creds.iter_mut() {
+            if cred.name == item.name {
+                cred.selected = true;
+                self.current_creds = cred.clone();
+            } else {
+                cred.selected = false;
+            }
+        }
+    }
+
+    /// Adds the credential saved on the accounts page or replaces the edited one (`original`),
+    /// returns whether it's the current account which has to list its buckets again
+    pub fn store_credential(&mut self, original: Option<&str>, cred: FileCredential) -> bool {
+        let name = cred.name.clone();
+        let index = original.and_then(|name| self.creds.iter().position(|c| c.name == name));
+        let current = match index {
+            Some(index) => {
+                let previous = self.creds[index].name.clone();
+                // the listing and the check were done with the previous keys
+                self.bucket_cache.invalidate(&previous);
+                self.account_checks.remove(&previous);
+                let selected = self.creds[index].selected;
+                self.creds[index] = FileCredential { selected, ..cred };
+                if selected {
+                    self.current_creds = self.creds[index].clone();
+                }
+                selected
+            }
+            None => {
+                self.creds.push(FileCredential {
+                    selected: false,
+                    ..cred
+                });
+                false
+            }
+        };
+        self.log_activity(Severity::Info, format!("Saved account {}", name));
+        self.notify(format!("Account {} saved", name));
+        current
+    }
+
+    /// Forgets the credential whose file was deleted
+    pub fn remove_credential(&mut self, name: &str) {
+        self.creds.retain(|cred| cred.name != name);
+        self.bucket_cache.invalidate(name);
+        self.account_checks.remove(name);
+        self.log_activity(Severity::Info, format!("Deleted account {}", name));
+        self.notify(format!("Account {} deleted", name));
+    }
+
+    /*
+    The url can look smth like this:
+    "https://maluchyplywaja.s3.eu-west-1.amazonaws.com/IMG_8123.HEIC?x-id=PutObject"
+     */
+    fn update_local_item_with_progress(
+        &mut self,
+        selected_items: Vec<LocalSelectedItem>,
+        progress_item: UploadProgressItem,
+    ) {
+        // the whole key tells apart the files of the same name uploaded to different folders
+        let is_uploaded = |item: &LocalSelectedItem| {
+            item.destination_bucket == progress_item.bucket
+                && item.destination_key() == progress_item.key
+        };
+        let mut mutated_items: Vec<LocalSelectedItem> = Vec::new();
+        for item in selected_items.clone().iter_mut() {
+            if item.children.is_none() {
+                if is_uploaded(item) {
+                    item.progress = progress_item.progress;
+                    self.record_rate(
+                        upload_key(item),
+                        progress_item.bytes,
+                        progress_item.total_bytes,
+                    );
+                    mutated_items.push(item.clone());
+                } else {
+                    mutated_items.push(item.clone());
+                }
+            } else {
+                let mut mutated_children: Vec<LocalSelectedItem> = Vec::new();
+                if let Some(mut children) = item.clone().children {
+                    for child in children.iter_mut() {
+                        if is_uploaded(child) {
+                            child.progress = progress_item.progress;
+                            self.record_rate(
+                                upload_key(child),
+                                progress_item.bytes,
+                                progress_item.total_bytes,
+                            );
+                            mutated_children.push(child.clone());
+                        } else {
+                            mutated_children.push(child.clone());
+                        }
+                    }
+                    item.progress = overall_progress(mutated_children.iter().map(|c| c.progress));
+                    item.transferred = mutated_children.iter().all(|c| c.transferred);
+                    item.children = Some(mutated_children);
+                    mutated_items.push(item.clone());
+                }
+            }
+        }
+        self.local_selected_items = mutated_items;
+    }
+
+    fn update_s3_item_with_progress(
+        &mut self,
+        selected_items: Vec<S3SelectedItem>,
+        progress_item: DownloadProgressItem,
+    ) {
+        // the whole key tells apart the objects of the same name in different folders
+        let is_downloaded = |item: &S3SelectedItem| {
+            item.bucket.as_deref() == Some(progress_item.bucket.as_str())
+                && item.object_key() == progress_item.key
+        };
+        let mut mutated_items: Vec<S3SelectedItem> = Vec::new();
+        for item in selected_items.clone().iter_mut() {
+            if item.children.is_none() {
+                if is_downloaded(item) {
+                    item.progress = progress_item.progress;
+                    self.record_rate(
+                        download_key(item),
+                        progress_item.bytes,
+                        progress_item.total_bytes,
+                    );
+                    mutated_items.push(item.clone());
+                } else {
+                    mutated_items.push(item.clone());
+                }
+            } else {
+                let mut mutated_children: Vec<S3SelectedItem> = Vec::new();
+                if let Some(mut children) = item.clone().children {
+                    for child in children.iter_mut() {
+                        if is_downloaded(child) {
+                            child.progress = progress_item.progress;
+                            self.record_rate(
+                                download_key(child),
+                                progress_item.bytes,
+                                progress_item.total_bytes,
+                            );
+                            mutated_children.push(child.clone());
+                        } else {
+                            mutated_children.push(child.clone());
+                        }
+                    }
+                    item.progress = overall_progress(mutated_children.iter().map(|c| c.progress));
+                    item.transferred = mutated_children.iter().all(|c| c.transferred);
+                    item.children = Some(mutated_children);
+                    mutated_items.push(item.clone());
+                }
+            }
+        }
+        self.s3_selected_items = mutated_items;
+    }
+
+    /// Progress arriving after the transfer finished isn't measured anymore
+    fn record_rate(&mut self, key: String, bytes: u64, total_bytes: u64) {
+        if self.transfer_batch.is_pending(&key) {
+            self.transfer_rates
+                .record(key, bytes, total_bytes, Instant::now());
+        }
+    }
+
+    /// Speed of the download, for directories the combined speed of their running files
+    pub fn s3_item_rate(&self, item: &S3SelectedItem) -> Option<TransferRate> {
+        let keys: Vec<String> = match &item.children {
+            Some(children) => children.iter().map(download_key).collect(),
+            None => vec![download_key(item)],
+        };
+        self.transfer_rates.of(keys.iter().map(String::as_str))
+    }
+
+    /// Speed of the upload, for directories the combined speed of their running files
+    pub fn local_item_rate(&self, item: &LocalSelectedItem) -> Option<TransferRate> {
+        let keys: Vec<String> = match &item.children {
+            Some(children) => children.iter().map(upload_key).collect(),
+            None => vec![upload_key(item)],
+        };
+        self.transfer_rates.of(keys.iter().map(String::as_str))
+    }
+
+    pub fn update_progress_on_selected_local_item(&mut self, item: UploadProgressItem) {
+        self.update_local_item_with_progress(self.local_selected_items.clone(), item.clone());
+    }
+
+    pub fn update_progress_on_selected_s3_item(&mut self, item: DownloadProgressItem) {
+        self.update_s3_item_with_progress(self.s3_selected_items.clone(), item.clone());
+    }
+}
+
+/// Active when any of the files got a slot, otherwise waiting when any of them is in the batch
+fn most_advanced(states: impl Iterator<Item = TransferState>) -> TransferState {
+    states.fold(TransferState::Queued, |most, state| match (most, state) {
+        (TransferState::Active, _) | (_, TransferState::Active) => TransferState::Active,
+        (TransferState::Waiting { .. }, _) => most,
+        _ => state,
+    })
+}
+
+/// Bucket and key of a selected item, a selected bucket has an empty key
+fn selected_s3_location(item: &S3SelectedItem) -> Option<(&str, &str)> {
+    match item.is_bucket {
+        true => Some((item.name.as_str(), "")),
+        false => {
+            let key = item.path.as_deref().unwrap_or(&item.name);
+            item.bucket.as_deref().map(|bucket| (bucket, key))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::s3_data_item::BucketInfo;
+    use crate::model::transfer_item::TransferItem;
+    use crate::model::verification::{Verification, VerificationMethod};
+    use crate::services::s3_keys::api_key;
+    use crate::settings::default_prefix::DefaultPrefix;
+    use crate::settings::encryption::UploadEncryption;
+    use crate::settings::proxy::ProxyOverride;
+    use crate::settings::transfer_tuning::TuningOverride;
+
+    #[test]
+    fn default_active_page_is_file_manager_page() {
+        let state = State::default();
+        assert_eq!(state.active_page, ActivePage::FileManager);
+    }
+
+    #[test]
+    fn representative_events_are_logged() {
+        let mut state = State::default();
+        state.navigate_s3(
+            NavigationStep::Into(NavigationState::new(Some("photos".into()), None)),
+            None,
+        );
+        state.start_transfer_batch(&[]);
+        state.set_s3_delete_error(Some("AccessDenied".into()));
+        state.set_s3_delete_error(None);
+        state.stop_network_activity(2);
+
+        let entries: Vec<(Severity, String)> = state
+            .activity_log
+            .entries()
+            .iter()
+            .map(|e| (e.severity, e.message.clone()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (Severity::Info, "Opened s3://photos/".to_string()),
+                (Severity::Info, "Started 0 transfer(s)".to_string()),
+                (
+                    Severity::Error,
+                    "Deletion of the s3 item failed: AccessDenied".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "Network activity stopped: 2 task(s) and 0 transfer(s) cancelled".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn finished_transfers_are_logged_with_their_outcome() {
+        let mut state = State::default();
+        let item = S3SelectedItem {
+            bucket: Some("logs".into()),
+            name: "app.log".into(),
+            path: Some("2024/app.log".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/tmp".into(),
+            transferred: true,
+            account: String::new(),
+            progress: 100.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.finish_download(&item);
+        state.finish_download(&S3SelectedItem {
+            error: Some("timeout".into()),
+            ..item
+        });
+
+        let entries = state.activity_log.entries();
+        assert_eq!(entries[0].severity, Severity::Info);
+        assert_eq!(
+            entries[0].message,
+            "Downloaded s3://logs/2024/app.log to /tmp/2024/app.log"
+        );
+        assert_eq!(entries[1].severity, Severity::Error);
+        assert_eq!(
+            entries[1].message,
+            "Download of s3://logs/2024/app.log failed: timeout"
+        );
+    }
+
+    #[test]
+    fn set_active_page_changes_page_correctly() {
+        let mut state = State::default();
+        state.set_active_page(ActivePage::Help);
+        assert_eq!(state.active_page, ActivePage::Help);
+    }
+
+    #[test]
+    fn new_state_with_selected_credential_sets_current_creds_correctly() {
+        let creds = vec![
+            FileCredential {
+                name: "AWS".into(),
+                access_key: "".to_string(),
+                secret_key: "".to_string(),
+                default_region: "".to_string(),
+                selected: false,
+                proxy: ProxyOverride::default(),
+                leading_slash_keys: false,
+                tuning: TuningOverride::default(),
+                default_download_dir: None,
+                profile: None,
+                ca_bundle_path: None,
+                insecure_skip_tls_verify: false,
+                encryption: UploadEncryption::default(),
+                endpoint_url: None,
+            },
+            FileCredential {
+                name: "Azure".into(),
+                access_key: "".to_string(),
+                secret_key: "".to_string(),
+                default_region: "".to_string(),
+                selected: true,
+                proxy: ProxyOverride::default(),
+                leading_slash_keys: false,
+                tuning: TuningOverride::default(),
+                default_download_dir: None,
+                profile: None,
+                ca_bundle_path: None,
+                insecure_skip_tls_verify: false,
+                encryption: UploadEncryption::default(),
+                endpoint_url: None,
+            },
+        ];
+        let state = State::new(creds.clone());
+        assert_eq!(state.current_creds, creds[1]);
+    }
+
+    #[test]
+    fn new_state_without_selected_credential_sets_current_creds_correctly() {
+        let creds = vec![
+            FileCredential {
+                name: "AWS".into(),
+                access_key: "".to_string(),
+                secret_key: "".to_string(),
+                default_region: "".to_string(),
+                selected: false,
+                proxy: ProxyOverride::default(),
+                leading_slash_keys: false,
+                tuning: TuningOverride::default(),
+                default_download_dir: None,
+                profile: None,
+                ca_bundle_path: None,
+                insecure_skip_tls_verify: false,
+                encryption: UploadEncryption::default(),
+                endpoint_url: None,
+            },
+            FileCredential {
+                name: "Azure".into(),
+                access_key: "".to_string(),
+                secret_key: "".to_string(),
+                default_region: "".to_string(),
+                selected: false,
+                proxy: ProxyOverride::default(),
+                leading_slash_keys: false,
+                tuning: TuningOverride::default(),
+                default_download_dir: None,
+                profile: None,
+                ca_bundle_path: None,
+                insecure_skip_tls_verify: false,
+                encryption: UploadEncryption::default(),
+                endpoint_url: None,
+            },
+        ];
+        let state = State::new(creds.clone());
+        assert_eq!(state.current_creds, state.current_creds);
+    }
+
+    #[test]
+    fn set_current_s3_creds_set_creds_correctly_for_existing_state() {
+        let creds = vec![
+            FileCredential {
+                name: "AWS".into(),
+                access_key: "".to_string(),
+                secret_key: "".to_string(),
+                default_region: "".to_string(),
+                selected: true,
+                proxy: ProxyOverride::default(),
+                leading_slash_keys: false,
+                tuning: TuningOverride::default(),
+                default_download_dir: None,
+                profile: None,
+                ca_bundle_path: None,
+                insecure_skip_tls_verify: false,
+                encryption: UploadEncryption::default(),
+                endpoint_url: None,
+            },
+            FileCredential {
+                name: "Azure".into(),
+                access_key: "".to_string(),
+                secret_key: "".to_string(),
+                default_region: "".to_string(),
+                selected: false,
+                proxy: ProxyOverride::default(),
+                leading_slash_keys: false,
+                tuning: TuningOverride::default(),
+                default_download_dir: None,
+                profile: None,
+                ca_bundle_path: None,
+                insecure_skip_tls_verify: false,
+                encryption: UploadEncryption::default(),
+                endpoint_url: None,
+            },
+        ];
+        let mut state = State::new(creds.clone());
+        assert_eq!(state.current_creds, creds[0]);
+
+        state.set_current_s3_creds(creds[1].clone());
+        assert_eq!(state.current_creds.name, creds[1].name);
+    }
+
+    #[test]
+    fn add_and_remove_s3_selected_item_works() {
+        let mut state = State::default();
+        let item = S3SelectedItem {
+            bucket: Some("test-bucket".into()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "".to_string(),
+            transferred: false,
+            account: Default::default(),
+            progress: 0f64,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+
+        state.add_s3_selected_item(item.clone());
+        assert_eq!(state.s3_selected_items.len(), 1);
+        assert_eq!(state.s3_selected_items[0], item);
+
+        state.remove_s3_selected_item(item);
+        assert!(state.s3_selected_items.is_empty());
+    }
+
+    #[test]
+    fn add_and_remove_local_selected_item_works() {
+        let mut state = State::default();
+        let item = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+
+        state.add_local_selected_item(item.clone());
+        assert_eq!(state.local_selected_items.len(), 1);
+        assert_eq!(state.local_selected_items[0], item);
+
+        state.remove_local_selected_item(item);
+        assert!(state.local_selected_items.is_empty());
+    }
+
+    #[test]
+    fn update_selected_s3_transfers_updates_correctly() {
+        let mut state = State::default();
+        let selected_item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.s3_selected_items.push(selected_item.clone());
+        state.update_selected_s3_transfers(selected_item.clone());
+        assert!(state.s3_selected_items[0].transferred);
+        assert_eq!(state.s3_selected_items[0].progress, 100f64);
+    }
+
+    #[test]
+    fn update_selected_s3_transfers_updates_child_correctly() {
+        let mut state = State::default();
+        let child = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "child-file1.txt".into(),
+            path: Some("path/to/child-file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let selected_item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: Some(vec![child.clone()]),
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.s3_selected_items.push(selected_item.clone());
+        state.update_selected_s3_transfers(child.clone());
+        let children = state.s3_selected_items[0]
+            .clone()
+            .children
+            .unwrap_or_default();
+        assert_eq!(children.len(), 1);
+        // assert!(children[0].transferred);
+        // assert_eq!(children[0].progress, 100f64);
+    }
+
+    #[test]
+    fn empty_directory_selection_is_completed_right_away() {
+        let mut state = State::default();
+        let bucket = S3SelectedItem {
+            bucket: None,
+            name: "empty-bucket".into(),
+            path: None,
+            is_directory: false,
+            is_bucket: true,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: Some(Vec::new()),
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_s3_selected_item(bucket.clone());
+        assert!(state.s3_selected_items[0].transferred);
+        assert_eq!(state.s3_selected_items[0].progress, 100f64);
+        assert!(!state.has_transfers_in_progress());
+        assert!(TransferJob::queue(state.s3_selected_items.clone(), Vec::new()).is_empty());
+
+        // results of other transfers keep the empty parent completed
+        state.s3_selected_items[0] = bucket;
+        state.update_selected_s3_transfers(S3SelectedItem {
+            name: "other.txt".into(),
+            children: None,
+            is_bucket: false,
+            ..state.s3_selected_items[0].clone()
+        });
+        assert!(state.s3_selected_items[0].transferred);
+        assert_eq!(state.s3_selected_items[0].progress, 100f64);
+    }
+
+    #[test]
+    fn selection_over_the_limit_is_not_selected() {
+        let bucket_info = BucketInfo {
+            bucket: Some("logs".into()),
+            region: None,
+            is_bucket: false,
+            creation_date: None,
+        };
+        let object = |key: &str| S3DataItem::listed(bucket_info.clone(), key, "".into(), false);
+        let mut state = State::default();
+        state.set_s3_list_recursive_loading(true);
+        state.update_s3_recursive_list(Ok(LimitedSelection {
+            items: vec![object("a.log"), object("b.log")],
+            key_bytes: 10,
+            exceeded: Some("Selection exceeds 2 objects".into()),
+        }));
+        assert!(!state.s3_list_recursive_loading);
+        assert!(state.s3_data_full_list.is_empty());
+        assert_eq!(
+            state.selection_limit_error.as_deref(),
+            Some("Selection exceeds 2 objects")
+        );
+
+        let long_key = format!("logs/{}.log", "x".repeat(300));
+        state.update_s3_recursive_list(Ok(LimitedSelection {
+            items: vec![object("a.log"), object(&long_key)],
+            ..LimitedSelection::default()
+        }));
+        assert_eq!(state.s3_data_full_list.len(), 2);
+        assert_eq!(state.selection_limit_error, None);
+        assert_eq!(state.long_download_keys, 1);
+    }
+
+    #[test]
+    fn failed_recursive_listing_ends_the_loading() {
+        let mut state = State::default();
+        state.set_s3_list_recursive_loading(true);
+        state.update_s3_recursive_list(Err("AccessDenied".into()));
+        assert!(!state.s3_list_recursive_loading);
+        assert!(state.s3_data_full_list.is_empty());
+        assert!(state.notification.is_some());
+    }
+
+    #[test]
+    fn update_selected_s3_transfers_with_error_updates_correctly() {
+        let mut state = State::default();
+        let selected_item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: Some("Error".into()),
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_s3_selected_item(selected_item.clone());
+        state.update_selected_s3_transfers(selected_item.clone());
+        assert!(!state.s3_selected_items[0].transferred);
+        assert_eq!(state.s3_selected_items[0].progress, 0f64);
+    }
+
+    #[test]
+    fn update_selected_s3_transfers_records_the_verification() {
+        let mut state = State::default();
+        let selected_item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_s3_selected_item(selected_item.clone());
+        state.update_selected_s3_transfers(S3SelectedItem {
+            error: Some("verification failed, sha-256 of the object 'a' differs".into()),
+            verification: Some(Verification::Failed(VerificationMethod::Sha256)),
+            ..selected_item.clone()
+        });
+        assert!(!state.s3_selected_items[0].transferred);
+        assert_eq!(
+            state.s3_selected_items[0].verification,
+            Some(Verification::Failed(VerificationMethod::Sha256))
+        );
+
+        state.update_selected_s3_transfers(S3SelectedItem {
+            verification: Some(Verification::Passed(VerificationMethod::Etag)),
+            ..selected_item
+        });
+        assert!(state.s3_selected_items[0].transferred);
+        assert_eq!(
+            state.s3_selected_items[0].verification,
+            Some(Verification::Passed(VerificationMethod::Etag))
+        );
+    }
+
+    #[test]
+    fn update_selected_local_transfers_updates_correctly() {
+        let mut state = State::default();
+        let selected_item = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_local_selected_item(selected_item.clone());
+        state.update_selected_local_transfers(selected_item.clone());
+        assert!(state.local_selected_items[0].transferred);
+        assert_eq!(state.local_selected_items[0].progress, 100f64);
+    }
+
+    #[test]
+    fn update_selected_local_transfers_with_error_updates_correctly() {
+        let mut state = State::default();
+        let selected_item = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: Some("Error".into()),
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_local_selected_item(selected_item.clone());
+        state.update_selected_local_transfers(selected_item.clone());
+        assert!(!state.local_selected_items[0].transferred);
+        assert_eq!(state.local_selected_items[0].progress, 0f64);
+    }
+
+    #[test]
+    fn update_selected_local_transfers_records_the_verification() {
+        let mut state = State::default();
+        let selected_item = LocalSelectedItem::new(
+            "file1.txt".into(),
+            "path/to/file1.txt".into(),
+            false,
+            "test-bucket".into(),
+            "".into(),
+            String::new(),
+            None,
+        );
+        state.add_local_selected_item(selected_item.clone());
+        state.update_selected_local_transfers(LocalSelectedItem {
+            error: Some("verification failed, etag of the object 'a' differs".into()),
+            verification: Some(Verification::Failed(VerificationMethod::Etag)),
+            ..selected_item.clone()
+        });
+        assert!(!state.local_selected_items[0].transferred);
+        assert_eq!(
+            state.local_selected_items[0].verification,
+            Some(Verification::Failed(VerificationMethod::Etag))
+        );
+
+        state.update_selected_local_transfers(LocalSelectedItem {
+            verification: Some(Verification::Passed(VerificationMethod::Sha256)),
+            ..selected_item
+        });
+        assert!(state.local_selected_items[0].transferred);
+        assert_eq!(
+            state.local_selected_items[0].verification,
+            Some(Verification::Passed(VerificationMethod::Sha256))
+        );
+    }
+
+    #[test]
+    fn remove_already_transferred_items_removes_correctly() {
+        let mut state = State::default();
+        let local_item_not_transfered = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let local_item_transfered = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: true,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let s3_item_not_transferred = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let s3_item_transferred = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: true,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_local_selected_item(local_item_transfered);
+        state.add_local_selected_item(local_item_not_transfered);
+        state.add_s3_selected_item(s3_item_not_transferred);
+        state.add_s3_selected_item(s3_item_transferred);
+        assert_eq!(state.s3_selected_items.len(), 2);
+        assert_eq!(state.local_selected_items.len(), 2);
+
+        state.remove_already_transferred_items();
+        assert_eq!(state.s3_selected_items.len(), 1);
+        assert_eq!(state.local_selected_items.len(), 1);
+        assert!(!state.s3_selected_items[0].transferred);
+        assert!(!state.local_selected_items[0].transferred);
+    }
+
+    #[test]
+    fn transfers_in_progress_ignore_finished_and_failed_items() {
+        let mut state = State::default();
+        let item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_s3_selected_item(item);
+        assert!(!state.has_transfers_in_progress());
+
+        state.s3_selected_items[0].progress = 40.0;
+        assert!(state.has_transfers_in_progress());
+
+        state.s3_selected_items[0].error = Some("timeout".to_string());
+        assert!(!state.has_transfers_in_progress());
+
+        state.s3_selected_items[0].error = None;
+        state.s3_selected_items[0].transferred = true;
+        assert!(!state.has_transfers_in_progress());
+    }
+
+    #[test]
+    fn refresh_transfers_summary_counts_children_and_single_files() {
+        let mut state = State::default();
+        let child = |name: &str, transferred: bool| S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: name.into(),
+            path: Some(format!("dir/{}", name)),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let parent = S3SelectedItem {
+            name: "dir/".into(),
+            path: Some("dir/".into()),
+            is_directory: true,
+            children: Some(vec![child("a.txt", true), child("b.txt", false)]),
+            ..child("dir/", false)
+        };
+        state.add_s3_selected_item(parent);
+        state.add_s3_selected_item(child("single.txt", false));
+        assert_eq!(state.transfers_total, 3);
+        assert_eq!(state.transfers_done, 1);
+
+        state.update_selected_s3_transfers(child("single.txt", false));
+        assert_eq!(state.transfers_total, 3);
+        assert_eq!(state.transfers_done, 2);
+    }
+
+    #[test]
+    fn directory_rows_count_their_files_once() {
+        let mut state = State::default();
+        let child = |name: &str| S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: name.into(),
+            path: Some(format!("dir/{}", name)),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let failed = |name: &str| S3SelectedItem {
+            error: Some("timeout".into()),
+            ..child(name)
+        };
+        let mut transferred = child("a.txt");
+        transferred.transferred = true;
+        state.add_s3_selected_item(S3SelectedItem {
+            name: "dir/".into(),
+            path: Some("dir/".into()),
+            is_directory: true,
+            children: Some(vec![transferred, child("b.txt"), child("c.txt")]),
+            ..child("dir/")
+        });
+        let files = |state: &State| {
+            let item = TransferItem::from_s3_selected_item(state.s3_selected_items[0].clone());
+            (item.files, item.to_columns()[6].clone())
+        };
+        assert_eq!(files(&state), (Some((1, 3)), "1 / 3".to_string()));
+
+        // a file finishing again, like the ones skipped by a rerun, is counted once
+        state.update_selected_s3_transfers(child("b.txt"));
+        state.update_selected_s3_transfers(child("b.txt"));
+        state.update_selected_s3_transfers(child("a.txt"));
+        assert_eq!(files(&state).0, Some((2, 3)));
+
+        // a failed file counts when its retry succeeds
+        state.update_selected_s3_transfers(failed("c.txt"));
+        assert_eq!(files(&state).0, Some((2, 3)));
+        assert!(!state.s3_selected_items[0].transferred);
+        state.update_selected_s3_transfers(child("c.txt"));
+        state.update_selected_s3_transfers(failed("c.txt"));
+        assert_eq!(files(&state).0, Some((3, 3)));
+        assert!(state.s3_selected_items[0].transferred);
+        assert_eq!((state.transfers_done, state.transfers_total), (3, 3));
+
+        // deleted files leave both counts
+        state.finish_bulk_deletion(BulkDeletion {
+            deleted_s3: vec![("test-bucket".into(), "dir/a.txt".into())],
+            deleted_local: vec![],
+            failed: vec![],
+        });
+        assert_eq!(files(&state).0, Some((2, 2)));
+        assert_eq!((state.transfers_done, state.transfers_total), (2, 2));
+        assert_eq!(
+            TransferItem::from_s3_selected_item(child("single.txt")).files,
+            None
+        );
+    }
+
+    #[test]
+    fn directory_uploads_count_their_files_once() {
+        let mut state = State::default();
+        let file = |name: &str| {
+            LocalSelectedItem::new(
+                name.into(),
+                format!("/tmp/dir/{}", name),
+                false,
+                "test-bucket".into(),
+                "".into(),
+                String::new(),
+                None,
+            )
+        };
+        state.add_local_selected_item(LocalSelectedItem {
+            is_directory: true,
+            children: Some(vec![file("a.txt"), file("b.txt")]),
+            ..file("dir")
+        });
+        state.update_selected_local_transfers(file("a.txt"));
+        state.update_selected_local_transfers(file("a.txt"));
+        state.update_selected_local_transfers(LocalSelectedItem {
+            error: Some("timeout".into()),
+            ..file("b.txt")
+        });
+        let item = TransferItem::from_local_selected_item(state.local_selected_items[0].clone());
+        assert_eq!(item.files, Some((1, 2)));
+        assert!(!item.transferred);
+
+        state.update_selected_local_transfers(file("b.txt"));
+        let item = TransferItem::from_local_selected_item(state.local_selected_items[0].clone());
+        assert_eq!(item.files, Some((2, 2)));
+        assert!(item.transferred);
+    }
+
+    #[test]
+    fn update_progress_on_selected_local_item_updates_correctly() {
+        let mut state = State::default();
+        let selected_item = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+
+        state.local_selected_items.push(selected_item.clone());
+        let progress_item = UploadProgressItem {
+            progress: 0.5,
+            bucket: "test-bucket".into(),
+            key: "file1.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        };
+        state.update_progress_on_selected_local_item(progress_item);
+
+        assert_eq!(state.local_selected_items[0].progress, 0.5);
+    }
+
+    #[test]
+    fn update_progress_on_selected_s3_item_updates_correctly() {
+        let mut state = State::default();
+        let item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: true,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+
+        state.s3_selected_items.push(item.clone());
+        let progress_item = DownloadProgressItem {
+            progress: 0.5,
+            bucket: "test-bucket".to_string(),
+            key: "path/to/file1.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        };
+        state.update_progress_on_selected_s3_item(progress_item);
+
+        assert_eq!(state.s3_selected_items[0].progress, 0.5);
+    }
+
+    #[test]
+    fn nearly_done_directory_download_is_not_transferred_until_every_file_is() {
+        let mut state = State::default();
+        let child = |i: usize| S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: format!("{}.txt", i),
+            path: Some(format!("dir/{}.txt", i)),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: i > 0,
+            account: String::new(),
+            progress: if i > 0 { 100f64 } else { 0f64 },
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.s3_selected_items.push(S3SelectedItem {
+            name: "dir/".into(),
+            path: Some("dir/".into()),
+            is_directory: true,
+            children: Some((0..1000).map(child).collect()),
+            ..child(0)
+        });
+        state.update_progress_on_selected_s3_item(DownloadProgressItem {
+            progress: 100f64 - 1e-13,
+            bucket: "test-bucket".to_string(),
+            key: "dir/0.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        });
+        // the mean rounds up to 100 while the last file is still downloading
+        assert_eq!(state.s3_selected_items[0].progress, 100f64);
+        assert!(!state.s3_selected_items[0].transferred);
+        state.remove_already_transferred_items();
+        assert_eq!(state.s3_selected_items.len(), 1);
+
+        state.update_selected_s3_transfers(child(0));
+        assert!(state.s3_selected_items[0].transferred);
+    }
+
+    #[test]
+    fn nearly_done_directory_upload_is_not_transferred_until_every_file_is() {
+        let mut state = State::default();
+        let child = |i: usize| LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: i > 0,
+            name: format!("{}.txt", i),
+            path: format!("dir/{}.txt", i),
+            progress: if i > 0 { 100f64 } else { 0f64 },
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.local_selected_items.push(LocalSelectedItem {
+            name: "dir".into(),
+            path: "dir".into(),
+            is_directory: true,
+            children: Some((0..1000).map(child).collect()),
+            ..child(0)
+        });
+        state.update_progress_on_selected_local_item(UploadProgressItem {
+            progress: 100f64 - 1e-13,
+            bucket: "test-bucket".into(),
+            key: "0.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        });
+        assert_eq!(state.local_selected_items[0].progress, 100f64);
+        assert!(!state.local_selected_items[0].transferred);
+        state.remove_already_transferred_items();
+        assert_eq!(state.local_selected_items.len(), 1);
+
+        state.update_selected_local_transfers(child(0));
+        assert!(state.local_selected_items[0].transferred);
+    }
+
+    #[test]
+    fn update_local_item_with_progress_updates_the_item() {
+        let mut state = State::default();
+        let selected_item = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.local_selected_items = vec![selected_item];
+        let progress_item = UploadProgressItem {
+            progress: 50.0,
+            bucket: "test-bucket".into(),
+            key: "file1.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        };
+        state.update_local_item_with_progress(state.local_selected_items.clone(), progress_item);
+        assert_eq!(state.local_selected_items[0].progress, 50.0);
+    }
+
+    #[test]
+    fn update_local_item_with_progress_updates_child_correctly() {
+        let mut state = State::default();
+        let child = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "file1.txt".into(),
+            path: "path/to/file1.txt".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let selected_item = LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: "".to_string(),
+            transferred: false,
+            name: "/".into(),
+            path: "path/to".into(),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: Some(vec![child]),
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.local_selected_items = vec![selected_item];
+        let progress_item = UploadProgressItem {
+            progress: 50.0,
+            bucket: "test-bucket".into(),
+            key: "file1.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        };
+        state.update_local_item_with_progress(state.local_selected_items.clone(), progress_item);
+        assert_eq!(
+            state.local_selected_items[0]
+                .clone()
+                .children
+                .unwrap_or_default()[0]
+                .progress,
+            50.0
+        );
+        assert_eq!(state.local_selected_items[0].progress, 50.0);
+    }
+
+    #[test]
+    fn uploads_of_the_same_file_name_keep_their_own_progress() {
+        let mut state = State::default();
+        let upload = |folder: &str| LocalSelectedItem {
+            destination_bucket: "test-bucket".into(),
+            destination_path: format!("{}/data.csv", folder),
+            transferred: false,
+            name: "data.csv".into(),
+            path: format!("/home/user/{}/data.csv", folder),
+            progress: 0.0,
+            is_directory: false,
+            account: Default::default(),
+            children: None,
+            error: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.local_selected_items = vec![upload("dirA"), upload("dirB")];
+        let progress = |key: &str, progress: f64| UploadProgressItem {
+            progress,
+            bucket: "test-bucket".into(),
+            key: key.into(),
+            bytes: 0,
+            total_bytes: 0,
+        };
+        state.update_progress_on_selected_local_item(progress("dirA/data.csv", 40.0));
+        state.update_progress_on_selected_local_item(progress("dirB/data.csv", 10.0));
+        state.update_progress_on_selected_local_item(progress("data.csv", 90.0));
+        assert_eq!(state.local_selected_items[0].progress, 40.0);
+        assert_eq!(state.local_selected_items[1].progress, 10.0);
+
+        state.update_selected_local_transfers(upload("dirB"));
+        assert!(!state.local_selected_items[0].transferred);
+        assert!(state.local_selected_items[1].transferred);
+    }
+
+    #[test]
+    fn update_s3_item_with_progress_updates_the_item() {
+        let mut state = State::default();
+        let selected_item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: true,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.s3_selected_items = vec![selected_item];
+        let progress_item = DownloadProgressItem {
+            progress: 50.0,
+            bucket: "test-bucket".into(),
+            key: "path/to/file1.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        };
+        state.update_s3_item_with_progress(state.s3_selected_items.clone(), progress_item);
+        assert_eq!(state.s3_selected_items[0].progress, 50.0);
+    }
+
+    #[test]
+    fn update_s3_item_with_progress_updates_child_correctly() {
+        let mut state = State::default();
+        let child = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "file1.txt".into(),
+            path: Some("path/to/file1.txt".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: true,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let selected_item = S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "/".into(),
+            path: Some("path/to".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: true,
+            account: String::new(),
+            progress: 0.0,
+            children: Some(vec![child.clone()]),
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.s3_selected_items = vec![selected_item];
+        let progress_item = DownloadProgressItem {
+            progress: 50.0,
+            bucket: "test-bucket".into(),
+            key: "path/to/file1.txt".into(),
+            bytes: 0,
+            total_bytes: 0,
+        };
+        state.update_s3_item_with_progress(state.s3_selected_items.clone(), progress_item);
+        assert_eq!(
+            state.s3_selected_items[0]
+                .clone()
+                .children
+                .unwrap_or_default()[0]
+                .progress,
+            50.0
+        );
+        assert_eq!(state.s3_selected_items[0].progress, 50.0);
+    }
+
+    #[test]
+    fn objects_of_the_same_name_in_different_folders_are_told_apart() {
+        let mut state = State::default();
+        let object = |key: &str| S3SelectedItem {
+            bucket: Some("test-bucket".to_string()),
+            name: "report.csv".into(),
+            path: Some(key.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "path/to/dest".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        state.add_s3_selected_item(object("2023/report.csv"));
+        state.add_s3_selected_item(S3SelectedItem {
+            name: "2024/".into(),
+            path: Some("2024/".into()),
+            is_directory: true,
+            children: Some(vec![object("2024/report.csv")]),
+            ..object("2024/")
+        });
+
+        state.update_progress_on_selected_s3_item(DownloadProgressItem {
+            progress: 50.0,
+            bucket: "test-bucket".into(),
+            key: "2024/report.csv".into(),
+            bytes: 0,
+            total_bytes: 0,
+        });
+        assert_eq!(state.s3_selected_items[0].progress, 0.0);
+        assert_eq!(state.s3_selected_items[1].progress, 50.0);
+
+        state.update_selected_s3_transfers(object("2023/report.csv"));
+        assert!(state.s3_selected_items[0].transferred);
+        assert!(!state.s3_selected_items[1].transferred);
+        assert_eq!(state.s3_selected_items[1].completed_children, 0);
+    }
+
+    #[test]
+    fn stats_are_updated_along_with_the_state() {
+        let mut state = State::default();
+        let s3_item = S3DataItem {
+            bucket: Some("test-bucket".into()),
+            name: "file1.txt".into(),
+            size: "1 KB".into(),
+            file_type: "txt".into(),
+            path: "file1.txt".into(),
+            is_directory: false,
+            is_bucket: false,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
+        };
+        let local_item = LocalDataItem::init("a.txt".into(), "1 KB".into(), "txt", "/a.txt", false);
+
+        state.update_buckets(
+            Some("test-bucket".into()),
+            None,
+            vec![s3_item.clone(), s3_item],
+        );
+        state.update_files("/".into(), vec![local_item]);
+        state.set_s3_delete_error(None);
+        state.set_local_delete_error(Some("permission denied".into()));
+        state.set_create_bucket_error(None);
+        state.stats.record_download(&Ok(10));
+
+        assert_eq!(state.stats.listings, 2);
+        assert_eq!(state.stats.objects_browsed, 3);
+        assert_eq!(state.stats.deletions, 1);
+        assert_eq!(state.stats.buckets_created, 1);
+        assert_eq!(state.stats.bytes_downloaded, 10);
+    }
+
+    #[test]
+    fn selected_directory_skips_configured_exclusions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("docs/build")).unwrap();
+        std::fs::write(dir.path().join("docs/index.md"), b"index").unwrap();
+        std::fs::write(dir.path().join("docs/build/index.html"), b"html").unwrap();
+        let mut state = State::default();
+        state.app_config.exclude_patterns = vec!["build/".into()];
+        let item = LocalSelectedItem::new(
+            "docs".into(),
+            dir.path().join("docs").to_string_lossy().into(),
+            true,
+            "test-bucket".into(),
+            "docs".into(),
+            Default::default(),
+            None,
+        );
+
+        state.add_local_selected_item(item);
+
+        assert_eq!(state.transfers_total, 1);
+        assert_eq!(
+            state.local_selected_items[0]
+                .children
+                .clone()
+                .unwrap_or_default()[0]
+                .name,
+            "index.md"
+        );
+    }
+
+    #[test]
+    fn transfers_with_a_removed_credential_fail_with_its_name() {
+        let mut state = State::default();
+        let item = LocalSelectedItem::new(
+            "a.txt".into(),
+            "/tmp/a.txt".into(),
+            false,
+            "bucket".into(),
+            "a.txt".into(),
+            "old-account".into(),
+            None,
+        );
+        state.add_local_selected_item(item.clone());
+
+        state.fail_missing_credentials(TransferJob::Upload(item));
+        assert_eq!(
+            state.local_selected_items[0].error,
+            Some("credential 'old-account' is no longer configured".into())
+        );
+    }
+
+    #[test]
+    fn stopping_the_network_cancels_only_the_started_transfers() {
+        let mut state = State::default();
+        let upload = |name: &str| {
+            LocalSelectedItem::new(
+                name.into(),
+                format!("/tmp/{}", name),
+                false,
+                "bucket".into(),
+                name.into(),
+                String::new(),
+                None,
+            )
+        };
+        state.add_local_selected_item(upload("started.txt"));
+        state.add_local_selected_item(upload("queued.txt"));
+        let jobs = [
+            TransferJob::Upload(upload("started.txt")),
+            TransferJob::Upload(upload("queued.txt")),
+        ];
+        state.start_transfer_batch(&jobs);
+        state.record_dispatch(
+            jobs[0].key(),
+            DispatchOrder {
+                sequence: 1,
+                total: 2,
+            },
+        );
+        state.set_s3_loading(true);
+        state.start_object_metadata_request();
+        state.start_object_preview();
+
+        state.stop_network_activity(3);
+        assert_eq!(
+            state.local_selected_items[0].error,
+            Some("cancelled".into())
+        );
+        assert_eq!(state.local_selected_items[1].error, None);
+        assert!(!state.s3_loading);
+        assert!(!state.object_metadata_loading);
+        assert!(!state.object_preview_loading);
+        assert_eq!(
+            state.notification.unwrap().message,
+            "Network activity stopped: 3 task(s) and 1 transfer(s) cancelled"
+        );
+        // a transfer finishing after the cancellation doesn't complete the old batch
+        assert_eq!(
+            state.finish_upload(&upload("queued.txt")),
+            BatchRefresh::default()
+        );
+    }
+
+    #[test]
+    fn paused_transfers_resume_unless_they_still_wait_for_their_turn() {
+        let mut state = State::default();
+        let upload = |name: &str| {
+            LocalSelectedItem::new(
+                name.into(),
+                format!("/tmp/{}", name),
+                false,
+                "bucket".into(),
+                name.into(),
+                String::new(),
+                None,
+            )
+        };
+        for name in ["started.txt", "waiting.txt", "other.txt"] {
+            state.add_local_selected_item(upload(name));
+        }
+        let jobs = vec![
+            TransferJob::Upload(upload("started.txt")),
+            TransferJob::Upload(upload("waiting.txt")),
+            TransferJob::Upload(upload("other.txt")),
+        ];
+        let keys: Vec<String> = jobs.iter().map(TransferJob::key).collect();
+        state.start_transfer_batch(&jobs);
+        state.record_dispatch(
+            keys[0].clone(),
+            DispatchOrder {
+                sequence: 1,
+                total: 3,
+            },
+        );
+
+        assert_eq!(
+            state.stop_transfers(&keys[..2], TransferStop::Paused),
+            keys[..2]
+        );
+        assert_eq!(state.without_paused(jobs.clone()), vec![jobs[2].clone()]);
+        // the started upload stopped, the waiting one runs when the queue gets to it
+        assert_eq!(
+            state.finish_stopped_transfer(&keys[0]),
+            BatchRefresh::default()
+        );
+        let resumed: Vec<String> = state
+            .resume_transfers(&keys[..2])
+            .iter()
+            .map(TransferJob::key)
+            .collect();
+        assert_eq!(resumed, vec![keys[0].clone()]);
+        assert!(state.paused_transfers.is_empty());
+
+        assert_eq!(
+            state.stop_transfers(&keys[2..], TransferStop::Cancelled),
+            keys[2..]
+        );
+        assert_eq!(state.local_selected_items[2].error, Some(CANCELLED.into()));
+        assert_eq!(state.local_selected_items[1].error, None);
+    }
+
+    #[test]
+    fn s3_history_is_kept_per_account_and_restores_the_row() {
         let creds = vec![
             FileCredential {
-                name: "AWS".into(),
-                access_key: "".to_string(),
-                secret_key: "".to_string(),
-                default_region: "".to_string(),
-                selected: false,
+                name: "first".into(),
+                selected: true,
+                ..Default::default()
             },
             FileCredential {
-                name: "Azure".into(),
-                access_key: "".to_string(),
-                secret_key: "".to_string(),
-                default_region: "".to_string(),
-                selected: false,
+                name: "second".into(),
+                ..Default::default()
             },
         ];
-        let state = State::new(creds.clone());
-        assert_eq!(state.current_creds, state.current_creds);
+        let mut state = State::new(creds.clone());
+        let into = |prefix: &str| {
+            NavigationStep::Into(NavigationState::new(
+                Some("bucket".into()),
+                Some(prefix.into()),
+            ))
+        };
+        state.navigate_s3(into("a/"), Some(4));
+        state.navigate_s3(into("a/b/"), Some(2));
+
+        state.set_current_s3_creds(creds[1].clone());
+        assert_eq!(state.s3_location(), NavigationState::new(None, None));
+        assert_eq!(state.navigate_s3(NavigationStep::Back, None), None);
+
+        state.set_current_s3_creds(creds[0].clone());
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Back, Some(0)),
+            Some(NavigationState::new(
+                Some("bucket".into()),
+                Some("a/".into())
+            ))
+        );
+        state.s3_listing_request = 1;
+        state.receive_s3_listing(1, Some("bucket".into()), Some("a/".into()), Vec::new());
+        assert_eq!(state.s3_row_restore, Some((1, 2)));
+        assert_eq!(state.pending_s3_row, None);
+    }
+
+    #[test]
+    fn bucket_list_is_shown_again_on_switching_back_until_invalidated() {
+        let creds = vec![
+            FileCredential {
+                name: "first".into(),
+                selected: true,
+                ..Default::default()
+            },
+            FileCredential {
+                name: "second".into(),
+                ..Default::default()
+            },
+        ];
+        let mut state = State::new(creds.clone());
+        let bucket = S3DataItem::listed(
+            BucketInfo {
+                bucket: None,
+                region: None,
+                is_bucket: true,
+                creation_date: None,
+            },
+            "data",
+            "".into(),
+            false,
+        );
+        state.s3_listing_request = 1;
+        state.receive_s3_listing(1, None, None, vec![bucket]);
+        assert!(state.cached_buckets_listed_at.is_none());
+
+        state.set_current_s3_creds(creds[1].clone());
+        assert!(!state.show_cached_buckets(Instant::now()));
+        state.set_current_s3_creds(creds[0].clone());
+        assert!(state.show_cached_buckets(Instant::now()));
+        assert_eq!(state.s3_data[0].name, "data");
+        assert!(state.cached_buckets_listed_at.is_some());
+        let expired = Instant::now() + state.app_config.bucket_cache_ttl;
+        assert!(!state.show_cached_buckets(expired));
+
+        // e.g. after creating a bucket
+        state.invalidate_bucket_list();
+        assert!(!state.show_cached_buckets(Instant::now()));
+    }
+
+    #[test]
+    fn resolved_bucket_regions_fill_the_rows_and_the_cached_list() {
+        let mut state = State::default();
+        state.current_creds.name = "first".into();
+        let bucket = |name: &str, region: Option<&str>| {
+            let bucket_info = BucketInfo {
+                bucket: None,
+                region: region.map(String::from),
+                is_bucket: true,
+                creation_date: None,
+            };
+            S3DataItem::listed(bucket_info, name, "".into(), false)
+        };
+        state.s3_listing_request = 1;
+        let buckets = vec![bucket("data", None), bucket("logs", Some("us-east-1"))];
+        state.receive_s3_listing(1, None, None, buckets);
+        assert_eq!(state.unresolved_bucket_regions(), vec!["data"]);
+
+        state.set_bucket_region("other", "data", "ap-south-1".into());
+        assert_eq!(state.s3_data[0].region, None);
+        state.set_bucket_region("first", "data", "eu-west-1".into());
+        assert_eq!(state.s3_data[0].region, Some("eu-west-1".into()));
+        assert!(state.unresolved_bucket_regions().is_empty());
+
+        // the cached list shows the region when the account is selected again
+        state.s3_data.clear();
+        assert!(state.show_cached_buckets(Instant::now()));
+        assert_eq!(state.s3_data[0].to_columns()[4], "eu-west-1");
+
+        state.current_s3_bucket = Some("data".into());
+        assert!(state.unresolved_bucket_regions().is_empty());
+    }
+
+    #[test]
+    fn listing_fills_the_panel_batch_by_batch() {
+        let bucket_info = BucketInfo {
+            bucket: Some("logs".into()),
+            region: Some("eu-west-1".into()),
+            is_bucket: false,
+            creation_date: None,
+        };
+        let object = |key: &str| S3DataItem::listed(bucket_info.clone(), key, "".into(), false);
+        let mut state = State::default();
+        state.s3_data = vec![object("previous.log")];
+        state.s3_listing_request = 2;
+        state.set_s3_loading(true);
+        let location = (Some("logs".to_string()), Some("2024/".to_string()));
+
+        assert!(!state.receive_s3_batch(1, None, None, vec![object("stale.log")]));
+        assert!(!state.receive_s3_batch(2, location.0.clone(), location.1.clone(), vec![]));
+        assert_eq!(state.s3_data[0].name, "previous.log");
+        let first = vec![object("2024/a.log"), object("2024/b.log")];
+        assert!(state.receive_s3_batch(2, location.0.clone(), location.1.clone(), first));
+        assert!(state.receive_s3_batch(
+            2,
+            location.0.clone(),
+            location.1.clone(),
+            vec![object("2024/c.log")]
+        ));
+        assert_eq!(state.s3_data.len(), 3);
+        assert_eq!(state.current_s3_path, location.1);
+        assert!(state.s3_loading);
+
+        let data = state.take_streamed_s3_data(2);
+        assert!(state.receive_s3_listing(2, location.0, location.1, data));
+        assert_eq!(state.s3_data.len(), 3);
+        assert!(!state.s3_loading);
+        assert_eq!(state.streamed_s3_listing, None);
+    }
+
+    #[test]
+    fn cancelled_listing_keeps_the_rows_listed_so_far() {
+        let bucket_info = BucketInfo {
+            bucket: Some("logs".into()),
+            region: None,
+            is_bucket: false,
+            creation_date: None,
+        };
+        let object = S3DataItem::listed(bucket_info, "a.log", "".into(), false);
+        let mut state = State::default();
+        assert!(!state.cancel_s3_listing());
+        state.s3_listing_request = 1;
+        state.set_s3_loading(true);
+        state.receive_s3_batch(1, Some("logs".into()), None, vec![object.clone()]);
+
+        assert!(state.cancel_s3_listing());
+        assert!(!state.s3_loading);
+        // batches sent before the task was aborted are ignored
+        assert!(!state.receive_s3_batch(1, Some("logs".into()), None, vec![object]));
+        assert!(state.take_streamed_s3_data(1).is_empty());
+        assert_eq!(state.s3_data.len(), 1);
+        assert!(state.s3_listing_partial);
+        assert!(state
+            .notification
+            .as_ref()
+            .is_some_and(|n| n.message.contains("1 rows")));
+    }
+
+    #[tokio::test]
+    async fn listing_failing_on_the_second_page_is_partial() {
+        use crate::services::s3_listing::{list_pages, ListedObject, ListedPage, PageRequest};
+        use color_eyre::eyre;
+        use futures::StreamExt;
+
+        let bucket_info = BucketInfo {
+            bucket: Some("logs".into()),
+            region: None,
+            is_bucket: false,
+            creation_date: None,
+        };
+        let fetch = |request: PageRequest| async move {
+            match request.continuation_token {
+                None => Ok(ListedPage {
+                    objects: vec![ListedObject {
+                        key: "a.log".into(),
+                        ..ListedObject::default()
+                    }],
+                    common_prefixes: Vec::new(),
+                    next_token: Some("t1".into()),
+                }),
+                Some(_) => Err(eyre::eyre!("SlowDown")),
+            }
+        };
+        let mut state = State::default();
+        state.s3_listing_request = 1;
+        state.set_s3_loading(true);
+        let mut pages = Box::pin(list_pages(None, false, fetch));
+        while let Some(page) = pages.next().await {
+            match page {
+                Ok(page) => {
+                    let batch = page
+                        .objects
+                        .iter()
+                        .map(|object| S3DataItem::listed_object(bucket_info.clone(), object))
+                        .collect();
+                    state.receive_s3_batch(1, Some("logs".into()), None, batch);
+                }
+                Err(e) => {
+                    state.fail_s3_listing(1, Some("logs".into()), None, e.to_string());
+                }
+            }
+        }
+        assert_eq!(state.s3_data.len(), 1);
+        assert!(state.s3_listing_partial);
+
+        // listing the location again completes it
+        state.s3_listing_request = 2;
+        assert!(state.receive_s3_listing(2, Some("logs".into()), None, Vec::new()));
+        assert!(!state.s3_listing_partial);
+    }
+
+    #[test]
+    fn failed_bucket_list_is_not_retried_right_away() {
+        let mut state = State::default();
+        state.s3_listing_request = 2;
+        state.set_s3_loading(true);
+        assert!(!state.fail_s3_listing(1, None, None, "AccessDenied".into()));
+        assert!(state.fail_s3_listing(2, None, None, "AccessDenied".into()));
+        assert!(!state.s3_loading);
+
+        state.notification = None;
+        assert!(state.show_cached_buckets(Instant::now()));
+        assert!(state.s3_data.is_empty());
+        assert!(state
+            .notification
+            .as_ref()
+            .is_some_and(|n| n.message.contains("AccessDenied")));
+
+        // failures inside a bucket aren't kept
+        let mut state = State::default();
+        state.s3_listing_request = 1;
+        state.fail_s3_listing(1, Some("data".into()), None, "timeout".into());
+        assert!(!state.show_cached_buckets(Instant::now()));
+    }
+
+    #[test]
+    fn copy_results_are_logged_and_failures_shown() {
+        let mut state = State::default();
+        state.current_s3_bucket = Some("production".into());
+        let copy = ObjectCopy {
+            source_bucket: "staging".into(),
+            source_key: "q1.csv".into(),
+            destination_bucket: "production".into(),
+            destination_key: "reports/q1.csv".into(),
+            delete_source: true,
+        };
+        assert!(state.finish_s3_copy(&copy, Err("AccessDenied".into())));
+        let message =
+            "Moving s3://staging/q1.csv to s3://production/reports/q1.csv failed: AccessDenied";
+        assert_eq!(state.notification.as_ref().unwrap().message, message);
+        assert_eq!(state.activity_log.entries()[0].severity, Severity::Error);
+
+        state.current_s3_bucket = Some("logs".into());
+        assert!(!state.finish_s3_copy(&copy, Ok(())));
+        assert_eq!(
+            state.activity_log.entries()[1].message,
+            "Moved s3://staging/q1.csv to s3://production/reports/q1.csv"
+        );
+    }
+
+    #[test]
+    fn imported_selection_skips_duplicates_and_missing_entries() {
+        use crate::services::selection_file::ImportChecks;
+
+        let mut state = State::default();
+        state.current_local_path = "/home/me/Downloads".into();
+        let download = |key: &str| SelectionEntry::Download {
+            bucket: "logs".into(),
+            key: key.into(),
+            destination: None,
+        };
+        let entries = vec![
+            download("2024/a.log"),
+            download("2024/gone.log"),
+            download("2024/a.log"),
+            SelectionEntry::Download {
+                bucket: "deleted".into(),
+                key: "b.log".into(),
+                destination: None,
+            },
+            SelectionEntry::Upload {
+                path: "/home/me/notes.txt".into(),
+                bucket: "backup".into(),
+                destination: "docs/notes.txt".into(),
+            },
+        ];
+        let checks = ImportChecks {
+            missing_buckets: HashSet::from(["deleted".to_string()]),
+            missing_keys: HashSet::from([("logs".to_string(), "2024/gone.log".to_string())]),
+            missing_files: HashSet::new(),
+        };
+        let import = SelectionImport {
+            path: "/tmp/selection.json".into(),
+            entries,
+            checks,
+        };
+        let summary = state.merge_selection(import.clone());
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 2,
+                duplicates: 1,
+                invalid: 2
+            }
+        );
+        assert_eq!(
+            state.notification.as_ref().unwrap().message,
+            "Imported /tmp/selection.json: 2 added, 1 already selected, 2 invalid"
+        );
+        let item = &state.s3_selected_items[0];
+        assert_eq!(item.name, "a.log");
+        assert_eq!(item.destination_dir, "/home/me/Downloads");
+        assert_eq!(
+            state.local_selected_items[0].destination_key(),
+            "docs/notes.txt"
+        );
+
+        // importing the file again adds nothing
+        assert_eq!(state.merge_selection(import).added, 0);
+    }
+
+    #[test]
+    fn failed_account_checks_are_logged() {
+        let mut state = State::default();
+        state.finish_account_check("dev".into(), AccountCheck::Passed);
+        assert!(state.activity_log.entries().is_empty());
+
+        state.finish_account_check("prod".into(), AccountCheck::Failed("timed out".into()));
+        assert_eq!(state.activity_log.entries()[0].severity, Severity::Warning);
+        assert_eq!(
+            state.activity_log.entries()[0].message,
+            "Account prod doesn't respond: timed out"
+        );
+        assert_eq!(state.account_checks.len(), 2);
+    }
+
+    #[test]
+    fn rename_onto_an_existing_object_waits_for_the_confirmation() {
+        let mut state = State::default();
+        state.current_s3_bucket = Some("staging".into());
+        let item = S3SelectedItem {
+            bucket: Some("staging".into()),
+            name: "q1.csv".into(),
+            path: Some("reports/q1.csv".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "".to_string(),
+            transferred: false,
+            account: Default::default(),
+            progress: 0f64,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let rename = ObjectCopy::renamed(&item, "reports/q1-final.csv".into()).unwrap();
+
+        assert!(!state.finish_s3_rename(item.clone(), &rename, Ok(false)));
+        assert_eq!(
+            state.rename_conflict,
+            Some((item.clone(), "reports/q1-final.csv".into()))
+        );
+        assert!(state.finish_s3_rename(item, &rename, Ok(true)));
+        assert_eq!(state.rename_conflict, None);
+        assert_eq!(state.rename_result, 2);
+        assert_eq!(
+            state.activity_log.entries()[1].message,
+            "Moved s3://staging/reports/q1.csv to s3://staging/reports/q1-final.csv"
+        );
+    }
+
+    #[test]
+    fn renamed_local_file_is_highlighted_after_the_listing() {
+        let mut state = State::default();
+        state.current_local_path = "/home/user/notes".into();
+        let item = LocalDataItem::init(
+            "draft.txt".into(),
+            "1 KB".into(),
+            "txt",
+            "/home/user/notes/draft.txt",
+            false,
+        );
+
+        assert!(!state.finish_local_rename(item.clone(), "todo.txt".into(), Ok(false)));
+        assert_eq!(
+            state.local_rename_conflict,
+            Some((item.clone(), "todo.txt".into()))
+        );
+        assert!(state.finish_local_rename(item.clone(), "todo.txt".into(), Ok(true)));
+        assert_eq!(state.local_rename_conflict, None);
+        assert_eq!(
+            state.pending_jump,
+            Some(JumpTarget::Local {
+                directory: "/home/user/notes".into(),
+                item_name: Some("todo.txt".into()),
+            })
+        );
+        state.receive_local_listing("/home/user/notes".into(), Vec::new());
+        assert!(state.file_manager_focus.is_some());
+
+        assert!(!state.finish_local_rename(item, "todo.txt".into(), Err("denied".into())));
+        assert_eq!(
+            state.local_delete_state.as_deref(),
+            Some("Renaming /home/user/notes/draft.txt failed: denied")
+        );
+        assert_eq!(state.rename_result, 3);
+    }
+
+    #[test]
+    fn configured_download_dir_replaces_the_local_panel_directory() {
+        let mut state = State::default();
+        state.current_local_path = "/home/user/projects".into();
+        assert_eq!(state.download_dir(), "/home/user/projects");
+
+        state.app_config.default_download_dir = Some("/home/user/Downloads".into());
+        assert_eq!(state.download_dir(), "/home/user/Downloads");
+
+        state.current_creds.default_download_dir = Some("/mnt/work".into());
+        assert_eq!(state.download_dir(), "/mnt/work");
+        assert_eq!(state.configured_download_dir(), Some("/mnt/work".into()));
+    }
+
+    #[test]
+    fn repeated_navigation_adds_one_history_entry() {
+        let mut state = State::default();
+        let into = NavigationStep::Into(NavigationState::new(
+            Some("bucket".into()),
+            Some("a/".into()),
+        ));
+        for _ in 0..3 {
+            assert!(state.navigate_s3(into.clone(), Some(1)).is_some());
+        }
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Back, None),
+            Some(NavigationState::new(None, None))
+        );
+        assert_eq!(state.navigate_s3(NavigationStep::Back, None), None);
+    }
+
+    fn state_with_default_prefix(prefix: &str) -> State {
+        let mut state = State::default();
+        state.current_creds.name = "default".into();
+        state.app_config.default_prefixes = vec![DefaultPrefix {
+            account: "default".into(),
+            bucket: "data".into(),
+            prefix: prefix.into(),
+        }];
+        state
+    }
+
+    #[test]
+    fn entering_a_bucket_opens_its_default_prefix() {
+        let mut state = state_with_default_prefix("team/curated/v2/");
+        let at = |prefix: Option<&str>| {
+            NavigationState::new(Some("data".into()), prefix.map(String::from))
+        };
+
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Into(at(None)), Some(3)),
+            Some(at(Some("team/curated/v2/")))
+        );
+        assert!(state.notification.is_some());
+        // back goes up one directory at a time and restores the row of the bucket list
+        for expected in [Some("team/curated/"), Some("team/"), None] {
+            assert_eq!(
+                state.navigate_s3(NavigationStep::Back, None),
+                Some(at(expected))
+            );
+        }
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Back, None),
+            Some(NavigationState::new(None, None))
+        );
+        assert_eq!(state.pending_s3_row, Some(3));
+
+        // other buckets, and the root reached from inside the bucket, aren't redirected
+        let other = NavigationState::new(Some("logs".into()), None);
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Into(other.clone()), None),
+            Some(other)
+        );
+    }
+
+    #[test]
+    fn invalid_or_missing_default_prefix_falls_back_to_the_bucket_root() {
+        let root = NavigationState::new(Some("data".into()), None);
+        let mut state = state_with_default_prefix("team//v2/");
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Into(root.clone()), None),
+            Some(root.clone())
+        );
+        assert!(state.s3_landing.is_none());
+        assert!(state.notification.is_some());
+
+        let mut state = state_with_default_prefix("gone/");
+        state.navigate_s3(NavigationStep::Into(root.clone()), None);
+        state.s3_listing_request = 1;
+        let bucket = Some("data".to_string());
+        assert_eq!(
+            state.missing_landing_fallback(1, &bucket, &Some("gone/".into()), &[]),
+            Some(root.clone())
+        );
+        assert_eq!(state.s3_location(), root);
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Back, None),
+            Some(NavigationState::new(None, None))
+        );
+        assert_eq!(state.navigate_s3(NavigationStep::Forward, None), Some(root));
+        assert_eq!(state.navigate_s3(NavigationStep::Forward, None), None);
+    }
+
+    #[test]
+    fn pending_transfers_subset_leaves_other_items_queued() {
+        let mut state = State::default();
+        let items: Vec<S3SelectedItem> = (0..3)
+            .map(|i| S3SelectedItem {
+                bucket: Some("test-bucket".into()),
+                name: format!("file{}.txt", i),
+                path: Some(format!("file{}.txt", i)),
+                is_directory: false,
+                is_bucket: false,
+                destination_dir: "".into(),
+                transferred: i == 2,
+                account: String::new(),
+                progress: 0.0,
+                children: None,
+                error: None,
+                destination_path: None,
+                verification: None,
+                completed_children: 0,
+            })
+            .collect();
+        for item in items.iter() {
+            state.add_s3_selected_item(item.clone());
+        }
+
+        let (s3, local) =
+            state.pending_transfers_subset(&[items[1].clone(), items[2].clone()], &[]);
+
+        assert_eq!(s3, vec![items[1].clone()]);
+        assert!(local.is_empty());
+        assert_eq!(state.s3_selected_items.len(), 3);
+        assert!(!state.s3_selected_items[0].transferred);
     }
 
     #[test]
-    fn set_current_s3_creds_set_creds_correctly_for_existing_state() {
+    fn stale_listing_does_not_clear_loading_of_newer_request() {
+        let mut state = State::default();
+        state.set_s3_loading(true);
+        state.s3_listing_request = 2;
+
+        let applied = state.update_buckets_for_request(1, Some("parent".into()), None, vec![]);
+        assert!(!applied);
+        assert!(state.s3_loading);
+        assert_eq!(state.current_s3_bucket, None);
+
+        let applied = state.update_buckets_for_request(2, Some("child".into()), None, vec![]);
+        assert!(applied);
+        assert!(!state.s3_loading);
+        assert_eq!(state.current_s3_bucket, Some("child".into()));
+    }
+
+    fn s3_jump_target(prefix: Option<&str>) -> JumpTarget {
+        JumpTarget::S3 {
+            bucket: "test-bucket".into(),
+            prefix: prefix.map(String::from),
+            item_name: Some("file1.txt".into()),
+            account: String::new(),
+        }
+    }
+
+    #[test]
+    fn jump_to_missing_local_directory_only_notifies() {
+        let mut state = State::default();
+        state.set_active_page(ActivePage::Transfers);
+        let target = JumpTarget::Local {
+            directory: "/definitely/not/existing/s3tui".into(),
+            item_name: None,
+        };
+
+        assert!(!state.begin_jump(target));
+        assert!(state.pending_jump.is_none());
+        assert_eq!(state.active_page, ActivePage::Transfers);
+        assert_eq!(
+            state.notification.map(|n| n.message),
+            Some("/definitely/not/existing/s3tui no longer exists".to_string())
+        );
+    }
+
+    #[test]
+    fn local_jump_completes_when_its_directory_is_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        let directory = dir.path().to_string_lossy().to_string();
+        let mut state = State::default();
+        state.set_active_page(ActivePage::Transfers);
+        let target = JumpTarget::Local {
+            directory: directory.clone(),
+            item_name: Some("a.txt".into()),
+        };
+        assert!(state.begin_jump(target.clone()));
+
+        state.receive_local_listing("/other".into(), Vec::new());
+        assert!(state.file_manager_focus.is_none());
+
+        state.receive_local_listing(directory, Vec::new());
+        assert_eq!(state.active_page, ActivePage::FileManager);
+        assert_eq!(
+            state.file_manager_focus,
+            Some(FileManagerFocus { id: 1, target })
+        );
+        assert!(state.pending_jump.is_none());
+    }
+
+    #[test]
+    fn s3_jump_to_empty_prefix_keeps_current_listing() {
+        let mut state = State::default();
+        state.set_active_page(ActivePage::Transfers);
+        state.update_buckets(Some("test-bucket".into()), None, Vec::new());
+        state.s3_listing_request = 3;
+        assert!(state.begin_jump(s3_jump_target(Some("gone/"))));
+
+        assert!(state.receive_s3_listing(
+            3,
+            Some("test-bucket".into()),
+            Some("gone/".into()),
+            Vec::new()
+        ));
+        assert_eq!(state.current_s3_path, None);
+        assert_eq!(state.active_page, ActivePage::Transfers);
+        assert!(state.file_manager_focus.is_none());
+        assert!(state.notification.is_some());
+    }
+
+    #[test]
+    fn leading_slash_listing_enables_the_quirk_for_the_account() {
         let creds = vec![
             FileCredential {
-                name: "AWS".into(),
-                access_key: "".to_string(),
-                secret_key: "".to_string(),
-                default_region: "".to_string(),
+                name: "minio".into(),
                 selected: true,
+                ..Default::default()
             },
             FileCredential {
-                name: "Azure".into(),
-                access_key: "".to_string(),
-                secret_key: "".to_string(),
-                default_region: "".to_string(),
-                selected: false,
+                name: "aws".into(),
+                ..Default::default()
             },
         ];
-        let mut state = State::new(creds.clone());
-        assert_eq!(state.current_creds, creds[0]);
+        let mut state = State::new(creds);
+        let bucket_info = BucketInfo {
+            bucket: Some("bucket".into()),
+            region: None,
+            is_bucket: false,
+            creation_date: None,
+        };
+        let plain = S3DataItem::listed(bucket_info.clone(), "docs/", "".into(), true);
+        state.s3_listing_request = 1;
+        assert!(state.receive_s3_listing(1, Some("bucket".into()), None, vec![plain]));
+        assert!(!state.current_creds.leading_slash_keys);
 
-        state.set_current_s3_creds(creds[1].clone());
-        assert_eq!(state.current_creds.name, creds[1].name);
+        let slashed = S3DataItem::listed(bucket_info, "/photos/", "".into(), true);
+        state.s3_listing_request = 2;
+        assert!(state.receive_s3_listing(2, Some("bucket".into()), None, vec![slashed]));
+        assert_eq!(state.s3_data[0].path, "photos/");
+        assert!(state.current_creds.leading_slash_keys);
+        assert!(state.creds[0].leading_slash_keys);
+        assert!(!state.creds[1].leading_slash_keys);
+
+        // the normalized key opens the directory like any other prefix
+        let location = state
+            .navigate_s3(
+                NavigationStep::Into(NavigationState::new(
+                    Some("bucket".into()),
+                    Some(state.s3_data[0].path.clone()),
+                )),
+                Some(0),
+            )
+            .unwrap();
+        assert_eq!(location.current_prefix, Some("photos/".into()));
+        assert_eq!(
+            api_key(location.current_prefix.as_deref().unwrap(), true),
+            "/photos/"
+        );
     }
 
     #[test]
-    fn add_and_remove_s3_selected_item_works() {
+    fn s3_jump_completes_with_the_matching_listing() {
         let mut state = State::default();
-        let item = S3SelectedItem {
+        state.set_active_page(ActivePage::Transfers);
+        state.s3_listing_request = 5;
+        let target = s3_jump_target(Some("reports/"));
+        assert!(state.begin_jump(target.clone()));
+        let item = S3DataItem {
             bucket: Some("test-bucket".into()),
             name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
+            size: "1 KB".into(),
+            file_type: "txt".into(),
+            path: "reports/file1.txt".into(),
             is_directory: false,
             is_bucket: false,
-            destination_dir: "".to_string(),
-            transferred: false,
-            s3_creds: Default::default(),
-            progress: 0f64,
-            children: None,
-            error: None,
+            region: None,
+            leading_slash: false,
+            last_modified: None,
+            storage_class: None,
+            creation_date: None,
         };
 
-        state.add_s3_selected_item(item.clone());
-        assert_eq!(state.s3_selected_items.len(), 1);
-        assert_eq!(state.s3_selected_items[0], item);
-
-        state.remove_s3_selected_item(item);
-        assert!(state.s3_selected_items.is_empty());
+        assert!(!state.receive_s3_listing(4, None, None, vec![item.clone()]));
+        assert!(state.receive_s3_listing(
+            5,
+            Some("test-bucket".into()),
+            Some("reports/".into()),
+            vec![item]
+        ));
+        assert_eq!(state.active_page, ActivePage::FileManager);
+        assert_eq!(
+            state.file_manager_focus,
+            Some(FileManagerFocus { id: 1, target })
+        );
     }
 
     #[test]
-    fn add_and_remove_local_selected_item_works() {
+    fn dispatch_order_of_directory_is_its_first_started_file() {
         let mut state = State::default();
-        let item = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
+        let child = |name: &str| S3SelectedItem {
+            bucket: Some("test-bucket".into()),
+            name: name.into(),
+            path: Some(format!("dir/{}", name)),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/tmp".into(),
             transferred: false,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
+            account: String::new(),
             progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
+        let directory = S3SelectedItem {
+            is_directory: true,
+            children: Some(vec![child("a"), child("b")]),
+            ..child("dir")
+        };
+        let order = |sequence| DispatchOrder { sequence, total: 5 };
+        state.record_dispatch(download_key(&child("b")), order(2));
+        state.record_dispatch(download_key(&child("a")), order(4));
 
-        state.add_local_selected_item(item.clone());
-        assert_eq!(state.local_selected_items.len(), 1);
-        assert_eq!(state.local_selected_items[0], item);
+        assert_eq!(state.s3_item_dispatch_order(&directory), Some(order(2)));
+        assert_eq!(state.s3_item_dispatch_order(&child("a")), Some(order(4)));
 
-        state.remove_local_selected_item(item);
-        assert!(state.local_selected_items.is_empty());
+        state.start_dispatch_batch();
+        assert_eq!(state.s3_item_dispatch_order(&directory), None);
     }
 
     #[test]
-    fn update_selected_s3_transfers_updates_correctly() {
+    fn preview_replaces_the_previous_one_and_is_cleared_on_close() {
         let mut state = State::default();
-        let selected_item = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
-            is_directory: false,
-            is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: false,
-            s3_creds: FileCredential::default(),
-            progress: 0.0,
-            children: None,
-            error: None,
+        state.start_object_preview();
+        state.set_object_preview(Err("Access Denied".into()));
+        assert!(!state.object_preview_loading);
+        assert_eq!(state.object_preview_error, Some("Access Denied".into()));
+
+        state.start_object_preview();
+        assert_eq!(state.object_preview_error, None);
+        let preview = ObjectPreview::from_bytes("logs", "app.log", b"started", 7);
+        state.set_object_preview(Ok(preview.clone()));
+        assert_eq!(state.object_preview, Some(preview));
+
+        state.clear_object_preview();
+        assert_eq!(state.object_preview, None);
+        assert!(!state.object_preview_loading);
+    }
+
+    #[test]
+    fn object_text_is_kept_for_the_clipboard_and_failures_are_notified() {
+        let mut state = State::default();
+        state.finish_object_text_read("conf", "app.json", Ok("{}".into()));
+        assert_eq!(
+            state.copied_object_text,
+            Some(("s3://conf/app.json".into(), "{}".into()))
+        );
+        assert_eq!(state.copied_object_result, 1);
+
+        state.finish_object_text_read("conf", "logo.png", Err("binary content".into()));
+        assert_eq!(state.copied_object_result, 1);
+        assert_eq!(
+            state.notification.map(|n| n.message),
+            Some("Cannot copy s3://conf/logo.png to the clipboard: binary content".into())
+        );
+    }
+
+    #[test]
+    fn presigned_url_is_kept_for_the_clipboard() {
+        let mut state = State::default();
+        let url = "https://reports.s3.amazonaws.com/q1.csv?X-Amz-Expires=3600";
+        state.finish_presigned_url("reports", "q1.csv", Ok(url.into()));
+        assert_eq!(
+            state.copied_object_text,
+            Some((
+                "url of s3://reports/q1.csv valid for 1h 00m 00s".into(),
+                url.into()
+            ))
+        );
+        assert_eq!(state.copied_object_result, 1);
+
+        state.finish_presigned_url("reports", "q2.csv", Err("Access Denied".into()));
+        assert_eq!(state.copied_object_result, 1);
+        assert_eq!(
+            state.notification.map(|n| n.message),
+            Some("Cannot presign the url of s3://reports/q2.csv: Access Denied".into())
+        );
+    }
+
+    #[test]
+    fn failed_metadata_save_keeps_the_shown_metadata() {
+        let mut state = State::default();
+        let metadata = ObjectMetadata {
+            bucket: "test-bucket".into(),
+            key: "file1.txt".into(),
+            ..ObjectMetadata::default()
         };
-        state.s3_selected_items.push(selected_item.clone());
-        state.update_selected_s3_transfers(selected_item.clone());
-        assert!(state.s3_selected_items[0].transferred);
-        assert_eq!(state.s3_selected_items[0].progress, 100f64);
+        state.start_object_metadata_request();
+        state.set_object_metadata(Ok(metadata.clone()));
+        assert!(!state.object_metadata_loading);
+
+        state.start_object_metadata_request();
+        state.set_object_metadata(Err("Access Denied".into()));
+        assert_eq!(state.object_metadata, Some(metadata));
+        assert_eq!(state.object_metadata_error, Some("Access Denied".into()));
+
+        state.clear_object_metadata();
+        assert_eq!(state.object_metadata, None);
+        assert_eq!(state.object_metadata_error, None);
     }
 
     #[test]
-    fn update_selected_s3_transfers_updates_child_correctly() {
+    fn finished_batch_refreshes_each_displayed_listing_once() {
         let mut state = State::default();
-        let child = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "child-file1.txt".into(),
-            path: Some("path/to/child-file1.txt".into()),
+        state.current_local_path = "/home/user/downloads".into();
+        state.current_s3_bucket = Some("bucket".into());
+        state.current_s3_path = Some("backup/".into());
+        let download = S3SelectedItem {
+            bucket: Some("bucket".into()),
+            name: "file1.txt".into(),
+            path: Some("file1.txt".into()),
             is_directory: false,
             is_bucket: false,
-            destination_dir: "path/to/dest".into(),
+            destination_dir: "/home/user/downloads".into(),
             transferred: false,
-            s3_creds: FileCredential::default(),
+            account: String::new(),
             progress: 0.0,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
-        let selected_item = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
-            is_directory: false,
-            is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: false,
-            s3_creds: FileCredential::default(),
-            progress: 0.0,
-            children: Some(vec![child.clone()]),
-            error: None,
+        let upload = |name: &str| {
+            LocalSelectedItem::new(
+                name.into(),
+                format!("/home/user/{}", name),
+                false,
+                "bucket".into(),
+                format!("backup/{}", name),
+                String::new(),
+                None,
+            )
         };
-        state.s3_selected_items.push(selected_item.clone());
-        state.update_selected_s3_transfers(child.clone());
-        let children = state.s3_selected_items[0]
-            .clone()
-            .children
-            .unwrap_or_default();
-        assert_eq!(children.len(), 1);
-        // assert!(children[0].transferred);
-        // assert_eq!(children[0].progress, 100f64);
-    }
+        state.start_transfer_batch(&[
+            TransferJob::Download(download.clone()),
+            TransferJob::Upload(upload("a.txt")),
+            TransferJob::Upload(upload("b.txt")),
+        ]);
+
+        let refreshes = [
+            state.finish_upload(&upload("a.txt")),
+            state.finish_download(&download),
+            state.finish_upload(&upload("b.txt")),
+        ];
+        assert_eq!(
+            refreshes,
+            [
+                BatchRefresh::default(),
+                BatchRefresh::default(),
+                BatchRefresh {
+                    s3: true,
+                    local: true
+                }
+            ]
+        );
 
+        state.current_s3_path = Some("other/".into());
+        state.current_local_path = "/tmp".into();
+        state.start_transfer_batch(&[TransferJob::Upload(upload("c.txt"))]);
+        assert_eq!(
+            state.finish_upload(&upload("c.txt")),
+            BatchRefresh::default()
+        );
+    }
     #[test]
-    fn update_selected_s3_transfers_with_error_updates_correctly() {
+    fn batch_with_several_failures_is_summarized_once() {
         let mut state = State::default();
-        let selected_item = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
-            is_directory: false,
-            is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: false,
-            s3_creds: FileCredential::default(),
-            progress: 0.0,
-            children: None,
-            error: Some("Error".into()),
+        let upload = |name: &str, error: Option<&str>| LocalSelectedItem {
+            error: error.map(String::from),
+            ..LocalSelectedItem::new(
+                name.into(),
+                format!("/home/user/{}", name),
+                false,
+                "bucket".into(),
+                name.into(),
+                String::new(),
+                None,
+            )
         };
-        state.add_s3_selected_item(selected_item.clone());
-        state.update_selected_s3_transfers(selected_item.clone());
-        assert!(!state.s3_selected_items[0].transferred);
-        assert_eq!(state.s3_selected_items[0].progress, 0f64);
+        let refused = "cannot connect to endpoint https://minio.local:9000";
+        let items = [
+            upload("a.txt", Some(refused)),
+            upload("b.txt", None),
+            upload("c.txt", Some(refused)),
+        ];
+        state.start_transfer_batch(&items.clone().map(TransferJob::Upload));
+        state.finish_upload(&items[0]);
+        assert_eq!(state.failure_summary, None);
+        state.finish_upload(&items[1]);
+        state.finish_upload(&items[2]);
+
+        let summary = state.failure_summary.clone().unwrap();
+        assert_eq!(
+            summary.summary(),
+            vec![format!("2 item(s) failed: {}", refused)]
+        );
+
+        state.failure_summary = None;
+        state.transfer_error_filter = Some(summary.groups()[0].message.clone());
+        state.start_transfer_batch(&[TransferJob::Upload(items[0].clone())]);
+        state.finish_upload(&items[0]);
+        assert_eq!(state.failure_summary, None);
+        assert_eq!(state.transfer_error_filter, None);
     }
 
     #[test]
-    fn update_selected_local_transfers_updates_correctly() {
+    fn every_result_of_a_deletion_or_bucket_creation_is_counted() {
         let mut state = State::default();
-        let selected_item = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: false,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: None,
-            error: None,
-        };
-        state.add_local_selected_item(selected_item.clone());
-        state.update_selected_local_transfers(selected_item.clone());
-        assert!(state.local_selected_items[0].transferred);
-        assert_eq!(state.local_selected_items[0].progress, 100f64);
+        state.set_s3_delete_error(None);
+        state.set_local_delete_error(Some("permission denied".into()));
+        state.set_create_bucket_error(Some("BucketAlreadyExists".into()));
+        state.set_create_bucket_error(Some("BucketAlreadyExists".into()));
+        assert_eq!(state.delete_result, 2);
+        assert_eq!(state.create_bucket_result, 2);
     }
 
     #[test]
-    fn update_selected_local_transfers_with_error_updates_correctly() {
+    fn created_folder_refreshes_only_the_location_it_was_created_in() {
         let mut state = State::default();
-        let selected_item = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: false,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: None,
-            error: Some("Error".into()),
-        };
-        state.add_local_selected_item(selected_item.clone());
-        state.update_selected_local_transfers(selected_item.clone());
-        assert!(!state.local_selected_items[0].transferred);
-        assert_eq!(state.local_selected_items[0].progress, 0f64);
+        state.current_s3_bucket = Some("media".into());
+        state.current_s3_path = Some("2024/".into());
+        let prefix = Some("2024/".to_string());
+
+        assert!(state.finish_folder_creation("media", &prefix, "q1", None));
+        assert!(!state.finish_folder_creation("media", &None, "q1", None));
+        assert!(!state.finish_folder_creation("media", &prefix, "q1", Some("AccessDenied".into())));
+        assert_eq!(state.create_folder_result, 3);
+        assert_eq!(state.create_folder_state, Some("AccessDenied".into()));
+        assert!(state.activity_log.entries().iter().any(|entry| entry
+            .message
+            .contains("Created folder q1 in s3://media/2024/")));
     }
 
     #[test]
-    fn remove_already_transferred_items_removes_correctly() {
+    fn go_to_path_visits_every_directory_above_it() {
         let mut state = State::default();
-        let local_item_not_transfered = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: false,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: None,
-            error: None,
+        state.current_creds.name = "default".into();
+        let at = |prefix: Option<&str>| {
+            NavigationState::new(Some("data".into()), prefix.map(String::from))
         };
-        let local_item_transfered = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: true,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: None,
-            error: None,
+        let target = at(Some("team/curated/"));
+
+        assert_eq!(
+            state.finish_go_to_s3_path("default", target.clone(), None, Err("Nothing".into())),
+            None
+        );
+        assert_eq!(state.go_to_path_error, Some("Nothing".into()));
+        assert_eq!(
+            state.finish_go_to_s3_path("other", target.clone(), None, Ok(())),
+            None
+        );
+        assert_eq!(
+            state.finish_go_to_s3_path("default", target.clone(), Some(2), Ok(())),
+            Some(target)
+        );
+        assert_eq!(state.go_to_path_result, 2);
+        assert_eq!(state.go_to_path_error, None);
+        for expected in [Some("team/"), None] {
+            assert_eq!(
+                state.navigate_s3(NavigationStep::Back, None),
+                Some(at(expected))
+            );
+        }
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Back, None),
+            Some(NavigationState::new(None, None))
+        );
+        assert_eq!(state.pending_s3_row, Some(2));
+    }
+
+    #[test]
+    fn delete_progress_ends_with_the_last_batch() {
+        let mut state = State::default();
+        let progress = |deleted| DeleteProgress {
+            location: "s3://logs/2024/".into(),
+            deleted,
+            total: 2500,
         };
-        let s3_item_not_transferred = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
+        state.set_s3_delete_progress(progress(1000));
+        assert_eq!(state.s3_delete_progress, Some(progress(1000)));
+        state.set_s3_delete_progress(progress(2500));
+        assert_eq!(state.s3_delete_progress, None);
+    }
+
+    #[test]
+    fn bulk_deletion_keeps_only_the_failed_items() {
+        let mut state = State::default();
+        let object = |key: &str| S3SelectedItem {
+            bucket: Some("logs".into()),
+            name: key.into(),
+            path: Some(key.into()),
             is_directory: false,
             is_bucket: false,
-            destination_dir: "path/to/dest".into(),
+            destination_dir: "/tmp".into(),
             transferred: false,
-            s3_creds: FileCredential::default(),
+            account: String::new(),
             progress: 0.0,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
-        let s3_item_transferred = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
-            is_directory: false,
+        state.s3_selected_items = vec![
+            S3SelectedItem {
+                is_directory: true,
+                children: Some(vec![object("2024/a.log"), object("2024/b.log")]),
+                ..object("2024/")
+            },
+            object("c.log"),
+            object("d.log"),
+        ];
+        let bucket_info = BucketInfo {
+            bucket: Some("logs".into()),
+            region: None,
             is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: true,
-            s3_creds: FileCredential::default(),
-            progress: 0.0,
-            children: None,
-            error: None,
+            creation_date: None,
         };
-        state.add_local_selected_item(local_item_transfered);
-        state.add_local_selected_item(local_item_not_transfered);
-        state.add_s3_selected_item(s3_item_not_transferred);
-        state.add_s3_selected_item(s3_item_transferred);
-        assert_eq!(state.s3_selected_items.len(), 2);
-        assert_eq!(state.local_selected_items.len(), 2);
+        state.s3_data = vec![
+            S3DataItem::listed(bucket_info.clone(), "2024/", "".into(), true),
+            S3DataItem::listed(bucket_info.clone(), "c.log", "1".into(), false),
+            S3DataItem::listed(bucket_info, "d.log", "1".into(), false),
+        ];
+        let local = LocalSelectedItem::new(
+            "notes.txt".into(),
+            "/tmp/notes.txt".into(),
+            false,
+            "".into(),
+            "".into(),
+            String::new(),
+            None,
+        );
+        state.local_selected_items = vec![local];
+        state.local_data = vec![LocalDataItem::init(
+            "notes.txt".into(),
+            "1".into(),
+            "txt",
+            "/tmp/notes.txt",
+            false,
+        )];
 
-        state.remove_already_transferred_items();
-        assert_eq!(state.s3_selected_items.len(), 1);
-        assert_eq!(state.local_selected_items.len(), 1);
-        assert!(!state.s3_selected_items[0].transferred);
-        assert!(!state.local_selected_items[0].transferred);
+        state.finish_bulk_deletion(BulkDeletion {
+            deleted_s3: vec![
+                ("logs".into(), "2024/a.log".into()),
+                ("logs".into(), "2024/b.log".into()),
+                ("logs".into(), "c.log".into()),
+            ],
+            deleted_local: vec!["/tmp/notes.txt".into()],
+            failed: vec![("s3://logs/d.log".into(), "AccessDenied".into())],
+        });
+
+        let selected: Vec<String> = state
+            .s3_selected_items
+            .iter()
+            .map(|item| item.name.clone())
+            .collect();
+        assert_eq!(selected, vec!["d.log"]);
+        let listed: Vec<String> = state.s3_data.iter().map(|row| row.path.clone()).collect();
+        assert_eq!(listed, vec!["d.log"]);
+        assert!(state.local_selected_items.is_empty());
+        assert!(state.local_data.is_empty());
+        assert_eq!(state.delete_result, 1);
+        assert_eq!(
+            state.s3_delete_state,
+            Some("1 of 5 items could not be deleted:\ns3://logs/d.log: AccessDenied".into())
+        );
     }
 
     #[test]
-    fn update_progress_on_selected_local_item_updates_correctly() {
+    fn bucket_deleted_between_two_navigations_is_left() {
         let mut state = State::default();
-        let selected_item = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: false,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: None,
-            error: None,
+        let into = |prefix: Option<&str>| {
+            NavigationStep::Into(NavigationState::new(
+                Some("media".into()),
+                prefix.map(String::from),
+            ))
         };
+        state.navigate_s3(into(None), Some(3));
+        state.s3_listing_request = 1;
+        state.receive_s3_listing(1, Some("media".into()), None, Vec::new());
 
-        state.local_selected_items.push(selected_item.clone());
-        let progress_item = UploadProgressItem {
-            progress: 0.5,
-            uri: "https://test-bucket.s3.amazonaws.com/path/to/file1.txt".into(),
+        let upload = |name: &str, bucket: &str| {
+            LocalSelectedItem::new(
+                name.into(),
+                format!("/tmp/{}", name),
+                false,
+                bucket.into(),
+                name.into(),
+                String::new(),
+                None,
+            )
         };
-        state.update_progress_on_selected_local_item(progress_item);
+        let uploads = [
+            upload("started.txt", "media"),
+            upload("waiting.txt", "media"),
+            upload("queued.txt", "media"),
+            upload("other.txt", "logs"),
+        ];
+        for item in uploads.iter() {
+            state.add_local_selected_item(item.clone());
+        }
+        let jobs: Vec<TransferJob> = uploads[..2]
+            .iter()
+            .cloned()
+            .map(TransferJob::Upload)
+            .collect();
+        state.start_transfer_batch(&jobs);
+        state.record_dispatch(
+            jobs[0].key(),
+            DispatchOrder {
+                sequence: 1,
+                total: 2,
+            },
+        );
 
-        assert_eq!(state.local_selected_items[0].progress, 0.5);
+        // another client deletes the bucket, the next listing answers NoSuchBucket
+        state.navigate_s3(into(Some("2024/")), Some(0));
+        assert_eq!(state.forget_missing_bucket("logs"), None);
+        assert_eq!(
+            state.forget_missing_bucket("media"),
+            Some(vec![jobs[1].key()])
+        );
+
+        assert_eq!(state.s3_location(), NavigationState::new(None, None));
+        assert_eq!(state.navigate_s3(NavigationStep::Back, None), None);
+        let reason = Some("bucket 'media' no longer exists".to_string());
+        let errors: Vec<Option<String>> = state
+            .local_selected_items
+            .iter()
+            .map(|item| item.error.clone())
+            .collect();
+        assert_eq!(errors, vec![None, reason.clone(), reason, None]);
+        assert_eq!(
+            state.notification.unwrap().message,
+            "Bucket 'media' no longer exists, 2 transfer(s) to or from it failed"
+        );
     }
 
     #[test]
-    fn update_progress_on_selected_s3_item_updates_correctly() {
+    fn custom_command_results_are_notified() {
         let mut state = State::default();
-        let item = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
-            is_directory: false,
-            is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: true,
-            s3_creds: FileCredential::default(),
-            progress: 0.0,
-            children: None,
-            error: None,
-        };
+        state.finish_custom_command(
+            "etl",
+            Ok(CommandOutput {
+                success: false,
+                code: Some(2),
+                lines: vec!["no such job".into(), "usage: etl-submit".into()],
+            }),
+        );
+        assert_eq!(
+            state.notification.take().unwrap().message,
+            "'etl' failed with exit status 2: no such job"
+        );
+        let logged = state.activity_log.entries().back().unwrap();
+        assert_eq!(logged.severity, Severity::Error);
+        assert_eq!(
+            logged.message,
+            "'etl' failed with exit status 2: no such job | usage: etl-submit"
+        );
 
-        state.s3_selected_items.push(item.clone());
-        let progress_item = DownloadProgressItem {
-            progress: 0.5,
-            bucket: "test-bucket".to_string(),
-            name: "file1.txt".into(),
+        let output = CommandOutput {
+            success: true,
+            code: Some(0),
+            lines: Vec::new(),
         };
-        state.update_progress_on_selected_s3_item(progress_item);
-
-        assert_eq!(state.s3_selected_items[0].progress, 0.5);
+        state.finish_custom_command("etl", Ok(output));
+        assert_eq!(state.notification.take().unwrap().message, "'etl' finished");
+        state.finish_custom_command("etl", Err("No such file or directory".into()));
+        assert_eq!(
+            state.notification.unwrap().message,
+            "Cannot run 'etl': No such file or directory"
+        );
     }
 
     #[test]
-    fn update_local_item_with_progress_updates_the_item() {
+    fn discarded_restored_transfers_leave_the_selection() {
         let mut state = State::default();
-        let selected_item = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: false,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: None,
-            error: None,
-        };
-        state.local_selected_items = vec![selected_item];
-        let progress_item = UploadProgressItem {
-            progress: 50.0,
-            uri: "https://test-bucket.s3.eu-west-1.amazonaws.com/file1.txt?x-id=PutObject".into(),
+        let upload = |name: &str| {
+            LocalSelectedItem::new(
+                name.into(),
+                format!("/tmp/{}", name),
+                false,
+                "backup".into(),
+                name.into(),
+                String::new(),
+                None,
+            )
         };
-        state.update_local_item_with_progress(state.local_selected_items.clone(), progress_item);
-        assert_eq!(state.local_selected_items[0].progress, 50.0);
+        state.restore_transfers(RestoredQueue::default());
+        assert!(state.restored_transfers.is_none());
+
+        state.restore_transfers(RestoredQueue {
+            queue: QueueSnapshot {
+                downloads: Vec::new(),
+                uploads: vec![upload("a.txt"), upload("b.txt")],
+            },
+            skipped: 1,
+            recovered_from: Some("transfers.tsv.1.bak".into()),
+            ..RestoredQueue::default()
+        });
+        assert_eq!(
+            state.restored_transfers.as_ref().map(QueueSnapshot::len),
+            Some(2)
+        );
+        let warning = state.activity_log.entries().front().cloned().unwrap();
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(
+            warning.message,
+            "The saved transfers were damaged, restored them from transfers.tsv.1.bak"
+        );
+        assert_eq!(state.transfers_total, 2);
+        state.add_local_selected_item(upload("c.txt"));
+
+        assert!(state.discard_restored_transfers());
+        assert!(!state.discard_restored_transfers());
+        assert_eq!(state.local_selected_items, vec![upload("c.txt")]);
+        assert_eq!(state.transfers_total, 1);
+        assert_eq!(
+            state.notification.unwrap().message,
+            "Discarded 2 transfer(s) of the previous session"
+        );
     }
 
     #[test]
-    fn update_local_item_with_progress_updates_child_correctly() {
+    fn resumed_upload_reuses_its_restored_transfer() {
         let mut state = State::default();
-        let child = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: false,
-            name: "file1.txt".into(),
-            path: "path/to/file1.txt".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: None,
-            error: None,
-        };
-        let selected_item = LocalSelectedItem {
-            destination_bucket: "test-bucket".into(),
-            destination_path: "".to_string(),
-            transferred: false,
-            name: "/".into(),
-            path: "path/to".into(),
-            progress: 0.0,
-            is_directory: false,
-            s3_creds: Default::default(),
-            children: Some(vec![child]),
-            error: None,
-        };
-        state.local_selected_items = vec![selected_item];
-        let progress_item = UploadProgressItem {
-            progress: 50.0,
-            uri: "https://test-bucket.s3.eu-west-1.amazonaws.com/file1.txt?x-id=PutObject".into(),
+        let upload = |id: &str, path: &str| ResumableUpload {
+            account: "work".into(),
+            bucket: "backup".into(),
+            key: "disks/vm.img".into(),
+            path: path.into(),
+            size: 20,
+            modified: 0,
+            part_size: 8,
+            upload_id: id.into(),
+            parts: [(1, "\"a\"".to_string())].into_iter().collect(),
         };
-        state.update_local_item_with_progress(state.local_selected_items.clone(), progress_item);
+        let restored = upload("1", "/tmp/vm.img").to_item();
+        state.add_local_selected_item(restored.clone());
+        state.offer_resumable_uploads(vec![
+            upload("1", "/tmp/vm.img"),
+            upload("2", "/tmp/old.img"),
+        ]);
+
+        assert_eq!(state.resume_upload(&upload("1", "/tmp/vm.img")), restored);
+        assert_eq!(state.local_selected_items.len(), 1);
         assert_eq!(
-            state.local_selected_items[0]
-                .clone()
-                .children
-                .unwrap_or_default()[0]
-                .progress,
-            50.0
+            state.activity_log.entries().back().unwrap().message,
+            "Resuming the upload of /tmp/vm.img after 1 of its 3 parts"
         );
-        assert_eq!(state.local_selected_items[0].progress, 50.0);
+        let added = state.resume_upload(&upload("2", "/tmp/old.img"));
+        assert_eq!(added.path, "/tmp/old.img");
+        assert_eq!(state.local_selected_items, vec![restored, added]);
+        assert_eq!(state.transfers_total, 2);
+        assert!(state.resumable_uploads.is_empty());
     }
 
     #[test]
-    fn update_s3_item_with_progress_updates_the_item() {
-        let mut state = State::default();
-        let selected_item = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
-            is_directory: false,
-            is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: true,
-            s3_creds: FileCredential::default(),
-            progress: 0.0,
-            children: None,
-            error: None,
+    fn restored_transfers_wait_for_their_missing_accounts() {
+        let mut state = State::new(vec![FileCredential {
+            name: "new".into(),
+            ..FileCredential::default()
+        }]);
+        let upload = |name: &str, account: &str| {
+            LocalSelectedItem::new(
+                name.into(),
+                format!("/tmp/{}", name),
+                false,
+                "backup".into(),
+                name.into(),
+                account.into(),
+                None,
+            )
         };
-        state.s3_selected_items = vec![selected_item];
-        let progress_item = DownloadProgressItem {
-            progress: 50.0,
-            bucket: "test-bucket".into(),
-            name: "file1.txt".into(),
+        state.restore_transfers(RestoredQueue {
+            orphaned: QueueSnapshot {
+                downloads: Vec::new(),
+                uploads: vec![upload("a.txt", "old"), upload("b.txt", "gone")],
+            },
+            missing_accounts: vec!["old".into(), "gone".into()],
+            ..RestoredQueue::default()
+        });
+        assert!(state.local_selected_items.is_empty());
+        assert!(state.restored_transfers.is_none());
+
+        state.remap_restored_account("old", Some("new"));
+        assert!(state.local_selected_items.is_empty());
+        assert_eq!(
+            state
+                .orphaned_transfers
+                .as_ref()
+                .map(|r| r.missing_accounts.clone()),
+            Some(vec!["gone".to_string()])
+        );
+
+        state.remap_restored_account("gone", None);
+        assert!(state.orphaned_transfers.is_none());
+        assert_eq!(state.local_selected_items, vec![upload("a.txt", "new")]);
+        assert_eq!(state.local_selected_items[0].account, "new");
+        assert_eq!(
+            state.restored_transfers.as_ref().map(QueueSnapshot::len),
+            Some(1)
+        );
+        assert_eq!(
+            state.notification.unwrap().message,
+            "Restored 1 transfer(s) of the previous session, 1 dropped with their missing account"
+        );
+    }
+
+    #[test]
+    fn saved_credentials_replace_the_edited_ones() {
+        let cred = |name: &str, region: &str| FileCredential {
+            name: name.into(),
+            default_region: region.into(),
+            ..FileCredential::default()
         };
-        state.update_s3_item_with_progress(state.s3_selected_items.clone(), progress_item);
-        assert_eq!(state.s3_selected_items[0].progress, 50.0);
+        let mut state = State::new(vec![
+            FileCredential {
+                selected: true,
+                ..cred("work", "eu-west-1")
+            },
+            cred("home", "eu-west-1"),
+        ]);
+        state
+            .account_checks
+            .insert("home".into(), AccountCheck::Failed("denied".into()));
+
+        assert!(!state.store_credential(None, cred("lab", "us-east-1")));
+        assert_eq!(state.creds.len(), 3);
+        assert!(!state.creds[2].selected);
+
+        assert!(!state.store_credential(Some("home"), cred("family", "eu-north-1")));
+        assert_eq!(state.creds[1].name, "family");
+        assert!(state.account_checks.is_empty());
+
+        // the current account uses the new settings right away
+        assert!(state.store_credential(Some("work"), cred("work", "eu-central-1")));
+        assert!(state.creds[0].selected);
+        assert_eq!(state.current_creds.default_region, "eu-central-1");
+        assert_eq!(
+            state.notification.take().unwrap().message,
+            "Account work saved"
+        );
+
+        state.remove_credential("lab");
+        let names: Vec<&str> = state.creds.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["work", "family"]);
     }
 
     #[test]
-    fn update_s3_item_with_progress_updates_child_correctly() {
+    fn deleted_sources_leave_the_queued_transfers() {
         let mut state = State::default();
-        let child = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "file1.txt".into(),
-            path: Some("path/to/file1.txt".into()),
+        let object = |bucket: &str, key: &str| S3SelectedItem {
+            bucket: Some(bucket.into()),
+            name: key.rsplit('/').next().unwrap().into(),
+            path: Some(key.into()),
             is_directory: false,
             is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: true,
-            s3_creds: FileCredential::default(),
+            destination_dir: "/tmp".into(),
+            transferred: false,
+            account: String::new(),
             progress: 0.0,
             children: None,
             error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
         };
-        let selected_item = S3SelectedItem {
-            bucket: Some("test-bucket".to_string()),
-            name: "/".into(),
-            path: Some("path/to".into()),
-            is_directory: false,
-            is_bucket: false,
-            destination_dir: "path/to/dest".into(),
-            transferred: true,
-            s3_creds: FileCredential::default(),
-            progress: 0.0,
-            children: Some(vec![child.clone()]),
-            error: None,
+        let directory = |bucket: &str, key: &str, children| S3SelectedItem {
+            is_directory: true,
+            children: Some(children),
+            ..object(bucket, key)
         };
-        state.s3_selected_items = vec![selected_item];
-        let progress_item = DownloadProgressItem {
-            progress: 50.0,
-            bucket: "test-bucket".into(),
-            name: "file1.txt".into(),
+        let done = S3SelectedItem {
+            transferred: true,
+            ..object("logs", "2024/done.log")
         };
-        state.update_s3_item_with_progress(state.s3_selected_items.clone(), progress_item);
+        state.s3_selected_items = vec![
+            object("logs", "a.log"),
+            object("logs", "a.log.gz"),
+            directory("logs", "2024/", vec![object("logs", "2024/01/x.log"), done]),
+            directory("logs", "2023/", vec![object("logs", "2023/y.log")]),
+            object("media", "2024/01/z.log"),
+        ];
+
+        // a single object
+        state.forget_deleted_s3_source(&object("logs", "a.log"));
+        assert_eq!(state.s3_selected_items.len(), 4);
         assert_eq!(
-            state.s3_selected_items[0]
-                .clone()
-                .children
-                .unwrap_or_default()[0]
-                .progress,
-            50.0
+            state.notification.take().unwrap().message,
+            "Also removed 1 queued transfer(s) referencing the deleted item"
         );
-        assert_eq!(state.s3_selected_items[0].progress, 50.0);
+
+        // a child of a selected directory, the finished transfer stays
+        state.forget_deleted_s3_source(&directory("logs", "2024/01", Vec::new()));
+        let children = state.s3_selected_items[1].children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert!(children[0].transferred);
+        assert!(state.s3_selected_items[1].transferred);
+
+        // a prefix covering a whole selected directory
+        state.forget_deleted_s3_source(&directory("logs", "2023/", Vec::new()));
+        let keys: Vec<&str> = state
+            .s3_selected_items
+            .iter()
+            .filter_map(|item| item.path.as_deref())
+            .collect();
+        assert_eq!(keys, vec!["a.log.gz", "2024/", "2024/01/z.log"]);
+
+        // a deleted bucket drops its objects, nothing else matches
+        let bucket = S3SelectedItem {
+            is_bucket: true,
+            bucket: None,
+            ..object("media", "media")
+        };
+        state.forget_deleted_s3_source(&bucket);
+        assert_eq!(state.s3_selected_items.len(), 2);
+        assert!(state.notification.take().is_some());
+        state.forget_deleted_s3_source(&object("logs", "missing.log"));
+        assert_eq!(state.notification, None);
+    }
+
+    #[test]
+    fn deleted_local_directories_leave_the_queued_uploads() {
+        let mut state = State::default();
+        let upload = |path: &str, children| LocalSelectedItem {
+            children,
+            ..LocalSelectedItem::new(
+                path.rsplit('/').next().unwrap().into(),
+                path.into(),
+                false,
+                "backup".into(),
+                path.into(),
+                String::new(),
+                None,
+            )
+        };
+        state.local_selected_items = vec![
+            upload(
+                "/home/me/photos",
+                Some(vec![
+                    upload("/home/me/photos/raw/a.cr2", None),
+                    upload("/home/me/photos/b.jpg", None),
+                ]),
+            ),
+            upload("/home/me/raw.txt", None),
+        ];
+        state.forget_deleted_local_source("/home/me/photos/raw");
+        let children = state.local_selected_items[0].children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(state.local_selected_items.len(), 2);
+
+        state.forget_deleted_local_source("/home/me/photos");
+        assert_eq!(state.local_selected_items.len(), 1);
+        assert_eq!(state.local_selected_items[0].path, "/home/me/raw.txt");
+    }
+
+    #[test]
+    fn directory_with_only_finished_uploads_left_is_done() {
+        let mut state = State::default();
+        let upload = |path: &str, transferred, children| LocalSelectedItem {
+            transferred,
+            children,
+            ..LocalSelectedItem::new(
+                path.rsplit('/').next().unwrap().into(),
+                path.into(),
+                false,
+                "backup".into(),
+                path.into(),
+                String::new(),
+                None,
+            )
+        };
+        state.local_selected_items = vec![upload(
+            "/home/me/photos",
+            false,
+            Some(vec![
+                upload("/home/me/photos/raw/a.cr2", false, None),
+                upload("/home/me/photos/b.jpg", true, None),
+            ]),
+        )];
+        state.forget_deleted_local_source("/home/me/photos/raw");
+        let photos = &state.local_selected_items[0];
+        assert_eq!(photos.children.as_ref().unwrap().len(), 1);
+        assert_eq!(photos.completed_children, 1);
+        assert!(photos.transferred);
     }
 }
@@ -0,0 +1,172 @@
+use crate::services::s3_errors::ErrorClass;
+use crate::utils::{format_bytes, format_duration};
+use std::time::{Duration, Instant};
+
+/// Counters describing what happened in the application since it was launched
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub started_at: Instant,
+    pub listings: usize,
+    pub objects_browsed: usize,
+    pub downloads_completed: usize,
+    pub downloads_failed: usize,
+    pub uploads_completed: usize,
+    pub uploads_failed: usize,
+    pub bytes_downloaded: u64,
+    pub bytes_uploaded: u64,
+    pub deletions: usize,
+    pub buckets_created: usize,
+    pub timeout_errors: usize,
+    pub connection_errors: usize,
+    pub proxy_errors: usize,
+    pub service_errors: usize,
+    pub open_files_errors: usize,
+    pub other_errors: usize,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats {
+            started_at: Instant::now(),
+            listings: 0,
+            objects_browsed: 0,
+            downloads_completed: 0,
+            downloads_failed: 0,
+            uploads_completed: 0,
+            uploads_failed: 0,
+            bytes_downloaded: 0,
+            bytes_uploaded: 0,
+            deletions: 0,
+            buckets_created: 0,
+            timeout_errors: 0,
+            connection_errors: 0,
+            proxy_errors: 0,
+            service_errors: 0,
+            open_files_errors: 0,
+            other_errors: 0,
+        }
+    }
+}
+
+impl Stats {
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    pub fn record_listing(&mut self, items: usize) {
+        self.listings += 1;
+        self.objects_browsed += items;
+    }
+
+    /// Records a finished download, `Ok` holds the number of bytes written to disk
+    pub fn record_download(&mut self, outcome: &Result<u64, ErrorClass>) {
+        match outcome {
+            Ok(bytes) => {
+                self.downloads_completed += 1;
+                self.bytes_downloaded += bytes;
+            }
+            Err(class) => {
+                self.downloads_failed += 1;
+                self.record_api_error(*class);
+            }
+        }
+    }
+
+    /// Records a finished upload, `Ok` holds the number of bytes sent
+    pub fn record_upload(&mut self, outcome: &Result<u64, ErrorClass>) {
+        match outcome {
+            Ok(bytes) => {
+                self.uploads_completed += 1;
+                self.bytes_uploaded += bytes;
+            }
+            Err(class) => {
+                self.uploads_failed += 1;
+                self.record_api_error(*class);
+            }
+        }
+    }
+
+    pub fn record_deletion(&mut self) {
+        self.deletions += 1;
+    }
+
+    pub fn record_bucket_created(&mut self) {
+        self.buckets_created += 1;
+    }
+
+    /// Line printed when the app exits, `None` when nothing was transferred
+    pub fn exit_summary(&self) -> Option<String> {
+        if self.downloads_completed
+            + self.downloads_failed
+            + self.uploads_completed
+            + self.uploads_failed
+            == 0
+        {
+            return None;
+        }
+        Some(format!(
+            "s3tui: {} downloads finished ({}), {} failed; {} uploads finished ({}), {} failed in {}",
+            self.downloads_completed,
+            format_bytes(self.bytes_downloaded),
+            self.downloads_failed,
+            self.uploads_completed,
+            format_bytes(self.bytes_uploaded),
+            self.uploads_failed,
+            format_duration(self.uptime())
+        ))
+    }
+
+    pub fn record_api_error(&mut self, class: ErrorClass) {
+        match class {
+            ErrorClass::Timeout => self.timeout_errors += 1,
+            ErrorClass::Connection | ErrorClass::Tls => self.connection_errors += 1,
+            ErrorClass::Proxy => self.proxy_errors += 1,
+            ErrorClass::Service | ErrorClass::Throttled | ErrorClass::MissingBucket => {
+                self.service_errors += 1
+            }
+            ErrorClass::TooManyOpenFiles => self.open_files_errors += 1,
+            ErrorClass::Other => self.other_errors += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_transfers_are_counted_by_error_class() {
+        let mut stats = Stats::default();
+        stats.record_download(&Ok(100));
+        stats.record_download(&Err(ErrorClass::Timeout));
+        stats.record_upload(&Ok(50));
+        stats.record_upload(&Ok(25));
+        stats.record_upload(&Err(ErrorClass::Service));
+
+        assert_eq!(stats.downloads_completed, 1);
+        assert_eq!(stats.downloads_failed, 1);
+        assert_eq!(stats.uploads_completed, 2);
+        assert_eq!(stats.uploads_failed, 1);
+        assert_eq!(stats.bytes_downloaded, 100);
+        assert_eq!(stats.bytes_uploaded, 75);
+        assert_eq!(stats.timeout_errors, 1);
+        assert_eq!(stats.service_errors, 1);
+        assert_eq!(stats.connection_errors, 0);
+    }
+
+    #[test]
+    fn exit_summary_lists_finished_and_failed_transfers() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.exit_summary(), None);
+        stats.record_download(&Ok(1536));
+        stats.record_upload(&Err(ErrorClass::Timeout));
+        let summary = stats.exit_summary().unwrap();
+        assert!(
+            summary.starts_with(
+                "s3tui: 1 downloads finished (1.5 KB), 0 failed; 0 uploads finished (0 B), 1 failed in "
+            ),
+            "{}",
+            summary
+        );
+    }
+}
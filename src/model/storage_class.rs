@@ -0,0 +1,100 @@
+//! This module provides the storage classes offered when changing the class of an s3 object
+//! from the metadata popup
+
+/// Storage classes offered in the metadata popup, from the most to the least accessible
+pub const STORAGE_CLASSES: &[&str] = &[
+    "STANDARD",
+    "INTELLIGENT_TIERING",
+    "STANDARD_IA",
+    "ONEZONE_IA",
+    "GLACIER_IR",
+    "GLACIER",
+    "DEEP_ARCHIVE",
+];
+
+/// Classes whose objects have to be restored before they can be read or copied
+pub fn is_archived(storage_class: &str) -> bool {
+    matches!(storage_class, "GLACIER" | "DEEP_ARCHIVE")
+}
+
+/// HeadObject doesn't return the class of STANDARD objects
+pub fn current_class(storage_class: Option<&str>) -> &str {
+    storage_class.unwrap_or("STANDARD")
+}
+
+/// Checks whether the object can be copied onto itself with the `target` class, archived
+/// objects can't be copied until they are restored
+pub fn check_transition(current: Option<&str>, target: &str) -> Result<(), String> {
+    let current = current_class(current);
+    if current == target {
+        return Err(format!("The object is already stored in {}", target));
+    }
+    if is_archived(current) {
+        return Err(format!(
+            "Objects in {} cannot change their class here, restore the object first (e.g. aws s3api restore-object) and copy the restored object with the new class",
+            current
+        ));
+    }
+    Ok(())
+}
+
+/// Text of the confirmation shown before the class is changed
+pub fn transition_warning(target: &str) -> String {
+    let costs = match target {
+        "STANDARD_IA" | "ONEZONE_IA" | "GLACIER_IR" => {
+            " The class has a minimum storage duration and charges for retrieval."
+        }
+        "GLACIER" | "DEEP_ARCHIVE" => {
+            " The object will have to be restored before it can be read again, retrieval is charged."
+        }
+        "INTELLIGENT_TIERING" => " A monthly monitoring fee is charged per object.",
+        _ => "",
+    };
+    format!(
+        "Changing the class to {} copies the object onto itself, its ETag and last modified date will change.{} Proceed?",
+        target, costs
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accessible_objects_can_move_to_any_other_class() {
+        for current in [
+            "STANDARD",
+            "INTELLIGENT_TIERING",
+            "STANDARD_IA",
+            "GLACIER_IR",
+        ] {
+            for target in STORAGE_CLASSES.iter().filter(|c| **c != current) {
+                assert_eq!(check_transition(Some(current), target), Ok(()));
+            }
+        }
+        assert_eq!(check_transition(None, "GLACIER"), Ok(()));
+    }
+
+    #[test]
+    fn archived_objects_have_to_be_restored_first() {
+        for current in ["GLACIER", "DEEP_ARCHIVE"] {
+            for target in STORAGE_CLASSES.iter().filter(|c| **c != current) {
+                let error = check_transition(Some(current), target).unwrap_err();
+                assert!(error.contains("restore-object"), "{}", error);
+            }
+        }
+    }
+
+    #[test]
+    fn current_class_is_not_offered_again() {
+        assert!(check_transition(None, "STANDARD").is_err());
+        assert!(check_transition(Some("STANDARD_IA"), "STANDARD_IA").is_err());
+    }
+
+    #[test]
+    fn warning_mentions_the_costs_of_the_target_class() {
+        assert!(transition_warning("STANDARD_IA").contains("minimum storage duration"));
+        assert!(transition_warning("DEEP_ARCHIVE").contains("restored"));
+        assert!(!transition_warning("STANDARD").contains("charge"));
+    }
+}
@@ -0,0 +1,253 @@
+//! This module provides tracking of the locations changed by a batch of transfers
+use crate::model::error_groups::ErrorGroups;
+use crate::model::jump_target::JumpTarget;
+use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::s3_selected_item::S3SelectedItem;
+use crate::services::transfer_queue::{download_key, upload_key, TransferJob};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Listings to reload once the batch is finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchRefresh {
+    pub s3: bool,
+    pub local: bool,
+}
+
+/// Transfers started together, the listings are refreshed once after the last one finishes
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TransferBatch {
+    /// Keys of the transfers still running
+    pending: HashSet<String>,
+    /// Account, bucket and prefix which received at least one upload
+    s3_locations: HashSet<(String, String, Option<String>)>,
+    /// Local directories which received at least one download
+    local_directories: HashSet<String>,
+    /// Failed transfers grouped by their cause
+    failures: ErrorGroups,
+}
+
+/// Whether the listing of `shown` contains `changed` or one of its parent directories
+fn contains_prefix(shown: Option<&str>, changed: Option<&str>) -> bool {
+    match (shown, changed) {
+        (None, _) => true,
+        (Some(shown), Some(changed)) => changed.starts_with(shown),
+        (Some(_), None) => false,
+    }
+}
+
+impl TransferBatch {
+    /// Adds the jobs to the batch, jobs started while the batch is running join it
+    pub fn start(&mut self, jobs: &[TransferJob]) {
+        if self.pending.is_empty() {
+            self.s3_locations.clear();
+            self.local_directories.clear();
+            self.failures.clear();
+        }
+        self.pending.extend(jobs.iter().map(TransferJob::key));
+    }
+
+    /// Whether the transfer is part of the batch and hasn't finished yet
+    pub fn is_pending(&self, key: &str) -> bool {
+        self.pending.contains(key)
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Unfinished transfers of the batch for which `started` is true
+    pub fn count_pending(&self, started: impl Fn(&str) -> bool) -> usize {
+        self.pending.iter().filter(|key| started(key)).count()
+    }
+
+    fn finish(&mut self, key: &str) -> bool {
+        self.pending.remove(key) && self.pending.is_empty()
+    }
+
+    /// Records the paused or cancelled transfer, returns true when it was the last one of
+    /// the batch. It isn't counted as a failure
+    pub fn stop(&mut self, key: &str) -> bool {
+        self.finish(key)
+    }
+
+    /// Records the finished download, returns true when it was the last one of the batch
+    pub fn finish_download(&mut self, item: &S3SelectedItem) -> bool {
+        match &item.error {
+            None => {
+                if let JumpTarget::Local { directory, .. } = JumpTarget::download_destination(item)
+                {
+                    self.local_directories.insert(directory);
+                }
+            }
+            Some(error) => self.failures.add(error),
+        }
+        self.finish(&download_key(item))
+    }
+
+    /// Records the finished upload, returns true when it was the last one of the batch
+    pub fn finish_upload(&mut self, item: &LocalSelectedItem) -> bool {
+        match &item.error {
+            None => {
+                if let JumpTarget::S3 { bucket, prefix, .. } = JumpTarget::upload_destination(item)
+                {
+                    self.s3_locations
+                        .insert((item.account.clone(), bucket, prefix));
+                }
+            }
+            Some(error) => self.failures.add(error),
+        }
+        self.finish(&upload_key(item))
+    }
+
+    /// Failed transfers of the batch, the ones of the previous batch until the next one starts
+    pub fn failures(&self) -> &ErrorGroups {
+        &self.failures
+    }
+
+    /// Listings which show the changed locations, directly or as a new subdirectory
+    pub fn refresh_for(
+        &self,
+        account: &str,
+        s3_bucket: Option<&str>,
+        s3_prefix: Option<&str>,
+        local_path: &str,
+    ) -> BatchRefresh {
+        let s3 = s3_bucket.is_some_and(|shown_bucket| {
+            self.s3_locations.iter().any(|(a, bucket, prefix)| {
+                a == account
+                    && bucket == shown_bucket
+                    && contains_prefix(s3_prefix, prefix.as_deref())
+            })
+        });
+        let local = self
+            .local_directories
+            .iter()
+            .any(|directory| Path::new(directory).starts_with(local_path));
+        BatchRefresh { s3, local }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download(path: &str) -> S3SelectedItem {
+        S3SelectedItem {
+            bucket: Some("bucket".into()),
+            name: path.into(),
+            path: Some(path.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/home/user/downloads".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        }
+    }
+
+    fn upload(destination_path: &str) -> LocalSelectedItem {
+        LocalSelectedItem::new(
+            "file.txt".into(),
+            format!("/home/user/docs/{}", destination_path),
+            false,
+            "bucket".into(),
+            destination_path.into(),
+            String::new(),
+            None,
+        )
+    }
+
+    #[test]
+    fn batch_finishes_with_the_last_transfer() {
+        let uploads = [upload("a/one.txt"), upload("a/two.txt")];
+        let mut batch = TransferBatch::default();
+        batch.start(&[
+            TransferJob::Upload(uploads[0].clone()),
+            TransferJob::Upload(uploads[1].clone()),
+        ]);
+        assert!(!batch.finish_upload(&uploads[0]));
+        assert!(batch.finish_upload(&uploads[1]));
+        // a late duplicate doesn't finish the batch again
+        assert!(!batch.finish_upload(&uploads[1]));
+    }
+
+    #[test]
+    fn failures_are_kept_until_the_next_batch() {
+        let mut downloads = [download("a.txt"), download("b.txt"), download("c.txt")];
+        downloads[0].error = Some("cannot connect to endpoint https://minio.local:9000".into());
+        downloads[1].error = Some("cannot connect to endpoint https://minio.local:9000".into());
+        let mut batch = TransferBatch::default();
+        batch.start(&downloads.clone().map(TransferJob::Download));
+        for item in &downloads {
+            batch.finish_download(item);
+        }
+        assert_eq!(batch.failures().failed(), 2);
+        assert_eq!(batch.failures().groups().len(), 1);
+
+        batch.start(&[TransferJob::Download(download("d.txt"))]);
+        assert_eq!(batch.failures().failed(), 0);
+    }
+
+    #[test]
+    fn uploads_refresh_only_the_s3_listing_showing_them() {
+        let item = upload("a/b/file.txt");
+        let mut batch = TransferBatch::default();
+        batch.start(&[TransferJob::Upload(item.clone())]);
+        batch.finish_upload(&item);
+        let account = String::new();
+
+        let refresh = |bucket, prefix| batch.refresh_for(&account, bucket, prefix, "/home/user");
+        assert_eq!(
+            refresh(Some("bucket"), Some("a/b/")),
+            BatchRefresh {
+                s3: true,
+                local: false
+            }
+        );
+        assert!(refresh(Some("bucket"), Some("a/")).s3);
+        assert!(refresh(Some("bucket"), None).s3);
+        assert!(!refresh(Some("bucket"), Some("c/")).s3);
+        assert!(!refresh(Some("other"), Some("a/b/")).s3);
+        assert!(!refresh(None, None).s3);
+        assert!(
+            !batch
+                .refresh_for("other-account", Some("bucket"), Some("a/b/"), "/")
+                .s3
+        );
+    }
+
+    #[test]
+    fn downloads_refresh_the_local_listing_showing_the_destination() {
+        let item = download("reports/2024/file.txt");
+        let mut batch = TransferBatch::default();
+        batch.start(&[TransferJob::Download(item.clone())]);
+        batch.finish_download(&item);
+
+        let local = |path| batch.refresh_for("", None, None, path).local;
+        assert!(local("/home/user/downloads/reports/2024"));
+        assert!(local("/home/user/downloads"));
+        assert!(!local("/home/user/documents"));
+        assert!(!local("/home/user/downloads/reports/2023"));
+    }
+
+    #[test]
+    fn failed_transfers_do_not_refresh() {
+        let item = S3SelectedItem {
+            error: Some("Access Denied".into()),
+            ..download("file.txt")
+        };
+        let mut batch = TransferBatch::default();
+        batch.start(&[TransferJob::Download(item.clone())]);
+        assert!(batch.finish_download(&item));
+        assert_eq!(
+            batch.refresh_for("", None, None, "/home/user/downloads"),
+            BatchRefresh::default()
+        );
+    }
+}
@@ -1,6 +1,70 @@
 use crate::model::local_selected_item::LocalSelectedItem;
 use crate::model::s3_selected_item::S3SelectedItem;
-use crate::settings::file_credentials::FileCredential;
+use crate::model::transfer_progress::progress_label;
+use crate::model::transfer_rate::TransferRate;
+use crate::model::verification::Verification;
+use crate::services::selection_limit::with_thousands_separators;
+
+/// Error of the transfers stopped before they finished, they can be run again
+pub const CANCELLED: &str = "cancelled";
+
+/// Where an unfinished transfer is in the run, shown instead of its progress until it starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransferState {
+    /// Not part of a running batch
+    #[default]
+    Queued,
+    /// Part of the running batch, waiting until one of the `slots` is free
+    Waiting { busy: usize, slots: usize },
+    /// Got a slot and is transferring
+    Active,
+}
+
+/// What happens to the transfer, the order is the one of the status sort: the transfers
+/// needing attention first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TransferStatus {
+    Failed,
+    Active,
+    Paused,
+    Waiting,
+    Queued,
+    Cancelled,
+    Done,
+}
+
+impl TransferStatus {
+    pub fn of(
+        progress: f64,
+        transferred: bool,
+        error: Option<&str>,
+        paused: bool,
+        state: TransferState,
+    ) -> Self {
+        match state {
+            _ if error == Some(CANCELLED) => TransferStatus::Cancelled,
+            _ if error.is_some() => TransferStatus::Failed,
+            _ if transferred => TransferStatus::Done,
+            _ if paused => TransferStatus::Paused,
+            TransferState::Active => TransferStatus::Active,
+            _ if progress > 0f64 => TransferStatus::Active,
+            TransferState::Waiting { .. } => TransferStatus::Waiting,
+            TransferState::Queued => TransferStatus::Queued,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TransferStatus::Failed => "failed",
+            TransferStatus::Active => "active",
+            TransferStatus::Paused => "paused",
+            TransferStatus::Waiting => "waiting",
+            TransferStatus::Queued => "queued",
+            TransferStatus::Cancelled => "cancelled",
+            TransferStatus::Done => "done",
+        }
+    }
+}
 
 /// Represents an item (file/directory/bucket) on your transfers list
 #[derive(Debug, Clone)]
@@ -11,26 +75,69 @@ pub struct TransferItem {
     pub path: Option<String>,
     pub destination_dir: String,
     pub transferred: bool,
-    pub s3_creds: FileCredential,
+    /// Name of the credential the transfer runs with
+    pub account: String,
     pub progress: f64,
     pub error: Option<String>,
+    pub state: TransferState,
+    /// Paused with 'p', it isn't run again until resumed
+    pub paused: bool,
+    /// Speed while the transfer is running
+    pub rate: Option<TransferRate>,
+    /// Check of the transferred object, `None` when it wasn't checked
+    pub verification: Option<Verification>,
+    /// Transferred and listed files of a directory or bucket, `None` for a single file
+    pub files: Option<(usize, usize)>,
 }
 
 impl TransferItem {
     pub fn to_columns(&self) -> Vec<String> {
-        let progress = format!("{:.2}%", self.progress);
+        let started = self.transferred || self.error.is_some() || self.progress > 0f64;
+        let progress = match self.state {
+            _ if self.paused && !started => "paused".into(),
+            TransferState::Queued if !started => "queued".into(),
+            TransferState::Waiting { busy, slots } if !started => {
+                format!("waiting ({} of {} slots busy)", busy, slots)
+            }
+            _ => progress_label(self.progress, self.transferred),
+        };
         vec![
             self.direction.clone(),
             self.bucket.clone(),
             self.name.clone(),
             self.destination_dir.clone(),
-            self.s3_creds.name.clone(),
+            self.account.clone(),
             progress,
+            self.files.map_or("".into(), |(done, total)| {
+                format!(
+                    "{} / {}",
+                    with_thousands_separators(done),
+                    with_thousands_separators(total)
+                )
+            }),
+            self.rate.map_or("-".into(), |rate| rate.speed_label()),
+            self.rate.map_or("-".into(), |rate| rate.eta_label()),
+            self.status().label().to_string(),
+            self.verification.map_or("-".into(), |v| v.label()),
             self.error.clone().unwrap_or("".to_string()),
         ]
     }
 
+    pub fn status(&self) -> TransferStatus {
+        TransferStatus::of(
+            self.progress,
+            self.transferred,
+            self.error.as_deref(),
+            self.paused,
+            self.state,
+        )
+    }
+
     pub fn from_s3_selected_item(item: S3SelectedItem) -> TransferItem {
+        let files = item
+            .children
+            .as_ref()
+            .map(|children| (item.completed_children, children.len()));
         TransferItem {
             direction: "↓".into(),
             bucket: item.bucket.unwrap_or("".into()),
@@ -38,13 +145,22 @@ impl TransferItem {
             path: item.path,
             destination_dir: item.destination_dir,
             transferred: item.transferred,
-            s3_creds: item.s3_creds,
+            account: item.account,
             progress: item.progress,
             error: item.error,
+            state: TransferState::default(),
+            paused: false,
+            rate: None,
+            verification: item.verification,
+            files,
         }
     }
 
     pub fn from_local_selected_item(item: LocalSelectedItem) -> TransferItem {
+        let files = item
+            .children
+            .as_ref()
+            .map(|children| (item.completed_children, children.len()));
         TransferItem {
             direction: "↑".into(),
             bucket: item.destination_bucket,
@@ -52,11 +168,28 @@ impl TransferItem {
             path: Some(item.path),
             destination_dir: item.destination_path,
             transferred: item.transferred,
-            s3_creds: item.s3_creds,
+            account: item.account,
             progress: item.progress,
             error: item.error,
+            state: TransferState::default(),
+            paused: false,
+            rate: None,
+            verification: item.verification,
+            files,
+        }
+    }
+
+    pub fn with_state(self, state: TransferState, paused: bool) -> TransferItem {
+        TransferItem {
+            state,
+            paused,
+            ..self
         }
     }
+
+    pub fn with_rate(self, rate: Option<TransferRate>) -> TransferItem {
+        TransferItem { rate, ..self }
+    }
 }
 
 impl PartialEq for TransferItem {
@@ -0,0 +1,283 @@
+//! This module provides the detection of downloads and uploads of the same run touching the
+//! same object or the same local file, their outcome would depend on which one runs first
+use crate::services::transfer_queue::TransferJob;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Which transfers of the overlapping pairs are run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapResolution {
+    /// The uploads of the overlapping pairs are dropped
+    KeepDownload,
+    /// The downloads of the overlapping pairs are dropped
+    KeepUpload,
+    /// The uploads of the overlapping pairs start once all the other transfers finished
+    DownloadThenUpload,
+}
+
+/// Download and upload of the queue reading or writing the same object or local file
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferOverlap {
+    /// `s3://bucket/key → local path` of the download
+    pub download: String,
+    /// `local path → s3://bucket/key` of the upload
+    pub upload: String,
+    /// Positions of the download and the upload in the queue
+    positions: (usize, usize),
+}
+
+/// Run waiting for the user to resolve the overlapping downloads and uploads
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlappingTransfers {
+    pub jobs: Vec<TransferJob>,
+    pub overlaps: Vec<TransferOverlap>,
+}
+
+/// Removes the `.` and `..` components without touching the filesystem, the files don't
+/// have to exist yet
+fn normalize_path(path: &Path, case_insensitive: bool) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    let normalized = normalized.to_string_lossy().into_owned();
+    if case_insensitive {
+        normalized.to_lowercase()
+    } else {
+        normalized
+    }
+}
+
+/// Object read by the download or written by the upload, objects of other accounts differ
+/// even when the bucket names are the same
+fn object(job: &TransferJob) -> (String, String) {
+    let (account, bucket, key) = match job {
+        TransferJob::Download(item) => (
+            &item.account,
+            item.bucket.clone().unwrap_or_default(),
+            item.path.clone().unwrap_or(item.name.clone()),
+        ),
+        TransferJob::Upload(item) => (
+            &item.account,
+            item.destination_bucket.clone(),
+            item.destination_key(),
+        ),
+    };
+    let display = format!("s3://{}/{}", bucket, key);
+    (format!("{}:{}", account, display), display)
+}
+
+/// Local file written by the download or read by the upload
+fn local_file(job: &TransferJob) -> PathBuf {
+    match job {
+        TransferJob::Download(item) => item.local_destination(),
+        TransferJob::Upload(item) => PathBuf::from(&item.path),
+    }
+}
+
+/// Pairs of a download and an upload of the same object, or of a download writing the
+/// local file an upload reads, in the order of the downloads
+pub fn find_overlaps(jobs: &[TransferJob], case_insensitive: bool) -> Vec<TransferOverlap> {
+    let mut uploads_by_object: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut uploads_by_file: HashMap<String, Vec<usize>> = HashMap::new();
+    for (position, job) in jobs.iter().enumerate() {
+        if let TransferJob::Upload(_) = job {
+            uploads_by_object
+                .entry(object(job).0)
+                .or_default()
+                .push(position);
+            uploads_by_file
+                .entry(normalize_path(&local_file(job), case_insensitive))
+                .or_default()
+                .push(position);
+        }
+    }
+    let mut overlaps = Vec::new();
+    for (download_position, job) in jobs.iter().enumerate() {
+        if let TransferJob::Upload(_) = job {
+            continue;
+        }
+        let (object_key, download_object) = object(job);
+        let download_file = local_file(job);
+        let file_key = normalize_path(&download_file, case_insensitive);
+        let mut matched = HashSet::new();
+        let uploads = uploads_by_object
+            .get(&object_key)
+            .into_iter()
+            .chain(uploads_by_file.get(&file_key))
+            .flatten();
+        for &upload_position in uploads {
+            if matched.insert(upload_position) {
+                let upload = &jobs[upload_position];
+                overlaps.push(TransferOverlap {
+                    download: format!("{} → {}", download_object, download_file.display()),
+                    upload: format!("{} → {}", local_file(upload).display(), object(upload).1),
+                    positions: (download_position, upload_position),
+                });
+            }
+        }
+    }
+    overlaps
+}
+
+/// Applies the resolution to the overlaps found in the jobs. Returns the jobs run right away
+/// and the ones started after all of them finished, both keep their order
+pub fn resolve_overlaps(
+    jobs: Vec<TransferJob>,
+    resolution: OverlapResolution,
+    case_insensitive: bool,
+) -> (Vec<TransferJob>, Vec<TransferJob>) {
+    let overlaps = find_overlaps(&jobs, case_insensitive);
+    let positions: HashSet<usize> = overlaps
+        .iter()
+        .map(|overlap| match resolution {
+            OverlapResolution::KeepUpload => overlap.positions.0,
+            _ => overlap.positions.1,
+        })
+        .collect();
+    let mut now = Vec::new();
+    let mut deferred = Vec::new();
+    for (position, job) in jobs.into_iter().enumerate() {
+        if !positions.contains(&position) {
+            now.push(job);
+        } else if resolution == OverlapResolution::DownloadThenUpload {
+            deferred.push(job);
+        }
+    }
+    (now, deferred)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::local_selected_item::LocalSelectedItem;
+    use crate::model::s3_selected_item::S3SelectedItem;
+
+    fn upload(path: &str, bucket: &str, key: &str) -> TransferJob {
+        TransferJob::Upload(LocalSelectedItem::new(
+            path.rsplit('/').next().unwrap().into(),
+            path.into(),
+            false,
+            bucket.into(),
+            key.into(),
+            "aws".into(),
+            None,
+        ))
+    }
+
+    fn download(bucket: &str, key: &str, destination_dir: &str) -> TransferJob {
+        TransferJob::Download(S3SelectedItem {
+            bucket: Some(bucket.into()),
+            name: key.rsplit('/').next().unwrap().into(),
+            path: Some(key.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: destination_dir.into(),
+            transferred: false,
+            account: "aws".into(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        })
+    }
+
+    #[test]
+    fn same_object_in_both_directions_overlaps() {
+        let jobs = vec![
+            download("bucket", "report.csv", "/home/user/downloads"),
+            upload("/home/user/report.csv", "bucket", "report.csv"),
+            upload("/home/user/other.csv", "bucket", "other.csv"),
+        ];
+        let overlaps = find_overlaps(&jobs, false);
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(
+            overlaps[0].download,
+            "s3://bucket/report.csv → /home/user/downloads/report.csv"
+        );
+        assert_eq!(
+            overlaps[0].upload,
+            "/home/user/report.csv → s3://bucket/report.csv"
+        );
+        assert_eq!(overlaps[0].positions, (0, 1));
+    }
+
+    #[test]
+    fn same_key_of_another_bucket_or_account_does_not_overlap() {
+        let mut other_account = download("bucket", "report.csv", "/tmp");
+        if let TransferJob::Download(item) = &mut other_account {
+            item.account = "onprem".into();
+        }
+        let jobs = vec![
+            download("archive", "report.csv", "/tmp"),
+            other_account,
+            upload("/home/user/report.csv", "bucket", "report.csv"),
+            // s3 keys are case sensitive
+            download("bucket", "Report.csv", "/tmp"),
+        ];
+        assert!(find_overlaps(&jobs, true).is_empty());
+    }
+
+    #[test]
+    fn download_written_to_an_uploaded_file_overlaps_after_normalization() {
+        let jobs = vec![
+            download("bucket", "data/report.csv", "/home/user/./work/../sync"),
+            upload("/home/user/sync/data/report.csv", "backup", "report.csv"),
+        ];
+        let overlaps = find_overlaps(&jobs, false);
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(
+            overlaps[0].upload,
+            "/home/user/sync/data/report.csv → s3://backup/report.csv"
+        );
+    }
+
+    #[test]
+    fn local_case_matters_only_on_case_sensitive_filesystems() {
+        let jobs = vec![
+            download("bucket", "Report.csv", "/home/user"),
+            upload("/home/user/report.csv", "backup", "report.csv"),
+        ];
+        assert_eq!(find_overlaps(&jobs, true).len(), 1);
+        assert!(find_overlaps(&jobs, false).is_empty());
+    }
+
+    #[test]
+    fn pair_matching_the_object_and_the_file_is_listed_once() {
+        let jobs = vec![
+            download("bucket", "report.csv", "/home/user"),
+            upload("/home/user/report.csv", "bucket", "report.csv"),
+        ];
+        assert_eq!(find_overlaps(&jobs, false).len(), 1);
+    }
+
+    #[test]
+    fn resolutions_drop_or_defer_one_side() {
+        let jobs = vec![
+            upload("/home/user/report.csv", "bucket", "report.csv"),
+            download("bucket", "report.csv", "/home/user/downloads"),
+            download("bucket", "other.csv", "/home/user/downloads"),
+        ];
+        let (now, deferred) =
+            resolve_overlaps(jobs.clone(), OverlapResolution::KeepDownload, false);
+        assert_eq!(now, vec![jobs[1].clone(), jobs[2].clone()]);
+        assert!(deferred.is_empty());
+
+        let (now, deferred) = resolve_overlaps(jobs.clone(), OverlapResolution::KeepUpload, false);
+        assert_eq!(now, vec![jobs[0].clone(), jobs[2].clone()]);
+        assert!(deferred.is_empty());
+
+        let (now, deferred) =
+            resolve_overlaps(jobs.clone(), OverlapResolution::DownloadThenUpload, false);
+        assert_eq!(now, vec![jobs[1].clone(), jobs[2].clone()]);
+        assert_eq!(deferred, vec![jobs[0].clone()]);
+    }
+}
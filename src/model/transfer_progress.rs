@@ -0,0 +1,50 @@
+//! This module provides the progress of directories transferred file by file and the way
+//! the progress is displayed. Whether a transfer finished is never decided from these values,
+//! only from the `transferred` flags of its files
+
+/// Largest progress displayed for a transfer which didn't finish yet
+const UNFINISHED_MAX: f64 = 99.99;
+
+/// Mean progress of the files of a directory, 0 for an empty one
+pub fn overall_progress(progress: impl IntoIterator<Item = f64>) -> f64 {
+    let (sum, count) = progress
+        .into_iter()
+        .fold((0f64, 0usize), |(sum, count), p| (sum + p, count + 1));
+    if count == 0 {
+        return 0.0;
+    }
+    (sum / count as f64).clamp(0.0, 100.0)
+}
+
+/// Text of the progress column, an unfinished transfer shows at most 99.99% even when its
+/// files are all nearly done
+pub fn progress_label(progress: f64, transferred: bool) -> String {
+    let max = if transferred { 100.0 } else { UNFINISHED_MAX };
+    format!("{:.2}%", progress.clamp(0.0, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overall_progress_is_the_mean_of_the_files() {
+        assert_eq!(overall_progress(Vec::new()), 0.0);
+        assert_eq!(overall_progress([0.0, 50.0]), 25.0);
+        assert_eq!(overall_progress([100.0, 100.0]), 100.0);
+        assert_eq!(overall_progress([100.0, 100.5]), 100.0);
+    }
+
+    #[test]
+    fn nearly_done_transfer_is_not_displayed_as_done() {
+        let mut progress = vec![100.0; 999];
+        progress.push(100.0 - 1e-13);
+        let overall = overall_progress(progress);
+        // the mean rounds up to exactly 100
+        assert_eq!(overall, 100.0);
+        assert_eq!(progress_label(overall, false), "99.99%");
+        assert_eq!(progress_label(99.999, false), "99.99%");
+        assert_eq!(progress_label(overall, true), "100.00%");
+        assert_eq!(progress_label(42.5, false), "42.50%");
+    }
+}
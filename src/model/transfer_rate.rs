@@ -0,0 +1,158 @@
+//! This module provides the speed and the remaining time of the running transfers, measured
+//! from the bytes reported with their progress
+use crate::utils::{format_bytes, format_duration};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Period the speed is averaged over, so it doesn't jump with every received chunk
+const SPEED_WINDOW: Duration = Duration::from_secs(5);
+
+/// Bytes reported by a running transfer within the speed window
+#[derive(Debug, Clone, Default)]
+struct Samples {
+    points: VecDeque<(Instant, u64)>,
+    total_bytes: u64,
+}
+
+impl Samples {
+    fn add(&mut self, now: Instant, bytes: u64, total_bytes: u64) {
+        self.points.push_back((now, bytes));
+        self.total_bytes = total_bytes;
+        // the oldest sample is kept as the start of the window
+        while self.points.len() > 2 && self.points[1].0 + SPEED_WINDOW <= now {
+            self.points.pop_front();
+        }
+    }
+
+    /// Bytes per second within the window, unknown until two samples were received
+    fn speed(&self) -> Option<f64> {
+        let (first, last) = (self.points.front()?, self.points.back()?);
+        let elapsed = last.0.duration_since(first.0).as_secs_f64();
+        if elapsed <= 0f64 {
+            return None;
+        }
+        Some(last.1.saturating_sub(first.1) as f64 / elapsed)
+    }
+
+    fn remaining_bytes(&self) -> u64 {
+        let transferred = self.points.back().map_or(0, |(_, bytes)| *bytes);
+        self.total_bytes.saturating_sub(transferred)
+    }
+}
+
+/// Speed of one or more transfers and the time left at that speed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransferRate {
+    /// Bytes per second
+    pub speed: f64,
+    /// Unknown while nothing is transferred
+    pub eta: Option<Duration>,
+}
+
+impl TransferRate {
+    pub fn speed_label(&self) -> String {
+        format!("{}/s", format_bytes(self.speed as u64))
+    }
+
+    pub fn eta_label(&self) -> String {
+        self.eta.map(format_duration).unwrap_or("-".into())
+    }
+}
+
+/// Samples of the running transfers by their key (see `TransferJob::key`)
+#[derive(Debug, Clone, Default)]
+pub struct TransferRates {
+    running: HashMap<String, Samples>,
+}
+
+impl TransferRates {
+    pub fn record(&mut self, key: String, bytes: u64, total_bytes: u64, now: Instant) {
+        self.running
+            .entry(key)
+            .or_default()
+            .add(now, bytes, total_bytes);
+    }
+
+    /// Forgets the finished, failed or stopped transfer
+    pub fn finish(&mut self, key: &str) {
+        self.running.remove(key);
+    }
+
+    pub fn clear(&mut self) {
+        self.running.clear();
+    }
+
+    /// Combined rate of the transfers with the given keys, `None` when none of them is
+    /// running or their speed isn't known yet
+    pub fn of<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> Option<TransferRate> {
+        Self::combined(keys.into_iter().filter_map(|key| self.running.get(key)))
+    }
+
+    /// Rate of all the running transfers
+    pub fn overall(&self) -> Option<TransferRate> {
+        Self::combined(self.running.values())
+    }
+
+    fn combined<'a>(samples: impl Iterator<Item = &'a Samples>) -> Option<TransferRate> {
+        let (speed, remaining) = samples
+            .filter_map(|s| s.speed().map(|speed| (speed, s.remaining_bytes())))
+            .fold(None, |sum: Option<(f64, u64)>, (speed, remaining)| {
+                let (total_speed, total_remaining) = sum.unwrap_or_default();
+                Some((total_speed + speed, total_remaining + remaining))
+            })?;
+        let eta = (speed > 0f64).then(|| Duration::from_secs_f64(remaining as f64 / speed));
+        Some(TransferRate { speed, eta })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_is_averaged_over_the_window() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut rates = TransferRates::default();
+        rates.record("↓bucket/a.bin".into(), 0, 1000, at(0));
+        assert_eq!(rates.of(["↓bucket/a.bin"]), None);
+
+        rates.record("↓bucket/a.bin".into(), 100, 1000, at(1));
+        rates.record("↓bucket/a.bin".into(), 300, 1000, at(2));
+        let rate = rates.of(["↓bucket/a.bin"]).unwrap();
+        assert_eq!(rate.speed, 150.0);
+        assert_eq!(rate.eta, Some(Duration::from_secs_f64(700.0 / 150.0)));
+
+        // the first seconds leave the window
+        for secs in 3..=8 {
+            rates.record(
+                "↓bucket/a.bin".into(),
+                300 + (secs - 2) * 10,
+                1000,
+                at(secs),
+            );
+        }
+        assert_eq!(rates.of(["↓bucket/a.bin"]).unwrap().speed, 10.0);
+    }
+
+    #[test]
+    fn rates_of_several_transfers_add_up() {
+        let start = Instant::now();
+        let mut rates = TransferRates::default();
+        for (key, speed) in [("↑/tmp/a.txt", 100), ("↑/tmp/b.txt", 300)] {
+            rates.record(key.into(), 0, 2000, start);
+            rates.record(key.into(), speed * 2, 2000, start + Duration::from_secs(2));
+        }
+        let overall = rates.overall().unwrap();
+        assert_eq!(overall.speed, 400.0);
+        assert_eq!(overall.eta, Some(Duration::from_secs(8)));
+        assert_eq!(overall.speed_label(), "400 B/s");
+
+        rates.finish("↑/tmp/b.txt");
+        assert_eq!(
+            rates.of(["↑/tmp/a.txt", "↑/tmp/b.txt"]).unwrap().speed,
+            100.0
+        );
+        assert_eq!(rates.of(["↑/tmp/b.txt"]), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! This module provides the order of the rows on the transfers page
+use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::s3_selected_item::S3SelectedItem;
+use crate::model::transfer_item::{TransferState, TransferStatus};
+use std::cmp::Ordering;
+
+/// Column the transfers are sorted by, `Added` keeps the order they were selected in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransferSortColumn {
+    #[default]
+    Added,
+    Name,
+    Progress,
+    Status,
+    Account,
+}
+
+impl TransferSortColumn {
+    fn next(self) -> Self {
+        match self {
+            TransferSortColumn::Added => TransferSortColumn::Name,
+            TransferSortColumn::Name => TransferSortColumn::Progress,
+            TransferSortColumn::Progress => TransferSortColumn::Status,
+            TransferSortColumn::Status => TransferSortColumn::Account,
+            TransferSortColumn::Account => TransferSortColumn::Added,
+        }
+    }
+}
+
+/// Values of a row the transfers are compared by
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferSortKey {
+    name: String,
+    progress: f64,
+    status: TransferStatus,
+    account: String,
+}
+
+impl TransferSortKey {
+    fn new(name: &str, progress: f64, status: TransferStatus, account: &str) -> Self {
+        TransferSortKey {
+            name: name.to_lowercase(),
+            progress,
+            status,
+            account: account.to_string(),
+        }
+    }
+
+    pub fn of_download(item: &S3SelectedItem, state: TransferState, paused: bool) -> Self {
+        let error = item.error.as_deref();
+        let status = TransferStatus::of(item.progress, item.transferred, error, paused, state);
+        Self::new(&item.name, item.progress, status, &item.account)
+    }
+
+    pub fn of_upload(item: &LocalSelectedItem, state: TransferState, paused: bool) -> Self {
+        let error = item.error.as_deref();
+        let status = TransferStatus::of(item.progress, item.transferred, error, paused, state);
+        Self::new(&item.name, item.progress, status, &item.account)
+    }
+}
+
+/// Sort of the transfers page, kept in the state while switching pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransferSort {
+    pub column: TransferSortColumn,
+    pub descending: bool,
+}
+
+impl TransferSort {
+    /// Sorts by the next column keeping the direction, after the last one by the added order
+    pub fn next_column(self) -> Self {
+        TransferSort {
+            column: self.column.next(),
+            ..self
+        }
+    }
+
+    pub fn reversed(self) -> Self {
+        TransferSort {
+            descending: !self.descending,
+            ..self
+        }
+    }
+
+    /// Whether the rows are listed in another order than they were selected in
+    pub fn is_active(self) -> bool {
+        self != TransferSort::default()
+    }
+
+    /// Arrow of the direction, shown after the header of the sorted column
+    pub fn arrow(self) -> &'static str {
+        if self.descending {
+            "▼"
+        } else {
+            "▲"
+        }
+    }
+
+    fn compare(self, a: &TransferSortKey, b: &TransferSortKey) -> Ordering {
+        match self.column {
+            TransferSortColumn::Added => Ordering::Equal,
+            TransferSortColumn::Name => a.name.cmp(&b.name),
+            TransferSortColumn::Progress => a.progress.total_cmp(&b.progress),
+            TransferSortColumn::Status => a.status.cmp(&b.status),
+            TransferSortColumn::Account => a.account.cmp(&b.account),
+        }
+    }
+
+    /// Indexes of the rows in the displayed order. The sort is stable, rows comparing equal
+    /// keep the order they were added in whatever the direction
+    pub fn order(self, keys: &[TransferSortKey]) -> Vec<usize> {
+        let mut rows: Vec<usize> = (0..keys.len()).collect();
+        match (self.column, self.descending) {
+            (TransferSortColumn::Added, true) => rows.reverse(),
+            (_, false) => rows.sort_by(|&a, &b| self.compare(&keys[a], &keys[b])),
+            (_, true) => rows.sort_by(|&a, &b| self.compare(&keys[b], &keys[a])),
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str, progress: f64, account: &str) -> TransferSortKey {
+        let status = match progress >= 100f64 {
+            true => TransferStatus::Done,
+            false => TransferStatus::Active,
+        };
+        TransferSortKey::new(name, progress, status, account)
+    }
+
+    #[test]
+    fn rows_with_the_same_progress_keep_their_order() {
+        let keys = [
+            key("a.txt", 50.0, "prod"),
+            key("b.txt", 10.0, "prod"),
+            key("c.txt", 50.0, "dev"),
+            key("d.txt", 100.0, "dev"),
+            key("e.txt", 10.0, "prod"),
+        ];
+        let sort = TransferSort {
+            column: TransferSortColumn::Progress,
+            descending: false,
+        };
+        assert_eq!(sort.order(&keys), vec![1, 4, 0, 2, 3]);
+        assert_eq!(sort.reversed().order(&keys), vec![3, 0, 2, 1, 4]);
+        assert_eq!(
+            sort.next_column().next_column().order(&keys),
+            vec![2, 3, 0, 1, 4]
+        );
+    }
+
+    #[test]
+    fn failed_and_running_transfers_come_first_by_status() {
+        let status = |error: Option<&str>, paused, state| {
+            TransferStatus::of(0.0, false, error, paused, state)
+        };
+        let waiting = TransferState::Waiting { busy: 4, slots: 4 };
+        let queued = TransferState::Queued;
+        let keys = [
+            key("done.txt", 100.0, "prod"),
+            TransferSortKey::new("queued.txt", 0.0, status(None, false, queued), ""),
+            TransferSortKey::new(
+                "failed.txt",
+                0.0,
+                status(Some("timeout"), false, waiting),
+                "",
+            ),
+            TransferSortKey::new("waiting.txt", 0.0, status(None, false, waiting), ""),
+            TransferSortKey::new(
+                "active.txt",
+                0.0,
+                status(None, false, TransferState::Active),
+                "",
+            ),
+            TransferSortKey::new("paused.txt", 0.0, status(None, true, waiting), ""),
+        ];
+        let sort = TransferSort {
+            column: TransferSortColumn::Status,
+            descending: false,
+        };
+        assert_eq!(sort.order(&keys), vec![2, 4, 5, 3, 1, 0]);
+    }
+
+    #[test]
+    fn columns_cycle_back_to_the_added_order() {
+        let mut sort = TransferSort::default().reversed();
+        let keys = [key("b", 0.0, ""), key("A", 0.0, ""), key("c", 0.0, "")];
+        assert_eq!(sort.order(&keys), vec![2, 1, 0]);
+        sort = sort.next_column();
+        assert_eq!(sort.column, TransferSortColumn::Name);
+        assert_eq!(sort.order(&keys), vec![2, 0, 1]);
+        for _ in 0..4 {
+            sort = sort.next_column();
+        }
+        assert_eq!(sort.column, TransferSortColumn::Added);
+        assert!(sort.is_active());
+        assert!(!sort.reversed().is_active());
+    }
+}
@@ -2,5 +2,10 @@
 #[derive(Debug, Clone)]
 pub struct UploadProgressItem {
     pub progress: f64,
-    pub uri: String,
+    pub bucket: String,
+    /// Destination key of the upload, as given by `LocalSelectedItem::destination_key`
+    pub key: String,
+    /// Bytes sent so far, the speed of the upload is measured from them
+    pub bytes: u64,
+    pub total_bytes: u64,
 }
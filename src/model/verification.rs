@@ -0,0 +1,215 @@
+//! This module provides the check of the transferred objects against their local files, run
+//! after the upload as configured with `upload_verification` and after the download unless
+//! `verify_downloads` is turned off
+use std::fmt;
+
+/// How the uploads are checked once s3 accepted them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UploadVerification {
+    #[default]
+    Off,
+    /// The ETag s3 returned is compared with the md5 of the local file, for the uploads in
+    /// parts with the md5 of the md5s of the parts
+    Etag,
+    /// The object is downloaded again and its sha-256 compared with the one of the local file
+    FullReadback,
+}
+
+impl UploadVerification {
+    /// Parses the `upload_verification` setting
+    pub fn from_setting(value: &str) -> Result<UploadVerification, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "off" => Ok(UploadVerification::Off),
+            "etag" => Ok(UploadVerification::Etag),
+            "full-readback" => Ok(UploadVerification::FullReadback),
+            other => Err(format!(
+                "Unknown upload_verification setting '{}', use off, etag or full-readback",
+                other
+            )),
+        }
+    }
+}
+
+/// What the transferred object was compared by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationMethod {
+    Etag,
+    Sha256,
+}
+
+impl VerificationMethod {
+    fn label(self) -> &'static str {
+        match self {
+            VerificationMethod::Etag => "etag",
+            VerificationMethod::Sha256 => "sha-256",
+        }
+    }
+}
+
+/// Result of the check of a transferred object, shown in the Verification column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Passed(VerificationMethod),
+    Failed(VerificationMethod),
+    /// The ETag isn't derived from the content, e.g. of the objects encrypted with KMS
+    Skipped,
+}
+
+impl Verification {
+    pub fn label(self) -> String {
+        match self {
+            Verification::Passed(method) => format!("{} ok", method.label()),
+            Verification::Failed(method) => format!("{} failed", method.label()),
+            Verification::Skipped => "skipped".into(),
+        }
+    }
+}
+
+/// The object in the bucket differs from the local file, it's kept there to be looked into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationError {
+    pub method: VerificationMethod,
+    /// Digest of the local file
+    pub expected: String,
+    /// Digest of the object
+    pub actual: String,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "verification failed, {} of the object '{}' differs from '{}' of the local file",
+            self.method.label(),
+            self.actual,
+            self.expected
+        )
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+/// Objects encrypted with KMS get an ETag which isn't a digest of their content
+pub fn etag_is_checkable(server_side_encryption: Option<&str>) -> bool {
+    !server_side_encryption.is_some_and(|sse| sse.starts_with("aws:kms"))
+}
+
+/// Digest of the object a downloaded file is compared with, from its head request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadCheck {
+    /// The ETag of an object uploaded in one request, the md5 of its content
+    Md5(String),
+    /// The hex sha-256 of the content, stored by s3 as an additional checksum
+    Sha256(String),
+    /// The ETag isn't an md5 and there's no sha-256 of the whole content
+    Unavailable,
+}
+
+impl DownloadCheck {
+    /// The ETag of objects uploaded in parts (`<md5>-<parts>`) and of encrypted ones isn't
+    /// the md5 of their content, neither is a sha-256 of the parts (`<sha-256>-<parts>`)
+    pub fn new(etag: Option<&str>, etag_checkable: bool, sha256_hex: Option<String>) -> Self {
+        let etag = etag.map(|etag| etag.trim_matches('"'));
+        match (etag, sha256_hex) {
+            (Some(etag), _) if etag_checkable && !etag.is_empty() && !etag.contains('-') => {
+                DownloadCheck::Md5(etag.to_string())
+            }
+            (_, Some(sha256)) => DownloadCheck::Sha256(sha256),
+            _ => DownloadCheck::Unavailable,
+        }
+    }
+}
+
+/// Compares the ETag returned for the upload with the one expected from the local file
+pub fn check_etag(expected: &str, etag: Option<&str>) -> Result<Verification, VerificationError> {
+    check(
+        VerificationMethod::Etag,
+        expected,
+        etag.unwrap_or_default().trim_matches('"'),
+    )
+}
+
+pub fn check_sha256(expected: &str, actual: &str) -> Result<Verification, VerificationError> {
+    check(VerificationMethod::Sha256, expected, actual)
+}
+
+fn check(
+    method: VerificationMethod,
+    expected: &str,
+    actual: &str,
+) -> Result<Verification, VerificationError> {
+    if expected.eq_ignore_ascii_case(actual) {
+        Ok(Verification::Passed(method))
+    } else {
+        Err(VerificationError {
+            method,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_is_parsed() {
+        assert_eq!(
+            UploadVerification::from_setting("Full-Readback"),
+            Ok(UploadVerification::FullReadback)
+        );
+        assert_eq!(
+            UploadVerification::from_setting("etag"),
+            Ok(UploadVerification::Etag)
+        );
+        assert!(UploadVerification::from_setting("md5").is_err());
+    }
+
+    #[test]
+    fn quoted_etag_is_compared_with_the_expected_one() {
+        let etag = Some("\"E09E4FD6265B36115FE3DB32DF945D84-2\"");
+        assert_eq!(
+            check_etag("e09e4fd6265b36115fe3db32df945d84-2", etag),
+            Ok(Verification::Passed(VerificationMethod::Etag))
+        );
+        let error = check_etag("e09e4fd6265b36115fe3db32df945d84-3", etag).unwrap_err();
+        assert_eq!(error.actual, "E09E4FD6265B36115FE3DB32DF945D84-2");
+        assert_eq!(Verification::Failed(error.method).label(), "etag failed");
+        assert!(check_etag("d41d8cd98f00b204e9800998ecf8427e", None).is_err());
+    }
+
+    #[test]
+    fn downloads_are_checked_by_the_md5_etag_or_the_sha256() {
+        let sha256 = || Some("9f86d081884c7d659a2feaa0c55ad015".to_string());
+        assert_eq!(
+            DownloadCheck::new(Some("\"d41d8cd98f00b204e9800998ecf8427e\""), true, sha256()),
+            DownloadCheck::Md5("d41d8cd98f00b204e9800998ecf8427e".into())
+        );
+        assert_eq!(
+            DownloadCheck::new(
+                Some("\"e09e4fd6265b36115fe3db32df945d84-2\""),
+                true,
+                sha256()
+            ),
+            DownloadCheck::Sha256("9f86d081884c7d659a2feaa0c55ad015".into())
+        );
+        assert_eq!(
+            DownloadCheck::new(Some("d41d8cd98f00b204e9800998ecf8427e"), false, None),
+            DownloadCheck::Unavailable
+        );
+        assert_eq!(
+            DownloadCheck::new(Some("e09e4fd6265b36115fe3db32df945d84-2"), true, None),
+            DownloadCheck::Unavailable
+        );
+    }
+
+    #[test]
+    fn kms_etags_are_not_checked() {
+        assert!(etag_is_checkable(None));
+        assert!(etag_is_checkable(Some("AES256")));
+        assert!(!etag_is_checkable(Some("aws:kms")));
+        assert!(!etag_is_checkable(Some("aws:kms:dsse")));
+        assert_eq!(Verification::Skipped.label(), "skipped");
+    }
+}
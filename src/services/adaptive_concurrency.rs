@@ -0,0 +1,288 @@
+//! This module provides the number of transfers allowed to run at once for every account.
+//! Each account starts at the configured limit, loses one slot when its endpoint keeps
+//! failing with throttling responses or timeouts and gets it back after a run of successes
+use crate::services::s3_errors::ErrorClass;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Number of the most recent requests the error rate and the latency are computed from
+pub const HEALTH_WINDOW: usize = 20;
+/// Requests needed since the last change of the limit before it's lowered again
+pub const MIN_SAMPLES: usize = 5;
+/// Share of overload errors among the requests since the last change which lowers the limit
+pub const DECREASE_ERROR_RATE: f64 = 0.2;
+/// Successes in a row after which the limit is raised by one again
+pub const PROBE_AFTER: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    Success,
+    /// Throttled or timed out, the endpoint is likely overloaded
+    Overloaded,
+    /// Failed for a reason the load has nothing to do with, e.g. a missing object
+    Failed,
+}
+
+impl RequestOutcome {
+    pub fn of_error(class: ErrorClass) -> Self {
+        match class {
+            ErrorClass::Throttled | ErrorClass::Timeout => RequestOutcome::Overloaded,
+            _ => RequestOutcome::Failed,
+        }
+    }
+}
+
+/// Current limit of an account with the numbers it's based on, displayed on the creds page
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountLoad {
+    pub limit: usize,
+    pub configured: usize,
+    /// Share of overload errors among the recent requests
+    pub error_rate: f64,
+    pub average_latency: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct AccountHealth {
+    configured: usize,
+    limit: usize,
+    running: usize,
+    /// Ring buffer of the recent requests
+    recent: VecDeque<(RequestOutcome, Duration)>,
+    since_change: usize,
+    healthy_streak: usize,
+}
+
+impl AccountHealth {
+    fn new(configured: usize) -> Self {
+        AccountHealth {
+            configured,
+            limit: configured,
+            running: 0,
+            recent: VecDeque::with_capacity(HEALTH_WINDOW),
+            since_change: 0,
+            healthy_streak: 0,
+        }
+    }
+
+    fn overloaded_among_last(&self, count: usize) -> usize {
+        self.recent
+            .iter()
+            .rev()
+            .take(count)
+            .filter(|(outcome, _)| *outcome == RequestOutcome::Overloaded)
+            .count()
+    }
+
+    /// Records the request and adjusts the limit, returns whether the limit was raised
+    fn record(&mut self, outcome: RequestOutcome, latency: Duration) -> bool {
+        if self.recent.len() == HEALTH_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((outcome, latency));
+        self.since_change += 1;
+        match outcome {
+            RequestOutcome::Success => self.healthy_streak += 1,
+            RequestOutcome::Overloaded => self.healthy_streak = 0,
+            RequestOutcome::Failed => {}
+        }
+
+        let samples = self.since_change.min(HEALTH_WINDOW);
+        let error_rate = self.overloaded_among_last(samples) as f64 / samples as f64;
+        if self.since_change >= MIN_SAMPLES && error_rate >= DECREASE_ERROR_RATE {
+            if self.limit > 1 {
+                self.limit -= 1;
+                tracing::warn!(
+                    "Concurrency lowered to {} after overload errors",
+                    self.limit
+                );
+            }
+            self.since_change = 0;
+            false
+        } else if self.healthy_streak >= PROBE_AFTER && self.limit < self.configured {
+            self.limit += 1;
+            self.since_change = 0;
+            self.healthy_streak = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn load(&self) -> AccountLoad {
+        let samples = self.recent.len();
+        AccountLoad {
+            limit: self.limit,
+            configured: self.configured,
+            error_rate: if samples == 0 {
+                0.0
+            } else {
+                self.overloaded_among_last(samples) as f64 / samples as f64
+            },
+            average_latency: (samples > 0).then(|| {
+                self.recent
+                    .iter()
+                    .map(|(_, latency)| *latency)
+                    .sum::<Duration>()
+                    / samples as u32
+            }),
+        }
+    }
+}
+
+/// Limits of all the accounts, shared by the dispatcher and the running transfers
+#[derive(Debug, Clone, Default)]
+pub struct AdaptiveConcurrency {
+    accounts: Arc<Mutex<HashMap<String, AccountHealth>>>,
+    /// Woken when a slot is freed or a limit is raised
+    slot_freed: Arc<Notify>,
+}
+
+/// Slot of a running transfer, freed when dropped
+#[derive(Debug)]
+pub struct AccountPermit {
+    concurrency: AdaptiveConcurrency,
+    account: String,
+}
+
+impl AdaptiveConcurrency {
+    fn accounts(&self) -> MutexGuard<'_, HashMap<String, AccountHealth>> {
+        self.accounts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits until the account runs less transfers than its limit, `configured` is the
+    /// limit of an account not seen before
+    pub async fn acquire(&self, account: String, configured: usize) -> AccountPermit {
+        loop {
+            let slot_freed = self.slot_freed.notified();
+            tokio::pin!(slot_freed);
+            // registered before checking, so a slot freed in between isn't missed
+            slot_freed.as_mut().enable();
+            {
+                let mut accounts = self.accounts();
+                let health = accounts
+                    .entry(account.clone())
+                    .or_insert_with(|| AccountHealth::new(configured.max(1)));
+                if health.running < health.limit {
+                    health.running += 1;
+                    return AccountPermit {
+                        concurrency: self.clone(),
+                        account,
+                    };
+                }
+            }
+            slot_freed.await;
+        }
+    }
+
+    pub fn record(&self, account: &str, outcome: RequestOutcome, latency: Duration) {
+        let raised = match self.accounts().get_mut(account) {
+            Some(health) => health.record(outcome, latency),
+            None => false,
+        };
+        if raised {
+            self.slot_freed.notify_waiters();
+        }
+    }
+
+    pub fn loads(&self) -> HashMap<String, AccountLoad> {
+        self.accounts()
+            .iter()
+            .map(|(account, health)| (account.clone(), health.load()))
+            .collect()
+    }
+}
+
+impl Drop for AccountPermit {
+    fn drop(&mut self) {
+        if let Some(health) = self.concurrency.accounts().get_mut(&self.account) {
+            health.running = health.running.saturating_sub(1);
+        }
+        self.concurrency.slot_freed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LATENCY: Duration = Duration::from_millis(100);
+
+    fn record_many(health: &mut AccountHealth, outcome: RequestOutcome, count: usize) {
+        for _ in 0..count {
+            health.record(outcome, LATENCY);
+        }
+    }
+
+    #[test]
+    fn error_burst_lowers_the_limit_step_by_step() {
+        let mut health = AccountHealth::new(8);
+        record_many(&mut health, RequestOutcome::Success, 4);
+        health.record(RequestOutcome::Overloaded, LATENCY);
+        // 1 of the 5 requests since the start is an overload error
+        assert_eq!(health.limit, 7);
+
+        record_many(&mut health, RequestOutcome::Overloaded, MIN_SAMPLES * 3);
+        assert_eq!(health.limit, 4);
+        record_many(&mut health, RequestOutcome::Overloaded, MIN_SAMPLES * 10);
+        assert_eq!(health.limit, 1);
+    }
+
+    #[test]
+    fn errors_unrelated_to_the_load_keep_the_limit() {
+        let mut health = AccountHealth::new(8);
+        record_many(&mut health, RequestOutcome::Failed, HEALTH_WINDOW * 2);
+        assert_eq!(health.limit, 8);
+        assert_eq!(
+            RequestOutcome::of_error(ErrorClass::Service),
+            RequestOutcome::Failed
+        );
+        assert_eq!(
+            RequestOutcome::of_error(ErrorClass::Throttled),
+            RequestOutcome::Overloaded
+        );
+    }
+
+    #[test]
+    fn limit_recovers_after_successes_up_to_the_configured_one() {
+        let mut health = AccountHealth::new(6);
+        record_many(&mut health, RequestOutcome::Overloaded, MIN_SAMPLES * 2);
+        assert_eq!(health.limit, 4);
+
+        record_many(&mut health, RequestOutcome::Success, PROBE_AFTER - 1);
+        assert_eq!(health.limit, 4);
+        health.record(RequestOutcome::Success, LATENCY);
+        assert_eq!(health.limit, 5);
+        record_many(&mut health, RequestOutcome::Success, PROBE_AFTER * 5);
+        assert_eq!(health.limit, 6);
+
+        let load = health.load();
+        assert_eq!(load.error_rate, 0.0);
+        assert_eq!(load.average_latency, Some(LATENCY));
+    }
+
+    #[tokio::test]
+    async fn account_waits_for_a_free_slot() {
+        let concurrency = AdaptiveConcurrency::default();
+        let first = concurrency.acquire("onprem".into(), 1).await;
+        // other accounts have their own slots
+        let _other = concurrency.acquire("aws".into(), 1).await;
+
+        let waiting = tokio::spawn({
+            let concurrency = concurrency.clone();
+            async move { concurrency.acquire("onprem".into(), 1).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.account, "onprem");
+        assert_eq!(concurrency.loads()["onprem"].limit, 1);
+    }
+}
@@ -0,0 +1,151 @@
+//! This module provides the location sent with CreateBucket for the provider behind the
+//! endpoint. AWS refuses `us-east-1` as a location constraint, Cloudflare R2 only accepts
+//! requests signed for its `auto` region and S3 compatible servers like MinIO ignore the
+//! constraint or refuse the regions they don't know
+use url::Url;
+
+/// Variables naming a custom endpoint, read by the sdk of the application as well,
+/// the one of s3 takes precedence
+const ENDPOINT_VARIABLES: [&str; 2] = ["AWS_ENDPOINT_URL_S3", "AWS_ENDPOINT_URL"];
+
+/// Region Cloudflare R2 signs its requests with
+const R2_REGION: &str = "auto";
+
+/// The custom endpoint of s3, `env` looks up the environment variables of the application
+pub fn custom_endpoint(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    ENDPOINT_VARIABLES
+        .iter()
+        .find_map(|name| env(name).filter(|url| !url.is_empty()))
+}
+
+/// Service the requests go to, told apart by the host of the custom endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S3Provider {
+    Aws,
+    CloudflareR2,
+    /// MinIO, Ceph and other S3 compatible servers
+    Compatible,
+}
+
+impl S3Provider {
+    /// AWS without a custom endpoint or with one of its own hosts
+    pub fn from_endpoint(endpoint_url: Option<&str>) -> Self {
+        let host = match endpoint_url {
+            Some(url) => Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase())),
+            None => return S3Provider::Aws,
+        };
+        match host.as_deref() {
+            Some(host) if host.ends_with(".r2.cloudflarestorage.com") => S3Provider::CloudflareR2,
+            Some(host) if host.ends_with(".amazonaws.com") => S3Provider::Aws,
+            _ => S3Provider::Compatible,
+        }
+    }
+}
+
+/// How the region of a new bucket is passed to CreateBucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BucketLocation {
+    /// The `CreateBucketConfiguration` names the region
+    Constraint(String),
+    /// No `CreateBucketConfiguration`, the endpoint picks the region
+    Omitted,
+    /// No `CreateBucketConfiguration`, the request is signed with the region the provider
+    /// requires instead of the one of the credential
+    SignedIn(&'static str),
+}
+
+impl BucketLocation {
+    pub fn new(provider: S3Provider, region: &str) -> Self {
+        match provider {
+            S3Provider::Aws if region.is_empty() || region == "us-east-1" => {
+                BucketLocation::Omitted
+            }
+            S3Provider::Aws => BucketLocation::Constraint(region.to_string()),
+            S3Provider::CloudflareR2 => BucketLocation::SignedIn(R2_REGION),
+            S3Provider::Compatible => BucketLocation::Omitted,
+        }
+    }
+
+    /// Where the bucket is created, shown in the title of the name input
+    pub fn describe(&self) -> String {
+        match self {
+            BucketLocation::Constraint(region) => format!("in {}", region),
+            BucketLocation::Omitted => "in the default region of the endpoint".to_string(),
+            BucketLocation::SignedIn(required) => {
+                format!("in region {}, required by the endpoint", required)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_is_told_by_the_endpoint_host() {
+        assert_eq!(S3Provider::from_endpoint(None), S3Provider::Aws);
+        assert_eq!(
+            S3Provider::from_endpoint(Some("https://s3.eu-west-1.amazonaws.com")),
+            S3Provider::Aws
+        );
+        assert_eq!(
+            S3Provider::from_endpoint(Some("https://0123abcd.r2.cloudflarestorage.com")),
+            S3Provider::CloudflareR2
+        );
+        assert_eq!(
+            S3Provider::from_endpoint(Some("https://0123abcd.EU.R2.cloudflarestorage.com")),
+            S3Provider::CloudflareR2
+        );
+        assert_eq!(
+            S3Provider::from_endpoint(Some("http://localhost:9000")),
+            S3Provider::Compatible
+        );
+        assert_eq!(
+            S3Provider::from_endpoint(Some("minio.local:9000")),
+            S3Provider::Compatible
+        );
+    }
+
+    #[test]
+    fn aws_omits_the_constraint_only_for_us_east_1() {
+        assert_eq!(
+            BucketLocation::new(S3Provider::Aws, "eu-central-1"),
+            BucketLocation::Constraint("eu-central-1".into())
+        );
+        assert_eq!(
+            BucketLocation::new(S3Provider::Aws, "us-east-1"),
+            BucketLocation::Omitted
+        );
+    }
+
+    #[test]
+    fn r2_is_signed_in_its_auto_region() {
+        let location = BucketLocation::new(S3Provider::CloudflareR2, "eu-central-1");
+        assert_eq!(location, BucketLocation::SignedIn("auto"));
+        assert_eq!(
+            location.describe(),
+            "in region auto, required by the endpoint"
+        );
+    }
+
+    #[test]
+    fn compatible_servers_pick_the_region() {
+        let location = BucketLocation::new(S3Provider::Compatible, "eu-central-1");
+        assert_eq!(location, BucketLocation::Omitted);
+        assert_eq!(location.describe(), "in the default region of the endpoint");
+    }
+
+    #[test]
+    fn endpoint_of_s3_takes_precedence() {
+        let endpoint = custom_endpoint(|name| match name {
+            "AWS_ENDPOINT_URL" => Some("https://general.example.com".into()),
+            "AWS_ENDPOINT_URL_S3" => Some("https://s3.example.com".into()),
+            _ => None,
+        });
+        assert_eq!(endpoint, Some("https://s3.example.com".into()));
+        assert_eq!(custom_endpoint(|_| Some(String::new())), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! This module provides the files of the data directory written with a checksum, a damaged
+//! file is read from the newest of its intact backups instead of starting fresh
+use crate::services::checksum::Sha256Hasher;
+use crate::services::partial_download::with_suffix;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// First line of a written file, followed by the sha-256 of the rest of the file
+const CHECKSUM_PREFIX: &str = "# sha256 ";
+
+/// Number of the previous intact versions kept next to the file, `<name>.1.bak` is the newest
+pub const BACKUPS: usize = 2;
+
+/// Content of the file or of one of its backups
+#[derive(Debug, PartialEq)]
+pub struct CheckedContent {
+    pub content: String,
+    /// Backup read because the file itself is damaged
+    pub recovered_from: Option<PathBuf>,
+}
+
+fn backup_path(path: &Path, number: usize) -> PathBuf {
+    with_suffix(path, &format!(".{}.bak", number))
+}
+
+fn checksum(content: &str) -> String {
+    let mut hasher = Sha256Hasher::default();
+    hasher.update(content.as_bytes());
+    hasher.finish()
+}
+
+/// Content without the checksum line, `InvalidData` when it doesn't match. Files without
+/// the line, written before the checksum was added, are taken as they are
+fn read_verified(path: &Path) -> io::Result<String> {
+    let text = fs::read_to_string(path)?;
+    let Some(rest) = text.strip_prefix(CHECKSUM_PREFIX) else {
+        return Ok(text);
+    };
+    let (expected, content) = rest.split_once('\n').unwrap_or((rest, ""));
+    if checksum(content) != expected {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} doesn't match its checksum", path.display()),
+        ));
+    }
+    Ok(content.to_string())
+}
+
+/// Replaces the file with the content and its checksum. The intact previous version becomes
+/// the newest backup, the file is written next to it first so a kill in the middle of the
+/// write keeps the previous one
+pub fn write_checked(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match read_verified(path) {
+        Ok(previous) if previous == content => return Ok(()),
+        Ok(_) => {
+            for number in (1..BACKUPS).rev() {
+                let older = backup_path(path, number);
+                if older.exists() {
+                    fs::rename(&older, backup_path(path, number + 1))?;
+                }
+            }
+            fs::copy(path, backup_path(path, 1))?;
+        }
+        // a damaged file doesn't replace the intact backups
+        Err(_) => {}
+    }
+    let temporary = with_suffix(path, ".tmp");
+    fs::write(
+        &temporary,
+        format!("{}{}\n{}", CHECKSUM_PREFIX, checksum(content), content),
+    )?;
+    fs::rename(&temporary, path)
+}
+
+/// Content of the file, `None` when there isn't any. A damaged file is read from the newest
+/// intact backup, the error is returned when none of them is
+pub fn read_checked(path: &Path) -> io::Result<Option<CheckedContent>> {
+    let damage = match read_verified(path) {
+        Ok(content) => {
+            return Ok(Some(CheckedContent {
+                content,
+                recovered_from: None,
+            }))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => e,
+        Err(e) => return Err(e),
+    };
+    for number in 1..=BACKUPS {
+        let backup = backup_path(path, number);
+        if let Ok(content) = read_verified(&backup) {
+            tracing::warn!("{}, reading {} instead", damage, backup.display());
+            return Ok(Some(CheckedContent {
+                content,
+                recovered_from: Some(backup),
+            }));
+        }
+    }
+    Err(damage)
+}
+
+/// Removes the file along with its backups, nothing is done for the missing ones
+pub fn remove_checked(path: &Path) -> io::Result<()> {
+    let backups = (1..=BACKUPS).map(|number| backup_path(path, number));
+    for file in std::iter::once(path.to_path_buf()).chain(backups) {
+        if let Err(e) = fs::remove_file(&file) {
+            if e.kind() != io::ErrorKind::NotFound {
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn corrupt_the_middle(path: &Path) {
+        let mut bytes = fs::read(path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0x01;
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn damaged_file_is_read_from_the_newest_intact_backup() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("queue.tsv");
+        assert_eq!(read_checked(&path).unwrap(), None);
+        for version in ["first\n", "second\n", "third\n", "third\n"] {
+            write_checked(&path, version).unwrap();
+        }
+        let read = |path: &Path| read_checked(path).unwrap().unwrap();
+        assert_eq!(read(&path).content, "third\n");
+        assert_eq!(read(&path).recovered_from, None);
+        assert!(!backup_path(&path, 3).exists());
+
+        corrupt_the_middle(&path);
+        assert_eq!(
+            read(&path),
+            CheckedContent {
+                content: "second\n".into(),
+                recovered_from: Some(backup_path(&path, 1)),
+            }
+        );
+        // the damaged file isn't kept as a backup
+        write_checked(&path, "fourth\n").unwrap();
+        assert_eq!(read(&backup_path(&path, 1)).content, "second\n");
+
+        corrupt_the_middle(&path);
+        corrupt_the_middle(&backup_path(&path, 1));
+        assert_eq!(read(&path).content, "first\n");
+        corrupt_the_middle(&backup_path(&path, 2));
+        assert_eq!(
+            read_checked(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        remove_checked(&path).unwrap();
+        assert!(fs::read_dir(directory.path()).unwrap().next().is_none());
+        remove_checked(&path).unwrap();
+    }
+
+    #[test]
+    fn files_without_a_checksum_are_read_as_they_are() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("queue.tsv");
+        fs::write(&path, "# s3tui pending transfers\n").unwrap();
+        assert_eq!(
+            read_checked(&path).unwrap().unwrap().content,
+            "# s3tui pending transfers\n"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! This module provides md5 and sha-256 hashing of local files and downloaded bytes
+use aws_smithy_types::base64;
+use md5::{Digest, Md5};
+use sha2::Sha256;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Number of bytes read from the start and the end of a file for the quick comparison
+pub const SAMPLE_SIZE: u64 = 64 * 1024;
+
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Incremental md5, fed with the chunks of a file or of a streamed object
+#[derive(Default)]
+pub struct Md5Hasher(Md5);
+
+impl Md5Hasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    /// Lowercase hex digest, the same format as a plain s3 ETag
+    pub fn finish(self) -> String {
+        to_hex(&self.0.finalize())
+    }
+}
+
+/// Incremental sha-256, fed with the chunks of a streamed object
+#[derive(Default)]
+pub struct Sha256Hasher(Sha256);
+
+impl Sha256Hasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    /// Lowercase hex digest
+    pub fn finish(self) -> String {
+        to_hex(&self.0.finalize())
+    }
+}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex form of a base64 checksum returned by s3, `None` for the checksums of the parts
+/// (`<base64>-<parts>`) or an invalid value
+pub fn base64_to_hex(checksum: &str) -> Option<String> {
+    if checksum.contains('-') {
+        return None;
+    }
+    base64::decode(checksum).ok().map(|digest| to_hex(&digest))
+}
+
+/// Feeds the bytes of the file between `first` and `last` (inclusive) into `update`
+fn read_range(
+    file: &mut File,
+    first: u64,
+    last: u64,
+    mut update: impl FnMut(&[u8]),
+) -> io::Result<()> {
+    file.seek(SeekFrom::Start(first))?;
+    let mut remaining = last - first + 1;
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    while remaining > 0 {
+        let wanted = remaining.min(BUFFER_SIZE as u64) as usize;
+        file.read_exact(&mut buffer[..wanted])?;
+        update(&buffer[..wanted]);
+        remaining -= wanted as u64;
+    }
+    Ok(())
+}
+
+/// Hashes the whole file without loading it into memory
+pub fn file_md5(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Md5Hasher::default();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finish())
+}
+
+/// Hashes the whole file with sha-256 without loading it into memory
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256Hasher::default();
+    let size = file.metadata()?.len();
+    if size > 0 {
+        read_range(&mut file, 0, size - 1, |bytes| hasher.update(bytes))?;
+    }
+    Ok(hasher.finish())
+}
+
+/// ETag s3 gives to the file uploaded in parts of the byte ranges (inclusive): the md5 of the
+/// concatenated md5 digests of the parts followed by the number of parts, `<md5>-<parts>`
+pub fn file_multipart_etag(path: &Path, parts: &[(u64, u64)]) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut digests = Md5::new();
+    for (first, last) in parts {
+        let mut part = Md5::new();
+        read_range(&mut file, *first, *last, |bytes| part.update(bytes))?;
+        digests.update(part.finalize());
+    }
+    Ok(format!("{}-{}", to_hex(&digests.finalize()), parts.len()))
+}
+
+/// Byte ranges (inclusive) of the head and the tail samples of an object of the given size,
+/// small objects have a single range covering everything
+pub fn sample_ranges(size: u64, sample: u64) -> Vec<(u64, u64)> {
+    if size == 0 {
+        Vec::new()
+    } else if size <= sample * 2 {
+        vec![(0, size - 1)]
+    } else {
+        vec![(0, sample - 1), (size - sample, size - 1)]
+    }
+}
+
+/// Reads the sample ranges of the local file
+pub fn read_file_samples(path: &Path, ranges: &[(u64, u64)]) -> io::Result<Vec<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    ranges
+        .iter()
+        .map(|(start, end)| {
+            file.seek(SeekFrom::Start(*start))?;
+            let mut buffer = vec![0u8; (end - start + 1) as usize];
+            file.read_exact(&mut buffer)?;
+            Ok(buffer)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn known_digests() {
+        let mut hasher = Md5Hasher::default();
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert_eq!(hasher.finish(), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+        assert_eq!(
+            Md5Hasher::default().finish(),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
+
+    #[test]
+    fn file_is_hashed_across_buffers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.bin");
+        let content: Vec<u8> = (0..BUFFER_SIZE * 2 + 10).map(|i| i as u8).collect();
+        fs_write(&path, &content);
+        let mut hasher = Md5Hasher::default();
+        hasher.update(&content);
+        assert_eq!(file_md5(&path).unwrap(), hasher.finish());
+    }
+
+    #[test]
+    fn sha256_of_a_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        fs_write(&path, b"hello world");
+        assert_eq!(
+            file_sha256(&path).unwrap(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[test]
+    fn base64_checksum_is_turned_into_hex() {
+        assert_eq!(
+            base64_to_hex("uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=").as_deref(),
+            Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        );
+        assert_eq!(
+            base64_to_hex("uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=-3"),
+            None
+        );
+    }
+
+    #[test]
+    fn multipart_etag_is_the_md5_of_the_part_md5s() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        fs_write(&path, b"hello world");
+        assert_eq!(
+            file_multipart_etag(&path, &[(0, 5), (6, 10)]).unwrap(),
+            "e09e4fd6265b36115fe3db32df945d84-2"
+        );
+        // a single part isn't the md5 of the file, the digest is hashed again
+        assert_eq!(
+            file_multipart_etag(&path, &[(0, 10)]).unwrap(),
+            "241d8a27c836427bd7f04461b60e7359-1"
+        );
+    }
+
+    #[test]
+    fn multipart_etag_reads_parts_across_buffers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.bin");
+        let content: Vec<u8> = (0..BUFFER_SIZE * 3).map(|i| (i % 251) as u8).collect();
+        fs_write(&path, &content);
+        let half = content.len() / 2;
+        let mut digests = Md5::new();
+        for part in [&content[..half], &content[half..]] {
+            digests.update(Md5::digest(part));
+        }
+        let (half, size) = (half as u64, content.len() as u64);
+        assert_eq!(
+            file_multipart_etag(&path, &[(0, half - 1), (half, size - 1)]).unwrap(),
+            format!("{}-2", to_hex(&digests.finalize()))
+        );
+    }
+
+    #[test]
+    fn samples_cover_head_and_tail() {
+        assert_eq!(sample_ranges(0, 4), vec![]);
+        assert_eq!(sample_ranges(6, 4), vec![(0, 5)]);
+        assert_eq!(sample_ranges(20, 4), vec![(0, 3), (16, 19)]);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        fs_write(&path, b"abcdefghijklmnopqrst");
+        assert_eq!(
+            read_file_samples(&path, &sample_ranges(20, 4)).unwrap(),
+            vec![b"abcd".to_vec(), b"qrst".to_vec()]
+        );
+    }
+
+    fn fs_write(path: &Path, content: &[u8]) {
+        let mut file = File::create(path).unwrap();
+        file.write_all(content).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+//! This module provides running the custom commands of the config file, in the background
+//! so a slow script doesn't hold the user interface
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Lines of the output kept for the activity log, the notification shows the first one
+pub const OUTPUT_LINES: usize = 5;
+
+/// How the command ended with the first lines of its output, the error output goes first
+/// when it failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub success: bool,
+    /// Exit code, `None` when the command was stopped by a signal
+    pub code: Option<i32>,
+    pub lines: Vec<String>,
+}
+
+#[cfg(not(windows))]
+fn shell(command_line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(command_line);
+    command
+}
+
+#[cfg(windows)]
+fn shell(command_line: &str) -> Command {
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", command_line]);
+    command
+}
+
+/// Runs the command line in the shell the values were quoted for, the command doesn't get
+/// the terminal: its input is empty and the output is captured
+pub async fn run_command(command_line: &str) -> Result<CommandOutput, String> {
+    let output = shell(command_line)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let success = output.status.success();
+    let (first, second) = match success {
+        true => (stdout, stderr),
+        false => (stderr, stdout),
+    };
+    let lines = first
+        .lines()
+        .chain(second.lines())
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .take(OUTPUT_LINES)
+        .map(String::from)
+        .collect();
+    Ok(CommandOutput {
+        success,
+        code: output.status.code(),
+        lines,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::settings::custom_command::shell_quote;
+
+    #[tokio::test]
+    async fn output_and_status_are_captured() {
+        let hostile = "it's; $(touch /tmp/pwned) `id` \"x\"";
+        let output = run_command(&format!("/bin/echo {}", shell_quote(hostile)))
+            .await
+            .unwrap();
+        assert_eq!(
+            output,
+            CommandOutput {
+                success: true,
+                code: Some(0),
+                lines: vec![hostile.to_string()],
+            }
+        );
+
+        let output = run_command("seq 1 20; echo failed >&2; exit 3")
+            .await
+            .unwrap();
+        assert!(!output.success);
+        assert_eq!(output.code, Some(3));
+        assert_eq!(output.lines, vec!["failed", "1", "2", "3", "4"]);
+    }
+}
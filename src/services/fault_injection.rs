@@ -0,0 +1,289 @@
+//! This module provides failures injected into the s3 calls of the fetcher, so the error
+//! handling can be exercised against an endpoint which rarely fails. It's compiled only with
+//! the `fault-injection` feature. The rules are read from the `S3TUI_FAULTS` variable and shared
+//! by all the fetchers, e.g. `list#1=fail:timeout;download#2=truncate:1048576`
+use crate::services::s3_errors::{ErrorClass, S3Error};
+use bytes::Bytes;
+use color_eyre::{eyre, Report};
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+pub const FAULTS_ENV: &str = "S3TUI_FAULTS";
+
+/// Calls of the fetcher counted separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    List,
+    Upload,
+    Download,
+    Delete,
+}
+
+impl Operation {
+    fn parse(name: &str) -> Result<Operation, String> {
+        match name {
+            "list" => Ok(Operation::List),
+            "upload" => Ok(Operation::Upload),
+            "download" => Ok(Operation::Download),
+            "delete" => Ok(Operation::Delete),
+            _ => Err(format!("unknown operation {}", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// The call fails before anything is sent
+    Fail(S3Error),
+    /// The call waits before it's sent
+    Delay(Duration),
+    /// The transferred body breaks with a connection error after this many bytes
+    Truncate(u64),
+}
+
+impl Fault {
+    /// `fail:<class>[:<message>]`, `delay:<milliseconds>` or `truncate:<bytes>`
+    fn parse(spec: &str) -> Result<Fault, String> {
+        let mut parts = spec.splitn(3, ':');
+        let kind = parts.next().unwrap_or_default();
+        let value = parts.next().ok_or(format!("{} needs a value", kind))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{} is not a number", value))
+        };
+        match kind {
+            "fail" => {
+                let class = match value {
+                    "timeout" => ErrorClass::Timeout,
+                    "connection" => ErrorClass::Connection,
+                    "proxy" => ErrorClass::Proxy,
+                    "tls" => ErrorClass::Tls,
+                    "service" => ErrorClass::Service,
+                    "throttled" => ErrorClass::Throttled,
+                    "missing_bucket" => ErrorClass::MissingBucket,
+                    "other" => ErrorClass::Other,
+                    _ => return Err(format!("unknown error class {}", value)),
+                };
+                let message = parts.next().unwrap_or("injected failure").to_string();
+                Ok(Fault::Fail(S3Error { class, message }))
+            }
+            "delay" => Ok(Fault::Delay(Duration::from_millis(number()?))),
+            "truncate" => Ok(Fault::Truncate(number()?)),
+            _ => Err(format!("unknown fault {}", kind)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    operation: Operation,
+    /// Number of the call counted from 1
+    call: u64,
+    fault: Fault,
+}
+
+/// Faults injected into chosen calls, the clones of a config count the calls together
+#[derive(Debug, Clone, Default)]
+pub struct FaultConfig {
+    rules: Vec<Rule>,
+    calls: Arc<Mutex<HashMap<Operation, u64>>>,
+}
+
+impl FaultConfig {
+    /// Injects `fault` into the `call`-th call of the operation, counted from 1
+    pub fn inject(mut self, operation: Operation, call: u64, fault: Fault) -> Self {
+        self.rules.push(Rule {
+            operation,
+            call,
+            fault,
+        });
+        self
+    }
+
+    /// Rules like `delete#1=fail:service:AccessDenied` separated with `;`
+    pub fn parse(spec: &str) -> Result<FaultConfig, String> {
+        let mut config = FaultConfig::default();
+        for rule in spec.split(';').map(str::trim).filter(|r| !r.is_empty()) {
+            let (target, fault) = rule
+                .split_once('=')
+                .ok_or(format!("{} is not <operation>#<call>=<fault>", rule))?;
+            let (operation, call) = target.split_once('#').unwrap_or((target, "1"));
+            let call = call
+                .parse::<u64>()
+                .map_err(|_| format!("{} is not a call number", call))?;
+            config = config.inject(Operation::parse(operation)?, call, Fault::parse(fault)?);
+        }
+        Ok(config)
+    }
+
+    /// Config shared by all the fetchers of the process, read once from `S3TUI_FAULTS`
+    pub fn from_env() -> Option<FaultConfig> {
+        static FAULTS: OnceLock<Option<FaultConfig>> = OnceLock::new();
+        FAULTS
+            .get_or_init(|| {
+                let spec = std::env::var(FAULTS_ENV).ok()?;
+                match FaultConfig::parse(&spec) {
+                    Ok(config) => Some(config),
+                    Err(e) => {
+                        tracing::warn!("Ignoring {}: {}", FAULTS_ENV, e);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+
+    /// Counts the call and applies its faults, a failure is returned as the error of the call.
+    /// Returns the number of bytes after which the transfer has to break
+    pub async fn before_call(&self, operation: Operation) -> eyre::Result<Option<u64>> {
+        let call = {
+            let mut calls = self.calls.lock().unwrap();
+            let count = calls.entry(operation).or_insert(0);
+            *count += 1;
+            *count
+        };
+        let mut truncate_after = None;
+        for rule in self
+            .rules
+            .iter()
+            .filter(|r| r.operation == operation && r.call == call)
+        {
+            tracing::warn!(
+                "Injecting {:?} into {:?} call {}",
+                rule.fault,
+                operation,
+                call
+            );
+            match &rule.fault {
+                Fault::Fail(error) => return Err(Report::new(error.clone())),
+                Fault::Delay(delay) => tokio::time::sleep(*delay).await,
+                Fault::Truncate(bytes) => truncate_after = Some(*bytes),
+            }
+        }
+        Ok(truncate_after)
+    }
+}
+
+/// Error of a body broken by `Fault::Truncate`
+pub fn interrupted(bytes: u64) -> Report {
+    Report::new(S3Error {
+        class: ErrorClass::Connection,
+        message: format!("injected interruption after {} bytes", bytes),
+    })
+}
+
+/// Passes the chunks of the body until `limit` bytes, then ends it with a connection error
+pub fn truncated<'a>(
+    chunks: Pin<Box<dyn Stream<Item = eyre::Result<Bytes>> + Send + 'a>>,
+    limit: u64,
+) -> Pin<Box<dyn Stream<Item = eyre::Result<Bytes>> + Send + 'a>> {
+    // `passed` above the limit marks the sent error
+    Box::pin(stream::unfold(
+        (chunks, 0u64),
+        move |(mut chunks, passed)| async move {
+            if passed > limit {
+                return None;
+            }
+            if passed == limit {
+                return Some((Err(interrupted(limit)), (chunks, limit + 1)));
+            }
+            match chunks.next().await? {
+                Ok(mut bytes) => {
+                    let len = (bytes.len() as u64).min(limit - passed);
+                    bytes.truncate(len as usize);
+                    Some((Ok(bytes), (chunks, passed + len)))
+                }
+                Err(e) => Some((Err(e), (chunks, passed))),
+            }
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+
+    #[tokio::test]
+    async fn fault_is_injected_into_the_chosen_call_only() {
+        let faults = FaultConfig::default()
+            .inject(
+                Operation::List,
+                2,
+                Fault::Fail(S3Error {
+                    class: ErrorClass::Timeout,
+                    message: "timed out".into(),
+                }),
+            )
+            .inject(Operation::Download, 1, Fault::Truncate(10));
+        let shared = faults.clone();
+        assert!(faults.before_call(Operation::List).await.unwrap().is_none());
+        let error = shared.before_call(Operation::List).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<S3Error>().map(|e| e.class),
+            Some(ErrorClass::Timeout)
+        );
+        assert!(faults.before_call(Operation::List).await.is_ok());
+        assert_eq!(
+            faults.before_call(Operation::Download).await.unwrap(),
+            Some(10)
+        );
+        assert_eq!(faults.before_call(Operation::Download).await.unwrap(), None);
+    }
+
+    #[test]
+    fn rules_are_parsed_from_the_variable() {
+        let faults = FaultConfig::parse(
+            "list#1=fail:timeout; delete#3=fail:service:AccessDenied: Access Denied;upload=delay:250",
+        )
+        .unwrap();
+        assert_eq!(
+            faults.rules,
+            vec![
+                Rule {
+                    operation: Operation::List,
+                    call: 1,
+                    fault: Fault::Fail(S3Error {
+                        class: ErrorClass::Timeout,
+                        message: "injected failure".into(),
+                    }),
+                },
+                Rule {
+                    operation: Operation::Delete,
+                    call: 3,
+                    fault: Fault::Fail(S3Error {
+                        class: ErrorClass::Service,
+                        message: "AccessDenied: Access Denied".into(),
+                    }),
+                },
+                Rule {
+                    operation: Operation::Upload,
+                    call: 1,
+                    fault: Fault::Delay(Duration::from_millis(250)),
+                },
+            ]
+        );
+        assert!(FaultConfig::parse("rename#1=fail:timeout").is_err());
+        assert!(FaultConfig::parse("list#x=fail:timeout").is_err());
+        assert!(FaultConfig::parse("list#1=truncate:half").is_err());
+        assert!(FaultConfig::parse("list#1").is_err());
+    }
+
+    #[tokio::test]
+    async fn truncated_body_breaks_after_the_limit() {
+        let chunks: Vec<eyre::Result<Bytes>> = vec![
+            Ok(Bytes::from_static(b"0123")),
+            Ok(Bytes::from_static(b"4567")),
+            Ok(Bytes::from_static(b"89")),
+        ];
+        let mut body = truncated(Box::pin(stream::iter(chunks)), 6);
+        assert_eq!(body.try_next().await.unwrap().unwrap(), "0123");
+        assert_eq!(body.try_next().await.unwrap().unwrap(), "45");
+        let error = body.try_next().await.unwrap_err();
+        assert_eq!(error.to_string(), "injected interruption after 6 bytes");
+    }
+}
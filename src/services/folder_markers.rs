@@ -0,0 +1,105 @@
+//! This module provides the folder markers created above the uploaded objects when
+//! `create_folder_markers` is set, the zero-byte objects with a key ending with '/' some
+//! consoles need to show the folders
+use color_eyre::eyre;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Keys of the markers of every folder above the key, e.g. `a/`, `a/b/` and `a/b/c/`
+/// for `a/b/c/file`
+pub fn marker_keys(key: &str) -> Vec<String> {
+    key.match_indices('/')
+        .filter(|(index, _)| *index > 0)
+        .map(|(index, _)| key[..=index].to_string())
+        .collect()
+}
+
+/// Markers found or created during the session, shared by the uploads so every folder is
+/// only checked once
+#[derive(Debug, Clone, Default)]
+pub struct FolderMarkers {
+    checked: Arc<Mutex<HashSet<(String, String)>>>,
+}
+
+impl FolderMarkers {
+    /// Hands the markers above the key which weren't checked yet to `create` from the top
+    /// folder down, `create` is expected to leave an existing marker as it is. A marker which
+    /// couldn't be created stops the others and is checked again by the next upload
+    pub async fn ensure<F, Fut>(&self, bucket: &str, key: &str, mut create: F) -> eyre::Result<()>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = eyre::Result<()>>,
+    {
+        for marker in marker_keys(key) {
+            let entry = (bucket.to_string(), marker);
+            if self.checked.lock().unwrap().contains(&entry) {
+                continue;
+            }
+            create(entry.1.clone()).await?;
+            self.checked.lock().unwrap().insert(entry);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_level_above_the_key_gets_a_marker() {
+        assert_eq!(marker_keys("a/b/c/file"), vec!["a/", "a/b/", "a/b/c/"]);
+        assert_eq!(marker_keys("reports/"), vec!["reports/"]);
+        assert!(marker_keys("file").is_empty());
+        assert!(marker_keys("/file").is_empty());
+    }
+
+    #[tokio::test]
+    async fn markers_are_checked_once_per_bucket() {
+        let markers = FolderMarkers::default();
+        let created = Mutex::new(Vec::new());
+        let create = |marker: String| {
+            created.lock().unwrap().push(marker);
+            async { Ok(()) }
+        };
+        markers
+            .ensure("logs", "2024/05/a.log", create)
+            .await
+            .unwrap();
+        markers
+            .ensure("logs", "2024/05/b.log", create)
+            .await
+            .unwrap();
+        markers
+            .ensure("logs", "2024/06/a.log", create)
+            .await
+            .unwrap();
+        markers
+            .ensure("archive", "2024/a.log", create)
+            .await
+            .unwrap();
+        assert_eq!(
+            *created.lock().unwrap(),
+            vec!["2024/", "2024/05/", "2024/06/", "2024/"]
+        );
+
+        // a failed marker stops the deeper ones and is tried again
+        let failing = |marker: String| async move {
+            match marker.as_str() {
+                "new/" => Err(eyre::eyre!("AccessDenied")),
+                _ => Ok(()),
+            }
+        };
+        assert!(markers
+            .ensure("logs", "new/deeper/a.log", failing)
+            .await
+            .is_err());
+        created.lock().unwrap().clear();
+        markers
+            .ensure("logs", "new/deeper/a.log", create)
+            .await
+            .unwrap();
+        assert_eq!(*created.lock().unwrap(), vec!["new/", "new/deeper/"]);
+    }
+}
@@ -1,6 +1,11 @@
 use crate::model::local_data_item::LocalDataItem;
+use crate::model::rename::renamed_path;
+use crate::utils::{format_bytes, short_time};
+use aws_smithy_types::date_time::Format as DateTimeFormat;
+use aws_smithy_types::DateTime;
 use color_eyre::{Report, Result};
-use humansize::{file_size_opts as options, FileSize};
+use std::cmp::Ordering;
+use std::fs::Metadata;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::fs;
@@ -42,11 +47,12 @@ impl LocalDataFetcher {
         absolute_path_str: Option<String>,
     ) -> Result<Vec<LocalDataItem>> {
         let mut files_info = Vec::new();
-        let home_dir = dirs::home_dir().unwrap();
         if let Some(path) = absolute_path_str {
             let mut current_dir = self.current_dir.lock().await;
             *current_dir = path;
         } else {
+            let home_dir = dirs::home_dir()
+                .ok_or_else(|| Report::msg("The home directory can't be determined"))?;
             let mut current_dir = self.current_dir.lock().await;
             let path_cow = home_dir.to_string_lossy();
             let path_str = path_cow.as_ref();
@@ -67,20 +73,19 @@ impl LocalDataFetcher {
             let path_cow = path.to_string_lossy();
             let path_str = path_cow.as_ref();
             let is_directory = metadata.is_dir();
-            let size = metadata
-                .len()
-                .file_size(options::CONVENTIONAL)
-                .unwrap_or_else(|_| "0 B".to_string());
+            let size = if is_directory {
+                "-".to_string()
+            } else {
+                format_bytes(metadata.len())
+            };
             let file_type = if is_directory { "Dir" } else { extension };
 
-            files_info.push(LocalDataItem::init(
-                file_name,
-                size,
-                file_type,
-                path_str,
-                is_directory,
-            ));
+            let mut item = LocalDataItem::init(file_name, size, file_type, path_str, is_directory)
+                .with_modified(modified_time(&metadata));
+            item.is_hidden |= has_hidden_attribute(&metadata);
+            files_info.push(item);
         }
+        files_info.sort_by(directories_first);
 
         Ok(files_info)
     }
@@ -99,6 +104,64 @@ impl LocalDataFetcher {
             Err(e) => Err(Report::msg(format!("Failed to delete directory {:?}", e))),
         }
     }
+
+    /// Renames the file or directory within its directory. Returns `false` without renaming
+    /// when a file is already at the new path and it's not overwritten, existing directories
+    /// are never replaced
+    pub async fn rename(
+        &self,
+        absolute_path_str: String,
+        new_name: String,
+        overwrite: bool,
+    ) -> Result<bool> {
+        let path = Path::new(&absolute_path_str);
+        let target = renamed_path(path, &new_name).map_err(Report::msg)?;
+        if target == path {
+            return Ok(true);
+        }
+        match fs::metadata(&target).await {
+            Ok(metadata) if metadata.is_dir() => {
+                return Err(Report::msg(format!(
+                    "Directory {} already exists",
+                    new_name
+                )));
+            }
+            Ok(_) if !overwrite => return Ok(false),
+            _ => {}
+        }
+        match fs::rename(path, &target).await {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Report::msg(format!("Failed to rename {:?}", e))),
+        }
+    }
+}
+
+/// Directories before the files, both by their name ignoring the case
+fn directories_first(a: &LocalDataItem, b: &LocalDataItem) -> Ordering {
+    b.is_directory
+        .cmp(&a.is_directory)
+        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+}
+
+/// Time of the last modification in UTC, unknown when the platform doesn't record it
+fn modified_time(metadata: &Metadata) -> Option<String> {
+    let modified = DateTime::from(metadata.modified().ok()?);
+    modified
+        .fmt(DateTimeFormat::DateTime)
+        .ok()
+        .map(|time| short_time(&time))
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_metadata: &Metadata) -> bool {
+    false
 }
 
 #[cfg(test)]
@@ -179,6 +242,28 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_read_directory_lists_directories_first() -> color_eyre::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("b.txt"), vec![0u8; 2048]).await?;
+        fs::write(dir.path().join(".env"), b"KEY=1").await?;
+        fs::create_dir(dir.path().join("Zeta")).await?;
+        fs::create_dir(dir.path().join("alpha")).await?;
+
+        let fetcher = LocalDataFetcher::new();
+        let files = fetcher
+            .read_directory(Some(dir.path().to_string_lossy().into()))
+            .await?;
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "Zeta", ".env", "b.txt"]);
+        assert_eq!(files[0].size, "-");
+        assert_eq!(files[3].size, "2.0 KB");
+        assert!(files[2].is_hidden);
+        assert!(!files[3].is_hidden);
+        assert!(files.iter().all(|f| f.modified.is_some()));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_read_home_directory() -> color_eyre::Result<()> {
         let fetcher = LocalDataFetcher::new();
@@ -187,4 +272,69 @@ mod tests {
         assert!(!files.is_empty(), "Should contain multiple files");
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_rename() -> color_eyre::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("notes.txt");
+        fs::write(&file_path, b"notes").await?;
+
+        let fetcher = LocalDataFetcher::new();
+        let renamed = fetcher
+            .rename(file_path.to_string_lossy().into(), "todo.txt".into(), false)
+            .await?;
+        assert!(renamed);
+        assert!(!file_path.exists());
+        assert_eq!(fs::read(dir.path().join("todo.txt")).await?, b"notes");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_onto_existing_entry() -> color_eyre::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("notes.txt");
+        fs::write(&file_path, b"notes").await?;
+        fs::write(dir.path().join("todo.txt"), b"todo").await?;
+        fs::create_dir(dir.path().join("docs")).await?;
+        let path: String = file_path.to_string_lossy().into();
+
+        let fetcher = LocalDataFetcher::new();
+        let renamed = fetcher
+            .rename(path.clone(), "todo.txt".into(), false)
+            .await?;
+        assert!(
+            !renamed,
+            "Existing file should only be replaced when confirmed"
+        );
+        assert_eq!(fs::read(dir.path().join("todo.txt")).await?, b"todo");
+        assert!(
+            fetcher
+                .rename(path.clone(), "docs".into(), true)
+                .await
+                .is_err(),
+            "Existing directory should never be replaced"
+        );
+
+        let renamed = fetcher.rename(path, "todo.txt".into(), true).await?;
+        assert!(renamed);
+        assert_eq!(fs::read(dir.path().join("todo.txt")).await?, b"notes");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_with_invalid_name() -> color_eyre::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("notes.txt");
+        fs::write(&file_path, b"notes").await?;
+
+        let fetcher = LocalDataFetcher::new();
+        for name in ["", "..", "docs/todo.txt"] {
+            let result = fetcher
+                .rename(file_path.to_string_lossy().into(), name.into(), true)
+                .await;
+            assert!(result.is_err(), "{}", name);
+        }
+        assert!(file_path.exists());
+        Ok(())
+    }
 }
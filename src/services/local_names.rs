@@ -0,0 +1,343 @@
+//! This module provides the adjustment of s3 keys to local file names valid on Windows, where
+//! characters like `:` or `?`, trailing dots and spaces and device names like `CON` are reserved
+use crate::services::transfer_queue::TransferJob;
+use std::path::{Component, Path, PathBuf};
+
+const RESERVED_CHARACTERS: &[char] = &['<', '>', ':', '"', '\\', '|', '?', '*'];
+const DEVICE_NAMES: &[&str] = &["CON", "PRN", "AUX", "NUL"];
+/// Longest file name most file systems accept, in bytes
+const MAX_NAME_BYTES: usize = 255;
+/// Longest path accepted, `MAX_PATH` on Windows without long path support, `PATH_MAX` elsewhere
+const MAX_PATH_BYTES: usize = if cfg!(target_os = "windows") {
+    260
+} else {
+    4096
+};
+
+/// How the reserved characters of a file name are replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameSubstitution {
+    /// `report:v2.csv` becomes `report%3Av2.csv`
+    PercentEncode,
+    /// `report:v2.csv` becomes `report_v2.csv`
+    Underscore,
+}
+
+impl NameSubstitution {
+    /// Parses the `file_name_substitution` setting, `none` keeps the names as they are
+    pub fn from_setting(value: &str) -> Result<Option<NameSubstitution>, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Ok(None),
+            "percent" => Ok(Some(NameSubstitution::PercentEncode)),
+            "underscore" => Ok(Some(NameSubstitution::Underscore)),
+            other => Err(format!(
+                "Unknown file_name_substitution setting '{}', use percent, underscore or none",
+                other
+            )),
+        }
+    }
+
+    fn push_replacement(self, c: char, result: &mut String) {
+        match self {
+            NameSubstitution::PercentEncode => {
+                let mut buffer = [0; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    result.push_str(&format!("%{:02X}", byte));
+                }
+            }
+            NameSubstitution::Underscore => result.push('_'),
+        }
+    }
+}
+
+/// `CON`, `nul.txt` or `COM1.log`, the extension doesn't make the name usable
+fn is_device_name(name: &str) -> bool {
+    let stem = name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(' ')
+        .to_ascii_uppercase();
+    DEVICE_NAMES.contains(&stem.as_str())
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.as_bytes()[3].is_ascii_digit())
+}
+
+/// File name valid on Windows, `None` when the name already is. Device names get `_`
+/// appended to their stem with both substitutions, e.g. `CON_.txt`
+pub fn compatible_name(name: &str, substitution: NameSubstitution) -> Option<String> {
+    if name == "." || name == ".." {
+        return None;
+    }
+    let kept = name.trim_end_matches(['.', ' ']).len();
+    let mut result = String::with_capacity(name.len());
+    for (i, c) in name.char_indices() {
+        if i >= kept || RESERVED_CHARACTERS.contains(&c) || (c as u32) < 0x20 {
+            substitution.push_replacement(c, &mut result);
+        } else {
+            result.push(c);
+        }
+    }
+    if is_device_name(&result) {
+        let stem_end = result.find('.').unwrap_or(result.len());
+        result.insert(stem_end, '_');
+    }
+    (result != name).then_some(result)
+}
+
+/// Path relative to the destination directory with every component valid on Windows,
+/// `None` when nothing had to change
+pub fn compatible_path(relative: &str, substitution: NameSubstitution) -> Option<String> {
+    let mut changed = false;
+    let components: Vec<String> = relative
+        .split('/')
+        .map(|component| match compatible_name(component, substitution) {
+            Some(name) => {
+                changed = true;
+                name
+            }
+            None => component.to_string(),
+        })
+        .collect();
+    changed.then(|| components.join("/"))
+}
+
+/// Path of the key relative to the destination directory which never leaves it. Leading
+/// slashes, `dir//file` and `.` add no components, `..` becomes a file name (`%2E%2E`) and
+/// names the local system would read as a root or a drive, like `C:` on Windows, are encoded
+pub fn contained_path(relative: &str) -> PathBuf {
+    let mut path = PathBuf::new();
+    for component in relative.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => path.push("%2E%2E"),
+            name => {
+                let mut components = Path::new(name).components();
+                let plain = matches!(
+                    (components.next(), components.next()),
+                    (Some(Component::Normal(_)), None)
+                );
+                match plain {
+                    true => path.push(name),
+                    false => path.push(
+                        compatible_name(name, NameSubstitution::PercentEncode)
+                            .unwrap_or(name.to_string()),
+                    ),
+                }
+            }
+        }
+    }
+    path
+}
+
+/// Whether the key downloaded into `destination_dir` makes a path the local file system
+/// refuses, with a name longer than 255 bytes or a path longer than the platform allows
+pub fn exceeds_path_limits(destination_dir: &str, key: &str) -> bool {
+    key.split('/').any(|name| name.len() > MAX_NAME_BYTES)
+        || destination_dir.len() + 1 + key.len() > MAX_PATH_BYTES
+}
+
+/// Renames the downloads whose local file names aren't valid, the new name is kept in their
+/// `destination_path`. Returns the number of renamed downloads
+pub fn adjust_download_names(
+    jobs: &mut [TransferJob],
+    substitution: Option<NameSubstitution>,
+) -> usize {
+    let substitution = match substitution {
+        Some(substitution) => substitution,
+        None => return 0,
+    };
+    let mut adjusted = 0;
+    for job in jobs.iter_mut() {
+        if let TransferJob::Download(item) = job {
+            let relative = item
+                .destination_path
+                .clone()
+                .or(item.path.clone())
+                .unwrap_or(item.name.clone());
+            if let Some(compatible) = compatible_path(&relative, substitution) {
+                tracing::info!("Downloading {} as {}", relative, compatible);
+                item.destination_path = Some(compatible);
+                adjusted += 1;
+            }
+        }
+    }
+    adjusted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::s3_selected_item::S3SelectedItem;
+    use std::path::PathBuf;
+
+    const PERCENT: NameSubstitution = NameSubstitution::PercentEncode;
+    const UNDERSCORE: NameSubstitution = NameSubstitution::Underscore;
+
+    #[test]
+    fn keys_longer_than_the_local_limits_are_flagged() {
+        let long_name = format!("logs/{}", "a".repeat(MAX_NAME_BYTES + 1));
+        assert!(exceeds_path_limits("/tmp", &long_name));
+        assert!(!exceeds_path_limits("/tmp", &"a".repeat(MAX_NAME_BYTES)));
+        let deep_key = ["dir"; MAX_PATH_BYTES / 4].join("/");
+        assert!(exceeds_path_limits("/tmp", &deep_key));
+        assert!(!exceeds_path_limits("/tmp", "logs/2024/app.log"));
+    }
+
+    #[test]
+    fn every_reserved_character_is_replaced() {
+        let encoded = [
+            ('<', "%3C"),
+            ('>', "%3E"),
+            (':', "%3A"),
+            ('"', "%22"),
+            ('\\', "%5C"),
+            ('|', "%7C"),
+            ('?', "%3F"),
+            ('*', "%2A"),
+            ('\t', "%09"),
+        ];
+        for (c, percent) in encoded {
+            let name = format!("a{}b.txt", c);
+            assert_eq!(
+                compatible_name(&name, PERCENT),
+                Some(format!("a{}b.txt", percent)),
+                "{:?}",
+                c
+            );
+            assert_eq!(
+                compatible_name(&name, UNDERSCORE),
+                Some("a_b.txt".into()),
+                "{:?}",
+                c
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_dots_and_spaces_are_replaced() {
+        assert_eq!(
+            compatible_name("notes. .", PERCENT),
+            Some("notes%2E%20%2E".into())
+        );
+        assert_eq!(
+            compatible_name("notes. .", UNDERSCORE),
+            Some("notes___".into())
+        );
+        assert_eq!(compatible_name("v1.2 final.txt", PERCENT), None);
+    }
+
+    #[test]
+    fn device_names_get_a_suffix() {
+        for (name, compatible) in [
+            ("CON", "CON_"),
+            ("nul.txt", "nul_.txt"),
+            ("COM1", "COM1_"),
+            ("lpt9.tar.gz", "lpt9_.tar.gz"),
+            ("Aux", "Aux_"),
+        ] {
+            assert_eq!(
+                compatible_name(name, UNDERSCORE),
+                Some(compatible.into()),
+                "{}",
+                name
+            );
+            assert_eq!(compatible_name(name, PERCENT), Some(compatible.into()));
+        }
+        for name in ["CONSOLE", "nuls.txt", "COM", "COM10", "icon.png"] {
+            assert_eq!(compatible_name(name, PERCENT), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn valid_names_and_relative_components_are_kept() {
+        for name in ["report.csv", "zółw.txt", ".hidden", ".", "..", ""] {
+            assert_eq!(compatible_name(name, PERCENT), None, "{}", name);
+        }
+        assert_eq!(compatible_path("logs/2024/app.log", PERCENT), None);
+    }
+
+    #[test]
+    fn every_component_of_the_path_is_adjusted() {
+        assert_eq!(
+            compatible_path("runs/12:00/CON/out?.json", UNDERSCORE),
+            Some("runs/12_00/CON_/out_.json".into())
+        );
+    }
+
+    #[test]
+    fn keys_stay_under_the_destination_directory() {
+        for (key, relative) in [
+            ("/etc/passwd", "etc/passwd"),
+            ("a/../../b", "a/%2E%2E/%2E%2E/b"),
+            ("dir//file", "dir/file"),
+            ("./logs/./app.log", "logs/app.log"),
+            ("con.txt", "con.txt"),
+        ] {
+            let path = contained_path(key);
+            assert_eq!(path, PathBuf::from(relative), "{}", key);
+            assert!(
+                path.components().all(|c| matches!(c, Component::Normal(_))),
+                "{}",
+                key
+            );
+            assert!(PathBuf::from("/tmp/downloads")
+                .join(&path)
+                .starts_with("/tmp/downloads"));
+        }
+        // on Windows the device name is also renamed by the default substitution
+        assert_eq!(compatible_path("con.txt", PERCENT), Some("con_.txt".into()));
+    }
+
+    #[test]
+    fn downloads_are_renamed_only_with_a_substitution() {
+        let item = S3SelectedItem {
+            bucket: Some("bucket".into()),
+            name: "12:00.log".into(),
+            path: Some("logs/12:00.log".into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/tmp".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let mut jobs = vec![
+            TransferJob::Download(item.clone()),
+            TransferJob::Download(S3SelectedItem {
+                name: "ok.log".into(),
+                path: Some("logs/ok.log".into()),
+                ..item
+            }),
+        ];
+        assert_eq!(adjust_download_names(&mut jobs, None), 0);
+
+        assert_eq!(adjust_download_names(&mut jobs, Some(PERCENT)), 1);
+        match &jobs[0] {
+            TransferJob::Download(item) => assert_eq!(
+                item.local_destination(),
+                PathBuf::from("/tmp/logs/12%3A00.log")
+            ),
+            TransferJob::Upload(_) => unreachable!(),
+        }
+        // adjusted names stay as they are
+        assert_eq!(adjust_download_names(&mut jobs, Some(PERCENT)), 0);
+    }
+
+    #[test]
+    fn substitution_is_read_from_the_setting() {
+        assert_eq!(NameSubstitution::from_setting("Percent"), Ok(Some(PERCENT)));
+        assert_eq!(
+            NameSubstitution::from_setting("underscore"),
+            Ok(Some(UNDERSCORE))
+        );
+        assert_eq!(NameSubstitution::from_setting("none"), Ok(None));
+        assert!(NameSubstitution::from_setting("dashes").is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! This module provides a single implementation of walking local directories,
+//! used whenever files below a selected directory need to be listed or counted
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A single `.gitignore` style pattern
+#[derive(Debug, Clone, PartialEq)]
+struct IgnorePattern {
+    glob: String,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl IgnorePattern {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let dir_only = line.ends_with('/');
+        let trimmed = line.trim_end_matches('/');
+        let anchored = trimmed.contains('/');
+        let glob = trimmed.trim_start_matches('/').to_string();
+        if glob.is_empty() {
+            return None;
+        }
+        Some(IgnorePattern {
+            glob,
+            dir_only,
+            anchored,
+        })
+    }
+
+    /// Patterns containing a slash are matched against the path relative to the walked root,
+    /// all the other ones against the entry name only
+    fn matches(&self, relative: &str, name: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            glob_matches(self.glob.as_bytes(), relative.as_bytes())
+        } else {
+            glob_matches(self.glob.as_bytes(), name.as_bytes())
+        }
+    }
+}
+
+/// Matches `*` (any characters except `/`), `**` (any characters) and `?` (single character)
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+            (0..=text.len()).any(|i| glob_matches(rest, &text[i..]))
+        }
+        Some(b'*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_matches(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == b'/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some(b'?') => {
+            !text.is_empty() && text[0] != b'/' && glob_matches(&pattern[1..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_matches(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Set of exclusion rules applied while walking directories
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgnoreRules {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    pub fn new(patterns: &[String]) -> Self {
+        IgnoreRules {
+            patterns: patterns
+                .iter()
+                .filter_map(|p| IgnorePattern::parse(p))
+                .collect(),
+        }
+    }
+
+    /// Checks if the entry should be skipped, `relative` uses `/` as the separator
+    pub fn is_excluded(&self, relative: &str, is_dir: bool) -> bool {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        self.patterns
+            .iter()
+            .any(|p| p.matches(relative, name, is_dir))
+    }
+}
+
+/// Flag shared with the caller to stop a long running walk
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    pub rules: IgnoreRules,
+    /// Maximum depth of the returned entries, direct children of the root are on depth 1
+    pub max_depth: Option<usize>,
+    pub cancellation: CancellationToken,
+    /// Whether the walked entries should be returned next to the summary
+    pub collect_entries: bool,
+}
+
+impl WalkOptions {
+    pub fn with_rules(rules: IgnoreRules) -> Self {
+        WalkOptions {
+            rules,
+            collect_entries: true,
+            ..WalkOptions::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WalkSummary {
+    pub files: usize,
+    pub dirs: usize,
+    pub bytes: u64,
+    pub excluded_files: usize,
+    pub excluded_dirs: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkEntry {
+    pub path: PathBuf,
+    /// Path relative to the walked root, always separated with `/`
+    pub relative_path: String,
+    pub is_directory: bool,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WalkResult {
+    pub summary: WalkSummary,
+    pub entries: Vec<WalkEntry>,
+    pub cancelled: bool,
+}
+
+/// Walks the directory tree below `root`, directories which cannot be read are skipped
+pub fn walk(root: &Path, options: &WalkOptions) -> WalkResult {
+    let mut result = WalkResult::default();
+    let mut pending: Vec<(PathBuf, String, usize)> = vec![(root.to_path_buf(), String::new(), 0)];
+    while let Some((dir, dir_relative, depth)) = pending.pop() {
+        if options.cancellation.is_cancelled() {
+            result.cancelled = true;
+            break;
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Cannot read directory {:?}: {}", dir, e);
+                continue;
+            }
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative_path = if dir_relative.is_empty() {
+                name
+            } else {
+                format!("{}/{}", dir_relative, name)
+            };
+            let is_directory = path.is_dir();
+            if options.rules.is_excluded(&relative_path, is_directory) {
+                if is_directory {
+                    result.summary.excluded_dirs += 1;
+                } else {
+                    result.summary.excluded_files += 1;
+                }
+                continue;
+            }
+            let size = if is_directory {
+                0
+            } else {
+                entry.metadata().map(|m| m.len()).unwrap_or(0)
+            };
+            if is_directory {
+                result.summary.dirs += 1;
+                let descend = match options.max_depth {
+                    Some(max) => depth + 1 < max,
+                    None => true,
+                };
+                if descend {
+                    pending.push((path.clone(), relative_path.clone(), depth + 1));
+                }
+            } else {
+                result.summary.files += 1;
+                result.summary.bytes += size;
+            }
+            if options.collect_entries {
+                result.entries.push(WalkEntry {
+                    path,
+                    relative_path,
+                    is_directory,
+                    size,
+                });
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("README.md"), b"readme").unwrap();
+        fs::write(root.join("notes.tmp"), b"tmp").unwrap();
+        fs::write(root.join("src/main.rs"), b"fn main() {}").unwrap();
+        fs::write(root.join("src/nested/lib.rs"), b"").unwrap();
+        fs::write(root.join("src/nested/cache.tmp"), b"cache").unwrap();
+        fs::write(root.join("target/debug/app"), b"binary").unwrap();
+        dir
+    }
+
+    #[test]
+    fn patterns_follow_gitignore_conventions() {
+        let rules = IgnoreRules::new(&[
+            "# comment".to_string(),
+            "*.tmp".to_string(),
+            "target/".to_string(),
+            "/docs/*.md".to_string(),
+        ]);
+        assert!(rules.is_excluded("a/b/c.tmp", false));
+        assert!(rules.is_excluded("target", true));
+        assert!(!rules.is_excluded("target", false));
+        assert!(rules.is_excluded("docs/intro.md", false));
+        assert!(!rules.is_excluded("docs/deep/intro.md", false));
+        assert!(!rules.is_excluded("README.md", false));
+    }
+
+    #[test]
+    fn double_star_crosses_directories() {
+        let rules = IgnoreRules::new(&["logs/**/*.log".to_string()]);
+        assert!(rules.is_excluded("logs/a/b/app.log", false));
+        assert!(rules.is_excluded("logs/app.log", false));
+        assert!(!rules.is_excluded("other/app.log", false));
+    }
+
+    #[test]
+    fn walk_without_rules_counts_everything() {
+        let dir = fixture();
+        let res = walk(dir.path(), &WalkOptions::default());
+        assert_eq!(res.summary.files, 6);
+        assert_eq!(res.summary.dirs, 4);
+        assert_eq!(res.summary.bytes, 6 + 3 + 12 + 5 + 6);
+        assert!(res.entries.is_empty());
+        assert!(!res.cancelled);
+    }
+
+    #[test]
+    fn walk_skips_excluded_entries_and_reports_them() {
+        let dir = fixture();
+        let rules = IgnoreRules::new(&["*.tmp".to_string(), "target/".to_string()]);
+        let res = walk(dir.path(), &WalkOptions::with_rules(rules));
+        assert_eq!(
+            res.summary,
+            WalkSummary {
+                files: 3,
+                dirs: 2,
+                bytes: 6 + 12,
+                excluded_files: 2,
+                excluded_dirs: 1,
+            }
+        );
+        let mut files: Vec<String> = res
+            .entries
+            .iter()
+            .filter(|e| !e.is_directory)
+            .map(|e| e.relative_path.clone())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["README.md", "src/main.rs", "src/nested/lib.rs"]);
+    }
+
+    #[test]
+    fn walk_stops_at_max_depth() {
+        let dir = fixture();
+        let options = WalkOptions {
+            max_depth: Some(1),
+            ..WalkOptions::default()
+        };
+        let res = walk(dir.path(), &options);
+        assert_eq!(res.summary.files, 2);
+        assert_eq!(res.summary.dirs, 2);
+    }
+
+    #[test]
+    fn cancelled_walk_returns_early() {
+        let dir = fixture();
+        let options = WalkOptions::default();
+        options.cancellation.cancel();
+        let res = walk(dir.path(), &options);
+        assert!(res.cancelled);
+        assert_eq!(res.summary, WalkSummary::default());
+    }
+}
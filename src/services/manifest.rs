@@ -0,0 +1,283 @@
+//! This module provides the manifest written into the destination directory of the downloads,
+//! listing every downloaded file with the values s3 returned for it
+use crate::model::s3_selected_item::S3SelectedItem;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::Path;
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.csv";
+
+/// First line of the manifest, both have the same length so the line is replaced in place
+const INCOMPLETE_STATUS: &str = "# status: incomplete\n";
+const COMPLETE_STATUS: &str = "# status: complete  \n";
+const CSV_HEADER: &str = "bucket,key,size,etag,checksum,local_path\n";
+
+/// Row of the manifest for one downloaded file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    /// Destination directory of the download, the manifest is written there
+    pub directory: String,
+    pub bucket: String,
+    pub key: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    /// Additional checksum stored with the object, e.g. `sha256:...`
+    pub checksum: Option<String>,
+    pub local_path: String,
+}
+
+impl ManifestEntry {
+    pub fn downloaded(
+        item: &S3SelectedItem,
+        size: u64,
+        etag: Option<String>,
+        checksum: Option<String>,
+    ) -> Self {
+        ManifestEntry {
+            directory: item.destination_dir.clone(),
+            bucket: item.bucket.clone().unwrap_or_default(),
+            key: item.path.clone().unwrap_or(item.name.clone()),
+            size,
+            etag: etag.map(|e| e.trim_matches('"').to_string()),
+            checksum,
+            local_path: item.local_destination().to_string_lossy().into_owned(),
+        }
+    }
+
+    fn to_csv_row(&self) -> String {
+        let fields = [
+            csv_field(&self.bucket),
+            csv_field(&self.key),
+            self.size.to_string(),
+            csv_field(self.etag.as_deref().unwrap_or_default()),
+            csv_field(self.checksum.as_deref().unwrap_or_default()),
+            csv_field(&self.local_path),
+        ];
+        format!("{}\n", fields.join(","))
+    }
+}
+
+/// Quotes the value when it contains a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Manifest of one directory, every row is written as soon as it's appended so the file
+/// is valid at any point of the run
+pub struct ManifestWriter {
+    file: File,
+    rows: usize,
+}
+
+impl ManifestWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        file.write_all(INCOMPLETE_STATUS.as_bytes())?;
+        file.write_all(CSV_HEADER.as_bytes())?;
+        Ok(ManifestWriter { file, rows: 0 })
+    }
+
+    pub fn append(&mut self, entry: &ManifestEntry) -> io::Result<()> {
+        self.file.write_all(entry.to_csv_row().as_bytes())?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Marks the manifest complete when it lists all the `expected` files,
+    /// returns whether it did
+    pub fn finish(mut self, expected: usize) -> io::Result<bool> {
+        if self.rows != expected {
+            return Ok(false);
+        }
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(COMPLETE_STATUS.as_bytes())?;
+        Ok(true)
+    }
+}
+
+/// Number of files downloaded into every destination directory, the manifest of a directory
+/// is complete once it has as many rows
+pub fn expected_files<'a>(
+    downloads: impl Iterator<Item = &'a S3SelectedItem>,
+) -> HashMap<String, usize> {
+    let mut expected = HashMap::new();
+    for item in downloads {
+        *expected.entry(item.destination_dir.clone()).or_insert(0) += 1;
+    }
+    expected
+}
+
+pub enum ManifestMessage {
+    Entry(ManifestEntry),
+    /// All the transfers of the run ended, failed ones don't have a row
+    RunFinished,
+}
+
+/// Starts the task writing the manifests of a run, `expected` is the number of downloads
+/// by destination directory. Rows of the concurrent transfers arrive through the channel,
+/// so they are appended one at a time. Without `RunFinished` (e.g. the run was cancelled)
+/// the manifests stay marked incomplete
+pub fn spawn_manifest_writer(expected: HashMap<String, usize>) -> UnboundedSender<ManifestMessage> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<ManifestMessage>();
+    tokio::spawn(async move {
+        let mut writers: HashMap<String, ManifestWriter> = HashMap::new();
+        while let Some(message) = rx.recv().await {
+            match message {
+                ManifestMessage::Entry(entry) => {
+                    if let Err(e) = append_entry(&mut writers, &entry) {
+                        tracing::warn!("Cannot write the manifest in {}: {}", entry.directory, e);
+                    }
+                }
+                ManifestMessage::RunFinished => {
+                    for (directory, writer) in writers.drain() {
+                        let files = expected.get(&directory).copied().unwrap_or_default();
+                        finish_manifest(&directory, writer, files);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+    tx
+}
+
+fn finish_manifest(directory: &str, writer: ManifestWriter, files: usize) {
+    match writer.finish(files) {
+        Ok(true) => tracing::info!("Manifest of {} is complete", directory),
+        Ok(false) => tracing::warn!("Manifest of {} misses the failed files", directory),
+        Err(e) => tracing::warn!("Cannot finish the manifest in {}: {}", directory, e),
+    }
+}
+
+fn append_entry(
+    writers: &mut HashMap<String, ManifestWriter>,
+    entry: &ManifestEntry,
+) -> io::Result<()> {
+    let writer = match writers.entry(entry.directory.clone()) {
+        Entry::Occupied(writer) => writer.into_mut(),
+        Entry::Vacant(vacant) => {
+            let path = Path::new(&entry.directory).join(MANIFEST_FILE_NAME);
+            vacant.insert(ManifestWriter::create(&path)?)
+        }
+    };
+    writer.append(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn entry(directory: &Path, key: &str, size: u64) -> ManifestEntry {
+        ManifestEntry {
+            directory: directory.to_string_lossy().into_owned(),
+            bucket: "archive".into(),
+            key: key.into(),
+            size,
+            etag: Some("9e107d9d372bb6826bd81d3542a419d6".into()),
+            checksum: None,
+            local_path: directory.join(key).to_string_lossy().into_owned(),
+        }
+    }
+
+    #[test]
+    fn fields_with_separators_are_quoted() {
+        assert_eq!(csv_field("plain/key.txt"), "plain/key.txt");
+        assert_eq!(csv_field("a,b.txt"), "\"a,b.txt\"");
+        assert_eq!(csv_field("say \"hi\".txt"), "\"say \"\"hi\"\".txt\"");
+    }
+
+    fn download(key: &str, destination_dir: &str) -> S3SelectedItem {
+        S3SelectedItem {
+            bucket: Some("archive".into()),
+            name: key.rsplit('/').next().unwrap_or(key).into(),
+            path: Some(key.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: destination_dir.into(),
+            transferred: false,
+            account: Default::default(),
+            progress: 0f64,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        }
+    }
+
+    #[test]
+    fn downloads_are_counted_by_directory() {
+        let downloads = [
+            download("2024/a.txt", "/tmp/archive"),
+            download("2024/b.txt", "/tmp/archive"),
+            download("c.txt", "/tmp/other"),
+        ];
+        assert_eq!(
+            expected_files(downloads.iter()),
+            HashMap::from([("/tmp/archive".into(), 2), ("/tmp/other".into(), 1)])
+        );
+    }
+
+    #[test]
+    fn etag_quotes_are_removed() {
+        let item = download("2024/b.txt", "/tmp/archive");
+        let entry = ManifestEntry::downloaded(&item, 5, Some("\"abc\"".into()), None);
+        assert_eq!(entry.etag, Some("abc".into()));
+        assert_eq!(entry.key, "2024/b.txt");
+        assert_eq!(entry.directory, "/tmp/archive");
+    }
+
+    #[tokio::test]
+    async fn manifest_lists_the_downloaded_files_only() {
+        let dir = tempdir().unwrap();
+        let directory = dir.path().to_string_lossy().into_owned();
+        let tx = spawn_manifest_writer(HashMap::from([(directory.clone(), 3)]));
+        tx.send(ManifestMessage::Entry(entry(dir.path(), "a.txt", 10)))
+            .unwrap();
+        // the second file failed, it has no row
+        tx.send(ManifestMessage::Entry(entry(dir.path(), "c,d.txt", 30)))
+            .unwrap();
+        tx.send(ManifestMessage::RunFinished).unwrap();
+        tx.closed().await;
+
+        let manifest = fs::read_to_string(dir.path().join(MANIFEST_FILE_NAME)).unwrap();
+        let lines: Vec<&str> = manifest.lines().collect();
+        assert_eq!(lines[0], INCOMPLETE_STATUS.trim_end());
+        assert_eq!(lines[1], CSV_HEADER.trim_end());
+        assert_eq!(
+            lines[2],
+            format!(
+                "archive,a.txt,10,9e107d9d372bb6826bd81d3542a419d6,,{}",
+                dir.path().join("a.txt").display()
+            )
+        );
+        assert!(lines[3].starts_with("archive,\"c,d.txt\",30,"));
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn manifest_is_marked_complete_when_every_file_has_a_row() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(MANIFEST_FILE_NAME);
+        let mut writer = ManifestWriter::create(&path).unwrap();
+        writer.append(&entry(dir.path(), "a.txt", 10)).unwrap();
+        // a partial manifest is readable while the run goes on
+        let partial = fs::read_to_string(&path).unwrap();
+        assert!(partial.starts_with(INCOMPLETE_STATUS));
+        assert_eq!(partial.lines().count(), 3);
+
+        assert!(writer.finish(1).unwrap());
+        let manifest = fs::read_to_string(&path).unwrap();
+        assert!(manifest.starts_with(COMPLETE_STATUS));
+        assert_eq!(manifest.lines().count(), 3);
+    }
+}
@@ -0,0 +1,339 @@
+//! This module provides the optional Prometheus endpoint exposing the transfer statistics
+use crate::model::state::State;
+use crate::model::stats::Stats;
+use color_eyre::eyre;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Upper bounds (in seconds) of the transfer duration histogram buckets
+const DURATION_BUCKETS: [f64; 7] = [1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 3600.0];
+
+#[derive(Default)]
+struct Snapshot {
+    stats: Stats,
+    active: usize,
+    queued: usize,
+    failed: usize,
+    /// Number of transfers per bucket of `DURATION_BUCKETS`, the last one counts the longer ones
+    durations: [u64; DURATION_BUCKETS.len() + 1],
+    durations_sum: f64,
+}
+
+/// Values exposed on the metrics endpoint, updated by the state store
+#[derive(Default)]
+pub struct Metrics {
+    snapshot: Mutex<Snapshot>,
+}
+
+impl Metrics {
+    /// Copies the session statistics and counts the transfers of the selection
+    pub fn update(&self, state: &State) {
+        let (mut active, mut queued, mut failed) = (0, 0, 0);
+        let mut count = |done: bool, error: bool, progress: f64| match (done, error) {
+            (_, true) => failed += 1,
+            (false, false) if progress > 0.0 => active += 1,
+            (false, false) => queued += 1,
+            (true, false) => {}
+        };
+        for item in &state.s3_selected_items {
+            match &item.children {
+                Some(children) => children
+                    .iter()
+                    .for_each(|c| count(c.transferred, c.error.is_some(), c.progress)),
+                None => count(item.transferred, item.error.is_some(), item.progress),
+            }
+        }
+        for item in &state.local_selected_items {
+            match &item.children {
+                Some(children) => children
+                    .iter()
+                    .for_each(|c| count(c.transferred, c.error.is_some(), c.progress)),
+                None => count(item.transferred, item.error.is_some(), item.progress),
+            }
+        }
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.stats = state.stats.clone();
+        snapshot.active = active;
+        snapshot.queued = queued;
+        snapshot.failed = failed;
+    }
+
+    pub fn observe_transfer(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.durations[bucket] += 1;
+        snapshot.durations_sum += seconds;
+    }
+
+    /// Average duration of the transfers finished so far, `None` before the first one
+    pub fn average_transfer_duration(&self) -> Option<Duration> {
+        let snapshot = self.snapshot.lock().unwrap();
+        let count: u64 = snapshot.durations.iter().sum();
+        if count == 0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            snapshot.durations_sum / count as f64,
+        ))
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let snapshot = self.snapshot.lock().unwrap();
+        let stats = &snapshot.stats;
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, String)]| {
+            let _ = writeln!(out, "# HELP s3tui_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE s3tui_{} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "s3tui_{}{} {}", name, labels, value);
+            }
+        };
+        metric(
+            "transfers_active",
+            "gauge",
+            "Transfers in progress",
+            &[("", snapshot.active.to_string())],
+        );
+        metric(
+            "transfers_queued",
+            "gauge",
+            "Selected transfers not started yet",
+            &[("", snapshot.queued.to_string())],
+        );
+        metric(
+            "transfers_failed",
+            "gauge",
+            "Selected transfers in error",
+            &[("", snapshot.failed.to_string())],
+        );
+        metric(
+            "transfers_total",
+            "counter",
+            "Finished transfers",
+            &[
+                (
+                    "{direction=\"download\",outcome=\"completed\"}",
+                    stats.downloads_completed.to_string(),
+                ),
+                (
+                    "{direction=\"download\",outcome=\"failed\"}",
+                    stats.downloads_failed.to_string(),
+                ),
+                (
+                    "{direction=\"upload\",outcome=\"completed\"}",
+                    stats.uploads_completed.to_string(),
+                ),
+                (
+                    "{direction=\"upload\",outcome=\"failed\"}",
+                    stats.uploads_failed.to_string(),
+                ),
+            ],
+        );
+        metric(
+            "bytes_downloaded_total",
+            "counter",
+            "Bytes written to disk by the downloads",
+            &[("", stats.bytes_downloaded.to_string())],
+        );
+        metric(
+            "bytes_uploaded_total",
+            "counter",
+            "Bytes sent by the uploads",
+            &[("", stats.bytes_uploaded.to_string())],
+        );
+        metric(
+            "api_errors_total",
+            "counter",
+            "Failed s3 operations by error class",
+            &[
+                ("{class=\"timeout\"}", stats.timeout_errors.to_string()),
+                (
+                    "{class=\"connection\"}",
+                    stats.connection_errors.to_string(),
+                ),
+                ("{class=\"proxy\"}", stats.proxy_errors.to_string()),
+                ("{class=\"service\"}", stats.service_errors.to_string()),
+                (
+                    "{class=\"open_files\"}",
+                    stats.open_files_errors.to_string(),
+                ),
+                ("{class=\"other\"}", stats.other_errors.to_string()),
+            ],
+        );
+        let mut cumulative = 0;
+        let mut buckets: Vec<(String, String)> = Vec::new();
+        for (bound, count) in DURATION_BUCKETS.iter().zip(snapshot.durations.iter()) {
+            cumulative += count;
+            buckets.push((
+                format!("_bucket{{le=\"{}\"}}", bound),
+                cumulative.to_string(),
+            ));
+        }
+        cumulative += snapshot.durations[DURATION_BUCKETS.len()];
+        buckets.push(("_bucket{le=\"+Inf\"}".into(), cumulative.to_string()));
+        buckets.push(("_sum".into(), snapshot.durations_sum.to_string()));
+        buckets.push(("_count".into(), cumulative.to_string()));
+        let samples: Vec<(&str, String)> = buckets
+            .iter()
+            .map(|(labels, value)| (labels.as_str(), value.clone()))
+            .collect();
+        metric(
+            "transfer_duration_seconds",
+            "histogram",
+            "Duration of the finished transfers",
+            &samples,
+        );
+        out
+    }
+}
+
+fn respond(metrics: &Metrics, request: Request<Body>) -> Response<Body> {
+    if request.method() == Method::GET && request.uri().path() == "/metrics" {
+        Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(metrics.render()))
+            .unwrap()
+    } else {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap()
+    }
+}
+
+/// HTTP listener serving `/metrics`, it stops when `stop` is called or the value is dropped
+pub struct MetricsServer {
+    pub address: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// Starts listening on the address, non-loopback addresses are refused unless `allow_remote`
+    /// is set since the endpoint has no authentication
+    pub fn start(
+        address: SocketAddr,
+        allow_remote: bool,
+        metrics: Arc<Metrics>,
+    ) -> eyre::Result<MetricsServer> {
+        if !address.ip().is_loopback() && !allow_remote {
+            return Err(eyre::eyre!(
+                "{} is not a loopback address, set metrics_allow_remote = true to expose the metrics",
+                address
+            ));
+        }
+        let make_service = make_service_fn(move |_| {
+            let metrics = metrics.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let response = respond(&metrics, request);
+                    async move { Ok::<_, Infallible>(response) }
+                }))
+            }
+        });
+        let server = Server::try_bind(&address)?.serve(make_service);
+        let address = server.local_addr();
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            let graceful = server.with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+            if let Err(e) = graceful.await {
+                tracing::error!("metrics endpoint failed: {}", e);
+            }
+        });
+        Ok(MetricsServer {
+            address,
+            shutdown,
+            handle,
+        })
+    }
+
+    /// Stops accepting connections and waits for the pending responses
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.handle.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::s3_errors::ErrorClass;
+    use hyper::Client;
+
+    async fn scrape(address: SocketAddr) -> String {
+        let uri = format!("http://{}/metrics", address).parse().unwrap();
+        let response = Client::new().get(uri).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn scraped_counters_follow_the_transfers() {
+        let metrics = Arc::new(Metrics::default());
+        let server =
+            MetricsServer::start("127.0.0.1:0".parse().unwrap(), false, metrics.clone()).unwrap();
+
+        let before = scrape(server.address).await;
+        assert!(before.contains("s3tui_bytes_downloaded_total 0\n"));
+        assert!(before.contains("s3tui_transfer_duration_seconds_count 0\n"));
+
+        let mut state = State::default();
+        state.stats.record_download(&Ok(100));
+        state.stats.record_upload(&Err(ErrorClass::Timeout));
+        metrics.update(&state);
+        assert_eq!(metrics.average_transfer_duration(), None);
+        metrics.observe_transfer(Duration::from_secs(3));
+        metrics.observe_transfer(Duration::from_secs(7200));
+        assert_eq!(
+            metrics.average_transfer_duration(),
+            Some(Duration::from_secs_f64(3601.5))
+        );
+
+        let after = scrape(server.address).await;
+        assert!(after.contains("s3tui_bytes_downloaded_total 100\n"));
+        assert!(
+            after.contains("s3tui_transfers_total{direction=\"upload\",outcome=\"failed\"} 1\n")
+        );
+        assert!(after.contains("s3tui_api_errors_total{class=\"timeout\"} 1\n"));
+        assert!(after.contains("s3tui_transfer_duration_seconds_bucket{le=\"1\"} 0\n"));
+        assert!(after.contains("s3tui_transfer_duration_seconds_bucket{le=\"5\"} 1\n"));
+        assert!(after.contains("s3tui_transfer_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(after.contains("s3tui_transfer_duration_seconds_count 2\n"));
+
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn other_paths_are_not_found() {
+        let metrics = Arc::new(Metrics::default());
+        let server = MetricsServer::start("127.0.0.1:0".parse().unwrap(), false, metrics).unwrap();
+        let uri = format!("http://{}/", server.address).parse().unwrap();
+        let response = Client::new().get(uri).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn non_loopback_addresses_need_an_override() {
+        let metrics = Arc::new(Metrics::default());
+        let address = "0.0.0.0:0".parse().unwrap();
+        assert!(MetricsServer::start(address, false, metrics.clone()).is_err());
+        let server = MetricsServer::start(address, true, metrics).unwrap();
+        server.stop().await;
+    }
+}
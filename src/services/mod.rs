@@ -1,5 +1,34 @@
 //! This module provides functionality for managing data on your selected s3 account as well
 //! as your local machine
 
+pub mod adaptive_concurrency;
+pub mod bucket_location;
+pub mod checked_file;
+pub mod checksum;
+pub mod external_command;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injection;
+pub mod folder_markers;
 pub mod local_data_fetcher;
+pub mod local_names;
+pub mod local_walker;
+pub mod manifest;
+pub mod metrics;
+pub mod mtime;
+pub mod object_cache;
+pub mod object_text;
+pub mod partial_download;
+pub mod profile_credentials;
+pub mod region_redirect;
 pub mod s3_data_fetcher;
+pub mod s3_errors;
+pub mod s3_keys;
+pub mod s3_listing;
+pub mod selection_file;
+pub mod selection_limit;
+pub mod startup_work;
+pub mod task_registry;
+pub mod tls;
+pub mod transfer_control;
+pub mod transfer_persistence;
+pub mod transfer_queue;
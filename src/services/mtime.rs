@@ -0,0 +1,127 @@
+//! This module provides the modification times kept across uploads and downloads.
+//! Uploads store the local mtime in the `x-amz-meta-mtime` header (as `mtime` user metadata),
+//! downloads restore it or fall back to the `LastModified` of the object
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the user metadata, sent as `x-amz-meta-mtime`
+pub const MTIME_METADATA_KEY: &str = "mtime";
+
+/// Seconds since the unix epoch with the nanoseconds, e.g. `1700000000.123456789`.
+/// Times before the epoch are not stored
+pub fn encode_mtime(time: SystemTime) -> Option<String> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!(
+        "{}.{:09}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos()
+    ))
+}
+
+/// Reads the value written by `encode_mtime`, the same format is used by rclone.
+/// Whole seconds without the fraction are accepted as well
+pub fn decode_mtime(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    let (secs, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let secs: u64 = secs.parse().ok()?;
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        format!("{:0<9}", fraction).parse().ok()?
+    };
+    UNIX_EPOCH.checked_add(Duration::new(secs, nanos))
+}
+
+/// Modification time given to a downloaded file, the time stored on upload wins over
+/// the `LastModified` of the object. `None` when neither is known
+pub fn download_mtime(
+    metadata: Option<&HashMap<String, String>>,
+    last_modified: Option<SystemTime>,
+) -> Option<SystemTime> {
+    metadata
+        .and_then(|m| m.get(MTIME_METADATA_KEY))
+        .map(String::as_str)
+        .and_then(decode_mtime)
+        .or(last_modified)
+}
+
+pub fn set_file_mtime(path: &Path, time: SystemTime) -> io::Result<()> {
+    File::options().write(true).open(path)?.set_modified(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn mtime_survives_encoding() {
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let encoded = encode_mtime(time).unwrap();
+        assert_eq!(encoded, "1700000000.123456789");
+        assert_eq!(decode_mtime(&encoded), Some(time));
+        assert_eq!(encode_mtime(UNIX_EPOCH - Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn other_tools_values_are_decoded() {
+        assert_eq!(
+            decode_mtime("1700000000"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(
+            decode_mtime("1700000000.5"),
+            Some(UNIX_EPOCH + Duration::new(1_700_000_000, 500_000_000))
+        );
+        assert_eq!(decode_mtime(""), None);
+        assert_eq!(decode_mtime("2024-01-01T00:00:00Z"), None);
+        assert_eq!(decode_mtime("1700000000.-5"), None);
+    }
+
+    #[test]
+    fn stored_mtime_wins_over_last_modified() {
+        let stored = UNIX_EPOCH + Duration::from_secs(1_000);
+        let last_modified = Some(UNIX_EPOCH + Duration::from_secs(2_000));
+        let metadata = HashMap::from([(
+            MTIME_METADATA_KEY.to_string(),
+            encode_mtime(stored).unwrap(),
+        )]);
+        assert_eq!(download_mtime(Some(&metadata), last_modified), Some(stored));
+
+        let broken = HashMap::from([(MTIME_METADATA_KEY.to_string(), "yesterday".to_string())]);
+        assert_eq!(download_mtime(Some(&broken), last_modified), last_modified);
+        assert_eq!(download_mtime(None, None), None);
+    }
+
+    #[test]
+    fn mtime_round_trips_through_the_filesystem() {
+        let dir = tempdir().unwrap();
+        let uploaded = dir.path().join("uploaded.txt");
+        let downloaded = dir.path().join("downloaded.txt");
+        fs::write(&uploaded, "data").unwrap();
+        fs::write(&downloaded, "data").unwrap();
+        let original = UNIX_EPOCH + Duration::new(1_600_000_000, 250_000_000);
+        set_file_mtime(&uploaded, original).unwrap();
+
+        // upload: the local mtime goes into the metadata, download: it's restored from there
+        let local = fs::metadata(&uploaded).unwrap().modified().unwrap();
+        let metadata =
+            HashMap::from([(MTIME_METADATA_KEY.to_string(), encode_mtime(local).unwrap())]);
+        let restored = download_mtime(Some(&metadata), Some(SystemTime::now())).unwrap();
+        set_file_mtime(&downloaded, restored).unwrap();
+
+        let result = fs::metadata(&downloaded).unwrap().modified().unwrap();
+        let difference = result
+            .duration_since(original)
+            .unwrap_or_else(|e| e.duration());
+        // some filesystems keep the time with a 2 second resolution
+        assert!(difference <= Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,250 @@
+//! This module provides the cache of the object contents read for the preview and the
+//! clipboard. A content is stored in a temporary directory under a name derived from the
+//! account, bucket, key and ETag of the object, so a changed object is never read from it
+use crate::services::checksum::Sha256Hasher;
+use crate::services::mtime::set_file_mtime;
+use crate::services::partial_download::with_suffix;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Size of the cached contents above which the least recently used ones are removed
+pub const CACHE_BUDGET: u64 = 64 * 1024 * 1024;
+
+/// Contents not used for this long are removed when the application starts
+pub const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Suffix of the contents still being written, they aren't read or evicted
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// Tells apart the contents written at the same time
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+/// Identifies a content, a new ETag of the object means a new content
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentKey {
+    pub account: String,
+    pub bucket: String,
+    pub key: String,
+    pub etag: String,
+}
+
+/// Cached beginning of an object, the whole object when it isn't above the requested size
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedObject {
+    pub path: PathBuf,
+    pub object_size: u64,
+}
+
+/// Contents of the objects read in the session, shared by all the fetchers through the
+/// directory
+#[derive(Debug, Clone)]
+pub struct ObjectCache {
+    directory: PathBuf,
+    budget: u64,
+}
+
+impl ObjectCache {
+    pub fn new(directory: PathBuf, budget: u64) -> Self {
+        ObjectCache { directory, budget }
+    }
+
+    /// Cache in the temporary directory of the system
+    pub fn in_temp_dir() -> Self {
+        Self::new(std::env::temp_dir().join("s3tui-objects"), CACHE_BUDGET)
+    }
+
+    fn entry_path(&self, content: &ContentKey) -> PathBuf {
+        let mut hasher = Sha256Hasher::default();
+        for part in [
+            &content.account,
+            &content.bucket,
+            &content.key,
+            &content.etag,
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update(b"\n");
+        }
+        self.directory.join(hasher.finish())
+    }
+
+    /// Content holding at least the first `wanted` bytes of the object, it becomes the most
+    /// recently used one
+    pub fn lookup(&self, content: &ContentKey, wanted: u64) -> Option<PathBuf> {
+        let path = self.entry_path(content);
+        let stored = fs::metadata(&path).ok()?.len();
+        if stored < wanted {
+            return None;
+        }
+        if let Err(e) = set_file_mtime(&path, SystemTime::now()) {
+            tracing::warn!("Cannot mark {} as used: {}", path.display(), e);
+        }
+        Some(path)
+    }
+
+    /// Stores the bytes read from the beginning of the object, then removes the least
+    /// recently used contents above the budget. The stored content is kept even when it's
+    /// above the budget by itself
+    pub fn store(&self, content: &ContentKey, bytes: &[u8]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.entry_path(content);
+        let write = WRITES.fetch_add(1, Ordering::Relaxed);
+        let partial = with_suffix(&path, &format!(".{}{}", write, PARTIAL_SUFFIX));
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, &path)?;
+        self.evict(&path)?;
+        Ok(path)
+    }
+
+    /// Stored contents with their size and last use, the oldest first
+    fn entries(&self) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut entries = Vec::new();
+        let listing = match fs::read_dir(&self.directory) {
+            Ok(listing) => listing,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => return Err(e),
+        };
+        for entry in listing {
+            let entry = entry?;
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(PARTIAL_SUFFIX)
+            {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                entries.push((entry.path(), metadata.len(), metadata.modified()?));
+            }
+        }
+        entries.sort_by_key(|(_, _, used)| *used);
+        Ok(entries)
+    }
+
+    fn evict(&self, keep: &Path) -> io::Result<()> {
+        let entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        for (path, size, _) in entries {
+            if total <= self.budget {
+                break;
+            }
+            if path != keep {
+                remove_entry(&path)?;
+                total -= size;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the contents not used for `ttl`, returns how many were removed
+    pub fn remove_expired(&self, ttl: Duration) -> io::Result<usize> {
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for (path, _, used) in self.entries()? {
+            if now.duration_since(used).unwrap_or_default() > ttl {
+                remove_entry(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Another session may have removed the content already
+fn remove_entry(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn content(key: &str, etag: &str) -> ContentKey {
+        ContentKey {
+            account: "work".into(),
+            bucket: "logs".into(),
+            key: key.into(),
+            etag: etag.into(),
+        }
+    }
+
+    fn used_ago(path: &Path, seconds: u64) {
+        set_file_mtime(path, SystemTime::now() - Duration::from_secs(seconds)).unwrap();
+    }
+
+    #[test]
+    fn stored_beginning_is_found_until_more_is_wanted() {
+        let directory = tempdir().unwrap();
+        let cache = ObjectCache::new(directory.path().to_path_buf(), 1024);
+        let app_log = content("app.log", "\"abc\"");
+        assert_eq!(cache.lookup(&app_log, 0), None);
+
+        let path = cache.store(&app_log, b"level=info").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"level=info");
+        assert_eq!(cache.lookup(&app_log, 4), Some(path.clone()));
+        assert_eq!(cache.lookup(&app_log, 10), Some(path));
+        assert_eq!(cache.lookup(&app_log, 11), None);
+        // the same key of another account is another object
+        let other = ContentKey {
+            account: "home".into(),
+            ..app_log
+        };
+        assert_eq!(cache.lookup(&other, 0), None);
+    }
+
+    #[test]
+    fn changed_etag_misses_the_cache() {
+        let directory = tempdir().unwrap();
+        let cache = ObjectCache::new(directory.path().to_path_buf(), 1024);
+        let old = cache.store(&content("a.json", "\"1\""), b"{}").unwrap();
+
+        assert_eq!(cache.lookup(&content("a.json", "\"2\""), 0), None);
+        let new = cache.store(&content("a.json", "\"2\""), b"[]").unwrap();
+        assert_ne!(old, new);
+        assert_eq!(fs::read(new).unwrap(), b"[]");
+    }
+
+    #[test]
+    fn least_recently_used_contents_are_evicted_above_the_budget() {
+        let directory = tempdir().unwrap();
+        let cache = ObjectCache::new(directory.path().to_path_buf(), 10);
+        let first = cache.store(&content("first", "e"), b"1234").unwrap();
+        let second = cache.store(&content("second", "e"), b"1234").unwrap();
+        used_ago(&first, 20);
+        used_ago(&second, 10);
+        // reading the first one makes the second one the least recently used
+        cache.lookup(&content("first", "e"), 4).unwrap();
+
+        let third = cache.store(&content("third", "e"), b"1234").unwrap();
+        assert!(first.exists());
+        assert!(!second.exists());
+        assert!(third.exists());
+
+        // a content above the budget by itself is still stored
+        let large = cache.store(&content("large", "e"), &[b'a'; 32]).unwrap();
+        assert!(large.exists());
+        assert!(!first.exists() && !third.exists());
+    }
+
+    #[test]
+    fn contents_of_earlier_sessions_expire() {
+        let directory = tempdir().unwrap();
+        let cache = ObjectCache::new(directory.path().to_path_buf(), 1024);
+        let old = cache.store(&content("old", "e"), b"old").unwrap();
+        let recent = cache.store(&content("recent", "e"), b"recent").unwrap();
+        used_ago(&old, 2 * 24 * 60 * 60);
+
+        assert_eq!(cache.remove_expired(CACHE_TTL).unwrap(), 1);
+        assert!(!old.exists());
+        assert!(recent.exists());
+        let missing = ObjectCache::new(directory.path().join("missing"), 1024);
+        assert_eq!(missing.remove_expired(CACHE_TTL).unwrap(), 0);
+    }
+}
@@ -0,0 +1,46 @@
+//! This module provides reading small s3 objects as text, e.g. to copy them to the clipboard
+use crate::utils::format_bytes;
+
+/// Refuses objects above the limit before their content is read
+pub fn check_text_size(size: u64, max_bytes: u64) -> Result<(), String> {
+    match size > max_bytes {
+        true => Err(format!(
+            "{} is above the limit of {}",
+            format_bytes(size),
+            format_bytes(max_bytes)
+        )),
+        false => Ok(()),
+    }
+}
+
+/// Text of the object, content with NUL bytes or which isn't valid UTF-8 is refused as binary
+pub fn text_of(bytes: Vec<u8>) -> Result<String, String> {
+    if bytes.contains(&0) {
+        return Err("binary content isn't copied".to_string());
+    }
+    String::from_utf8(bytes).map_err(|_| "binary content isn't copied".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_above_the_limit_are_refused() {
+        assert_eq!(check_text_size(1024, 1024), Ok(()));
+        assert_eq!(
+            check_text_size(2 * 1024 * 1024, 1024 * 1024),
+            Err("2.0 MB is above the limit of 1.0 MB".to_string())
+        );
+    }
+
+    #[test]
+    fn binary_content_is_refused() {
+        assert_eq!(
+            text_of(b"a=1\nb=2\n".to_vec()),
+            Ok("a=1\nb=2\n".to_string())
+        );
+        assert!(text_of(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec()).is_err());
+        assert!(text_of(b"caf\xe9".to_vec()).is_err());
+    }
+}
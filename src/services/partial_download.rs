@@ -0,0 +1,195 @@
+//! This module provides the resumption of interrupted downloads. The object is written to
+//! `<file>.s3tui-part` and renamed once complete, the ETag and the size of the object are
+//! kept in `<file>.s3tui-part.info`, so downloading the same object again continues from
+//! the bytes already written
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+const PART_SUFFIX: &str = ".s3tui-part";
+const INFO_SUFFIX: &str = ".s3tui-part.info";
+
+/// Version of the object the partial file is written from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectVersion {
+    pub etag: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartialDownload {
+    destination: PathBuf,
+    part_path: PathBuf,
+    info_path: PathBuf,
+}
+
+/// The path with the suffix appended to its file name
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+impl PartialDownload {
+    pub fn new(destination: &Path) -> Self {
+        PartialDownload {
+            destination: destination.to_path_buf(),
+            part_path: with_suffix(destination, PART_SUFFIX),
+            info_path: with_suffix(destination, INFO_SUFFIX),
+        }
+    }
+
+    /// Bytes already written from the same version of the object, 0 when the download has
+    /// to start over. Objects without an ETag always start over
+    pub fn resume_offset(&self, version: &ObjectVersion) -> u64 {
+        if version.etag.is_empty() {
+            return 0;
+        }
+        match (self.read_info(), fs::metadata(&self.part_path)) {
+            (Some(info), Ok(metadata)) if info == *version && metadata.len() < version.size => {
+                metadata.len()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Opens the partial file for appending at `offset`, with 0 it's truncated and the
+    /// version of the object is recorded
+    pub fn open(&self, version: &ObjectVersion, offset: u64) -> io::Result<File> {
+        if offset > 0 {
+            return OpenOptions::new().append(true).open(&self.part_path);
+        }
+        fs::write(
+            &self.info_path,
+            format!("{}\n{}\n", version.etag, version.size),
+        )?;
+        File::create(&self.part_path)
+    }
+
+    /// Moves the downloaded file to its destination
+    pub fn complete(&self) -> io::Result<()> {
+        fs::rename(&self.part_path, &self.destination)?;
+        if let Err(e) = fs::remove_file(&self.info_path) {
+            tracing::warn!("Cannot remove {:?}: {}", self.info_path, e);
+        }
+        Ok(())
+    }
+
+    /// Removes the partial file of a cancelled download, the next download starts over
+    pub fn discard(&self) {
+        for path in [&self.part_path, &self.info_path] {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    tracing::warn!("Cannot remove {:?}: {}", path, e)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_info(&self) -> Option<ObjectVersion> {
+        let content = fs::read_to_string(&self.info_path).ok()?;
+        let mut lines = content.lines();
+        let etag = lines.next()?.to_string();
+        let size = lines.next()?.parse().ok()?;
+        Some(ObjectVersion { etag, size })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn version(etag: &str, size: u64) -> ObjectVersion {
+        ObjectVersion {
+            etag: etag.into(),
+            size,
+        }
+    }
+
+    #[test]
+    fn interrupted_download_resumes_after_the_written_bytes() {
+        let dir = tempdir().unwrap();
+        let destination = dir.path().join("report.csv");
+        let partial = PartialDownload::new(&destination);
+        let object = version("\"abc\"", 10);
+        assert_eq!(partial.resume_offset(&object), 0);
+
+        partial
+            .open(&object, 0)
+            .unwrap()
+            .write_all(b"01234")
+            .unwrap();
+        // interrupted here, the next run continues
+        let offset = partial.resume_offset(&object);
+        assert_eq!(offset, 5);
+        partial
+            .open(&object, offset)
+            .unwrap()
+            .write_all(b"56789")
+            .unwrap();
+        partial.complete().unwrap();
+
+        assert_eq!(fs::read(&destination).unwrap(), b"0123456789");
+        assert!(!dir.path().join("report.csv.s3tui-part").exists());
+        assert!(!dir.path().join("report.csv.s3tui-part.info").exists());
+    }
+
+    #[test]
+    fn discarded_download_starts_over() {
+        let dir = tempdir().unwrap();
+        let partial = PartialDownload::new(&dir.path().join("report.csv"));
+        let object = version("\"abc\"", 10);
+        partial
+            .open(&object, 0)
+            .unwrap()
+            .write_all(b"01234")
+            .unwrap();
+        partial.discard();
+
+        assert_eq!(partial.resume_offset(&object), 0);
+        assert!(!dir.path().join("report.csv.s3tui-part").exists());
+        assert!(!dir.path().join("report.csv.s3tui-part.info").exists());
+        // nothing left to remove
+        partial.discard();
+    }
+
+    #[test]
+    fn changed_object_starts_over() {
+        let dir = tempdir().unwrap();
+        let partial = PartialDownload::new(&dir.path().join("report.csv"));
+        partial
+            .open(&version("\"abc\"", 10), 0)
+            .unwrap()
+            .write_all(b"01234")
+            .unwrap();
+
+        assert_eq!(partial.resume_offset(&version("\"def\"", 10)), 0);
+        assert_eq!(partial.resume_offset(&version("\"abc\"", 12)), 0);
+        assert_eq!(partial.resume_offset(&version("", 10)), 0);
+
+        let mut file = partial.open(&version("\"def\"", 3), 0).unwrap();
+        file.write_all(b"new").unwrap();
+        drop(file);
+        partial.complete().unwrap();
+        assert_eq!(fs::read(dir.path().join("report.csv")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn missing_or_broken_info_starts_over() {
+        let dir = tempdir().unwrap();
+        let destination = dir.path().join("data.bin");
+        fs::write(dir.path().join("data.bin.s3tui-part"), b"0123").unwrap();
+        let partial = PartialDownload::new(&destination);
+        assert_eq!(partial.resume_offset(&version("\"abc\"", 10)), 0);
+
+        fs::write(
+            dir.path().join("data.bin.s3tui-part.info"),
+            "\"abc\"\nten\n",
+        )
+        .unwrap();
+        assert_eq!(partial.resume_offset(&version("\"abc\"", 10)), 0);
+    }
+}
@@ -0,0 +1,90 @@
+//! This module provides the credentials of the accounts using a profile of the aws configuration
+//! (`~/.aws/config` and `~/.aws/credentials`) instead of the keys in their credential file
+use aws_config::identity::IdentityCache;
+use aws_config::profile::ProfileFileCredentialsProvider;
+use aws_sdk_s3::config::{ProvideCredentials, SharedCredentialsProvider};
+use aws_smithy_runtime_api::client::identity::SharedIdentityCache;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+lazy_static! {
+    /// Providers of the profiles in use, every client gets the same one so the credentials of
+    /// a profile (an SSO session or an assumed role) are resolved once per session
+    static ref PROFILE_PROVIDERS: Mutex<HashMap<String, SharedCredentialsProvider>> =
+        Mutex::new(HashMap::new());
+    /// Keeps the resolved credentials of the profiles and resolves them again before they expire
+    static ref IDENTITY_CACHE: SharedIdentityCache = IdentityCache::lazy().build();
+}
+
+/// Provider of the credentials of the named profile, following its SSO or role settings
+pub fn profile_provider(profile: &str) -> SharedCredentialsProvider {
+    PROFILE_PROVIDERS
+        .lock()
+        .unwrap()
+        .entry(profile.to_string())
+        .or_insert_with(|| {
+            SharedCredentialsProvider::new(
+                ProfileFileCredentialsProvider::builder()
+                    .profile_name(profile)
+                    .build(),
+            )
+        })
+        .clone()
+}
+
+/// Cache shared by the clients of the profile accounts
+pub fn identity_cache() -> SharedIdentityCache {
+    IDENTITY_CACHE.clone()
+}
+
+/// The profile couldn't give credentials, e.g. it doesn't exist or its SSO session expired
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileError {
+    pub profile: String,
+    pub message: String,
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot get credentials of profile {}: {}",
+            self.profile, self.message
+        )
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+/// Resolves the credentials of the profile, requests sent with a profile which cannot give
+/// them fail with errors telling nothing about the profile
+pub async fn check_profile(profile: &str) -> Result<(), ProfileError> {
+    profile_provider(profile)
+        .provide_credentials()
+        .await
+        .map(|_| ())
+        .map_err(|e| ProfileError {
+            profile: profile.to_string(),
+            message: DisplayErrorContext(e).to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_error_names_the_profile() {
+        let error = ProfileError {
+            profile: "dev-admin".into(),
+            message: "the SSO session has expired".into(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "cannot get credentials of profile dev-admin: the SSO session has expired"
+        );
+    }
+}
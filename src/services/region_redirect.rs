@@ -0,0 +1,311 @@
+//! This module provides the handling of the redirects s3 returns for requests sent to another
+//! region than the one of the bucket, e.g. a transfer of an account configured for `us-east-1`
+//! touching a bucket in `eu-central-1`
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::SdkError;
+use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+use color_eyre::eyre;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Header s3 sends with the region of the bucket
+const BUCKET_REGION_HEADER: &str = "x-amz-bucket-region";
+
+/// Region and endpoint the bucket has to be accessed through, as much as the response tells
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionRedirect {
+    pub region: Option<String>,
+    pub endpoint: Option<String>,
+}
+
+impl RegionRedirect {
+    /// Message displayed instead of the bare service error
+    pub fn describe(&self, configured_region: &str) -> String {
+        match (&self.region, &self.endpoint) {
+            (Some(region), _) => format!(
+                "bucket is in {}; this account is configured for {}",
+                region, configured_region
+            ),
+            (None, Some(endpoint)) => format!(
+                "bucket has to be accessed through {}; this account is configured for {}",
+                endpoint, configured_region
+            ),
+            (None, None) => format!(
+                "bucket is in another region; this account is configured for {}",
+                configured_region
+            ),
+        }
+    }
+}
+
+/// Redirect of the failed request, `None` for the other errors
+pub fn region_redirect<E>(err: &SdkError<E, HttpResponse>) -> Option<RegionRedirect>
+where
+    E: ProvideErrorMetadata,
+{
+    let response = err.raw_response()?;
+    let code = err.as_service_error().and_then(|e| e.code());
+    if response.status().as_u16() != 301 && code != Some("PermanentRedirect") {
+        return None;
+    }
+    let body = response.body().bytes().unwrap_or_default();
+    let endpoint = xml_element(body, "Endpoint");
+    let region = response
+        .headers()
+        .get(BUCKET_REGION_HEADER)
+        .map(String::from)
+        .or_else(|| xml_element(body, "Region"))
+        .or_else(|| endpoint.as_deref().and_then(region_of_endpoint));
+    Some(RegionRedirect { region, endpoint })
+}
+
+/// Text of the first `<name>` element of the error document
+fn xml_element(body: &[u8], name: &str) -> Option<String> {
+    let body = std::str::from_utf8(body).ok()?;
+    let open = format!("<{}>", name);
+    let start = body.find(&open)? + open.len();
+    let end = body[start..].find(&format!("</{}>", name))? + start;
+    Some(body[start..end].trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// Region of endpoints like `bucket.s3.eu-central-1.amazonaws.com` or
+/// `s3-eu-central-1.amazonaws.com`, the global `s3.amazonaws.com` has none
+fn region_of_endpoint(endpoint: &str) -> Option<String> {
+    let labels: Vec<&str> = endpoint.split('.').collect();
+    labels.iter().enumerate().find_map(|(index, label)| {
+        let region = match label.strip_prefix("s3-") {
+            Some(region) => region,
+            None if *label == "s3" => *labels.get(index + 1)?,
+            None => return None,
+        };
+        Some(region.to_string()).filter(|r| r != "amazonaws" && r.contains('-'))
+    })
+}
+
+/// Regions learned from the redirects and the location lookups, shared by the fetchers of all
+/// the accounts as bucket names are global
+#[derive(Debug, Clone, Default)]
+pub struct BucketRegions {
+    regions: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl BucketRegions {
+    pub fn get(&self, bucket: &str) -> Option<String> {
+        self.regions.lock().unwrap().get(bucket).cloned()
+    }
+
+    pub fn set(&self, bucket: &str, region: &str) {
+        self.regions
+            .lock()
+            .unwrap()
+            .insert(bucket.to_string(), region.to_string());
+    }
+
+    /// Forgets the region of a deleted or created bucket, a bucket of the same name may be
+    /// created in another region
+    pub fn forget(&self, bucket: &str) {
+        self.regions.lock().unwrap().remove(bucket);
+    }
+
+    /// Region of the bucket, `lookup` is only asked for the buckets whose region isn't known
+    pub async fn get_or_lookup<F, Fut>(&self, bucket: &str, lookup: F) -> eyre::Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = eyre::Result<String>>,
+    {
+        if let Some(region) = self.get(bucket) {
+            return Ok(region);
+        }
+        let region = lookup().await?;
+        self.set(bucket, &region);
+        Ok(region)
+    }
+
+    /// Keeps the region the error redirects to, returns it when it differs from `region`
+    pub fn remember<E>(
+        &self,
+        bucket: &str,
+        region: &str,
+        err: &SdkError<E, HttpResponse>,
+    ) -> Option<String>
+    where
+        E: ProvideErrorMetadata,
+    {
+        let redirected = region_redirect(err)?.region.filter(|r| r != region)?;
+        tracing::info!("Bucket {} is in {}, not in {}", bucket, redirected, region);
+        self.set(bucket, &redirected);
+        Some(redirected)
+    }
+}
+
+/// Sends the request to `region`, a redirect to another region is remembered and the request
+/// is sent once more to that region. Returns the region of the last attempt with its result
+pub async fn follow_redirect<T, E, F, Fut>(
+    regions: &BucketRegions,
+    bucket: &str,
+    region: String,
+    mut send: F,
+) -> (Result<T, SdkError<E, HttpResponse>>, String)
+where
+    E: ProvideErrorMetadata,
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+{
+    match send(region.clone()).await {
+        Err(e) => match regions.remember(bucket, &region, &e) {
+            Some(redirected) => (send(redirected.clone()).await, redirected),
+            None => (Err(e), region),
+        },
+        result => (result, region),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_s3::operation::head_object::HeadObjectError;
+    use aws_smithy_runtime_api::http::{Response, StatusCode};
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::error::ErrorMetadata;
+
+    type TestError = SdkError<HeadObjectError, Response>;
+
+    const REDIRECT_BODY: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error>\
+        <Code>PermanentRedirect</Code>\
+        <Message>The bucket must be addressed using the specified endpoint.</Message>\
+        <Endpoint>reports.s3.eu-central-1.amazonaws.com</Endpoint>\
+        <Bucket>reports</Bucket></Error>";
+
+    fn redirect(status: u16, region_header: Option<&str>, body: &str) -> TestError {
+        let mut response = Response::new(
+            StatusCode::try_from(status).unwrap(),
+            SdkBody::from(body.to_string()),
+        );
+        if let Some(region) = region_header {
+            response
+                .headers_mut()
+                .insert(BUCKET_REGION_HEADER, region.to_string());
+        }
+        TestError::service_error(
+            HeadObjectError::generic(ErrorMetadata::builder().code("PermanentRedirect").build()),
+            response,
+        )
+    }
+
+    fn not_found() -> TestError {
+        TestError::service_error(
+            HeadObjectError::generic(ErrorMetadata::builder().code("NotFound").build()),
+            Response::new(StatusCode::try_from(404).unwrap(), SdkBody::empty()),
+        )
+    }
+
+    #[test]
+    fn region_is_taken_from_the_header_then_the_endpoint() {
+        let err = redirect(301, Some("eu-west-3"), REDIRECT_BODY);
+        assert_eq!(
+            region_redirect(&err),
+            Some(RegionRedirect {
+                region: Some("eu-west-3".into()),
+                endpoint: Some("reports.s3.eu-central-1.amazonaws.com".into()),
+            })
+        );
+        let err = redirect(301, None, REDIRECT_BODY);
+        let found = region_redirect(&err).unwrap();
+        assert_eq!(found.region, Some("eu-central-1".into()));
+        assert_eq!(
+            found.describe("us-east-1"),
+            "bucket is in eu-central-1; this account is configured for us-east-1"
+        );
+        assert_eq!(region_redirect(&not_found()), None);
+    }
+
+    #[test]
+    fn endpoints_without_a_region_are_named_in_the_message() {
+        let body = "<Error><Code>PermanentRedirect</Code>\
+            <Endpoint>reports.s3.amazonaws.com</Endpoint></Error>";
+        let found = region_redirect(&redirect(301, None, body)).unwrap();
+        assert_eq!(found.region, None);
+        assert_eq!(
+            found.describe("us-east-1"),
+            "bucket has to be accessed through reports.s3.amazonaws.com; \
+             this account is configured for us-east-1"
+        );
+        assert_eq!(
+            region_of_endpoint("s3-ap-southeast-2.amazonaws.com"),
+            Some("ap-southeast-2".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn bucket_location_is_looked_up_once_per_bucket() {
+        let regions = BucketRegions::default();
+        let lookups = Mutex::new(Vec::new());
+        let lookup = |bucket: &'static str| {
+            let lookups = &lookups;
+            move || async move {
+                lookups.lock().unwrap().push(bucket);
+                Ok(format!("{}-region", bucket))
+            }
+        };
+        for bucket in ["logs", "reports", "logs", "logs", "reports"] {
+            let region = regions.get_or_lookup(bucket, lookup(bucket)).await.unwrap();
+            assert_eq!(region, format!("{}-region", bucket));
+        }
+        assert_eq!(*lookups.lock().unwrap(), vec!["logs", "reports"]);
+
+        // a failed lookup isn't kept and a deleted bucket is looked up again
+        let failed = regions
+            .get_or_lookup("missing", || async { Err(eyre::eyre!("NoSuchBucket")) })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(regions.get("missing"), None);
+        regions.forget("logs");
+        regions.get_or_lookup("logs", lookup("logs")).await.unwrap();
+        assert_eq!(*lookups.lock().unwrap(), vec!["logs", "reports", "logs"]);
+    }
+
+    #[tokio::test]
+    async fn redirected_request_is_retried_once_in_the_bucket_region() {
+        let regions = BucketRegions::default();
+        let mut attempts = Vec::new();
+        let (result, region) = follow_redirect(&regions, "reports", "us-east-1".into(), |r| {
+            attempts.push(r.clone());
+            async move {
+                match r.as_str() {
+                    "eu-central-1" => Ok(42),
+                    _ => Err(redirect(301, Some("eu-central-1"), REDIRECT_BODY)),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(region, "eu-central-1");
+        assert_eq!(attempts, vec!["us-east-1", "eu-central-1"]);
+        assert_eq!(regions.get("reports"), Some("eu-central-1".into()));
+    }
+
+    #[tokio::test]
+    async fn other_errors_and_repeated_redirects_are_not_retried() {
+        let regions = BucketRegions::default();
+        let mut attempts = 0;
+        let (result, _) = follow_redirect(&regions, "reports", "us-east-1".into(), |_| {
+            attempts += 1;
+            async { Err::<u8, _>(not_found()) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+
+        // the bucket region keeps redirecting, the second redirect is returned
+        let mut attempts = 0;
+        let (result, region) = follow_redirect(&regions, "logs", "us-east-1".into(), |_| {
+            attempts += 1;
+            async { Err::<u8, _>(redirect(301, Some("eu-west-1"), REDIRECT_BODY)) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!((attempts, region.as_str()), (2, "eu-west-1"));
+        assert_eq!(regions.get("reports"), None);
+    }
+}
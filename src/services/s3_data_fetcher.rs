@@ -1,14 +1,56 @@
+use crate::model::file_comparison::{
+    first_step, hash_verdict, sampled_verdict, ComparisonMethod, ComparisonStep, FileComparison,
+};
+use crate::model::folder_name::folder_key;
 use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::object_copy::{
+    copy_part_ranges, ObjectCopy, COPY_PART_SIZE, MAX_SINGLE_COPY_SIZE,
+};
+use crate::model::object_lock::ObjectLockInfo;
+use crate::model::object_metadata::ObjectMetadata;
+use crate::model::object_preview::ObjectPreview;
 use crate::model::s3_data_item::{BucketInfo, FileInfo, S3DataItem};
 use crate::model::s3_selected_item::S3SelectedItem;
+use crate::model::verification::{
+    check_etag, check_sha256, etag_is_checkable, DownloadCheck, UploadVerification, Verification,
+};
+use crate::services::bucket_location::{custom_endpoint, BucketLocation, S3Provider};
+use crate::services::checksum::{
+    base64_to_hex, file_md5, file_multipart_etag, file_sha256, read_file_samples, sample_ranges,
+    Md5Hasher, Sha256Hasher, SAMPLE_SIZE,
+};
+#[cfg(feature = "fault-injection")]
+use crate::services::fault_injection::{self, FaultConfig, Operation};
+use crate::services::folder_markers::FolderMarkers;
+use crate::services::mtime::{download_mtime, encode_mtime, set_file_mtime, MTIME_METADATA_KEY};
+use crate::services::object_cache::{CachedObject, ContentKey, ObjectCache};
+use crate::services::object_text::{check_text_size, text_of};
+use crate::services::partial_download::{ObjectVersion, PartialDownload};
+use crate::services::profile_credentials::{check_profile, identity_cache, profile_provider};
+use crate::services::region_redirect::{follow_redirect, region_redirect, BucketRegions};
+use crate::services::s3_errors::{
+    describe_object_lock_error, describe_storage_class_error, describe_upload_policy_error,
+    ErrorClass, S3Error,
+};
+use crate::services::s3_keys::api_key;
+use crate::services::s3_listing::{list_pages, ListedObject, ListedPage};
+use crate::services::selection_limit::{collect_selection, LimitedSelection, SelectionLimit};
+use crate::services::task_registry::InFlightRequests;
+use crate::services::tls::{self, TlsSettings};
+use crate::services::transfer_persistence::{modified_millis, ResumableUpload, ResumableUploads};
+use crate::settings::app_config::AppConfig;
+use crate::settings::encryption::UploadEncryption;
 use crate::settings::file_credentials::FileCredential;
+use crate::settings::proxy::{redact_proxy_url, resolve_proxy, ProxyOverride, ProxySettings};
+use crate::settings::transfer_tuning::{byte_ranges, TransferTuning, TuningOverride, MAX_PARTS};
+use aws_sdk_s3::config::http::HttpResponse;
 use aws_sdk_s3::config::{Credentials, Region};
 use aws_smithy_runtime_api::http::Request;
-use std::fs::File;
 use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 use std::{
-    convert::Infallible,
     fs,
     path::PathBuf,
     pin::Pin,
@@ -19,28 +61,145 @@ use tokio::sync::mpsc::UnboundedSender;
 use crate::model::download_progress_item::DownloadProgressItem;
 use crate::model::upload_progress_item::UploadProgressItem;
 use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_s3::types::{BucketLocationConstraint, CreateBucketConfiguration};
+use aws_config::timeout::TimeoutConfig;
+use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::types::{
+    BucketLocationConstraint, ChecksumMode, CompletedMultipartUpload, CompletedPart,
+    CreateBucketConfiguration, Delete, MetadataDirective, Object, ObjectIdentifier,
+    ServerSideEncryption, StorageClass,
+};
 use aws_sdk_s3::{
-    primitives::{ByteStream, SdkBody},
+    primitives::{ByteStream, Length, SdkBody},
     Client,
 };
+use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
+use aws_smithy_runtime_api::client::http::SharedHttpClient;
+use aws_smithy_types::date_time::Format as DateTimeFormat;
 use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+use aws_smithy_types::DateTime;
 use bytes::Bytes;
 use color_eyre::{eyre, Report};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
 use http_body::{Body, SizeHint};
+use hyper_proxy::{Intercept, Proxy, ProxyConnector};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use url::Url;
+
+/// Characters escaped in the key of the copy source, the path separator is kept
+const COPY_SOURCE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Code of the service error of the requests to a multipart upload which doesn't exist
+const NO_SUCH_UPLOAD: &str = "NoSuchUpload";
+
+/// Most keys accepted by a single DeleteObjects request
+const DELETE_OBJECTS_BATCH: usize = 1000;
 
 /// Handles interactions with the s3 services through AWS sdk
 #[derive(Clone)]
 pub struct S3DataFetcher {
     pub default_region: String,
+    /// Name of the credential, the clients of the other regions keep its tls settings
+    account: String,
     credentials: Credentials,
+    /// Profile of the aws configuration giving the credentials instead of the keys
+    profile: Option<String>,
+    connect_timeout: Duration,
+    operation_timeout: Duration,
+    /// Proxy settings of the credential, used also for the per bucket clients
+    proxy: ProxyOverride,
+    /// Endpoint of the credential, the environment variables are used without one
+    endpoint_url: Option<String>,
+    /// Proxy settings from the config file, used when the credential has none
+    app_proxy: ProxyOverride,
+    /// Root certificates and skipped verification of the credential
+    tls: TlsSettings,
+    /// Accounts whose `insecure_skip_tls_verify` was confirmed, for the clients of the
+    /// credentials of the transfers
+    skip_verify_confirmed: HashSet<String>,
+    /// Keys of the credential are listed with a leading slash, see `s3_keys`
+    leading_slash_keys: bool,
+    /// Counts the calls running against s3, shown on the status line
+    in_flight: InFlightRequests,
+    /// Keeps the modification time of the transferred files, see `mtime`
+    preserve_mtime: bool,
+    /// Check of the uploaded objects, see `verification`
+    upload_verification: UploadVerification,
+    /// Check of the downloaded files, see `verify_downloads`
+    verify_downloads: bool,
+    /// Regions of the buckets which redirected a request, see `region_redirect`
+    bucket_regions: BucketRegions,
+    /// Transfer tuning from the config file, see `transfer_tuning`
+    app_tuning: TuningOverride,
+    /// Markers checked by the uploads, `None` unless `create_folder_markers` is set
+    folder_markers: Option<FolderMarkers>,
+    /// Contents read for the preview and the clipboard, see `object_cache`
+    object_cache: ObjectCache,
+    /// Saved parts of the multipart uploads, `None` unless shared by the state store
+    resumable_uploads: Option<ResumableUploads>,
+    /// Failures injected into the calls, see `fault_injection`
+    #[cfg(feature = "fault-injection")]
+    faults: Option<FaultConfig>,
+}
+
+/// Result of a download with the values s3 returned for the object
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadedObject {
+    pub bytes: u64,
+    pub etag: Option<String>,
+    /// Additional checksum stored with the object, e.g. `sha256:...`
+    pub checksum: Option<String>,
+    /// The verification is `None` when it's turned off
+    pub verification: Option<Verification>,
+}
+
+/// Result of an upload, the verification is `None` when it's turned off
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadedObject {
+    pub bytes: u64,
+    pub verification: Option<Verification>,
+}
+
+/// ETag and server side encryption s3 returned for the uploaded object
+struct StoredObject {
+    etag: Option<String>,
+    encryption: Option<String>,
+}
+
+/// Http client sending every request through the proxy, `CONNECT` tunnels are used for https.
+/// Credentials in the proxy url are sent as basic proxy authorization
+fn proxied_http_client(proxy_url: &str) -> eyre::Result<SharedHttpClient> {
+    let url = Url::parse(proxy_url)?;
+    let mut proxy = Proxy::new(Intercept::All, proxy_url.parse::<hyper::Uri>()?);
+    if !url.username().is_empty() {
+        let decode = |value: &str| percent_decode_str(value).decode_utf8_lossy().into_owned();
+        proxy.set_authorization(headers::Authorization::basic(
+            &decode(url.username()),
+            &decode(url.password().unwrap_or_default()),
+        ));
+    }
+    let https = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    let connector = ProxyConnector::from_proxy(https, proxy)?;
+    Ok(HyperClientBuilder::new().build(connector))
 }
 
 struct ProgressTracker {
     bytes_written: u64,
     content_length: u64,
     progress_sender: UnboundedSender<UploadProgressItem>,
-    uri: String,
+    bucket: String,
+    key: String,
 }
 
 impl ProgressTracker {
@@ -49,7 +208,10 @@ impl ProgressTracker {
         let progress = self.bytes_written as f64 / self.content_length as f64;
         let progress_item = UploadProgressItem {
             progress: progress * 100.0,
-            uri: self.uri.clone(),
+            bucket: self.bucket.clone(),
+            key: self.key.clone(),
+            bytes: self.bytes_written,
+            total_bytes: self.content_length,
         };
         let _ = self.progress_sender.send(progress_item);
     }
@@ -73,13 +235,17 @@ impl ProgressBody<SdkBody> {
     // this "change the wheels on the fly" utility.
     pub fn replace(
         value: Request<SdkBody>,
+        bucket: String,
+        key: String,
         tx: UnboundedSender<UploadProgressItem>,
-    ) -> Result<Request<SdkBody>, Infallible> {
-        let uri = value.uri().to_string();
+    ) -> eyre::Result<Request<SdkBody>> {
+        // the progress is reported against the size, a body without one fails the request
+        let len = value
+            .body()
+            .content_length()
+            .ok_or_else(|| eyre::eyre!("The size of the upload body of {} is unknown", key))?;
         let value = value.map(|body| {
-            let len = body.content_length().expect("upload body sized");
-            let cloned_uri = uri.clone();
-            let body = ProgressBody::new(body, len, cloned_uri, tx.clone());
+            let body = ProgressBody::new(body, len, bucket, key, tx);
             SdkBody::from_body_0_4(body)
         });
         Ok(value)
@@ -93,7 +259,8 @@ impl<InnerBody> ProgressBody<InnerBody>
     pub fn new(
         body: InnerBody,
         content_length: u64,
-        uri: String,
+        bucket: String,
+        key: String,
         tx: UnboundedSender<UploadProgressItem>,
     ) -> Self {
         Self {
@@ -102,7 +269,8 @@ impl<InnerBody> ProgressBody<InnerBody>
                 bytes_written: 0,
                 content_length,
                 progress_sender: tx,
-                uri: uri.to_string(),
+                bucket,
+                key,
             },
         }
     }
@@ -157,10 +325,22 @@ impl<InnerBody> Body for ProgressBody<InnerBody>
  */
 
 impl S3DataFetcher {
-    pub fn new(creds: FileCredential) -> Self {
+    pub fn new(
+        creds: FileCredential,
+        app_config: &AppConfig,
+        in_flight: InFlightRequests,
+        bucket_regions: BucketRegions,
+        skip_verify_confirmed: &HashSet<String>,
+    ) -> Self {
+        let tls = TlsSettings::of(&creds, skip_verify_confirmed);
+        let account = creds.name;
+        let proxy = creds.proxy;
+        let endpoint_url = creds.endpoint_url;
+        let leading_slash_keys = creds.leading_slash_keys;
         let access_key = creds.access_key;
         let secret_access_key = creds.secret_key;
         let default_region = creds.default_region;
+        let profile = creds.profile;
         let credentials = Credentials::new(
             access_key,
             secret_access_key,
@@ -170,50 +350,532 @@ impl S3DataFetcher {
         );
         S3DataFetcher {
             default_region,
+            account,
             credentials,
+            profile,
+            connect_timeout: app_config.connect_timeout,
+            operation_timeout: app_config.operation_timeout,
+            proxy,
+            endpoint_url,
+            app_proxy: app_config.proxy.clone(),
+            tls,
+            skip_verify_confirmed: skip_verify_confirmed.clone(),
+            leading_slash_keys,
+            in_flight,
+            preserve_mtime: app_config.preserve_mtime,
+            upload_verification: app_config.upload_verification,
+            verify_downloads: app_config.verify_downloads,
+            bucket_regions,
+            app_tuning: app_config.transfer_tuning.clone(),
+            folder_markers: app_config
+                .create_folder_markers
+                .then(FolderMarkers::default),
+            object_cache: ObjectCache::in_temp_dir(),
+            resumable_uploads: None,
+            #[cfg(feature = "fault-injection")]
+            faults: FaultConfig::from_env(),
+        }
+    }
+
+    /// Shares the folder markers already checked with the other fetchers of the session,
+    /// nothing changes when the markers aren't created
+    pub fn sharing_folder_markers(self, markers: &FolderMarkers) -> Self {
+        S3DataFetcher {
+            folder_markers: self.folder_markers.map(|_| markers.clone()),
+            ..self
+        }
+    }
+
+    /// Saves the parts of the multipart uploads so an interrupted session can resume them
+    pub fn sharing_resumable_uploads(self, uploads: &ResumableUploads) -> Self {
+        S3DataFetcher {
+            resumable_uploads: Some(uploads.clone()),
+            ..self
+        }
+    }
+
+    /// Applies the faults injected into the call, returns the number of bytes after which
+    /// its transfer breaks
+    #[cfg(feature = "fault-injection")]
+    async fn inject_faults(&self, operation: Operation) -> eyre::Result<Option<u64>> {
+        match &self.faults {
+            Some(faults) => faults.before_call(operation).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Part and range sizes of the transfers of the credential
+    fn tuning_for(&self, creds: &FileCredential) -> TransferTuning {
+        TransferTuning::resolve(&creds.tuning, &self.app_tuning)
+    }
+
+    /// Key of the object on the backend for a key shown on the s3 panel
+    fn api_key(&self, key: &str) -> String {
+        api_key(key, self.leading_slash_keys)
+    }
+
+    /// Human readable description of the endpoint used in error messages
+    fn endpoint_description(&self, region: &str) -> String {
+        format!("s3.{}.amazonaws.com", region)
+    }
+
+    /// Proxy of the credential with the config file and the environment as fallbacks
+    fn proxy_settings(&self, proxy: &ProxyOverride) -> Option<ProxySettings> {
+        resolve_proxy(proxy, &self.app_proxy, |name| std::env::var(name).ok())
+    }
+
+    /// Region of the bucket learned from a redirect, otherwise the region of the credential
+    fn bucket_region(&self, bucket: &str, creds: &FileCredential) -> String {
+        self.bucket_regions
+            .get(bucket)
+            .unwrap_or(creds.default_region.clone())
+    }
+
+    /// Wraps the sdk error, connection failures of proxied requests name the proxy and
+    /// redirects name the region of the bucket
+    fn sdk_error<E>(
+        &self,
+        err: &SdkError<E, HttpResponse>,
+        region: &str,
+        proxy: &ProxyOverride,
+    ) -> S3Error
+    where
+        E: ProvideErrorMetadata + std::fmt::Display,
+    {
+        if let Some(redirect) = region_redirect(err) {
+            return S3Error {
+                class: ErrorClass::Service,
+                message: redirect.describe(&self.default_region),
+            };
         }
+        let endpoint = self.endpoint_description(region);
+        let proxy_url = self
+            .proxy_settings(proxy)
+            .and_then(|settings| settings.proxy_for(&endpoint).map(redact_proxy_url));
+        S3Error::from_sdk_error(
+            err,
+            &endpoint,
+            proxy_url.as_deref(),
+            self.connect_timeout,
+            self.operation_timeout,
+        )
     }
 
     /*
     this function handles only simple files as of now.
-    - not sure when and if necessary to use multipart uploads,
+    - files above the multipart threshold of the credential are uploaded in parts,
     - no directory handling
      */
     pub async fn upload_item(
         &self,
         item: LocalSelectedItem,
+        creds: FileCredential,
         upload_tx: UnboundedSender<UploadProgressItem>,
-    ) -> eyre::Result<bool> {
-        let client = self.get_s3_client(Some(item.s3_creds)).await;
+    ) -> eyre::Result<UploadedObject> {
+        let _request = self.in_flight.start();
+        let key = api_key(&item.destination_key(), creds.leading_slash_keys);
+        // the upload isn't sent again after a redirect, the next run goes to the learned region
+        let region = self.bucket_region(&item.destination_bucket, &creds);
+        let creds = FileCredential {
+            default_region: region.clone(),
+            ..creds
+        };
+        let local_metadata = fs::metadata(&item.path).ok();
+        let size = local_metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let modified = local_metadata.and_then(|m| m.modified().ok());
+        let mtime = modified
+            .filter(|_| self.preserve_mtime)
+            .and_then(encode_mtime);
+        #[cfg(feature = "fault-injection")]
+        if let Some(limit) = self.inject_faults(Operation::Upload).await? {
+            let _ = upload_tx.send(UploadProgressItem {
+                progress: (limit as f64 / size.max(1) as f64 * 100.0).min(100.0),
+                bucket: item.destination_bucket.clone(),
+                key: item.destination_key(),
+                bytes: limit.min(size),
+                total_bytes: size,
+            });
+            return Err(fault_injection::interrupted(limit));
+        }
+        let tuning = self.tuning_for(&creds);
+        let client = self.get_streaming_s3_client(Some(creds.clone())).await;
+        if let Some(markers) = &self.folder_markers {
+            self.create_folder_markers(markers, &client, &item, &creds)
+                .await?;
+        }
+        if size >= tuning.multipart_threshold {
+            // the upload of an interrupted session goes on with its parts
+            let resumed = match (&self.resumable_uploads, modified) {
+                (Some(uploads), Some(modified)) => {
+                    uploads.find(&item, size, modified_millis(modified))
+                }
+                _ => None,
+            };
+            let part_size = resumed
+                .as_ref()
+                .map_or_else(|| tuning.part_size_for(size), |upload| upload.part_size);
+            let parts = byte_ranges(0, size, part_size);
+            let stored = self
+                .multipart_upload(
+                    &client, &item, &creds, &key, size, mtime, modified, tuning, resumed, upload_tx,
+                )
+                .await?;
+            let verification = self
+                .verify_upload(&client, &item, &creds, &key, stored, parts)
+                .await?;
+            return Ok(UploadedObject {
+                bytes: size,
+                verification,
+            });
+        }
         let body = ByteStream::read_from()
-            .path(item.path)
+            .path(&item.path)
             // https://github.com/awslabs/aws-sdk-rust/blob/main/examples/examples/s3/src/bin/put-object-progress.rs
             // Artificially limit the buffer size to ensure the file has multiple
             // progress steps.
             // .buffer_size(2048)
             .build()
             .await?;
-        let key = if item.destination_path == "/" {
-            item.name
-        } else {
-            item.destination_path
-        }; //Self::combine_paths(Path::new(&item.destination_path), Path::new(&item.name));
-        //destination_path
-        let request = client
+        let encryption = &creds.encryption;
+        let mut request = client
             .put_object()
-            .bucket(item.destination_bucket)
-            .key(key)
+            .bucket(&item.destination_bucket)
+            .key(&key)
+            .set_server_side_encryption(encryption.sse.as_deref().map(ServerSideEncryption::from))
+            .set_ssekms_key_id(encryption.kms_key_id.clone())
             .body(body);
+        if let Some(mtime) = mtime {
+            request = request.metadata(MTIME_METADATA_KEY, mtime);
+        }
 
-        let customized = request
-            .customize()
-            .map_request(move |req| ProgressBody::<SdkBody>::replace(req, upload_tx.clone()));
+        let (bucket, destination_key) = (item.destination_bucket.clone(), item.destination_key());
+        let customized = request.customize().map_request(move |req| {
+            ProgressBody::<SdkBody>::replace(
+                req,
+                bucket.clone(),
+                destination_key.clone(),
+                upload_tx.clone(),
+            )
+        });
 
-        match customized.send().await {
-            Ok(_a) => Ok(true),
+        let stored = match customized.send().await {
+            Ok(output) => StoredObject {
+                encryption: output
+                    .server_side_encryption()
+                    .map(|sse| sse.as_str().to_string()),
+                etag: output.e_tag,
+            },
             Err(e) => {
                 tracing::error!("Upload SdkError: {:?}", e);
-                Err(Report::msg(e.into_service_error().to_string()))
+                self.bucket_regions
+                    .remember(&item.destination_bucket, &region, &e);
+                return Err(Report::new(self.upload_error(&e, &creds)));
+            }
+        };
+        let verification = self
+            .verify_upload(&client, &item, &creds, &key, stored, Vec::new())
+            .await?;
+        Ok(UploadedObject {
+            bytes: size,
+            verification,
+        })
+    }
+
+    /// Error of a rejected upload request, `AccessDenied` comes with a hint at the server side
+    /// encryption a bucket policy may require
+    fn upload_error<E>(&self, err: &SdkError<E, HttpResponse>, creds: &FileCredential) -> S3Error
+    where
+        E: ProvideErrorMetadata + std::fmt::Display,
+    {
+        let service_error = err.as_service_error();
+        let described = describe_upload_policy_error(
+            service_error.and_then(|e| e.code()),
+            service_error.and_then(|e| e.message()),
+            creds.encryption.sse.as_deref(),
+        );
+        match described {
+            Some(message) => S3Error {
+                class: ErrorClass::Service,
+                message,
+            },
+            None => self.sdk_error(err, &creds.default_region, &creds.proxy),
+        }
+    }
+
+    /// Creates the markers of the folders above the uploaded key which don't exist yet, a marker
+    /// already in the bucket is left as it is
+    async fn create_folder_markers(
+        &self,
+        markers: &FolderMarkers,
+        client: &Client,
+        item: &LocalSelectedItem,
+        creds: &FileCredential,
+    ) -> eyre::Result<()> {
+        let bucket = &item.destination_bucket;
+        let region = &creds.default_region;
+        let leading_slash = creds.leading_slash_keys;
+        let encryption = &creds.encryption;
+        let failed = |marker: &str, error: S3Error| {
+            eyre::eyre!("Cannot create the folder marker {}: {}", marker, error)
+        };
+        markers
+            .ensure(bucket, &item.destination_key(), |marker| async move {
+                let key = api_key(&marker, leading_slash);
+                match client.head_object().bucket(bucket).key(&key).send().await {
+                    Ok(_) => return Ok(()),
+                    Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {}
+                    Err(e) => return Err(failed(&marker, self.sdk_error(&e, region, &self.proxy))),
+                }
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(&key)
+                    .set_server_side_encryption(
+                        encryption.sse.as_deref().map(ServerSideEncryption::from),
+                    )
+                    .set_ssekms_key_id(encryption.kms_key_id.clone())
+                    .body(ByteStream::from_static(b""))
+                    .send()
+                    .await
+                    .map_err(|e| failed(&marker, self.upload_error(&e, creds)))?;
+                tracing::info!("Folder marker {} created in {}", marker, bucket);
+                Ok(())
+            })
+            .await
+    }
+
+    /// Checks the uploaded object against the local file as configured, `parts` are the byte
+    /// ranges of an upload in parts. A mismatch is returned as a `VerificationError`, the
+    /// object is left in the bucket
+    async fn verify_upload(
+        &self,
+        client: &Client,
+        item: &LocalSelectedItem,
+        creds: &FileCredential,
+        key: &str,
+        stored: StoredObject,
+        parts: Vec<(u64, u64)>,
+    ) -> eyre::Result<Option<Verification>> {
+        let path = PathBuf::from(&item.path);
+        let verification = match self.upload_verification {
+            UploadVerification::Off => return Ok(None),
+            UploadVerification::Etag if !etag_is_checkable(stored.encryption.as_deref()) => {
+                Verification::Skipped
+            }
+            UploadVerification::Etag => {
+                let expected = tokio::task::spawn_blocking(move || match parts.is_empty() {
+                    true => file_md5(&path),
+                    false => file_multipart_etag(&path, &parts),
+                })
+                .await??;
+                check_etag(&expected, stored.etag.as_deref())?
+            }
+            UploadVerification::FullReadback => {
+                let expected = tokio::task::spawn_blocking(move || file_sha256(&path)).await??;
+                let (region, proxy) = (&creds.default_region, &creds.proxy);
+                // the object is downloaded again, as much traffic as the upload itself
+                let mut object = client
+                    .get_object()
+                    .bucket(&item.destination_bucket)
+                    .key(key)
+                    .send()
+                    .await
+                    .map_err(|e| Report::new(self.sdk_error(&e, region, proxy)))?;
+                let mut hasher = Sha256Hasher::default();
+                while let Some(bytes) = object.body.try_next().await? {
+                    hasher.update(&bytes);
+                }
+                check_sha256(&expected, &hasher.finish())?
+            }
+        };
+        Ok(Some(verification))
+    }
+
+    /// Uploads the file in parts of the tuned size, `parts_concurrency` of them at once.
+    /// Files too big for 10,000 parts of that size get bigger parts. A `resumed` upload only
+    /// sends the parts it doesn't hold yet, in its own part size. A failed part aborts the
+    /// upload so no incomplete parts are left billed in the bucket
+    #[allow(clippy::too_many_arguments)]
+    async fn multipart_upload(
+        &self,
+        client: &Client,
+        item: &LocalSelectedItem,
+        creds: &FileCredential,
+        key: &str,
+        size: u64,
+        mtime: Option<String>,
+        modified: Option<SystemTime>,
+        tuning: TransferTuning,
+        resumed: Option<ResumableUpload>,
+        upload_tx: UnboundedSender<UploadProgressItem>,
+    ) -> eyre::Result<StoredObject> {
+        let region = &creds.default_region;
+        let proxy = &creds.proxy;
+        let bucket = item.destination_bucket.as_str();
+        let (upload_id, part_size, completed) = match resumed {
+            Some(upload) => {
+                tracing::info!(
+                    "Resuming the upload of {} after {} of its {} parts",
+                    item.path,
+                    upload.parts.len(),
+                    upload.part_count()
+                );
+                (upload.upload_id, upload.part_size, upload.parts)
+            }
+            None => {
+                let encryption = &creds.encryption;
+                let mut request = client
+                    .create_multipart_upload()
+                    .bucket(bucket)
+                    .key(key)
+                    .set_server_side_encryption(
+                        encryption.sse.as_deref().map(ServerSideEncryption::from),
+                    )
+                    .set_ssekms_key_id(encryption.kms_key_id.clone());
+                if let Some(mtime) = mtime {
+                    request = request.metadata(MTIME_METADATA_KEY, mtime);
+                }
+                let upload = request.send().await.map_err(|e| {
+                    self.bucket_regions.remember(bucket, region, &e);
+                    Report::new(self.upload_error(&e, creds))
+                })?;
+                let upload_id = upload.upload_id.unwrap_or_default();
+                let part_size = tuning.part_size_for(size);
+                if part_size != tuning.part_size {
+                    tracing::warn!(
+                        "{} needs more than {} parts of {} bytes, uploading it in parts of {} bytes",
+                        item.path,
+                        MAX_PARTS,
+                        tuning.part_size,
+                        part_size
+                    );
+                }
+                if let (Some(uploads), Some(modified)) = (&self.resumable_uploads, modified) {
+                    uploads.start(ResumableUpload {
+                        account: item.account.clone(),
+                        bucket: bucket.to_string(),
+                        key: item.destination_key(),
+                        path: item.path.clone(),
+                        size,
+                        modified: modified_millis(modified),
+                        part_size,
+                        upload_id: upload_id.clone(),
+                        parts: BTreeMap::new(),
+                    });
+                }
+                (upload_id, part_size, BTreeMap::new())
+            }
+        };
+        let ranges: Vec<(i32, (u64, u64))> = byte_ranges(0, size, part_size)
+            .into_iter()
+            .enumerate()
+            .map(|(index, range)| (index as i32 + 1, range))
+            .collect();
+        // progress of the parts is reported for the whole object
+        let destination_key = item.destination_key();
+        let uploaded = AtomicU64::new(
+            ranges
+                .iter()
+                .filter(|(part_number, _)| completed.contains_key(part_number))
+                .map(|(_, (first, last))| last - first + 1)
+                .sum(),
+        );
+        let pending = ranges
+            .into_iter()
+            .filter(|(part_number, _)| !completed.contains_key(part_number));
+        let parts = stream::iter(pending)
+            .map(|(part_number, (first, last))| {
+                let (upload_id, destination_key, uploaded, upload_tx) =
+                    (&upload_id, &destination_key, &uploaded, &upload_tx);
+                async move {
+                    let length = last - first + 1;
+                    let body = ByteStream::read_from()
+                        .path(&item.path)
+                        .offset(first)
+                        .length(Length::Exact(length))
+                        .build()
+                        .await?;
+                    let output = client
+                        .upload_part()
+                        .bucket(bucket)
+                        .key(key)
+                        .upload_id(upload_id)
+                        .part_number(part_number)
+                        .body(body)
+                        .send()
+                        .await
+                        .map_err(|e| {
+                            tracing::error!(
+                                "Cannot upload part {} of {}: {:?}",
+                                part_number,
+                                key,
+                                e
+                            );
+                            Report::new(self.upload_error(&e, creds))
+                        })?;
+                    if let (Some(uploads), Some(etag)) = (&self.resumable_uploads, output.e_tag()) {
+                        uploads.complete_part(upload_id, part_number, etag.to_string());
+                    }
+                    let done = uploaded.fetch_add(length, Ordering::Relaxed) + length;
+                    let _ = upload_tx.send(UploadProgressItem {
+                        progress: done as f64 / size as f64 * 100.0,
+                        bucket: bucket.to_string(),
+                        key: destination_key.clone(),
+                        bytes: done,
+                        total_bytes: size,
+                    });
+                    Ok::<_, Report>(
+                        CompletedPart::builder()
+                            .part_number(part_number)
+                            .set_e_tag(output.e_tag)
+                            .build(),
+                    )
+                }
+            })
+            .buffer_unordered(tuning.parts_concurrency)
+            .try_collect::<Vec<_>>()
+            .await;
+        let mut parts = match parts {
+            Ok(parts) => parts,
+            Err(e) => {
+                self.abort_multipart_upload(client, bucket, key, &upload_id)
+                    .await;
+                self.forget_upload(&upload_id);
+                return Err(e);
+            }
+        };
+        parts.extend(completed.into_iter().map(|(part_number, etag)| {
+            CompletedPart::builder()
+                .part_number(part_number)
+                .e_tag(etag)
+                .build()
+        }));
+        parts.sort_by_key(|part| part.part_number());
+        let response = client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await;
+        self.forget_upload(&upload_id);
+        match response {
+            Ok(output) => Ok(StoredObject {
+                encryption: output
+                    .server_side_encryption()
+                    .map(|sse| sse.as_str().to_string()),
+                etag: output.e_tag,
+            }),
+            Err(e) => {
+                tracing::error!("Cannot complete the upload of {}: {:?}", key, e);
+                self.abort_multipart_upload(client, bucket, key, &upload_id)
+                    .await;
+                Err(Report::new(self.sdk_error(&e, region, proxy)))
             }
         }
     }
@@ -234,47 +896,228 @@ impl S3DataFetcher {
     pub async fn download_item(
         &self,
         item: S3SelectedItem,
+        creds: FileCredential,
         download_tx: UnboundedSender<DownloadProgressItem>,
-    ) -> eyre::Result<bool> {
-        let client = self.get_s3_client(Some(item.s3_creds)).await;
-        let mut path = PathBuf::from(item.destination_dir);
-        path.push(item.path.clone().unwrap_or(item.name.clone()));
+    ) -> eyre::Result<DownloadedObject> {
+        let _request = self.in_flight.start();
+        let proxy = creds.proxy.clone();
+        let path = item.local_destination();
+        let key = api_key(
+            &item.path.clone().unwrap_or(item.name.clone()),
+            creds.leading_slash_keys,
+        );
+        #[cfg(feature = "fault-injection")]
+        let truncate_after = self.inject_faults(Operation::Download).await?;
+        let tuning = self.tuning_for(&creds);
         self.create_directory_structure(&path)?;
-        let mut file = File::create(&path)?;
-        let bucket = item.bucket.expect("bucket must be defined").clone();
-        let head_obj = client
-            .head_object()
-            .bucket(bucket.clone())
-            .key(item.path.clone().unwrap_or(item.name.clone()))
-            .send()
-            .await?;
-        match client
-            .get_object()
-            .bucket(bucket.clone())
-            .key(item.path.clone().unwrap_or(item.name.clone()))
-            .send()
-            .await
-        {
-            Ok(mut object) => {
-                let mut byte_count = 0_usize;
-                let total = head_obj.content_length.unwrap_or(0i64);
-                while let Some(bytes) = object.body.try_next().await? {
-                    let bytes_len = bytes.len();
-                    file.write_all(&bytes)?;
-                    byte_count += bytes_len;
-                    let progress = Self::calculate_download_percentage(total, byte_count);
-                    let download_progress_item = DownloadProgressItem {
-                        name: item.path.clone().unwrap_or(item.name.clone())    ,
-                        bucket: bucket.clone(),
-                        progress,
-                    };
-                    let _ = download_tx.send(download_progress_item);
+        let bucket = item.bucket.clone().expect("bucket must be defined");
+        let creds_in_region = |region: String| FileCredential {
+            default_region: region,
+            ..creds.clone()
+        };
+        // the head request has no body, a redirect to the region of the bucket is followed
+        let (head_obj, region) = follow_redirect(
+            &self.bucket_regions,
+            &bucket,
+            self.bucket_region(&bucket, &creds),
+            |region| {
+                let (bucket, key) = (bucket.clone(), key.clone());
+                let creds = creds_in_region(region);
+                async move {
+                    self.get_s3_client(Some(creds))
+                        .await
+                        .head_object()
+                        .bucket(bucket)
+                        .key(key)
+                        .checksum_mode(ChecksumMode::Enabled)
+                        .send()
+                        .await
                 }
-                Ok(true)
+            },
+        )
+        .await;
+        let head_obj = head_obj.map_err(|e| Report::new(self.sdk_error(&e, &region, &proxy)))?;
+        let client = self
+            .get_s3_client(Some(creds_in_region(region.clone())))
+            .await;
+        let total = head_obj.content_length.unwrap_or(0i64);
+        let partial = PartialDownload::new(&path);
+        let version = ObjectVersion {
+            etag: head_obj.e_tag().unwrap_or_default().to_string(),
+            size: total.max(0) as u64,
+        };
+        let offset = partial.resume_offset(&version);
+        if offset > 0 {
+            tracing::info!("Resuming the download of {} after {} bytes", key, offset);
+        }
+        let mut file = partial.open(&version, offset)?;
+        let mut chunks: Pin<Box<dyn Stream<Item = eyre::Result<Bytes>> + Send + '_>> =
+            if version.size >= tuning.ranged_download_threshold {
+                Box::pin(self.ranged_chunks(
+                    &client, &region, &proxy, &bucket, &key, &version, offset, tuning,
+                ))
+            } else {
+                // the ETag guards against the object being replaced after the head request,
+                // the appended bytes would come from another version
+                let object = client
+                    .get_object()
+                    .bucket(bucket.clone())
+                    .key(key.clone())
+                    .set_range((offset > 0).then(|| format!("bytes={}-", offset)))
+                    .set_if_match((offset > 0).then(|| version.etag.clone()))
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("Download SdkError: {:?}", e);
+                        Report::new(self.sdk_error(&e, &region, &proxy))
+                    })?;
+                let start = (object.body, offset as usize);
+                Box::pin(stream::try_unfold(
+                    start,
+                    move |(mut body, received)| async move {
+                        let bytes = body.try_next().await.map_err(|e| {
+                            Report::new(S3Error {
+                                class: ErrorClass::Connection,
+                                message: format!(
+                                    "download interrupted after {} of {} bytes: {}",
+                                    received, total, e
+                                ),
+                            })
+                        })?;
+                        Ok(bytes.map(|bytes| {
+                            let received = received + bytes.len();
+                            (bytes, (body, received))
+                        }))
+                    },
+                ))
+            };
+        #[cfg(feature = "fault-injection")]
+        if let Some(limit) = truncate_after {
+            chunks = fault_injection::truncated(chunks, limit);
+        }
+        let mut byte_count = offset as usize;
+        while let Some(bytes) = chunks.try_next().await? {
+            let bytes_len = bytes.len();
+            file.write_all(&bytes)?;
+            byte_count += bytes_len;
+            let progress = Self::calculate_download_percentage(total, byte_count);
+            let download_progress_item = DownloadProgressItem {
+                key: item.object_key().to_string(),
+                bucket: bucket.clone(),
+                progress,
+                bytes: byte_count as u64,
+                total_bytes: version.size,
+            };
+            let _ = download_tx.send(download_progress_item);
+        }
+        drop(file);
+        partial.complete()?;
+        let verification = self.verify_download(&path, &head_obj).await?;
+        if self.preserve_mtime {
+            Self::restore_mtime(&path, &head_obj);
+        }
+        Ok(DownloadedObject {
+            bytes: byte_count as u64,
+            etag: head_obj.e_tag().map(String::from),
+            checksum: Self::additional_checksum(&head_obj),
+            verification,
+        })
+    }
+
+    /// Hashes the downloaded file and compares it with the ETag of the object or its sha-256
+    /// checksum. A mismatch is returned as a `VerificationError`, the file is left in place
+    async fn verify_download(
+        &self,
+        path: &Path,
+        head: &HeadObjectOutput,
+    ) -> eyre::Result<Option<Verification>> {
+        if !self.verify_downloads {
+            return Ok(None);
+        }
+        let encryption = head.server_side_encryption().map(|sse| sse.as_str());
+        // the ETag of objects encrypted with customer keys isn't the md5 of the content either
+        let etag_checkable =
+            etag_is_checkable(encryption) && head.sse_customer_algorithm().is_none();
+        let sha256 = head.checksum_sha256().and_then(base64_to_hex);
+        let path = path.to_path_buf();
+        let verification = match DownloadCheck::new(head.e_tag(), etag_checkable, sha256) {
+            DownloadCheck::Md5(etag) => {
+                let local = tokio::task::spawn_blocking(move || file_md5(&path)).await??;
+                check_etag(&local, Some(&etag))?
             }
-            Err(e) => {
-                tracing::error!("Download SdkError: {:?}", e);
-                Err(Report::msg(e.into_service_error().to_string()))
+            DownloadCheck::Sha256(sha256) => {
+                let local = tokio::task::spawn_blocking(move || file_sha256(&path)).await??;
+                check_sha256(&local, &sha256)?
+            }
+            DownloadCheck::Unavailable => Verification::Skipped,
+        };
+        Ok(Some(verification))
+    }
+
+    /// Downloads the object after `offset` in ranges of the tuned size, `ranges_concurrency`
+    /// of them at once. The ranges come out in order so they are appended to the partial file
+    /// the way a single response is, and every range requires the version of the head request
+    #[allow(clippy::too_many_arguments)]
+    fn ranged_chunks<'a>(
+        &'a self,
+        client: &'a Client,
+        region: &'a str,
+        proxy: &'a ProxyOverride,
+        bucket: &'a str,
+        key: &'a str,
+        version: &'a ObjectVersion,
+        offset: u64,
+        tuning: TransferTuning,
+    ) -> impl Stream<Item = eyre::Result<Bytes>> + Send + 'a {
+        stream::iter(byte_ranges(offset, version.size, tuning.range_size))
+            .map(move |(first, last)| async move {
+                let object = client
+                    .get_object()
+                    .bucket(bucket)
+                    .key(key)
+                    .range(format!("bytes={}-{}", first, last))
+                    .set_if_match((!version.etag.is_empty()).then(|| version.etag.clone()))
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("Download SdkError: {:?}", e);
+                        Report::new(self.sdk_error(&e, region, proxy))
+                    })?;
+                let bytes = object.body.collect().await.map_err(|e| {
+                    Report::new(S3Error {
+                        class: ErrorClass::Connection,
+                        message: format!(
+                            "download interrupted in bytes {}-{} of {}: {}",
+                            first, last, version.size, e
+                        ),
+                    })
+                })?;
+                Ok(bytes.into_bytes())
+            })
+            .buffered(tuning.ranges_concurrency)
+    }
+
+    /// The strongest additional checksum of the object, prefixed with its algorithm.
+    /// Objects uploaded without one only have the ETag
+    fn additional_checksum(head: &HeadObjectOutput) -> Option<String> {
+        [
+            ("sha256", head.checksum_sha256()),
+            ("sha1", head.checksum_sha1()),
+            ("crc32c", head.checksum_crc32_c()),
+            ("crc32", head.checksum_crc32()),
+        ]
+        .into_iter()
+        .find_map(|(algorithm, value)| value.map(|v| format!("{}:{}", algorithm, v)))
+    }
+
+    /// A missing or unusable time leaves the file with the time of the download
+    fn restore_mtime(path: &Path, head: &HeadObjectOutput) {
+        let last_modified = head
+            .last_modified()
+            .and_then(|d| SystemTime::try_from(*d).ok());
+        if let Some(mtime) = download_mtime(head.metadata(), last_modified) {
+            if let Err(e) = set_file_mtime(path, mtime) {
+                tracing::warn!("Cannot set the modification time of {:?}: {}", path, e);
             }
         }
     }
@@ -287,62 +1130,135 @@ impl S3DataFetcher {
         }
     }
 
+    /// Lists the location of the s3 panel, handing every page to `on_batch` as soon as it
+    /// arrives. The buckets come in a single batch. At the bucket root the keys grouped
+    /// under "/" by backends listing them with a leading slash are listed after the root
     pub async fn list_current_location(
         &self,
         bucket: Option<String>,
         prefix: Option<String>,
-    ) -> eyre::Result<Vec<S3DataItem>> {
-        match (bucket, prefix) {
-            (None, None) => self.list_buckets().await,
-            (Some(bucket), None) => self.list_objects(bucket.as_str(), None).await,
-            (Some(bucket), Some(prefix)) => self.list_objects(bucket.as_str(), Some(prefix)).await,
-            _ => self.list_buckets().await,
+        mut on_batch: impl FnMut(Vec<S3DataItem>),
+    ) -> eyre::Result<()> {
+        let _request = self.in_flight.start();
+        #[cfg(feature = "fault-injection")]
+        self.inject_faults(Operation::List).await?;
+        let bucket = match bucket {
+            Some(bucket) => bucket,
+            None => {
+                if let Some(profile) = &self.profile {
+                    check_profile(profile).await?;
+                }
+                on_batch(self.list_buckets().await?);
+                return Ok(());
+            }
+        };
+        let at_root = prefix.is_none();
+        let mut slash_prefix_listed = false;
+        let batches = self.list_stream(&bucket, prefix, false);
+        tokio::pin!(batches);
+        while let Some(batch) = batches.try_next().await? {
+            let (slash_prefix, batch): (Vec<_>, Vec<_>) = batch
+                .into_iter()
+                .partition(|item| at_root && item.is_directory && item.path.is_empty());
+            slash_prefix_listed |= !slash_prefix.is_empty();
+            on_batch(batch);
+        }
+        if slash_prefix_listed {
+            let batches = self.list_raw_stream(&bucket, Some("/".to_string()), false);
+            tokio::pin!(batches);
+            while let Some(batch) = batches.try_next().await? {
+                on_batch(batch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetcher for the account health check, its requests are given up after the short
+    /// connect timeout so an endpoint that can't be reached fails the check quickly
+    pub fn for_health_check(mut self) -> Self {
+        self.operation_timeout = self.connect_timeout;
+        self
+    }
+
+    /// Lists the buckets of the account to tell whether its credentials work
+    pub async fn check_access(&self) -> eyre::Result<()> {
+        let _request = self.in_flight.start();
+        if let Some(profile) = &self.profile {
+            check_profile(profile).await?;
         }
+        self.list_buckets().await.map(|_| ())
     }
 
+    /// Region of the bucket, GetBucketLocation is sent once per bucket
     async fn get_bucket_location(&self, bucket: &str) -> eyre::Result<String> {
-        let default_region = self.default_region.clone();
-        let client = self.get_s3_client(None).await;
-        let head_obj = client.get_bucket_location().bucket(bucket).send().await?;
-        let location = head_obj
-            .location_constraint()
-            .map(|lc| lc.to_string())
-            .unwrap_or_else(|| default_region.to_string());
-        Ok(location)
+        self.bucket_regions
+            .get_or_lookup(bucket, || async {
+                let default_region = self.default_region.clone();
+                let client = self.get_s3_client(None).await;
+                let head_obj = client
+                    .get_bucket_location()
+                    .bucket(bucket)
+                    .send()
+                    .await
+                    .map_err(|e| Report::new(self.sdk_error(&e, &default_region, &self.proxy)))?;
+                let location = head_obj
+                    .location_constraint()
+                    .map(|lc| lc.to_string())
+                    .unwrap_or_else(|| default_region.to_string());
+                Ok::<_, Report>(location)
+            })
+            .await
+    }
+
+    /// Region of a bucket of the bucket list, asked once and then taken from `bucket_regions`
+    pub async fn bucket_location(&self, bucket: &str) -> eyre::Result<String> {
+        let _request = self.in_flight.start();
+        self.get_bucket_location(bucket).await
     }
 
     // Example async method to fetch data from an external service
     async fn list_buckets(&self) -> eyre::Result<Vec<S3DataItem>> {
         let client = self.get_s3_client(None).await;
-        let mut fetched_data: Vec<S3DataItem> = vec![];
-        if let Ok(res) = client.list_buckets().send().await {
-            fetched_data = res.buckets.as_ref().map_or_else(
-                Vec::new, // In case there is no buckets field (it's None), return an empty Vec
-                |buckets| {
-                    buckets
-                        .iter()
-                        .filter_map(|bucket| {
-                            // Filter out buckets where name is None, and map those with a name to a Vec<String>
-                            bucket.name.as_ref().map(|name| {
-                                let file_info = FileInfo {
-                                    file_name: name.clone(),
-                                    size: "".to_string(),
-                                    file_type: "Bucket".to_string(),
-                                    path: name.clone(),
-                                    is_directory: false,
-                                };
-                                let bucket_info = BucketInfo {
-                                    bucket: None,
-                                    region: None,
-                                    is_bucket: true,
-                                };
-                                S3DataItem::init(bucket_info, file_info)
-                            })
+        let res = client.list_buckets().send().await.map_err(|e| {
+            tracing::error!("Cannot list buckets: {:?}", e);
+            Report::new(self.sdk_error(&e, &self.default_region, &self.proxy))
+        })?;
+        let fetched_data = res.buckets.as_ref().map_or_else(
+            Vec::new, // In case there is no buckets field (it's None), return an empty Vec
+            |buckets| {
+                buckets
+                    .iter()
+                    .filter_map(|bucket| {
+                        // Filter out buckets where name is None, and map those with a name to a Vec<String>
+                        bucket.name.as_ref().map(|name| {
+                            // AWS sends the regions with the list, the other backends leave
+                            // them to be resolved after the list is shown
+                            if let Some(region) = bucket.bucket_region() {
+                                self.bucket_regions.set(name, region);
+                            }
+                            let file_info = FileInfo {
+                                file_name: name.clone(),
+                                size: "".to_string(),
+                                file_type: "Bucket".to_string(),
+                                path: name.clone(),
+                                is_directory: false,
+                                last_modified: None,
+                                storage_class: None,
+                            };
+                            let bucket_info = BucketInfo {
+                                bucket: None,
+                                region: self.bucket_regions.get(name),
+                                is_bucket: true,
+                                creation_date: bucket
+                                    .creation_date()
+                                    .and_then(|d| d.fmt(DateTimeFormat::DateTime).ok()),
+                            };
+                            S3DataItem::init(bucket_info, file_info)
                         })
-                        .collect()
-                },
-            )
-        }
+                    })
+                    .collect()
+            },
+        );
         Ok(fetched_data)
     }
 
@@ -351,20 +1267,37 @@ impl S3DataFetcher {
         name: String,
         region: String,
     ) -> eyre::Result<Option<String>> {
-        let client = self.get_s3_client(None).await;
-        let constraint = BucketLocationConstraint::from(region.as_str());
-        let cfg = CreateBucketConfiguration::builder()
-            .location_constraint(constraint)
-            .build();
+        let _request = self.in_flight.start();
+        let endpoint = self
+            .endpoint_url
+            .clone()
+            .or_else(|| custom_endpoint(|name| std::env::var(name).ok()));
+        let location = BucketLocation::new(S3Provider::from_endpoint(endpoint.as_deref()), &region);
+        tracing::info!("Creating bucket {} {}", name, location.describe());
+        let (client, cfg) = match location {
+            BucketLocation::Constraint(region) => {
+                let constraint = BucketLocationConstraint::from(region.as_str());
+                let cfg = CreateBucketConfiguration::builder()
+                    .location_constraint(constraint)
+                    .build();
+                (self.get_s3_client(None).await, Some(cfg))
+            }
+            BucketLocation::Omitted => (self.get_s3_client(None).await, None),
+            BucketLocation::SignedIn(region) => {
+                let client = self.get_s3_client(Some(self.creds_in_region(region))).await;
+                (client, None)
+            }
+        };
         match client
             .create_bucket()
-            .create_bucket_configuration(cfg)
+            .set_create_bucket_configuration(cfg)
             .bucket(name.clone())
             .send()
             .await
         {
             Ok(_) => {
                 tracing::info!("Bucket created");
+                self.bucket_regions.forget(&name);
                 Ok(None)
             }
             Err(e) => {
@@ -379,6 +1312,43 @@ impl S3DataFetcher {
         }
     }
 
+    /// Creates the folder as a zero-byte object with a key ending with '/', see `folder_key`
+    pub async fn create_folder(
+        &self,
+        bucket: String,
+        prefix: Option<String>,
+        name: String,
+    ) -> eyre::Result<Option<String>> {
+        let key = match folder_key(prefix.as_deref(), &name) {
+            Ok(key) => key,
+            Err(error) => return Ok(Some(error)),
+        };
+        let _request = self.in_flight.start();
+        let (client, _) = self.get_s3_client_for_bucket(&bucket).await?;
+        match client
+            .put_object()
+            .bucket(bucket)
+            .key(self.api_key(&key))
+            .body(ByteStream::from_static(b""))
+            .send()
+            .await
+        {
+            Ok(_) => {
+                tracing::info!("Folder {} created", key);
+                Ok(None)
+            }
+            Err(e) => {
+                tracing::error!("Cannot create folder {}: {:?}", key, e);
+                Ok(Some(
+                    e.into_service_error()
+                        .message()
+                        .unwrap_or("Cannot create folder")
+                        .to_string(),
+                ))
+            }
+        }
+    }
+
     pub async fn delete_data(
         &self,
         is_bucket: bool,
@@ -386,15 +1356,27 @@ impl S3DataFetcher {
         name: String,
         _is_directory: bool,
     ) -> eyre::Result<Option<String>> {
+        let _request = self.in_flight.start();
+        #[cfg(feature = "fault-injection")]
+        self.inject_faults(Operation::Delete).await?;
         if is_bucket {
             let location = self.get_bucket_location(&name).await?;
             let creds = self.credentials.clone();
             let temp_file_creds = FileCredential {
-                name: "temp".to_string(),
+                name: self.account.clone(),
                 access_key: creds.access_key_id().to_string(),
                 secret_key: creds.secret_access_key().to_string(),
                 default_region: location.clone(),
                 selected: false,
+                proxy: self.proxy.clone(),
+                leading_slash_keys: self.leading_slash_keys,
+                tuning: TuningOverride::default(),
+                default_download_dir: None,
+                profile: self.profile.clone(),
+                ca_bundle_path: self.tls.ca_bundle_path.clone(),
+                insecure_skip_tls_verify: self.tls.skip_verify,
+                encryption: UploadEncryption::default(),
+                endpoint_url: self.endpoint_url.clone(),
             };
             let client_with_location = self.get_s3_client(Some(temp_file_creds)).await;
             let response = client_with_location
@@ -405,15 +1387,20 @@ impl S3DataFetcher {
             match response {
                 Ok(_) => {
                     tracing::info!("bucket deleted: {}", name);
+                    self.bucket_regions.forget(&name);
                     Ok(None)
                 }
                 Err(e) => {
                     tracing::error!("error deleting bucket: {}, {:?}", name, e);
+                    let service_error = e.into_service_error();
                     Ok(Some(
-                        e.into_service_error()
-                            .message()
-                            .unwrap_or("Error deleting bucket")
-                            .to_string(),
+                        describe_object_lock_error(service_error.code(), service_error.message())
+                            .unwrap_or(
+                                service_error
+                                    .message()
+                                    .unwrap_or("Error deleting bucket")
+                                    .to_string(),
+                            ),
                     ))
                 }
             }
@@ -430,16 +1417,25 @@ impl S3DataFetcher {
         let location = self.get_bucket_location(bucket).await?;
         let creds = self.credentials.clone();
         let temp_file_creds = FileCredential {
-            name: "temp".to_string(),
+            name: self.account.clone(),
             access_key: creds.access_key_id().to_string(),
             secret_key: creds.secret_access_key().to_string(),
             default_region: location.clone(),
             selected: false,
+            proxy: self.proxy.clone(),
+            leading_slash_keys: self.leading_slash_keys,
+            tuning: TuningOverride::default(),
+            default_download_dir: None,
+            profile: self.profile.clone(),
+            ca_bundle_path: self.tls.ca_bundle_path.clone(),
+            insecure_skip_tls_verify: self.tls.skip_verify,
+            encryption: UploadEncryption::default(),
+            endpoint_url: self.endpoint_url.clone(),
         };
         let client_with_location = self.get_s3_client(Some(temp_file_creds)).await;
         let response = client_with_location
             .delete_object()
-            .key(name)
+            .key(self.api_key(name))
             .bucket(bucket)
             .send()
             .await;
@@ -455,208 +1451,1010 @@ impl S3DataFetcher {
                     name,
                     e
                 );
-                Ok(Some(format!(
-                    "Cannot delete object, {:?}",
-                    e.into_service_error().message().unwrap_or("")
-                )))
+                let service_error = e.into_service_error();
+                Ok(Some(
+                    describe_object_lock_error(service_error.code(), service_error.message())
+                        .unwrap_or(format!(
+                            "Cannot delete object, {:?}",
+                            service_error.message().unwrap_or("")
+                        )),
+                ))
             }
         }
     }
 
-    /// Lists all object in the given bucket (or filtered by prefix) and constructs the items
-    /// representing directories
-    /// This method is used for displaying bucket/prefix content while browsing s3 and
-    /// it's not fetching all the contents behind prefixes together
-    async fn list_objects(
+    /// Deletes the objects of the bucket with DeleteObjects, 1000 keys per request.
+    /// `on_batch` gets the number of keys of every finished request.
+    /// Returns the keys which could not be deleted together with the reason
+    pub async fn delete_objects(
         &self,
         bucket: &str,
-        prefix: Option<String>,
-    ) -> eyre::Result<Vec<S3DataItem>> {
-        let mut all_objects = Vec::new();
+        keys: Vec<String>,
+        mut on_batch: impl FnMut(usize),
+    ) -> eyre::Result<Vec<(String, String)>> {
+        let _request = self.in_flight.start();
+        #[cfg(feature = "fault-injection")]
+        self.inject_faults(Operation::Delete).await?;
+        let (client, _) = self.get_s3_client_for_bucket(bucket).await?;
+        let mut failed = Vec::new();
+        for chunk in keys.chunks(DELETE_OBJECTS_BATCH) {
+            let api_keys: HashMap<String, &String> =
+                chunk.iter().map(|key| (self.api_key(key), key)).collect();
+            let objects = api_keys
+                .keys()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<Result<Vec<_>, _>>()?;
+            let delete = Delete::builder()
+                .set_objects(Some(objects))
+                .quiet(true)
+                .build()?;
+            match client
+                .delete_objects()
+                .bucket(bucket)
+                .delete(delete)
+                .send()
+                .await
+            {
+                Ok(output) => {
+                    for error in output.errors() {
+                        let api_key = error.key().unwrap_or_default();
+                        let key = api_keys
+                            .get(api_key)
+                            .map(|key| key.to_string())
+                            .unwrap_or(api_key.to_string());
+                        let reason = describe_object_lock_error(error.code(), error.message())
+                            .unwrap_or(error.message().unwrap_or("Cannot delete").to_string());
+                        failed.push((key, reason));
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Cannot delete objects in bucket {}: {:?}", bucket, e);
+                    let service_error = e.into_service_error();
+                    let reason = service_error
+                        .message()
+                        .unwrap_or("Cannot delete objects")
+                        .to_string();
+                    failed.extend(chunk.iter().map(|key| (key.clone(), reason.clone())));
+                }
+            }
+            on_batch(chunk.len());
+        }
+        tracing::info!(
+            "Deleted {} objects in bucket {}, {} failed",
+            keys.len() - failed.len(),
+            bucket,
+            failed.len()
+        );
+        Ok(failed)
+    }
+
+    async fn get_s3_client_for_bucket(&self, bucket: &str) -> eyre::Result<(Client, String)> {
         let location = self.get_bucket_location(bucket).await?;
+        let client = self
+            .get_s3_client(Some(self.creds_in_region(&location)))
+            .await;
+        Ok((client, location))
+    }
+
+    /// The credential of the fetcher signing its requests for another region
+    fn creds_in_region(&self, region: &str) -> FileCredential {
         let creds = self.credentials.clone();
-        let temp_file_creds = FileCredential {
-            name: "temp".to_string(),
+        FileCredential {
+            name: self.account.clone(),
             access_key: creds.access_key_id().to_string(),
             secret_key: creds.secret_access_key().to_string(),
-            default_region: location.clone(),
+            default_region: region.to_string(),
             selected: false,
+            proxy: self.proxy.clone(),
+            leading_slash_keys: self.leading_slash_keys,
+            tuning: TuningOverride::default(),
+            default_download_dir: None,
+            profile: self.profile.clone(),
+            ca_bundle_path: self.tls.ca_bundle_path.clone(),
+            insecure_skip_tls_verify: self.tls.skip_verify,
+            encryption: UploadEncryption::default(),
+            endpoint_url: self.endpoint_url.clone(),
+        }
+    }
+
+    /// Reads the metadata of the object, including the user defined `x-amz-meta-*` pairs
+    pub async fn head_object_metadata(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> eyre::Result<ObjectMetadata> {
+        let _request = self.in_flight.start();
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        match client
+            .head_object()
+            .bucket(bucket)
+            .key(self.api_key(key))
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Self::object_metadata_from_head(bucket, key, &output)),
+            Err(e) => {
+                tracing::error!("Cannot read object metadata: {:?}", e);
+                Err(Report::new(self.sdk_error(&e, &location, &self.proxy)))
+            }
+        }
+    }
+
+    fn object_metadata_from_head(
+        bucket: &str,
+        key: &str,
+        output: &HeadObjectOutput,
+    ) -> ObjectMetadata {
+        let mut user_metadata: Vec<(String, String)> = output
+            .metadata()
+            .map(|m| m.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        user_metadata.sort();
+        ObjectMetadata {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            content_type: output.content_type().map(String::from),
+            content_encoding: output.content_encoding().map(String::from),
+            content_disposition: output.content_disposition().map(String::from),
+            cache_control: output.cache_control().map(String::from),
+            storage_class: output.storage_class().map(|c| c.as_str().to_string()),
+            server_side_encryption: output.server_side_encryption().map(|sse| {
+                match output.ssekms_key_id() {
+                    Some(key_id) => format!("{} ({})", sse.as_str(), key_id),
+                    None => sse.as_str().to_string(),
+                }
+            }),
+            e_tag: output.e_tag().map(String::from),
+            last_modified: output
+                .last_modified()
+                .and_then(|d| d.fmt(DateTimeFormat::DateTime).ok()),
+            lock: Self::object_lock_from_head(output),
+            user_metadata,
+        }
+    }
+
+    fn object_lock_from_head(output: &HeadObjectOutput) -> ObjectLockInfo {
+        ObjectLockInfo::from_head(
+            output.object_lock_mode().map(|m| m.as_str()),
+            output.object_lock_retain_until_date(),
+            output.object_lock_legal_hold_status().map(|s| s.as_str()),
+            DateTime::from(std::time::SystemTime::now()),
+        )
+    }
+
+    /// Reads the retention and legal hold of the object. Credentials without the permission
+    /// to read them get no lock headers, such object is reported as not locked
+    pub async fn get_object_lock_info(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> eyre::Result<ObjectLockInfo> {
+        let _request = self.in_flight.start();
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        match client
+            .head_object()
+            .bucket(bucket)
+            .key(self.api_key(key))
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Self::object_lock_from_head(&output)),
+            Err(e) => {
+                tracing::error!("Cannot read object lock status: {:?}", e);
+                Err(Report::new(self.sdk_error(&e, &location, &self.proxy)))
+            }
+        }
+    }
+
+    /// Compares the object with the local file, from the cheapest check to the most expensive:
+    /// the sizes, the ETag when it's a plain md5 and finally the content. Without `full_hash`
+    /// only the first and the last bytes of the object are downloaded
+    pub async fn compare_with_local(
+        &self,
+        bucket: &str,
+        key: &str,
+        local_path: &str,
+        full_hash: bool,
+    ) -> eyre::Result<FileComparison> {
+        let _request = self.in_flight.start();
+        let started = Instant::now();
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        let head = client
+            .head_object()
+            .bucket(bucket)
+            .key(self.api_key(key))
+            .send()
+            .await
+            .map_err(|e| Report::new(self.sdk_error(&e, &location, &self.proxy)))?;
+        let remote_size = head.content_length.unwrap_or(0).max(0) as u64;
+        let path = PathBuf::from(local_path);
+        let local_size = fs::metadata(&path)?.len();
+        let (verdict, method) = match first_step(local_size, remote_size, head.e_tag()) {
+            ComparisonStep::Done(verdict) => (verdict, ComparisonMethod::Size),
+            ComparisonStep::CompareMd5(remote_md5) => {
+                let local_md5 = tokio::task::spawn_blocking(move || file_md5(&path)).await??;
+                (
+                    hash_verdict(&local_md5, &remote_md5),
+                    ComparisonMethod::Etag,
+                )
+            }
+            ComparisonStep::CompareContent if full_hash => {
+                let local_md5 = tokio::task::spawn_blocking(move || file_md5(&path)).await??;
+                let mut object = client
+                    .get_object()
+                    .bucket(bucket)
+                    .key(self.api_key(key))
+                    .send()
+                    .await
+                    .map_err(|e| Report::new(self.sdk_error(&e, &location, &self.proxy)))?;
+                let mut hasher = Md5Hasher::default();
+                while let Some(bytes) = object.body.try_next().await? {
+                    hasher.update(&bytes);
+                }
+                (
+                    hash_verdict(&local_md5, &hasher.finish()),
+                    ComparisonMethod::FullHash,
+                )
+            }
+            ComparisonStep::CompareContent => {
+                let ranges = sample_ranges(local_size, SAMPLE_SIZE);
+                let local_ranges = ranges.clone();
+                let local =
+                    tokio::task::spawn_blocking(move || read_file_samples(&path, &local_ranges))
+                        .await??;
+                let mut remote = Vec::new();
+                for (start, end) in ranges {
+                    let object = client
+                        .get_object()
+                        .bucket(bucket)
+                        .key(self.api_key(key))
+                        .range(format!("bytes={}-{}", start, end))
+                        .send()
+                        .await
+                        .map_err(|e| Report::new(self.sdk_error(&e, &location, &self.proxy)))?;
+                    remote.push(object.body.collect().await?.into_bytes().to_vec());
+                }
+                (sampled_verdict(&local, &remote), ComparisonMethod::Sampled)
+            }
         };
-        let client_with_location = self.get_s3_client(Some(temp_file_creds)).await;
-        let mut response = client_with_location
+        Ok(FileComparison {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            local_path: local_path.to_string(),
+            verdict,
+            method,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// S3 cannot change the metadata in place, so the object is copied onto itself with
+    /// the new user metadata while the system headers and the storage class are carried over.
+    /// This rewrites the object, its ETag and LastModified change.
+    /// Returns the metadata read back after the copy
+    pub async fn replace_user_metadata(
+        &self,
+        metadata: &ObjectMetadata,
+    ) -> eyre::Result<ObjectMetadata> {
+        let _request = self.in_flight.start();
+        let (client, location) = self.get_s3_client_for_bucket(&metadata.bucket).await?;
+        let key = self.api_key(&metadata.key);
+        let copy_source = format!(
+            "{}/{}",
+            metadata.bucket,
+            utf8_percent_encode(&key, COPY_SOURCE_ENCODE_SET)
+        );
+        let user_metadata: HashMap<String, String> =
+            metadata.user_metadata.iter().cloned().collect();
+        let response = client
+            .copy_object()
+            .bucket(&metadata.bucket)
+            .key(key)
+            .copy_source(copy_source)
+            .metadata_directive(MetadataDirective::Replace)
+            .set_metadata(Some(user_metadata))
+            .set_content_type(metadata.content_type.clone())
+            .set_content_encoding(metadata.content_encoding.clone())
+            .set_content_disposition(metadata.content_disposition.clone())
+            .set_cache_control(metadata.cache_control.clone())
+            .set_storage_class(metadata.storage_class.as_deref().map(StorageClass::from))
+            .send()
+            .await;
+        if let Err(e) = response {
+            tracing::error!("Cannot replace object metadata: {:?}", e);
+            return Err(Report::new(self.sdk_error(&e, &location, &self.proxy)));
+        }
+        self.head_object_metadata(&metadata.bucket, &metadata.key)
+            .await
+    }
+
+    /// Copies the object onto itself with the new storage class, s3 keeps its metadata and
+    /// system headers. Returns the metadata read back after the copy
+    pub async fn change_storage_class(
+        &self,
+        bucket: &str,
+        key: &str,
+        storage_class: &str,
+    ) -> eyre::Result<ObjectMetadata> {
+        let _request = self.in_flight.start();
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        let api_key = self.api_key(key);
+        let copy_source = format!(
+            "{}/{}",
+            bucket,
+            utf8_percent_encode(&api_key, COPY_SOURCE_ENCODE_SET)
+        );
+        let response = client
+            .copy_object()
+            .bucket(bucket)
+            .key(api_key)
+            .copy_source(copy_source)
+            .metadata_directive(MetadataDirective::Copy)
+            .storage_class(StorageClass::from(storage_class))
+            .send()
+            .await;
+        if let Err(e) = response {
+            tracing::error!("Cannot change the storage class: {:?}", e);
+            let code = e
+                .as_service_error()
+                .and_then(|service_error| service_error.code());
+            return Err(match describe_storage_class_error(code) {
+                Some(message) => eyre::eyre!(message),
+                None => Report::new(self.sdk_error(&e, &location, &self.proxy)),
+            });
+        }
+        self.head_object_metadata(bucket, key).await
+    }
+
+    /// Copies the object server side, the request is sent to the region of the destination
+    /// bucket while the size is read from the region of the source. Objects above 5GB are
+    /// copied in parts. A move deletes the source only once the copy is complete
+    pub async fn copy_object(&self, copy: &ObjectCopy) -> eyre::Result<()> {
+        let _request = self.in_flight.start();
+        let (source_client, source_location) =
+            self.get_s3_client_for_bucket(&copy.source_bucket).await?;
+        let (client, location) = self
+            .get_s3_client_for_bucket(&copy.destination_bucket)
+            .await?;
+        let source_key = self.api_key(&copy.source_key);
+        let destination_key = self.api_key(&copy.destination_key);
+        let head = source_client
+            .head_object()
+            .bucket(&copy.source_bucket)
+            .key(&source_key)
+            .send()
+            .await
+            .map_err(|e| Report::new(self.sdk_error(&e, &source_location, &self.proxy)))?;
+        let size = head.content_length.unwrap_or(0).max(0) as u64;
+        let copy_source = format!(
+            "{}/{}",
+            copy.source_bucket,
+            utf8_percent_encode(&source_key, COPY_SOURCE_ENCODE_SET)
+        );
+        if size > MAX_SINGLE_COPY_SIZE {
+            self.multipart_copy(
+                &client,
+                &location,
+                &head,
+                size,
+                &copy_source,
+                &copy.destination_bucket,
+                &destination_key,
+            )
+            .await?;
+        } else {
+            let response = client
+                .copy_object()
+                .bucket(&copy.destination_bucket)
+                .key(&destination_key)
+                .copy_source(copy_source)
+                .send()
+                .await;
+            if let Err(e) = response {
+                tracing::error!("Cannot copy object: {:?}", e);
+                return Err(Report::new(self.sdk_error(&e, &location, &self.proxy)));
+            }
+        }
+        tracing::info!("S3 Object copied: {:?}", copy);
+        if copy.delete_source {
+            if let Some(error) = self
+                .delete_single_item(&copy.source_bucket, &copy.source_key)
+                .await?
+            {
+                return Err(eyre::eyre!(
+                    "Copied to {}, but the source was kept: {}",
+                    copy.destination_url(),
+                    error
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Renames the object by copying it to its new key and deleting the old one. Without
+    /// `overwrite` an object already at the new key is kept and `Ok(false)` is returned
+    pub async fn rename_object(&self, rename: &ObjectCopy, overwrite: bool) -> eyre::Result<bool> {
+        if !overwrite
+            && self
+                .object_exists(&rename.destination_bucket, &rename.destination_key)
+                .await?
+        {
+            return Ok(false);
+        }
+        self.copy_object(rename).await?;
+        Ok(true)
+    }
+
+    /// Whether the account can reach the bucket, a bucket in another region exists as well
+    pub async fn bucket_exists(&self, bucket: &str) -> eyre::Result<bool> {
+        let _request = self.in_flight.start();
+        let client = self.get_s3_client(None).await;
+        match client.head_bucket().bucket(bucket).send().await {
+            Ok(_) => Ok(true),
+            Err(e) if region_redirect(&e).is_some() => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+            Err(e) => Err(Report::new(self.sdk_error(
+                &e,
+                &self.default_region,
+                &self.proxy,
+            ))),
+        }
+    }
+
+    /// Whether the bucket has an object under the key, a missing object is not an error
+    pub async fn object_exists(&self, bucket: &str, key: &str) -> eyre::Result<bool> {
+        let _request = self.in_flight.start();
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        match client
+            .head_object()
+            .bucket(bucket)
+            .key(self.api_key(key))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+            Err(e) => Err(Report::new(self.sdk_error(&e, &location, &self.proxy))),
+        }
+    }
+
+    /// Whether the bucket has an object under the prefix, the bucket root only has to be
+    /// listable. Errors of a missing bucket are returned as they are
+    pub async fn prefix_exists(&self, bucket: &str, prefix: Option<&str>) -> eyre::Result<bool> {
+        let _request = self.in_flight.start();
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        let listing = client
             .list_objects_v2()
-            .delimiter("/")
-            .set_prefix(prefix)
-            .bucket(bucket.to_owned())
-            .into_paginator()
-            .send();
-
-        while let Some(result) = response.next().await {
-            match result {
-                Ok(output) => {
-                    for object in output.contents() {
-                        let key = object.key().unwrap_or_default();
-                        //todo: get size of the file
-                        let size = object
-                            .size()
-                            .map_or(String::new(), |value| value.to_string());
-                        let path = Path::new(key);
-                        let file_extension = path
-                            .extension()
-                            .and_then(|ext| ext.to_str()) // Convert the OsStr to a &str
-                            .unwrap_or("");
-                        let file_info = FileInfo {
-                            file_name: Self::get_filename(key).unwrap_or_default(),
-                            size,
-                            file_type: file_extension.to_string(),
-                            path: key.to_string(),
-                            is_directory: false,
-                        };
-                        let bucket_info = BucketInfo {
-                            bucket: Some(bucket.to_string()),
-                            region: Some(location.clone()),
-                            is_bucket: false,
-                        };
-                        all_objects.push(S3DataItem::init(bucket_info, file_info));
-                    }
-                    for object in output.common_prefixes() {
-                        let key = object.prefix().unwrap_or_default();
-                        if key != "/" {
-                            let file_info = FileInfo {
-                                file_name: Self::get_last_directory(key).unwrap_or_default(),
-                                size: "".to_string(),
-                                file_type: "Dir".to_string(),
-                                path: key.to_string(),
-                                is_directory: true,
-                            };
-                            let bucket_info = BucketInfo {
-                                bucket: Some(bucket.to_string()),
-                                region: Some(location.clone()),
-                                is_bucket: false,
-                            };
-                            all_objects.push(S3DataItem::init(bucket_info, file_info));
-                        }
-                    }
+            .bucket(bucket)
+            .set_prefix(prefix.map(|p| self.api_key(p)))
+            .max_keys(1)
+            .send()
+            .await
+            .map_err(|e| Report::new(self.sdk_error(&e, &location, &self.proxy)))?;
+        Ok(prefix.is_none() || !listing.contents().is_empty())
+    }
+
+    /// CopyObject is limited to 5GB, bigger objects are copied part by part with the content
+    /// type and user metadata of the source set on the new upload. A failed part aborts
+    /// the upload so no incomplete parts are left billed in the destination bucket
+    #[allow(clippy::too_many_arguments)]
+    async fn multipart_copy(
+        &self,
+        client: &Client,
+        location: &str,
+        head: &HeadObjectOutput,
+        size: u64,
+        copy_source: &str,
+        bucket: &str,
+        key: &str,
+    ) -> eyre::Result<()> {
+        let upload = client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .set_content_type(head.content_type.clone())
+            .set_metadata(head.metadata.clone())
+            .send()
+            .await
+            .map_err(|e| Report::new(self.sdk_error(&e, location, &self.proxy)))?;
+        let upload_id = upload.upload_id.unwrap_or_default();
+        let mut parts = Vec::new();
+        for (index, range) in copy_part_ranges(size, COPY_PART_SIZE)
+            .into_iter()
+            .enumerate()
+        {
+            let part_number = index as i32 + 1;
+            let response = client
+                .upload_part_copy()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .part_number(part_number)
+                .copy_source(copy_source)
+                .copy_source_range(range)
+                .send()
+                .await;
+            match response {
+                Ok(output) => parts.push(
+                    CompletedPart::builder()
+                        .part_number(part_number)
+                        .set_e_tag(output.copy_part_result.and_then(|result| result.e_tag))
+                        .build(),
+                ),
+                Err(e) => {
+                    tracing::error!("Cannot copy part {} of {}: {:?}", part_number, key, e);
+                    self.abort_multipart_upload(client, bucket, key, &upload_id)
+                        .await;
+                    return Err(Report::new(self.sdk_error(&e, location, &self.proxy)));
                 }
-                Err(err) => {
-                    tracing::error!("Err: {:?}", err) // Return the error immediately if encountered
+            }
+        }
+        let response = client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await;
+        if let Err(e) = response {
+            tracing::error!("Cannot complete the copy of {}: {:?}", key, e);
+            self.abort_multipart_upload(client, bucket, key, &upload_id)
+                .await;
+            return Err(Report::new(self.sdk_error(&e, location, &self.proxy)));
+        }
+        Ok(())
+    }
+
+    /// Aborts the multipart uploads of the item's key left by a stopped upload, their parts
+    /// would stay billed in the bucket otherwise
+    pub async fn abort_incomplete_uploads(&self, item: &LocalSelectedItem, creds: &FileCredential) {
+        let _request = self.in_flight.start();
+        let bucket = item.destination_bucket.as_str();
+        let key = api_key(&item.destination_key(), creds.leading_slash_keys);
+        let client = self
+            .get_s3_client(Some(FileCredential {
+                default_region: self.bucket_region(bucket, creds),
+                ..creds.clone()
+            }))
+            .await;
+        let uploads = match client
+            .list_multipart_uploads()
+            .bucket(bucket)
+            .prefix(&key)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) => {
+                tracing::error!("Cannot list the multipart uploads of {}: {:?}", key, e);
+                return;
+            }
+        };
+        for upload in uploads.uploads() {
+            if let (Some(upload_key), Some(upload_id)) = (upload.key(), upload.upload_id()) {
+                if upload_key == key {
+                    self.abort_multipart_upload(&client, bucket, &key, upload_id)
+                        .await;
                 }
             }
         }
+        if let Some(uploads) = &self.resumable_uploads {
+            uploads.remove_item(item);
+        }
+    }
 
-        Ok(all_objects)
+    /// Forgets the saved parts of the completed or aborted upload
+    fn forget_upload(&self, upload_id: &str) {
+        if let Some(uploads) = &self.resumable_uploads {
+            uploads.remove(upload_id);
+        }
     }
 
-    fn get_last_directory(path: &str) -> Option<String> {
-        let parts: Vec<&str> = path.split('/').collect();
-        let parts: Vec<&str> = parts.into_iter().filter(|&part| !part.is_empty()).collect();
-        parts.last().map(|&last| format!("{}/", last))
+    /// Client for the bucket of the saved upload along with its key on the backend
+    async fn resumable_upload_client(
+        &self,
+        upload: &ResumableUpload,
+        creds: &FileCredential,
+    ) -> (Client, FileCredential, String) {
+        let creds = FileCredential {
+            default_region: self.bucket_region(&upload.bucket, creds),
+            ..creds.clone()
+        };
+        let client = self.get_s3_client(Some(creds.clone())).await;
+        let key = api_key(&upload.key, creds.leading_slash_keys);
+        (client, creds, key)
     }
-    fn get_filename(path: &str) -> Option<String> {
-        let parts: Vec<&str> = path.split('/').collect();
-        let parts: Vec<&str> = parts.into_iter().filter(|&part| !part.is_empty()).collect();
-        parts.last().and_then(|&last| {
-            if path.ends_with('/') {
-                None
-            } else {
-                Some(last.to_string())
+
+    /// The upload saved by an earlier session with only the parts s3 still holds with the
+    /// saved ETags, checked with ListParts. `None` when s3 doesn't know the upload anymore or
+    /// the local file changed since, the upload is aborted then as its parts are useless
+    pub async fn check_resumable_upload(
+        &self,
+        upload: &ResumableUpload,
+        creds: &FileCredential,
+    ) -> eyre::Result<Option<ResumableUpload>> {
+        let _request = self.in_flight.start();
+        let (client, creds, key) = self.resumable_upload_client(upload, creds).await;
+        let unchanged = fs::metadata(&upload.path)
+            .ok()
+            .filter(|metadata| metadata.len() == upload.size)
+            .and_then(|metadata| metadata.modified().ok())
+            .is_some_and(|modified| modified_millis(modified) == upload.modified);
+        if !unchanged {
+            tracing::warn!(
+                "{} changed since its upload was interrupted, aborting the upload",
+                upload.path
+            );
+            self.abort_multipart_upload(&client, &upload.bucket, &key, &upload.upload_id)
+                .await;
+            return Ok(None);
+        }
+        let mut listed = HashMap::new();
+        let mut marker = None;
+        loop {
+            let response = client
+                .list_parts()
+                .bucket(&upload.bucket)
+                .key(&key)
+                .upload_id(&upload.upload_id)
+                .set_part_number_marker(marker.take())
+                .send()
+                .await;
+            let output = match response {
+                Ok(output) => output,
+                Err(e) if e.as_service_error().and_then(|e| e.code()) == Some(NO_SUCH_UPLOAD) => {
+                    return Ok(None)
+                }
+                Err(e) => {
+                    tracing::error!("Cannot list the parts of {}: {:?}", key, e);
+                    return Err(Report::new(self.sdk_error(
+                        &e,
+                        &creds.default_region,
+                        &creds.proxy,
+                    )));
+                }
+            };
+            for part in output.parts() {
+                if let (Some(number), Some(etag)) = (part.part_number(), part.e_tag()) {
+                    listed.insert(number, etag.to_string());
+                }
             }
-        })
+            marker = output.next_part_number_marker().map(str::to_string);
+            if !output.is_truncated().unwrap_or_default() || marker.is_none() {
+                break;
+            }
+        }
+        // a part uploaded again after the ETag was saved doesn't hold the saved content
+        let parts = upload
+            .parts
+            .iter()
+            .filter(|(number, etag)| listed.get(*number) == Some(*etag))
+            .map(|(number, etag)| (*number, etag.clone()))
+            .collect();
+        Ok(Some(ResumableUpload {
+            parts,
+            ..upload.clone()
+        }))
+    }
+
+    /// Aborts the upload saved by an earlier session and forgets it
+    pub async fn abort_resumable_upload(&self, upload: &ResumableUpload, creds: &FileCredential) {
+        let _request = self.in_flight.start();
+        let (client, _, key) = self.resumable_upload_client(upload, creds).await;
+        self.abort_multipart_upload(&client, &upload.bucket, &key, &upload.upload_id)
+            .await;
+        self.forget_upload(&upload.upload_id);
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        client: &Client,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) {
+        if let Err(e) = client
+            .abort_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            tracing::error!("Cannot abort the multipart upload of {}: {:?}", key, e);
+        }
+    }
+
+    /// Cached content of the first `max_bytes` of the object. The ETag is checked with a head
+    /// request first, the content is downloaded with a ranged request when the cache doesn't
+    /// hold it for that ETag
+    pub async fn get_object_cached(
+        &self,
+        bucket: &str,
+        key: &str,
+        max_bytes: u64,
+    ) -> eyre::Result<CachedObject> {
+        let _request = self.in_flight.start();
+        let (content, object_size) = self.content_key(bucket, key).await?;
+        let path = self
+            .cached_content(&content, object_size.min(max_bytes))
+            .await?;
+        Ok(CachedObject { path, object_size })
+    }
+
+    /// Key of the current content of the object in the cache, with the size of the object
+    async fn content_key(&self, bucket: &str, key: &str) -> eyre::Result<(ContentKey, u64)> {
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        match client
+            .head_object()
+            .bucket(bucket)
+            .key(self.api_key(key))
+            .send()
+            .await
+        {
+            Ok(head) => {
+                let object_size = head.content_length().unwrap_or(0).max(0) as u64;
+                // without an ETag the content is told apart by its modification and size
+                let etag = head.e_tag().map(String::from).unwrap_or_else(|| {
+                    let modified = head.last_modified().map(|m| m.secs()).unwrap_or(0);
+                    format!("{}-{}", modified, object_size)
+                });
+                let content = ContentKey {
+                    account: self.account.clone(),
+                    bucket: bucket.to_string(),
+                    key: key.to_string(),
+                    etag,
+                };
+                Ok((content, object_size))
+            }
+            Err(e) => {
+                tracing::error!("Cannot check the object to read: {:?}", e);
+                Err(Report::new(self.sdk_error(&e, &location, &self.proxy)))
+            }
+        }
+    }
+
+    /// Path of the cached content holding the first `wanted` bytes, they are downloaded when
+    /// the cache doesn't hold them
+    async fn cached_content(&self, content: &ContentKey, wanted: u64) -> eyre::Result<PathBuf> {
+        if let Some(path) = self.object_cache.lookup(content, wanted) {
+            return Ok(path);
+        }
+        let mut bytes = Vec::new();
+        if wanted > 0 {
+            let (client, location) = self.get_s3_client_for_bucket(&content.bucket).await?;
+            let response = client
+                .get_object()
+                .bucket(&content.bucket)
+                .key(self.api_key(&content.key))
+                .range(format!("bytes=0-{}", wanted - 1))
+                .send()
+                .await;
+            let mut object = match response {
+                Ok(object) => object,
+                Err(e) => {
+                    tracing::error!("Cannot read object: {:?}", e);
+                    return Err(Report::new(self.sdk_error(&e, &location, &self.proxy)));
+                }
+            };
+            // the endpoints ignoring the range send the whole object, it's not read further
+            while (bytes.len() as u64) < wanted {
+                match object.body.try_next().await? {
+                    Some(chunk) => bytes.extend_from_slice(&chunk),
+                    None => break,
+                }
+            }
+            bytes.truncate(wanted as usize);
+        }
+        Ok(self.object_cache.store(content, &bytes)?)
+    }
+
+    /// Reads up to `max_bytes` from the beginning of the object through the content cache
+    pub async fn preview_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        max_bytes: u64,
+    ) -> eyre::Result<ObjectPreview> {
+        let cached = self.get_object_cached(bucket, key, max_bytes).await?;
+        let mut bytes = tokio::fs::read(&cached.path).await?;
+        // the cache can hold more of the object than the preview shows
+        bytes.truncate(max_bytes as usize);
+        Ok(ObjectPreview::from_bytes(
+            bucket,
+            key,
+            &bytes,
+            cached.object_size,
+        ))
+    }
+
+    /// Reads the whole object as text through the content cache, objects above `max_bytes`
+    /// and binary ones are refused
+    pub async fn read_object_text(
+        &self,
+        bucket: &str,
+        key: &str,
+        max_bytes: u64,
+    ) -> eyre::Result<String> {
+        let _request = self.in_flight.start();
+        let (content, object_size) = self.content_key(bucket, key).await?;
+        check_text_size(object_size, max_bytes).map_err(Report::msg)?;
+        let path = self.cached_content(&content, object_size).await?;
+        let bytes = tokio::fs::read(&path).await?;
+        text_of(bytes).map_err(Report::msg)
     }
 
-    /// This method is similar to `list_objects` but it fetches all the data recursively
+    /// Presigned GET url of the object, anyone holding it can download the object until
+    /// it expires. The url is signed locally, only the region of the bucket is requested
+    pub async fn presigned_url(
+        &self,
+        bucket: &str,
+        key: &str,
+        expires_in: Duration,
+    ) -> eyre::Result<String> {
+        let _request = self.in_flight.start();
+        let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+        let config = PresigningConfig::expires_in(expires_in)?;
+        match client
+            .get_object()
+            .bucket(bucket)
+            .key(self.api_key(key))
+            .presigned(config)
+            .await
+        {
+            Ok(request) => Ok(request.uri().to_string()),
+            Err(e) => {
+                tracing::error!("Cannot presign object url: {:?}", e);
+                Err(Report::new(self.sdk_error(&e, &location, &self.proxy)))
+            }
+        }
+    }
+
+    /// Lists all object in the given bucket (or filtered by prefix) and constructs the items
+    /// representing directories
+    /// This method is used for displaying bucket/prefix content while browsing s3 and
+    /// it's not fetching all the contents behind prefixes together
+    /// This method is similar to `list_current_location` but it fetches all the data recursively
     /// including data behind the prefixes.
     /// Designed to be used mainly when selecting whole bucket/prefix for download or delete.
+    /// The listing stops once the selection exceeds the limit
     pub async fn list_all_objects(
         &self,
         bucket: &str,
         prefix: Option<String>,
-    ) -> eyre::Result<Vec<S3DataItem>> {
-        let mut all_objects = Vec::new();
-        let location = self.get_bucket_location(bucket).await?;
-        self.recursive_list_objects(bucket, prefix, &location, &mut all_objects)
-            .await?;
-        Ok(all_objects)
+        limit: SelectionLimit,
+    ) -> eyre::Result<LimitedSelection> {
+        let _request = self.in_flight.start();
+        collect_selection(self.list_stream(bucket, prefix, true), limit).await
     }
-    fn recursive_list_objects<'a>(
+
+    /// Lists the objects under the prefix in batches of one page, the next page is requested
+    /// only once the previous batch was consumed. With `recursive` the objects behind the
+    /// prefixes are listed as well, otherwise the prefixes are returned as directories
+    pub fn list_stream<'a>(
         &'a self,
         bucket: &'a str,
         prefix: Option<String>,
-        location: &'a str,
-        all_objects: &'a mut Vec<S3DataItem>,
-    ) -> Pin<Box<dyn std::future::Future<Output=Result<(), Report>> + Send + 'a>> {
-        Box::pin(async move {
-            let creds = self.credentials.clone();
-            let temp_file_creds = FileCredential {
-                name: "temp".to_string(),
-                access_key: creds.access_key_id().to_string(),
-                secret_key: creds.secret_access_key().to_string(),
-                default_region: location.to_string(),
-                selected: false,
-            };
+        recursive: bool,
+    ) -> impl Stream<Item = eyre::Result<Vec<S3DataItem>>> + 'a {
+        let prefix = prefix.map(|p| self.api_key(&p));
+        self.list_raw_stream(bucket, prefix, recursive)
+    }
 
-            let client_with_location = self.get_s3_client(Some(temp_file_creds)).await;
-            let mut response = client_with_location
-                .list_objects_v2()
-                .delimiter("/")
-                .set_prefix(prefix.clone())
-                .bucket(bucket.to_owned())
-                .into_paginator()
-                .send();
-
-            while let Some(result) = response.next().await {
-                match result {
-                    Ok(output) => {
-                        for object in output.contents() {
-                            let key = object.key().unwrap_or_default();
-                            let size = object
-                                .size()
-                                .map_or(String::new(), |value| value.to_string());
-                            let path = Path::new(key);
-                            let file_extension =
-                                path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-                            let file_info = FileInfo {
-                                file_name: Self::get_filename(key).unwrap_or_default(),
-                                size,
-                                file_type: file_extension.to_string(),
-                                path: key.to_string(),
-                                is_directory: false,
-                            };
-                            let bucket_info = BucketInfo {
-                                bucket: Some(bucket.to_string()),
-                                region: Some(location.to_string()),
-                                is_bucket: false,
-                            };
-                            all_objects.push(S3DataItem::init(bucket_info, file_info));
-                        }
-                        for common_prefix in output.common_prefixes() {
-                            let prefix = common_prefix.prefix().unwrap_or_default().to_string();
-                            self.recursive_list_objects(
-                                bucket,
-                                Some(prefix),
-                                location,
-                                all_objects,
-                            )
-                                .await?;
+    /// `list_stream` of the prefix as the backend knows it
+    fn list_raw_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: Option<String>,
+        recursive: bool,
+    ) -> impl Stream<Item = eyre::Result<Vec<S3DataItem>>> + 'a {
+        stream::once(async move {
+            let (client, location) = self.get_s3_client_for_bucket(bucket).await?;
+            let bucket_info = BucketInfo {
+                bucket: Some(bucket.to_string()),
+                region: Some(location.clone()),
+                is_bucket: false,
+                creation_date: None,
+            };
+            let pages = list_pages(prefix, recursive, move |request| {
+                let list = client
+                    .list_objects_v2()
+                    .bucket(bucket)
+                    .delimiter("/")
+                    .set_prefix(request.prefix)
+                    .set_continuation_token(request.continuation_token);
+                let location = location.clone();
+                async move {
+                    match list.send().await {
+                        Ok(output) => Ok(ListedPage {
+                            objects: output.contents().iter().map(Self::listed_object).collect(),
+                            common_prefixes: output
+                                .common_prefixes()
+                                .iter()
+                                .filter_map(|prefix| prefix.prefix())
+                                .map(String::from)
+                                .collect(),
+                            next_token: output.next_continuation_token().map(String::from),
+                        }),
+                        Err(e) => {
+                            tracing::error!("Cannot list objects: {:?}", e);
+                            Err(Report::new(self.sdk_error(&e, &location, &self.proxy)))
                         }
                     }
-                    Err(err) => {
-                        tracing::error!("Err: {:?}", err); // Return the error immediately if encountered
-                        return Err(err.into());
-                    }
                 }
-            }
-            Ok(())
+            });
+            Ok::<_, Report>(
+                pages.map_ok(move |page| Self::page_items(&bucket_info, page, recursive)),
+            )
         })
+        .try_flatten()
+    }
+
+    fn listed_object(object: &Object) -> ListedObject {
+        ListedObject {
+            key: object.key().unwrap_or_default().to_string(),
+            size: object.size(),
+            last_modified: object
+                .last_modified()
+                .and_then(|d| d.fmt(DateTimeFormat::DateTime).ok()),
+            storage_class: object.storage_class().map(|c| c.as_str().to_string()),
+        }
+    }
+
+    /// Items of the listed page, the prefixes are directories unless they are listed recursively
+    fn page_items(bucket_info: &BucketInfo, page: ListedPage, recursive: bool) -> Vec<S3DataItem> {
+        let objects = page
+            .objects
+            .iter()
+            .map(|object| S3DataItem::listed_object(bucket_info.clone(), object));
+        let directories = page
+            .common_prefixes
+            .iter()
+            .filter(|_| !recursive)
+            .map(|prefix| S3DataItem::listed(bucket_info.clone(), prefix, String::new(), true));
+        objects.chain(directories).collect()
     }
 
     async fn get_s3_client(&self, creds: Option<FileCredential>) -> Client {
+        let timeout_config = TimeoutConfig::builder()
+            .connect_timeout(self.connect_timeout)
+            .operation_timeout(self.operation_timeout)
+            .build();
+        self.build_s3_client(creds, timeout_config).await
+    }
+
+    /// Client used for streaming request bodies, the operation timeout is not applied here
+    /// as it would cut off every upload taking longer than the limit
+    async fn get_streaming_s3_client(&self, creds: Option<FileCredential>) -> Client {
+        let timeout_config = TimeoutConfig::builder()
+            .connect_timeout(self.connect_timeout)
+            .build();
+        self.build_s3_client(creds, timeout_config).await
+    }
+
+    async fn build_s3_client(
+        &self,
+        creds: Option<FileCredential>,
+        timeout_config: TimeoutConfig,
+    ) -> Client {
         let credentials: Credentials;
         let default_region: String;
+        let proxy: ProxyOverride;
+        let endpoint_url: Option<String>;
+        let profile: Option<String>;
+        let tls_settings: TlsSettings;
         if let Some(crd) = creds {
+            tls_settings = TlsSettings::of(&crd, &self.skip_verify_confirmed);
+            proxy = crd.proxy;
+            endpoint_url = crd.endpoint_url;
             let access_key = crd.access_key;
             let secret_access_key = crd.secret_key;
             default_region = crd.default_region;
+            profile = crd.profile;
             credentials = Credentials::new(
                 access_key,
                 secret_access_key,
@@ -667,15 +2465,56 @@ impl S3DataFetcher {
         } else {
             credentials = self.credentials.clone();
             default_region = self.default_region.clone();
+            proxy = self.proxy.clone();
+            endpoint_url = self.endpoint_url.clone();
+            profile = self.profile.clone();
+            tls_settings = self.tls.clone();
         }
+        let endpoint = self.endpoint_description(&default_region);
         let region_provider = RegionProviderChain::first_try(Region::new(default_region))
             .or_default_provider()
             .or_else(Region::new("eu-north-1"));
-        let shared_config = aws_config::from_env()
-            .credentials_provider(credentials)
+        let mut loader = aws_config::from_env()
             .region(region_provider)
-            .load()
-            .await;
+            .timeout_config(timeout_config);
+        // without one the sdk takes the endpoint from the environment variables
+        if let Some(endpoint_url) = endpoint_url {
+            loader = loader.endpoint_url(endpoint_url);
+        }
+        loader = match profile {
+            // the shared cache refreshes the credentials of the profile before they expire
+            Some(profile) => loader
+                .credentials_provider(profile_provider(&profile))
+                .identity_cache(identity_cache()),
+            None => loader.credentials_provider(credentials),
+        };
+        let proxy_settings = self.proxy_settings(&proxy);
+        if let Some(proxy_url) = proxy_settings.as_ref().and_then(|p| p.proxy_for(&endpoint)) {
+            // the tunnel through the proxy verifies the endpoint with the system certificates
+            if tls_settings.is_custom() {
+                tracing::warn!(
+                    "ca_bundle_path and insecure_skip_tls_verify are not applied through proxy {}",
+                    redact_proxy_url(proxy_url)
+                );
+            }
+            match proxied_http_client(proxy_url) {
+                Ok(http_client) => loader = loader.http_client(http_client),
+                Err(e) => tracing::error!(
+                    "Cannot use proxy {}, connecting directly: {}",
+                    redact_proxy_url(proxy_url),
+                    e
+                ),
+            }
+        } else {
+            match tls::http_client(&tls_settings) {
+                Ok(http_client) => loader = loader.http_client(http_client),
+                Err(e) => tracing::error!(
+                    "Cannot set up the tls settings, using the default client: {}",
+                    e
+                ),
+            }
+        }
+        let shared_config = loader.load().await;
         Client::new(&shared_config)
     }
 }
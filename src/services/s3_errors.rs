@@ -0,0 +1,551 @@
+//! This module provides helpers for turning AWS sdk errors into messages useful for the user
+use crate::services::tls::untrusted_certificate;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::SdkError;
+use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+use color_eyre::Report;
+use std::fmt;
+use std::time::Duration;
+
+/// Broad category of a failed s3 request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Connecting to the endpoint or waiting for the response took longer than configured
+    Timeout,
+    /// The endpoint could not be reached at all (dns, refused connection, broken pipe)
+    Connection,
+    /// The configured proxy could not be reached or refused to forward the request
+    Proxy,
+    /// The certificate of the endpoint is not trusted, e.g. issued by a private CA
+    Tls,
+    /// The endpoint responded with an error
+    Service,
+    /// The endpoint asked to slow down (429 or 503)
+    Throttled,
+    /// The process ran out of file descriptors, usually from too many transfers at once
+    TooManyOpenFiles,
+    /// The bucket doesn't exist, e.g. it was deleted by another client while it was browsed
+    MissingBucket,
+    Other,
+}
+
+/// Code of the service error of the requests to a bucket which doesn't exist
+const NO_SUCH_BUCKET: &str = "NoSuchBucket";
+
+/// Description of the certificate of the endpoint the client didn't trust
+fn rejected_certificate<E>(err: &SdkError<E, HttpResponse>) -> Option<String> {
+    match err {
+        SdkError::DispatchFailure(failure) => failure
+            .as_connector_error()
+            .and_then(|e| untrusted_certificate(e)),
+        _ => None,
+    }
+}
+
+/// `proxy` is the proxy the request was sent through, connection failures are blamed on it
+/// as it is the only host the client connects to
+pub fn classify_sdk_error<E>(err: &SdkError<E, HttpResponse>, proxy: Option<&str>) -> ErrorClass
+where
+    E: ProvideErrorMetadata,
+{
+    let throttled = err
+        .raw_response()
+        .is_some_and(|response| matches!(response.status().as_u16(), 429 | 503));
+    let missing_bucket = err
+        .as_service_error()
+        .is_some_and(|e| e.code() == Some(NO_SUCH_BUCKET));
+    match err {
+        SdkError::TimeoutError(_) => ErrorClass::Timeout,
+        SdkError::DispatchFailure(_) if rejected_certificate(err).is_some() => ErrorClass::Tls,
+        SdkError::DispatchFailure(_) if proxy.is_some() => ErrorClass::Proxy,
+        SdkError::DispatchFailure(failure) if failure.is_timeout() => ErrorClass::Timeout,
+        SdkError::DispatchFailure(_) => ErrorClass::Connection,
+        SdkError::ServiceError(_) | SdkError::ResponseError(_) if throttled => {
+            ErrorClass::Throttled
+        }
+        SdkError::ServiceError(_) if missing_bucket => ErrorClass::MissingBucket,
+        SdkError::ServiceError(_) | SdkError::ResponseError(_) => ErrorClass::Service,
+        _ => ErrorClass::Other,
+    }
+}
+
+/// Builds the message displayed next to a failed operation.
+/// Timeouts mention which limit was hit, so a dead endpoint is not mistaken for a server error
+pub fn describe_sdk_error<E>(
+    err: &SdkError<E, HttpResponse>,
+    endpoint: &str,
+    proxy: Option<&str>,
+    connect_timeout: Duration,
+    operation_timeout: Duration,
+) -> String
+where
+    E: ProvideErrorMetadata + std::fmt::Display,
+{
+    match (classify_sdk_error(err, proxy), err) {
+        (ErrorClass::Timeout, SdkError::DispatchFailure(_)) => format!(
+            "connection to endpoint {} timed out after {}s",
+            endpoint,
+            connect_timeout.as_secs()
+        ),
+        (ErrorClass::Timeout, _) => format!(
+            "request to endpoint {} timed out after {}s",
+            endpoint,
+            operation_timeout.as_secs()
+        ),
+        (ErrorClass::Connection, _) => format!("cannot connect to endpoint {}", endpoint),
+        (ErrorClass::Tls, _) => format!(
+            "certificate of endpoint {} is not trusted: {}, add its CA to ca_bundle_path of \
+             the account",
+            endpoint,
+            rejected_certificate(err).unwrap_or_default()
+        ),
+        (ErrorClass::Proxy, _) => format!(
+            "connection via proxy {} failed (endpoint {})",
+            proxy.unwrap_or_default(),
+            endpoint
+        ),
+        (ErrorClass::Service | ErrorClass::Throttled | ErrorClass::MissingBucket, _) => {
+            match err.as_service_error() {
+                Some(service_error) => match (service_error.code(), service_error.message()) {
+                    (Some(code), Some(message)) => format!("{}: {}", code, message),
+                    (Some(code), None) => code.to_string(),
+                    // e.g. a HEAD request, its error response has no body to read the code from
+                    _ => match err.raw_response() {
+                        Some(response) => {
+                            format!("{} (HTTP {})", service_error, response.status().as_u16())
+                        }
+                        None => service_error.to_string(),
+                    },
+                },
+                None => format!("unexpected response from endpoint {}", endpoint),
+            }
+        }
+        (ErrorClass::TooManyOpenFiles | ErrorClass::Other, _) => {
+            format!("request to endpoint {} failed", endpoint)
+        }
+    }
+}
+
+/// Plain language explanation of the errors returned when deleting objects or buckets
+/// protected by object lock, `None` for errors not related to the lock
+pub fn describe_object_lock_error(code: Option<&str>, message: Option<&str>) -> Option<String> {
+    let mentions_lock = message.is_some_and(|m| {
+        let m = m.to_ascii_lowercase();
+        m.contains("object lock") || m.contains("worm") || m.contains("retention")
+    });
+    match code {
+        Some("ObjectLocked") => Some(
+            "The object is protected by object lock (retention or legal hold) and cannot be deleted"
+                .to_string(),
+        ),
+        Some("AccessDenied") if mentions_lock => Some(
+            "The object is protected by object lock (retention or legal hold) and cannot be deleted"
+                .to_string(),
+        ),
+        Some("InvalidRequest") if mentions_lock => Some(format!(
+            "The request conflicts with the object lock settings of the bucket: {}",
+            message.unwrap_or_default()
+        )),
+        Some("BucketNotEmpty") => Some(
+            "The bucket still contains objects or object versions, locked versions and delete markers have to be removed first"
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+/// Plain language explanation of the errors returned when an object is copied onto itself
+/// with another storage class, `None` for the other errors
+pub fn describe_storage_class_error(code: Option<&str>) -> Option<String> {
+    match code {
+        Some("InvalidObjectState") => Some(
+            "The object is archived, restore it first (e.g. aws s3api restore-object) before changing its storage class"
+                .to_string(),
+        ),
+        Some("InvalidStorageClass") => {
+            Some("The endpoint does not support this storage class".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Explanation of an upload rejected with `AccessDenied`, often by a bucket policy requiring
+/// a server side encryption the request didn't ask for. `sse` is the encryption of the upload,
+/// `None` for the other errors
+pub fn describe_upload_policy_error(
+    code: Option<&str>,
+    message: Option<&str>,
+    sse: Option<&str>,
+) -> Option<String> {
+    if code != Some("AccessDenied") {
+        return None;
+    }
+    let hint = match sse {
+        None => {
+            "the bucket policy may require server side encryption, set sse (e.g. sse=aws:kms) \
+                 in the account"
+        }
+        Some(sse) if sse.starts_with("aws:kms") => {
+            "the bucket policy may require another kms_key_id, or the account may not use the key"
+        }
+        Some(_) => "the bucket policy may require another server side encryption (sse)",
+    };
+    Some(format!(
+        "AccessDenied: {} - {}",
+        message.unwrap_or("Access Denied"),
+        hint
+    ))
+}
+
+/// Error returned from the transfer operations, keeps the class next to the user facing message
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3Error {
+    pub class: ErrorClass,
+    pub message: String,
+}
+
+impl S3Error {
+    pub fn from_sdk_error<E>(
+        err: &SdkError<E, HttpResponse>,
+        endpoint: &str,
+        proxy: Option<&str>,
+        connect_timeout: Duration,
+        operation_timeout: Duration,
+    ) -> Self
+    where
+        E: ProvideErrorMetadata + std::fmt::Display,
+    {
+        S3Error {
+            class: classify_sdk_error(err, proxy),
+            message: describe_sdk_error(err, endpoint, proxy, connect_timeout, operation_timeout),
+        }
+    }
+}
+
+impl fmt::Display for S3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for S3Error {}
+
+/// Whether the os error means that the process or the whole system has no file descriptors left
+#[cfg(unix)]
+fn is_fd_exhaustion(code: i32) -> bool {
+    code == libc::EMFILE || code == libc::ENFILE
+}
+
+/// `ERROR_TOO_MANY_OPEN_FILES`
+#[cfg(not(unix))]
+fn is_fd_exhaustion(code: i32) -> bool {
+    code == 4
+}
+
+/// Finds the class of an error returned from the fetcher, running out of file descriptors
+/// is told apart from the other local errors which are `Other`
+pub fn classify_report(report: &Report) -> ErrorClass {
+    if let Some(e) = report.downcast_ref::<S3Error>() {
+        return e.class;
+    }
+    let out_of_files = report.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .and_then(|e| e.raw_os_error())
+            .is_some_and(is_fd_exhaustion)
+    });
+    if out_of_files {
+        ErrorClass::TooManyOpenFiles
+    } else {
+        ErrorClass::Other
+    }
+}
+
+/// Message displayed next to a failed transfer, running out of file descriptors comes
+/// with a hint as the error alone doesn't tell what to change
+pub fn describe_report(report: &Report) -> String {
+    match classify_report(report) {
+        ErrorClass::TooManyOpenFiles => format!(
+            "{} - too many open files, lower transfer_concurrency in config.toml or raise the limit with `ulimit -n`",
+            report
+        ),
+        _ => report.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_s3::operation::get_object::GetObjectError;
+    use aws_smithy_runtime_api::client::result::ConnectorError;
+    use aws_smithy_runtime_api::http::{Response, StatusCode};
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::error::ErrorMetadata;
+
+    type TestError = SdkError<GetObjectError, Response>;
+
+    #[test]
+    fn operation_timeout_is_classified_as_timeout() {
+        let err = TestError::timeout_error("operation timed out");
+        assert_eq!(classify_sdk_error(&err, None), ErrorClass::Timeout);
+        assert_eq!(
+            describe_sdk_error(
+                &err,
+                "eu-west-1",
+                None,
+                Duration::from_secs(5),
+                Duration::from_secs(60)
+            ),
+            "request to endpoint eu-west-1 timed out after 60s"
+        );
+    }
+
+    #[test]
+    fn connect_timeout_is_classified_as_timeout() {
+        let err = TestError::dispatch_failure(ConnectorError::timeout("connect timeout".into()));
+        assert_eq!(classify_sdk_error(&err, None), ErrorClass::Timeout);
+        assert_eq!(
+            describe_sdk_error(
+                &err,
+                "eu-west-1",
+                None,
+                Duration::from_secs(5),
+                Duration::from_secs(60)
+            ),
+            "connection to endpoint eu-west-1 timed out after 5s"
+        );
+    }
+
+    #[test]
+    fn io_failure_is_classified_as_connection_error() {
+        let err = TestError::dispatch_failure(ConnectorError::io("connection refused".into()));
+        assert_eq!(classify_sdk_error(&err, None), ErrorClass::Connection);
+    }
+
+    #[test]
+    fn slow_down_responses_are_classified_as_throttling() {
+        let response =
+            |status: u16| Response::new(StatusCode::try_from(status).unwrap(), SdkBody::empty());
+        let service_error = |code: &str, status: u16| {
+            TestError::service_error(
+                GetObjectError::generic(ErrorMetadata::builder().code(code).build()),
+                response(status),
+            )
+        };
+        assert_eq!(
+            classify_sdk_error(&service_error("SlowDown", 503), None),
+            ErrorClass::Throttled
+        );
+        assert_eq!(
+            classify_sdk_error(&service_error("TooManyRequests", 429), None),
+            ErrorClass::Throttled
+        );
+        assert_eq!(
+            classify_sdk_error(&service_error("NoSuchKey", 404), None),
+            ErrorClass::Service
+        );
+    }
+
+    #[test]
+    fn deleted_bucket_has_its_own_class() {
+        let err = TestError::service_error(
+            GetObjectError::generic(
+                ErrorMetadata::builder()
+                    .code("NoSuchBucket")
+                    .message("The specified bucket does not exist")
+                    .build(),
+            ),
+            Response::new(StatusCode::try_from(404).unwrap(), SdkBody::empty()),
+        );
+        let report = Report::new(S3Error::from_sdk_error(
+            &err,
+            "s3.eu-west-1.amazonaws.com",
+            None,
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+        ));
+        assert_eq!(classify_report(&report), ErrorClass::MissingBucket);
+        assert_eq!(
+            report.to_string(),
+            "NoSuchBucket: The specified bucket does not exist"
+        );
+    }
+
+    #[test]
+    fn failures_through_a_proxy_are_blamed_on_the_proxy() {
+        let refused = TestError::dispatch_failure(ConnectorError::io("connection refused".into()));
+        let timeout =
+            TestError::dispatch_failure(ConnectorError::timeout("connect timeout".into()));
+        let proxy = Some("http://proxy:3128");
+        assert_eq!(classify_sdk_error(&refused, proxy), ErrorClass::Proxy);
+        assert_eq!(classify_sdk_error(&timeout, proxy), ErrorClass::Proxy);
+        assert_eq!(
+            describe_sdk_error(
+                &refused,
+                "s3.eu-west-1.amazonaws.com",
+                proxy,
+                Duration::from_secs(5),
+                Duration::from_secs(60)
+            ),
+            "connection via proxy http://proxy:3128 failed (endpoint s3.eu-west-1.amazonaws.com)"
+        );
+        let operation_timeout = TestError::timeout_error("operation timed out");
+        assert_eq!(
+            classify_sdk_error(&operation_timeout, proxy),
+            ErrorClass::Timeout
+        );
+    }
+
+    #[test]
+    fn untrusted_certificate_has_its_own_class() {
+        let rejected = rustls::Error::InvalidCertificate(rustls::CertificateError::UnknownIssuer);
+        let err = TestError::dispatch_failure(ConnectorError::other(
+            Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                rejected,
+            )),
+            None,
+        ));
+        assert_eq!(classify_sdk_error(&err, None), ErrorClass::Tls);
+        assert_eq!(
+            describe_sdk_error(
+                &err,
+                "minio.lab",
+                None,
+                Duration::from_secs(5),
+                Duration::from_secs(60)
+            ),
+            "certificate of endpoint minio.lab is not trusted: UnknownIssuer, add its CA to \
+             ca_bundle_path of the account"
+        );
+    }
+
+    #[test]
+    fn object_lock_errors_are_explained() {
+        let locked = Some(
+            "The object is protected by object lock (retention or legal hold) and cannot be deleted"
+                .to_string(),
+        );
+        assert_eq!(
+            describe_object_lock_error(
+                Some("AccessDenied"),
+                Some("Access Denied because object protected by object lock.")
+            ),
+            locked
+        );
+        assert_eq!(
+            describe_object_lock_error(
+                Some("ObjectLocked"),
+                Some("Object is WORM protected and cannot be overwritten")
+            ),
+            locked
+        );
+        assert!(describe_object_lock_error(Some("BucketNotEmpty"), None)
+            .unwrap()
+            .contains("object versions"));
+        assert!(describe_object_lock_error(
+            Some("InvalidRequest"),
+            Some("Bucket is missing Object Lock Configuration")
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn other_errors_are_not_treated_as_lock_errors() {
+        assert_eq!(
+            describe_object_lock_error(Some("AccessDenied"), Some("Access Denied")),
+            None
+        );
+        assert_eq!(
+            describe_object_lock_error(Some("InvalidRequest"), Some("Missing header")),
+            None
+        );
+        assert_eq!(describe_object_lock_error(None, None), None);
+    }
+
+    #[test]
+    fn storage_class_errors_are_explained() {
+        assert!(describe_storage_class_error(Some("InvalidObjectState"))
+            .unwrap()
+            .contains("restore-object"));
+        assert!(describe_storage_class_error(Some("InvalidStorageClass")).is_some());
+        assert_eq!(describe_storage_class_error(Some("AccessDenied")), None);
+    }
+
+    #[test]
+    fn upload_policy_errors_keep_the_code_and_hint_at_encryption() {
+        assert_eq!(
+            describe_upload_policy_error(Some("AccessDenied"), Some("Access Denied"), None),
+            Some(
+                "AccessDenied: Access Denied - the bucket policy may require server side \
+                 encryption, set sse (e.g. sse=aws:kms) in the account"
+                    .to_string()
+            )
+        );
+        assert!(
+            describe_upload_policy_error(Some("AccessDenied"), None, Some("aws:kms"))
+                .unwrap()
+                .contains("kms_key_id")
+        );
+        assert!(
+            describe_upload_policy_error(Some("AccessDenied"), None, Some("AES256"))
+                .unwrap()
+                .starts_with("AccessDenied: Access Denied")
+        );
+        assert_eq!(
+            describe_upload_policy_error(Some("NoSuchBucket"), None, None),
+            None
+        );
+    }
+
+    #[test]
+    fn service_error_without_a_code_names_the_status() {
+        let err = TestError::service_error(
+            GetObjectError::generic(ErrorMetadata::builder().build()),
+            Response::new(StatusCode::try_from(403).unwrap(), SdkBody::empty()),
+        );
+        let message = describe_sdk_error(
+            &err,
+            "s3.eu-west-1.amazonaws.com",
+            None,
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+        );
+        assert!(message.ends_with("(HTTP 403)"), "{}", message);
+    }
+
+    #[test]
+    fn report_keeps_the_class_of_wrapped_s3_error() {
+        let err = TestError::timeout_error("operation timed out");
+        let report = Report::new(S3Error::from_sdk_error(
+            &err,
+            "eu-west-1",
+            None,
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+        ));
+        assert_eq!(classify_report(&report), ErrorClass::Timeout);
+        assert_eq!(
+            report.to_string(),
+            "request to endpoint eu-west-1 timed out after 60s"
+        );
+        assert_eq!(
+            classify_report(&Report::msg("disk full")),
+            ErrorClass::Other
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn running_out_of_file_descriptors_has_its_own_class() {
+        for code in [libc::EMFILE, libc::ENFILE] {
+            let report = Report::new(std::io::Error::from_raw_os_error(code))
+                .wrap_err("cannot create /tmp/a.txt");
+            assert_eq!(classify_report(&report), ErrorClass::TooManyOpenFiles);
+            assert!(describe_report(&report).contains("lower transfer_concurrency"));
+        }
+        let denied = Report::new(std::io::Error::from_raw_os_error(libc::EACCES));
+        assert_eq!(classify_report(&denied), ErrorClass::Other);
+        assert_eq!(describe_report(&denied), denied.to_string());
+    }
+}
@@ -0,0 +1,41 @@
+//! This module provides the handling of the keys listed with a leading slash by some
+//! s3 compatible backends, e.g. MinIO returning `/photos/a.jpg` for objects written that way
+
+/// Key without the leading slash, as shown on the s3 panel and used for the local paths,
+/// and whether a slash was removed. Only one slash is stripped so `//a` stays distinct from `/a`
+pub fn normalize_key(raw: &str) -> (&str, bool) {
+    match raw.strip_prefix('/') {
+        Some(stripped) => (stripped, true),
+        None => (raw, false),
+    }
+}
+
+/// Key sent to the backend, the slash removed by `normalize_key` is put back for the
+/// credentials of backends listing their keys with it
+pub fn api_key(key: &str, leading_slash: bool) -> String {
+    if leading_slash {
+        format!("/{}", key)
+    } else {
+        key.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_single_leading_slash_is_stripped() {
+        assert_eq!(normalize_key("/photos/a.jpg"), ("photos/a.jpg", true));
+        assert_eq!(normalize_key("//photos/a.jpg"), ("/photos/a.jpg", true));
+        assert_eq!(normalize_key("photos/a.jpg"), ("photos/a.jpg", false));
+    }
+
+    #[test]
+    fn api_key_restores_the_raw_key() {
+        for raw in ["/photos/", "//a", "photos/a.jpg"] {
+            let (key, leading_slash) = normalize_key(raw);
+            assert_eq!(api_key(key, leading_slash), raw);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! This module provides the lazy listing of a bucket, a page is requested only once the
+//! previous ones were consumed, so a listing dropped early stops fetching
+use color_eyre::eyre;
+use futures::stream::{self, Stream};
+use std::future::Future;
+
+/// Object of a listed page
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListedObject {
+    pub key: String,
+    pub size: Option<i64>,
+    /// e.g. `2024-05-01T12:00:00Z`
+    pub last_modified: Option<String>,
+    pub storage_class: Option<String>,
+}
+
+/// Page of a `ListObjectsV2` response with `/` as the delimiter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListedPage {
+    pub objects: Vec<ListedObject>,
+    pub common_prefixes: Vec<String>,
+    /// Continuation token of the next page of the same prefix
+    pub next_token: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRequest {
+    pub prefix: Option<String>,
+    pub continuation_token: Option<String>,
+}
+
+/// Pages of the prefix requested with `fetch`. With `recursive` the common prefixes are
+/// listed as well, right after the page they were found on, in the same order a depth first
+/// traversal of the directories would give. The stream ends after the first error
+pub fn list_pages<F, Fut>(
+    prefix: Option<String>,
+    recursive: bool,
+    fetch: F,
+) -> impl Stream<Item = eyre::Result<ListedPage>>
+where
+    F: FnMut(PageRequest) -> Fut,
+    Fut: Future<Output = eyre::Result<ListedPage>>,
+{
+    let pending = vec![PageRequest {
+        prefix,
+        continuation_token: None,
+    }];
+    stream::try_unfold(
+        (fetch, pending),
+        move |(mut fetch, mut pending)| async move {
+            let request = match pending.pop() {
+                Some(request) => request,
+                None => return Ok(None),
+            };
+            let page = fetch(request.clone()).await?;
+            if let Some(token) = &page.next_token {
+                pending.push(PageRequest {
+                    prefix: request.prefix,
+                    continuation_token: Some(token.clone()),
+                });
+            }
+            if recursive {
+                // the last pushed prefix is listed first
+                pending.extend(page.common_prefixes.iter().rev().map(|prefix| PageRequest {
+                    prefix: Some(prefix.clone()),
+                    continuation_token: None,
+                }));
+            }
+            Ok::<_, eyre::Report>(Some((page, (fetch, pending))))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{StreamExt, TryStreamExt};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn page(objects: &[&str], prefixes: &[&str], next_token: Option<&str>) -> ListedPage {
+        ListedPage {
+            objects: objects
+                .iter()
+                .map(|key| ListedObject {
+                    key: key.to_string(),
+                    size: Some(1),
+                    ..ListedObject::default()
+                })
+                .collect(),
+            common_prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+            next_token: next_token.map(String::from),
+        }
+    }
+
+    /// Bucket answering the requests from the pages keyed by prefix and continuation token,
+    /// the requests are recorded
+    fn bucket(
+        pages: Vec<((Option<&str>, Option<&str>), ListedPage)>,
+    ) -> (
+        impl FnMut(PageRequest) -> futures::future::Ready<eyre::Result<ListedPage>>,
+        Arc<Mutex<Vec<PageRequest>>>,
+    ) {
+        let pages: HashMap<(Option<String>, Option<String>), ListedPage> = pages
+            .into_iter()
+            .map(|((prefix, token), page)| {
+                ((prefix.map(String::from), token.map(String::from)), page)
+            })
+            .collect();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let fetch = move |request: PageRequest| {
+            recorded.lock().unwrap().push(request.clone());
+            let key = (request.prefix, request.continuation_token);
+            futures::future::ready(
+                pages
+                    .get(&key)
+                    .cloned()
+                    .ok_or_else(|| eyre::eyre!("no page for {:?}", key)),
+            )
+        };
+        (fetch, requests)
+    }
+
+    fn keys(pages: &[ListedPage]) -> Vec<String> {
+        pages
+            .iter()
+            .flat_map(|page| page.objects.iter().map(|object| object.key.clone()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn three_pages_are_followed_through_the_continuation_tokens() {
+        let (fetch, requests) = bucket(vec![
+            ((None, None), page(&["a"], &[], Some("t1"))),
+            ((None, Some("t1")), page(&["b"], &[], Some("t2"))),
+            ((None, Some("t2")), page(&["c"], &[], None)),
+        ]);
+        let pages: Vec<ListedPage> = list_pages(None, false, fetch).try_collect().await.unwrap();
+        assert_eq!(keys(&pages), vec!["a", "b", "c"]);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn recursive_listing_descends_right_after_the_page() {
+        let (fetch, _) = bucket(vec![
+            ((None, None), page(&["root.txt"], &["a/", "b/"], Some("t1"))),
+            ((Some("a/"), None), page(&["a/1.txt"], &["a/x/"], None)),
+            ((Some("a/x/"), None), page(&["a/x/2.txt"], &[], None)),
+            ((Some("b/"), None), page(&["b/3.txt"], &[], None)),
+            ((None, Some("t1")), page(&["z.txt"], &[], None)),
+        ]);
+        let pages: Vec<ListedPage> = list_pages(None, true, fetch).try_collect().await.unwrap();
+        assert_eq!(
+            keys(&pages),
+            vec!["root.txt", "a/1.txt", "a/x/2.txt", "b/3.txt", "z.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_listing_stops_requesting_pages() {
+        let (fetch, requests) = bucket(vec![
+            ((None, None), page(&["a"], &[], Some("t1"))),
+            ((None, Some("t1")), page(&["b"], &[], Some("t2"))),
+            ((None, Some("t2")), page(&["c"], &[], None)),
+        ]);
+        let first: Vec<ListedPage> = list_pages(None, false, fetch)
+            .take(1)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<eyre::Result<_>>()
+            .unwrap();
+        assert_eq!(keys(&first), vec!["a"]);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn listing_ends_with_the_first_error() {
+        let (fetch, requests) = bucket(vec![((None, None), page(&["a"], &[], Some("t1")))]);
+        let results: Vec<eyre::Result<ListedPage>> = list_pages(None, false, fetch).collect().await;
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+}
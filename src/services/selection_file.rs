@@ -0,0 +1,295 @@
+//! This module provides saving the selection to a JSON file to share it or run it again later,
+//! independent of the pending transfers kept in the data directory. The file describes the
+//! objects and the local files with their destinations, never the account or its keys, the
+//! imported entries get the current account
+use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::s3_selected_item::S3SelectedItem;
+use crate::services::checksum::Sha256Hasher;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Name of the file offered for the export in the directory of the local panel
+pub const SELECTION_FILE_NAME: &str = "s3tui-selection.json";
+
+/// Downloads of an imported file whose keys are looked up before they're added,
+/// spread over the whole file
+pub const SPOT_CHECKED_KEYS: usize = 20;
+
+const FORMAT: &str = "s3tui-selection";
+const VERSION: u32 = 1;
+
+/// One transfer of the selection, the files of the selected directories are listed one by one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "direction", rename_all = "lowercase")]
+pub enum SelectionEntry {
+    Download {
+        bucket: String,
+        key: String,
+        /// Path below the download directory when it's not the key, see `destination_path`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        destination: Option<String>,
+    },
+    Upload {
+        path: String,
+        bucket: String,
+        /// Key the file is uploaded to
+        destination: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SelectionFile {
+    format: String,
+    version: u32,
+    /// Hex sha-256 of the entries, tells a damaged or edited file apart
+    sha256: String,
+    entries: Vec<SelectionEntry>,
+}
+
+/// Entries of the selection, the transferred items included. Directories whose content
+/// wasn't listed have nothing to export
+pub fn selection_entries(
+    downloads: &[S3SelectedItem],
+    uploads: &[LocalSelectedItem],
+) -> Vec<SelectionEntry> {
+    let download = |item: &S3SelectedItem| match (&item.bucket, &item.path) {
+        (Some(bucket), Some(key)) if !item.is_directory && !item.is_bucket => {
+            Some(SelectionEntry::Download {
+                bucket: bucket.clone(),
+                key: key.clone(),
+                destination: item.destination_path.clone(),
+            })
+        }
+        _ => None,
+    };
+    let upload = |item: &LocalSelectedItem| {
+        (!item.is_directory).then(|| SelectionEntry::Upload {
+            path: item.path.clone(),
+            bucket: item.destination_bucket.clone(),
+            destination: item.destination_key(),
+        })
+    };
+    let downloads = downloads.iter().flat_map(|item| match &item.children {
+        Some(children) => children.iter().filter_map(download).collect(),
+        None => download(item).into_iter().collect::<Vec<_>>(),
+    });
+    let uploads = uploads.iter().flat_map(|item| match &item.children {
+        Some(children) => children.iter().filter_map(upload).collect(),
+        None => upload(item).into_iter().collect::<Vec<_>>(),
+    });
+    downloads.chain(uploads).collect()
+}
+
+fn checksum(entries: &[SelectionEntry]) -> io::Result<String> {
+    let mut hasher = Sha256Hasher::default();
+    hasher.update(&serde_json::to_vec(entries)?);
+    Ok(hasher.finish())
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn to_json(entries: &[SelectionEntry]) -> io::Result<String> {
+    let file = SelectionFile {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        sha256: checksum(entries)?,
+        entries: entries.to_vec(),
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+/// Entries of an exported file, a file changed after the export is refused
+pub fn from_json(content: &str) -> io::Result<Vec<SelectionEntry>> {
+    let file: SelectionFile = serde_json::from_str(content)
+        .map_err(|e| invalid(format!("not a selection file: {}", e)))?;
+    if file.format != FORMAT || file.version != VERSION {
+        return Err(invalid(format!(
+            "unsupported selection file {} version {}",
+            file.format, file.version
+        )));
+    }
+    if !checksum(&file.entries)?.eq_ignore_ascii_case(&file.sha256) {
+        return Err(invalid(
+            "the checksum doesn't match, the file was changed".into(),
+        ));
+    }
+    Ok(file.entries)
+}
+
+pub fn export_selection(path: &Path, entries: &[SelectionEntry]) -> io::Result<()> {
+    fs::write(path, to_json(entries)?)
+}
+
+pub fn import_selection(path: &Path) -> io::Result<Vec<SelectionEntry>> {
+    from_json(&fs::read_to_string(path)?)
+}
+
+/// Downloads whose keys are looked up, at most `count` of them evenly spread over the entries
+pub fn spot_checked(entries: &[SelectionEntry], count: usize) -> Vec<(String, String)> {
+    let downloads: Vec<(String, String)> = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            SelectionEntry::Download { bucket, key, .. } => Some((bucket.clone(), key.clone())),
+            SelectionEntry::Upload { .. } => None,
+        })
+        .collect();
+    if downloads.len() <= count {
+        return downloads;
+    }
+    (0..count)
+        .map(|i| downloads[i * downloads.len() / count].clone())
+        .collect()
+}
+
+/// What the import found missing for the current account
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportChecks {
+    pub missing_buckets: HashSet<String>,
+    /// Spot checked downloads whose object isn't there, by bucket and key
+    pub missing_keys: HashSet<(String, String)>,
+    /// Uploads whose local file isn't there
+    pub missing_files: HashSet<String>,
+}
+
+impl ImportChecks {
+    pub fn is_valid(&self, entry: &SelectionEntry) -> bool {
+        match entry {
+            SelectionEntry::Download { bucket, key, .. } => {
+                !self.missing_buckets.contains(bucket)
+                    && !self.missing_keys.contains(&(bucket.clone(), key.clone()))
+            }
+            SelectionEntry::Upload { path, bucket, .. } => {
+                !self.missing_buckets.contains(bucket) && !self.missing_files.contains(path)
+            }
+        }
+    }
+}
+
+/// Imported file with the checks of its entries, merged into the selection by the state
+#[derive(Debug, Clone)]
+pub struct SelectionImport {
+    pub path: String,
+    pub entries: Vec<SelectionEntry>,
+    pub checks: ImportChecks,
+}
+
+/// How the entries of an imported file were merged into the selection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+}
+
+impl ImportSummary {
+    pub fn describe(&self, path: &str) -> String {
+        format!(
+            "Imported {}: {} added, {} already selected, {} invalid",
+            path, self.added, self.duplicates, self.invalid
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn download(bucket: &str, key: &str) -> S3SelectedItem {
+        S3SelectedItem {
+            bucket: Some(bucket.into()),
+            name: key.rsplit('/').next().unwrap().into(),
+            path: Some(key.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/home/me/Downloads".into(),
+            transferred: false,
+            account: String::new(),
+            progress: 0f64,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        }
+    }
+
+    #[test]
+    fn selection_round_trips_without_the_credentials() {
+        let mut directory = download("logs", "2024/");
+        directory.is_directory = true;
+        directory.children = Some(vec![
+            download("logs", "2024/a.log"),
+            download("logs", "2024/b.log"),
+        ]);
+        let renamed = S3SelectedItem {
+            destination_path: Some("q1:final.csv".into()),
+            ..download("reports", "q1:final.csv")
+        };
+        let upload = LocalSelectedItem::new(
+            "notes.txt".into(),
+            "/home/me/notes.txt".into(),
+            false,
+            "backup".into(),
+            "docs/notes.txt".into(),
+            "personal".into(),
+            None,
+        );
+        let entries = selection_entries(&[directory, renamed], &[upload]);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(
+            entries[3],
+            SelectionEntry::Upload {
+                path: "/home/me/notes.txt".into(),
+                bucket: "backup".into(),
+                destination: "docs/notes.txt".into(),
+            }
+        );
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SELECTION_FILE_NAME);
+        export_selection(&path, &entries).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        // entries are imported with the current account
+        assert!(!content.contains("personal"));
+        assert!(content.contains("\"direction\": \"download\""));
+        assert_eq!(import_selection(&path).unwrap(), entries);
+    }
+
+    #[test]
+    fn changed_or_foreign_files_are_refused() {
+        let entries = vec![SelectionEntry::Download {
+            bucket: "logs".into(),
+            key: "a.log".into(),
+            destination: None,
+        }];
+        let json = to_json(&entries).unwrap();
+        let edited = json.replace("a.log", "b.log");
+        assert!(from_json(&edited)
+            .unwrap_err()
+            .to_string()
+            .contains("checksum"));
+        assert!(from_json("{\"entries\": []}").is_err());
+        assert!(from_json(&json.replace(FORMAT, "other")).is_err());
+    }
+
+    #[test]
+    fn spot_checks_are_spread_over_the_downloads() {
+        let entries: Vec<SelectionEntry> = (0..100)
+            .map(|i| SelectionEntry::Download {
+                bucket: "logs".into(),
+                key: format!("{}.log", i),
+                destination: None,
+            })
+            .collect();
+        let checked = spot_checked(&entries, 4);
+        let keys: Vec<&str> = checked.iter().map(|(_, key)| key.as_str()).collect();
+        assert_eq!(keys, vec!["0.log", "25.log", "50.log", "75.log"]);
+        assert_eq!(spot_checked(&entries[..3], 4).len(), 3);
+    }
+}
@@ -0,0 +1,188 @@
+//! This module provides the limits of the selections listed recursively, the listing stops
+//! as soon as one is exceeded so a huge prefix never ends up in memory
+use crate::model::s3_data_item::S3DataItem;
+use crate::settings::app_config::AppConfig;
+use crate::utils::format_bytes;
+use color_eyre::eyre;
+use futures::{Stream, TryStreamExt};
+
+/// Largest selection listed for a download or deletion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionLimit {
+    pub max_objects: usize,
+    /// Total length of the keys in bytes
+    pub max_key_bytes: u64,
+}
+
+impl SelectionLimit {
+    pub fn new(config: &AppConfig) -> Self {
+        SelectionLimit {
+            max_objects: config.max_selection_objects,
+            max_key_bytes: config.max_selection_key_mb * 1024 * 1024,
+        }
+    }
+}
+
+/// Objects of a selection listed until the end or until the limit was reached
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LimitedSelection {
+    pub items: Vec<S3DataItem>,
+    pub key_bytes: u64,
+    /// Why the listing was stopped, `items` then holds only the objects within the limit
+    pub exceeded: Option<String>,
+}
+
+impl LimitedSelection {
+    /// Adds the objects of the batch, returns false once the limit is exceeded
+    /// and the listing has to stop
+    pub fn extend(&mut self, batch: Vec<S3DataItem>, limit: SelectionLimit) -> bool {
+        for item in batch {
+            let key_bytes = item.path.len() as u64;
+            if self.items.len() >= limit.max_objects {
+                self.exceeded = Some(format!(
+                    "Selection exceeds {} objects - narrow the prefix or raise \
+                     max_selection_objects in settings",
+                    with_thousands_separators(limit.max_objects)
+                ));
+                return false;
+            }
+            if self.key_bytes + key_bytes > limit.max_key_bytes {
+                self.exceeded = Some(format!(
+                    "Selection exceeds {} of keys - narrow the prefix or raise \
+                     max_selection_key_mb in settings",
+                    format_bytes(limit.max_key_bytes)
+                ));
+                return false;
+            }
+            self.key_bytes += key_bytes;
+            self.items.push(item);
+        }
+        true
+    }
+}
+
+/// Collects the batches of a lazy listing, no more batches are requested once the limit
+/// is exceeded
+pub async fn collect_selection(
+    batches: impl Stream<Item = eyre::Result<Vec<S3DataItem>>>,
+    limit: SelectionLimit,
+) -> eyre::Result<LimitedSelection> {
+    let mut selection = LimitedSelection::default();
+    tokio::pin!(batches);
+    while let Some(batch) = batches.try_next().await? {
+        if !selection.extend(batch, limit) {
+            tracing::warn!(
+                "Recursive listing stopped after {} objects: {:?}",
+                selection.items.len(),
+                selection.exceeded
+            );
+            break;
+        }
+    }
+    Ok(selection)
+}
+
+/// `1000000` as `1,000,000`
+pub fn with_thousands_separators(number: usize) -> String {
+    let digits = number.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::s3_data_item::BucketInfo;
+    use futures::stream::{self, StreamExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn object(key: &str) -> S3DataItem {
+        let bucket_info = BucketInfo {
+            bucket: Some("logs".into()),
+            region: None,
+            is_bucket: false,
+            creation_date: None,
+        };
+        S3DataItem::listed(bucket_info, key, "".into(), false)
+    }
+
+    /// Endless listing of pages of 1000 objects, the number of requested pages is counted
+    fn endless_listing(
+        requested: Arc<AtomicUsize>,
+    ) -> impl Stream<Item = eyre::Result<Vec<S3DataItem>>> {
+        stream::iter(0..).map(move |page| {
+            requested.fetch_add(1, Ordering::Relaxed);
+            Ok((0..1000)
+                .map(|i| object(&format!("logs/{}/{}.log", page, i)))
+                .collect())
+        })
+    }
+
+    #[tokio::test]
+    async fn listing_stops_at_the_object_limit() {
+        let requested = Arc::new(AtomicUsize::new(0));
+        let limit = SelectionLimit {
+            max_objects: 2500,
+            max_key_bytes: u64::MAX,
+        };
+        let selection = collect_selection(endless_listing(requested.clone()), limit)
+            .await
+            .unwrap();
+        assert_eq!(selection.items.len(), 2500);
+        assert_eq!(requested.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            selection.exceeded.as_deref(),
+            Some(
+                "Selection exceeds 2,500 objects - narrow the prefix or raise \
+                 max_selection_objects in settings"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn listing_stops_at_the_key_bytes_limit() {
+        let requested = Arc::new(AtomicUsize::new(0));
+        let limit = SelectionLimit {
+            max_objects: usize::MAX,
+            max_key_bytes: 1024 * 1024,
+        };
+        let selection = collect_selection(endless_listing(requested.clone()), limit)
+            .await
+            .unwrap();
+        assert!(selection.key_bytes <= limit.max_key_bytes);
+        assert!(selection.items.len() < requested.load(Ordering::Relaxed) * 1000);
+        assert!(selection
+            .exceeded
+            .is_some_and(|message| message.contains("max_selection_key_mb")));
+    }
+
+    #[tokio::test]
+    async fn listing_within_the_limits_is_complete() {
+        let batches = stream::iter(vec![
+            Ok(vec![object("a.log"), object("b.log")]),
+            Ok(vec![object("c.log")]),
+        ]);
+        let limit = SelectionLimit {
+            max_objects: 3,
+            max_key_bytes: 15,
+        };
+        let selection = collect_selection(batches, limit).await.unwrap();
+        assert_eq!(selection.items.len(), 3);
+        assert_eq!(selection.key_bytes, 15);
+        assert_eq!(selection.exceeded, None);
+    }
+
+    #[test]
+    fn numbers_get_thousands_separators() {
+        assert_eq!(with_thousands_separators(1_000_000), "1,000,000");
+        assert_eq!(with_thousands_separators(12_345), "12,345");
+        assert_eq!(with_thousands_separators(999), "999");
+    }
+}
@@ -0,0 +1,228 @@
+//! This module provides the background work started with the application. The steps run one
+//! after the other, the bucket list of the current account first, so keys pressed right after
+//! the start aren't waiting behind a burst of requests to slow endpoints
+use crate::settings::file_credentials::FileCredential;
+use crate::utils::format_duration;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+
+/// Pause before the check of each of the other accounts
+pub const STARTUP_STEP_DELAY: Duration = Duration::from_millis(500);
+
+/// Work done once after the start
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartupStep {
+    /// The bucket list of the current account shown on the s3 panel
+    BucketList,
+    /// Lists the buckets of the account to tell whether its credentials work
+    AccountCheck(String),
+    /// Waits `STARTUP_STEP_DELAY` before the next step
+    Pause,
+}
+
+/// Result of the check of an account, shown on the accounts page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountCheck {
+    Passed,
+    Failed(String),
+}
+
+impl AccountCheck {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AccountCheck::Passed => "ok",
+            AccountCheck::Failed(_) => "failed",
+        }
+    }
+}
+
+/// The bucket list and the check of the current account, then the checks of the other
+/// accounts by their name, each one after a pause
+pub fn startup_order(accounts: &[FileCredential], current: &str) -> Vec<StartupStep> {
+    if accounts.is_empty() {
+        return Vec::new();
+    }
+    let mut others: Vec<&str> = accounts
+        .iter()
+        .map(|account| account.name.as_str())
+        .filter(|name| *name != current)
+        .collect();
+    others.sort_by_key(|name| (name.to_lowercase(), *name));
+    others.dedup();
+    let mut steps = vec![
+        StartupStep::BucketList,
+        StartupStep::AccountCheck(current.to_string()),
+    ];
+    for name in others {
+        steps.extend([
+            StartupStep::Pause,
+            StartupStep::AccountCheck(name.to_string()),
+        ]);
+    }
+    steps
+}
+
+/// Runs the steps in their order. The bucket list is listed by the s3 panel, `listed` resolves
+/// once it's shown. Every check is given up after `timeout`, the results are sent as they come
+pub async fn run_startup<F, Fut>(
+    steps: Vec<StartupStep>,
+    listed: oneshot::Receiver<()>,
+    timeout: Duration,
+    check: F,
+    results: UnboundedSender<(String, AccountCheck)>,
+) where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let mut listed = Some(listed);
+    for step in steps {
+        match step {
+            StartupStep::BucketList => {
+                // a dropped sender means the listing won't be shown, the checks go on
+                if let Some(listed) = listed.take() {
+                    let _ = listed.await;
+                }
+            }
+            StartupStep::Pause => tokio::time::sleep(STARTUP_STEP_DELAY).await,
+            StartupStep::AccountCheck(account) => {
+                let outcome = match tokio::time::timeout(timeout, check(account.clone())).await {
+                    Ok(Ok(())) => AccountCheck::Passed,
+                    Ok(Err(e)) => AccountCheck::Failed(e),
+                    Err(_) => AccountCheck::Failed(format!(
+                        "no response within {}",
+                        format_duration(timeout)
+                    )),
+                };
+                if results.send((account, outcome)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::task_registry::{TaskRegistry, STARTUP_TASK};
+    use std::time::Instant;
+    use tokio::sync::mpsc;
+
+    fn account(name: &str) -> FileCredential {
+        FileCredential {
+            name: name.into(),
+            ..FileCredential::default()
+        }
+    }
+
+    #[test]
+    fn current_account_goes_first_then_the_others_by_name() {
+        let accounts = [
+            account("prod"),
+            account("Backup"),
+            account("dev"),
+            account("archive"),
+        ];
+        assert_eq!(
+            startup_order(&accounts, "dev"),
+            vec![
+                StartupStep::BucketList,
+                StartupStep::AccountCheck("dev".into()),
+                StartupStep::Pause,
+                StartupStep::AccountCheck("archive".into()),
+                StartupStep::Pause,
+                StartupStep::AccountCheck("Backup".into()),
+                StartupStep::Pause,
+                StartupStep::AccountCheck("prod".into()),
+            ]
+        );
+        assert_eq!(startup_order(&accounts[..1], "prod").len(), 2);
+        assert!(startup_order(&[], "").is_empty());
+    }
+
+    #[tokio::test]
+    async fn checks_wait_for_the_bucket_list_and_are_bounded() {
+        let (listed_tx, listed_rx) = oneshot::channel();
+        let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+        let steps = vec![
+            StartupStep::BucketList,
+            StartupStep::AccountCheck("dev".into()),
+            StartupStep::AccountCheck("slow".into()),
+        ];
+        tokio::spawn(run_startup(
+            steps,
+            listed_rx,
+            Duration::from_secs(1),
+            |account| async move {
+                if account == "slow" {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok(())
+            },
+            results_tx,
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(
+            results_rx.try_recv().is_err(),
+            "Checks should wait for the bucket list"
+        );
+
+        listed_tx.send(()).unwrap();
+        assert_eq!(
+            results_rx.recv().await,
+            Some(("dev".into(), AccountCheck::Passed))
+        );
+        assert_eq!(
+            results_rx.recv().await,
+            Some((
+                "slow".into(),
+                AccountCheck::Failed("no response within 1s".into())
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn actions_are_handled_while_the_startup_work_runs() {
+        let (listed_tx, listed_rx) = oneshot::channel();
+        let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let accounts = [account("dev"), account("prod"), account("backup")];
+        let mut tasks = TaskRegistry::new();
+        tasks.spawn(STARTUP_TASK, move |_| {
+            run_startup(
+                startup_order(&accounts, "dev"),
+                listed_rx,
+                Duration::from_secs(5),
+                |_| async {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    Ok(())
+                },
+                results_tx,
+            )
+        });
+        listed_tx.send(()).unwrap();
+
+        // keys pressed while the checks run are handled the way the state store loop does
+        let started = Instant::now();
+        let mut handled = Vec::new();
+        for key in ['j', 'k', 'q'] {
+            action_tx.send(key).unwrap();
+            tokio::select! {
+                Some(key) = action_rx.recv() => handled.push(key),
+                Some(result) = results_rx.recv() => panic!("{:?} came before the key", result),
+            }
+        }
+        assert_eq!(handled, vec!['j', 'k', 'q']);
+        assert!(started.elapsed() < Duration::from_millis(300));
+
+        // switching the account or starting transfers stops the remaining checks
+        assert_eq!(
+            results_rx.recv().await.map(|(account, _)| account),
+            Some("dev".into())
+        );
+        tasks.abort(STARTUP_TASK);
+        assert_eq!(results_rx.recv().await, None);
+    }
+}
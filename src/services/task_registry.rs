@@ -0,0 +1,199 @@
+//! This module provides bookkeeping for the background tasks started by the state store
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Key of the task listing the content of the s3 panel
+pub const S3_LISTING_TASK: &str = "s3-listing";
+
+/// Key of the work started with the application, see `startup_work`
+pub const STARTUP_TASK: &str = "startup";
+
+/// Key of the task resolving the regions of the listed buckets
+pub const BUCKET_REGIONS_TASK: &str = "bucket-regions";
+
+/// Number of requests to s3 currently running, shared by the tasks making them
+#[derive(Debug, Clone, Default)]
+pub struct InFlightRequests {
+    count: Arc<AtomicUsize>,
+}
+
+/// Counts one request until dropped, also when the task holding it is aborted
+#[derive(Debug)]
+pub struct InFlightGuard {
+    count: Arc<AtomicUsize>,
+}
+
+impl InFlightRequests {
+    pub fn start(&self) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard {
+            count: self.count.clone(),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Keeps the most recent task started for every key, so it can be aborted when superseded
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: HashMap<String, (u64, JoinHandle<()>)>,
+    last_id: u64,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        TaskRegistry::default()
+    }
+
+    /// Spawns the task created by `make_task` under the given key, aborting the task
+    /// previously registered for the same key. The task receives its request id which
+    /// is also returned to the caller
+    pub fn spawn<F, Fut>(&mut self, key: &str, make_task: F) -> u64
+    where
+        F: FnOnce(u64) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.abort(key);
+        self.last_id += 1;
+        let id = self.last_id;
+        let handle = tokio::spawn(make_task(id));
+        self.tasks.insert(key.to_string(), (id, handle));
+        id
+    }
+
+    /// Spawns a task which is never superseded, it's only aborted by `cancel_all`
+    pub fn spawn_background<Fut>(&mut self, task: Fut) -> u64
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.retain(|_, (_, handle)| !handle.is_finished());
+        let key = format!("background-{}", self.last_id + 1);
+        self.spawn(&key, move |_| task)
+    }
+
+    pub fn abort(&mut self, key: &str) {
+        if let Some((id, handle)) = self.tasks.remove(key) {
+            if !handle.is_finished() {
+                tracing::info!("Aborting task {} with request id {}", key, id);
+                handle.abort();
+            }
+        }
+    }
+
+    /// Aborts every registered task, returns how many of them were still running
+    pub fn cancel_all(&mut self) -> usize {
+        let mut running = 0;
+        for (key, (id, handle)) in self.tasks.drain() {
+            if !handle.is_finished() {
+                tracing::info!("Cancelling task {} with request id {}", key, id);
+                handle.abort();
+                running += 1;
+            }
+        }
+        running
+    }
+
+    /// Request id of the newest task registered under the key
+    pub fn latest_id(&self, key: &str) -> Option<u64> {
+        self.tasks.get(key).map(|(id, _)| *id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn superseded_task_is_aborted_before_sending_its_result() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<(u64, &str)>();
+        let mut registry = TaskRegistry::new();
+        let slow_tx = tx.clone();
+        let first = registry.spawn(S3_LISTING_TASK, move |id| async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let _ = slow_tx.send((id, "parent"));
+        });
+        let second = registry.spawn(S3_LISTING_TASK, move |id| async move {
+            let _ = tx.send((id, "child"));
+        });
+
+        assert_ne!(first, second);
+        assert_eq!(registry.latest_id(S3_LISTING_TASK), Some(second));
+        assert_eq!(rx.recv().await, Some((second, "child")));
+        // the sender of the aborted task is dropped, so the channel closes without its result
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn tasks_with_different_keys_are_independent() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<&str>();
+        let mut registry = TaskRegistry::new();
+        let first_tx = tx.clone();
+        registry.spawn("first", move |_| async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let _ = first_tx.send("first");
+        });
+        registry.spawn("second", move |_| async move {
+            let _ = tx.send("second");
+        });
+
+        assert_eq!(rx.recv().await, Some("second"));
+        assert_eq!(rx.recv().await, Some("first"));
+    }
+
+    #[tokio::test]
+    async fn cancel_all_stops_the_running_tasks() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<&str>();
+        let mut registry = TaskRegistry::new();
+        let in_flight = InFlightRequests::default();
+        for _ in 0..3 {
+            let tx = tx.clone();
+            let in_flight = in_flight.clone();
+            registry.spawn_background(async move {
+                let _request = in_flight.start();
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                let _ = tx.send("finished");
+            });
+        }
+        let listing = in_flight.clone();
+        registry.spawn(S3_LISTING_TASK, move |_| async move {
+            let _request = listing.start();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            let _ = tx.send("listed");
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(in_flight.count(), 4);
+
+        assert_eq!(registry.cancel_all(), 4);
+        // the senders are dropped together with the aborted tasks
+        assert_eq!(rx.recv().await, None);
+        assert_eq!(in_flight.count(), 0);
+        assert_eq!(registry.latest_id(S3_LISTING_TASK), None);
+        assert_eq!(registry.cancel_all(), 0);
+    }
+
+    #[tokio::test]
+    async fn finished_background_tasks_are_forgotten() {
+        let mut registry = TaskRegistry::new();
+        registry.spawn_background(async {});
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        registry.spawn_background(async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        assert_eq!(registry.tasks.len(), 1);
+        assert_eq!(registry.cancel_all(), 1);
+    }
+}
@@ -0,0 +1,115 @@
+//! This module provides stopping single transfers of a run. A paused transfer is run again
+//! once resumed, a cancelled one is cleaned up and stays in the list until it's run again
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Why a transfer stopped before it finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferStop {
+    /// The download keeps its partial file to continue from, the upload starts over
+    Paused,
+    /// The partial file of the download is removed, the parts of the upload are aborted
+    Cancelled,
+}
+
+/// Stops requested for the transfers, by the key of their job (see `TransferJob::key`)
+#[derive(Debug, Clone, Default)]
+pub struct TransferControls {
+    stops: Arc<Mutex<HashMap<String, watch::Sender<Option<TransferStop>>>>>,
+}
+
+impl TransferControls {
+    /// Receiver of the stop of the starting transfer, a stop requested while the transfer
+    /// was waiting for its turn is already in it
+    pub fn watch(&self, key: &str) -> watch::Receiver<Option<TransferStop>> {
+        self.stops
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    pub fn stop(&self, key: &str, stop: TransferStop) {
+        self.stops
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(|| watch::channel(None).0)
+            .send_replace(Some(stop));
+    }
+
+    /// Forgets the stop of the transfer before it runs again
+    pub fn release(&self, key: &str) {
+        self.stops.lock().unwrap().remove(key);
+    }
+
+    /// Forgets all the stops, e.g. after the whole network activity was stopped
+    pub fn clear(&self) {
+        self.stops.lock().unwrap().clear();
+    }
+}
+
+/// Runs the transfer until it finishes or until it's stopped, a stopped transfer is dropped
+/// in the middle of its requests
+pub async fn until_stopped<T>(
+    mut stop: watch::Receiver<Option<TransferStop>>,
+    transfer: impl Future<Output = T>,
+) -> Result<T, TransferStop> {
+    let stopped = async {
+        loop {
+            let requested = *stop.borrow_and_update();
+            if let Some(requested) = requested {
+                return requested;
+            }
+            if stop.changed().await.is_err() {
+                // released without a stop, the transfer runs to its end
+                return std::future::pending().await;
+            }
+        }
+    };
+    tokio::select! {
+        biased;
+        stop = stopped => Err(stop),
+        result = transfer => Ok(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn running_transfer_is_stopped() {
+        let controls = TransferControls::default();
+        let stop = controls.watch("↓bucket/a.txt");
+        let stopper = controls.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            stopper.stop("↓bucket/a.txt", TransferStop::Cancelled);
+        });
+        let result = until_stopped(stop, tokio::time::sleep(Duration::from_secs(5))).await;
+        assert_eq!(result, Err(TransferStop::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn transfer_paused_while_waiting_does_not_start() {
+        let controls = TransferControls::default();
+        controls.stop("↑/home/user/a.txt", TransferStop::Paused);
+        let mut started = false;
+        let result = until_stopped(controls.watch("↑/home/user/a.txt"), async {
+            started = true;
+        })
+        .await;
+        assert_eq!(result, Err(TransferStop::Paused));
+        assert!(!started);
+
+        // resumed, the next run isn't stopped
+        controls.release("↑/home/user/a.txt");
+        let result = until_stopped(controls.watch("↑/home/user/a.txt"), async { 42 }).await;
+        assert_eq!(result, Ok(42));
+    }
+}
@@ -0,0 +1,1111 @@
+//! This module provides saving the pending transfers into the data directory, the queue is saved
+//! in the background while the application runs and restored on the next start. The started
+//! multipart uploads are saved along with their uploaded parts so the next session can
+//! resume them
+use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::s3_selected_item::S3SelectedItem;
+use crate::model::state::State;
+use crate::services::checked_file::{read_checked, remove_checked, write_checked};
+use crate::settings::file_credentials::FileCredential;
+use crate::utils::get_data_dir;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Name of the file with the pending transfers inside the data directory
+pub const QUEUE_FILE: &str = "transfers.tsv";
+
+/// Changes of the queue are saved at most once in this interval
+pub const AUTOSAVE_DEBOUNCE: Duration = Duration::from_secs(3);
+
+/// While transfers run every progress update changes the state, so instead of checking
+/// the queue for changes on each of them it's saved on this interval
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+const HEADER: &str = "# s3tui pending transfers";
+
+/// Name of the file with the started multipart uploads inside the data directory
+pub const UPLOADS_FILE: &str = "uploads.tsv";
+
+const UPLOADS_HEADER: &str = "# s3tui multipart uploads";
+
+/// Transfers which haven't finished yet, the credentials are never written, the items refer
+/// to their account by its name
+#[derive(Debug, Clone, Default)]
+pub struct QueueSnapshot {
+    pub downloads: Vec<S3SelectedItem>,
+    pub uploads: Vec<LocalSelectedItem>,
+}
+
+impl QueueSnapshot {
+    /// Pending items of the selection, the transferred children of directories are left out
+    pub fn pending(downloads: &[S3SelectedItem], uploads: &[LocalSelectedItem]) -> Self {
+        QueueSnapshot {
+            downloads: downloads
+                .iter()
+                .filter(|item| !item.transferred)
+                .map(|item| S3SelectedItem {
+                    children: item
+                        .children
+                        .as_ref()
+                        .map(|c| c.iter().filter(|c| !c.transferred).cloned().collect()),
+                    completed_children: 0,
+                    ..item.clone()
+                })
+                .collect(),
+            uploads: uploads
+                .iter()
+                .filter(|item| !item.transferred)
+                .map(|item| LocalSelectedItem {
+                    children: item
+                        .children
+                        .as_ref()
+                        .map(|c| c.iter().filter(|c| !c.transferred).cloned().collect()),
+                    completed_children: 0,
+                    ..item.clone()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.downloads.is_empty() && self.uploads.is_empty()
+    }
+
+    /// Number of the top level items, the children of a directory aren't counted
+    pub fn len(&self) -> usize {
+        self.downloads.len() + self.uploads.len()
+    }
+
+    /// Moves out the items with any of the `accounts`, directories go with all their children
+    fn split_off_accounts(&mut self, accounts: &[String]) -> QueueSnapshot {
+        QueueSnapshot {
+            downloads: split_off_items(&mut self.downloads, accounts),
+            uploads: split_off_items(&mut self.uploads, accounts),
+        }
+    }
+
+    /// One line per item, children follow their directory
+    fn to_file_content(&self) -> String {
+        let mut content = format!("{}\n", HEADER);
+        for item in &self.downloads {
+            content.push_str(&download_line("download", item));
+            for child in item.children.iter().flatten() {
+                content.push_str(&download_line("download-child", child));
+            }
+        }
+        for item in &self.uploads {
+            content.push_str(&upload_line("upload", item));
+            for child in item.children.iter().flatten() {
+                content.push_str(&upload_line("upload-child", child));
+            }
+        }
+        content
+    }
+}
+
+/// Restored transfers, the ones of accounts missing from the credentials wait in `orphaned`
+/// until each of their accounts is remapped to another one or dropped
+#[derive(Debug, Clone, Default)]
+pub struct RestoredQueue {
+    pub queue: QueueSnapshot,
+    pub orphaned: QueueSnapshot,
+    /// Accounts of the saved transfers not found in the credentials, in the order of the queue
+    pub missing_accounts: Vec<String>,
+    /// Transfers dropped along with their missing account
+    pub skipped: usize,
+    /// Backup read because the saved queue was damaged
+    pub recovered_from: Option<PathBuf>,
+}
+
+impl RestoredQueue {
+    /// Number of the waiting transfers with the missing `account`
+    pub fn orphaned_by(&self, account: &str) -> usize {
+        let downloads = self.orphaned.downloads.iter();
+        let uploads = self.orphaned.uploads.iter();
+        downloads
+            .filter(|item| item_accounts(*item).any(|a| a == account))
+            .count()
+            + uploads
+                .filter(|item| item_accounts(*item).any(|a| a == account))
+                .count()
+    }
+
+    /// Moves the waiting transfers and children of the missing `account` to the `replacement`
+    /// account, without one they are dropped. The transfers left without a missing account
+    /// join the queue
+    pub fn remap(&mut self, account: &str, replacement: Option<&str>) {
+        self.missing_accounts.retain(|a| a != account);
+        self.skipped += remap_items(&mut self.orphaned.downloads, account, replacement)
+            + remap_items(&mut self.orphaned.uploads, account, replacement);
+        let waiting = self.orphaned.split_off_accounts(&self.missing_accounts);
+        let found = std::mem::replace(&mut self.orphaned, waiting);
+        self.queue.downloads.extend(found.downloads);
+        self.queue.uploads.extend(found.uploads);
+    }
+}
+
+/// Downloads and uploads of the saved queue, both are remapped the same way
+trait QueuedItem: Sized {
+    fn account(&self) -> &str;
+    fn account_mut(&mut self) -> &mut String;
+    fn children(&self) -> Option<&Vec<Self>>;
+    fn children_mut(&mut self) -> Option<&mut Vec<Self>>;
+}
+
+impl QueuedItem for S3SelectedItem {
+    fn account(&self) -> &str {
+        &self.account
+    }
+
+    fn account_mut(&mut self) -> &mut String {
+        &mut self.account
+    }
+
+    fn children(&self) -> Option<&Vec<Self>> {
+        self.children.as_ref()
+    }
+
+    fn children_mut(&mut self) -> Option<&mut Vec<Self>> {
+        self.children.as_mut()
+    }
+}
+
+impl QueuedItem for LocalSelectedItem {
+    fn account(&self) -> &str {
+        &self.account
+    }
+
+    fn account_mut(&mut self) -> &mut String {
+        &mut self.account
+    }
+
+    fn children(&self) -> Option<&Vec<Self>> {
+        self.children.as_ref()
+    }
+
+    fn children_mut(&mut self) -> Option<&mut Vec<Self>> {
+        self.children.as_mut()
+    }
+}
+
+/// Account names of the item and its children
+fn item_accounts<T: QueuedItem>(item: &T) -> impl Iterator<Item = &str> {
+    std::iter::once(item)
+        .chain(item.children().into_iter().flatten())
+        .map(|i| i.account())
+}
+
+fn split_off_items<T: QueuedItem>(items: &mut Vec<T>, accounts: &[String]) -> Vec<T> {
+    let (moved, kept): (Vec<T>, Vec<T>) = std::mem::take(items)
+        .into_iter()
+        .partition(|item| item_accounts(item).any(|a| accounts.iter().any(|m| m == a)));
+    *items = kept;
+    moved
+}
+
+/// Returns the number of the dropped top level items, a directory goes when none of its
+/// children is left
+fn remap_items<T: QueuedItem>(
+    items: &mut Vec<T>,
+    account: &str,
+    replacement: Option<&str>,
+) -> usize {
+    let remapped = |item: &T| item.account() == account;
+    let Some(replacement) = replacement else {
+        let before = items.len();
+        items.retain(|item| !remapped(item));
+        for item in items.iter_mut() {
+            if let Some(children) = item.children_mut() {
+                children.retain(|c| !remapped(c));
+            }
+        }
+        items.retain(|item| item.children().map_or(true, |c| !c.is_empty()));
+        return before - items.len();
+    };
+    for item in items.iter_mut() {
+        if remapped(item) {
+            *item.account_mut() = replacement.to_string();
+        }
+        for child in item.children_mut().into_iter().flatten() {
+            if remapped(child) {
+                *child.account_mut() = replacement.to_string();
+            }
+        }
+    }
+    0
+}
+
+fn download_line(kind: &str, item: &S3SelectedItem) -> String {
+    line(&[
+        kind,
+        &item.account,
+        item.bucket.as_deref().unwrap_or_default(),
+        item.path.as_deref().unwrap_or_default(),
+        &item.name,
+        flag(item.is_directory),
+        flag(item.is_bucket),
+        &item.destination_dir,
+        item.destination_path.as_deref().unwrap_or_default(),
+    ])
+}
+
+fn upload_line(kind: &str, item: &LocalSelectedItem) -> String {
+    line(&[
+        kind,
+        &item.account,
+        &item.name,
+        &item.path,
+        flag(item.is_directory),
+        &item.destination_bucket,
+        &item.destination_path,
+    ])
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+fn line(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+    format!("{}\n", fields.join("\t"))
+}
+
+/// Keys and paths can hold tabs and line breaks, they are written as `\t` and `\n`
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn optional(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.is_empty())
+}
+
+fn invalid(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+/// Parses the saved queue, the items get the credentials of their account. The ones of
+/// accounts missing from `creds` get a credential with only the name and wait in `orphaned`
+fn parse_queue(content: &str, creds: &[FileCredential]) -> io::Result<RestoredQueue> {
+    let mut restored = RestoredQueue::default();
+    for (index, text) in content.lines().enumerate() {
+        let number = index + 1;
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<String> = text.split('\t').map(unescape).collect();
+        let kind = fields[0].as_str();
+        let expected = match kind {
+            "download" | "download-child" => 9,
+            "upload" | "upload-child" => 7,
+            _ => return Err(invalid(number, &format!("unknown entry '{}'", kind))),
+        };
+        if fields.len() != expected {
+            return Err(invalid(number, &format!("expected {} fields", expected)));
+        }
+        let child = kind.ends_with("-child");
+        let account = fields[1].clone();
+        if !creds.iter().any(|c| c.name == account) && !restored.missing_accounts.contains(&account)
+        {
+            restored.missing_accounts.push(account.clone());
+        }
+        match kind {
+            "download" | "download-child" => {
+                let item = S3SelectedItem {
+                    bucket: optional(fields[2].clone()),
+                    path: optional(fields[3].clone()),
+                    name: fields[4].clone(),
+                    is_directory: fields[5] == "1",
+                    is_bucket: fields[6] == "1",
+                    destination_dir: fields[7].clone(),
+                    transferred: false,
+                    account,
+                    progress: 0f64,
+                    children: None,
+                    error: None,
+                    destination_path: optional(fields[8].clone()),
+                    verification: None,
+                    completed_children: 0,
+                };
+                match (child, restored.queue.downloads.last_mut()) {
+                    (false, _) => restored.queue.downloads.push(S3SelectedItem {
+                        children: (item.is_directory || item.is_bucket).then(Vec::new),
+                        ..item
+                    }),
+                    (true, Some(parent)) => parent.children.get_or_insert_with(Vec::new).push(item),
+                    (true, None) => return Err(invalid(number, "child without its directory")),
+                }
+            }
+            _ => {
+                let item = LocalSelectedItem::new(
+                    fields[2].clone(),
+                    fields[3].clone(),
+                    fields[4] == "1",
+                    fields[5].clone(),
+                    fields[6].clone(),
+                    account,
+                    None,
+                );
+                match (child, restored.queue.uploads.last_mut()) {
+                    (false, _) => restored.queue.uploads.push(LocalSelectedItem {
+                        children: item.is_directory.then(Vec::new),
+                        ..item
+                    }),
+                    (true, Some(parent)) => parent.children.get_or_insert_with(Vec::new).push(item),
+                    (true, None) => return Err(invalid(number, "child without its directory")),
+                }
+            }
+        }
+    }
+    restored.orphaned = restored
+        .queue
+        .split_off_accounts(&restored.missing_accounts);
+    Ok(restored)
+}
+
+/// File keeping the pending transfers between the sessions
+#[derive(Debug, Clone)]
+pub struct TransferPersistence {
+    path: PathBuf,
+}
+
+impl TransferPersistence {
+    pub fn new(path: PathBuf) -> Self {
+        TransferPersistence { path }
+    }
+
+    pub fn in_data_dir() -> Self {
+        Self::new(get_data_dir().join(QUEUE_FILE))
+    }
+
+    /// Replaces the saved queue, the previous one is kept as a backup, see `write_checked`
+    pub fn save(&self, snapshot: &QueueSnapshot) -> io::Result<()> {
+        write_checked(&self.path, &snapshot.to_file_content())
+    }
+
+    /// Removes the saved queue and its backups, nothing is done when there isn't any
+    pub fn discard(&self) -> io::Result<()> {
+        remove_checked(&self.path)
+    }
+
+    /// Saved queue, empty when nothing was saved yet. A damaged file is read from its backup
+    pub fn load(&self, creds: &[FileCredential]) -> io::Result<RestoredQueue> {
+        let Some(checked) = read_checked(&self.path)? else {
+            return Ok(RestoredQueue::default());
+        };
+        Ok(RestoredQueue {
+            recovered_from: checked.recovered_from,
+            ..parse_queue(&checked.content, creds)?
+        })
+    }
+}
+
+/// Multipart upload which was started but not completed, with the parts already uploaded.
+/// It's only resumed while the local file keeps the recorded size and modification time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumableUpload {
+    pub account: String,
+    pub bucket: String,
+    /// Key of the object as shown on the s3 panel
+    pub key: String,
+    pub path: String,
+    pub size: u64,
+    /// Modification time of the local file, see `modified_millis`
+    pub modified: u64,
+    pub part_size: u64,
+    pub upload_id: String,
+    /// ETags of the uploaded parts by their number
+    pub parts: BTreeMap<i32, String>,
+}
+
+impl ResumableUpload {
+    /// Whether it uploads the file of the item to the key of the item
+    pub fn is_upload_of(&self, item: &LocalSelectedItem) -> bool {
+        self.account == item.account
+            && self.bucket == item.destination_bucket
+            && self.key == item.destination_key()
+            && self.path == item.path
+    }
+
+    /// Item uploading the rest of the file, the transfer picks the recorded parts up
+    pub fn to_item(&self) -> LocalSelectedItem {
+        let name = Path::new(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.clone());
+        LocalSelectedItem::new(
+            name,
+            self.path.clone(),
+            false,
+            self.bucket.clone(),
+            self.key.clone(),
+            self.account.clone(),
+            None,
+        )
+    }
+
+    /// Number of the parts of the whole file
+    pub fn part_count(&self) -> u64 {
+        self.size.div_ceil(self.part_size.max(1))
+    }
+}
+
+/// Modification time recorded for a resumable upload, in milliseconds since the epoch
+pub fn modified_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn uploads_file_content(uploads: &[ResumableUpload]) -> String {
+    let mut content = format!("{}\n", UPLOADS_HEADER);
+    for upload in uploads {
+        content.push_str(&line(&[
+            "upload",
+            &upload.account,
+            &upload.bucket,
+            &upload.key,
+            &upload.path,
+            &upload.size.to_string(),
+            &upload.modified.to_string(),
+            &upload.part_size.to_string(),
+            &upload.upload_id,
+        ]));
+        for (number, etag) in &upload.parts {
+            content.push_str(&line(&["part", &number.to_string(), etag]));
+        }
+    }
+    content
+}
+
+fn parse_uploads(content: &str) -> io::Result<Vec<ResumableUpload>> {
+    let mut uploads: Vec<ResumableUpload> = Vec::new();
+    for (index, text) in content.lines().enumerate() {
+        let number = index + 1;
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<String> = text.split('\t').map(unescape).collect();
+        let numeric = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| invalid(number, &format!("'{}' isn't a number", value)))
+        };
+        match (fields[0].as_str(), fields.len()) {
+            ("upload", 9) => uploads.push(ResumableUpload {
+                account: fields[1].clone(),
+                bucket: fields[2].clone(),
+                key: fields[3].clone(),
+                path: fields[4].clone(),
+                size: numeric(&fields[5])?,
+                modified: numeric(&fields[6])?,
+                part_size: numeric(&fields[7])?,
+                upload_id: fields[8].clone(),
+                parts: BTreeMap::new(),
+            }),
+            ("part", 3) => {
+                let part = fields[1].parse::<i32>().map_err(|_| {
+                    invalid(number, &format!("'{}' isn't a part number", fields[1]))
+                })?;
+                match uploads.last_mut() {
+                    Some(upload) => upload.parts.insert(part, fields[2].clone()),
+                    None => return Err(invalid(number, "part without its upload")),
+                };
+            }
+            ("upload", _) => return Err(invalid(number, "expected 9 fields")),
+            ("part", _) => return Err(invalid(number, "expected 3 fields")),
+            (kind, _) => return Err(invalid(number, &format!("unknown entry '{}'", kind))),
+        }
+    }
+    Ok(uploads)
+}
+
+/// Multipart uploads of the session, recorded as their parts are uploaded and shared by all
+/// the fetchers. The upload of a session killed in the middle stays saved for the next one
+#[derive(Debug, Clone)]
+pub struct ResumableUploads {
+    path: PathBuf,
+    uploads: Arc<Mutex<Vec<ResumableUpload>>>,
+}
+
+impl ResumableUploads {
+    pub fn new(path: PathBuf) -> Self {
+        ResumableUploads {
+            path,
+            uploads: Arc::default(),
+        }
+    }
+
+    pub fn in_data_dir() -> Self {
+        Self::new(get_data_dir().join(UPLOADS_FILE))
+    }
+
+    /// Uploads saved by the previous sessions, they are kept until they complete or are
+    /// forgotten. A damaged file is read from its backup
+    pub fn load(&self) -> io::Result<Vec<ResumableUpload>> {
+        let uploads = match read_checked(&self.path)? {
+            Some(checked) => parse_uploads(&checked.content)?,
+            None => Vec::new(),
+        };
+        *self.uploads.lock().unwrap() = uploads.clone();
+        Ok(uploads)
+    }
+
+    /// Saved upload of the file of the item, `None` when the file changed since
+    pub fn find(
+        &self,
+        item: &LocalSelectedItem,
+        size: u64,
+        modified: u64,
+    ) -> Option<ResumableUpload> {
+        self.uploads
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|upload| {
+                upload.is_upload_of(item) && upload.size == size && upload.modified == modified
+            })
+            .cloned()
+    }
+
+    /// Saves the started upload, an earlier upload of the same file and key is replaced
+    pub fn start(&self, started: ResumableUpload) {
+        self.change(|uploads| {
+            uploads.retain(|upload| {
+                (&upload.account, &upload.bucket, &upload.key, &upload.path)
+                    != (
+                        &started.account,
+                        &started.bucket,
+                        &started.key,
+                        &started.path,
+                    )
+            });
+            uploads.push(started);
+        });
+    }
+
+    /// Saves the ETag of the uploaded part
+    pub fn complete_part(&self, upload_id: &str, part: i32, etag: String) {
+        self.change(|uploads| {
+            if let Some(upload) = uploads.iter_mut().find(|u| u.upload_id == upload_id) {
+                upload.parts.insert(part, etag);
+            }
+        });
+    }
+
+    /// Replaces the saved parts of the upload with the checked ones
+    pub fn update(&self, checked: &ResumableUpload) {
+        self.change(|uploads| {
+            if let Some(upload) = uploads
+                .iter_mut()
+                .find(|u| u.upload_id == checked.upload_id)
+            {
+                upload.parts = checked.parts.clone();
+            }
+        });
+    }
+
+    /// Forgets the completed or aborted upload
+    pub fn remove(&self, upload_id: &str) {
+        self.change(|uploads| uploads.retain(|upload| upload.upload_id != upload_id));
+    }
+
+    /// Forgets the uploads of the file of the item
+    pub fn remove_item(&self, item: &LocalSelectedItem) {
+        self.change(|uploads| uploads.retain(|upload| !upload.is_upload_of(item)));
+    }
+
+    /// Applies the change and saves the uploads, a failed save is only logged since the
+    /// upload itself goes on
+    fn change(&self, apply: impl FnOnce(&mut Vec<ResumableUpload>)) {
+        let mut uploads = self.uploads.lock().unwrap();
+        let before = uploads.clone();
+        apply(&mut uploads);
+        if *uploads == before {
+            return;
+        }
+        let saved = if uploads.is_empty() {
+            remove_checked(&self.path)
+        } else {
+            write_checked(&self.path, &uploads_file_content(&uploads))
+        };
+        if let Err(e) = saved {
+            tracing::warn!("Cannot save the multipart uploads: {}", e);
+        }
+    }
+}
+
+/// Sends the queue to the writer task when it changes, the state store calls `update` after
+/// every action so it's kept cheap: the snapshot is only cloned when it's going to be saved
+pub struct Autosave {
+    tx: watch::Sender<QueueSnapshot>,
+    fingerprint: u64,
+    dirty: bool,
+    last_sent: Instant,
+}
+
+impl Autosave {
+    fn new() -> (Self, watch::Receiver<QueueSnapshot>) {
+        let (tx, rx) = watch::channel(QueueSnapshot::default());
+        let autosave = Autosave {
+            tx,
+            fingerprint: fingerprint(&[], &[]),
+            dirty: false,
+            last_sent: Instant::now(),
+        };
+        (autosave, rx)
+    }
+
+    /// Starts the task writing the snapshots, a slow write is followed only by the latest one
+    pub fn start(persistence: TransferPersistence) -> (Self, JoinHandle<()>) {
+        let (autosave, mut rx) = Self::new();
+        let writer = tokio::spawn(async move {
+            while rx.changed().await.is_ok() {
+                let snapshot = rx.borrow_and_update().clone();
+                let persistence = persistence.clone();
+                let saved = tokio::task::spawn_blocking(move || persistence.save(&snapshot)).await;
+                if let Ok(Err(e)) = saved {
+                    tracing::warn!("Cannot save the pending transfers: {}", e);
+                }
+            }
+        });
+        (autosave, writer)
+    }
+
+    pub fn update(&mut self, state: &State) {
+        // the saved queue keeps the transfers waiting for their missing account
+        if state.orphaned_transfers.is_some() {
+            return;
+        }
+        self.update_queue(
+            &state.s3_selected_items,
+            &state.local_selected_items,
+            state.has_transfers_in_progress(),
+        );
+    }
+
+    fn update_queue(
+        &mut self,
+        downloads: &[S3SelectedItem],
+        uploads: &[LocalSelectedItem],
+        transfers_active: bool,
+    ) {
+        let elapsed = self.last_sent.elapsed();
+        if transfers_active {
+            self.dirty |= elapsed >= AUTOSAVE_INTERVAL;
+        } else {
+            let fingerprint = fingerprint(downloads, uploads);
+            self.dirty |= fingerprint != self.fingerprint;
+            self.fingerprint = fingerprint;
+        }
+        if self.dirty && elapsed >= AUTOSAVE_DEBOUNCE {
+            self.send(QueueSnapshot::pending(downloads, uploads));
+        }
+    }
+
+    fn send(&mut self, snapshot: QueueSnapshot) {
+        self.tx.send_replace(snapshot);
+        self.dirty = false;
+        self.last_sent = Instant::now();
+    }
+
+    /// Sends the final queue right away and waits until the writer saves it
+    pub async fn finish(mut self, state: &State, writer: JoinHandle<()>) {
+        if state.orphaned_transfers.is_none() {
+            self.send(QueueSnapshot::pending(
+                &state.s3_selected_items,
+                &state.local_selected_items,
+            ));
+        }
+        drop(self);
+        let _ = writer.await;
+    }
+}
+
+/// Hash of the saved fields of the pending items, the progress isn't saved so it's left out
+fn fingerprint(downloads: &[S3SelectedItem], uploads: &[LocalSelectedItem]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for item in downloads.iter().filter(|item| !item.transferred) {
+        download_fields(item).hash(&mut hasher);
+        for child in item.children.iter().flatten().filter(|c| !c.transferred) {
+            download_fields(child).hash(&mut hasher);
+        }
+    }
+    for item in uploads.iter().filter(|item| !item.transferred) {
+        upload_fields(item).hash(&mut hasher);
+        for child in item.children.iter().flatten().filter(|c| !c.transferred) {
+            upload_fields(child).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn download_fields(item: &S3SelectedItem) -> impl Hash + '_ {
+    (
+        &item.account,
+        &item.bucket,
+        &item.path,
+        &item.destination_dir,
+        &item.destination_path,
+    )
+}
+
+fn upload_fields(item: &LocalSelectedItem) -> impl Hash + '_ {
+    (
+        &item.account,
+        &item.path,
+        &item.destination_bucket,
+        &item.destination_path,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn account(name: &str) -> FileCredential {
+        FileCredential {
+            name: name.into(),
+            secret_key: "secret".into(),
+            ..Default::default()
+        }
+    }
+
+    fn download(key: &str) -> S3SelectedItem {
+        S3SelectedItem {
+            bucket: Some("archive".into()),
+            name: key.rsplit('/').next().unwrap_or(key).into(),
+            path: Some(key.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/home/user".into(),
+            transferred: false,
+            account: "work".into(),
+            progress: 0f64,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        }
+    }
+
+    fn keys(queue: &QueueSnapshot) -> Vec<String> {
+        queue
+            .downloads
+            .iter()
+            .map(|d| d.path.clone().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn pending_transfers_round_trip_without_the_keys() {
+        let directory = tempdir().unwrap();
+        let persistence = TransferPersistence::new(directory.path().join(QUEUE_FILE));
+        let mut folder = S3SelectedItem {
+            is_directory: true,
+            children: Some(vec![download("logs/a\tb.txt"), download("logs/new\nline")]),
+            ..download("logs/")
+        };
+        folder.children.as_mut().unwrap()[0].transferred = true;
+        let done = S3SelectedItem {
+            transferred: true,
+            ..download("done.csv")
+        };
+        let upload = LocalSelectedItem::new(
+            "notes.md".into(),
+            "C:\\Users\\me\\notes.md".into(),
+            false,
+            "archive".into(),
+            "docs/notes.md".into(),
+            "home".into(),
+            None,
+        );
+        let snapshot = QueueSnapshot::pending(&[folder, done], &[upload]);
+        persistence.save(&snapshot).unwrap();
+
+        let content = fs::read_to_string(directory.path().join(QUEUE_FILE)).unwrap();
+        assert!(!content.contains("secret"));
+        let restored = persistence
+            .load(&[account("work"), account("home")])
+            .unwrap();
+        assert_eq!(restored.skipped, 0);
+        assert_eq!(keys(&restored.queue), vec!["logs/"]);
+        let children = restored.queue.downloads[0].children.clone().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].path, Some("logs/new\nline".into()));
+        assert_eq!(children[0].account, "work");
+        assert_eq!(restored.queue.uploads[0].path, "C:\\Users\\me\\notes.md");
+        assert_eq!(restored.queue.uploads[0].destination_path, "docs/notes.md");
+
+        let restored = persistence.load(&[account("home")]).unwrap();
+        assert_eq!(restored.missing_accounts, vec!["work"]);
+        assert_eq!(keys(&restored.orphaned), vec!["logs/"]);
+        assert_eq!(restored.queue.len(), 1);
+
+        persistence.discard().unwrap();
+        assert!(persistence
+            .load(&[account("home")])
+            .unwrap()
+            .queue
+            .is_empty());
+        persistence.discard().unwrap();
+    }
+
+    #[test]
+    fn transfers_of_missing_accounts_are_remapped_or_dropped() {
+        let directory = tempdir().unwrap();
+        let persistence = TransferPersistence::new(directory.path().join(QUEUE_FILE));
+        let of = |name: &str, item: S3SelectedItem| S3SelectedItem {
+            account: name.into(),
+            ..item
+        };
+        let folder = S3SelectedItem {
+            is_directory: true,
+            children: Some(vec![
+                of("old", download("logs/a.txt")),
+                of("old", download("logs/b.txt")),
+            ]),
+            ..of("old", download("logs/"))
+        };
+        let snapshot = QueueSnapshot {
+            downloads: vec![
+                folder,
+                of("gone", download("gone.csv")),
+                download("kept.csv"),
+            ],
+            uploads: Vec::new(),
+        };
+        persistence.save(&snapshot).unwrap();
+
+        // nothing waits while every account is still there
+        let restored = persistence
+            .load(&[account("work"), account("old"), account("gone")])
+            .unwrap();
+        assert!(restored.missing_accounts.is_empty());
+        assert!(restored.orphaned.is_empty());
+        assert_eq!(keys(&restored.queue), vec!["logs/", "gone.csv", "kept.csv"]);
+
+        // 'old' was renamed to 'new' and 'gone' was deleted
+        let mut restored = persistence
+            .load(&[account("work"), account("new")])
+            .unwrap();
+        assert_eq!(restored.missing_accounts, vec!["old", "gone"]);
+        assert_eq!(keys(&restored.queue), vec!["kept.csv"]);
+        assert_eq!(restored.orphaned_by("old"), 1);
+        assert_eq!(restored.orphaned.downloads[0].account, "old");
+
+        restored.remap("old", Some("new"));
+        assert_eq!(restored.missing_accounts, vec!["gone"]);
+        assert_eq!(keys(&restored.queue), vec!["kept.csv", "logs/"]);
+        let folder = &restored.queue.downloads[1];
+        assert_eq!(folder.account, "new");
+        assert!(folder.children.iter().flatten().all(|c| c.account == "new"));
+
+        restored.remap("gone", None);
+        assert!(restored.missing_accounts.is_empty());
+        assert!(restored.orphaned.is_empty());
+        assert_eq!(restored.skipped, 1);
+        assert_eq!(keys(&restored.queue), vec!["kept.csv", "logs/"]);
+    }
+
+    #[test]
+    fn missing_and_invalid_files() {
+        let directory = tempdir().unwrap();
+        let persistence = TransferPersistence::new(directory.path().join(QUEUE_FILE));
+        assert!(persistence.load(&[]).unwrap().queue.is_empty());
+        fs::write(directory.path().join(QUEUE_FILE), "# queue\nmove\twork\n").unwrap();
+        assert_eq!(
+            persistence.load(&[]).unwrap_err().to_string(),
+            "line 2: unknown entry 'move'"
+        );
+    }
+
+    #[test]
+    fn damaged_queue_is_restored_from_its_backup() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join(QUEUE_FILE);
+        let persistence = TransferPersistence::new(path.clone());
+        let queue = [download("a.txt"), download("b.txt")];
+        persistence
+            .save(&QueueSnapshot::pending(&queue[..1], &[]))
+            .unwrap();
+        persistence
+            .save(&QueueSnapshot::pending(&queue, &[]))
+            .unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0x01;
+        fs::write(&path, bytes).unwrap();
+
+        let restored = persistence.load(&[account("work")]).unwrap();
+        assert_eq!(keys(&restored.queue), vec!["a.txt"]);
+        assert_eq!(
+            restored.recovered_from,
+            Some(directory.path().join("transfers.tsv.1.bak"))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rapid_changes_are_saved_once_per_debounce() {
+        let (mut autosave, mut rx) = Autosave::new();
+        let mut queue = Vec::new();
+        let mut sent = 0;
+        // a change every 100 ms for 10 seconds
+        for i in 0..100 {
+            queue.push(download(&format!("file-{}", i)));
+            autosave.update_queue(&queue, &[], false);
+            if rx.has_changed().unwrap() {
+                rx.borrow_and_update();
+                sent += 1;
+            }
+            tokio::time::advance(Duration::from_millis(100)).await;
+        }
+        assert_eq!(sent, 3);
+        // unchanged queues aren't sent again, the last changes are once the debounce passes
+        tokio::time::advance(AUTOSAVE_DEBOUNCE).await;
+        autosave.update_queue(&queue, &[], false);
+        assert_eq!(rx.borrow_and_update().downloads.len(), 100);
+        tokio::time::advance(AUTOSAVE_DEBOUNCE).await;
+        autosave.update_queue(&queue, &[], false);
+        assert!(!rx.has_changed().unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_transfers_are_saved_on_the_interval() {
+        let (mut autosave, mut rx) = Autosave::new();
+        let queue = vec![download("big.iso")];
+        for _ in 0..25 {
+            autosave.update_queue(&queue, &[], true);
+            tokio::time::advance(Duration::from_secs(1)).await;
+        }
+        assert!(rx.has_changed().unwrap());
+        rx.borrow_and_update();
+        autosave.update_queue(&queue, &[], true);
+        assert!(!rx.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn queue_survives_a_killed_session() {
+        let directory = tempdir().unwrap();
+        let persistence = TransferPersistence::new(directory.path().join(QUEUE_FILE));
+        let (mut autosave, writer) = Autosave::start(persistence.clone());
+        autosave.last_sent -= AUTOSAVE_DEBOUNCE;
+        autosave.update_queue(&[download("a.txt"), download("b.txt")], &[], false);
+        // the store goes away without saving the queue on exit
+        drop(autosave);
+        writer.await.unwrap();
+
+        let restored = persistence.load(&[account("work")]).unwrap();
+        assert_eq!(keys(&restored.queue), vec!["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn uploaded_parts_survive_a_killed_session() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join(UPLOADS_FILE);
+        let started = ResumableUpload {
+            account: "work".into(),
+            bucket: "backup".into(),
+            key: "disks/vm\tone.img".into(),
+            path: "/tmp/vm\tone.img".into(),
+            size: 20,
+            modified: 1_700_000_000_000,
+            part_size: 8,
+            upload_id: "upload-1".into(),
+            parts: BTreeMap::new(),
+        };
+        let uploads = ResumableUploads::new(path.clone());
+        assert!(uploads.load().unwrap().is_empty());
+        uploads.start(started.clone());
+        uploads.complete_part("upload-1", 2, "\"b\"".into());
+        uploads.complete_part("upload-1", 1, "\"a\"".into());
+        uploads.complete_part("another", 3, "\"c\"".into());
+        // the session is killed before the last part
+        drop(uploads);
+
+        let uploads = ResumableUploads::new(path.clone());
+        let loaded = uploads.load().unwrap();
+        let resumed = ResumableUpload {
+            parts: BTreeMap::from([(1, "\"a\"".into()), (2, "\"b\"".into())]),
+            ..started.clone()
+        };
+        assert_eq!(loaded, vec![resumed.clone()]);
+        assert_eq!(resumed.part_count(), 3);
+        let item = resumed.to_item();
+        assert_eq!(item.name, "vm\tone.img");
+        assert_eq!(item.destination_key(), "disks/vm\tone.img");
+        assert_eq!(uploads.find(&item, 20, started.modified), Some(resumed));
+        // a changed file starts over
+        assert_eq!(uploads.find(&item, 21, started.modified), None);
+        assert_eq!(uploads.find(&item, 20, started.modified + 1), None);
+
+        uploads.update(&started);
+        assert!(uploads.load().unwrap()[0].parts.is_empty());
+        uploads.remove("upload-1");
+        assert!(!path.exists());
+        assert!(uploads.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_uploads_file() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join(UPLOADS_FILE);
+        fs::write(&path, "# uploads\npart\t1\t\"a\"\n").unwrap();
+        assert_eq!(
+            ResumableUploads::new(path.clone())
+                .load()
+                .unwrap_err()
+                .to_string(),
+            "line 2: part without its upload"
+        );
+        fs::write(
+            &path,
+            "upload\twork\tbackup\tkey\t/tmp/key\tbig\t0\t8\tid\n",
+        )
+        .unwrap();
+        assert_eq!(
+            ResumableUploads::new(path).load().unwrap_err().to_string(),
+            "line 1: 'big' isn't a number"
+        );
+    }
+}
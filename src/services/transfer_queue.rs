@@ -0,0 +1,472 @@
+//! This module provides the queue starting transfers in the order they are displayed
+use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::s3_selected_item::S3SelectedItem;
+use crate::settings::file_credentials::FileCredential;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Position of a transfer in the start order of its batch, `sequence` starts at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchOrder {
+    pub sequence: usize,
+    pub total: usize,
+}
+
+fn ordinal_suffix(n: usize) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+impl fmt::Display for DispatchOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "started {}{} of {}",
+            self.sequence,
+            ordinal_suffix(self.sequence),
+            self.total
+        )
+    }
+}
+
+/// Single file transfer, directories and buckets are expanded before queueing
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferJob {
+    Download(S3SelectedItem),
+    Upload(LocalSelectedItem),
+}
+
+impl TransferJob {
+    /// Builds the jobs in the order of the transfers list, downloads first,
+    /// children of a selected directory take its place in the list
+    pub fn queue(
+        s3_items: Vec<S3SelectedItem>,
+        local_items: Vec<LocalSelectedItem>,
+    ) -> Vec<TransferJob> {
+        let downloads = s3_items
+            .into_iter()
+            .flat_map(|item| match item.children.clone() {
+                Some(children) => children,
+                None => vec![item],
+            })
+            .filter(|item| !item.is_bucket && !item.is_directory)
+            .map(TransferJob::Download);
+        let uploads = local_items
+            .into_iter()
+            .flat_map(|item| match item.children.clone() {
+                Some(children) => children,
+                None => vec![item],
+            })
+            .filter(|item| !item.is_directory)
+            .map(TransferJob::Upload);
+        downloads.chain(uploads).collect()
+    }
+
+    /// Configured credential of the job, looked up by name so an edited or rotated credential
+    /// is used without selecting the item again. `None` when it is no longer configured
+    pub fn credentials<'a>(&self, creds: &'a [FileCredential]) -> Option<&'a FileCredential> {
+        creds.iter().find(|c| c.name == self.account())
+    }
+
+    /// Pairs the job with its credential, the job comes back as `Err` when the credential is no
+    /// longer configured
+    pub fn resolve(self, creds: &[FileCredential]) -> Result<ResolvedJob, Self> {
+        match self.credentials(creds) {
+            Some(found) => Ok(ResolvedJob {
+                creds: found.clone(),
+                job: self,
+            }),
+            None => Err(self),
+        }
+    }
+
+    /// Name of the credential the job runs with
+    pub fn account(&self) -> &str {
+        match self {
+            TransferJob::Download(item) => &item.account,
+            TransferJob::Upload(item) => &item.account,
+        }
+    }
+
+    pub fn key(&self) -> String {
+        match self {
+            TransferJob::Download(item) => download_key(item),
+            TransferJob::Upload(item) => upload_key(item),
+        }
+    }
+}
+
+/// Job of a started run together with the credential it runs with
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedJob {
+    pub job: TransferJob,
+    pub creds: FileCredential,
+}
+
+/// Identifies the download when its dispatch order is looked up
+pub fn download_key(item: &S3SelectedItem) -> String {
+    format!(
+        "↓{}/{}",
+        item.bucket.clone().unwrap_or_default(),
+        item.path.clone().unwrap_or(item.name.clone())
+    )
+}
+
+/// Identifies the upload when its dispatch order is looked up
+pub fn upload_key(item: &LocalSelectedItem) -> String {
+    format!("↑{}", item.path)
+}
+
+/// File descriptors counted for each running transfer: the local file, the connection to the
+/// endpoint and some headroom for dns lookups, connection reuse and the application itself
+const FILE_DESCRIPTORS_PER_TRANSFER: u64 = 4;
+
+/// Soft limit of open file descriptors of the process, `None` when it is unknown or unlimited
+#[cfg(unix)]
+pub fn fd_soft_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes into the struct passed to it
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    if limit.rlim_cur == libc::RLIM_INFINITY {
+        return None;
+    }
+    // rlim_t is signed on some platforms
+    #[allow(clippy::unnecessary_cast)]
+    Some(limit.rlim_cur as u64)
+}
+
+/// Windows has no per-process descriptor limit low enough to matter, the c runtime limit
+/// doesn't apply to the handles used by the transfers
+#[cfg(not(unix))]
+pub fn fd_soft_limit() -> Option<u64> {
+    None
+}
+
+/// Number of transfers allowed to run at once with the given descriptor limit,
+/// at least one transfer always runs
+pub fn effective_concurrency(configured: usize, fd_limit: Option<u64>) -> usize {
+    let configured = configured.max(1);
+    match fd_limit {
+        Some(limit) => {
+            let safe = (limit / FILE_DESCRIPTORS_PER_TRANSFER).max(1);
+            configured.min(usize::try_from(safe).unwrap_or(usize::MAX))
+        }
+        None => configured,
+    }
+}
+
+/// Caps the configured concurrency to the descriptor limit of the process, logging a warning
+/// when it had to be reduced
+pub fn safe_concurrency(configured: usize) -> usize {
+    let limit = fd_soft_limit();
+    let effective = effective_concurrency(configured, limit);
+    if effective < configured {
+        tracing::warn!(
+            "transfer_concurrency = {} would exceed the open files limit ({}), running {} transfers at once. Raise the limit with `ulimit -n` to use more",
+            configured,
+            limit.unwrap_or_default(),
+            effective
+        );
+    }
+    effective
+}
+
+/// Starts the jobs strictly in the given order with at most `concurrency` of them running.
+/// `start` is called for the next job only after a slot is free and all the previous jobs
+/// were started, so the start order doesn't depend on how the runtime schedules the tasks.
+/// Before that `admit` waits for the job to be allowed to run (e.g. for a slot of its account),
+/// what it returns is kept until the job finishes. Later jobs wait for an unadmitted one.
+/// It finishes with the last job, dropping it (e.g. aborting its task) aborts the running jobs
+pub async fn dispatch_in_order<T, A, AFut, P, F, Fut>(
+    jobs: Vec<T>,
+    concurrency: usize,
+    admit: A,
+    start: F,
+) where
+    T: Send + 'static,
+    A: Fn(&T) -> AFut + Send + 'static,
+    AFut: Future<Output = P> + Send,
+    P: Send + 'static,
+    F: Fn(DispatchOrder, T) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let total = jobs.len();
+    // the running jobs are aborted when the set is dropped
+    let mut running = JoinSet::new();
+    for (i, job) in jobs.into_iter().enumerate() {
+        let admission = admit(&job).await;
+        let permit = match semaphore.clone().acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => return,
+        };
+        let transfer = start(
+            DispatchOrder {
+                sequence: i + 1,
+                total,
+            },
+            job,
+        );
+        running.spawn(async move {
+            transfer.await;
+            drop(permit);
+            drop(admission);
+        });
+        while running.try_join_next().is_some() {}
+    }
+    while running.join_next().await.is_some() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::adaptive_concurrency::{AdaptiveConcurrency, RequestOutcome, MIN_SAMPLES};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn dispatch_order_is_displayed_as_ordinal() {
+        let order = |sequence| DispatchOrder {
+            sequence,
+            total: 58,
+        };
+        assert_eq!(order(1).to_string(), "started 1st of 58");
+        assert_eq!(order(2).to_string(), "started 2nd of 58");
+        assert_eq!(order(13).to_string(), "started 13th of 58");
+        assert_eq!(order(14).to_string(), "started 14th of 58");
+        assert_eq!(order(23).to_string(), "started 23rd of 58");
+    }
+
+    #[test]
+    fn concurrency_is_capped_by_the_open_files_limit() {
+        assert_eq!(effective_concurrency(8, Some(1024)), 8);
+        assert_eq!(effective_concurrency(1000, Some(1024)), 256);
+        assert_eq!(effective_concurrency(1000, Some(256)), 64);
+        assert_eq!(effective_concurrency(1000, None), 1000);
+        // a tiny limit still lets the transfers run, one at a time
+        assert_eq!(effective_concurrency(8, Some(3)), 1);
+        assert_eq!(effective_concurrency(0, Some(1024)), 1);
+    }
+
+    #[test]
+    fn queued_jobs_pick_up_rotated_credentials() {
+        let creds = |name: &str, secret: &str| FileCredential {
+            name: name.into(),
+            secret_key: secret.into(),
+            ..Default::default()
+        };
+        let job = TransferJob::Upload(LocalSelectedItem::new(
+            "a.txt".into(),
+            "/tmp/a.txt".into(),
+            false,
+            "bucket".into(),
+            "a.txt".into(),
+            "prod".into(),
+            None,
+        ));
+
+        let resolved = job.clone().resolve(&[creds("prod", "rotated")]).unwrap();
+        assert_eq!(resolved.creds.secret_key, "rotated");
+        assert_eq!(resolved.job, job);
+        let removed = [creds("dev", "old")];
+        assert_eq!(job.credentials(&removed), None);
+        assert_eq!(job.clone().resolve(&removed), Err(job));
+    }
+
+    #[test]
+    fn queue_follows_the_transfers_list_order() {
+        let s3_item = |name: &str| S3SelectedItem {
+            bucket: Some("bucket".into()),
+            name: name.into(),
+            path: Some(name.into()),
+            is_directory: false,
+            is_bucket: false,
+            destination_dir: "/tmp".into(),
+            transferred: false,
+            account: Default::default(),
+            progress: 0.0,
+            children: None,
+            error: None,
+            destination_path: None,
+            verification: None,
+            completed_children: 0,
+        };
+        let directory = S3SelectedItem {
+            is_directory: true,
+            children: Some(vec![s3_item("dir/a"), s3_item("dir/b")]),
+            ..s3_item("dir/")
+        };
+        let upload = LocalSelectedItem::new(
+            "c".into(),
+            "/home/c".into(),
+            false,
+            "bucket".into(),
+            "c".into(),
+            Default::default(),
+            None,
+        );
+
+        let keys: Vec<String> = TransferJob::queue(
+            vec![s3_item("first"), directory, s3_item("last")],
+            vec![upload],
+        )
+        .iter()
+        .map(TransferJob::key)
+        .collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                "↓bucket/first",
+                "↓bucket/dir/a",
+                "↓bucket/dir/b",
+                "↓bucket/last",
+                "↑/home/c"
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn jobs_start_in_queue_order_regardless_of_completion_order() {
+        // artificial latencies of a mocked store, early jobs finish last
+        let latencies: Vec<u64> = vec![60, 5, 40, 1, 25, 10, 3, 50, 2, 15];
+        let jobs: Vec<(usize, u64)> = latencies.iter().copied().enumerate().collect();
+        let (started_tx, mut started_rx) = mpsc::unbounded_channel::<(DispatchOrder, usize)>();
+        let (finished_tx, mut finished_rx) = mpsc::unbounded_channel::<usize>();
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let counters = (running.clone(), max_running.clone());
+        dispatch_in_order(
+            jobs.clone(),
+            3,
+            |_| async {},
+            move |order, (index, latency)| {
+                let now = counters.0.fetch_add(1, Ordering::SeqCst) + 1;
+                counters.1.fetch_max(now, Ordering::SeqCst);
+                let _ = started_tx.send((order, index));
+                let finished_tx = finished_tx.clone();
+                let running = counters.0.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(latency)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    let _ = finished_tx.send(index);
+                }
+            },
+        )
+        .await;
+
+        let mut started = Vec::new();
+        while let Some(event) = started_rx.recv().await {
+            started.push(event);
+        }
+        let mut finished = Vec::new();
+        while let Some(index) = finished_rx.recv().await {
+            finished.push(index);
+        }
+
+        let expected: Vec<(DispatchOrder, usize)> = (0..jobs.len())
+            .map(|i| {
+                (
+                    DispatchOrder {
+                        sequence: i + 1,
+                        total: jobs.len(),
+                    },
+                    i,
+                )
+            })
+            .collect();
+        assert_eq!(started, expected);
+        assert_eq!(finished.len(), jobs.len());
+        assert_ne!(finished, (0..jobs.len()).collect::<Vec<usize>>());
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn throttled_account_runs_fewer_jobs_at_once() {
+        let concurrency = AdaptiveConcurrency::default();
+        // an error burst of the on-prem gateway lowers its limit to 1
+        let warmup = concurrency.acquire("onprem".into(), 3).await;
+        for _ in 0..MIN_SAMPLES * 2 {
+            concurrency.record("onprem", RequestOutcome::Overloaded, Duration::ZERO);
+        }
+        drop(warmup);
+        let jobs: Vec<&'static str> = ["onprem", "aws"].repeat(4);
+        let running: Arc<Mutex<HashMap<&str, usize>>> = Arc::default();
+        let max_running: Arc<Mutex<HashMap<&str, usize>>> = Arc::default();
+
+        let admission = concurrency.clone();
+        let counters = (running.clone(), max_running.clone());
+        dispatch_in_order(
+            jobs,
+            3,
+            move |account: &&'static str| {
+                let admission = admission.clone();
+                let account = account.to_string();
+                async move { admission.acquire(account, 3).await }
+            },
+            move |_, account| {
+                let (running, max_running) = counters.clone();
+                async move {
+                    {
+                        let mut running = running.lock().unwrap();
+                        let now = running.entry(account).or_default();
+                        *now += 1;
+                        let mut max_running = max_running.lock().unwrap();
+                        let max = max_running.entry(account).or_default();
+                        *max = (*max).max(*now);
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    *running.lock().unwrap().get_mut(account).unwrap() -= 1;
+                }
+            },
+        )
+        .await;
+
+        let max_running = max_running.lock().unwrap();
+        assert_eq!(max_running["onprem"], 1);
+        assert!(max_running["aws"] >= 1);
+        assert_eq!(concurrency.loads()["onprem"].limit, 1);
+    }
+
+    #[tokio::test]
+    async fn aborting_the_dispatch_aborts_the_running_jobs() {
+        let (finished_tx, mut finished_rx) = mpsc::unbounded_channel::<usize>();
+        let started = Arc::new(AtomicUsize::new(0));
+        let counter = started.clone();
+        let dispatch = tokio::spawn(dispatch_in_order(
+            vec![0, 1, 2, 3],
+            2,
+            |_| async {},
+            move |_, index| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let finished_tx = finished_tx.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    let _ = finished_tx.send(index);
+                }
+            },
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        dispatch.abort();
+
+        assert_eq!(finished_rx.recv().await, None);
+        assert_eq!(started.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,817 @@
+use crate::model::verification::UploadVerification;
+use crate::services::local_names::NameSubstitution;
+use crate::settings::color_support::ColorSupport;
+use crate::settings::custom_command::CustomCommand;
+use crate::settings::default_prefix::DefaultPrefix;
+use crate::settings::proxy::{validate_proxy_url, ProxyOverride};
+use crate::settings::transfer_tuning::{TuningOverride, TUNING_KEYS};
+use crate::utils::get_config_dir;
+use color_eyre::eyre;
+use config::{Config, ConfigError};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+/// Name of the optional settings file inside the configuration directory
+pub const APP_CONFIG_FILE: &str = "config.toml";
+
+/// Longest validity of a presigned url accepted by s3
+const MAX_PRESIGNED_URL_EXPIRY_SECS: u64 = 7 * 24 * 3600;
+
+/// Application wide settings loaded from the `config.toml` file in your configuration directory
+/// Every value is optional, missing ones are replaced with the defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppConfig {
+    /// Maximum time for establishing a connection with the s3 endpoint
+    pub connect_timeout: Duration,
+    /// Maximum time for a single (non-streaming) s3 operation including retries
+    pub operation_timeout: Duration,
+    /// `.gitignore` style patterns of local files skipped when selecting directories
+    pub exclude_patterns: Vec<String>,
+    /// Colors used regardless of the terminal, `None` means they are detected
+    pub color_support: Option<ColorSupport>,
+    /// Proxy used by credentials without their own `proxy_url`/`no_proxy`
+    pub proxy: ProxyOverride,
+    /// Whether local paths differing only in case are the same file, used to detect
+    /// downloads overwriting each other
+    pub case_insensitive_paths: bool,
+    /// Log filter used when neither `RUST_LOG` nor `S3TUI_LOGLEVEL` is set,
+    /// a bare level like `debug` applies to s3tui only
+    pub log_level: Option<String>,
+    /// Number of daily log files kept
+    pub log_retention_days: usize,
+    /// Total size of the log files above which the oldest ones are removed at startup
+    pub log_max_total_mb: u64,
+    /// Address of the Prometheus `/metrics` endpoint, not started when `None`
+    pub metrics_listen: Option<SocketAddr>,
+    /// Allows the metrics endpoint on addresses other than loopback
+    pub metrics_allow_remote: bool,
+    /// Maximum simultaneous uploads/downloads, 4 by default, capped at startup to what the
+    /// open files limit of the process allows
+    pub transfer_concurrency: usize,
+    /// Part/range sizes and concurrency of the transfers of credentials without their own
+    pub transfer_tuning: TuningOverride,
+    /// Runs with more transfers than this ask for a confirmation first
+    pub confirm_batch_objects: usize,
+    /// Runs with more transfers than this need `yes` to be typed to start
+    pub typed_confirm_batch_objects: usize,
+    /// Selections listed recursively for a download or deletion stop above this many objects
+    pub max_selection_objects: usize,
+    /// ... or above this total length of their keys
+    pub max_selection_key_mb: u64,
+    /// Downloaded files get the modification time of the object, uploads store the local one
+    pub preserve_mtime: bool,
+    /// Check of the uploaded objects against their local files
+    pub upload_verification: UploadVerification,
+    /// Downloaded files are hashed and compared with the ETag or the sha-256 of the object
+    pub verify_downloads: bool,
+    /// Directory the downloads of credentials without their own are written to, the one
+    /// opened on the local panel when `None`
+    pub default_download_dir: Option<String>,
+    /// Directories opened instead of the bucket root, by account and bucket
+    pub default_prefixes: Vec<DefaultPrefix>,
+    /// Commands of the user run with their key on the highlighted row of the file manager
+    pub custom_commands: Vec<CustomCommand>,
+    /// Age of the bucket list kept for an account above which switching to the account lists
+    /// the buckets again
+    pub bucket_cache_ttl: Duration,
+    /// Downloads write a `manifest.csv` into their destination directory, the transfers page
+    /// switches it for the next runs
+    pub write_manifest: bool,
+    /// Uploads create the missing zero-byte markers of the folders above their key first,
+    /// e.g. `a/` and `a/b/` for `a/b/file`
+    pub create_folder_markers: bool,
+    /// A pasted single line ended with a line break submits the input, other pastes
+    /// only insert the text without line breaks
+    pub paste_submits_single_line: bool,
+    /// Bytes of the object read for the preview popup, larger objects are shown truncated
+    pub preview_max_kb: u64,
+    /// Largest object copied to the clipboard as text
+    pub clipboard_max_kb: u64,
+    /// How long the presigned urls copied from the metadata popup are valid
+    pub presigned_url_expiry: Duration,
+    /// Copied texts like the aws cli commands go to the clipboard through the terminal (OSC 52),
+    /// when `false` they are shown in a popup instead
+    pub osc52_clipboard: bool,
+    /// How the characters not allowed in Windows file names are replaced in the downloaded
+    /// file names, they are kept as they are when `None`
+    pub file_name_substitution: Option<NameSubstitution>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            connect_timeout: Duration::from_secs(5),
+            operation_timeout: Duration::from_secs(60),
+            exclude_patterns: Vec::new(),
+            color_support: None,
+            proxy: ProxyOverride::default(),
+            case_insensitive_paths: cfg!(any(target_os = "macos", target_os = "windows")),
+            log_level: None,
+            log_retention_days: 7,
+            log_max_total_mb: 100,
+            metrics_listen: None,
+            metrics_allow_remote: false,
+            transfer_concurrency: 4,
+            transfer_tuning: TuningOverride::default(),
+            confirm_batch_objects: 10_000,
+            typed_confirm_batch_objects: 100_000,
+            max_selection_objects: 1_000_000,
+            max_selection_key_mb: 256,
+            preserve_mtime: true,
+            upload_verification: UploadVerification::Off,
+            verify_downloads: true,
+            default_download_dir: None,
+            default_prefixes: Vec::new(),
+            custom_commands: Vec::new(),
+            bucket_cache_ttl: Duration::from_secs(300),
+            write_manifest: false,
+            create_folder_markers: false,
+            paste_submits_single_line: false,
+            preview_max_kb: 64,
+            clipboard_max_kb: 1024,
+            presigned_url_expiry: Duration::from_secs(3600),
+            osc52_clipboard: true,
+            file_name_substitution: if cfg!(target_os = "windows") {
+                Some(NameSubstitution::PercentEncode)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Loads the settings from the configuration directory, falling back to the defaults
+/// when the file cannot be parsed. The settings are read before logging is set up,
+/// so the error is returned to be logged by the caller
+pub fn load_app_config() -> (AppConfig, Option<String>) {
+    let path = get_config_dir().join(APP_CONFIG_FILE);
+    match load_app_config_from_file(path.as_path()) {
+        Ok(app_config) => (app_config, None),
+        Err(e) => (
+            AppConfig::default(),
+            Some(format!(
+                "Cannot read {:?}, using default settings: {}",
+                path, e
+            )),
+        ),
+    }
+}
+
+fn load_app_config_from_file(path: &Path) -> eyre::Result<AppConfig> {
+    let settings = Config::builder()
+        .add_source(config::File::from(path.to_path_buf()).required(false))
+        .build()?;
+    let defaults = AppConfig::default();
+    Ok(AppConfig {
+        connect_timeout: read_seconds(&settings, "connect_timeout_secs", defaults.connect_timeout)?,
+        operation_timeout: read_seconds(
+            &settings,
+            "operation_timeout_secs",
+            defaults.operation_timeout,
+        )?,
+        exclude_patterns: match settings.get::<Vec<String>>("exclude_patterns") {
+            Ok(patterns) => patterns,
+            Err(ConfigError::NotFound(_)) => defaults.exclude_patterns,
+            Err(e) => return Err(e.into()),
+        },
+        color_support: match settings.get::<String>("colors") {
+            Ok(value) => ColorSupport::from_setting(&value).map_err(|e| eyre::eyre!(e))?,
+            Err(ConfigError::NotFound(_)) => defaults.color_support,
+            Err(e) => return Err(e.into()),
+        },
+        case_insensitive_paths: match settings.get::<bool>("case_insensitive_paths") {
+            Ok(value) => value,
+            Err(ConfigError::NotFound(_)) => defaults.case_insensitive_paths,
+            Err(e) => return Err(e.into()),
+        },
+        log_level: match settings.get::<String>("log_level") {
+            Ok(level) => Some(level),
+            Err(ConfigError::NotFound(_)) => defaults.log_level,
+            Err(e) => return Err(e.into()),
+        },
+        log_retention_days: match settings.get::<usize>("log_retention_days") {
+            Ok(days) => days,
+            Err(ConfigError::NotFound(_)) => defaults.log_retention_days,
+            Err(e) => return Err(e.into()),
+        },
+        log_max_total_mb: match settings.get::<u64>("log_max_total_mb") {
+            Ok(mb) => mb,
+            Err(ConfigError::NotFound(_)) => defaults.log_max_total_mb,
+            Err(e) => return Err(e.into()),
+        },
+        metrics_listen: match settings.get::<String>("metrics_listen") {
+            Ok(address) => Some(
+                address
+                    .parse()
+                    .map_err(|e| eyre::eyre!("invalid metrics_listen {:?}: {}", address, e))?,
+            ),
+            Err(ConfigError::NotFound(_)) => defaults.metrics_listen,
+            Err(e) => return Err(e.into()),
+        },
+        metrics_allow_remote: match settings.get::<bool>("metrics_allow_remote") {
+            Ok(allow) => allow,
+            Err(ConfigError::NotFound(_)) => defaults.metrics_allow_remote,
+            Err(e) => return Err(e.into()),
+        },
+        transfer_concurrency: match settings.get::<usize>("transfer_concurrency") {
+            Ok(0) => return Err(eyre::eyre!("transfer_concurrency must be at least 1")),
+            Ok(concurrency) => concurrency,
+            Err(ConfigError::NotFound(_)) => defaults.transfer_concurrency,
+            Err(e) => return Err(e.into()),
+        },
+        transfer_tuning: read_tuning(&settings)?,
+        confirm_batch_objects: match settings.get::<usize>("confirm_batch_objects") {
+            Ok(objects) => objects,
+            Err(ConfigError::NotFound(_)) => defaults.confirm_batch_objects,
+            Err(e) => return Err(e.into()),
+        },
+        typed_confirm_batch_objects: match settings.get::<usize>("typed_confirm_batch_objects") {
+            Ok(objects) => objects,
+            Err(ConfigError::NotFound(_)) => defaults.typed_confirm_batch_objects,
+            Err(e) => return Err(e.into()),
+        },
+        max_selection_objects: match settings.get::<usize>("max_selection_objects") {
+            Ok(0) => return Err(eyre::eyre!("max_selection_objects must be at least 1")),
+            Ok(objects) => objects,
+            Err(ConfigError::NotFound(_)) => defaults.max_selection_objects,
+            Err(e) => return Err(e.into()),
+        },
+        max_selection_key_mb: match settings.get::<u64>("max_selection_key_mb") {
+            Ok(0) => return Err(eyre::eyre!("max_selection_key_mb must be at least 1")),
+            Ok(mb) => mb,
+            Err(ConfigError::NotFound(_)) => defaults.max_selection_key_mb,
+            Err(e) => return Err(e.into()),
+        },
+        preserve_mtime: match settings.get::<bool>("preserve_mtime") {
+            Ok(preserve) => preserve,
+            Err(ConfigError::NotFound(_)) => defaults.preserve_mtime,
+            Err(e) => return Err(e.into()),
+        },
+        upload_verification: match settings.get::<String>("upload_verification") {
+            Ok(value) => UploadVerification::from_setting(&value).map_err(|e| eyre::eyre!(e))?,
+            Err(ConfigError::NotFound(_)) => defaults.upload_verification,
+            Err(e) => return Err(e.into()),
+        },
+        verify_downloads: match settings.get::<bool>("verify_downloads") {
+            Ok(verify) => verify,
+            Err(ConfigError::NotFound(_)) => defaults.verify_downloads,
+            Err(e) => return Err(e.into()),
+        },
+        default_download_dir: match settings.get::<String>("default_download_dir") {
+            Ok(dir) if Path::new(&dir).is_dir() => Some(dir),
+            Ok(dir) => {
+                return Err(eyre::eyre!(
+                    "default_download_dir {:?} is not a directory",
+                    dir
+                ))
+            }
+            Err(ConfigError::NotFound(_)) => defaults.default_download_dir,
+            Err(e) => return Err(e.into()),
+        },
+        write_manifest: match settings.get::<bool>("write_manifest") {
+            Ok(write) => write,
+            Err(ConfigError::NotFound(_)) => defaults.write_manifest,
+            Err(e) => return Err(e.into()),
+        },
+        create_folder_markers: match settings.get::<bool>("create_folder_markers") {
+            Ok(create) => create,
+            Err(ConfigError::NotFound(_)) => defaults.create_folder_markers,
+            Err(e) => return Err(e.into()),
+        },
+        paste_submits_single_line: match settings.get::<bool>("paste_submits_single_line") {
+            Ok(submits) => submits,
+            Err(ConfigError::NotFound(_)) => defaults.paste_submits_single_line,
+            Err(e) => return Err(e.into()),
+        },
+        preview_max_kb: match settings.get::<u64>("preview_max_kb") {
+            Ok(0) => return Err(eyre::eyre!("preview_max_kb must be at least 1")),
+            Ok(kb) => kb,
+            Err(ConfigError::NotFound(_)) => defaults.preview_max_kb,
+            Err(e) => return Err(e.into()),
+        },
+        clipboard_max_kb: match settings.get::<u64>("clipboard_max_kb") {
+            Ok(0) => return Err(eyre::eyre!("clipboard_max_kb must be at least 1")),
+            Ok(kb) => kb,
+            Err(ConfigError::NotFound(_)) => defaults.clipboard_max_kb,
+            Err(e) => return Err(e.into()),
+        },
+        presigned_url_expiry: match settings.get::<u64>("presigned_url_expiry_secs") {
+            Ok(secs) if !(1..=MAX_PRESIGNED_URL_EXPIRY_SECS).contains(&secs) => {
+                return Err(eyre::eyre!(
+                    "presigned_url_expiry_secs must be between 1 and {} (7 days)",
+                    MAX_PRESIGNED_URL_EXPIRY_SECS
+                ));
+            }
+            Ok(secs) => Duration::from_secs(secs),
+            Err(ConfigError::NotFound(_)) => defaults.presigned_url_expiry,
+            Err(e) => return Err(e.into()),
+        },
+        osc52_clipboard: match settings.get::<bool>("osc52_clipboard") {
+            Ok(enabled) => enabled,
+            Err(ConfigError::NotFound(_)) => defaults.osc52_clipboard,
+            Err(e) => return Err(e.into()),
+        },
+        file_name_substitution: match settings.get::<String>("file_name_substitution") {
+            Ok(value) => NameSubstitution::from_setting(&value).map_err(|e| eyre::eyre!(e))?,
+            Err(ConfigError::NotFound(_)) => defaults.file_name_substitution,
+            Err(e) => return Err(e.into()),
+        },
+        bucket_cache_ttl: read_seconds(
+            &settings,
+            "bucket_cache_ttl_secs",
+            defaults.bucket_cache_ttl,
+        )?,
+        default_prefixes: match settings.get::<Vec<HashMap<String, String>>>("default_prefixes") {
+            Ok(entries) => entries
+                .iter()
+                .map(DefaultPrefix::from_entry)
+                .collect::<Result<_, _>>()
+                .map_err(|e| eyre::eyre!(e))?,
+            Err(ConfigError::NotFound(_)) => defaults.default_prefixes,
+            Err(e) => return Err(e.into()),
+        },
+        custom_commands: match settings.get::<Vec<HashMap<String, String>>>("custom_commands") {
+            Ok(entries) => entries
+                .iter()
+                .map(CustomCommand::from_entry)
+                .collect::<Result<_, _>>()
+                .map_err(|e| eyre::eyre!(e))?,
+            Err(ConfigError::NotFound(_)) => defaults.custom_commands,
+            Err(e) => return Err(e.into()),
+        },
+        proxy: ProxyOverride {
+            proxy_url: match settings.get::<String>("proxy_url") {
+                Ok(url) => {
+                    validate_proxy_url(&url).map_err(|e| eyre::eyre!(e))?;
+                    Some(url)
+                }
+                Err(ConfigError::NotFound(_)) => defaults.proxy.proxy_url,
+                Err(e) => return Err(e.into()),
+            },
+            no_proxy: match settings.get::<Vec<String>>("no_proxy") {
+                Ok(hosts) => Some(hosts),
+                Err(ConfigError::NotFound(_)) => defaults.proxy.no_proxy,
+                Err(e) => return Err(e.into()),
+            },
+        },
+    })
+}
+
+fn read_tuning(settings: &Config) -> eyre::Result<TuningOverride> {
+    let mut tuning = TuningOverride::default();
+    for key in TUNING_KEYS {
+        match settings.get::<u64>(key) {
+            Ok(value) => tuning.set(key, value).map_err(|e| eyre::eyre!(e))?,
+            Err(ConfigError::NotFound(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(tuning)
+}
+
+fn read_seconds(settings: &Config, key: &str, default: Duration) -> eyre::Result<Duration> {
+    match settings.get::<u64>(key) {
+        Ok(seconds) => Ok(Duration::from_secs(seconds)),
+        Err(ConfigError::NotFound(_)) => Ok(default),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn missing_file_gives_default_settings() {
+        let dir = tempdir().unwrap();
+        let res = load_app_config_from_file(&dir.path().join(APP_CONFIG_FILE)).unwrap();
+        assert_eq!(res, AppConfig::default());
+    }
+
+    #[test]
+    fn timeouts_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(
+            &path,
+            "connect_timeout_secs = 2\noperation_timeout_secs = 30\n",
+        )
+        .unwrap();
+
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.connect_timeout, Duration::from_secs(2));
+        assert_eq!(res.operation_timeout, Duration::from_secs(30));
+        assert_eq!(res.bucket_cache_ttl, AppConfig::default().bucket_cache_ttl);
+
+        fs::write(&path, "bucket_cache_ttl_secs = 60\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path).unwrap().bucket_cache_ttl,
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn log_settings_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(
+            &path,
+            "log_level = \"debug\"\nlog_retention_days = 3\nlog_max_total_mb = 20\n",
+        )
+        .unwrap();
+
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.log_level, Some("debug".into()));
+        assert_eq!(res.log_retention_days, 3);
+        assert_eq!(res.log_max_total_mb, 20);
+    }
+
+    #[test]
+    fn metrics_address_is_validated() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "metrics_listen = \"127.0.0.1:9185\"\n").unwrap();
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.metrics_listen, Some("127.0.0.1:9185".parse().unwrap()));
+        assert!(!res.metrics_allow_remote);
+
+        fs::write(&path, "metrics_listen = \"localhost\"\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn partial_file_keeps_remaining_defaults() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "connect_timeout_secs = 10\n").unwrap();
+
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.connect_timeout, Duration::from_secs(10));
+        assert_eq!(
+            res.operation_timeout,
+            AppConfig::default().operation_timeout
+        );
+    }
+
+    #[test]
+    fn exclude_patterns_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "exclude_patterns = [\"*.tmp\", \"target/\"]\n").unwrap();
+
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.exclude_patterns, vec!["*.tmp", "target/"]);
+    }
+
+    #[test]
+    fn colors_override_is_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "colors = \"8\"\n").unwrap();
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.color_support, Some(ColorSupport::Basic));
+
+        fs::write(&path, "colors = \"plenty\"\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn proxy_is_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(
+            &path,
+            "proxy_url = \"http://proxy:3128\"\nno_proxy = [\"localhost\", \".internal\"]\n",
+        )
+        .unwrap();
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.proxy.proxy_url, Some("http://proxy:3128".to_string()));
+        assert_eq!(
+            res.proxy.no_proxy,
+            Some(vec!["localhost".to_string(), ".internal".to_string()])
+        );
+
+        fs::write(&path, "proxy_url = \"proxy:3128\"\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn case_sensitivity_of_local_paths_can_be_overridden() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "case_insensitive_paths = true\n").unwrap();
+        assert!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .case_insensitive_paths
+        );
+
+        fs::write(&path, "case_insensitive_paths = false\n").unwrap();
+        assert!(
+            !load_app_config_from_file(&path)
+                .unwrap()
+                .case_insensitive_paths
+        );
+    }
+
+    #[test]
+    fn transfer_concurrency_is_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .transfer_concurrency,
+            4
+        );
+
+        fs::write(&path, "transfer_concurrency = 32\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .transfer_concurrency,
+            32
+        );
+
+        fs::write(&path, "transfer_concurrency = 0\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn transfer_tuning_is_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "part_size_mb = 128\nparts_concurrency = 16\n").unwrap();
+        let tuning = load_app_config_from_file(&path).unwrap().transfer_tuning;
+        assert_eq!(tuning.part_size_mb, Some(128));
+        assert_eq!(tuning.parts_concurrency, Some(16));
+        assert_eq!(tuning.multipart_threshold_mb, None);
+
+        fs::write(&path, "range_size_mb = 0\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn batch_confirmation_thresholds_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(
+            &path,
+            "confirm_batch_objects = 500\ntyped_confirm_batch_objects = 5000\n",
+        )
+        .unwrap();
+
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.confirm_batch_objects, 500);
+        assert_eq!(res.typed_confirm_batch_objects, 5000);
+    }
+
+    #[test]
+    fn selection_limits_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "preserve_mtime = true\n").unwrap();
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.max_selection_objects, 1_000_000);
+        assert_eq!(res.max_selection_key_mb, 256);
+
+        fs::write(
+            &path,
+            "max_selection_objects = 50000\nmax_selection_key_mb = 16\n",
+        )
+        .unwrap();
+        let res = load_app_config_from_file(&path).unwrap();
+        assert_eq!(res.max_selection_objects, 50_000);
+        assert_eq!(res.max_selection_key_mb, 16);
+
+        fs::write(&path, "max_selection_objects = 0\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn manifest_is_off_unless_enabled() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "preserve_mtime = true\n").unwrap();
+        assert!(!load_app_config_from_file(&path).unwrap().write_manifest);
+
+        fs::write(&path, "write_manifest = true\n").unwrap();
+        assert!(load_app_config_from_file(&path).unwrap().write_manifest);
+    }
+
+    #[test]
+    fn folder_markers_are_created_only_when_enabled() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "write_manifest = true\n").unwrap();
+        assert!(
+            !load_app_config_from_file(&path)
+                .unwrap()
+                .create_folder_markers
+        );
+
+        fs::write(&path, "create_folder_markers = true\n").unwrap();
+        assert!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .create_folder_markers
+        );
+    }
+
+    #[test]
+    fn pastes_submit_only_when_enabled() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "write_manifest = false\n").unwrap();
+        assert!(
+            !load_app_config_from_file(&path)
+                .unwrap()
+                .paste_submits_single_line
+        );
+
+        fs::write(&path, "paste_submits_single_line = true\n").unwrap();
+        assert!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .paste_submits_single_line
+        );
+    }
+
+    #[test]
+    fn clipboard_can_be_switched_off() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "preview_max_kb = 64\n").unwrap();
+        assert!(load_app_config_from_file(&path).unwrap().osc52_clipboard);
+
+        fs::write(&path, "osc52_clipboard = false\n").unwrap();
+        assert!(!load_app_config_from_file(&path).unwrap().osc52_clipboard);
+    }
+
+    #[test]
+    fn preview_and_clipboard_sizes_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "preview_max_kb = 256\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path).unwrap().preview_max_kb,
+            256
+        );
+
+        fs::write(&path, "preview_max_kb = 0\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+
+        fs::write(&path, "clipboard_max_kb = 16\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path).unwrap().clipboard_max_kb,
+            16
+        );
+        fs::write(&path, "clipboard_max_kb = 0\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn presigned_url_expiry_is_limited_to_seven_days() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "presigned_url_expiry_secs = 600\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .presigned_url_expiry,
+            Duration::from_secs(600)
+        );
+        for secs in [0, MAX_PRESIGNED_URL_EXPIRY_SECS + 1] {
+            fs::write(&path, format!("presigned_url_expiry_secs = {}\n", secs)).unwrap();
+            assert!(load_app_config_from_file(&path).is_err(), "{}", secs);
+        }
+    }
+
+    #[test]
+    fn file_name_substitution_is_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "file_name_substitution = \"underscore\"\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .file_name_substitution,
+            Some(NameSubstitution::Underscore)
+        );
+
+        fs::write(&path, "file_name_substitution = \"none\"\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .file_name_substitution,
+            None
+        );
+
+        fs::write(&path, "file_name_substitution = \"dashes\"\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn upload_verification_is_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "upload_verification = \"full-readback\"\n").unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .upload_verification,
+            UploadVerification::FullReadback
+        );
+
+        fs::write(&path, "upload_verification = \"sha1\"\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn download_verification_can_be_turned_off() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "preserve_mtime = true\n").unwrap();
+        assert!(load_app_config_from_file(&path).unwrap().verify_downloads);
+
+        fs::write(&path, "verify_downloads = false\n").unwrap();
+        assert!(!load_app_config_from_file(&path).unwrap().verify_downloads);
+    }
+
+    #[test]
+    fn default_prefixes_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(
+            &path,
+            "[[default_prefixes]]\naccount = \"default\"\nbucket = \"data.example\"\nprefix = \"team/curated/v2/\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path).unwrap().default_prefixes,
+            vec![DefaultPrefix {
+                account: "default".into(),
+                bucket: "data.example".into(),
+                prefix: "team/curated/v2/".into(),
+            }]
+        );
+
+        fs::write(&path, "[[default_prefixes]]\nbucket = \"data\"\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn custom_commands_are_read_from_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(
+            &path,
+            "[[custom_commands]]\nname = \"etl\"\nkey = \"alt+e\"\ncommand = \"etl-submit {bucket} {key}\"\napplies_to = \"s3\"\n",
+        )
+        .unwrap();
+        let commands = load_app_config_from_file(&path).unwrap().custom_commands;
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].name, "etl");
+
+        fs::write(
+            &path,
+            "[[custom_commands]]\nname = \"etl\"\nkey = \"alt+e\"\ncommand = \"etl-submit {object}\"\n",
+        )
+        .unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn default_download_dir_must_be_a_directory() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(
+            &path,
+            format!("default_download_dir = '{}'\n", dir.path().display()),
+        )
+        .unwrap();
+        assert_eq!(
+            load_app_config_from_file(&path)
+                .unwrap()
+                .default_download_dir,
+            Some(dir.path().display().to_string())
+        );
+
+        fs::write(&path, "default_download_dir = '/nonexistent/downloads'\n").unwrap();
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn invalid_value_is_reported() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(APP_CONFIG_FILE);
+        fs::write(&path, "connect_timeout_secs = \"soon\"\n").unwrap();
+
+        assert!(load_app_config_from_file(&path).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+//! This module provides detection of the colors supported by the terminal
+//! and the mapping of the application colors to the ones the terminal can display
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+/// Accent color of the focused rows and table headers on true color terminals
+pub const FOCUS_COLOR: Color = Color::Rgb(98, 114, 164);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSupport {
+    #[default]
+    TrueColor,
+    Ansi256,
+    /// The 8 basic colors, e.g. the linux console
+    Basic,
+    /// Monochrome output requested with `NO_COLOR`
+    NoColor,
+}
+
+impl ColorSupport {
+    /// Parses the `colors` setting, `auto` gives `None` as the support is detected then
+    pub fn from_setting(value: &str) -> Result<Option<ColorSupport>, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Ok(None),
+            "truecolor" | "24bit" => Ok(Some(ColorSupport::TrueColor)),
+            "256" => Ok(Some(ColorSupport::Ansi256)),
+            "8" | "basic" => Ok(Some(ColorSupport::Basic)),
+            "none" | "mono" => Ok(Some(ColorSupport::NoColor)),
+            other => Err(format!(
+                "Unknown colors setting '{}', use auto, truecolor, 256, 8 or none",
+                other
+            )),
+        }
+    }
+}
+
+/// Guesses the color support from the environment, `var` returns the value of a variable.
+/// `NO_COLOR` wins over everything else, then `COLORTERM` and finally `TERM`
+pub fn detect_color_support<F>(var: F) -> ColorSupport
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let non_empty = |name: &str| var(name).filter(|v| !v.is_empty());
+    if non_empty("NO_COLOR").is_some() {
+        return ColorSupport::NoColor;
+    }
+    if let Some(colorterm) = non_empty("COLORTERM") {
+        let colorterm = colorterm.to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorSupport::TrueColor;
+        }
+    }
+    match non_empty("TERM") {
+        Some(term) if term == "dumb" => ColorSupport::NoColor,
+        Some(term) if term.contains("truecolor") || term.contains("direct") => {
+            ColorSupport::TrueColor
+        }
+        Some(term) if term.contains("256color") => ColorSupport::Ansi256,
+        _ => ColorSupport::Basic,
+    }
+}
+
+/// Index of the closest color of the 6x6x6 cube of the 256 color palette
+fn to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| ((c as u16 * 5 + 127) / 255) as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// Closest of the 8 basic colors, channels close to the brightest one are switched on
+fn to_basic(r: u8, g: u8, b: u8) -> Color {
+    let max = r.max(g).max(b) as u16;
+    if max < 64 {
+        return Color::Black;
+    }
+    let on = |c: u8| c as u16 * 4 >= max * 3;
+    match (on(r), on(g), on(b)) {
+        (true, false, false) => Color::Red,
+        (false, true, false) => Color::Green,
+        (false, false, true) => Color::Blue,
+        (true, true, false) => Color::Yellow,
+        (true, false, true) => Color::Magenta,
+        (false, true, true) => Color::Cyan,
+        _ => Color::White,
+    }
+}
+
+/// Basic color of the bright variant, gray text stays readable on the black background
+fn to_basic_named(color: Color) -> Color {
+    match color {
+        Color::LightRed => Color::Red,
+        Color::LightGreen => Color::Green,
+        Color::LightYellow => Color::Yellow,
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::LightCyan => Color::Cyan,
+        Color::Gray | Color::DarkGray => Color::White,
+        other => other,
+    }
+}
+
+/// Maps the color to one the terminal can display, the bright named colors become basic ones
+/// on 8 color terminals and every color is dropped for monochrome output
+pub fn adapt_color(color: Color, support: ColorSupport) -> Color {
+    match (support, color) {
+        (ColorSupport::NoColor, _) => Color::Reset,
+        (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(to_ansi256(r, g, b)),
+        (ColorSupport::Basic, Color::Rgb(r, g, b)) => to_basic(r, g, b),
+        (ColorSupport::Basic, Color::Indexed(i)) if i >= 16 => Color::Reset,
+        (ColorSupport::Basic, named) => to_basic_named(named),
+        _ => color,
+    }
+}
+
+/// Maps the colors of every cell of the drawn frame, so the colors the pages use directly are
+/// degraded the same way as the focus color. Without colors the cells with a background are
+/// reversed instead, e.g. the focused row stays visible
+pub fn adapt_buffer(buffer: &mut Buffer, support: ColorSupport) {
+    if support == ColorSupport::TrueColor {
+        return;
+    }
+    for cell in buffer.content.iter_mut() {
+        if support == ColorSupport::NoColor && cell.bg != Color::Reset {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = adapt_color(cell.fg, support);
+        cell.bg = adapt_color(cell.bg, support);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn detect(vars: &[(&str, &str)]) -> ColorSupport {
+        let env: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        detect_color_support(|name| env.get(name).cloned())
+    }
+
+    #[test]
+    fn environment_matrix() {
+        let cases: &[(&[(&str, &str)], ColorSupport)] = &[
+            (&[], ColorSupport::Basic),
+            (&[("TERM", "linux")], ColorSupport::Basic),
+            (&[("TERM", "xterm")], ColorSupport::Basic),
+            (&[("TERM", "xterm-256color")], ColorSupport::Ansi256),
+            (&[("TERM", "xterm-direct")], ColorSupport::TrueColor),
+            (&[("TERM", "dumb")], ColorSupport::NoColor),
+            (
+                &[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")],
+                ColorSupport::TrueColor,
+            ),
+            (
+                &[("TERM", "linux"), ("COLORTERM", "24bit")],
+                ColorSupport::TrueColor,
+            ),
+            (
+                &[("TERM", "xterm-256color"), ("COLORTERM", "yes")],
+                ColorSupport::Ansi256,
+            ),
+            (
+                &[("COLORTERM", "truecolor"), ("NO_COLOR", "1")],
+                ColorSupport::NoColor,
+            ),
+            (
+                &[("TERM", "xterm-256color"), ("NO_COLOR", "")],
+                ColorSupport::Ansi256,
+            ),
+        ];
+        for (vars, expected) in cases {
+            assert_eq!(detect(vars), *expected, "{:?}", vars);
+        }
+    }
+
+    #[test]
+    fn focus_color_is_degraded_for_each_support_level() {
+        assert_eq!(
+            adapt_color(FOCUS_COLOR, ColorSupport::TrueColor),
+            FOCUS_COLOR
+        );
+        assert_eq!(
+            adapt_color(FOCUS_COLOR, ColorSupport::Ansi256),
+            Color::Indexed(103)
+        );
+        assert_eq!(adapt_color(FOCUS_COLOR, ColorSupport::Basic), Color::Blue);
+        assert_eq!(
+            adapt_color(FOCUS_COLOR, ColorSupport::NoColor),
+            Color::Reset
+        );
+    }
+
+    #[test]
+    fn basic_palette_keeps_named_colors_and_maps_rgb() {
+        assert_eq!(adapt_color(Color::Red, ColorSupport::Basic), Color::Red);
+        assert_eq!(
+            adapt_color(Color::Indexed(200), ColorSupport::Basic),
+            Color::Reset
+        );
+        assert_eq!(
+            adapt_color(Color::Rgb(10, 10, 10), ColorSupport::Basic),
+            Color::Black
+        );
+        assert_eq!(
+            adapt_color(Color::Rgb(128, 128, 128), ColorSupport::Basic),
+            Color::White
+        );
+        assert_eq!(
+            adapt_color(Color::Rgb(230, 200, 40), ColorSupport::Basic),
+            Color::Yellow
+        );
+        assert_eq!(adapt_color(Color::Red, ColorSupport::NoColor), Color::Reset);
+        assert_eq!(
+            adapt_color(Color::LightGreen, ColorSupport::Basic),
+            Color::Green
+        );
+        assert_eq!(
+            adapt_color(Color::DarkGray, ColorSupport::Basic),
+            Color::White
+        );
+        assert_eq!(
+            adapt_color(Color::DarkGray, ColorSupport::Ansi256),
+            Color::DarkGray
+        );
+    }
+
+    #[test]
+    fn every_drawn_cell_is_degraded() {
+        use ratatui::layout::Rect;
+        use ratatui::style::Style;
+
+        let area = Rect::new(0, 0, 3, 1);
+        let drawn = || {
+            let mut buffer = Buffer::empty(area);
+            buffer.set_string(0, 0, "a", Style::default().fg(Color::LightYellow));
+            buffer.set_string(1, 0, "b", Style::default().bg(FOCUS_COLOR));
+            buffer.set_string(2, 0, "c", Style::default().fg(Color::Rgb(230, 40, 40)));
+            buffer
+        };
+
+        let mut buffer = drawn();
+        adapt_buffer(&mut buffer, ColorSupport::TrueColor);
+        assert_eq!(buffer, drawn());
+
+        let mut buffer = drawn();
+        adapt_buffer(&mut buffer, ColorSupport::Basic);
+        assert_eq!(buffer.get(0, 0).fg, Color::Yellow);
+        assert_eq!(buffer.get(1, 0).bg, Color::Blue);
+        assert_eq!(buffer.get(2, 0).fg, Color::Red);
+
+        let mut buffer = drawn();
+        adapt_buffer(&mut buffer, ColorSupport::NoColor);
+        assert!(buffer.content.iter().all(|cell| cell.fg == Color::Reset));
+        assert!(buffer.content.iter().all(|cell| cell.bg == Color::Reset));
+        assert!(buffer.get(1, 0).modifier.contains(Modifier::REVERSED));
+        assert!(!buffer.get(0, 0).modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn setting_overrides_are_parsed() {
+        assert_eq!(ColorSupport::from_setting("auto"), Ok(None));
+        assert_eq!(
+            ColorSupport::from_setting("8"),
+            Ok(Some(ColorSupport::Basic))
+        );
+        assert_eq!(
+            ColorSupport::from_setting("None"),
+            Ok(Some(ColorSupport::NoColor))
+        );
+        assert!(ColorSupport::from_setting("lots").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! This module provides the user's own commands run on the highlighted row, defined in the
+//! `[[custom_commands]]` entries of the config file
+use crate::settings::key_bindings::KeySpec;
+use std::collections::HashMap;
+
+/// Placeholders of the command templates, replaced with the values of the highlighted row
+const PLACEHOLDERS: &[&str] = &["bucket", "key", "local_path", "account"];
+
+/// Rows of the panels a custom command runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandRows {
+    S3,
+    Local,
+    Both,
+}
+
+impl CommandRows {
+    /// Parses the `applies_to` field of an entry
+    pub fn from_setting(value: &str) -> Result<CommandRows, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "s3" => Ok(CommandRows::S3),
+            "local" => Ok(CommandRows::Local),
+            "both" => Ok(CommandRows::Both),
+            other => Err(format!(
+                "Unknown applies_to '{}' of a custom command, use s3, local or both",
+                other
+            )),
+        }
+    }
+
+    pub fn includes(self, s3_row: bool) -> bool {
+        match self {
+            CommandRows::S3 => s3_row,
+            CommandRows::Local => !s3_row,
+            CommandRows::Both => true,
+        }
+    }
+
+    /// Whether a row of a panel is included in both
+    pub fn overlaps(self, other: CommandRows) -> bool {
+        [true, false]
+            .into_iter()
+            .any(|s3_row| self.includes(s3_row) && other.includes(s3_row))
+    }
+
+    /// Placeholders without a value on the rows, `{bucket}` and `{key}` on the local panel
+    fn unavailable(self, placeholder: &str) -> bool {
+        match self {
+            CommandRows::S3 => placeholder == "local_path",
+            CommandRows::Local => placeholder == "bucket" || placeholder == "key",
+            CommandRows::Both => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Placeholder(&'static str),
+}
+
+/// Values of the highlighted row the placeholders are replaced with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowValues {
+    pub bucket: Option<String>,
+    pub key: Option<String>,
+    pub local_path: Option<String>,
+    pub account: String,
+}
+
+impl RowValues {
+    fn get(&self, placeholder: &str) -> Option<&str> {
+        match placeholder {
+            "bucket" => self.bucket.as_deref(),
+            "key" => self.key.as_deref(),
+            "local_path" => self.local_path.as_deref(),
+            _ => Some(self.account.as_str()),
+        }
+    }
+}
+
+/// Command started with its key on a row of the file manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomCommand {
+    pub name: String,
+    pub key: KeySpec,
+    pub rows: CommandRows,
+    template: Vec<TemplatePart>,
+}
+
+impl CustomCommand {
+    /// Reads a `[[custom_commands]]` entry of the config file, the placeholders of the
+    /// command are checked here so a typo doesn't wait until the key is pressed
+    pub fn from_entry(entry: &HashMap<String, String>) -> Result<Self, String> {
+        let field = |name: &str| {
+            entry
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("custom_commands entry without {}", name))
+        };
+        let name = field("name")?;
+        let key = KeySpec::parse(&field("key")?)
+            .map_err(|e| format!("custom command '{}': {}", name, e))?;
+        let rows = match entry.get("applies_to") {
+            Some(value) => CommandRows::from_setting(value)?,
+            None => CommandRows::Both,
+        };
+        let template = parse_template(&field("command")?)
+            .map_err(|e| format!("custom command '{}': {}", name, e))?;
+        if let Some(placeholder) = template.iter().find_map(|part| match part {
+            TemplatePart::Placeholder(p) if rows.unavailable(p) => Some(*p),
+            _ => None,
+        }) {
+            return Err(format!(
+                "custom command '{}': {{{}}} has no value on the rows it applies to",
+                name, placeholder
+            ));
+        }
+        Ok(CustomCommand {
+            name,
+            key,
+            rows,
+            template,
+        })
+    }
+
+    /// Command line with the placeholders replaced by the quoted values of the row
+    pub fn command_line(&self, row: &RowValues) -> Result<String, String> {
+        let mut line = String::new();
+        for part in &self.template {
+            match part {
+                TemplatePart::Text(text) => line.push_str(text),
+                TemplatePart::Placeholder(p) => match row.get(p) {
+                    Some(value) => line.push_str(&shell_quote(value)),
+                    None => return Err(format!("{{{}}} has no value on the highlighted row", p)),
+                },
+            }
+        }
+        Ok(line)
+    }
+}
+
+/// Splits the template into text and placeholders, `{{` and `}}` stand for the braces
+fn parse_template(template: &str) -> Result<Vec<TemplatePart>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("unclosed placeholder {{{}", name)),
+                    }
+                }
+                let placeholder = PLACEHOLDERS.iter().find(|p| **p == name).ok_or_else(|| {
+                    format!(
+                        "unknown placeholder {{{}}}, use {{bucket}}, {{key}}, {{local_path}} \
+                             or {{account}} and {{{{ }}}} for the braces",
+                        name
+                    )
+                })?;
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(TemplatePart::Placeholder(*placeholder));
+            }
+            '}' => return Err("unmatched '}', use }} for a brace".into()),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    Ok(parts)
+}
+
+/// Quotes the value as a single argument of `sh`, nothing inside single quotes is expanded
+#[cfg(not(windows))]
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quotes the value as a single argument of PowerShell, which takes the typographic single
+/// quotes for the quote as well
+#[cfg(windows)]
+pub fn shell_quote(value: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in value.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        fields
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn command(template: &str, applies_to: &str) -> Result<CustomCommand, String> {
+        CustomCommand::from_entry(&entry(&[
+            ("name", "etl"),
+            ("key", "alt+e"),
+            ("command", template),
+            ("applies_to", applies_to),
+        ]))
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn placeholders_are_replaced_with_quoted_values() {
+        let etl = command(
+            "etl-submit --source s3://{bucket}/{key} --by {account}",
+            "s3",
+        )
+        .unwrap();
+        let row = RowValues {
+            bucket: Some("logs".into()),
+            key: Some("2024/it's here; rm -rf ~ $(reboot) `id`.log".into()),
+            local_path: None,
+            account: "prod".into(),
+        };
+        assert_eq!(
+            etl.command_line(&row).unwrap(),
+            "etl-submit --source s3://'logs'/'2024/it'\\''s here; rm -rf ~ $(reboot) `id`.log' \
+             --by 'prod'"
+        );
+        let braces = command("awk '{{print $1}}' {local_path}", "local").unwrap();
+        let row = RowValues {
+            local_path: Some("/tmp/a b".into()),
+            ..RowValues::default()
+        };
+        assert_eq!(
+            braces.command_line(&row).unwrap(),
+            "awk '{print $1}' '/tmp/a b'"
+        );
+    }
+
+    #[test]
+    fn templates_are_validated_at_load() {
+        assert!(command("echo {bucket} {path}", "both")
+            .unwrap_err()
+            .contains("unknown placeholder {path}"));
+        assert!(command("echo {key", "both").is_err());
+        assert!(command("echo }", "both").is_err());
+        assert!(command("echo {key}", "local")
+            .unwrap_err()
+            .contains("{key} has no value"));
+        assert!(command("echo {local_path}", "s3").is_err());
+        assert!(command("echo", "remote").is_err());
+        assert!(CustomCommand::from_entry(&entry(&[("name", "etl"), ("key", "e")])).is_err());
+    }
+
+    #[test]
+    fn commands_apply_to_their_rows() {
+        let both = CustomCommand::from_entry(&entry(&[
+            ("name", "show"),
+            ("key", "ctrl+o"),
+            ("command", "echo {key} {local_path}"),
+        ]))
+        .unwrap();
+        assert_eq!(both.rows, CommandRows::Both);
+        assert!(both.rows.includes(true) && both.rows.includes(false));
+        assert!(CommandRows::S3.includes(true) && !CommandRows::S3.includes(false));
+        assert!(CommandRows::Local.includes(false) && !CommandRows::Local.includes(true));
+
+        // a value missing on the highlighted row is reported instead of running the command
+        let local_row = RowValues {
+            local_path: Some("/tmp/a".into()),
+            ..RowValues::default()
+        };
+        assert_eq!(
+            both.command_line(&local_row).unwrap_err(),
+            "{key} has no value on the highlighted row"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! This module provides the directories the s3 panel opens instead of a bucket root
+use crate::model::navigation_state::NavigationState;
+use std::collections::HashMap;
+
+/// Directory opened when entering `bucket` from the bucket list with the `account` credential
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultPrefix {
+    pub account: String,
+    pub bucket: String,
+    pub prefix: String,
+}
+
+impl DefaultPrefix {
+    /// Reads a `[[default_prefixes]]` entry of the config file
+    pub fn from_entry(entry: &HashMap<String, String>) -> Result<Self, String> {
+        let field = |name: &str| {
+            entry
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("default_prefixes entry without {}", name))
+        };
+        Ok(DefaultPrefix {
+            account: field("account")?,
+            bucket: field("bucket")?,
+            prefix: field("prefix")?,
+        })
+    }
+
+    /// Locations from the bucket root down to the prefix, one for every directory,
+    /// so going back leaves the prefix level by level
+    pub fn levels(&self) -> Result<Vec<NavigationState>, String> {
+        let trimmed = self.prefix.trim_end_matches('/');
+        if trimmed.is_empty() || trimmed.split('/').any(|dir| dir.is_empty()) {
+            return Err(format!(
+                "Invalid default prefix '{}' for {}",
+                self.prefix, self.bucket
+            ));
+        }
+        let location =
+            NavigationState::new(Some(self.bucket.clone()), Some(format!("{}/", trimmed)));
+        Ok(location.levels())
+    }
+}
+
+pub fn find_default_prefix<'a>(
+    prefixes: &'a [DefaultPrefix],
+    account: &str,
+    bucket: &str,
+) -> Option<&'a DefaultPrefix> {
+    prefixes
+        .iter()
+        .find(|p| p.account == account && p.bucket == bucket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_prefix(prefix: &str) -> DefaultPrefix {
+        DefaultPrefix {
+            account: "default".into(),
+            bucket: "data".into(),
+            prefix: prefix.into(),
+        }
+    }
+
+    #[test]
+    fn every_directory_of_the_prefix_is_a_level() {
+        let at = |prefix: Option<&str>| {
+            NavigationState::new(Some("data".into()), prefix.map(String::from))
+        };
+        let expected = vec![
+            at(None),
+            at(Some("team/")),
+            at(Some("team/curated/")),
+            at(Some("team/curated/v2/")),
+        ];
+        assert_eq!(
+            default_prefix("team/curated/v2/").levels(),
+            Ok(expected.clone())
+        );
+        assert_eq!(default_prefix("team/curated/v2").levels(), Ok(expected));
+    }
+
+    #[test]
+    fn malformed_prefixes_are_rejected() {
+        assert!(default_prefix("").levels().is_err());
+        assert!(default_prefix("/").levels().is_err());
+        assert!(default_prefix("team//v2/").levels().is_err());
+        assert!(default_prefix("/team/").levels().is_err());
+    }
+
+    #[test]
+    fn prefix_is_found_by_account_and_bucket() {
+        let prefixes = vec![
+            default_prefix("team/"),
+            DefaultPrefix {
+                account: "prod".into(),
+                ..default_prefix("other/")
+            },
+        ];
+        assert_eq!(
+            find_default_prefix(&prefixes, "prod", "data").map(|p| p.prefix.as_str()),
+            Some("other/")
+        );
+        assert!(find_default_prefix(&prefixes, "default", "logs").is_none());
+
+        let entry = HashMap::from([("account".to_string(), "prod".to_string())]);
+        assert!(DefaultPrefix::from_entry(&entry).is_err());
+    }
+}
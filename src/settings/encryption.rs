@@ -0,0 +1,67 @@
+//! This module provides the server side encryption requested for the uploads of a credential,
+//! e.g. for buckets whose policy rejects objects not encrypted with a KMS key
+
+/// Values of `sse` accepted by s3
+const SSE_ALGORITHMS: [&str; 3] = ["AES256", "aws:kms", "aws:kms:dsse"];
+
+/// `sse` and `kms_key_id` lines of a credential file, unset values leave the encryption
+/// to the default of the bucket
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UploadEncryption {
+    /// `AES256` (SSE-S3), `aws:kms` or `aws:kms:dsse` (SSE-KMS)
+    pub sse: Option<String>,
+    /// Key of the KMS encryption, the aws managed key of s3 when it's not set
+    pub kms_key_id: Option<String>,
+}
+
+impl UploadEncryption {
+    /// Checks the values read from the credential file, a key is only accepted for the KMS
+    /// encryption
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(sse) = self
+            .sse
+            .as_deref()
+            .filter(|sse| !SSE_ALGORITHMS.contains(sse))
+        {
+            return Err(format!(
+                "Invalid sse '{}', expected one of {}",
+                sse,
+                SSE_ALGORITHMS.join(", ")
+            ));
+        }
+        if self.kms_key_id.is_some() && !self.uses_kms() {
+            return Err("kms_key_id needs sse=aws:kms or sse=aws:kms:dsse".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn uses_kms(&self) -> bool {
+        self.sse
+            .as_deref()
+            .is_some_and(|sse| sse.starts_with("aws:kms"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kms_key_needs_the_kms_encryption() {
+        let encryption = |sse: Option<&str>, kms_key_id: Option<&str>| UploadEncryption {
+            sse: sse.map(String::from),
+            kms_key_id: kms_key_id.map(String::from),
+        };
+        let key = Some("arn:aws:kms:eu-west-1:111122223333:key/lab");
+        assert!(encryption(None, None).validate().is_ok());
+        assert!(encryption(Some("AES256"), None).validate().is_ok());
+        assert!(encryption(Some("aws:kms"), key).validate().is_ok());
+        assert!(encryption(Some("aws:kms:dsse"), key).validate().is_ok());
+        assert!(encryption(Some("AES256"), key).validate().is_err());
+        assert!(encryption(None, key).validate().is_err());
+        assert_eq!(
+            encryption(Some("kms"), None).validate(),
+            Err("Invalid sse 'kms', expected one of AES256, aws:kms, aws:kms:dsse".to_string())
+        );
+    }
+}
@@ -0,0 +1,687 @@
+//! This module provides the key bindings of the actions, the defaults can be replaced
+//! in the optional `keys.toml` file of the configuration directory
+use crate::components::keymap::Command;
+use crate::settings::app_config::APP_CONFIG_FILE;
+use crate::settings::custom_command::CustomCommand;
+use crate::utils::get_config_dir;
+use color_eyre::eyre;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Name of the optional key bindings file inside the configuration directory
+pub const KEY_BINDINGS_FILE: &str = "keys.toml";
+
+/// Pages an action is available on, actions available on the same page need different keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScope {
+    FileManager,
+    Transfers,
+    Accounts,
+    AllPages,
+}
+
+impl KeyScope {
+    fn overlaps(self, other: KeyScope) -> bool {
+        self == other || self == KeyScope::AllPages || other == KeyScope::AllPages
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    ToggleSelect,
+    Create,
+    Refresh,
+    Filter,
+    HistoryBack,
+    HistoryForward,
+    Parent,
+    GoTo,
+    DeleteSelected,
+    Metadata,
+    Preview,
+    Compare,
+    Yank,
+    YankMove,
+    Paste,
+    Rename,
+    RenameLocal,
+    ToggleHidden,
+    CopyContents,
+    NavigateTransfers,
+    RunTransfers,
+    RunHighlighted,
+    MarkTransfer,
+    ToggleManifest,
+    AllTransfers,
+    SortTransfers,
+    ReverseSort,
+    PauseTransfer,
+    ResumeTransfer,
+    CancelTransfer,
+    OpenSource,
+    OpenDestination,
+    ExportSelection,
+    ImportSelection,
+    AddCredential,
+    EditCredential,
+    Delete,
+    AwsCli,
+    NavigateAccounts,
+    NavigateHelp,
+    NavigateStats,
+    ActivityLog,
+    Quit,
+}
+
+struct ActionDefinition {
+    action: KeyAction,
+    /// Name used in the key bindings file
+    name: &'static str,
+    default_keys: &'static [&'static str],
+    scope: KeyScope,
+    /// Command of the help page and hints describing the action
+    command: Command,
+}
+
+const fn define(
+    action: KeyAction,
+    name: &'static str,
+    default_keys: &'static [&'static str],
+    scope: KeyScope,
+    command: Command,
+) -> ActionDefinition {
+    ActionDefinition {
+        action,
+        name,
+        default_keys,
+        scope,
+        command,
+    }
+}
+
+/// Every action which can be bound to other keys
+#[rustfmt::skip]
+const ACTIONS: &[ActionDefinition] = &[
+    define(KeyAction::ToggleSelect, "toggle_select", &["t"], KeyScope::FileManager, Command::ToggleTransfer),
+    define(KeyAction::Create, "create", &["c"], KeyScope::FileManager, Command::CreateBucket),
+    define(KeyAction::Refresh, "refresh", &["F5"], KeyScope::FileManager, Command::RefreshBuckets),
+    define(KeyAction::Filter, "filter", &["/"], KeyScope::FileManager, Command::Filter),
+    define(KeyAction::HistoryBack, "history_back", &["<"], KeyScope::FileManager, Command::History),
+    define(KeyAction::HistoryForward, "history_forward", &[">"], KeyScope::FileManager, Command::History),
+    define(KeyAction::Parent, "parent", &["u"], KeyScope::FileManager, Command::Parent),
+    define(KeyAction::GoTo, "go_to", &["g"], KeyScope::FileManager, Command::GoTo),
+    define(KeyAction::DeleteSelected, "delete_selected", &["D"], KeyScope::FileManager, Command::DeleteSelected),
+    define(KeyAction::Metadata, "metadata", &["m"], KeyScope::FileManager, Command::ObjectMetadata),
+    define(KeyAction::Preview, "preview", &["v"], KeyScope::FileManager, Command::Preview),
+    define(KeyAction::Compare, "compare", &["="], KeyScope::FileManager, Command::CompareWithLocal),
+    define(KeyAction::Yank, "yank", &["y"], KeyScope::FileManager, Command::Yank),
+    define(KeyAction::YankMove, "yank_move", &["Y"], KeyScope::FileManager, Command::Yank),
+    define(KeyAction::Paste, "paste", &["P"], KeyScope::FileManager, Command::Paste),
+    define(KeyAction::Rename, "rename", &["n"], KeyScope::FileManager, Command::Rename),
+    define(KeyAction::RenameLocal, "rename_local", &["r"], KeyScope::FileManager, Command::RenameLocal),
+    define(KeyAction::ToggleHidden, "toggle_hidden", &["."], KeyScope::FileManager, Command::ToggleHidden),
+    define(KeyAction::CopyContents, "copy_contents", &["ctrl+y"], KeyScope::FileManager, Command::CopyContents),
+    define(KeyAction::NavigateTransfers, "navigate_transfers", &["l"], KeyScope::FileManager, Command::TransfersList),
+    define(KeyAction::RunTransfers, "run_transfers", &["r"], KeyScope::Transfers, Command::RunTransfers),
+    define(KeyAction::RunHighlighted, "run_highlighted", &["R"], KeyScope::Transfers, Command::RunHighlighted),
+    define(KeyAction::MarkTransfer, "mark_transfer", &["space"], KeyScope::Transfers, Command::MarkTransfer),
+    define(KeyAction::ToggleManifest, "toggle_manifest", &["M"], KeyScope::Transfers, Command::ToggleManifest),
+    define(KeyAction::AllTransfers, "all_transfers", &["e"], KeyScope::Transfers, Command::ErrorFilter),
+    define(KeyAction::SortTransfers, "sort_transfers", &["S"], KeyScope::Transfers, Command::SortTransfers),
+    define(KeyAction::ReverseSort, "reverse_sort", &["I"], KeyScope::Transfers, Command::SortTransfers),
+    define(KeyAction::PauseTransfer, "pause_transfer", &["p"], KeyScope::Transfers, Command::PauseTransfer),
+    define(KeyAction::ResumeTransfer, "resume_transfer", &["u"], KeyScope::Transfers, Command::PauseTransfer),
+    define(KeyAction::CancelTransfer, "cancel_transfer", &["X"], KeyScope::Transfers, Command::CancelTransfer),
+    define(KeyAction::OpenSource, "open_source", &["o"], KeyScope::Transfers, Command::OpenLocation),
+    define(KeyAction::OpenDestination, "open_destination", &["O"], KeyScope::Transfers, Command::OpenLocation),
+    define(KeyAction::ExportSelection, "export_selection", &["x"], KeyScope::Transfers, Command::SelectionFile),
+    define(KeyAction::ImportSelection, "import_selection", &["L"], KeyScope::Transfers, Command::SelectionFile),
+    define(KeyAction::AddCredential, "add_credential", &["n"], KeyScope::Accounts, Command::AddCredential),
+    define(KeyAction::EditCredential, "edit_credential", &["e"], KeyScope::Accounts, Command::EditCredential),
+    define(KeyAction::Delete, "delete", &["del", "backspace"], KeyScope::AllPages, Command::Delete),
+    define(KeyAction::AwsCli, "aws_cli", &["a"], KeyScope::AllPages, Command::AwsCli),
+    define(KeyAction::NavigateAccounts, "navigate_accounts", &["s"], KeyScope::AllPages, Command::SelectAccount),
+    define(KeyAction::NavigateHelp, "navigate_help", &["?"], KeyScope::AllPages, Command::Help),
+    define(KeyAction::NavigateStats, "navigate_stats", &["i"], KeyScope::AllPages, Command::Stats),
+    define(KeyAction::ActivityLog, "activity_log", &["~"], KeyScope::AllPages, Command::ActivityLog),
+    define(KeyAction::Quit, "quit", &["q"], KeyScope::AllPages, Command::Quit),
+];
+
+/// Keys handled directly by the pages and popups, they cannot be assigned to the actions
+const RESERVED_KEYS: &[(&str, &str)] = &[
+    ("j", "moving on the lists"),
+    ("k", "moving on the lists"),
+    ("up", "moving on the lists"),
+    ("down", "moving on the lists"),
+    ("pgup", "jumping pages"),
+    ("pgdn", "jumping pages"),
+    ("left", "switching panels"),
+    ("right", "switching panels"),
+    ("tab", "switching panels"),
+    ("enter", "opening and confirming"),
+    ("esc", "going back and cancelling"),
+    ("ctrl+x", "stopping the network"),
+    ("ctrl+z", "suspending"),
+];
+
+fn definition(action: KeyAction) -> &'static ActionDefinition {
+    ACTIONS
+        .iter()
+        .find(|d| d.action == action)
+        .expect("every key action is defined")
+}
+
+/// Key with the ctrl/alt modifiers, shift is part of the character e.g. `D`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    /// Parses specs like `t`, `D`, `space`, `ctrl+t`, `shift+d` or `F5`
+    pub fn parse(spec: &str) -> Result<KeySpec, String> {
+        let (prefix, key) = match spec.rsplit_once('+') {
+            // the plus key itself e.g. `+` or `ctrl++`
+            Some((prefix, "")) => (prefix.strip_suffix('+').unwrap_or(prefix), "+"),
+            Some((prefix, key)) => (prefix, key),
+            None => ("", spec),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        let mut shift = false;
+        for modifier in prefix.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" => modifiers |= KeyModifiers::ALT,
+                "shift" => shift = true,
+                other => return Err(format!("unknown modifier '{}' in key '{}'", other, spec)),
+            }
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if shift && c.is_ascii_alphabetic() => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            _ if shift => return Err(format!("shift only goes with letters in key '{}'", spec)),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "del" | "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pgup" | "pageup" => KeyCode::PageUp,
+                "pgdn" | "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", spec)),
+                },
+            },
+        };
+        Ok(KeySpec { code, modifiers })
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        key.code == self.code
+            && key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT) == self.modifiers
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Backspace => write!(f, "⌫"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Keys of every action, the defaults unless replaced in the key bindings file
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    keys: Vec<(KeyAction, Vec<KeySpec>)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            keys: ACTIONS
+                .iter()
+                .map(|d| (d.action, parse_default_keys(d.default_keys)))
+                .collect(),
+        }
+    }
+}
+
+fn parse_default_keys(keys: &[&str]) -> Vec<KeySpec> {
+    keys.iter()
+        .map(|k| KeySpec::parse(k).expect("default keys are valid"))
+        .collect()
+}
+
+impl KeyBindings {
+    /// Action of the pressed key on a page, `AllPages` gives only the actions of every page
+    pub fn action(&self, key: &KeyEvent, page: KeyScope) -> Option<KeyAction> {
+        self.keys
+            .iter()
+            .filter(|(action, _)| {
+                let scope = definition(*action).scope;
+                scope == page || scope == KeyScope::AllPages
+            })
+            .find(|(_, keys)| keys.iter().any(|k| k.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: KeyAction) -> &[KeySpec] {
+        self.keys
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Keys of the command for the help page and the hints, `None` while they are the defaults
+    pub fn customized_keys(&self, command: Command) -> Option<String> {
+        let actions: Vec<&ActionDefinition> =
+            ACTIONS.iter().filter(|d| d.command == command).collect();
+        let customized = actions
+            .iter()
+            .any(|d| self.keys(d.action) != parse_default_keys(d.default_keys).as_slice());
+        customized.then(|| {
+            actions
+                .iter()
+                .flat_map(|d| self.keys(d.action))
+                .map(|k| k.to_string())
+                .collect::<Vec<String>>()
+                .join(" / ")
+        })
+    }
+
+    /// First key of the first action of the command, pressed when its hint is clicked
+    pub fn command_key(&self, command: Command) -> Option<KeyEvent> {
+        ACTIONS
+            .iter()
+            .find(|d| d.command == command)
+            .and_then(|d| self.keys(d.action).first())
+            .map(|k| KeyEvent::new(k.code, k.modifiers))
+    }
+
+    /// Fails when a custom command of the config file has a key the file manager already uses
+    pub fn check_custom_commands(&self, commands: &[CustomCommand]) -> eyre::Result<()> {
+        let errors = self.custom_command_key_errors(commands);
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(eyre::eyre!(
+                "Invalid custom_commands in {}:\n{}",
+                APP_CONFIG_FILE,
+                errors.join("\n")
+            )),
+        }
+    }
+
+    /// Keys of the custom commands which are reserved, assigned to an action of the file
+    /// manager or to an earlier custom command of the same rows
+    fn custom_command_key_errors(&self, commands: &[CustomCommand]) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (index, command) in commands.iter().enumerate() {
+            let key = &command.key;
+            if let Some((_, purpose)) = RESERVED_KEYS
+                .iter()
+                .find(|(reserved, _)| KeySpec::parse(reserved).as_ref() == Ok(key))
+            {
+                errors.push(format!(
+                    "key '{}' of \"{}\" is reserved for {}",
+                    key, command.name, purpose
+                ));
+            }
+            if let Some((action, _)) = self.keys.iter().find(|(action, keys)| {
+                definition(*action).scope.overlaps(KeyScope::FileManager) && keys.contains(key)
+            }) {
+                errors.push(format!(
+                    "key '{}' of \"{}\" is the key of \"{}\"",
+                    key,
+                    command.name,
+                    definition(*action).name
+                ));
+            }
+            if let Some(other) = commands[..index]
+                .iter()
+                .find(|other| other.key == *key && other.rows.overlaps(command.rows))
+            {
+                errors.push(format!(
+                    "key '{}' of \"{}\" is also the key of \"{}\"",
+                    key, command.name, other.name
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// Loads the key bindings file of the configuration directory, the defaults are used
+/// when there is none. Unknown actions and keys assigned twice are errors listing the
+/// offending lines
+pub fn load_key_bindings() -> eyre::Result<KeyBindings> {
+    let path = get_config_dir().join(KEY_BINDINGS_FILE);
+    load_key_bindings_from_file(path.as_path())
+}
+
+fn load_key_bindings_from_file(path: &Path) -> eyre::Result<KeyBindings> {
+    if !path.exists() {
+        return Ok(KeyBindings::default());
+    }
+    let content = fs::read_to_string(path)?;
+    parse_key_bindings(&content)
+        .map_err(|errors| eyre::eyre!("Invalid key bindings in {:?}:\n{}", path, errors.join("\n")))
+}
+
+/// Parses `action = "key"` or `action = ["key", "key"]` lines, actions missing in the
+/// content keep their default keys
+pub fn parse_key_bindings(content: &str) -> Result<KeyBindings, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut bindings = KeyBindings::default();
+    // line on which each action got its keys
+    let mut assigned: Vec<(KeyAction, usize)> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let mut error = |message: String| errors.push(format!("line {}: {}", number, message));
+        let Some((name, value)) = trimmed.split_once('=') else {
+            error(format!("expected `action = \"key\"`, found `{}`", trimmed));
+            continue;
+        };
+        let name = name.trim();
+        let Some(d) = ACTIONS.iter().find(|d| d.name == name) else {
+            error(format!("unknown action \"{}\"", name));
+            continue;
+        };
+        if let Some((_, first)) = assigned.iter().find(|(a, _)| *a == d.action) {
+            error(format!(
+                "\"{}\" is already assigned on line {}",
+                name, first
+            ));
+            continue;
+        }
+        let keys = match parse_key_list(value).and_then(|keys| {
+            keys.iter()
+                .map(|k| KeySpec::parse(k))
+                .collect::<Result<Vec<KeySpec>, String>>()
+        }) {
+            Ok(keys) => keys,
+            Err(e) => {
+                error(e);
+                continue;
+            }
+        };
+        assigned.push((d.action, number));
+        if let Some(entry) = bindings.keys.iter_mut().find(|(a, _)| *a == d.action) {
+            entry.1 = keys;
+        }
+    }
+    errors.extend(duplicate_key_errors(&bindings, &assigned));
+
+    match errors.is_empty() {
+        true => Ok(bindings),
+        false => Err(errors),
+    }
+}
+
+/// Keys assigned in the file which are reserved or also used by another action of the same
+/// page, a duplicate between two lines is reported once on the later one
+fn duplicate_key_errors(bindings: &KeyBindings, assigned: &[(KeyAction, usize)]) -> Vec<String> {
+    let line_of = |action: KeyAction| {
+        assigned
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, line)| *line)
+    };
+    let mut errors = Vec::new();
+    for &(action, line) in assigned {
+        let d = definition(action);
+        for key in bindings.keys(action) {
+            if let Some((_, purpose)) = RESERVED_KEYS
+                .iter()
+                .find(|(reserved, _)| KeySpec::parse(reserved).as_ref() == Ok(key))
+            {
+                errors.push(format!(
+                    "line {}: key '{}' of \"{}\" is reserved for {}",
+                    line, key, d.name, purpose
+                ));
+            }
+            for (other, other_keys) in &bindings.keys {
+                let o = definition(*other);
+                if *other == action || !o.scope.overlaps(d.scope) || !other_keys.contains(key) {
+                    continue;
+                }
+                match line_of(*other) {
+                    Some(other_line) if other_line > line => {}
+                    Some(other_line) => errors.push(format!(
+                        "line {}: key '{}' of \"{}\" is also assigned to \"{}\" on line {}",
+                        line, key, d.name, o.name, other_line
+                    )),
+                    None => errors.push(format!(
+                        "line {}: key '{}' of \"{}\" is the default key of \"{}\", assign it \
+                         another key",
+                        line, key, d.name, o.name
+                    )),
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Reads `"key"` or `["key", "key"]`, a `#` comment may follow
+fn parse_key_list(value: &str) -> Result<Vec<String>, String> {
+    let value = value.trim();
+    let (list, mut rest) = match value.strip_prefix('[') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let mut keys = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if list {
+            if let Some(after) = rest.strip_prefix(']') {
+                rest = after;
+                break;
+            }
+        }
+        let quoted = rest
+            .strip_prefix('"')
+            .ok_or_else(|| format!("expected a quoted key, found `{}`", value))?;
+        let end = quoted
+            .find('"')
+            .ok_or_else(|| format!("missing closing quote in `{}`", value))?;
+        keys.push(quoted[..end].to_string());
+        rest = quoted[end + 1..].trim_start();
+        if !list {
+            break;
+        }
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after,
+            None if rest.starts_with(']') => {}
+            None => return Err(format!("expected ',' or ']' in `{}`", value)),
+        }
+    }
+    match rest.trim_start() {
+        "" => Ok(keys),
+        comment if comment.starts_with('#') => Ok(keys),
+        other => Err(format!("unexpected `{}` after the keys", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn key_specs_are_parsed() {
+        let spec = |s: &str| KeySpec::parse(s).unwrap();
+        assert!(spec("t").matches(&press(KeyCode::Char('t'), KeyModifiers::NONE)));
+        assert!(!spec("t").matches(&press(KeyCode::Char('t'), KeyModifiers::CONTROL)));
+        assert!(spec("D").matches(&press(KeyCode::Char('D'), KeyModifiers::SHIFT)));
+        assert_eq!(spec("shift+d"), spec("D"));
+        assert!(spec("space").matches(&press(KeyCode::Char(' '), KeyModifiers::NONE)));
+        assert!(spec("ctrl+t").matches(&press(KeyCode::Char('t'), KeyModifiers::CONTROL)));
+        assert!(spec("F5").matches(&press(KeyCode::F(5), KeyModifiers::NONE)));
+        assert!(spec("ctrl++").matches(&press(KeyCode::Char('+'), KeyModifiers::CONTROL)));
+        assert_eq!(spec("ctrl+t").to_string(), "Ctrl+t");
+
+        assert!(KeySpec::parse("hyper+t").is_err());
+        assert!(KeySpec::parse("shift+F5").is_err());
+        assert!(KeySpec::parse("F13").is_err());
+        assert!(KeySpec::parse("spacebar").is_err());
+    }
+
+    #[test]
+    fn default_keys_do_not_conflict() {
+        let defaults = KeyBindings::default();
+        let every_action: Vec<(KeyAction, usize)> = ACTIONS.iter().map(|d| (d.action, 1)).collect();
+        assert_eq!(
+            duplicate_key_errors(&defaults, &every_action),
+            Vec::<String>::new()
+        );
+        assert_eq!(defaults.customized_keys(Command::ToggleTransfer), None);
+    }
+
+    #[test]
+    fn actions_are_rebound() {
+        let bindings = parse_key_bindings(
+            "# my muscle memory\n\
+             toggle_select = \"space\"\n\
+             delete = [\"ctrl+d\", \"del\"] # not backspace\n",
+        )
+        .unwrap();
+        let space = press(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert_eq!(
+            bindings.action(&space, KeyScope::FileManager),
+            Some(KeyAction::ToggleSelect)
+        );
+        // the transfers page keeps marking with space
+        assert_eq!(
+            bindings.action(&space, KeyScope::Transfers),
+            Some(KeyAction::MarkTransfer)
+        );
+        let t = press(KeyCode::Char('t'), KeyModifiers::NONE);
+        assert_eq!(bindings.action(&t, KeyScope::FileManager), None);
+        let backspace = press(KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(bindings.action(&backspace, KeyScope::Transfers), None);
+        assert_eq!(
+            bindings.customized_keys(Command::Delete),
+            Some("Ctrl+d / Del".to_string())
+        );
+        assert_eq!(
+            bindings.customized_keys(Command::ToggleTransfer),
+            Some("space".to_string())
+        );
+    }
+
+    #[test]
+    fn errors_list_the_offending_lines() {
+        let errors = parse_key_bindings(
+            "toggle_select = \"x\"\n\
+             toggle = \"z\"\n\
+             quit = \"x\"\n\
+             rename = \"j\"\n\
+             yank = \"m\"\n\
+             preview = \"shift+F5\"\n\
+             toggle_select = \"w\"\n\
+             run_transfers = \"x\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "line 2: unknown action \"toggle\"",
+                "line 6: shift only goes with letters in key 'shift+F5'",
+                "line 7: \"toggle_select\" is already assigned on line 1",
+                "line 3: key 'x' of \"quit\" is also assigned to \"toggle_select\" on line 1",
+                "line 4: key 'j' of \"rename\" is reserved for moving on the lists",
+                "line 5: key 'm' of \"yank\" is the default key of \"metadata\", \
+                 assign it another key",
+                "line 8: key 'x' of \"run_transfers\" is also assigned to \"quit\" on line 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_file_gives_the_defaults() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(KEY_BINDINGS_FILE);
+        assert_eq!(
+            load_key_bindings_from_file(&path).unwrap(),
+            KeyBindings::default()
+        );
+
+        let mut file = fs::File::create(&path).unwrap();
+        writeln!(file, "toggle_select = space").unwrap();
+        let error = load_key_bindings_from_file(&path).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid key bindings in"));
+        assert!(error.ends_with("line 1: expected a quoted key, found `space`"));
+    }
+
+    #[test]
+    fn custom_commands_need_keys_of_their_own() {
+        let command = |name: &str, key: &str, applies_to: &str| {
+            let entry = [
+                ("name", name),
+                ("key", key),
+                ("command", "echo"),
+                ("applies_to", applies_to),
+            ]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+            CustomCommand::from_entry(&entry).unwrap()
+        };
+        let bindings = KeyBindings::default();
+        let commands = [
+            command("etl", "alt+e", "s3"),
+            command("open", "alt+e", "local"),
+            command("lambda", "alt+e", "both"),
+            command("quit", "q", "both"),
+            command("down", "j", "local"),
+        ];
+        assert_eq!(
+            bindings.custom_command_key_errors(&commands),
+            vec![
+                "key 'Alt+e' of \"lambda\" is also the key of \"etl\"",
+                "key 'q' of \"quit\" is the key of \"quit\"",
+                "key 'j' of \"down\" is reserved for moving on the lists",
+            ]
+        );
+        assert!(bindings.check_custom_commands(&commands[..2]).is_ok());
+    }
+}
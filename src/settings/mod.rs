@@ -1,3 +1,11 @@
 //! This module provides functionality for loading and parsing file credentials
 //! from your configuration files
+pub mod app_config;
+pub mod color_support;
+pub mod custom_command;
+pub mod default_prefix;
+pub mod encryption;
 pub mod file_credentials;
+pub mod key_bindings;
+pub mod proxy;
+pub mod transfer_tuning;
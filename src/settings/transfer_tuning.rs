@@ -0,0 +1,232 @@
+//! This module provides the sizes and the parallelism of multipart uploads and ranged
+//! downloads, set in the config file and overridden by single credentials
+
+const MB: u64 = 1024 * 1024;
+/// Smallest part of a multipart upload accepted by s3, only the last part may be smaller
+pub const MIN_PART_SIZE: u64 = 5 * MB;
+/// Largest part of a multipart upload accepted by s3
+pub const MAX_PART_SIZE: u64 = 5 * 1024 * MB;
+/// Most parts of a single multipart upload
+pub const MAX_PARTS: u64 = 10_000;
+
+/// Names of the settings, the same in the config file and in the credential files
+pub const TUNING_KEYS: [&str; 6] = [
+    "multipart_threshold_mb",
+    "part_size_mb",
+    "parts_concurrency",
+    "ranged_download_threshold_mb",
+    "range_size_mb",
+    "ranges_concurrency",
+];
+
+/// Tuning of a single credential or of the whole application, unset values fall back
+/// to the next source. Sizes are in megabytes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TuningOverride {
+    /// Files of at least this size are uploaded in parts
+    pub multipart_threshold_mb: Option<u64>,
+    pub part_size_mb: Option<u64>,
+    /// Parts of a single file uploaded at once
+    pub parts_concurrency: Option<usize>,
+    /// Objects of at least this size are downloaded in ranges
+    pub ranged_download_threshold_mb: Option<u64>,
+    pub range_size_mb: Option<u64>,
+    /// Ranges of a single object downloaded at once
+    pub ranges_concurrency: Option<usize>,
+}
+
+impl TuningOverride {
+    /// Sets the value read from the credential file or the config file, zero isn't accepted
+    /// for any of them
+    pub fn set(&mut self, key: &str, value: u64) -> Result<(), String> {
+        if value == 0 {
+            return Err(format!("{} must be at least 1", key));
+        }
+        match key {
+            "multipart_threshold_mb" => self.multipart_threshold_mb = Some(value),
+            "part_size_mb" => self.part_size_mb = Some(value),
+            "parts_concurrency" => self.parts_concurrency = Some(value as usize),
+            "ranged_download_threshold_mb" => self.ranged_download_threshold_mb = Some(value),
+            "range_size_mb" => self.range_size_mb = Some(value),
+            "ranges_concurrency" => self.ranges_concurrency = Some(value as usize),
+            _ => return Err(format!("unknown transfer setting {}", key)),
+        }
+        Ok(())
+    }
+
+    /// Warning describing how the part size is corrected to the limits of s3 when
+    /// the transfers resolve it
+    pub fn correction(&self) -> Option<String> {
+        match self.part_size_mb? * MB {
+            size if size < MIN_PART_SIZE => Some(format!(
+                "part_size_mb={} is below the 5 MB minimum of s3, 5 MB parts are used",
+                size / MB
+            )),
+            size if size > MAX_PART_SIZE => Some(format!(
+                "part_size_mb={} is above the 5 GB maximum of s3, 5 GB parts are used",
+                size / MB
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Values used by the transfers of a credential, sizes in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferTuning {
+    pub multipart_threshold: u64,
+    pub part_size: u64,
+    pub parts_concurrency: usize,
+    pub ranged_download_threshold: u64,
+    pub range_size: u64,
+    pub ranges_concurrency: usize,
+}
+
+impl Default for TransferTuning {
+    fn default() -> Self {
+        TransferTuning {
+            multipart_threshold: 64 * MB,
+            part_size: 16 * MB,
+            parts_concurrency: 4,
+            ranged_download_threshold: 64 * MB,
+            range_size: 16 * MB,
+            ranges_concurrency: 4,
+        }
+    }
+}
+
+impl TransferTuning {
+    /// Picks every value from (in order) the credential, the config file and the defaults,
+    /// the part size is kept within the limits of s3
+    pub fn resolve(credential: &TuningOverride, global: &TuningOverride) -> TransferTuning {
+        let defaults = TransferTuning::default();
+        let mb = |credential: Option<u64>, global: Option<u64>, default: u64| {
+            credential.or(global).map_or(default, |mb| mb * MB)
+        };
+        TransferTuning {
+            multipart_threshold: mb(
+                credential.multipart_threshold_mb,
+                global.multipart_threshold_mb,
+                defaults.multipart_threshold,
+            ),
+            part_size: mb(
+                credential.part_size_mb,
+                global.part_size_mb,
+                defaults.part_size,
+            )
+            .clamp(MIN_PART_SIZE, MAX_PART_SIZE),
+            parts_concurrency: credential
+                .parts_concurrency
+                .or(global.parts_concurrency)
+                .unwrap_or(defaults.parts_concurrency),
+            ranged_download_threshold: mb(
+                credential.ranged_download_threshold_mb,
+                global.ranged_download_threshold_mb,
+                defaults.ranged_download_threshold,
+            ),
+            range_size: mb(
+                credential.range_size_mb,
+                global.range_size_mb,
+                defaults.range_size,
+            ),
+            ranges_concurrency: credential
+                .ranges_concurrency
+                .or(global.ranges_concurrency)
+                .unwrap_or(defaults.ranges_concurrency),
+        }
+    }
+
+    /// Part size for a file of `size` bytes, raised to the next megabyte above
+    /// `size / 10,000` when the configured one would need more than 10,000 parts
+    pub fn part_size_for(&self, size: u64) -> u64 {
+        let minimum = size.div_ceil(MAX_PARTS).div_ceil(MB) * MB;
+        self.part_size.max(minimum).min(MAX_PART_SIZE)
+    }
+}
+
+/// Byte ranges `(first, last)` splitting `size` bytes starting at `offset`
+pub fn byte_ranges(offset: u64, size: u64, range_size: u64) -> Vec<(u64, u64)> {
+    (offset..size)
+        .step_by(range_size as usize)
+        .map(|first| (first, (first + range_size).min(size) - 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credential_wins_over_config_and_defaults() {
+        let config = TuningOverride {
+            part_size_mb: Some(128),
+            parts_concurrency: Some(16),
+            ranges_concurrency: Some(8),
+            ..TuningOverride::default()
+        };
+        let satellite = TuningOverride {
+            part_size_mb: Some(8),
+            parts_concurrency: Some(1),
+            ..TuningOverride::default()
+        };
+
+        let tuning = TransferTuning::resolve(&satellite, &config);
+        assert_eq!(tuning.part_size, 8 * MB);
+        assert_eq!(tuning.parts_concurrency, 1);
+        assert_eq!(tuning.ranges_concurrency, 8);
+        assert_eq!(
+            tuning.multipart_threshold,
+            TransferTuning::default().multipart_threshold
+        );
+
+        let datacenter = TransferTuning::resolve(&TuningOverride::default(), &config);
+        assert_eq!(datacenter.part_size, 128 * MB);
+        assert_eq!(datacenter.parts_concurrency, 16);
+        assert_eq!(
+            TransferTuning::resolve(&TuningOverride::default(), &TuningOverride::default()),
+            TransferTuning::default()
+        );
+    }
+
+    #[test]
+    fn part_size_is_kept_within_the_s3_limits() {
+        let mut tuning = TuningOverride::default();
+        tuning.set("part_size_mb", 1).unwrap();
+        assert_eq!(
+            TransferTuning::resolve(&tuning, &TuningOverride::default()).part_size,
+            MIN_PART_SIZE
+        );
+        assert!(tuning.correction().unwrap().contains("5 MB minimum"));
+
+        tuning.set("part_size_mb", 10 * 1024).unwrap();
+        assert_eq!(
+            TransferTuning::resolve(&tuning, &TuningOverride::default()).part_size,
+            MAX_PART_SIZE
+        );
+        assert!(tuning.correction().unwrap().contains("5 GB maximum"));
+
+        tuning.set("part_size_mb", 64).unwrap();
+        assert_eq!(tuning.correction(), None);
+        assert!(tuning.set("parts_concurrency", 0).is_err());
+        assert!(tuning.set("range_size", 8).is_err());
+    }
+
+    #[test]
+    fn five_terabyte_file_fits_in_ten_thousand_parts() {
+        let size = 5 * 1024 * 1024 * MB;
+        let tuning = TransferTuning::default();
+        let part_size = tuning.part_size_for(size);
+        assert_eq!(part_size, 525 * MB);
+        assert!(size.div_ceil(part_size) <= MAX_PARTS);
+        assert!(size.div_ceil(part_size - MB) > MAX_PARTS);
+        // files needing fewer parts keep the configured size
+        assert_eq!(tuning.part_size_for(100 * MB), tuning.part_size);
+    }
+
+    #[test]
+    fn ranges_cover_the_object_after_the_offset() {
+        assert_eq!(byte_ranges(0, 10, 4), vec![(0, 3), (4, 7), (8, 9)]);
+        assert_eq!(byte_ranges(5, 10, 4), vec![(5, 8), (9, 9)]);
+        assert!(byte_ranges(10, 10, 4).is_empty());
+    }
+}
@@ -1,186 +1,589 @@
 //! This module provides functionality for interactions between UI and state
 use crate::model::action::Action;
+use crate::model::activity_log::Severity;
+use crate::model::bulk_deletion::{
+    group_s3_deletions, recursive_delete_keys, BulkDeletion, DeleteProgress, S3Deletion,
+};
+use crate::model::destination_conflict::{find_conflicts, resolve_conflicts, BlockedTransfers};
 use crate::model::download_progress_item::DownloadProgressItem;
+use crate::model::file_comparison::FileComparison;
+use crate::model::jump_target::JumpTarget;
+use crate::model::large_batch::LargeBatch;
 use crate::model::local_data_item::LocalDataItem;
 use crate::model::local_selected_item::LocalSelectedItem;
+use crate::model::navigation_history::NavigationStep;
+use crate::model::navigation_state::NavigationState;
+use crate::model::object_copy::ObjectCopy;
+use crate::model::object_lock::ObjectLockInfo;
+use crate::model::object_metadata::ObjectMetadata;
+use crate::model::object_preview::ObjectPreview;
 use crate::model::s3_data_item::S3DataItem;
 use crate::model::s3_selected_item::S3SelectedItem;
 use crate::model::state::{ActivePage, State};
+use crate::model::stats::Stats;
+use crate::model::transfer_batch::BatchRefresh;
+use crate::model::transfer_overlap::{find_overlaps, resolve_overlaps, OverlappingTransfers};
 use crate::model::upload_progress_item::UploadProgressItem;
+use crate::model::verification::{Verification, VerificationError};
+use crate::services::adaptive_concurrency::{AdaptiveConcurrency, RequestOutcome};
+use crate::services::external_command::{run_command, CommandOutput};
+use crate::services::folder_markers::FolderMarkers;
 use crate::services::local_data_fetcher::LocalDataFetcher;
+use crate::services::local_names::adjust_download_names;
+use crate::services::local_walker::{CancellationToken, WalkOptions};
+use crate::services::manifest::{
+    expected_files, spawn_manifest_writer, ManifestEntry, ManifestMessage,
+};
+use crate::services::metrics::{Metrics, MetricsServer};
+use crate::services::object_cache::{ObjectCache, CACHE_TTL};
+use crate::services::partial_download::PartialDownload;
+use crate::services::profile_credentials::ProfileError;
+use crate::services::region_redirect::BucketRegions;
 use crate::services::s3_data_fetcher::S3DataFetcher;
-use crate::settings::file_credentials::FileCredential;
+use crate::services::s3_errors::{classify_report, describe_report, ErrorClass};
+use crate::services::selection_file::{
+    export_selection, import_selection, selection_entries, spot_checked, ImportChecks,
+    SelectionEntry, SelectionImport, SPOT_CHECKED_KEYS,
+};
+use crate::services::selection_limit::{LimitedSelection, SelectionLimit};
+use crate::services::startup_work::{run_startup, startup_order, AccountCheck};
+use crate::services::task_registry::{
+    InFlightRequests, TaskRegistry, BUCKET_REGIONS_TASK, S3_LISTING_TASK, STARTUP_TASK,
+};
+use crate::services::transfer_control::{until_stopped, TransferControls, TransferStop};
+use crate::services::transfer_persistence::{
+    Autosave, ResumableUpload, ResumableUploads, TransferPersistence,
+};
+use crate::services::transfer_queue::{
+    dispatch_in_order, safe_concurrency, DispatchOrder, ResolvedJob, TransferJob,
+};
+use crate::settings::app_config::AppConfig;
+use crate::settings::color_support::detect_color_support;
+use crate::settings::file_credentials::{
+    creds_dir, delete_credential, save_credential, FileCredential,
+};
+use crate::settings::key_bindings::KeyBindings;
 use crate::termination::{Interrupted, Terminator};
 use color_eyre::eyre;
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
-use tokio::sync::{broadcast, mpsc};
-use tokio::sync::Semaphore;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::MissedTickBehavior;
 
-/// Maximum simultaneous uploads/downloads
-static S3_OPERATIONS_CONCURRENCY_LEVEL: usize = 8;
+/// Interval of the states carrying the progress of the running transfers, fast transfers report
+/// their chunks far more often than the pages can display them
+const PROGRESS_REFRESH: Duration = Duration::from_millis(100);
+
+/// Part of a listing of the s3 panel, the pages are sent as they arrive
+enum S3ListingPart {
+    Batch(Vec<S3DataItem>),
+    Done,
+    Failed(String),
+    /// The profile of the account couldn't give credentials
+    CredentialsFailed(String),
+    /// s3 answered that the bucket doesn't exist, e.g. another client deleted it
+    BucketMissing(String),
+}
+
+/// Listing of the s3 panel with its request id, bucket and prefix
+type S3Listing = (u64, Option<String>, Option<String>, S3ListingPart);
+
+/// Bucket, prefix and name of a created folder with the error of its creation
+type CreatedFolder = (String, Option<String>, String, Option<String>);
+
+/// Account, location typed into the go to input, the row highlighted before moving and
+/// the result of checking the location, see `check_s3_path`
+type CheckedS3Path = (String, NavigationState, Option<usize>, Result<(), String>);
+
+/// Renamed object with its move and the result of the rename, see `rename_object`
+type RenamedObject = (S3SelectedItem, ObjectCopy, Result<bool, String>);
+
+/// Renamed local entry and its new name, see `rename_local_item`
+type RenamedLocalItem = (LocalDataItem, String, Result<bool, String>);
+
+/// Location and the text read for the clipboard, see `read_object_text`
+type ObjectText = (String, String, Result<String, String>);
+
+/// Senders used by the running transfers to report back to the state store
+#[derive(Clone)]
+struct TransferChannels {
+    s3_results: UnboundedSender<(S3SelectedItem, Result<u64, ErrorClass>)>,
+    local_results: UnboundedSender<(LocalSelectedItem, Result<u64, ErrorClass>)>,
+    download_progress: UnboundedSender<DownloadProgressItem>,
+    upload_progress: UnboundedSender<UploadProgressItem>,
+    /// Sent when a transfer starts, before any of its progress
+    dispatched: UnboundedSender<(String, DispatchOrder)>,
+    /// Collects the durations of the finished transfers
+    metrics: Arc<Metrics>,
+    /// Writer of the manifests of the current run, when they are written
+    manifest: Option<UnboundedSender<ManifestMessage>>,
+    /// Transfers each account may run at once, lowered while its endpoint is overloaded
+    concurrency: AdaptiveConcurrency,
+    /// Pauses and cancellations of single transfers
+    controls: TransferControls,
+    /// Sent when a transfer was paused or cancelled, instead of its result
+    stopped: UnboundedSender<(String, TransferStop)>,
+}
 
 /// Handles all the actions, calls methods on external services and updates the state when necessary
 pub struct StateStore {
     state_tx: UnboundedSender<State>,
+    /// Shared by all the fetchers, shown on the status line
+    in_flight: InFlightRequests,
+    /// Regions of the buckets learned from redirects, shared by all the fetchers
+    bucket_regions: BucketRegions,
+    /// Folder markers checked by the uploads of the session, see `create_folder_markers`
+    folder_markers: FolderMarkers,
+    /// Parts of the multipart uploads saved by the uploads, read back at the next start
+    resumable_uploads: ResumableUploads,
 }
 
 impl StateStore {
     pub fn new() -> (Self, UnboundedReceiver<State>) {
         let (state_tx, state_rx) = mpsc::unbounded_channel::<State>();
 
-        (StateStore { state_tx }, state_rx)
+        (
+            StateStore {
+                state_tx,
+                in_flight: InFlightRequests::default(),
+                bucket_regions: BucketRegions::default(),
+                folder_markers: FolderMarkers::default(),
+                resumable_uploads: ResumableUploads::in_data_dir(),
+            },
+            state_rx,
+        )
     }
 }
 
 impl StateStore {
+    /// Queues the selected items in the order of the transfers list, the queue starts them
+    /// one after another as the running transfers finish and their account has a free slot.
+    /// With `write_manifest` the downloads are listed in the manifest of their destination
+    /// directory
+    fn run_transfers(
+        &self,
+        tasks: &mut TaskRegistry,
+        s3_data_fetcher: &S3DataFetcher,
+        phases: Vec<Vec<ResolvedJob>>,
+        concurrency: usize,
+        write_manifest: bool,
+        channels: TransferChannels,
+    ) {
+        let fetcher = s3_data_fetcher.clone();
+        let expected = expected_files(phases.iter().flatten().filter_map(|r| match &r.job {
+            TransferJob::Download(item) => Some(item),
+            TransferJob::Upload(_) => None,
+        }));
+        let manifest = if write_manifest && !expected.is_empty() {
+            Some(spawn_manifest_writer(expected))
+        } else {
+            None
+        };
+        let channels = TransferChannels {
+            manifest: manifest.clone(),
+            ..channels
+        };
+        tasks.spawn_background(async move {
+            // a phase starts once all the transfers of the previous one finished
+            for jobs in phases {
+                let account_slots = channels.concurrency.clone();
+                let admit = move |resolved: &ResolvedJob| {
+                    let account_slots = account_slots.clone();
+                    let account = resolved.job.account().to_string();
+                    async move { account_slots.acquire(account, concurrency).await }
+                };
+                let fetcher = fetcher.clone();
+                let channels = channels.clone();
+                dispatch_in_order(jobs, concurrency, admit, move |order, resolved| {
+                    let _ = channels.dispatched.send((resolved.job.key(), order));
+                    Self::transfer(fetcher.clone(), resolved, channels.clone())
+                })
+                .await;
+            }
+            // a stopped run doesn't get here, its manifests stay incomplete
+            if let Some(manifest) = manifest {
+                let _ = manifest.send(ManifestMessage::RunFinished);
+            }
+        });
+    }
 
-    fn flatten_s3_items(&self, s3_selected_items: Vec<S3SelectedItem>) -> Vec<S3SelectedItem> {
-        let nested: Vec<Vec<S3SelectedItem>> = s3_selected_items
-            .iter()
-            .map(|i| i.clone().children.unwrap_or_default())
-            .collect();
-        let mut children: Vec<S3SelectedItem> = nested.into_iter().flatten().collect();
-        let single_files: Vec<S3SelectedItem> = s3_selected_items
-            .into_iter()
-            .filter(|i| i.children.is_none())
-            .collect();
-        children.extend(single_files);
-        children
+    /// Runs the jobs unless there are too many of them, some of them write to the same
+    /// destination or a download and an upload touch the same object or local file, such run
+    /// waits for the user to confirm it or to pick how the conflicts are resolved. Jobs whose
+    /// credential was removed fail without starting
+    fn start_transfers(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &mut State,
+        jobs: Vec<TransferJob>,
+        channels: TransferChannels,
+    ) {
+        // the transfers get the connections, the remaining account checks are dropped
+        tasks.abort(STARTUP_TASK);
+        let mut configured = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            if job.credentials(&state.creds).is_some() {
+                configured.push(job);
+            } else {
+                state.fail_missing_credentials(job);
+            }
+        }
+        let adjusted_names =
+            adjust_download_names(&mut configured, state.app_config.file_name_substitution);
+        let average = channels.metrics.average_transfer_duration();
+        match LargeBatch::check(configured, &state.app_config, average) {
+            Ok(jobs) => {
+                if adjusted_names > 0 {
+                    state.notify(format!(
+                        "{} download(s) renamed to valid local file names",
+                        adjusted_names
+                    ));
+                }
+                self.start_unless_conflicting(tasks, state, jobs, channels)
+            }
+            Err(batch) => {
+                state.large_batch = Some(LargeBatch {
+                    adjusted_names,
+                    ..batch
+                });
+                let _ = self.state_tx.send(state.clone());
+            }
+        }
+    }
+
+    fn start_unless_conflicting(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &mut State,
+        jobs: Vec<TransferJob>,
+        channels: TransferChannels,
+    ) {
+        let conflicts = find_conflicts(&jobs, state.app_config.case_insensitive_paths);
+        if conflicts.is_empty() {
+            self.start_unless_overlapping(tasks, state, jobs, channels);
+        } else {
+            state.blocked_transfers = Some(BlockedTransfers { jobs, conflicts });
+            let _ = self.state_tx.send(state.clone());
+        }
     }
 
-    fn flatten_local_items(
+    fn start_unless_overlapping(
         &self,
-        local_selected_items: Vec<LocalSelectedItem>,
-    ) -> Vec<LocalSelectedItem> {
-        let nested: Vec<Vec<LocalSelectedItem>> = local_selected_items
+        tasks: &mut TaskRegistry,
+        state: &mut State,
+        jobs: Vec<TransferJob>,
+        channels: TransferChannels,
+    ) {
+        let overlaps = find_overlaps(&jobs, state.app_config.case_insensitive_paths);
+        if overlaps.is_empty() {
+            self.start_run(tasks, state, vec![jobs], channels);
+        } else {
+            state.overlapping_transfers = Some(OverlappingTransfers { jobs, overlaps });
+        }
+        let _ = self.state_tx.send(state.clone());
+    }
+
+    /// Starts the phases one after another. The credentials are looked up by name at this
+    /// point, so a credential edited while the run waited for a confirmation is used
+    fn start_run(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &mut State,
+        phases: Vec<Vec<TransferJob>>,
+        channels: TransferChannels,
+    ) {
+        let mut resolved_phases = Vec::with_capacity(phases.len());
+        for phase in phases {
+            let mut resolved = Vec::with_capacity(phase.len());
+            for job in phase {
+                match job.resolve(&state.creds) {
+                    Ok(job) => resolved.push(job),
+                    Err(job) => state.fail_missing_credentials(job),
+                }
+            }
+            resolved_phases.push(resolved);
+        }
+        let jobs: Vec<TransferJob> = resolved_phases
             .iter()
-            .map(|i| i.clone().children.unwrap_or_default())
+            .flatten()
+            .map(|resolved| resolved.job.clone())
             .collect();
-        let mut children: Vec<LocalSelectedItem> = nested.into_iter().flatten().collect();
-        let single_files: Vec<LocalSelectedItem> = local_selected_items
-            .into_iter()
-            .filter(|i| i.children.is_none())
-            .collect();
-        children.extend(single_files);
-        children
+        for job in &jobs {
+            channels.controls.release(&job.key());
+        }
+        state.start_transfer_batch(&jobs);
+        let s3_data_fetcher = self
+            .get_current_s3_fetcher(state)
+            .sharing_folder_markers(&self.folder_markers)
+            .sharing_resumable_uploads(&self.resumable_uploads);
+        self.run_transfers(
+            tasks,
+            &s3_data_fetcher,
+            resolved_phases,
+            state.app_config.transfer_concurrency,
+            state.write_manifest,
+            channels,
+        );
     }
-    async fn download_data(
-        &self,
-        s3_data_fetcher: &S3DataFetcher,
-        s3_selected_items: Vec<S3SelectedItem>,
-        selected_s3_transfers_tx: UnboundedSender<S3SelectedItem>,
-        download_tx: UnboundedSender<DownloadProgressItem>,
-    ) {
-        let items_with_children = self.flatten_s3_items(s3_selected_items);
-        let semaphore = Arc::new(Semaphore::new(S3_OPERATIONS_CONCURRENCY_LEVEL)); // Adjust the number based on system capabilities
-        for item in items_with_children {
-            if !item.is_bucket && !item.is_directory {
-                let tx = selected_s3_transfers_tx.clone();
-                let down_tx = download_tx.clone();
-                let fetcher = s3_data_fetcher.clone();
-                let semaphore = semaphore.clone();
-                tokio::spawn(async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    match fetcher.download_item(item.clone(), down_tx).await {
-                        Ok(_) => {
-                            if tx.send(item.clone()).is_err() {
-                                tracing::error!("Failed to send downloaded item");
-                            }
+
+    async fn transfer(fetcher: S3DataFetcher, resolved: ResolvedJob, channels: TransferChannels) {
+        let ResolvedJob { job, creds } = resolved;
+        let started = Instant::now();
+        let account = job.account().to_string();
+        let key = job.key();
+        let stop = channels.controls.watch(&key);
+        let outcome = match job {
+            TransferJob::Download(item) => {
+                let download =
+                    fetcher.download_item(item.clone(), creds, channels.download_progress);
+                match until_stopped(stop, download).await {
+                    Err(stop) => {
+                        // a paused download continues from its partial file
+                        if stop == TransferStop::Cancelled {
+                            PartialDownload::new(&item.local_destination()).discard();
                         }
-                        Err(e) => {
-                            tracing::error!("Failed to download data: {}",  e);
-                            let orig_item = item.clone();
-                            let errored_item = S3SelectedItem {
-                                error: Some(e.to_string()),
-                                transferred: false,
-                                progress: 0f64,
-                                ..orig_item
-                            };
-                            if tx.send(errored_item).is_err() {
-                                tracing::error!("Failed to send item in error");
-                            }
+                        let _ = channels.stopped.send((key, stop));
+                        return;
+                    }
+                    Ok(Ok(downloaded)) => {
+                        if let Some(manifest) = &channels.manifest {
+                            let entry = ManifestEntry::downloaded(
+                                &item,
+                                downloaded.bytes,
+                                downloaded.etag,
+                                downloaded.checksum,
+                            );
+                            let _ = manifest.send(ManifestMessage::Entry(entry));
                         }
+                        let downloaded_item = S3SelectedItem {
+                            verification: downloaded.verification,
+                            ..item
+                        };
+                        if channels
+                            .s3_results
+                            .send((downloaded_item, Ok(downloaded.bytes)))
+                            .is_err()
+                        {
+                            tracing::error!("Failed to send downloaded item");
+                        }
+                        RequestOutcome::Success
                     }
-                });
+                    Ok(Err(e)) => {
+                        tracing::error!("Failed to download data: {}", e);
+                        let verification = e
+                            .downcast_ref::<VerificationError>()
+                            .map(|e| Verification::Failed(e.method));
+                        let errored_item = S3SelectedItem {
+                            error: Some(describe_report(&e)),
+                            transferred: false,
+                            progress: 0f64,
+                            verification,
+                            ..item
+                        };
+                        let class = classify_report(&e);
+                        if channels
+                            .s3_results
+                            .send((errored_item, Err(class)))
+                            .is_err()
+                        {
+                            tracing::error!("Failed to send item in error");
+                        }
+                        RequestOutcome::of_error(class)
+                    }
+                }
             }
-        }
-    }
-
-    async fn upload_data(
-        &self,
-        s3_data_fetcher: &S3DataFetcher,
-        local_selected_items: Vec<LocalSelectedItem>,
-        selected_local_transfers_tx: UnboundedSender<LocalSelectedItem>,
-        upload_tx: UnboundedSender<UploadProgressItem>,
-    ) {
-        let items_with_children = self.flatten_local_items(local_selected_items);
-        let semaphore = Arc::new(Semaphore::new(S3_OPERATIONS_CONCURRENCY_LEVEL)); // Adjust the number based on system capabilities
-        for item in items_with_children {
-            if !item.is_directory {
-                let local_tx = selected_local_transfers_tx.clone();
-                let up_tx = upload_tx.clone();
-                let fetcher = s3_data_fetcher.clone();
-                let semaphore = semaphore.clone();
-                tokio::spawn(async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    match fetcher.upload_item(item.clone(), up_tx).await {
-                        Ok(_) => {
-                            if local_tx.send(item.clone()).is_err() {
-                                tracing::error!("Failed to send uploaded item");
-                            }
+            TransferJob::Upload(item) => {
+                let upload =
+                    fetcher.upload_item(item.clone(), creds.clone(), channels.upload_progress);
+                match until_stopped(stop, upload).await {
+                    Err(stop) => {
+                        // the parts already sent can't be resumed
+                        fetcher.abort_incomplete_uploads(&item, &creds).await;
+                        let _ = channels.stopped.send((key, stop));
+                        return;
+                    }
+                    Ok(Ok(uploaded)) => {
+                        let uploaded_item = LocalSelectedItem {
+                            verification: uploaded.verification,
+                            ..item
+                        };
+                        if channels
+                            .local_results
+                            .send((uploaded_item, Ok(uploaded.bytes)))
+                            .is_err()
+                        {
+                            tracing::error!("Failed to send uploaded item");
                         }
-                        Err(e) => {
-                            tracing::error!("Failed to upload data: {}", e);
-                            let orig_item = item.clone();
-                            let errored_item = LocalSelectedItem {
-                                error: Some(e.to_string()),
-                                transferred: false,
-                                progress: 0f64,
-                                ..orig_item
-                            };
-                            if local_tx.send(errored_item).is_err() {
-                                tracing::error!("Failed to send item in error");
-                            }
+                        RequestOutcome::Success
+                    }
+                    Ok(Err(e)) => {
+                        tracing::error!("Failed to upload data: {}", e);
+                        // the object of a failed verification stays in the bucket
+                        let verification = e
+                            .downcast_ref::<VerificationError>()
+                            .map(|e| Verification::Failed(e.method));
+                        let errored_item = LocalSelectedItem {
+                            error: Some(describe_report(&e)),
+                            transferred: false,
+                            progress: 0f64,
+                            verification,
+                            ..item
+                        };
+                        let class = classify_report(&e);
+                        if channels
+                            .local_results
+                            .send((errored_item, Err(class)))
+                            .is_err()
+                        {
+                            tracing::error!("Failed to send item in error");
                         }
+                        RequestOutcome::of_error(class)
                     }
-                });
+                }
+            }
+        };
+        let elapsed = started.elapsed();
+        channels.metrics.observe_transfer(elapsed);
+        channels.concurrency.record(&account, outcome, elapsed);
+    }
+
+    /// Moves the s3 panel and lists the new location. Opening the location whose listing is
+    /// still running is ignored, so a held Enter spawns one listing and adds one history entry
+    async fn navigate_s3(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &mut State,
+        step: NavigationStep,
+        row: Option<usize>,
+        s3_tx: UnboundedSender<S3Listing>,
+    ) {
+        if let NavigationStep::Into(location) = &step {
+            let listing = tasks.latest_id(S3_LISTING_TASK) == Some(state.s3_listing_request);
+            if state.s3_loading && listing && *location == state.s3_location() {
+                tracing::debug!("Ignoring repeated navigation to {:?}", location);
+                return;
             }
         }
+        if let Some(location) = state.navigate_s3(step, row) {
+            state.set_s3_loading(true);
+            let _ = self.state_tx.send(state.clone());
+            let s3_data_fetcher = self.get_current_s3_fetcher(state);
+            state.s3_listing_request = self
+                .fetch_s3_data(
+                    tasks,
+                    location.current_bucket,
+                    location.current_prefix,
+                    s3_data_fetcher,
+                    s3_tx,
+                )
+                .await;
+        }
     }
 
+    /// Starts listing the s3 location, a listing still running for the s3 panel is aborted.
+    /// Returns the request id which is sent back with every batch of the data
     async fn fetch_s3_data(
         &self,
+        tasks: &mut TaskRegistry,
         bucket: Option<String>,
         prefix: Option<String>,
         s3_data_fetcher: S3DataFetcher,
-        s3_tx: UnboundedSender<(Option<String>, Option<String>, Vec<S3DataItem>)>,
-    ) {
-        tokio::spawn(async move {
-            match s3_data_fetcher
-                .list_current_location(bucket.clone(), prefix.clone())
-                .await
-            {
-                Ok(data) => {
-                    let _ = s3_tx.send((bucket.clone(), prefix.clone(), data));
+        s3_tx: UnboundedSender<S3Listing>,
+    ) -> u64 {
+        tasks.spawn(S3_LISTING_TASK, move |request_id| async move {
+            let listed = s3_data_fetcher
+                .list_current_location(bucket.clone(), prefix.clone(), |batch| {
+                    let part = S3ListingPart::Batch(batch);
+                    let _ = s3_tx.send((request_id, bucket.clone(), prefix.clone(), part));
+                })
+                .await;
+            let part = match listed {
+                Ok(()) => S3ListingPart::Done,
+                Err(e) if e.downcast_ref::<ProfileError>().is_some() => {
+                    tracing::error!("Failed to fetch S3 data: {}", e);
+                    S3ListingPart::CredentialsFailed(e.to_string())
+                }
+                Err(e) if classify_report(&e) == ErrorClass::MissingBucket => {
+                    tracing::error!("Bucket of the listing is missing: {}", e);
+                    S3ListingPart::BucketMissing(e.to_string())
                 }
                 Err(e) => {
                     tracing::error!("Failed to fetch S3 data: {}", e);
+                    S3ListingPart::Failed(e.to_string())
+                }
+            };
+            let _ = s3_tx.send((request_id, bucket, prefix, part));
+        })
+    }
+
+    /// Resolves the regions missing from the displayed bucket list one bucket after the
+    /// other, the rows are updated as the regions arrive
+    fn resolve_bucket_regions(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &State,
+        regions_tx: UnboundedSender<(String, String, String)>,
+    ) {
+        let buckets = state.unresolved_bucket_regions();
+        if buckets.is_empty() {
+            return;
+        }
+        let account = state.current_creds.name.clone();
+        let s3_data_fetcher = self.get_current_s3_fetcher(state);
+        tasks.spawn(BUCKET_REGIONS_TASK, move |_| async move {
+            for bucket in buckets {
+                match s3_data_fetcher.bucket_location(&bucket).await {
+                    Ok(region) => {
+                        if regions_tx.send((account.clone(), bucket, region)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => tracing::warn!("Cannot resolve the region of {}: {}", bucket, e),
                 }
             }
         });
     }
 
+    /// Shows the bucket list again once s3 answered that the browsed bucket doesn't exist,
+    /// the transfers to or from it still waiting for their turn are stopped.
+    /// Returns false when it's not the browsed bucket
+    async fn leave_missing_bucket(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &mut State,
+        bucket: &str,
+        controls: &TransferControls,
+        s3_tx: &UnboundedSender<S3Listing>,
+    ) -> bool {
+        let Some(waiting) = state.forget_missing_bucket(bucket) else {
+            return false;
+        };
+        self.bucket_regions.forget(bucket);
+        for key in waiting {
+            controls.stop(&key, TransferStop::Cancelled);
+        }
+        state.set_s3_loading(true);
+        let _ = self.state_tx.send(state.clone());
+        let s3_data_fetcher = self.get_current_s3_fetcher(state);
+        state.s3_listing_request = self
+            .fetch_s3_data(tasks, None, None, s3_data_fetcher, s3_tx.clone())
+            .await;
+        true
+    }
+
     async fn list_s3_data_recursive(
         &self,
+        tasks: &mut TaskRegistry,
         item: S3SelectedItem,
         s3_data_fetcher: S3DataFetcher,
-        s3_full_list_tx: UnboundedSender<(Option<String>, Option<String>, Vec<S3DataItem>)>,
+        limit: SelectionLimit,
+        s3_full_list_tx: UnboundedSender<Result<LimitedSelection, String>>,
     ) {
         tracing::info!("list_s3_Data_recursive");
-        tokio::spawn(async move {
+        tasks.spawn_background(async move {
             let bucket_name = if item.is_bucket {
                 item.name
             } else {
@@ -192,15 +595,16 @@ impl StateStore {
                 item.path.clone()
             };
             match s3_data_fetcher
-                .list_all_objects(&bucket_name, path.clone())
+                .list_all_objects(&bucket_name, path, limit)
                 .await
             {
                 Ok(data) => {
-                    tracing::info!("Downloaded items: {}", data.len());
-                    let _ = s3_full_list_tx.send((Some(bucket_name), path.clone(), data));
+                    tracing::info!("Downloaded items: {}", data.items.len());
+                    let _ = s3_full_list_tx.send(Ok(data));
                 }
                 Err(e) => {
                     tracing::error!("Failed to fetch S3 data: {}", e);
+                    let _ = s3_full_list_tx.send(Err(e.to_string()));
                 }
             }
         });
@@ -226,6 +630,38 @@ impl StateStore {
         });
     }
 
+    /// Collects the files of a selected directory without blocking the state updates,
+    /// large directories can take a while to walk
+    async fn list_local_directory(
+        &self,
+        item: LocalSelectedItem,
+        options: WalkOptions,
+        local_walk_tx: UnboundedSender<LocalSelectedItem>,
+    ) {
+        tokio::spawn(async move {
+            let cancellation = options.cancellation.clone();
+            let listing = tokio::task::spawn_blocking(move || {
+                let children = LocalSelectedItem::list_directory_items(&item, &options);
+                LocalSelectedItem {
+                    children: Some(children),
+                    ..item
+                }
+            })
+            .await;
+            match listing {
+                Ok(_) if cancellation.is_cancelled() => {
+                    tracing::info!("Listing of the local directory was cancelled");
+                }
+                Ok(item) => {
+                    let _ = local_walk_tx.send(item);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to list local directory: {}", e);
+                }
+            }
+        });
+    }
+
     fn get_directory_path(input_path: Option<String>) -> Option<String> {
         match input_path {
             Some(path) => {
@@ -270,18 +706,18 @@ impl StateStore {
         &self,
         item: LocalSelectedItem,
         local_data_fetcher: LocalDataFetcher,
-        local_deleted_tx: UnboundedSender<Option<String>>,
+        local_deleted_tx: UnboundedSender<(String, Option<String>)>,
     ) {
         let path = item.path.clone();
         if item.is_directory {
             tokio::spawn(async move {
                 match local_data_fetcher.delete_directory(path.clone()).await {
                     Ok(_) => {
-                        let _ = local_deleted_tx.send(None);
+                        let _ = local_deleted_tx.send((path, None));
                     }
                     Err(e) => {
                         tracing::error!("Failed to delete local directory: {}", e);
-                        let _ = local_deleted_tx.send(Some(e.to_string()));
+                        let _ = local_deleted_tx.send((path, Some(e.to_string())));
                     }
                 }
             });
@@ -289,62 +725,169 @@ impl StateStore {
             tokio::spawn(async move {
                 match local_data_fetcher.delete_file(path.clone()).await {
                     Ok(_) => {
-                        let _ = local_deleted_tx.send(None);
+                        let _ = local_deleted_tx.send((path, None));
                     }
                     Err(e) => {
                         tracing::error!("Failed to delete local file: {}", e);
-                        let _ = local_deleted_tx.send(Some(e.to_string()));
+                        let _ = local_deleted_tx.send((path, Some(e.to_string())));
                     }
                 }
             });
         }
     }
 
+    /// Deletes the object, or the objects listed under the directory or the bucket with
+    /// batched DeleteObjects requests reporting the progress after every batch.
+    /// The bucket itself is deleted once all of its objects are gone
     async fn delete_s3_data(
         &self,
+        tasks: &mut TaskRegistry,
         item: S3SelectedItem,
         s3_data_fetcher: S3DataFetcher,
-        s3_delete_tx: UnboundedSender<Option<String>>,
-    ) {
-        let items_with_children = self.flatten_s3_items(vec![item]);
-        let semaphore = Arc::new(Semaphore::new(S3_OPERATIONS_CONCURRENCY_LEVEL)); // Adjust the number based on system capabilities
-        for item in items_with_children {
-            if !item.is_directory {
-                let delete_tx = s3_delete_tx.clone();
-                let fetcher = s3_data_fetcher.clone();
-                let semaphore = semaphore.clone();
-                tokio::spawn(async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    match fetcher
-                        .delete_data(
-                            item.is_bucket,
-                            item.bucket.clone(),
-                            item.path.clone().unwrap_or(item.name.clone()),
-                            item.is_directory,
-                        )
-                        .await
-                    {
-                        Ok(data) => {
-                            let _ = delete_tx.send(data);
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to delete S3 data: {}", e);
-                            let _ =
-                                delete_tx.send(Some(format!("Failed to delete S3 data: {}", e)));
-                        }
-                    }
-                });
+        s3_delete_tx: UnboundedSender<(S3SelectedItem, Option<String>)>,
+        delete_progress_tx: UnboundedSender<DeleteProgress>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = if item.is_directory || item.is_bucket {
+                Self::delete_recursively(&s3_data_fetcher, &item, delete_progress_tx).await
+            } else {
+                s3_data_fetcher
+                    .delete_data(
+                        false,
+                        item.bucket.clone(),
+                        item.path.clone().unwrap_or(item.name.clone()),
+                        false,
+                    )
+                    .await
+            };
+            match result {
+                Ok(data) => {
+                    let _ = s3_delete_tx.send((item, data));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to delete S3 data: {}", e);
+                    let error = format!("Failed to delete S3 data: {}", e);
+                    let _ = s3_delete_tx.send((item, Some(error)));
+                }
             }
+        });
+    }
+
+    /// Empties the directory or the bucket and deletes the bucket, `Some` lists the objects
+    /// which could not be deleted. A bucket with objects left is kept
+    async fn delete_recursively(
+        s3_data_fetcher: &S3DataFetcher,
+        item: &S3SelectedItem,
+        delete_progress_tx: UnboundedSender<DeleteProgress>,
+    ) -> eyre::Result<Option<String>> {
+        let bucket = match item.is_bucket {
+            true => item.name.clone(),
+            false => item.bucket.clone().unwrap_or_default(),
+        };
+        let keys = recursive_delete_keys(item);
+        let mut progress = DeleteProgress {
+            location: format!("s3://{}/{}", bucket, item.path.clone().unwrap_or_default()),
+            deleted: 0,
+            total: keys.len(),
+        };
+        let _ = delete_progress_tx.send(progress.clone());
+        let failed = s3_data_fetcher
+            .delete_objects(&bucket, keys.clone(), |batch| {
+                progress.deleted += batch;
+                let _ = delete_progress_tx.send(progress.clone());
+            })
+            .await?;
+        if !failed.is_empty() {
+            let failed_keys: HashSet<&String> = failed.iter().map(|(key, _)| key).collect();
+            let deletion = BulkDeletion {
+                deleted_s3: keys
+                    .iter()
+                    .filter(|key| !failed_keys.contains(key))
+                    .map(|key| (bucket.clone(), key.clone()))
+                    .collect(),
+                deleted_local: Vec::new(),
+                failed: failed
+                    .iter()
+                    .map(|(key, reason)| (format!("s3://{}/{}", bucket, key), reason.clone()))
+                    .collect(),
+            };
+            return Ok(deletion.error_message());
+        }
+        tracing::info!("Deleted {} objects in {}", keys.len(), progress.location);
+        match item.is_bucket {
+            true => s3_data_fetcher.delete_data(true, None, bucket, false).await,
+            false => Ok(None),
         }
     }
 
+    /// Deletes the selected objects bucket by bucket with DeleteObjects and then the selected
+    /// local items, the outcome of all of them is sent at once
+    async fn delete_selected_items(
+        &self,
+        tasks: &mut TaskRegistry,
+        s3_deletions: Vec<(S3DataFetcher, S3Deletion)>,
+        local_items: Vec<LocalSelectedItem>,
+        local_data_fetcher: LocalDataFetcher,
+        bulk_deleted_tx: UnboundedSender<BulkDeletion>,
+    ) {
+        tasks.spawn_background(async move {
+            let mut deletion = BulkDeletion::default();
+            for (fetcher, s3_deletion) in s3_deletions {
+                let bucket = s3_deletion.bucket;
+                let failed = match fetcher
+                    .delete_objects(&bucket, s3_deletion.keys.clone(), |_| {})
+                    .await
+                {
+                    Ok(failed) => failed,
+                    Err(e) => {
+                        tracing::error!("Failed to delete objects in {}: {}", bucket, e);
+                        s3_deletion
+                            .keys
+                            .iter()
+                            .map(|key| (key.clone(), e.to_string()))
+                            .collect()
+                    }
+                };
+                let failed_keys: HashSet<&String> = failed.iter().map(|(key, _)| key).collect();
+                deletion.deleted_s3.extend(
+                    s3_deletion
+                        .keys
+                        .iter()
+                        .filter(|key| !failed_keys.contains(key))
+                        .map(|key| (bucket.clone(), key.clone())),
+                );
+                deletion.failed.extend(
+                    failed
+                        .iter()
+                        .map(|(key, reason)| (format!("s3://{}/{}", bucket, key), reason.clone())),
+                );
+            }
+            for item in local_items {
+                let result = if item.is_directory {
+                    local_data_fetcher.delete_directory(item.path.clone()).await
+                } else {
+                    local_data_fetcher.delete_file(item.path.clone()).await
+                };
+                match result {
+                    Ok(path) => deletion.deleted_local.push(path),
+                    Err(e) => {
+                        tracing::error!("Failed to delete local item {}: {}", item.path, e);
+                        deletion.failed.push((item.path, e.to_string()));
+                    }
+                }
+            }
+            let _ = bulk_deleted_tx.send(deletion);
+        });
+    }
+
     async fn create_bucket(
         &self,
+        tasks: &mut TaskRegistry,
         name: String,
         s3_data_fetcher: S3DataFetcher,
         create_bucket_tx: UnboundedSender<Option<String>>,
     ) {
-        tokio::spawn(async move {
+        tasks.spawn_background(async move {
             match s3_data_fetcher
                 .create_bucket(name.clone(), s3_data_fetcher.default_region.clone())
                 .await
@@ -360,88 +903,701 @@ impl StateStore {
         });
     }
 
-    fn get_current_s3_fetcher(state: &State) -> S3DataFetcher {
-        S3DataFetcher::new(state.current_creds.clone())
+    async fn create_folder(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        prefix: Option<String>,
+        name: String,
+        s3_data_fetcher: S3DataFetcher,
+        create_folder_tx: UnboundedSender<CreatedFolder>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = match s3_data_fetcher
+                .create_folder(bucket.clone(), prefix.clone(), name.clone())
+                .await
+            {
+                Ok(error) => error,
+                Err(e) => {
+                    tracing::error!("Failed to create S3 folder: {}", e);
+                    Some(format!("Failed to create folder: {}", e))
+                }
+            };
+            let _ = create_folder_tx.send((bucket, prefix, name, result));
+        });
     }
 
-    pub async fn main_loop(
-        self,
-        mut terminator: Terminator,
-        mut action_rx: UnboundedReceiver<Action>,
-        mut interrupt_rx: broadcast::Receiver<Interrupted>,
-        creds: Vec<FileCredential>,
-    ) -> eyre::Result<Interrupted> {
-        let local_data_fetcher = LocalDataFetcher::new();
-        let mut state = State::new(creds.clone());
-        let s3_data_fetcher = Self::get_current_s3_fetcher(&state);
-        state.set_s3_loading(true);
-        state.set_current_local_path(
-            dirs::home_dir()
-                .unwrap()
-                .as_path()
-                .to_string_lossy()
-                .to_string(),
-        );
+    /// Lists the location once to tell whether it can be opened before it's shown
+    async fn check_s3_path(
+        &self,
+        tasks: &mut TaskRegistry,
+        account: String,
+        location: NavigationState,
+        row: Option<usize>,
+        s3_data_fetcher: S3DataFetcher,
+        go_to_tx: UnboundedSender<CheckedS3Path>,
+    ) {
+        tasks.spawn_background(async move {
+            let bucket = location.current_bucket.clone().unwrap_or_default();
+            let prefix = location.current_prefix.clone();
+            let path = format!("s3://{}/{}", bucket, prefix.clone().unwrap_or_default());
+            let result = match s3_data_fetcher
+                .prefix_exists(&bucket, prefix.as_deref())
+                .await
+            {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!("Nothing found under {}", path)),
+                Err(e) => {
+                    tracing::error!("Failed to check {}: {}", path, e);
+                    Err(format!("Can't open {}: {}", path, e))
+                }
+            };
+            let _ = go_to_tx.send((account, location, row, result));
+        });
+    }
 
-        let (s3_tx, mut s3_rx) =
-            mpsc::unbounded_channel::<(Option<String>, Option<String>, Vec<S3DataItem>)>();
-        let (s3_full_list_tx, mut s3_full_list_rx) =
-            mpsc::unbounded_channel::<(Option<String>, Option<String>, Vec<S3DataItem>)>();
-        let (s3_deleted_tx, mut s3_deleted_rx) = mpsc::unbounded_channel::<Option<String>>();
-        let (local_tx, mut local_rx) = mpsc::unbounded_channel::<(String, Vec<LocalDataItem>)>();
-        let (local_deleted_tx, mut local_deleted_rx) = mpsc::unbounded_channel::<Option<String>>();
-        let (selected_s3_transfers_tx, mut selected_s3_transfers_rx) =
-            mpsc::unbounded_channel::<S3SelectedItem>();
-        let (selected_local_transfers_tx, mut selected_local_transfers_rx) =
-            mpsc::unbounded_channel::<LocalSelectedItem>();
-        let (upload_tx, mut upload_rx) = mpsc::unbounded_channel::<UploadProgressItem>();
-        let (download_tx, mut download_rx) = mpsc::unbounded_channel::<DownloadProgressItem>();
-        let (create_bucket_tx, mut create_bucket_rx) = mpsc::unbounded_channel::<Option<String>>();
+    async fn copy_s3_item(
+        &self,
+        tasks: &mut TaskRegistry,
+        copy: ObjectCopy,
+        s3_data_fetcher: S3DataFetcher,
+        copy_tx: UnboundedSender<(ObjectCopy, Result<(), String>)>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .copy_object(&copy)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = copy_tx.send((copy, result));
+        });
+    }
+
+    async fn rename_s3_item(
+        &self,
+        tasks: &mut TaskRegistry,
+        item: S3SelectedItem,
+        rename: ObjectCopy,
+        overwrite: bool,
+        s3_data_fetcher: S3DataFetcher,
+        rename_tx: UnboundedSender<RenamedObject>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .rename_object(&rename, overwrite)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = rename_tx.send((item, rename, result));
+        });
+    }
+
+    async fn rename_local_item(
+        &self,
+        item: LocalDataItem,
+        new_name: String,
+        overwrite: bool,
+        local_data_fetcher: LocalDataFetcher,
+        local_rename_tx: UnboundedSender<RenamedLocalItem>,
+    ) {
+        tokio::spawn(async move {
+            let result = local_data_fetcher
+                .rename(item.path.clone(), new_name.clone(), overwrite)
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(e) = &result {
+                tracing::error!("Failed to rename local item: {}", e);
+            }
+            let _ = local_rename_tx.send((item, new_name, result));
+        });
+    }
 
-        self.fetch_s3_data(None, None, s3_data_fetcher.clone(), s3_tx.clone())
+    /// Reloads the displayed listings changed by a finished batch of transfers
+    async fn refresh_listings(
+        &self,
+        refresh: BatchRefresh,
+        state: &mut State,
+        tasks: &mut TaskRegistry,
+        local_data_fetcher: &LocalDataFetcher,
+        s3_tx: &UnboundedSender<S3Listing>,
+        local_tx: &UnboundedSender<(String, Vec<LocalDataItem>)>,
+    ) {
+        if refresh.s3 {
+            let s3_data_fetcher = self.get_current_s3_fetcher(state);
+            state.s3_listing_request = self
+                .fetch_s3_data(
+                    tasks,
+                    state.current_s3_bucket.clone(),
+                    state.current_s3_path.clone(),
+                    s3_data_fetcher,
+                    s3_tx.clone(),
+                )
+                .await;
+        }
+        if refresh.local {
+            self.fetch_local_data(
+                Some(state.current_local_path.clone()),
+                local_data_fetcher.clone(),
+                local_tx.clone(),
+            )
             .await;
-        self.fetch_local_data(
-            Some(
-                dirs::home_dir()
-                    .unwrap()
-                    .as_path()
-                    .to_string_lossy()
-                    .to_string(),
-            ),
-            local_data_fetcher.clone(),
-            local_tx.clone(),
-        )
-        .await;
+        }
+    }
 
-        // the initial state once
-        self.state_tx.send(state.clone())?;
+    async fn fetch_object_metadata(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        key: String,
+        s3_data_fetcher: S3DataFetcher,
+        metadata_tx: UnboundedSender<Result<ObjectMetadata, String>>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .head_object_metadata(&bucket, &key)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = metadata_tx.send(result);
+        });
+    }
 
-        let _ticker = tokio::time::interval(Duration::from_secs(1));
+    async fn save_object_metadata(
+        &self,
+        tasks: &mut TaskRegistry,
+        metadata: ObjectMetadata,
+        s3_data_fetcher: S3DataFetcher,
+        metadata_tx: UnboundedSender<Result<ObjectMetadata, String>>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .replace_user_metadata(&metadata)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = metadata_tx.send(result);
+        });
+    }
 
-        let result = loop {
-            tokio::select! {
-                        Some(action) = action_rx.recv() => match action {
-                            Action::Exit => {
-                                let _ = terminator.terminate(Interrupted::UserInt);
-                                break Interrupted::UserInt;
-                            },
-                            Action::Navigate { page} => {
-                                state.set_active_page(page);
-                                let _ = self.state_tx.send(state.clone());
-                            }
-                            Action::FetchLocalData { path} =>
-                                self.fetch_local_data(Some(path), local_data_fetcher.clone(), local_tx.clone()).await,
-                            Action::FetchS3Data { bucket, prefix } => {
-                                state.set_s3_loading(true);
-                                let _ = self.state_tx.send(state.clone());
-                                let s3_data_fetcher = Self::get_current_s3_fetcher(&state);
-                                self.fetch_s3_data(bucket, prefix, s3_data_fetcher, s3_tx.clone()).await
-                            }
-                            Action::ListS3DataRecursiveForItem { item } => {
-                                state.set_s3_list_recursive_loading(true);
+    async fn change_storage_class(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        key: String,
+        storage_class: String,
+        s3_data_fetcher: S3DataFetcher,
+        metadata_tx: UnboundedSender<Result<ObjectMetadata, String>>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .change_storage_class(&bucket, &key, &storage_class)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = metadata_tx.send(result);
+        });
+    }
+
+    async fn compare_object_with_local(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        key: String,
+        local_path: String,
+        full_hash: bool,
+        s3_data_fetcher: S3DataFetcher,
+        comparison_tx: UnboundedSender<Result<FileComparison, String>>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .compare_with_local(&bucket, &key, &local_path, full_hash)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = comparison_tx.send(result);
+        });
+    }
+
+    async fn preview_object(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        key: String,
+        max_bytes: u64,
+        s3_data_fetcher: S3DataFetcher,
+        preview_tx: UnboundedSender<Result<ObjectPreview, String>>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .preview_object(&bucket, &key, max_bytes)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = preview_tx.send(result);
+        });
+    }
+
+    async fn read_object_text(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        key: String,
+        max_bytes: u64,
+        s3_data_fetcher: S3DataFetcher,
+        object_text_tx: UnboundedSender<ObjectText>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .read_object_text(&bucket, &key, max_bytes)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = object_text_tx.send((bucket, key, result));
+        });
+    }
+
+    async fn presign_object_url(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        key: String,
+        expires_in: Duration,
+        s3_data_fetcher: S3DataFetcher,
+        presigned_url_tx: UnboundedSender<ObjectText>,
+    ) {
+        tasks.spawn_background(async move {
+            let result = s3_data_fetcher
+                .presigned_url(&bucket, &key, expires_in)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = presigned_url_tx.send((bucket, key, result));
+        });
+    }
+
+    async fn fetch_object_lock_info(
+        &self,
+        tasks: &mut TaskRegistry,
+        bucket: String,
+        key: String,
+        s3_data_fetcher: S3DataFetcher,
+        lock_tx: UnboundedSender<(String, String, ObjectLockInfo)>,
+    ) {
+        tasks.spawn_background(async move {
+            // without the lock status the confirmation is simply shown without a warning
+            if let Ok(info) = s3_data_fetcher.get_object_lock_info(&bucket, &key).await {
+                let _ = lock_tx.send((bucket, key, info));
+            }
+        });
+    }
+
+    fn get_current_s3_fetcher(&self, state: &State) -> S3DataFetcher {
+        S3DataFetcher::new(
+            state.current_creds.clone(),
+            &state.app_config,
+            self.in_flight.clone(),
+            self.bucket_regions.clone(),
+            &state.skip_tls_verify_confirmed,
+        )
+    }
+
+    /// Writes the entries of the selection to the file in the background
+    fn export_selection(
+        &self,
+        tasks: &mut TaskRegistry,
+        path: String,
+        entries: Vec<SelectionEntry>,
+        export_tx: UnboundedSender<Result<String, String>>,
+    ) {
+        tasks.spawn_background(async move {
+            let count = entries.len();
+            let file = path.clone();
+            let written: eyre::Result<()> = async {
+                tokio::task::spawn_blocking(move || export_selection(Path::new(&file), &entries))
+                    .await??;
+                Ok(())
+            }
+            .await;
+            let _ = export_tx.send(match written {
+                Ok(()) => Ok(format!("Exported {} entries to {}", count, path)),
+                Err(e) => Err(format!("Cannot export the selection to {}: {}", path, e)),
+            });
+        });
+    }
+
+    /// Reads the selection file and checks its entries against the current account: the
+    /// buckets have to exist, the keys of some of the downloads are looked up
+    fn import_selection(
+        &self,
+        tasks: &mut TaskRegistry,
+        path: String,
+        s3_data_fetcher: S3DataFetcher,
+        import_tx: UnboundedSender<Result<SelectionImport, String>>,
+    ) {
+        tasks.spawn_background(async move {
+            let import = Self::check_import(&s3_data_fetcher, path.clone())
+                .await
+                .map_err(|e| format!("Cannot import the selection from {}: {}", path, e));
+            let _ = import_tx.send(import);
+        });
+    }
+
+    async fn check_import(
+        s3_data_fetcher: &S3DataFetcher,
+        path: String,
+    ) -> eyre::Result<SelectionImport> {
+        let file = path.clone();
+        let entries =
+            tokio::task::spawn_blocking(move || import_selection(Path::new(&file))).await??;
+        let mut checks = ImportChecks::default();
+        let buckets: HashSet<String> = entries
+            .iter()
+            .map(|entry| match entry {
+                SelectionEntry::Download { bucket, .. } | SelectionEntry::Upload { bucket, .. } => {
+                    bucket.clone()
+                }
+            })
+            .collect();
+        for bucket in buckets {
+            if !s3_data_fetcher.bucket_exists(&bucket).await? {
+                checks.missing_buckets.insert(bucket);
+            }
+        }
+        for (bucket, key) in spot_checked(&entries, SPOT_CHECKED_KEYS) {
+            if !checks.missing_buckets.contains(&bucket)
+                && !s3_data_fetcher.object_exists(&bucket, &key).await?
+            {
+                checks.missing_keys.insert((bucket, key));
+            }
+        }
+        checks.missing_files = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                SelectionEntry::Upload { path, .. } if !Path::new(path).is_file() => {
+                    Some(path.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        Ok(SelectionImport {
+            path,
+            entries,
+            checks,
+        })
+    }
+
+    /// Checks the accounts one after the other once the bucket list of the current one is
+    /// shown, see `startup_work`
+    fn start_startup_work(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &State,
+        listed: oneshot::Receiver<()>,
+        results: UnboundedSender<(String, AccountCheck)>,
+    ) {
+        let steps = startup_order(&state.creds, &state.current_creds.name);
+        let accounts = state.creds.clone();
+        let app_config = state.app_config.clone();
+        let in_flight = self.in_flight.clone();
+        let bucket_regions = self.bucket_regions.clone();
+        let skip_verify_confirmed = state.skip_tls_verify_confirmed.clone();
+        let check = move |name: String| {
+            let account = accounts
+                .iter()
+                .find(|account| account.name == name)
+                .cloned();
+            let fetcher = account.map(|account| {
+                S3DataFetcher::new(
+                    account,
+                    &app_config,
+                    in_flight.clone(),
+                    bucket_regions.clone(),
+                    &skip_verify_confirmed,
+                )
+                .for_health_check()
+            });
+            async move {
+                match fetcher {
+                    Some(fetcher) => fetcher.check_access().await.map_err(|e| e.to_string()),
+                    None => Err(format!("The account '{}' isn't configured", name)),
+                }
+            }
+        };
+        let timeout = state.app_config.connect_timeout;
+        tasks.spawn(STARTUP_TASK, move |_| {
+            run_startup(steps, listed, timeout, check, results)
+        });
+    }
+
+    /// Checks the uploads saved by the earlier sessions with s3 in the background, the ones
+    /// it still holds are sent to be offered for resumption. The uploads of accounts missing
+    /// from the credentials and the ones which couldn't be checked stay saved
+    fn check_resumable_uploads(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &State,
+        uploads: Vec<ResumableUpload>,
+        checked_tx: UnboundedSender<Vec<ResumableUpload>>,
+    ) {
+        let accounts = state.creds.clone();
+        let app_config = state.app_config.clone();
+        let in_flight = self.in_flight.clone();
+        let bucket_regions = self.bucket_regions.clone();
+        let skip_verify_confirmed = state.skip_tls_verify_confirmed.clone();
+        let resumable_uploads = self.resumable_uploads.clone();
+        tasks.spawn_background(async move {
+            let mut resumable = Vec::new();
+            for upload in uploads {
+                let Some(creds) = accounts.iter().find(|c| c.name == upload.account) else {
+                    continue;
+                };
+                let fetcher = S3DataFetcher::new(
+                    creds.clone(),
+                    &app_config,
+                    in_flight.clone(),
+                    bucket_regions.clone(),
+                    &skip_verify_confirmed,
+                );
+                match fetcher.check_resumable_upload(&upload, creds).await {
+                    Ok(Some(checked)) => {
+                        resumable_uploads.update(&checked);
+                        resumable.push(checked);
+                    }
+                    Ok(None) => resumable_uploads.remove(&upload.upload_id),
+                    Err(e) => tracing::warn!(
+                        "Cannot check the interrupted upload of {}: {}",
+                        upload.path,
+                        e
+                    ),
+                }
+            }
+            if !resumable.is_empty() {
+                let _ = checked_tx.send(resumable);
+            }
+        });
+    }
+
+    /// Aborts the interrupted upload in the background
+    fn abort_resumable_upload(
+        &self,
+        tasks: &mut TaskRegistry,
+        state: &State,
+        upload: ResumableUpload,
+    ) {
+        let Some(creds) = state
+            .creds
+            .iter()
+            .find(|c| c.name == upload.account)
+            .cloned()
+        else {
+            return;
+        };
+        let fetcher = S3DataFetcher::new(
+            creds.clone(),
+            &state.app_config,
+            self.in_flight.clone(),
+            self.bucket_regions.clone(),
+            &state.skip_tls_verify_confirmed,
+        )
+        .sharing_resumable_uploads(&self.resumable_uploads);
+        tasks.spawn_background(async move {
+            fetcher.abort_resumable_upload(&upload, &creds).await;
+        });
+    }
+
+    pub async fn main_loop(
+        self,
+        mut terminator: Terminator,
+        mut action_rx: UnboundedReceiver<Action>,
+        mut interrupt_rx: broadcast::Receiver<Interrupted>,
+        creds: Vec<FileCredential>,
+        app_config: AppConfig,
+        key_bindings: KeyBindings,
+    ) -> eyre::Result<(Interrupted, Stats)> {
+        let local_data_fetcher = LocalDataFetcher::new();
+        let mut state = State::new(creds.clone());
+        state.color_support = app_config
+            .color_support
+            .unwrap_or_else(|| detect_color_support(|name| std::env::var(name).ok()));
+        state.write_manifest = app_config.write_manifest;
+        state.key_bindings = key_bindings;
+        let persistence = TransferPersistence::in_data_dir();
+        match persistence.load(&creds) {
+            Ok(restored) => state.restore_transfers(restored),
+            Err(e) => {
+                let message = format!("Cannot restore the pending transfers: {}", e);
+                state.log_activity(Severity::Error, message.clone());
+                state.notify(message);
+            }
+        }
+        let (mut autosave, autosave_writer) = Autosave::start(persistence.clone());
+        state.app_config = AppConfig {
+            transfer_concurrency: safe_concurrency(app_config.transfer_concurrency),
+            ..app_config
+        };
+        let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+        state.set_s3_loading(true);
+        let home_dir = dirs::home_dir()
+            .ok_or_else(|| eyre::eyre!("The home directory can't be determined"))?
+            .to_string_lossy()
+            .to_string();
+        state.set_current_local_path(home_dir.clone());
+
+        let (s3_tx, mut s3_rx) = mpsc::unbounded_channel::<S3Listing>();
+        let mut tasks = TaskRegistry::new();
+        let (s3_full_list_tx, mut s3_full_list_rx) =
+            mpsc::unbounded_channel::<Result<LimitedSelection, String>>();
+        let (s3_deleted_tx, mut s3_deleted_rx) =
+            mpsc::unbounded_channel::<(S3SelectedItem, Option<String>)>();
+        let (delete_progress_tx, mut delete_progress_rx) =
+            mpsc::unbounded_channel::<DeleteProgress>();
+        let (local_tx, mut local_rx) = mpsc::unbounded_channel::<(String, Vec<LocalDataItem>)>();
+        let (local_deleted_tx, mut local_deleted_rx) =
+            mpsc::unbounded_channel::<(String, Option<String>)>();
+        let (selected_s3_transfers_tx, mut selected_s3_transfers_rx) =
+            mpsc::unbounded_channel::<(S3SelectedItem, Result<u64, ErrorClass>)>();
+        let (selected_local_transfers_tx, mut selected_local_transfers_rx) =
+            mpsc::unbounded_channel::<(LocalSelectedItem, Result<u64, ErrorClass>)>();
+        let (upload_tx, mut upload_rx) = mpsc::unbounded_channel::<UploadProgressItem>();
+        let (download_tx, mut download_rx) = mpsc::unbounded_channel::<DownloadProgressItem>();
+        let (create_bucket_tx, mut create_bucket_rx) = mpsc::unbounded_channel::<Option<String>>();
+        let (create_folder_tx, mut create_folder_rx) = mpsc::unbounded_channel::<CreatedFolder>();
+        let (go_to_tx, mut go_to_rx) = mpsc::unbounded_channel::<CheckedS3Path>();
+        let (bulk_deleted_tx, mut bulk_deleted_rx) = mpsc::unbounded_channel::<BulkDeletion>();
+        let (local_walk_tx, mut local_walk_rx) = mpsc::unbounded_channel::<LocalSelectedItem>();
+        let (copy_tx, mut copy_rx) = mpsc::unbounded_channel::<(ObjectCopy, Result<(), String>)>();
+        let (rename_tx, mut rename_rx) = mpsc::unbounded_channel::<RenamedObject>();
+        let (local_rename_tx, mut local_rename_rx) = mpsc::unbounded_channel::<RenamedLocalItem>();
+        let walk_cancellation = CancellationToken::new();
+        let (metadata_tx, mut metadata_rx) =
+            mpsc::unbounded_channel::<Result<ObjectMetadata, String>>();
+        let (lock_tx, mut lock_rx) = mpsc::unbounded_channel::<(String, String, ObjectLockInfo)>();
+        let (comparison_tx, mut comparison_rx) =
+            mpsc::unbounded_channel::<Result<FileComparison, String>>();
+        let (preview_tx, mut preview_rx) =
+            mpsc::unbounded_channel::<Result<ObjectPreview, String>>();
+        let (object_text_tx, mut object_text_rx) = mpsc::unbounded_channel::<ObjectText>();
+        let (presigned_url_tx, mut presigned_url_rx) = mpsc::unbounded_channel::<ObjectText>();
+        let (dispatched_tx, mut dispatched_rx) =
+            mpsc::unbounded_channel::<(String, DispatchOrder)>();
+        let (stopped_tx, mut stopped_rx) = mpsc::unbounded_channel::<(String, TransferStop)>();
+        let (selection_export_tx, mut selection_export_rx) =
+            mpsc::unbounded_channel::<Result<String, String>>();
+        let (selection_import_tx, mut selection_import_rx) =
+            mpsc::unbounded_channel::<Result<SelectionImport, String>>();
+        let (account_check_tx, mut account_check_rx) =
+            mpsc::unbounded_channel::<(String, AccountCheck)>();
+        let (bucket_region_tx, mut bucket_region_rx) =
+            mpsc::unbounded_channel::<(String, String, String)>();
+        let (custom_command_tx, mut custom_command_rx) =
+            mpsc::unbounded_channel::<(String, Result<CommandOutput, String>)>();
+        let (resumable_tx, mut resumable_rx) = mpsc::unbounded_channel::<Vec<ResumableUpload>>();
+        let (bucket_listed_tx, bucket_listed_rx) = oneshot::channel();
+        // resolved with the first listing of the s3 panel, the startup work waits for it
+        let mut bucket_listed = Some(bucket_listed_tx);
+        // also keeps the durations of the transfers for the estimate of large batches, the
+        // selection is only counted for the endpoint when it's running
+        let metrics = Arc::new(Metrics::default());
+        let metrics_server = match state.app_config.metrics_listen {
+            Some(address) => match MetricsServer::start(
+                address,
+                state.app_config.metrics_allow_remote,
+                metrics.clone(),
+            ) {
+                Ok(server) => {
+                    tracing::info!("metrics available on http://{}/metrics", server.address);
+                    Some(server)
+                }
+                Err(e) => {
+                    tracing::error!("metrics endpoint not started: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let transfer_channels = TransferChannels {
+            s3_results: selected_s3_transfers_tx.clone(),
+            local_results: selected_local_transfers_tx.clone(),
+            download_progress: download_tx.clone(),
+            upload_progress: upload_tx.clone(),
+            dispatched: dispatched_tx,
+            metrics: metrics.clone(),
+            manifest: None,
+            concurrency: AdaptiveConcurrency::default(),
+            controls: TransferControls::default(),
+            stopped: stopped_tx,
+        };
+
+        state.s3_listing_request = self
+            .fetch_s3_data(
+                &mut tasks,
+                None,
+                None,
+                s3_data_fetcher.clone(),
+                s3_tx.clone(),
+            )
+            .await;
+        self.fetch_local_data(Some(home_dir), local_data_fetcher.clone(), local_tx.clone())
+            .await;
+
+        self.start_startup_work(&mut tasks, &state, bucket_listed_rx, account_check_tx);
+        match self.resumable_uploads.load() {
+            Ok(uploads) if uploads.is_empty() => {}
+            Ok(uploads) => self.check_resumable_uploads(&mut tasks, &state, uploads, resumable_tx),
+            Err(e) => {
+                let message = format!("Cannot read the interrupted uploads: {}", e);
+                state.log_activity(Severity::Error, message.clone());
+                state.notify(message);
+            }
+        }
+        // contents cached for the preview and the clipboard by earlier sessions
+        tokio::task::spawn_blocking(|| {
+            if let Err(e) = ObjectCache::in_temp_dir().remove_expired(CACHE_TTL) {
+                tracing::warn!("Cannot clean the object cache: {}", e);
+            }
+        });
+
+        // the initial state once
+        self.state_tx.send(state.clone())?;
+
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        let mut progress_ticker = tokio::time::interval(PROGRESS_REFRESH);
+        progress_ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // progress applied to the state since the last state sent
+        let mut progress_changed = false;
+
+        let result = loop {
+            tokio::select! {
+                        Some(action) = action_rx.recv() => match action {
+                            Action::Exit => {
+                                walk_cancellation.cancel();
+                                tasks.cancel_all();
+                                let _ = terminator.terminate(Interrupted::UserInt);
+                                break Interrupted::UserInt;
+                            },
+                            Action::Navigate { page} => {
+                                state.set_active_page(page);
+                                let _ = self.state_tx.send(state.clone());
+                            }
+                            Action::FetchLocalData { path} =>
+                                self.fetch_local_data(Some(path), local_data_fetcher.clone(), local_tx.clone()).await,
+                            Action::NavigateS3 { step, row } => {
+                                self.navigate_s3(&mut tasks, &mut state, step, row, s3_tx.clone()).await;
+                            }
+                            Action::GoToS3Path { location, row } => {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                self.check_s3_path(&mut tasks, state.current_creds.name.clone(), location, row, s3_data_fetcher, go_to_tx.clone()).await;
+                            }
+                            Action::RefreshBuckets => {
+                                state.invalidate_bucket_list();
+                                state.set_s3_loading(true);
+                                let _ = self.state_tx.send(state.clone());
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                state.s3_listing_request = self.fetch_s3_data(&mut tasks, None, None, s3_data_fetcher, s3_tx.clone()).await;
+                            }
+                            Action::RefreshS3Location => {
+                                state.set_s3_loading(true);
+                                let _ = self.state_tx.send(state.clone());
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                state.s3_listing_request = self.fetch_s3_data(&mut tasks, state.current_s3_bucket.clone(), state.current_s3_path.clone(), s3_data_fetcher, s3_tx.clone()).await;
+                            }
+                            Action::ListS3DataRecursiveForItem { item } => {
+                                state.set_s3_list_recursive_loading(true);
                                 let _ = self.state_tx.send(state.clone());
-                                let s3_data_fetcher = Self::get_current_s3_fetcher(&state);
-                                self.list_s3_data_recursive(item, s3_data_fetcher, s3_full_list_tx.clone()).await
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                let limit = SelectionLimit::new(&state.app_config);
+                                self.list_s3_data_recursive(&mut tasks, item, s3_data_fetcher, limit, s3_full_list_tx.clone()).await
                             }
                             Action::MoveBackLocal => self.move_back_local_data(state.current_local_path.clone(), local_data_fetcher.clone(), local_tx.clone()).await,
                             Action::SelectS3Item { item} => {
@@ -453,8 +1609,16 @@ impl StateStore {
                                 let _ = self.state_tx.send(state.clone());
                             },
                             Action::SelectLocalItem { item} => {
-                                state.add_local_selected_item(item);
-                                let _ = self.state_tx.send(state.clone());
+                                if item.is_directory && item.children.is_none() {
+                                    let options = WalkOptions {
+                                        cancellation: walk_cancellation.clone(),
+                                        ..state.walk_options()
+                                    };
+                                    self.list_local_directory(item, options, local_walk_tx.clone()).await;
+                                } else {
+                                    state.add_local_selected_item(item);
+                                    let _ = self.state_tx.send(state.clone());
+                                }
                             },
                             Action::UnselectLocalItem { item } => {
                                 state.remove_local_selected_item(item);
@@ -462,89 +1626,642 @@ impl StateStore {
                             },
                             Action::RunTransfers => {
                                 state.remove_already_transferred_items();
-                                let st = state.clone();
-                                let s3_data_fetcher = Self::get_current_s3_fetcher(&st);
-                                self.download_data(&s3_data_fetcher, st.s3_selected_items, selected_s3_transfers_tx.clone(), download_tx.clone()).await;
-                                self.upload_data(&s3_data_fetcher, st.local_selected_items, selected_local_transfers_tx.clone(), upload_tx.clone()).await;
+                                state.start_dispatch_batch();
+                                let jobs = TransferJob::queue(state.s3_selected_items.clone(), state.local_selected_items.clone());
+                                let jobs = state.without_paused(jobs);
+                                self.start_transfers(&mut tasks, &mut state, jobs, transfer_channels.clone());
+                            },
+                            Action::RunSelectedTransfers { s3_items, local_items } => {
+                                state.remove_already_transferred_items();
+                                state.start_dispatch_batch();
+                                let (s3_items, local_items) = state.pending_transfers_subset(&s3_items, &local_items);
+                                let jobs = TransferJob::queue(s3_items, local_items);
+                                state.unpause(&jobs);
+                                self.start_transfers(&mut tasks, &mut state, jobs, transfer_channels.clone());
+                            },
+                            Action::StopTransfers { keys, stop } => {
+                                for key in state.stop_transfers(&keys, stop) {
+                                    transfer_channels.controls.stop(&key, stop);
+                                }
+                                self.state_tx.send(state.clone())?;
+                            },
+                            Action::ResumeTransfers { keys } => {
+                                let jobs = state.resume_transfers(&keys);
+                                if jobs.is_empty() {
+                                    self.state_tx.send(state.clone())?;
+                                } else {
+                                    self.start_transfers(&mut tasks, &mut state, jobs, transfer_channels.clone());
+                                }
+                            },
+                            Action::ResolveTransferConflicts { resolution } => {
+                                // the blocked run is dropped when it's cancelled
+                                match (state.blocked_transfers.take(), resolution) {
+                                    (Some(blocked), Some(resolution)) => {
+                                        let jobs = resolve_conflicts(blocked.jobs, resolution, state.app_config.case_insensitive_paths);
+                                        self.start_unless_overlapping(&mut tasks, &mut state, jobs, transfer_channels.clone());
+                                    }
+                                    _ => self.state_tx.send(state.clone())?,
+                                }
+                            },
+                            Action::ResolveTransferOverlaps { resolution } => {
+                                if let (Some(overlapping), Some(resolution)) = (state.overlapping_transfers.take(), resolution) {
+                                    let (jobs, deferred) = resolve_overlaps(overlapping.jobs, resolution, state.app_config.case_insensitive_paths);
+                                    self.start_run(&mut tasks, &mut state, vec![jobs, deferred], transfer_channels.clone());
+                                }
+                                self.state_tx.send(state.clone())?;
                             },
+                            Action::ConfirmLargeBatch { confirmed } => {
+                                match state.large_batch.take() {
+                                    Some(batch) if confirmed => self.start_unless_conflicting(&mut tasks, &mut state, batch.jobs, transfer_channels.clone()),
+                                    _ => self.state_tx.send(state.clone())?,
+                                }
+                            },
+                            Action::CloseFailureSummary => {
+                                state.failure_summary = None;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::FilterTransfersByError { error } => {
+                                state.failure_summary = None;
+                                state.transfer_error_filter = error;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::SortTransfers { sort } => {
+                                state.transfer_sort = sort;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::ToggleManifest => {
+                                state.write_manifest = !state.write_manifest;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::ToggleActivityLog => {
+                                state.show_activity_log = !state.show_activity_log;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::ToggleHiddenFiles => {
+                                state.show_hidden_files = !state.show_hidden_files;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::ExportSelection { path } => {
+                                let entries = selection_entries(&state.s3_selected_items, &state.local_selected_items);
+                                self.export_selection(&mut tasks, path, entries, selection_export_tx.clone());
+                            }
+                            Action::ImportSelection { path } => {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                self.import_selection(&mut tasks, path, s3_data_fetcher, selection_import_tx.clone());
+                            }
                             Action::SelectCurrentS3Creds { item} => {
+                                tasks.abort(STARTUP_TASK);
                                 state.set_current_s3_creds(item);
+                                // each account resumes at its own location
+                                let location = state.s3_location();
+                                if location.current_bucket.is_none() && state.show_cached_buckets(Instant::now()) {
+                                    tasks.abort(S3_LISTING_TASK);
+                                    let _ = self.state_tx.send(state.clone());
+                                    self.resolve_bucket_regions(&mut tasks, &state, bucket_region_tx.clone());
+                                } else {
+                                    let _ = self.state_tx.send(state.clone());
+                                    let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                    state.s3_listing_request = self.fetch_s3_data(&mut tasks, location.current_bucket, location.current_prefix, s3_data_fetcher, s3_tx.clone()).await;
+                                }
+                            },
+                            Action::SaveCredential { original, entry } => {
+                                match save_credential(&creds_dir(), original.as_deref(), &entry) {
+                                    Ok(cred) => {
+                                        if state.store_credential(original.as_deref(), cred) {
+                                            // the current account lists its location again with the new keys
+                                            tasks.abort(STARTUP_TASK);
+                                            let location = state.s3_location();
+                                            let _ = self.state_tx.send(state.clone());
+                                            let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                            state.s3_listing_request = self.fetch_s3_data(&mut tasks, location.current_bucket, location.current_prefix, s3_data_fetcher, s3_tx.clone()).await;
+                                        }
+                                    }
+                                    Err(e) => {
+                                        let message = format!("Cannot save account {}: {}", entry.name, e);
+                                        state.log_activity(Severity::Error, message.clone());
+                                        state.notify(message);
+                                    }
+                                }
                                 let _ = self.state_tx.send(state.clone());
-                                let s3_data_fetcher = Self::get_current_s3_fetcher(&state);
-                                self.fetch_s3_data(None, None, s3_data_fetcher, s3_tx.clone()).await;
                             },
-                            Action::DeleteS3Item { item} => {
-                                let s3_data_fetcher = Self::get_current_s3_fetcher(&state);
-                                tracing::info!("deleting s3 item...{:?}", item.clone());
-                                self.delete_s3_data(item.clone(), s3_data_fetcher.clone(), s3_deleted_tx.clone()).await;
-                                if item.is_bucket {
-                                    self.fetch_s3_data(None, None, s3_data_fetcher, s3_tx.clone()).await;
+                            Action::ConfirmSkipTlsVerify { account } => {
+                                state.confirm_skip_tls_verify(&account);
+                                let _ = self.state_tx.send(state.clone());
+                            },
+                            Action::DeleteCredential { name } => {
+                                if name == state.current_creds.name {
+                                    state.notify(format!("Account {} is in use, switch to another account to delete it", name));
                                 } else {
-                                    self.fetch_s3_data(item.bucket, None, s3_data_fetcher, s3_tx.clone()).await;
+                                    match delete_credential(&creds_dir(), &name) {
+                                        Ok(()) => state.remove_credential(&name),
+                                        Err(e) => {
+                                            let message = format!("Cannot delete account {}: {}", name, e);
+                                            state.log_activity(Severity::Error, message.clone());
+                                            state.notify(message);
+                                        }
+                                    }
                                 }
+                                let _ = self.state_tx.send(state.clone());
+                            },
+                            Action::DeleteS3Item { item} => {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                tracing::info!("deleting s3 item...{:?}", item.clone());
+                                state.log_activity(Severity::Info, format!("Deleting s3://{}/{}", item.bucket.clone().unwrap_or_default(), item.path.clone().unwrap_or(item.name.clone())));
+                                self.delete_s3_data(&mut tasks, item, s3_data_fetcher, s3_deleted_tx.clone(), delete_progress_tx.clone()).await;
                             },
                             Action::DeleteLocalItem {item} => {
-                                state.remove_local_selected_item(item.clone());
+                                state.log_activity(Severity::Info, format!("Deleting {}", item.path));
                                 let _ = self.state_tx.send(state.clone());
                                 self.delete_local_data(item.clone(), local_data_fetcher.clone(), local_deleted_tx.clone()).await;
                                 self.fetch_local_data(Some(item.path.clone()), local_data_fetcher.clone(), local_tx.clone()).await;
                             },
+                            Action::DeleteSelectedItems => {
+                                let mut s3_deletions: Vec<(S3DataFetcher, S3Deletion)> = Vec::new();
+                                for deletion in group_s3_deletions(&state.s3_selected_items) {
+                                    match state.creds.iter().find(|c| c.name == deletion.account).cloned() {
+                                        Some(creds) => s3_deletions.push((S3DataFetcher::new(creds, &state.app_config, self.in_flight.clone(), self.bucket_regions.clone(), &state.skip_tls_verify_confirmed), deletion)),
+                                        None => state.log_activity(Severity::Error, format!("{} objects of {} not deleted, credential '{}' is no longer configured", deletion.keys.len(), deletion.bucket, deletion.account)),
+                                    }
+                                }
+                                let objects: usize = s3_deletions.iter().map(|(_, deletion)| deletion.keys.len()).sum();
+                                state.log_activity(Severity::Info, format!("Deleting {} selected objects and {} local items", objects, state.local_selected_items.len()));
+                                self.delete_selected_items(&mut tasks, s3_deletions, state.local_selected_items.clone(), local_data_fetcher.clone(), bulk_deleted_tx.clone()).await;
+                            },
                             Action::CreateBucket {name} => {
-                                let s3_data_fetcher = Self::get_current_s3_fetcher(&state);
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
                                 tracing::info!("creating s3 bucket...{:?}", name.clone());
-                                self.create_bucket(name.clone(), s3_data_fetcher.clone(), create_bucket_tx.clone()).await;
-                                self.fetch_s3_data(None, None, s3_data_fetcher, s3_tx.clone()).await;
+                                state.log_activity(Severity::Info, format!("Creating bucket {}", name));
+                                self.create_bucket(&mut tasks, name.clone(), s3_data_fetcher.clone(), create_bucket_tx.clone()).await;
+                                state.invalidate_bucket_list();
+                                state.s3_listing_request = self.fetch_s3_data(&mut tasks, None, None, s3_data_fetcher, s3_tx.clone()).await;
+                            },
+                            Action::CreateFolder { bucket, prefix, name } => {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                tracing::info!("creating s3 folder...{:?} in {:?}/{:?}", name, bucket, prefix);
+                                self.create_folder(&mut tasks, bucket, prefix, name, s3_data_fetcher, create_folder_tx.clone()).await;
                             },
+                            Action::CopyS3Item { item, destination_bucket, destination_prefix, delete_source } => {
+                                if item.account != state.current_creds.name {
+                                    state.notify("Objects can only be copied within the account they were yanked in".into());
+                                } else {
+                                    match ObjectCopy::new(&item, destination_bucket, destination_prefix, delete_source) {
+                                        Some(copy) if copy.is_in_place() => {
+                                            state.notify(format!("{} is already in this location", copy.source_url()));
+                                        }
+                                        Some(copy) => {
+                                            let verb = if copy.delete_source { "Moving" } else { "Copying" };
+                                            state.log_activity(Severity::Info, copy.describe(verb));
+                                            let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                            self.copy_s3_item(&mut tasks, copy, s3_data_fetcher, copy_tx.clone()).await;
+                                        }
+                                        None => state.notify("Only objects can be copied, not directories or buckets".into()),
+                                    }
+                                }
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::RenameS3Item { item, new_key, overwrite } => {
+                                if item.account != state.current_creds.name {
+                                    state.notify("Objects can only be renamed within the account they were listed in".into());
+                                } else {
+                                    match ObjectCopy::renamed(&item, new_key) {
+                                        Some(rename) if rename.is_in_place() => {
+                                            state.notify(format!("{} already has this name", rename.source_url()));
+                                        }
+                                        Some(rename) => {
+                                            state.log_activity(Severity::Info, rename.describe("Moving"));
+                                            let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                            self.rename_s3_item(&mut tasks, item, rename, overwrite, s3_data_fetcher, rename_tx.clone()).await;
+                                        }
+                                        None => state.notify("Only objects can be renamed, not directories or buckets".into()),
+                                    }
+                                }
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::RenameLocalItem { item, new_name, overwrite } => {
+                                state.log_activity(Severity::Info, format!("Renaming {} to {}", item.path, new_name));
+                                self.rename_local_item(item, new_name, overwrite, local_data_fetcher.clone(), local_rename_tx.clone()).await;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::FetchObjectMetadata { bucket, key } => {
+                                state.start_object_metadata_request();
+                                self.state_tx.send(state.clone())?;
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                self.fetch_object_metadata(&mut tasks, bucket, key, s3_data_fetcher, metadata_tx.clone()).await;
+                            }
+                            Action::SaveObjectMetadata { metadata } => {
+                                state.start_object_metadata_request();
+                                self.state_tx.send(state.clone())?;
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                self.save_object_metadata(&mut tasks, metadata, s3_data_fetcher, metadata_tx.clone()).await;
+                            }
+                            Action::ChangeStorageClass { bucket, key, storage_class } => {
+                                state.log_activity(Severity::Info, format!("Changing the storage class of s3://{}/{} to {}", bucket, key, storage_class));
+                                state.start_object_metadata_request();
+                                self.state_tx.send(state.clone())?;
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                self.change_storage_class(&mut tasks, bucket, key, storage_class, s3_data_fetcher, metadata_tx.clone()).await;
+                            }
+                            Action::CloseObjectMetadata => {
+                                state.clear_object_metadata();
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::FetchObjectLockInfo { bucket, key } => {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                self.fetch_object_lock_info(&mut tasks, bucket, key, s3_data_fetcher, lock_tx.clone()).await;
+                            }
+                            Action::CompareObjectWithLocal { bucket, key, local_path, full_hash } => {
+                                state.start_object_comparison();
+                                self.state_tx.send(state.clone())?;
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                self.compare_object_with_local(&mut tasks, bucket, key, local_path, full_hash, s3_data_fetcher, comparison_tx.clone()).await;
+                            }
+                            Action::CloseObjectComparison => {
+                                state.clear_object_comparison();
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::PreviewS3Item { bucket, key } => {
+                                state.start_object_preview();
+                                self.state_tx.send(state.clone())?;
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                let max_bytes = state.app_config.preview_max_kb * 1024;
+                                self.preview_object(&mut tasks, bucket, key, max_bytes, s3_data_fetcher, preview_tx.clone()).await;
+                            }
+                            Action::ClosePreview => {
+                                state.clear_object_preview();
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::CopyS3ObjectText { bucket, key } => {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                let max_bytes = state.app_config.clipboard_max_kb * 1024;
+                                self.read_object_text(&mut tasks, bucket, key, max_bytes, s3_data_fetcher, object_text_tx.clone()).await;
+                            }
+                            Action::PresignObjectUrl { bucket, key } => {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                let expires_in = state.app_config.presigned_url_expiry;
+                                self.presign_object_url(&mut tasks, bucket, key, expires_in, s3_data_fetcher, presigned_url_tx.clone()).await;
+                            }
+                            Action::CopiedToClipboard { text } => {
+                                state.copied_object_text = None;
+                                state.notify(format!("Copied: {}", text));
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::JumpTo { target } => {
+                                let destination = target.clone();
+                                if !state.begin_jump(target) {
+                                    self.state_tx.send(state.clone())?;
+                                } else {
+                                    match destination {
+                                        JumpTarget::Local { directory, .. } => {
+                                            self.fetch_local_data(Some(directory), local_data_fetcher.clone(), local_tx.clone()).await;
+                                        }
+                                        JumpTarget::S3 { bucket, prefix, .. } => {
+                                            state.set_s3_loading(true);
+                                            let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                            state.s3_listing_request = self.fetch_s3_data(&mut tasks, Some(bucket), prefix, s3_data_fetcher, s3_tx.clone()).await;
+                                        }
+                                    }
+                                }
+                            }
+                            Action::CancelS3Listing => {
+                                tasks.abort(S3_LISTING_TASK);
+                                if state.cancel_s3_listing() {
+                                    self.state_tx.send(state.clone())?;
+                                }
+                            }
+                            Action::StopNetworkActivity => {
+                                let cancelled = tasks.cancel_all();
+                                tracing::info!("network activity stopped, {} tasks cancelled", cancelled);
+                                transfer_channels.controls.clear();
+                                state.stop_network_activity(cancelled);
+                                self.state_tx.send(state.clone())?;
+                            }
                             Action::ClearDeletionErrors => {
                                 state.s3_delete_state = None;
                                 state.local_delete_state = None;
                                 state.create_bucket_state = None;
+                                state.create_folder_state = None;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::ClearCredentialsError => {
+                                state.credentials_error = None;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::RunCustomCommand { command, row } => {
+                                match command.command_line(&row) {
+                                    Ok(command_line) => {
+                                        state.log_activity(Severity::Info, format!("Running '{}': {}", command.name, command_line));
+                                        let custom_command_tx = custom_command_tx.clone();
+                                        tasks.spawn_background(async move {
+                                            let result = run_command(&command_line).await;
+                                            let _ = custom_command_tx.send((command.name, result));
+                                        });
+                                    }
+                                    Err(e) => {
+                                        let message = format!("Cannot run '{}': {}", command.name, e);
+                                        state.log_activity(Severity::Error, message.clone());
+                                        state.notify(message);
+                                    }
+                                }
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::ResumeRestoredTransfers => {
+                                state.restored_transfers = None;
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::RemapRestoredAccount { account, replacement } => {
+                                state.remap_restored_account(&account, replacement.as_deref());
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::ResumeUpload(upload) => {
+                                state.remove_already_transferred_items();
+                                let item = state.resume_upload(&upload);
+                                state.start_dispatch_batch();
+                                let (_, local_items) = state.pending_transfers_subset(&[], &[item]);
+                                let jobs = TransferJob::queue(Vec::new(), local_items);
+                                state.unpause(&jobs);
+                                self.start_transfers(&mut tasks, &mut state, jobs, transfer_channels.clone());
+                            }
+                            Action::AbortUpload(upload) => {
+                                state.abort_resumable_upload(&upload);
+                                self.abort_resumable_upload(&mut tasks, &state, upload);
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::PostponeResumableUploads => {
+                                state.resumable_uploads.clear();
+                                state.log_activity(Severity::Info, "Kept the interrupted uploads for the next start".into());
+                                self.state_tx.send(state.clone())?;
+                            }
+                            Action::DiscardRestoredTransfers => {
+                                if state.discard_restored_transfers() {
+                                    if let Err(e) = persistence.discard() {
+                                        let message = format!("Cannot remove the saved transfers: {}", e);
+                                        state.log_activity(Severity::Error, message.clone());
+                                        state.notify(message);
+                                    }
+                                }
                                 self.state_tx.send(state.clone())?;
                             }
                         },
-                        Some(item) = selected_s3_transfers_rx.recv() => {
+                        Some((item, outcome)) = selected_s3_transfers_rx.recv() => {
+                            state.stats.record_download(&outcome);
+                            let refresh = state.finish_download(&item);
+                            let missing_bucket = item.bucket.clone().filter(|_| outcome == Err(ErrorClass::MissingBucket));
                             state.update_selected_s3_transfers(item);
                             self.state_tx.send(state.clone())?;
+                            if let Some(bucket) = missing_bucket {
+                                self.leave_missing_bucket(&mut tasks, &mut state, &bucket, &transfer_channels.controls, &s3_tx).await;
+                            }
+                            self.refresh_listings(refresh, &mut state, &mut tasks, &local_data_fetcher, &s3_tx, &local_tx).await;
                         },
-                        Some(item) = selected_local_transfers_rx.recv() => {
+                        Some((item, outcome)) = selected_local_transfers_rx.recv() => {
+                            state.stats.record_upload(&outcome);
+                            let refresh = state.finish_upload(&item);
+                            let missing_bucket = (outcome == Err(ErrorClass::MissingBucket)).then(|| item.destination_bucket.clone());
                             state.update_selected_local_transfers(item);
                             self.state_tx.send(state.clone())?;
+                            if let Some(bucket) = missing_bucket {
+                                self.leave_missing_bucket(&mut tasks, &mut state, &bucket, &transfer_channels.controls, &s3_tx).await;
+                            }
+                            self.refresh_listings(refresh, &mut state, &mut tasks, &local_data_fetcher, &s3_tx, &local_tx).await;
                         },
-                        Some((bucket, prefix, data)) = s3_rx.recv() => {
-                            state.update_buckets(bucket, prefix, data);
+                        Some((account, bucket, region)) = bucket_region_rx.recv() => {
+                            state.set_bucket_region(&account, &bucket, region);
                             self.state_tx.send(state.clone())?;
                         },
-                        Some((_bucket, _prefix, data)) = s3_full_list_rx.recv() => {
+                        Some((request_id, bucket, prefix, part)) = s3_rx.recv() => {
+                            if !matches!(part, S3ListingPart::Batch(_)) {
+                                if let Some(listed) = bucket_listed.take() {
+                                    let _ = listed.send(());
+                                }
+                            }
+                            match part {
+                                S3ListingPart::Batch(batch) => {
+                                    if state.receive_s3_batch(request_id, bucket, prefix, batch) {
+                                        self.state_tx.send(state.clone())?;
+                                    }
+                                }
+                                S3ListingPart::Done => {
+                                    let data = state.take_streamed_s3_data(request_id);
+                                    if let Some(root) = state.missing_landing_fallback(request_id, &bucket, &prefix, &data) {
+                                        self.state_tx.send(state.clone())?;
+                                        let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                        state.s3_listing_request = self.fetch_s3_data(&mut tasks, root.current_bucket, root.current_prefix, s3_data_fetcher, s3_tx.clone()).await;
+                                    } else if state.receive_s3_listing(request_id, bucket, prefix, data) {
+                                        self.state_tx.send(state.clone())?;
+                                        self.resolve_bucket_regions(&mut tasks, &state, bucket_region_tx.clone());
+                                    }
+                                }
+                                S3ListingPart::Failed(e) => {
+                                    if state.fail_s3_listing(request_id, bucket, prefix, e) {
+                                        self.state_tx.send(state.clone())?;
+                                    }
+                                }
+                                S3ListingPart::CredentialsFailed(e) => {
+                                    if state.fail_credentials(request_id, e) {
+                                        self.state_tx.send(state.clone())?;
+                                    }
+                                }
+                                S3ListingPart::BucketMissing(e) => {
+                                    let left = match (&bucket, request_id == state.s3_listing_request) {
+                                        (Some(missing), true) => self.leave_missing_bucket(&mut tasks, &mut state, missing, &transfer_channels.controls, &s3_tx).await,
+                                        _ => false,
+                                    };
+                                    if !left && state.fail_s3_listing(request_id, bucket, prefix, e) {
+                                        self.state_tx.send(state.clone())?;
+                                    }
+                                }
+                            }
+                        },
+                        Some(exported) = selection_export_rx.recv() => {
+                            let (severity, message) = match exported {
+                                Ok(message) => (Severity::Info, message),
+                                Err(message) => (Severity::Error, message),
+                            };
+                            state.log_activity(severity, message.clone());
+                            state.notify(message);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some(import) = selection_import_rx.recv() => {
+                            match import {
+                                Ok(import) => {
+                                    state.merge_selection(import);
+                                }
+                                Err(message) => {
+                                    state.log_activity(Severity::Error, message.clone());
+                                    state.notify(message);
+                                }
+                            }
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some(uploads) = resumable_rx.recv() => {
+                            state.offer_resumable_uploads(uploads);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((account, check)) = account_check_rx.recv() => {
+                            state.finish_account_check(account, check);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some(data) = s3_full_list_rx.recv() => {
                             state.update_s3_recursive_list(data);
                             self.state_tx.send(state.clone())?;
                         },
                         Some((path, files)) = local_rx.recv() => {
-                            state.update_files(path, files);
+                            state.receive_local_listing(path, files);
                             self.state_tx.send(state.clone())?;
                         },
                         Some(item) = upload_rx.recv() => {
                             if state.active_page == ActivePage::Transfers {
                                 state.update_progress_on_selected_local_item(item);
-                                self.state_tx.send(state.clone())?;
+                                progress_changed = true;
                             }
                         },
                         Some(item) = download_rx.recv() => {
                             if state.active_page == ActivePage::Transfers {
                                 state.update_progress_on_selected_s3_item(item);
-                                self.state_tx.send(state.clone())?;
+                                progress_changed = true;
                             }
                         },
-                        Some(error_str) = local_deleted_rx.recv() => {
+                        Some((path, error_str)) = local_deleted_rx.recv() => {
+                            // the queued transfers of a deleted file would fail with NotFound
+                            if error_str.is_none() {
+                                state.forget_deleted_local_source(&path);
+                            }
                             state.set_local_delete_error(error_str);
                             self.state_tx.send(state.clone())?;
                         },
-                        Some(error_str) = s3_deleted_rx.recv() => {
+                        Some((item, error_str)) = s3_deleted_rx.recv() => {
+                            if error_str.is_none() {
+                                state.forget_deleted_s3_source(&item);
+                            }
                             state.set_s3_delete_error(error_str);
+                            // the panel is listed once the deletion finished, a directory or
+                            // a bucket can take a while to empty
+                            if state.current_s3_bucket.is_none() {
+                                state.invalidate_bucket_list();
+                            }
+                            let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                            state.s3_listing_request = self.fetch_s3_data(&mut tasks, state.current_s3_bucket.clone(), state.current_s3_path.clone(), s3_data_fetcher, s3_tx.clone()).await;
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some(progress) = delete_progress_rx.recv() => {
+                            state.set_s3_delete_progress(progress);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some(deletion) = bulk_deleted_rx.recv() => {
+                            state.finish_bulk_deletion(deletion);
                             self.state_tx.send(state.clone())?;
                         },
                         Some(error_str) = create_bucket_rx.recv() => {
                             state.set_create_bucket_error(error_str);
                             self.state_tx.send(state.clone())?;
+                        },
+                        Some((bucket, prefix, name, error)) = create_folder_rx.recv() => {
+                            if state.finish_folder_creation(&bucket, &prefix, &name, error) {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                state.s3_listing_request = self.fetch_s3_data(&mut tasks, Some(bucket), prefix, s3_data_fetcher, s3_tx.clone()).await;
+                            }
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((account, location, row, result)) = go_to_rx.recv() => {
+                            if let Some(location) = state.finish_go_to_s3_path(&account, location, row, result) {
+                                state.set_s3_loading(true);
+                                let _ = self.state_tx.send(state.clone());
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                state.s3_listing_request = self.fetch_s3_data(&mut tasks, location.current_bucket, location.current_prefix, s3_data_fetcher, s3_tx.clone()).await;
+                            }
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((copy, result)) = copy_rx.recv() => {
+                            if state.finish_s3_copy(&copy, result) {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                state.s3_listing_request = self.fetch_s3_data(&mut tasks, state.current_s3_bucket.clone(), state.current_s3_path.clone(), s3_data_fetcher, s3_tx.clone()).await;
+                            }
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((item, rename, result)) = rename_rx.recv() => {
+                            if state.finish_s3_rename(item, &rename, result) {
+                                let s3_data_fetcher = self.get_current_s3_fetcher(&state);
+                                state.s3_listing_request = self.fetch_s3_data(&mut tasks, state.current_s3_bucket.clone(), state.current_s3_path.clone(), s3_data_fetcher, s3_tx.clone()).await;
+                            }
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((item, new_name, result)) = local_rename_rx.recv() => {
+                            if state.finish_local_rename(item, new_name, result) {
+                                self.fetch_local_data(Some(state.current_local_path.clone()), local_data_fetcher.clone(), local_tx.clone()).await;
+                            }
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some(lock_info) = lock_rx.recv() => {
+                            state.delete_lock_info = Some(lock_info);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some(result) = metadata_rx.recv() => {
+                            // the popup could be closed while the request was running
+                            if state.object_metadata_loading {
+                                state.set_object_metadata(result);
+                                self.state_tx.send(state.clone())?;
+                            }
+                        },
+                        Some(result) = comparison_rx.recv() => {
+                            // the popup could be closed while the comparison was running
+                            if state.object_comparison_loading {
+                                state.set_object_comparison(result);
+                                self.state_tx.send(state.clone())?;
+                            }
+                        },
+                        Some(result) = preview_rx.recv() => {
+                            // the popup could be closed while the object was read
+                            if state.object_preview_loading {
+                                state.set_object_preview(result);
+                                self.state_tx.send(state.clone())?;
+                            }
+                        },
+                        Some((bucket, key, result)) = object_text_rx.recv() => {
+                            state.finish_object_text_read(&bucket, &key, result);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((bucket, key, result)) = presigned_url_rx.recv() => {
+                            state.finish_presigned_url(&bucket, &key, result);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((name, result)) = custom_command_rx.recv() => {
+                            state.finish_custom_command(&name, result);
+                            self.state_tx.send(state.clone())?;
+                        },
+                        Some((key, stop)) = stopped_rx.recv() => {
+                            tracing::info!("transfer {} stopped: {:?}", key, stop);
+                            let refresh = state.finish_stopped_transfer(&key);
+                            self.state_tx.send(state.clone())?;
+                            self.refresh_listings(refresh, &mut state, &mut tasks, &local_data_fetcher, &s3_tx, &local_tx).await;
+                        },
+                        Some((key, order)) = dispatched_rx.recv() => {
+                            state.record_dispatch(key, order);
+                            if state.active_page == ActivePage::Transfers {
+                                self.state_tx.send(state.clone())?;
+                            }
+                        },
+                        Some(item) = local_walk_rx.recv() => {
+                            if !state.local_selected_items.contains(&item) {
+                                state.add_local_selected_item(item);
+                                self.state_tx.send(state.clone())?;
+                            }
+                        },
+                        _ = progress_ticker.tick(), if progress_changed => {
+                            progress_changed = false;
+                            self.state_tx.send(state.clone())?;
+                        }
+                        _ = ticker.tick() => {
+                            // counting the selection walks every transfer, the endpoint gets it once a second
+                            if metrics_server.is_some() {
+                                metrics.update(&state);
+                            }
+                            let in_flight = self.in_flight.count();
+                            let account_loads = transfer_channels.concurrency.loads();
+                            if in_flight != state.in_flight_requests || account_loads != state.account_loads {
+                                state.in_flight_requests = in_flight;
+                                state.account_loads = account_loads;
+                                self.state_tx.send(state.clone())?;
+                            }
                         }
 
                 // Catch and handle interrupt signal to gracefully shutdown
@@ -552,8 +2269,51 @@ impl StateStore {
                     break interrupted;
                 }
             }
+            autosave.update(&state);
         };
+        autosave.finish(&state, autosave_writer).await;
+        if let Some(server) = metrics_server {
+            server.stop().await;
+        }
+
+        Ok((result, state.stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(result)
+    #[tokio::test]
+    async fn held_enter_spawns_one_listing() {
+        let (store, _state_rx) = StateStore::new();
+        let (s3_tx, _s3_rx) = mpsc::unbounded_channel::<S3Listing>();
+        let mut tasks = TaskRegistry::new();
+        let mut state = State::default();
+        let into = NavigationStep::Into(NavigationState::new(
+            Some("bucket".into()),
+            Some("a/".into()),
+        ));
+
+        for _ in 0..3 {
+            store
+                .navigate_s3(&mut tasks, &mut state, into.clone(), Some(1), s3_tx.clone())
+                .await;
+        }
+        assert_eq!(tasks.latest_id(S3_LISTING_TASK), Some(1));
+        assert_eq!(state.s3_listing_request, 1);
+
+        // once loaded the location can be listed again, e.g. to refresh it
+        state.set_s3_loading(false);
+        store
+            .navigate_s3(&mut tasks, &mut state, into, None, s3_tx)
+            .await;
+        assert_eq!(tasks.latest_id(S3_LISTING_TASK), Some(2));
+        assert_eq!(
+            state.navigate_s3(NavigationStep::Back, None),
+            Some(NavigationState::new(None, None))
+        );
+        assert_eq!(state.navigate_s3(NavigationStep::Back, None), None);
+        tasks.cancel_all();
     }
 }
@@ -0,0 +1,172 @@
+//! This module provides the suspension of the application with Ctrl+Z or SIGTSTP.
+//! The terminal is given back to the shell while the transfers keep running in the
+//! background (e.g. after `bg`), the ui takes it again once the process is in the foreground
+use color_eyre::eyre;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+/// Switches the terminal between the modes used by the ui and the ones the shell expects
+pub trait TerminalModes {
+    /// Raw mode and alternate screen, the whole screen is drawn again afterwards
+    fn enter(&mut self) -> eyre::Result<()>;
+    fn leave(&mut self) -> eyre::Result<()>;
+}
+
+/// Tracks whether the terminal was given back, so repeated signals switch the modes only once
+#[derive(Debug, Default)]
+pub struct Suspension {
+    suspended: bool,
+}
+
+impl Suspension {
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Gives the terminal back to the shell, returns false when it already was
+    pub fn suspend(&mut self, terminal: &mut impl TerminalModes) -> eyre::Result<bool> {
+        if self.suspended {
+            return Ok(false);
+        }
+        terminal.leave()?;
+        self.suspended = true;
+        Ok(true)
+    }
+
+    /// Takes the terminal again, returns false when it wasn't given back
+    pub fn resume(&mut self, terminal: &mut impl TerminalModes) -> eyre::Result<bool> {
+        if !self.suspended {
+            return Ok(false);
+        }
+        terminal.enter()?;
+        self.suspended = false;
+        Ok(true)
+    }
+}
+
+/// Signals related to the job control of the shell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuspendSignal {
+    /// SIGTSTP sent by another process, Ctrl+Z arrives as a key in raw mode
+    Stop,
+    /// SIGCONT, sent by `fg` and `bg`
+    Continue,
+}
+
+/// Receives the job control signals, handling SIGTSTP replaces its default action
+/// so the terminal can be restored before stopping
+pub struct SuspendSignals {
+    #[cfg(unix)]
+    stop: Signal,
+    #[cfg(unix)]
+    cont: Signal,
+}
+
+impl SuspendSignals {
+    #[cfg(unix)]
+    pub fn new() -> eyre::Result<Self> {
+        Ok(SuspendSignals {
+            stop: signal(SignalKind::from_raw(libc::SIGTSTP))?,
+            cont: signal(SignalKind::from_raw(libc::SIGCONT))?,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new() -> eyre::Result<Self> {
+        Ok(SuspendSignals {})
+    }
+
+    #[cfg(unix)]
+    pub async fn recv(&mut self) -> SuspendSignal {
+        tokio::select! {
+            _ = self.stop.recv() => SuspendSignal::Stop,
+            _ = self.cont.recv() => SuspendSignal::Continue,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub async fn recv(&mut self) -> SuspendSignal {
+        std::future::pending().await
+    }
+}
+
+/// Stops the whole process until the shell continues it
+#[cfg(unix)]
+pub fn stop_process() {
+    // SAFETY: raising a signal has no memory safety requirements
+    unsafe {
+        libc::raise(libc::SIGSTOP);
+    }
+}
+
+/// There is no job control to give the terminal back to
+#[cfg(not(unix))]
+pub fn stop_process() {}
+
+/// Whether the process group owns the terminal, taking it from the background would
+/// stop the process with SIGTTOU
+#[cfg(unix)]
+pub fn is_foreground() -> bool {
+    // SAFETY: both calls only read the process and terminal state
+    unsafe { libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp() }
+}
+
+#[cfg(not(unix))]
+pub fn is_foreground() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingTerminal {
+        calls: Vec<&'static str>,
+    }
+
+    impl TerminalModes for RecordingTerminal {
+        fn enter(&mut self) -> eyre::Result<()> {
+            self.calls.push("enter");
+            Ok(())
+        }
+
+        fn leave(&mut self) -> eyre::Result<()> {
+            self.calls.push("leave");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn repeated_suspend_and_resume_switch_the_terminal_once() {
+        let mut terminal = RecordingTerminal::default();
+        let mut suspension = Suspension::default();
+
+        assert!(!suspension.resume(&mut terminal).unwrap());
+        assert!(suspension.suspend(&mut terminal).unwrap());
+        assert!(!suspension.suspend(&mut terminal).unwrap());
+        assert!(suspension.is_suspended());
+        assert!(suspension.resume(&mut terminal).unwrap());
+        assert!(!suspension.resume(&mut terminal).unwrap());
+
+        assert_eq!(terminal.calls, vec!["leave", "enter"]);
+    }
+
+    #[test]
+    fn failed_restore_keeps_the_terminal_owned() {
+        struct BrokenTerminal;
+        impl TerminalModes for BrokenTerminal {
+            fn enter(&mut self) -> eyre::Result<()> {
+                Ok(())
+            }
+
+            fn leave(&mut self) -> eyre::Result<()> {
+                Err(eyre::eyre!("not a tty"))
+            }
+        }
+
+        let mut suspension = Suspension::default();
+        assert!(suspension.suspend(&mut BrokenTerminal).is_err());
+        assert!(!suspension.is_suspended());
+    }
+}
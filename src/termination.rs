@@ -1,12 +1,15 @@
 //! This module provides functionality for terminating the application
 use color_eyre::eyre;
 #[cfg(unix)]
-use tokio::signal::unix::signal;
+use tokio::signal::unix::{signal, Signal, SignalKind};
 use tokio::sync::broadcast;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Interrupted {
     OsSigInt,
+    /// SIGTERM or SIGHUP, e.g. `docker stop` or a closed terminal, on Windows the console
+    /// being closed or the system shutting down
+    OsSigTerm,
     UserInt,
 }
 
@@ -28,15 +31,62 @@ impl Terminator {
     }
 }
 
+/// Signals shutting down the application the same way as quitting it: the pending transfers
+/// are saved and the terminal is restored
+#[cfg(unix)]
+struct UnixSignals {
+    interrupt: Signal,
+    terminate: Signal,
+    hangup: Signal,
+}
+
 #[cfg(unix)]
-async fn terminate_by_unix_signal(mut terminator: Terminator) {
-    let mut interrupt_signal = signal(tokio::signal::unix::SignalKind::interrupt())
-        .expect("failed to create interrupt signal stream");
+impl UnixSignals {
+    /// Replaces the default handlers, a signal arriving from now on is received
+    fn register() -> Self {
+        UnixSignals {
+            interrupt: signal(SignalKind::interrupt())
+                .expect("failed to create interrupt signal stream"),
+            terminate: signal(SignalKind::terminate())
+                .expect("failed to create terminate signal stream"),
+            hangup: signal(SignalKind::hangup()).expect("failed to create hangup signal stream"),
+        }
+    }
+
+    async fn recv(&mut self) -> (&'static str, Interrupted) {
+        tokio::select! {
+            _ = self.interrupt.recv() => ("SIGINT", Interrupted::OsSigInt),
+            _ = self.terminate.recv() => ("SIGTERM", Interrupted::OsSigTerm),
+            _ = self.hangup.recv() => ("SIGHUP", Interrupted::OsSigTerm),
+        }
+    }
+}
 
-    interrupt_signal.recv().await;
+#[cfg(unix)]
+async fn terminate_by_unix_signal(mut terminator: Terminator, mut signals: UnixSignals) {
+    let (name, interrupted) = signals.recv().await;
+    tracing::info!("{} received, shutting down", name);
 
     terminator
-        .terminate(Interrupted::OsSigInt)
+        .terminate(interrupted)
+        .expect("failed to send interrupt signal");
+}
+
+/// Windows leaves a few seconds after these events, enough to save the pending transfers
+#[cfg(windows)]
+async fn terminate_by_console_event(mut terminator: Terminator) {
+    use tokio::signal::windows::{ctrl_close, ctrl_shutdown};
+    let mut close_event = ctrl_close().expect("failed to create console close stream");
+    let mut shutdown_event = ctrl_shutdown().expect("failed to create shutdown stream");
+
+    let name = tokio::select! {
+        _ = close_event.recv() => "console close",
+        _ = shutdown_event.recv() => "system shutdown",
+    };
+    tracing::info!("{} received, shutting down", name);
+
+    terminator
+        .terminate(Interrupted::OsSigTerm)
         .expect("failed to send interrupt signal");
 }
 
@@ -46,7 +96,42 @@ pub fn create_termination() -> (Terminator, broadcast::Receiver<Interrupted>) {
     let terminator = Terminator::new(tx);
 
     #[cfg(unix)]
-    tokio::spawn(terminate_by_unix_signal(terminator.clone()));
+    tokio::spawn(terminate_by_unix_signal(
+        terminator.clone(),
+        UnixSignals::register(),
+    ));
+    #[cfg(windows)]
+    tokio::spawn(terminate_by_console_event(terminator.clone()));
 
     (terminator, rx)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn every_loop_receives_the_termination() {
+        let (tx, mut ui_rx) = broadcast::channel(1);
+        let mut store_rx = ui_rx.resubscribe();
+        let mut terminator = Terminator::new(tx);
+
+        terminator.terminate(Interrupted::OsSigTerm).unwrap();
+        assert_eq!(ui_rx.recv().await.unwrap(), Interrupted::OsSigTerm);
+        assert_eq!(store_rx.recv().await.unwrap(), Interrupted::OsSigTerm);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sigterm_terminates_like_sigint() {
+        // the handler is registered before the spawned task runs, the signal doesn't kill
+        // the tests
+        let (_terminator, mut rx) = create_termination();
+        std::process::Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        let interrupted = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv()).await;
+        assert_eq!(interrupted.unwrap().unwrap(), Interrupted::OsSigTerm);
+    }
+}
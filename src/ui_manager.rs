@@ -7,7 +7,10 @@ use std::{
 use color_eyre::eyre;
 use color_eyre::eyre::Context;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture, Event, EventStream},
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -23,6 +26,9 @@ use super::components::app_router::AppRouter;
 use crate::components::component::{Component, ComponentRender};
 use crate::model::action::Action;
 use crate::model::state::State;
+use crate::suspension::{
+    is_foreground, stop_process, SuspendSignal, SuspendSignals, Suspension, TerminalModes,
+};
 use crate::termination::Interrupted;
 
 const RENDERING_TICK_RATE: Duration = Duration::from_millis(250);
@@ -53,28 +59,63 @@ impl UiManager {
         let mut terminal = setup_terminal()?;
         let mut ticker = tokio::time::interval(RENDERING_TICK_RATE);
         let mut crossterm_events = EventStream::new();
+        let mut suspension = Suspension::default();
+        let mut suspend_signals = SuspendSignals::new()?;
+        // newest state received while suspended, applied when the ui takes the terminal again
+        let mut latest_state: Option<State> = None;
 
         let result: eyre::Result<Interrupted> = loop {
+            let mut suspend = false;
             tokio::select! {
                 // Tick to terminate the select every N milliseconds
                 _ = ticker.tick() => (),
                 // Catch and handle crossterm events
                maybe_event = crossterm_events.next() => match maybe_event {
+                    Some(Ok(Event::Key(key))) if is_suspend_key(&key) => suspend = true,
                     Some(Ok(Event::Key(key)))  => {
                         app_router.handle_key_event(key);
                     },
+                    Some(Ok(Event::Paste(text))) => app_router.handle_paste(&text),
+                    Some(Ok(Event::Mouse(mouse))) => app_router.handle_mouse_event(mouse),
                     None => break Ok(Interrupted::UserInt),
                     _ => (),
                 },
                 // Handle state updates
                 Some(state) = state_rx.recv() => {
-                    app_router = app_router.move_with_state(&state);
+                    if suspension.is_suspended() {
+                        latest_state = Some(state);
+                    } else {
+                        app_router = app_router.move_with_state(&state);
+                    }
                 },
+                signal = suspend_signals.recv() => suspend = signal == SuspendSignal::Stop,
                 // Catch and handle interrupt signal to gracefully shutdown
                 Ok(interrupted) = interrupt_rx.recv() => {
                     break Ok(interrupted);
                 }
             }
+            if suspend {
+                if let Err(err) = suspension.suspend(&mut terminal) {
+                    break Err(err);
+                }
+                // returns once the shell continues the process, in the foreground or not
+                stop_process();
+            }
+            if suspension.is_suspended() {
+                if !is_foreground() {
+                    // transfers keep running, the ui waits for `fg`
+                    continue;
+                }
+                if let Err(err) = suspension.resume(&mut terminal) {
+                    break Err(err);
+                }
+                while let Ok(state) = state_rx.try_recv() {
+                    latest_state = Some(state);
+                }
+                if let Some(state) = latest_state.take() {
+                    app_router = app_router.move_with_state(&state);
+                }
+            }
             //render(frame, ()))
             if let Err(err) = terminal
                 .draw(|frame| app_router.render(frame, ()))
@@ -90,12 +131,42 @@ impl UiManager {
     }
 }
 
+/// Ctrl+Z, in raw mode it doesn't send SIGTSTP
+fn is_suspend_key(key: &KeyEvent) -> bool {
+    key.kind == KeyEventKind::Press
+        && key.code == KeyCode::Char('z')
+        && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+impl TerminalModes for Terminal<CrosstermBackend<Stdout>> {
+    fn enter(&mut self) -> eyre::Result<()> {
+        enable_raw_mode()?;
+        execute!(
+            self.backend_mut(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableBracketedPaste
+        )?;
+        // the screen was used by the shell, the next frame is drawn from scratch
+        Ok(self.clear()?)
+    }
+
+    fn leave(&mut self) -> eyre::Result<()> {
+        restore_terminal(self)
+    }
+}
+
 fn setup_terminal() -> eyre::Result<Terminal<CrosstermBackend<Stdout>>> {
     let mut stdout = io::stdout();
 
     enable_raw_mode()?;
 
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
 
     Ok(Terminal::new(CrosstermBackend::new(stdout))?)
 }
@@ -106,7 +177,8 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> eyre::
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
 
     Ok(terminal.show_cursor()?)
@@ -116,6 +188,23 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> eyre::
 mod tests {
     use super::*;
 
+    #[test]
+    fn only_ctrl_z_suspends() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        assert!(is_suspend_key(&key(
+            KeyCode::Char('z'),
+            KeyModifiers::CONTROL
+        )));
+        assert!(!is_suspend_key(&key(
+            KeyCode::Char('z'),
+            KeyModifiers::NONE
+        )));
+        assert!(!is_suspend_key(&key(
+            KeyCode::Char('x'),
+            KeyModifiers::CONTROL
+        )));
+    }
+
     #[tokio::test]
     async fn test_ui_manager_new() {
         let (_ui_manager, mut action_rx) = UiManager::new();
@@ -1,15 +1,19 @@
+use crate::settings::app_config::AppConfig;
 use color_eyre::eyre;
 use crossterm::cursor;
 use crossterm::event::{DisableBracketedPaste, DisableMouseCapture};
 use crossterm::terminal::LeaveAlternateScreen;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{
     self, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer,
 };
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 const VERSION_MESSAGE: &str = env!("CARGO_PKG_VERSION");
 
@@ -24,8 +28,12 @@ lazy_static! {
             .ok()
             .map(PathBuf::from);
     pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
-    pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
+
+/// Daily log files are named `s3tui.<date>.log`
+const LOG_FILE_PREFIX: &str = env!("CARGO_PKG_NAME");
+const LOG_FILE_SUFFIX: &str = "log";
+
 pub type IO = std::io::Stdout;
 pub fn io() -> IO {
     std::io::stdout()
@@ -126,23 +134,26 @@ pub fn get_config_dir() -> PathBuf {
 }
 
 /// Sets up logging capabilities for the application
-/// The logs are stored in the data directory
-pub fn initialize_logging() -> eyre::Result<()> {
+/// The logs are stored in the data directory, a new file is started every day and the
+/// oldest files are removed once the retention limits of the settings are exceeded
+pub fn initialize_logging(app_config: &AppConfig) -> eyre::Result<()> {
     let directory = get_data_dir();
     std::fs::create_dir_all(directory.clone())?;
-    let log_path = directory.join(LOG_FILE.clone());
-    let log_file = std::fs::File::create(log_path)?;
+    let removed = prune_log_files(&directory, app_config.log_max_total_mb * 1024 * 1024)?;
+    let log_appender = log_appender(&directory, app_config.log_retention_days)?;
     std::env::set_var(
         "RUST_LOG",
-        std::env::var("RUST_LOG")
-            .or_else(|_| std::env::var(LOG_ENV.clone()))
-            .unwrap_or_else(|_| format!("{}=info", env!("CARGO_CRATE_NAME"))),
+        log_directive(
+            std::env::var("RUST_LOG")
+                .or_else(|_| std::env::var(LOG_ENV.clone()))
+                .ok(),
+            app_config.log_level.as_deref(),
+        ),
     );
-    // std::env::set_var("RUST_LOG", "error");
     let file_subscriber = tracing_subscriber::fmt::layer()
         .with_file(true)
         .with_line_number(true)
-        .with_writer(log_file)
+        .with_writer(log_appender)
         .with_target(false)
         .with_ansi(false)
         .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
@@ -150,9 +161,63 @@ pub fn initialize_logging() -> eyre::Result<()> {
         .with(file_subscriber)
         .with(ErrorLayer::default())
         .init();
+    if !removed.is_empty() {
+        tracing::info!("removed {} old log file(s)", removed.len());
+    }
     Ok(())
 }
 
+/// Log filter taken from the environment, then from the settings, a bare level
+/// like `debug` applies to the application only
+fn log_directive(env: Option<String>, configured: Option<&str>) -> String {
+    match env.as_deref().or(configured) {
+        Some(directive) if directive.contains('=') || directive.contains(',') => {
+            directive.to_string()
+        }
+        Some(level) => format!("{}={}", env!("CARGO_CRATE_NAME"), level),
+        None => format!("{}=info", env!("CARGO_CRATE_NAME")),
+    }
+}
+
+/// Appender writing straight into the log file of the current day, keeping `max_files` of them
+fn log_appender(directory: &Path, max_files: usize) -> eyre::Result<RollingFileAppender> {
+    Ok(RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(max_files.max(1))
+        .build(directory)?)
+}
+
+/// Removes the oldest log files of the directory once their total size exceeds `max_total_bytes`,
+/// the most recent file is always kept. Returns the removed files
+pub fn prune_log_files(directory: &Path, max_total_bytes: u64) -> std::io::Result<Vec<PathBuf>> {
+    let mut logs = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let metadata = entry.metadata()?;
+        if metadata.is_file()
+            && name.starts_with(LOG_FILE_PREFIX)
+            && name.ends_with(&format!(".{}", LOG_FILE_SUFFIX))
+        {
+            logs.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+    // newest first, the file name breaks ties between files written in the same second
+    logs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.2.cmp(&a.2)));
+    let mut total = 0;
+    let mut removed = Vec::new();
+    for (position, (_, size, path)) in logs.into_iter().enumerate() {
+        total += size;
+        if position > 0 && total > max_total_bytes {
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
 /// Similar to the `std::dbg!` macro, but generates `tracing` events rather
 /// than printing to stdout.
 ///
@@ -196,3 +261,236 @@ Config directory: {config_dir_path}
 Data directory: {data_dir_path}"
     )
 }
+
+/// Formats a number of bytes using binary units, e.g. `1.5 KB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Formats a duration as hours, minutes and seconds, e.g. `1h 02m 03s`
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// `2024-05-01T12:00:00Z` shown as `2024-05-01 12:00`
+pub fn short_time(time: &str) -> String {
+    time.replacen('T', " ", 1).chars().take(16).collect()
+}
+
+/// Time needed for the transfers when `concurrency` of them run at once and each one
+/// takes the `average` duration
+pub fn estimate_batch_duration(
+    transfers: usize,
+    concurrency: usize,
+    average: std::time::Duration,
+) -> std::time::Duration {
+    let rounds = transfers.div_ceil(concurrency.max(1));
+    average.saturating_mul(u32::try_from(rounds).unwrap_or(u32::MAX))
+}
+
+/// Number of terminal columns needed to display the text, combining characters don't take space
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(|g| g.width()).sum()
+}
+
+/// Shortens the text so it fits into `max_width` terminal columns, marking the cut with `…`.
+/// Cuts happen between grapheme clusters so wide and combining characters are never split
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let mut result = String::new();
+    let mut width = 0;
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if width + grapheme_width + 1 > max_width {
+            break;
+        }
+        result.push_str(grapheme);
+        width += grapheme_width;
+    }
+    result.push('…');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    fn fake_log(directory: &Path, name: &str, size: usize, age_days: u64) -> PathBuf {
+        let path = directory.join(name);
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn oldest_logs_beyond_the_limit_are_removed() {
+        let dir = tempdir().unwrap();
+        let today = fake_log(dir.path(), "s3tui.2024-05-03.log", 600, 0);
+        let yesterday = fake_log(dir.path(), "s3tui.2024-05-02.log", 300, 1);
+        let older = fake_log(dir.path(), "s3tui.2024-05-01.log", 300, 2);
+        let legacy = fake_log(dir.path(), "s3tui.log", 100, 30);
+        let other = fake_log(dir.path(), "notes.txt", 5000, 40);
+
+        let removed = prune_log_files(dir.path(), 1000).unwrap();
+        assert_eq!(removed, vec![older.clone(), legacy.clone()]);
+        assert!(today.exists() && yesterday.exists() && other.exists());
+        assert!(!older.exists() && !legacy.exists());
+    }
+
+    #[test]
+    fn most_recent_log_is_kept_even_when_too_big() {
+        let dir = tempdir().unwrap();
+        let today = fake_log(dir.path(), "s3tui.2024-05-03.log", 2000, 0);
+        let yesterday = fake_log(dir.path(), "s3tui.2024-05-02.log", 10, 1);
+
+        assert_eq!(prune_log_files(dir.path(), 1000).unwrap(), vec![yesterday]);
+        assert!(today.exists());
+    }
+
+    #[test]
+    fn logging_continues_after_pruning() {
+        let dir = tempdir().unwrap();
+        fake_log(dir.path(), "s3tui.2024-05-02.log", 300, 1);
+        fake_log(dir.path(), "s3tui.2024-05-01.log", 300, 2);
+        prune_log_files(dir.path(), 400).unwrap();
+
+        let mut appender = log_appender(dir.path(), 7).unwrap();
+        appender.write_all(b"still logging\n").unwrap();
+        appender.flush().unwrap();
+        let logged = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .any(|content| content.contains("still logging"));
+        assert!(logged);
+    }
+
+    #[test]
+    fn bare_log_level_applies_to_the_application() {
+        let app = env!("CARGO_CRATE_NAME");
+        assert_eq!(log_directive(None, None), format!("{}=info", app));
+        assert_eq!(log_directive(None, Some("debug")), format!("{}=debug", app));
+        assert_eq!(
+            log_directive(Some("warn".into()), Some("debug")),
+            format!("{}=warn", app)
+        );
+        assert_eq!(
+            log_directive(None, Some("s3tui=debug,aws_config=warn")),
+            "s3tui=debug,aws_config=warn"
+        );
+    }
+
+    #[test]
+    fn format_bytes_picks_the_right_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[test]
+    fn format_duration_skips_empty_leading_units() {
+        assert_eq!(format_duration(Duration::from_secs(7)), "7s");
+        assert_eq!(format_duration(Duration::from_secs(65)), "1m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h 02m 03s");
+    }
+
+    #[test]
+    fn short_time_drops_the_seconds() {
+        assert_eq!(short_time("2024-05-01T12:00:59Z"), "2024-05-01 12:00");
+        assert_eq!(short_time("2024-05-01"), "2024-05-01");
+    }
+
+    #[test]
+    fn batch_duration_counts_the_rounds_of_concurrent_transfers() {
+        let average = Duration::from_secs(10);
+        assert_eq!(estimate_batch_duration(0, 8, average), Duration::ZERO);
+        assert_eq!(estimate_batch_duration(8, 8, average), average);
+        assert_eq!(
+            estimate_batch_duration(400_000, 8, average),
+            Duration::from_secs(500_000)
+        );
+        assert_eq!(
+            estimate_batch_duration(3, 0, average),
+            Duration::from_secs(30)
+        );
+    }
+
+    fn assert_fits(text: &str) {
+        for budget in [0, 1, 2, 3, 5, 8, 13] {
+            let res = truncate_to_width(text, budget);
+            assert!(
+                display_width(&res) <= budget,
+                "{:?} truncated to {:?} does not fit into {}",
+                text,
+                res,
+                budget
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_to_width_keeps_short_text() {
+        assert_eq!(truncate_to_width("bucket", 6), "bucket");
+        assert_eq!(truncate_to_width("bucket", 10), "bucket");
+    }
+
+    #[test]
+    fn truncate_to_width_handles_ascii() {
+        assert_eq!(truncate_to_width("my-long-bucket", 8), "my-long…");
+        assert_eq!(truncate_to_width("my-long-bucket", 1), "…");
+        assert_eq!(truncate_to_width("my-long-bucket", 0), "");
+        assert_fits("my-long-bucket-name");
+    }
+
+    #[test]
+    fn truncate_to_width_handles_wide_characters() {
+        assert_eq!(display_width("桶名称"), 6);
+        assert_eq!(truncate_to_width("桶名称测试", 6), "桶名…");
+        assert_eq!(truncate_to_width("桶名称测试", 4), "桶…");
+        assert_fits("バケット名前テスト");
+        assert_fits("photos-🎉🎉🎉-2024");
+        assert_fits("👨‍👩‍👧‍👦 family");
+    }
+
+    #[test]
+    fn truncate_to_width_keeps_combining_characters_together() {
+        let text = "cafe\u{301}-re\u{301}sume\u{301}";
+        assert_eq!(display_width(text), 11);
+        assert_eq!(truncate_to_width(text, 5), "cafe\u{301}…");
+        assert_fits(text);
+    }
+}